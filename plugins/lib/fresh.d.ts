@@ -51,6 +51,32 @@ declare global {
    * Global editor API object available to all TypeScript plugins
    */
   const editor: EditorAPI;
+
+  /**
+   * Call `callback` once after `delay` ms (at least 4ms)
+   * @returns Timer ID for clearTimeout
+   */
+  function setTimeout<A extends unknown[]>(
+    callback: (...args: A) => void | Promise<void>,
+    delay?: number,
+    ...args: A
+  ): number;
+
+  /**
+   * Call `callback` every `delay` ms (at least 16ms) until cleared
+   * @returns Timer ID for clearInterval
+   */
+  function setInterval<A extends unknown[]>(
+    callback: (...args: A) => void | Promise<void>,
+    delay?: number,
+    ...args: A
+  ): number;
+
+  /** Cancel a timer created by setTimeout (unknown IDs are ignored) */
+  function clearTimeout(id: number | null | undefined): void;
+
+  /** Cancel a timer created by setInterval (unknown IDs are ignored) */
+  function clearInterval(id: number | null | undefined): void;
 }
 
 /**
@@ -467,7 +493,7 @@ interface EditorAPI {
    * Uses OSC 52 and arboard for cross-platform compatibility.
    * @param text - Text to copy to clipboard
    */
-  setClipboard(text: string): void;
+  setClipboard(text: string): Promise<[]>;
   /**
    * Insert text at a byte position in a buffer
    *
@@ -571,10 +597,9 @@ interface EditorAPI {
    * @param action - JavaScript function name to call when command is triggered
    * @param contexts - Comma-separated list of contexts, including both built-in (normal, prompt, popup,
    * fileexplorer, menu) and custom plugin-defined contexts (e.g., "normal,config-editor")
-   * @param source - Plugin source name (empty string for builtin)
    * @returns true if command was registered
    */
-  registerCommand(name: string, description: string, action: string, contexts: string, source: string): boolean;
+  registerCommand(name: string, description: string, action: string, contexts: string): boolean;
   /**
    * Unregister a custom command by name
    * @param name - The name of the command to unregister
@@ -25,6 +25,9 @@ let lastQuery: string = "";
 let searchDebounceTimer: number | null = null;
 let previewCreated: boolean = false;
 
+// Delay after the last keystroke before running ripgrep
+const SEARCH_DEBOUNCE_MS = 150;
+
 // Parse ripgrep output line
 // Format: file:line:column:content
 function parseRipgrepLine(line: string): GrepMatch | null {
@@ -157,6 +160,11 @@ async function updatePreview(match: GrepMatch): Promise<void> {
 
 // Close preview buffer and its split
 function closePreview(): void {
+  // Drop a search that is still waiting for its debounce delay
+  if (searchDebounceTimer !== null) {
+    clearTimeout(searchDebounceTimer);
+    searchDebounceTimer = null;
+  }
   // Close the buffer first
   if (previewBufferId !== null) {
     editor.closeBuffer(previewBufferId);
@@ -250,11 +258,13 @@ globalThis.onLiveGrepPromptChanged = function (args: {
 
   // Debounce search to avoid too many requests while typing
   if (searchDebounceTimer !== null) {
-    // Can't actually cancel in this runtime, but we track it
+    clearTimeout(searchDebounceTimer);
   }
-
-  // Run search (with small delay effect via async)
-  runSearch(args.input);
+  const query = args.input;
+  searchDebounceTimer = setTimeout(() => {
+    searchDebounceTimer = null;
+    runSearch(query);
+  }, SEARCH_DEBOUNCE_MS);
 
   return true;
 };
@@ -9,6 +9,7 @@
 
 use crate::model::event::BufferId;
 use crate::services::async_bridge::{LspMessageType, LspProgressValue, LspServerStatus};
+use crate::services::lsp::manager::LspFeature;
 use crate::view::file_tree::{FileTreeView, NodeId};
 use lsp_types::{Diagnostic, InlayHint};
 use serde_json::Value;
@@ -57,8 +58,16 @@ impl Editor {
 // =============================================================================
 
 impl Editor {
-    /// Store and apply diagnostics, emit hook for plugins
-    fn store_and_apply_diagnostics(&mut self, uri: String, diagnostics: Vec<Diagnostic>) {
+    /// Store and apply the diagnostics `server` reported, emit hook for plugins
+    fn store_and_apply_diagnostics(
+        &mut self,
+        uri: String,
+        server: &str,
+        diagnostics: Vec<Diagnostic>,
+    ) {
+        // Merge them with the diagnostics of the other servers of the file
+        let diagnostics = self.diagnostics_by_server.update(&uri, server, diagnostics);
+
         // Store diagnostics for later retrieval by plugins
         if diagnostics.is_empty() {
             self.stored_diagnostics.remove(&uri);
@@ -88,19 +97,26 @@ impl Editor {
     }
 
     /// Handle LSP diagnostics (push model)
-    pub(super) fn handle_lsp_diagnostics(&mut self, uri: String, diagnostics: Vec<Diagnostic>) {
+    pub(super) fn handle_lsp_diagnostics(
+        &mut self,
+        uri: String,
+        server: String,
+        diagnostics: Vec<Diagnostic>,
+    ) {
         tracing::debug!(
-            "Processing {} LSP diagnostics for {}",
+            "Processing {} LSP diagnostics from {} for {}",
             diagnostics.len(),
+            server,
             uri
         );
-        self.store_and_apply_diagnostics(uri, diagnostics);
+        self.store_and_apply_diagnostics(uri, &server, diagnostics);
     }
 
     /// Handle LSP pulled diagnostics (pull model - LSP 3.17+)
     pub(super) fn handle_lsp_pulled_diagnostics(
         &mut self,
         uri: String,
+        server: String,
         result_id: Option<String>,
         diagnostics: Vec<Diagnostic>,
         unchanged: bool,
@@ -123,10 +139,11 @@ impl Editor {
 
         // Store result_id for incremental updates
         if let Some(result_id) = result_id {
-            self.diagnostic_result_ids.insert(uri.clone(), result_id);
+            self.diagnostic_result_ids
+                .insert((uri.clone(), server.clone()), result_id);
         }
 
-        self.store_and_apply_diagnostics(uri, diagnostics);
+        self.store_and_apply_diagnostics(uri, &server, diagnostics);
     }
 }
 
@@ -189,7 +206,7 @@ impl Editor {
             return;
         };
 
-        let Some(client) = lsp.get_or_spawn(&language) else {
+        let Some(client) = lsp.get_or_spawn_for(&language, LspFeature::InlayHints) else {
             return;
         };
 
@@ -325,15 +342,21 @@ impl Editor {
     }
 
     /// Handle LSP server status update
-    pub(super) fn handle_lsp_status_update(&mut self, language: String, status: LspServerStatus) {
+    pub(super) fn handle_lsp_status_update(
+        &mut self,
+        language: String,
+        server: String,
+        status: LspServerStatus,
+    ) {
         use crate::services::async_bridge::LspServerStatus;
 
+        let key = (language.clone(), server.clone());
+
         // Get old status for event
-        let old_status = self.lsp_server_statuses.get(&language).cloned();
+        let old_status = self.lsp_server_statuses.get(&key).cloned();
 
         // Update server status
-        self.lsp_server_statuses
-            .insert(language.clone(), status.clone());
+        self.lsp_server_statuses.insert(key, status);
         self.update_lsp_status_from_server_statuses();
 
         // Handle server crash - trigger auto-restart
@@ -345,7 +368,7 @@ impl Editor {
 
             if was_running {
                 if let Some(lsp) = self.lsp.as_mut() {
-                    let message = lsp.handle_server_crash(&language);
+                    let message = lsp.handle_server_crash(&language, &server);
                     self.status_message = Some(message);
                 }
             }
@@ -373,6 +396,7 @@ impl Editor {
             crate::model::control_event::events::LSP_STATUS_CHANGED.name,
             serde_json::json!({
                 "language": language,
+                "server": server,
                 "old_status": old_status_str,
                 "status": status_str
             }),
@@ -694,8 +718,12 @@ impl Editor {
                         &self.config.languages,
                     ) {
                         if let Some(lsp) = self.lsp.as_mut() {
-                            if let Some(handle) = lsp.get_or_spawn(&lang_id) {
-                                let _ = handle.did_open(uri, content, lang_id);
+                            for server in lsp.get_or_spawn_all(&lang_id) {
+                                let _ = server.handle.did_open(
+                                    uri.clone(),
+                                    content.clone(),
+                                    lang_id.clone(),
+                                );
                             }
                         }
                     }
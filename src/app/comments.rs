@@ -0,0 +1,207 @@
+//! Comment toggling
+//!
+//! "Toggle Comment" adds or removes the line comment prefix on the current
+//! line or every selected line; languages without line comments (HTML, CSS)
+//! get one block comment per line instead. "Toggle Block Comment" wraps the
+//! selection or current line in a single block comment. The comment tokens
+//! are those of the language at the cursor; see [`crate::primitives::comment`].
+
+use super::*;
+use crate::primitives::comment::{
+    language_at, language_id, toggle_block_comment, toggle_line_block_comments,
+    toggle_line_comments, CommentEdit, CommentTokens,
+};
+use crate::primitives::highlighter::Language;
+
+impl Editor {
+    /// Toggle comment on the current line or selection
+    pub(super) fn toggle_comment(&mut self) {
+        let cursor = *self.active_state().cursors.primary();
+        let (start_pos, end_pos) = match cursor.selection_range() {
+            Some(range) => (range.start, range.end),
+            None => (cursor.position, cursor.position),
+        };
+        let tokens = self.comment_tokens_at(start_pos);
+
+        let lines = self.lines_in_range(start_pos, end_pos);
+        let lines: Vec<(usize, &str)> = lines
+            .iter()
+            .map(|(line_start, line)| (*line_start, line.as_str()))
+            .collect();
+        let (edits, commented) = match (&tokens.line, &tokens.block) {
+            (Some(prefix), _) => toggle_line_comments(&lines, prefix),
+            (None, Some((start, end))) => {
+                toggle_line_block_comments(&lines, (start.as_str(), end.as_str()))
+            }
+            (None, None) => {
+                self.set_status_message("No comment syntax for this language".to_string());
+                return;
+            }
+        };
+
+        let line_count = lines
+            .iter()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .count();
+        let action_desc = if commented { "Comment" } else { "Uncomment" };
+        if self.apply_comment_edits(edits, format!("{} lines", action_desc)) {
+            self.set_status_message(format!("{}ed {} line(s)", action_desc, line_count));
+        }
+    }
+
+    /// Wrap the selection or current line in a block comment, or unwrap it
+    pub(super) fn toggle_block_comment(&mut self) {
+        let estimated_line_length = self.config.editor.estimated_line_length;
+        let cursor = *self.active_state().cursors.primary();
+        let range = match cursor.selection_range() {
+            Some(range) => range,
+            None => {
+                let state = self.active_state_mut();
+                let mut iter = state
+                    .buffer
+                    .line_iterator(cursor.position, estimated_line_length);
+                match iter.next() {
+                    Some((line_start, line)) => {
+                        line_start..line_start + line.trim_end_matches(['\n', '\r']).len()
+                    }
+                    None => cursor.position..cursor.position,
+                }
+            }
+        };
+
+        let tokens = self.comment_tokens_at(range.start);
+        let Some((start, end)) = tokens.block else {
+            self.set_status_message("No block comment syntax for this language".to_string());
+            return;
+        };
+
+        let text = self
+            .active_state_mut()
+            .get_text_range(range.start, range.end);
+        let (edits, commented) =
+            toggle_block_comment(&text, range.start, (start.as_str(), end.as_str()));
+        let action_desc = if commented { "Comment" } else { "Uncomment" };
+        if self.apply_comment_edits(edits, format!("{} block", action_desc)) {
+            self.set_status_message(format!("{}ed block", action_desc));
+        }
+    }
+
+    /// Start offsets and text of the lines from the one containing `start` up
+    /// to the one containing `end`
+    fn lines_in_range(&mut self, start: usize, end: usize) -> Vec<(usize, String)> {
+        let estimated_line_length = self.config.editor.estimated_line_length;
+        let state = self.active_state_mut();
+        let buffer_len = state.buffer.len();
+
+        let mut lines = Vec::new();
+        let mut iter = state.buffer.line_iterator(start, estimated_line_length);
+        while let Some((line_start, line)) = iter.next() {
+            let next_start = line_start + line.len();
+            lines.push((line_start, line));
+            if next_start > end || next_start >= buffer_len {
+                break;
+            }
+        }
+        lines
+    }
+
+    /// Apply comment edits to the active buffer as one undoable batch;
+    /// returns false if there was nothing to do
+    fn apply_comment_edits(&mut self, edits: Vec<CommentEdit>, description: String) -> bool {
+        if edits.is_empty() {
+            return false;
+        }
+
+        let state = self.active_state_mut();
+        let cursor_id = state.cursors.primary_id();
+        let events = edits
+            .into_iter()
+            .map(|edit| {
+                if edit.range.is_empty() {
+                    Event::Insert {
+                        position: edit.range.start,
+                        text: edit.text,
+                        cursor_id,
+                    }
+                } else {
+                    Event::Delete {
+                        deleted_text: state.get_text_range(edit.range.start, edit.range.end),
+                        range: edit.range,
+                        cursor_id,
+                    }
+                }
+            })
+            .collect();
+        let batch = Event::Batch {
+            events,
+            description,
+        };
+
+        self.active_event_log_mut().append(batch.clone());
+        self.apply_event_to_active_buffer(&batch);
+        true
+    }
+
+    /// Comment tokens for the code at byte `position` of the active buffer
+    ///
+    /// Code embedded in another language, such as a `<script>` in HTML, uses
+    /// the tokens of the embedded language. Tokens set in the language's config
+    /// take precedence over the built-in ones.
+    fn comment_tokens_at(&self, position: usize) -> CommentTokens {
+        let state = self.active_state();
+        let grammar = state.highlighter.language().copied();
+
+        if let Some(grammar) = grammar {
+            let embedded = state
+                .buffer
+                .to_string()
+                .map(|source| language_at(grammar, &source, position))
+                .filter(|embedded| language_id(*embedded) != language_id(grammar));
+            if let Some(embedded) = embedded {
+                return self
+                    .configured_comment_tokens(language_id(embedded))
+                    .or_defaults(CommentTokens::for_language(embedded));
+            }
+        }
+
+        let path = self
+            .buffer_metadata
+            .get(&self.active_buffer())
+            .and_then(|metadata| metadata.file_path());
+        let configured = path
+            .and_then(|path| detect_language(path, &self.config.languages))
+            .map(|language| self.configured_comment_tokens(&language))
+            .unwrap_or_default();
+        let defaults = match grammar {
+            Some(grammar) if !matches!(grammar, Language::Dynamic(_)) => {
+                CommentTokens::for_language(grammar)
+            }
+            _ => path
+                .and_then(|path| path.extension()?.to_str())
+                .and_then(CommentTokens::for_extension)
+                .unwrap_or_else(|| CommentTokens {
+                    line: Some("//".to_string()),
+                    block: None,
+                }),
+        };
+        configured.or_defaults(defaults)
+    }
+
+    /// Comment tokens set in the config of `language`
+    fn configured_comment_tokens(&self, language: &str) -> CommentTokens {
+        let Some(config) = self.config.languages.get(language) else {
+            return CommentTokens::default();
+        };
+        CommentTokens {
+            line: config
+                .comment_prefix
+                .as_deref()
+                .map(|prefix| prefix.trim_end().to_string())
+                .filter(|prefix| !prefix.is_empty()),
+            block: config
+                .block_comment
+                .as_ref()
+                .map(|block| (block.start.clone(), block.end.clone())),
+        }
+    }
+}
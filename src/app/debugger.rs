@@ -0,0 +1,615 @@
+//! Debugging through the Debug Adapter Protocol
+//!
+//! Breakpoints are line indicators in the buffer's margin, so they follow
+//! edits. "Start Debugging" runs the debug adapter configured for the active
+//! file's language (see [`crate::services::dap`]) and opens the debug panel
+//! below the active split, listing the call stack, the selected frame's
+//! variables and the debuggee's output. When the debuggee stops, the
+//! current line is opened in the source split and highlighted with an
+//! overlay.
+//!
+//! In the panel, Enter shows the frame under the cursor, `c` continues, `n`,
+//! `s` and `o` step over, into and out, `p` pauses, `x` stops the session and
+//! `q` closes the panel.
+
+use super::*;
+use crate::primitives::text_property::TextPropertyEntry;
+use crate::services::dap::async_handler::DapHandle;
+use crate::services::dap::protocol::{ScopeVariables, StackFrame};
+use crate::view::margin::LineIndicator;
+use crate::view::overlay::OverlayNamespace;
+
+/// Display name of the debug panel buffer
+const DEBUG_BUFFER_NAME: &str = "*Debug*";
+
+/// Margin indicator namespace for breakpoints
+const BREAKPOINT_NAMESPACE: &str = "breakpoints";
+
+/// Overlay namespace for the highlighted current line
+const CURRENT_LINE_NAMESPACE: &str = "debug-current-line";
+
+/// Most output lines kept for the panel
+const MAX_OUTPUT_LINES: usize = 200;
+
+impl Editor {
+    /// Add or remove a breakpoint on the cursor's line
+    pub(crate) fn toggle_breakpoint(&mut self) {
+        let buffer_id = self.active_buffer();
+        let state = self.active_state_mut();
+        let line = state
+            .buffer
+            .get_line_number(state.cursors.primary().position);
+        let existing = state
+            .margins
+            .line_indicator_positions(BREAKPOINT_NAMESPACE)
+            .into_iter()
+            .find(|&(_, position)| state.buffer.get_line_number(position) == line);
+
+        let added = match existing {
+            Some((marker_id, _)) => {
+                state
+                    .margins
+                    .remove_line_indicator(marker_id, BREAKPOINT_NAMESPACE);
+                false
+            }
+            None => {
+                let line_start = state.buffer.line_start_offset(line).unwrap_or(0);
+                state.margins.set_line_indicator(
+                    line_start,
+                    BREAKPOINT_NAMESPACE.to_string(),
+                    LineIndicator::new("●", ratatui::style::Color::Red, 20),
+                );
+                true
+            }
+        };
+
+        self.send_breakpoints(buffer_id);
+        self.set_status_message(format!(
+            "Breakpoint {} line {}",
+            if added { "set on" } else { "removed from" },
+            line + 1
+        ));
+    }
+
+    /// Lines (0-based) with a breakpoint in a buffer
+    fn breakpoint_lines(&self, buffer_id: BufferId) -> Vec<usize> {
+        let Some(state) = self.buffers.get(&buffer_id) else {
+            return Vec::new();
+        };
+        let mut lines: Vec<usize> = state
+            .margins
+            .line_indicator_positions(BREAKPOINT_NAMESPACE)
+            .into_iter()
+            .map(|(_, position)| state.buffer.get_line_number(position))
+            .collect();
+        // Deleting text can move several breakpoints onto one line
+        lines.dedup();
+        lines
+    }
+
+    /// Send a buffer's breakpoints to the running debug session
+    fn send_breakpoints(&self, buffer_id: BufferId) {
+        let Some(session) = self.debug_session.as_ref().filter(|s| s.is_active()) else {
+            return;
+        };
+        let Some(path) = self
+            .buffers
+            .get(&buffer_id)
+            .and_then(|state| state.buffer.file_path())
+        else {
+            return;
+        };
+        session
+            .handle
+            .set_breakpoints(path.to_path_buf(), self.breakpoint_lines(buffer_id));
+    }
+
+    /// Start debugging the active file with its language's debug adapter
+    pub(crate) fn start_debugging(&mut self) {
+        if self.debug_session.as_ref().is_some_and(|s| s.is_active()) {
+            self.open_debug_panel();
+            self.set_status_message("A debug session is already running".to_string());
+            return;
+        }
+
+        let Some(path) = self
+            .active_state()
+            .buffer
+            .file_path()
+            .map(|path| path.to_path_buf())
+        else {
+            self.set_status_message("Save the buffer to a file to debug it".to_string());
+            return;
+        };
+        let Some(language) =
+            crate::services::lsp::manager::detect_language(&path, &self.config.languages)
+        else {
+            self.set_status_message(format!("Unknown language for {}", path.display()));
+            return;
+        };
+        let Some(config) = self.config.debug.get(&language).cloned() else {
+            self.set_status_message(format!("No debug adapter configured for {}", language));
+            return;
+        };
+        let (Some(runtime), Some(bridge)) = (&self.tokio_runtime, &self.async_bridge) else {
+            self.set_status_message("Async runtime not available".to_string());
+            return;
+        };
+
+        let breakpoints = self
+            .buffers
+            .iter()
+            .filter_map(|(&buffer_id, state)| {
+                let path = state.buffer.file_path()?.to_path_buf();
+                let lines = self.breakpoint_lines(buffer_id);
+                (!lines.is_empty()).then_some((path, lines))
+            })
+            .collect();
+        let arguments = match &config.configuration {
+            serde_json::Value::Null => serde_json::json!({}),
+            configuration => substitute_file(configuration, &path.to_string_lossy()),
+        };
+
+        self.next_debug_session_id += 1;
+        let handle = DapHandle::spawn(
+            runtime.handle(),
+            self.next_debug_session_id,
+            &config,
+            arguments,
+            language.clone(),
+            bridge,
+            breakpoints,
+        );
+        self.clear_debug_current_line();
+        self.debug_session = Some(DebugSession {
+            handle,
+            language,
+            state: DebugState::Running,
+            frames: Vec::new(),
+            selected_frame: 0,
+            scopes: Vec::new(),
+            output: Vec::new(),
+        });
+
+        self.open_debug_panel();
+        self.refresh_debug_panel(true);
+        self.set_status_message(format!("Debugging {}...", path.display()));
+    }
+
+    /// Resume the stopped debuggee
+    pub(crate) fn debug_continue(&mut self) {
+        self.with_stopped_session(DapHandle::continue_execution);
+    }
+
+    /// Step over the current line
+    pub(crate) fn debug_step_over(&mut self) {
+        self.with_stopped_session(DapHandle::step_over);
+    }
+
+    /// Step into the call on the current line
+    pub(crate) fn debug_step_into(&mut self) {
+        self.with_stopped_session(DapHandle::step_into);
+    }
+
+    /// Run until the current function returns
+    pub(crate) fn debug_step_out(&mut self) {
+        self.with_stopped_session(DapHandle::step_out);
+    }
+
+    /// Interrupt the running debuggee
+    pub(crate) fn debug_pause(&mut self) {
+        match &self.debug_session {
+            Some(session) if session.state == DebugState::Running => session.handle.pause(),
+            Some(session) if session.is_active() => {
+                self.set_status_message("The debuggee is already paused".to_string())
+            }
+            _ => self.set_status_message("No debug session is running".to_string()),
+        }
+    }
+
+    /// Stop the debug session, terminating the debuggee
+    pub(crate) fn debug_stop(&mut self) {
+        match self.debug_session.as_ref().filter(|s| s.is_active()) {
+            Some(session) => session.handle.stop(),
+            None => self.set_status_message("No debug session is running".to_string()),
+        }
+    }
+
+    fn with_stopped_session(&mut self, action: impl FnOnce(&DapHandle)) {
+        match &self.debug_session {
+            Some(session) if matches!(session.state, DebugState::Stopped { .. }) => {
+                action(&session.handle)
+            }
+            Some(session) if session.is_active() => {
+                self.set_status_message("The debuggee is running".to_string())
+            }
+            _ => self.set_status_message("No debug session is running".to_string()),
+        }
+    }
+
+    /// The session a message is for, unless it's from an older session
+    fn debug_session_for(&mut self, session_id: u64) -> Option<&mut DebugSession> {
+        self.debug_session
+            .as_mut()
+            .filter(|session| session.handle.session_id() == session_id)
+    }
+
+    pub(super) fn handle_dap_stopped(
+        &mut self,
+        session_id: u64,
+        reason: String,
+        frames: Vec<StackFrame>,
+    ) {
+        let Some(session) = self.debug_session_for(session_id) else {
+            return;
+        };
+        session.state = DebugState::Stopped {
+            reason: reason.clone(),
+        };
+        session.frames = frames;
+        session.selected_frame = 0;
+        session.scopes.clear();
+
+        self.show_selected_frame();
+        self.refresh_debug_panel(true);
+        self.set_status_message(format!("Debuggee stopped: {}", reason));
+    }
+
+    pub(super) fn handle_dap_variables(
+        &mut self,
+        session_id: u64,
+        frame_id: i64,
+        scopes: Vec<ScopeVariables>,
+    ) {
+        let Some(session) = self.debug_session_for(session_id) else {
+            return;
+        };
+        let selected = session.frames.get(session.selected_frame).map(|f| f.id);
+        if selected == Some(frame_id) {
+            session.scopes = scopes;
+            self.refresh_debug_panel(false);
+        }
+    }
+
+    pub(super) fn handle_dap_continued(&mut self, session_id: u64) {
+        let Some(session) = self.debug_session_for(session_id) else {
+            return;
+        };
+        if !session.is_active() {
+            return;
+        }
+        session.state = DebugState::Running;
+        session.frames.clear();
+        session.scopes.clear();
+        self.clear_debug_current_line();
+        self.refresh_debug_panel(false);
+    }
+
+    pub(super) fn handle_dap_output(&mut self, session_id: u64, output: String) {
+        let Some(session) = self.debug_session_for(session_id) else {
+            return;
+        };
+        session.output.extend(output.lines().map(str::to_string));
+        let excess = session.output.len().saturating_sub(MAX_OUTPUT_LINES);
+        session.output.drain(..excess);
+        self.refresh_debug_panel(false);
+    }
+
+    pub(super) fn handle_dap_error(&mut self, session_id: u64, error: String) {
+        if self.debug_session_for(session_id).is_some() {
+            self.set_status_message(format!("Debug error: {}", error));
+        }
+    }
+
+    pub(super) fn handle_dap_terminated(&mut self, session_id: u64, exit_code: Option<i64>) {
+        let Some(session) = self.debug_session_for(session_id) else {
+            return;
+        };
+        session.state = DebugState::Ended { exit_code };
+        session.frames.clear();
+        session.scopes.clear();
+        self.clear_debug_current_line();
+        self.refresh_debug_panel(false);
+        self.set_status_message(match exit_code {
+            Some(code) => format!("Debug session ended (exit code {})", code),
+            None => "Debug session ended".to_string(),
+        });
+    }
+
+    /// Open the selected frame's location in the source split, highlight the
+    /// line and load the frame's variables
+    fn show_selected_frame(&mut self) {
+        self.clear_debug_current_line();
+        let Some(session) = &self.debug_session else {
+            return;
+        };
+        let Some(frame) = session.frames.get(session.selected_frame) else {
+            return;
+        };
+        session.handle.select_frame(frame.id);
+        let Some(path) = frame
+            .source
+            .as_ref()
+            .and_then(|source| source.path.as_ref())
+            .map(PathBuf::from)
+        else {
+            return;
+        };
+        let line = frame.line.max(1) as usize - 1;
+
+        if let Some(panel) = &self.debug_panel {
+            let source_split = panel.source_split;
+            if let Some(buffer_id) = self.split_manager.get_buffer_id(source_split) {
+                self.focus_split(source_split, buffer_id);
+            }
+        }
+        let buffer_id = match self.open_file(&path) {
+            Ok(buffer_id) => buffer_id,
+            Err(e) => {
+                self.set_status_message(format!("Failed to open {}: {}", path.display(), e));
+                return;
+            }
+        };
+        let Some(state) = self.buffers.get_mut(&buffer_id) else {
+            return;
+        };
+        let Some(line_start) = state.buffer.line_start_offset(line) else {
+            return;
+        };
+        let line_end = state
+            .buffer
+            .line_start_offset(line + 1)
+            .unwrap_or(state.buffer.len());
+
+        let cursor = state.cursors.primary();
+        let event = Event::MoveCursor {
+            cursor_id: state.cursors.primary_id(),
+            old_position: cursor.position,
+            new_position: line_start,
+            old_anchor: cursor.anchor,
+            new_anchor: None,
+            old_sticky_column: cursor.sticky_column,
+            new_sticky_column: 0,
+        };
+        state.apply(&event);
+        // Overlays are ephemeral, so they aren't added to the event log
+        state.apply(&Event::AddOverlay {
+            namespace: Some(OverlayNamespace::from_string(
+                CURRENT_LINE_NAMESPACE.to_string(),
+            )),
+            range: line_start..line_end,
+            face: crate::model::event::OverlayFace::Background {
+                color: (90, 80, 20),
+            },
+            priority: 50,
+            message: None,
+        });
+    }
+
+    /// Remove the current line highlight from every buffer
+    fn clear_debug_current_line(&mut self) {
+        let event = Event::ClearNamespace {
+            namespace: OverlayNamespace::from_string(CURRENT_LINE_NAMESPACE.to_string()),
+        };
+        for state in self.buffers.values_mut() {
+            state.apply(&event);
+        }
+    }
+
+    pub(super) fn handle_debug_panel_command(&mut self, command: &str) {
+        match command {
+            "select-frame" => self.select_debug_frame(),
+            "continue" => self.debug_continue(),
+            "step-over" => self.debug_step_over(),
+            "step-into" => self.debug_step_into(),
+            "step-out" => self.debug_step_out(),
+            "pause" => self.debug_pause(),
+            "stop" => self.debug_stop(),
+            "close" => self.close_debug_panel(),
+            _ => {}
+        }
+    }
+
+    /// Show the frame on the panel line under the cursor
+    fn select_debug_frame(&mut self) {
+        let Some(panel) = &self.debug_panel else {
+            return;
+        };
+        let Some(state) = self.buffers.get(&panel.buffer_id) else {
+            return;
+        };
+        let position = state.cursors.primary().position;
+        let Some(frame) = state
+            .text_properties
+            .get_at(position)
+            .iter()
+            .find_map(|property| property.get_as::<usize>("frame"))
+        else {
+            return;
+        };
+        let Some(session) = self.debug_session.as_mut() else {
+            return;
+        };
+        if frame >= session.frames.len() {
+            return;
+        }
+        session.selected_frame = frame;
+        session.scopes.clear();
+        self.show_selected_frame();
+        self.refresh_debug_panel(false);
+    }
+
+    /// Open the debug panel in a split below the active split, or focus it
+    fn open_debug_panel(&mut self) {
+        if let Some(panel) = &self.debug_panel {
+            let (split_id, buffer_id) = (panel.split_id, panel.buffer_id);
+            self.focus_split(split_id, buffer_id);
+            return;
+        }
+
+        let source_split = self.split_manager.active_split();
+        let buffer_id = self.create_virtual_buffer(
+            DEBUG_BUFFER_NAME.to_string(),
+            "debug-panel".to_string(),
+            true,
+        );
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            state.margins.set_line_numbers(false);
+            state.editing_disabled = true;
+        }
+
+        // Keep the panel out of the source split's tabs
+        if let Some(view_state) = self.split_view_states.get_mut(&source_split) {
+            view_state.remove_buffer(buffer_id);
+        }
+
+        self.save_current_split_view_state();
+        let split_id =
+            match self
+                .split_manager
+                .split_active(SplitDirection::Horizontal, buffer_id, 0.6)
+            {
+                Ok(split_id) => {
+                    let mut view_state = SplitViewState::with_buffer(
+                        self.terminal_width,
+                        self.terminal_height,
+                        buffer_id,
+                    );
+                    view_state.viewport.line_wrap_enabled = false;
+                    self.split_view_states.insert(split_id, view_state);
+                    split_id
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to split for the debug panel: {}", e);
+                    self.set_active_buffer(buffer_id);
+                    source_split
+                }
+            };
+
+        self.debug_panel = Some(DebugPanel {
+            buffer_id,
+            split_id,
+            source_split,
+        });
+    }
+
+    fn close_debug_panel(&mut self) {
+        let Some(panel) = self.debug_panel.take() else {
+            return;
+        };
+        if panel.split_id != panel.source_split {
+            self.handle_close_split(panel.split_id);
+        }
+        if let Err(e) = self.close_buffer(panel.buffer_id) {
+            tracing::warn!("Failed to close the debug panel: {}", e);
+        }
+    }
+
+    fn refresh_debug_panel(&mut self, reset_cursor: bool) {
+        let (Some(panel), Some(session)) = (&self.debug_panel, &self.debug_session) else {
+            return;
+        };
+
+        let status = match &session.state {
+            DebugState::Running => "running".to_string(),
+            DebugState::Stopped { reason } => format!("stopped: {}", reason),
+            DebugState::Ended {
+                exit_code: Some(code),
+            } => format!("exited with code {}", code),
+            DebugState::Ended { exit_code: None } => "ended".to_string(),
+        };
+        let mut entries = vec![
+            TextPropertyEntry::text(format!("Debugging ({}) - {}\n", session.language, status)),
+            TextPropertyEntry::text("\nCall Stack\n".to_string()),
+        ];
+        let mut offset: usize = entries.iter().map(|e| e.text.len()).sum();
+        let mut selected_offset = None;
+
+        for (index, frame) in session.frames.iter().enumerate() {
+            let location = frame
+                .source
+                .as_ref()
+                .and_then(|source| source.path.as_deref().or(source.name.as_deref()))
+                .map(|path| {
+                    let path = Path::new(path);
+                    path.strip_prefix(&self.working_dir)
+                        .unwrap_or(path)
+                        .display()
+                        .to_string()
+                })
+                .unwrap_or_else(|| "<unknown>".to_string());
+            let marker = if index == session.selected_frame {
+                selected_offset = Some(offset);
+                ">"
+            } else {
+                " "
+            };
+            let line = format!("{} {}  {}:{}\n", marker, frame.name, location, frame.line);
+            offset += line.len();
+            entries.push(
+                TextPropertyEntry::text(line)
+                    .with_property("frame", serde_json::Value::from(index)),
+            );
+        }
+
+        if !session.scopes.is_empty() {
+            entries.push(TextPropertyEntry::text("\nVariables\n".to_string()));
+            for scope in &session.scopes {
+                entries.push(TextPropertyEntry::text(format!("  {}\n", scope.name)));
+                for variable in &scope.variables {
+                    let line = match &variable.type_name {
+                        Some(type_name) if !type_name.is_empty() => format!(
+                            "    {}: {} = {}\n",
+                            variable.name, type_name, variable.value
+                        ),
+                        _ => format!("    {} = {}\n", variable.name, variable.value),
+                    };
+                    entries.push(TextPropertyEntry::text(line));
+                }
+            }
+        }
+
+        if !session.output.is_empty() {
+            entries.push(TextPropertyEntry::text("\nOutput\n".to_string()));
+            for line in &session.output {
+                entries.push(TextPropertyEntry::text(format!("  {}\n", line)));
+            }
+        }
+
+        let (buffer_id, split_id) = (panel.buffer_id, panel.split_id);
+        if let Err(e) = self.set_virtual_buffer_content(buffer_id, entries) {
+            tracing::warn!("Failed to update the debug panel: {}", e);
+            return;
+        }
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            if reset_cursor {
+                state.cursors.primary_mut().position = selected_offset.unwrap_or(0);
+            }
+            let len = state.buffer.len();
+            let cursor = &mut state.cursors.primary_mut().position;
+            *cursor = (*cursor).min(len);
+            if let Some(view_state) = self.split_view_states.get_mut(&split_id) {
+                view_state.cursors = state.cursors.clone();
+            }
+        }
+    }
+}
+
+/// Replace `$FILE` in the strings of a launch/attach configuration
+fn substitute_file(value: &serde_json::Value, file: &str) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(text) => serde_json::Value::String(text.replace("$FILE", file)),
+        serde_json::Value::Array(items) => serde_json::Value::Array(
+            items
+                .iter()
+                .map(|item| substitute_file(item, file))
+                .collect(),
+        ),
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.iter()
+                .map(|(key, item)| (key.clone(), substitute_file(item, file)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
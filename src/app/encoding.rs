@@ -0,0 +1,104 @@
+//! Changing a buffer's file encoding
+//!
+//! "Reopen with Encoding" reloads the file from disk, decoding it with an
+//! encoding chosen by the user (for when detection guessed wrong). "Save with
+//! Encoding" converts the file by saving the buffer in a new encoding.
+
+use super::*;
+
+impl Editor {
+    /// Open a prompt listing the available encodings, with the active
+    /// buffer's encoding selected
+    pub(crate) fn start_encoding_prompt(&mut self, prompt_type: PromptType) {
+        if self.active_state().buffer.file_path().is_none() {
+            self.set_status_message("Buffer has no file".to_string());
+            return;
+        }
+
+        let current = self.active_state().buffer.encoding();
+        let suggestions: Vec<Suggestion> = TextEncoding::ALL
+            .iter()
+            .map(|encoding| Suggestion {
+                text: encoding.display_name().to_string(),
+                description: (*encoding == current).then(|| "(current)".to_string()),
+                value: Some(encoding.display_name().to_string()),
+                disabled: false,
+                keybinding: None,
+                source: None,
+            })
+            .collect();
+        let current_index = TextEncoding::ALL
+            .iter()
+            .position(|encoding| *encoding == current)
+            .unwrap_or(0);
+
+        let message = match prompt_type {
+            PromptType::ReopenWithEncoding => "Reopen with encoding: ",
+            _ => "Save with encoding: ",
+        };
+        let mut prompt = crate::view::prompt::Prompt::with_suggestions(
+            message.to_string(),
+            prompt_type,
+            suggestions,
+        );
+        prompt.selected_suggestion = Some(current_index);
+        prompt.input = current.display_name().to_string();
+        prompt.cursor_pos = prompt.input.len();
+        self.prompt = Some(prompt);
+    }
+
+    /// Reload the active buffer's file, decoding it as `name`
+    pub(crate) fn reopen_with_encoding(&mut self, name: &str) {
+        let Some(encoding) = TextEncoding::from_name(name) else {
+            self.set_status_message(format!("Unknown encoding: {}", name));
+            return;
+        };
+        let Some(path) = self
+            .active_state()
+            .buffer
+            .file_path()
+            .map(|p| p.to_path_buf())
+        else {
+            self.set_status_message("Buffer has no file".to_string());
+            return;
+        };
+        // Reloading discards the buffer, so don't lose unsaved edits
+        if self.active_state().buffer.is_modified() {
+            self.set_status_message(
+                "Buffer has unsaved changes; save or revert before reopening".to_string(),
+            );
+            return;
+        }
+
+        match self.reload_active_file(&path, Some(encoding)) {
+            Ok(()) => self.set_status_message(format!("Reopened with {}", encoding)),
+            Err(e) => self.set_status_message(format!("Failed to reopen: {}", e)),
+        }
+    }
+
+    /// Save the active buffer, converting it to the encoding `name`
+    ///
+    /// If saving fails (e.g. the text contains characters the encoding cannot
+    /// represent) the buffer keeps its previous encoding.
+    pub(crate) fn save_with_encoding(&mut self, name: &str) {
+        let Some(encoding) = TextEncoding::from_name(name) else {
+            self.set_status_message(format!("Unknown encoding: {}", name));
+            return;
+        };
+
+        let buffer = &mut self.active_state_mut().buffer;
+        let previous = buffer.encoding();
+        let was_modified = buffer.is_modified();
+        buffer.set_encoding(encoding);
+
+        match self.save() {
+            Ok(()) => self.set_status_message(format!("Saved as {}", encoding)),
+            Err(e) => {
+                let buffer = &mut self.active_state_mut().buffer;
+                buffer.set_encoding(previous);
+                buffer.set_modified(was_modified);
+                self.set_status_message(format!("Failed to save as {}: {}", encoding, e));
+            }
+        }
+    }
+}
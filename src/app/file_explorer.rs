@@ -1,4 +1,5 @@
 use super::*;
+use crate::services::trash::ItemSize;
 use crate::view::file_tree::TreeNode;
 use std::path::PathBuf;
 
@@ -369,8 +370,7 @@ impl Editor {
                     if let Some(runtime) = &self.tokio_runtime {
                         let path_clone = file_path.clone();
                         let selected_id = selected_id;
-                        let result =
-                            runtime.block_on(async { tokio::fs::File::create(&path_clone).await });
+                        let result = runtime.block_on(self.fs_manager.write_file(&path_clone, &[]));
 
                         match result {
                             Ok(_) => {
@@ -378,6 +378,9 @@ impl Editor {
                                     get_parent_node_id(explorer.tree(), selected_id, node.is_dir());
                                 let tree = explorer.tree_mut();
                                 let _ = runtime.block_on(tree.refresh_node(parent_id));
+                                self.record_file_operation(FileOperation::Create {
+                                    path: file_path,
+                                });
                                 self.set_status_message(format!("Created {}", filename));
                             }
                             Err(e) => {
@@ -403,8 +406,7 @@ impl Editor {
                         let path_clone = dir_path.clone();
                         let dirname_clone = dirname.clone();
                         let selected_id = selected_id;
-                        let result =
-                            runtime.block_on(async { tokio::fs::create_dir(&path_clone).await });
+                        let result = runtime.block_on(self.fs_manager.create_dir(&path_clone));
 
                         match result {
                             Ok(_) => {
@@ -412,6 +414,9 @@ impl Editor {
                                     get_parent_node_id(explorer.tree(), selected_id, node.is_dir());
                                 let tree = explorer.tree_mut();
                                 let _ = runtime.block_on(tree.refresh_node(parent_id));
+                                self.record_file_operation(FileOperation::Create {
+                                    path: dir_path,
+                                });
                                 self.set_status_message(format!("Created {}", dirname_clone));
 
                                 // Enter rename mode for the new folder
@@ -435,43 +440,58 @@ impl Editor {
         }
     }
 
+    /// Ask for confirmation before deleting the selected files and
+    /// directories, telling how much is about to be removed
     pub fn file_explorer_delete(&mut self) {
-        if let Some(explorer) = &mut self.file_explorer {
-            if let Some(selected_id) = explorer.get_selected() {
-                // Don't allow deleting the root directory
-                if selected_id == explorer.tree().root_id() {
-                    self.set_status_message("Cannot delete project root".to_string());
-                    return;
-                }
+        let Some(paths) = self.file_explorer_selected_paths("delete") else {
+            return;
+        };
 
-                let node = explorer.tree().get_node(selected_id);
-                if let Some(node) = node {
-                    let path = node.entry.path.clone();
-                    let name = node.entry.name.clone();
+        let mut size = Some(ItemSize::default());
+        for path in &paths {
+            let item_size = self
+                .fs_manager
+                .block_on(ItemSize::measure(&self.fs_manager, path));
+            size = size.zip(item_size.ok()).map(|(total, item)| total + item);
+        }
+        let items = match paths.as_slice() {
+            [path] => format!(
+                "'{}'",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            _ => format!("{} items", paths.len()),
+        };
+        let items = match size {
+            Some(size) => format!("{} ({})", items, size.describe()),
+            None => items,
+        };
+        let message = if self.file_trash.is_some() {
+            format!("Move {} to trash? (y/N) ", items)
+        } else {
+            format!("Permanently delete {}? (y/N) ", items)
+        };
+        self.start_prompt(message, PromptType::ConfirmFileExplorerDelete { paths });
+    }
 
-                    if let Some(runtime) = &self.tokio_runtime {
-                        let result = if node.is_dir() {
-                            runtime.block_on(async { tokio::fs::remove_dir_all(&path).await })
-                        } else {
-                            runtime.block_on(async { tokio::fs::remove_file(&path).await })
-                        };
+    /// Directory things are pasted into: the selected directory, or the
+    /// directory of the selected file
+    pub(super) fn file_explorer_target_dir(&self) -> Option<PathBuf> {
+        let explorer = self.file_explorer.as_ref()?;
+        let node = explorer.tree().get_node(explorer.get_selected()?)?;
+        Some(get_parent_dir_path(node))
+    }
 
-                        match result {
-                            Ok(_) => {
-                                // For delete, always get the parent (the deleted item can't be refreshed)
-                                let parent_id =
-                                    get_parent_node_id(explorer.tree(), selected_id, false);
-                                let tree = explorer.tree_mut();
-                                let _ = runtime.block_on(tree.refresh_node(parent_id));
-                                self.set_status_message(format!("Deleted {}", name));
-                            }
-                            Err(e) => {
-                                self.set_status_message(format!("Error deleting: {}", e));
-                            }
-                        }
-                    }
-                }
-            }
+    pub fn file_explorer_extend_selection_up(&mut self) {
+        if let Some(explorer) = &mut self.file_explorer {
+            explorer.extend_selection_prev();
+            explorer.update_scroll_for_selection();
+        }
+    }
+
+    pub fn file_explorer_extend_selection_down(&mut self) {
+        if let Some(explorer) = &mut self.file_explorer {
+            explorer.extend_selection_next();
+            explorer.update_scroll_for_selection();
         }
     }
 
@@ -521,29 +541,23 @@ impl Editor {
             .map(|p| p.join(&new_name))
             .unwrap_or_else(|| original_path.clone());
 
-        if let Some(runtime) = &self.tokio_runtime {
-            let result =
-                runtime.block_on(async { tokio::fs::rename(&original_path, &new_path).await });
+        if let Err(e) = self.move_file(&original_path, &new_path) {
+            self.set_status_message(format!("Error renaming: {}", e));
+            return;
+        }
 
-            match result {
-                Ok(_) => {
-                    // Refresh the parent directory and select the renamed item
-                    if let Some(explorer) = &mut self.file_explorer {
-                        if let Some(selected_id) = explorer.get_selected() {
-                            let parent_id = get_parent_node_id(explorer.tree(), selected_id, false);
-                            let tree = explorer.tree_mut();
-                            let _ = runtime.block_on(tree.refresh_node(parent_id));
-                        }
-                        // Navigate to the renamed file to restore selection
-                        explorer.navigate_to_path(&new_path);
-                    }
-                    self.set_status_message(format!("Renamed {} to {}", original_name, new_name));
-                }
-                Err(e) => {
-                    self.set_status_message(format!("Error renaming: {}", e));
-                }
+        // Refresh the parent directory and select the renamed item
+        if let (Some(runtime), Some(explorer)) = (&self.tokio_runtime, &mut self.file_explorer) {
+            if let Some(selected_id) = explorer.get_selected() {
+                let parent_id = get_parent_node_id(explorer.tree(), selected_id, false);
+                let tree = explorer.tree_mut();
+                let _ = runtime.block_on(tree.refresh_node(parent_id));
             }
+            // Navigate to the renamed file to restore selection
+            explorer.navigate_to_path(&new_path);
         }
+        self.record_file_move(original_path, new_path);
+        self.set_status_message(format!("Renamed {} to {}", original_name, new_name));
     }
 
     pub fn file_explorer_toggle_hidden(&mut self) {
@@ -0,0 +1,687 @@
+//! File operations done from the file explorer
+//!
+//! Deleting, copying, moving and pasting files, with undo: each operation is
+//! recorded on a stack, most recent last, so it can be undone. Buffers open on
+//! affected files are kept in step: they follow their file when it is moved,
+//! are closed (or, with unsaved changes, marked as orphaned) when it is
+//! deleted, and stop being orphaned when it is restored.
+
+use super::*;
+use crate::services::trash::TrashedItem;
+use std::collections::VecDeque;
+
+/// File operations kept for undo at most
+const MAX_FILE_OPERATIONS: usize = 100;
+
+/// A file operation done from the file explorer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FileOperation {
+    /// A file or directory was moved to the trash
+    Trash(TrashedItem),
+    /// A file or directory was renamed or moved
+    Move { from: PathBuf, to: PathBuf },
+    /// A file or directory was created
+    Create { path: PathBuf },
+    /// Operations done together (such as pasting several files), undone together
+    Batch(Vec<FileOperation>),
+}
+
+/// Files copied or cut in the file explorer
+#[derive(Debug, Clone)]
+pub(crate) struct FileClipboard {
+    paths: Vec<PathBuf>,
+    /// Whether the files are moved, rather than copied, when pasted
+    cut: bool,
+}
+
+/// How to paste a file where one of the same name exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConflictResolution {
+    /// Paste under a free name
+    Rename,
+    /// Replace the existing file, which goes to the trash
+    Overwrite,
+    /// Leave the file out
+    Skip,
+}
+
+/// A paste (or drag-and-drop move) in progress
+#[derive(Debug)]
+pub(crate) struct PendingPaste {
+    /// Items still to paste, each with where it goes
+    items: VecDeque<(PathBuf, PathBuf)>,
+    /// Whether the items are moved rather than copied
+    cut: bool,
+    /// How to resolve the remaining conflicts, once the user chose for all
+    resolve_all: Option<ConflictResolution>,
+    /// Operations done so far, recorded together when the paste ends
+    done: Vec<FileOperation>,
+    /// Number of items pasted so far
+    pasted: usize,
+}
+
+impl Editor {
+    /// Record a file operation so it can be undone
+    pub(super) fn record_file_operation(&mut self, operation: FileOperation) {
+        if self.file_operations.len() >= MAX_FILE_OPERATIONS {
+            self.file_operations.remove(0);
+        }
+        self.file_operations.push(operation);
+    }
+
+    /// Record operations done together, to be undone together
+    fn record_file_operations(&mut self, mut operations: Vec<FileOperation>) {
+        match operations.len() {
+            0 => {}
+            1 => self.record_file_operation(operations.remove(0)),
+            _ => self.record_file_operation(FileOperation::Batch(operations)),
+        }
+    }
+
+    /// Record the renaming of a file, folding it into the creation of the
+    /// file when that was the last operation (as when naming a new folder)
+    pub(super) fn record_file_move(&mut self, from: PathBuf, to: PathBuf) {
+        match self.file_operations.last_mut() {
+            Some(FileOperation::Create { path }) if *path == from => *path = to,
+            _ => self.record_file_operation(FileOperation::Move { from, to }),
+        }
+    }
+
+    /// Paths of the items selected in the file explorer, leaving out those
+    /// inside other selected directories
+    ///
+    /// None (with a message saying why) when the project root is selected.
+    pub(super) fn file_explorer_selected_paths(&mut self, verb: &str) -> Option<Vec<PathBuf>> {
+        let explorer = self.file_explorer.as_ref()?;
+        let nodes = explorer.selected_nodes();
+        if nodes.contains(&explorer.tree().root_id()) {
+            self.set_status_message(format!("Cannot {} project root", verb));
+            return None;
+        }
+
+        let paths: Vec<PathBuf> = nodes
+            .iter()
+            .filter_map(|&id| explorer.tree().get_node(id))
+            .map(|node| node.entry.path.clone())
+            .collect();
+        let paths: Vec<PathBuf> = paths
+            .iter()
+            .filter(|path| {
+                !paths
+                    .iter()
+                    .any(|other| other != *path && path.starts_with(other))
+            })
+            .cloned()
+            .collect();
+        (!paths.is_empty()).then_some(paths)
+    }
+
+    /// Delete files or directories after the user confirmed it: move them to
+    /// the trash, or remove them for good when there is no trash
+    pub fn perform_file_explorer_delete(&mut self, paths: Vec<PathBuf>) {
+        let mut done = Vec::new();
+        let mut error = None;
+        for path in &paths {
+            match self.delete_path(path) {
+                Ok(trashed) => {
+                    self.close_buffers_under(path);
+                    done.extend(trashed.map(FileOperation::Trash));
+                }
+                Err(e) => {
+                    error = Some((path, e));
+                    break;
+                }
+            }
+        }
+
+        let refreshed: Vec<&Path> = paths.iter().map(PathBuf::as_path).collect();
+        self.refresh_file_explorer_parents(&refreshed, None);
+        let trashed = !done.is_empty();
+        self.record_file_operations(done);
+        let message = match error {
+            Some((path, e)) => format!("Error deleting {}: {}", file_name_of(path), e),
+            None if trashed => format!("Moved {} to trash", describe_items(&paths)),
+            None => format!("Deleted {}", describe_items(&paths)),
+        };
+        self.set_status_message(message);
+    }
+
+    /// Move `path` to the trash, or remove it when there is no trash
+    fn delete_path(&self, path: &Path) -> io::Result<Option<TrashedItem>> {
+        if let Some(trash) = &self.file_trash {
+            return trash.trash(path).map(Some);
+        }
+        let is_dir = self
+            .fs_manager
+            .block_on(self.fs_manager.is_dir(path))
+            .unwrap_or(false);
+        if is_dir {
+            self.fs_manager
+                .block_on(self.fs_manager.remove_dir_all(path))?;
+        } else {
+            self.fs_manager
+                .block_on(self.fs_manager.remove_file(path))?;
+        }
+        Ok(None)
+    }
+
+    /// Copy the selected files to the file explorer clipboard
+    pub fn file_explorer_copy(&mut self) {
+        self.set_file_clipboard(false);
+    }
+
+    /// Cut the selected files to the file explorer clipboard, to be moved
+    /// when pasted
+    pub fn file_explorer_cut(&mut self) {
+        self.set_file_clipboard(true);
+    }
+
+    fn set_file_clipboard(&mut self, cut: bool) {
+        let Some(paths) = self.file_explorer_selected_paths(if cut { "cut" } else { "copy" })
+        else {
+            return;
+        };
+        let verb = if cut { "Cut" } else { "Copied" };
+        self.set_status_message(format!("{} {}", verb, describe_items(&paths)));
+        self.file_clipboard = Some(FileClipboard { paths, cut });
+    }
+
+    /// Paste the copied or cut files into the selected directory (or the
+    /// directory of the selected file)
+    pub fn file_explorer_paste(&mut self) {
+        let Some(clipboard) = self.file_clipboard.clone() else {
+            self.set_status_message("Nothing to paste".to_string());
+            return;
+        };
+        let Some(mut target_dir) = self.file_explorer_target_dir() else {
+            return;
+        };
+        if clipboard.paths.contains(&target_dir) {
+            // Pasting a directory onto itself puts the copy next to it
+            if let Some(parent) = target_dir.parent() {
+                target_dir = parent.to_path_buf();
+            }
+        }
+        if clipboard.cut {
+            // Cut files can only be pasted once: they are gone from where
+            // they were cut
+            self.file_clipboard = None;
+        }
+        self.start_paste(clipboard.paths, target_dir, clipboard.cut);
+    }
+
+    /// Move files into `target_dir`, as when they are dragged onto it
+    pub fn file_explorer_move_to(&mut self, paths: Vec<PathBuf>, target_dir: PathBuf) {
+        self.start_paste(paths, target_dir, true);
+    }
+
+    fn start_paste(&mut self, sources: Vec<PathBuf>, target_dir: PathBuf, cut: bool) {
+        // Wrap up a paste left at a conflict prompt the user dismissed
+        if let Some(paste) = self.pending_paste.take() {
+            self.finish_paste(paste, None);
+        }
+
+        if let Some(source) = sources.iter().find(|source| target_dir.starts_with(source)) {
+            self.set_status_message(format!("Cannot paste {} into itself", file_name_of(source)));
+            return;
+        }
+
+        let items = sources
+            .into_iter()
+            .filter_map(|source| {
+                let dest = target_dir.join(source.file_name()?);
+                Some((source, dest))
+            })
+            .collect();
+        self.continue_paste(PendingPaste {
+            items,
+            cut,
+            resolve_all: None,
+            done: Vec::new(),
+            pasted: 0,
+        });
+    }
+
+    /// Paste the remaining items, stopping to ask the user at a conflict
+    fn continue_paste(&mut self, mut paste: PendingPaste) {
+        while let Some((source, dest)) = paste.items.pop_front() {
+            let resolution = if source == dest {
+                if paste.cut {
+                    // Moving a file to where it is
+                    continue;
+                }
+                // Copying a file next to itself
+                Some(ConflictResolution::Rename)
+            } else if self.fs_manager.block_on(self.fs_manager.exists(&dest)) {
+                match paste.resolve_all {
+                    Some(resolution) => Some(resolution),
+                    None => {
+                        let message = format!(
+                            "'{}' already exists. (r)ename, (o)verwrite, (s)kip, (C)ancel? (uppercase for all) ",
+                            file_name_of(&dest)
+                        );
+                        paste.items.push_front((source, dest));
+                        self.pending_paste = Some(paste);
+                        self.start_prompt(message, PromptType::FileExplorerPasteConflict);
+                        return;
+                    }
+                }
+            } else {
+                None
+            };
+
+            if let Err(e) = self.paste_item(&mut paste, source, dest, resolution) {
+                self.finish_paste(paste, Some(e));
+                return;
+            }
+        }
+        self.finish_paste(paste, None);
+    }
+
+    /// Resolve the conflict a paste stopped at with the user's answer
+    pub(super) fn resolve_paste_conflict(&mut self, input: &str) {
+        let Some(mut paste) = self.pending_paste.take() else {
+            return;
+        };
+        let answer = input.trim();
+        let resolution = match answer.to_lowercase().as_str() {
+            "r" | "rename" => ConflictResolution::Rename,
+            "o" | "overwrite" => ConflictResolution::Overwrite,
+            "s" | "skip" => ConflictResolution::Skip,
+            _ => {
+                self.pending_paste = Some(paste);
+                self.cancel_paste();
+                return;
+            }
+        };
+        if answer.starts_with(|c: char| c.is_uppercase()) {
+            paste.resolve_all = Some(resolution);
+        }
+
+        if let Some((source, dest)) = paste.items.pop_front() {
+            if let Err(e) = self.paste_item(&mut paste, source, dest, Some(resolution)) {
+                self.finish_paste(paste, Some(e));
+                return;
+            }
+        }
+        self.continue_paste(paste);
+    }
+
+    /// Stop a paste waiting at a conflict prompt, keeping what was pasted
+    pub(super) fn cancel_paste(&mut self) {
+        if let Some(paste) = self.pending_paste.take() {
+            self.finish_paste(paste, None);
+            self.set_status_message("Paste cancelled".to_string());
+        }
+    }
+
+    fn paste_item(
+        &mut self,
+        paste: &mut PendingPaste,
+        source: PathBuf,
+        dest: PathBuf,
+        resolution: Option<ConflictResolution>,
+    ) -> io::Result<()> {
+        let dest = match resolution {
+            Some(ConflictResolution::Skip) => return Ok(()),
+            Some(ConflictResolution::Rename) => self.free_copy_path(&dest),
+            Some(ConflictResolution::Overwrite) => {
+                let trashed = self.delete_path(&dest)?;
+                self.close_buffers_under(&dest);
+                paste.done.extend(trashed.map(FileOperation::Trash));
+                dest
+            }
+            None => dest,
+        };
+
+        if paste.cut {
+            self.move_file(&source, &dest)?;
+            paste.done.push(FileOperation::Move {
+                from: source,
+                to: dest,
+            });
+        } else {
+            self.fs_manager
+                .block_on(self.fs_manager.copy_recursive(&source, &dest))?;
+            paste.done.push(FileOperation::Create { path: dest });
+        }
+        paste.pasted += 1;
+        Ok(())
+    }
+
+    fn finish_paste(&mut self, paste: PendingPaste, error: Option<io::Error>) {
+        let mut touched: Vec<&Path> = Vec::new();
+        let mut last_pasted = None;
+        for operation in &paste.done {
+            match operation {
+                FileOperation::Move { from, to } => {
+                    touched.extend([from.as_path(), to.as_path()]);
+                    last_pasted = Some(to.as_path());
+                }
+                FileOperation::Create { path } => {
+                    touched.push(path);
+                    last_pasted = Some(path.as_path());
+                }
+                FileOperation::Trash(item) => touched.push(&item.original),
+                FileOperation::Batch(_) => {}
+            }
+        }
+        self.refresh_file_explorer_parents(&touched, last_pasted);
+
+        let verb = if paste.cut { "Moved" } else { "Pasted" };
+        let message = match error {
+            Some(e) => format!("Error pasting: {}", e),
+            None if paste.pasted == 0 => "Nothing pasted".to_string(),
+            None if paste.pasted == 1 => format!("{} 1 item", verb),
+            None => format!("{} {} items", verb, paste.pasted),
+        };
+        self.record_file_operations(paste.done);
+        self.set_status_message(message);
+    }
+
+    /// Duplicate the selected files next to themselves
+    pub fn file_explorer_duplicate(&mut self) {
+        let Some(paths) = self.file_explorer_selected_paths("duplicate") else {
+            return;
+        };
+
+        let mut done = Vec::new();
+        let mut error = None;
+        for path in &paths {
+            let copy = self.free_copy_path(path);
+            match self
+                .fs_manager
+                .block_on(self.fs_manager.copy_recursive(path, &copy))
+            {
+                Ok(()) => done.push(FileOperation::Create { path: copy }),
+                Err(e) => {
+                    error = Some(e);
+                    break;
+                }
+            }
+        }
+
+        let copies: Vec<PathBuf> = done
+            .iter()
+            .filter_map(|operation| match operation {
+                FileOperation::Create { path } => Some(path.clone()),
+                _ => None,
+            })
+            .collect();
+        let refreshed: Vec<&Path> = copies.iter().map(PathBuf::as_path).collect();
+        self.refresh_file_explorer_parents(&refreshed, copies.last().map(PathBuf::as_path));
+        self.record_file_operations(done);
+        let message = match error {
+            Some(e) => format!("Error duplicating: {}", e),
+            None => format!("Duplicated {}", describe_items(&paths)),
+        };
+        self.set_status_message(message);
+    }
+
+    /// A free path for a copy of `path` next to it: "name copy.ext",
+    /// "name copy 2.ext", ...
+    fn free_copy_path(&self, path: &Path) -> PathBuf {
+        let is_dir = self
+            .fs_manager
+            .block_on(self.fs_manager.is_dir(path))
+            .unwrap_or(false);
+        let name = file_name_of(path);
+        let (stem, extension) = match name.rfind('.') {
+            Some(dot) if dot > 0 && !is_dir => name.split_at(dot),
+            _ => (name.as_str(), ""),
+        };
+        let parent = path.parent().unwrap_or(Path::new(""));
+
+        let mut n = 1;
+        loop {
+            let copy_name = if n == 1 {
+                format!("{} copy{}", stem, extension)
+            } else {
+                format!("{} copy {}{}", stem, n, extension)
+            };
+            let candidate = parent.join(copy_name);
+            if !self.fs_manager.block_on(self.fs_manager.exists(&candidate)) {
+                return candidate;
+            }
+            n += 1;
+        }
+    }
+
+    /// Undo the most recent file operation done from the file explorer
+    pub fn file_explorer_undo(&mut self) {
+        let Some(operation) = self.file_operations.pop() else {
+            self.set_status_message("No file operation to undo".to_string());
+            return;
+        };
+
+        match self.undo_file_operation(operation) {
+            Ok(message) => self.set_status_message(message),
+            Err((e, remaining)) => {
+                self.set_status_message(format!("Cannot undo: {}", e));
+                // Keep what is left to undo so undoing can be retried once
+                // the obstacle is out of the way
+                self.file_operations.push(remaining);
+            }
+        }
+    }
+
+    fn undo_file_operation(
+        &mut self,
+        operation: FileOperation,
+    ) -> Result<String, (io::Error, FileOperation)> {
+        let result = match &operation {
+            FileOperation::Trash(item) => self.undo_trash(item),
+            FileOperation::Move { from, to } => self.undo_move(from, to),
+            FileOperation::Create { path } => self.undo_create(path),
+            FileOperation::Batch(operations) => {
+                let mut remaining = operations.clone();
+                while let Some(last) = remaining.pop() {
+                    if let Err((e, last)) = self.undo_file_operation(last) {
+                        remaining.push(last);
+                        return Err((e, FileOperation::Batch(remaining)));
+                    }
+                }
+                Ok(format!("Undid {} file operations", operations.len()))
+            }
+        };
+        result.map_err(|e| (e, operation))
+    }
+
+    fn undo_trash(&mut self, item: &TrashedItem) -> io::Result<String> {
+        let trash = self
+            .file_trash
+            .as_ref()
+            .ok_or_else(|| io::Error::other("no trash to restore from"))?;
+        trash.restore(item)?;
+
+        for (buffer_id, _) in self.buffers_under(&item.original) {
+            if let Some(metadata) = self.buffer_metadata.get_mut(&buffer_id) {
+                metadata.orphaned = false;
+            }
+        }
+        self.refresh_file_explorer_parents(&[&item.original], Some(&item.original));
+        Ok(format!("Restored {}", file_name_of(&item.original)))
+    }
+
+    fn undo_move(&mut self, from: &Path, to: &Path) -> io::Result<String> {
+        if self.fs_manager.block_on(self.fs_manager.exists(from)) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", from.display()),
+            ));
+        }
+        self.move_file(to, from)?;
+        self.refresh_file_explorer_parents(&[to, from], Some(from));
+        Ok(format!(
+            "Moved {} back to {}",
+            file_name_of(to),
+            file_name_of(from)
+        ))
+    }
+
+    fn undo_create(&mut self, path: &Path) -> io::Result<String> {
+        match &self.file_trash {
+            Some(trash) => {
+                trash.trash(path)?;
+            }
+            None => {
+                // Without a trash, only remove what is still empty
+                let entry = self.fs_manager.block_on(self.fs_manager.get_entry(path))?;
+                let is_empty = if entry.is_dir() {
+                    self.fs_manager
+                        .block_on(self.fs_manager.list_dir(path.to_path_buf()))?
+                        .is_empty()
+                } else {
+                    entry.metadata.as_ref().and_then(|m| m.size) == Some(0)
+                };
+                if !is_empty {
+                    return Err(io::Error::other(format!(
+                        "{} is no longer empty",
+                        path.display()
+                    )));
+                }
+                if entry.is_dir() {
+                    self.fs_manager
+                        .block_on(self.fs_manager.remove_dir_all(path))?;
+                } else {
+                    self.fs_manager
+                        .block_on(self.fs_manager.remove_file(path))?;
+                }
+            }
+        }
+
+        self.close_buffers_under(path);
+        self.refresh_file_explorer_parents(&[path], None);
+        Ok(format!("Removed {}", file_name_of(path)))
+    }
+
+    /// File-backed buffers whose file is `path` or inside it, with their paths
+    fn buffers_under(&self, path: &Path) -> Vec<(BufferId, PathBuf)> {
+        self.buffer_metadata
+            .iter()
+            .filter_map(|(id, metadata)| {
+                metadata
+                    .file_path()
+                    .filter(|file| file.starts_with(path))
+                    .map(|file| (*id, file.clone()))
+            })
+            .collect()
+    }
+
+    /// Close the buffers of files deleted along with `path`, marking those
+    /// with unsaved changes as orphaned instead
+    fn close_buffers_under(&mut self, path: &Path) {
+        for (buffer_id, _) in self.buffers_under(path) {
+            let modified = self
+                .buffers
+                .get(&buffer_id)
+                .is_some_and(|state| state.buffer.is_modified());
+            if modified {
+                if let Some(metadata) = self.buffer_metadata.get_mut(&buffer_id) {
+                    metadata.orphaned = true;
+                }
+            } else {
+                let _ = self.force_close_buffer(buffer_id);
+            }
+        }
+    }
+
+    /// Point the buffers of files moved from `from` to `to` at their new paths
+    pub(super) fn retarget_buffers(&mut self, from: &Path, to: &Path) {
+        for (buffer_id, old_path) in self.buffers_under(from) {
+            let new_path = match old_path.strip_prefix(from) {
+                Ok(relative) if !relative.as_os_str().is_empty() => to.join(relative),
+                _ => to.to_path_buf(),
+            };
+            if let Some(state) = self.buffers.get_mut(&buffer_id) {
+                state.buffer.set_file_path(new_path.clone());
+            }
+            if let Some(metadata) = self.buffer_metadata.get_mut(&buffer_id) {
+                metadata.set_file_path(new_path.clone(), &self.working_dir);
+            }
+            self.lsp_reopen_renamed_buffer(buffer_id, &old_path);
+            if let Some(mtime) = self.file_mod_times.remove(&old_path) {
+                self.file_mod_times.insert(new_path, mtime);
+            }
+        }
+    }
+
+    /// Move a file or directory, keeping the language servers and the
+    /// buffers open on it in step
+    pub(super) fn move_file(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+        let renames = self.lsp_will_rename_files(&[(from.to_path_buf(), to.to_path_buf())]);
+        let result = self
+            .fs_manager
+            .block_on(self.fs_manager.move_path(from, to));
+        if result.is_ok() {
+            self.retarget_buffers(from, to);
+        }
+        self.lsp_did_rename_files(renames, result.is_ok());
+        result
+    }
+
+    /// Re-read the explorer directories holding `paths` that are shown, then
+    /// select `select` (or a re-read directory, when the selection went away)
+    pub(super) fn refresh_file_explorer_parents(&mut self, paths: &[&Path], select: Option<&Path>) {
+        let Some(explorer) = &mut self.file_explorer else {
+            return;
+        };
+
+        let mut dirs: Vec<&Path> = Vec::new();
+        for dir in paths.iter().filter_map(|path| path.parent()) {
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        let mut refreshed = Vec::new();
+        for dir in dirs {
+            let Some(dir_id) = explorer
+                .tree()
+                .get_node_by_path(dir)
+                .filter(|node| node.is_expanded())
+                .map(|node| node.id)
+            else {
+                continue;
+            };
+            let _ = self
+                .fs_manager
+                .block_on(explorer.tree_mut().refresh_node(dir_id));
+            refreshed.push(dir);
+        }
+        if refreshed.is_empty() {
+            return;
+        }
+
+        // Node ids of the re-read directories' contents changed
+        explorer.clear_marks();
+        if let Some(select) = select {
+            explorer.navigate_to_path(select);
+        }
+        let selection_gone = explorer
+            .get_selected()
+            .is_none_or(|id| explorer.tree().get_node(id).is_none());
+        if selection_gone {
+            let dir_id = refreshed
+                .iter()
+                .find_map(|dir| explorer.tree().get_node_by_path(dir))
+                .map(|node| node.id);
+            explorer.set_selected(dir_id);
+        }
+    }
+}
+
+/// Name of the file or directory at `path`, for messages
+fn file_name_of(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// Describe a set of items for messages: the item's name if there is one,
+/// else how many there are
+fn describe_items(paths: &[PathBuf]) -> String {
+    match paths {
+        [path] => format!("'{}'", file_name_of(path)),
+        _ => format!("{} items", paths.len()),
+    }
+}
@@ -0,0 +1,428 @@
+//! Code folding
+//!
+//! Fold ranges are requested from the language server when it is running for
+//! the buffer's language and supports `textDocument/foldingRange`. Otherwise
+//! (or if the server returns nothing) they are computed from the tree-sitter
+//! parse of the buffer, falling back to indentation; see
+//! [`crate::primitives::folding`].
+//!
+//! Folds are view state, kept per split in [`SplitViewState::folds`]. They are
+//! shifted on edits, skipped by cursor movement and expanded again when a
+//! cursor ends up inside one (after a search, goto line or undo, for example).
+
+use super::*;
+use crate::model::buffer::Buffer;
+use crate::primitives::display_width::byte_offset_at_visual_column;
+use crate::primitives::folding::{
+    fold_depths, indent_fold_ranges, innermost_fold_range, normalize_fold_ranges,
+    tree_sitter_fold_ranges, FoldRange,
+};
+use crate::view::folding::{Fold, FoldState};
+
+/// A fold action waiting for the buffer's fold ranges
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FoldCommand {
+    /// Fold the innermost unfolded region around the cursor
+    Fold,
+    /// Fold every region
+    FoldAll,
+    /// Keep the first `n` nesting levels open and fold the regions below them
+    UnfoldToLevel(usize),
+}
+
+impl Editor {
+    /// Run a fold command once the fold ranges of the active buffer are known
+    pub(crate) fn run_fold_command(&mut self, command: FoldCommand) {
+        if let Some(request_id) = self.request_lsp_folding_ranges() {
+            self.pending_folding_range_request = Some((request_id, command));
+            return;
+        }
+        let ranges = self.local_fold_ranges();
+        self.apply_fold_command(command, ranges);
+    }
+
+    /// Expand the folded region whose header is on the cursor's line
+    pub(crate) fn unfold_at_cursor(&mut self) {
+        let buffer_id = self.active_buffer();
+        let split_id = self.split_manager.active_split();
+        let state = self.active_state();
+        let cursor_line = state
+            .buffer
+            .get_line_number(state.cursors.primary().position);
+        let header_lines: Vec<(Fold, usize)> = self
+            .split_view_states
+            .get(&split_id)
+            .and_then(|vs| vs.folds.get(&buffer_id))
+            .map(|folds| {
+                folds
+                    .folds()
+                    .iter()
+                    .map(|fold| (*fold, state.buffer.get_line_number(fold.header_end())))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let removed = self.active_folds_mut().remove_where(|fold| {
+            header_lines
+                .iter()
+                .any(|(f, line)| f == fold && *line == cursor_line)
+        });
+        if removed == 0 {
+            self.set_status_message("No folded region at cursor".to_string());
+        } else {
+            self.sync_active_hidden_ranges();
+        }
+    }
+
+    /// Expand every folded region of the active buffer in the active split
+    pub(crate) fn unfold_all(&mut self) {
+        self.active_folds_mut().clear();
+        self.sync_active_hidden_ranges();
+    }
+
+    /// Handle the "Unfold to level" prompt
+    pub(crate) fn handle_fold_level_input(&mut self, input: &str) {
+        match input.trim().parse::<usize>() {
+            Ok(level) => self.run_fold_command(FoldCommand::UnfoldToLevel(level)),
+            Err(_) => self.set_status_message(format!("Invalid fold level: {}", input)),
+        }
+    }
+
+    /// Handle LSP folding range response
+    pub(super) fn handle_lsp_folding_ranges(
+        &mut self,
+        request_id: u64,
+        uri: String,
+        ranges: Vec<lsp_types::FoldingRange>,
+    ) {
+        let Some((pending_id, command)) = self.pending_folding_range_request else {
+            return;
+        };
+        if pending_id != request_id {
+            tracing::debug!(
+                "Ignoring stale folding range response (request_id={})",
+                request_id
+            );
+            return;
+        }
+        self.pending_folding_range_request = None;
+
+        // The command applies to the buffer it was issued for
+        if self.find_buffer_by_uri(&uri) != Some(self.active_buffer()) {
+            return;
+        }
+
+        let ranges = normalize_fold_ranges(
+            ranges
+                .iter()
+                .map(|range| FoldRange::new(range.start_line as usize, range.end_line as usize)),
+        );
+        tracing::debug!("Received {} folding ranges for {}", ranges.len(), uri);
+        let ranges = if ranges.is_empty() {
+            self.local_fold_ranges()
+        } else {
+            ranges
+        };
+        self.apply_fold_command(command, ranges);
+    }
+
+    /// Send `textDocument/foldingRange` for the active buffer if its server is ready
+    fn request_lsp_folding_ranges(&mut self) -> Option<u64> {
+        let metadata = self.buffer_metadata.get(&self.active_buffer())?;
+        let uri = metadata.file_uri()?.clone();
+        let language = detect_language(metadata.file_path()?, &self.config.languages)?;
+
+        let lsp = self.lsp.as_mut()?;
+        if !lsp.is_server_ready(&language) {
+            return None;
+        }
+        let client = lsp.get_or_spawn_for(&language, LspFeature::FoldingRanges)?;
+        if !client.is_initialized() {
+            return None;
+        }
+        let request_id = self.next_lsp_request_id;
+        if let Err(e) = client.folding_ranges(request_id, uri) {
+            tracing::debug!("Failed to request folding ranges: {}", e);
+            return None;
+        }
+        self.next_lsp_request_id += 1;
+        Some(request_id)
+    }
+
+    /// Fold ranges of the active buffer from tree-sitter, or from indentation
+    fn local_fold_ranges(&self) -> Vec<FoldRange> {
+        let state = self.active_state();
+        let Some(text) = state.buffer.to_string() else {
+            return Vec::new();
+        };
+        state
+            .highlighter
+            .language()
+            .and_then(|language| tree_sitter_fold_ranges(language, &text))
+            .filter(|ranges| !ranges.is_empty())
+            .unwrap_or_else(|| indent_fold_ranges(&text, self.config.editor.tab_size))
+    }
+
+    fn apply_fold_command(&mut self, command: FoldCommand, ranges: Vec<FoldRange>) {
+        match command {
+            FoldCommand::Fold => self.fold_at_cursor(&ranges),
+            FoldCommand::FoldAll => self.fold_from_depth(&ranges, 0),
+            FoldCommand::UnfoldToLevel(level) => self.fold_from_depth(&ranges, level),
+        }
+    }
+
+    fn fold_at_cursor(&mut self, ranges: &[FoldRange]) {
+        let state = self.active_state();
+        let cursor_line = state
+            .buffer
+            .get_line_number(state.cursors.primary().position);
+        let folded: Vec<Fold> = self
+            .split_view_states
+            .get(&self.split_manager.active_split())
+            .and_then(|vs| vs.folds.get(&self.active_buffer()))
+            .map(|folds| folds.folds().to_vec())
+            .unwrap_or_default();
+        let candidates: Vec<FoldRange> = ranges
+            .iter()
+            .copied()
+            .filter(|range| {
+                self.fold_for_range(*range)
+                    .is_some_and(|fold| !folded.contains(&fold))
+            })
+            .collect();
+
+        match innermost_fold_range(candidates, cursor_line).and_then(|r| self.fold_for_range(r)) {
+            Some(fold) => {
+                self.active_folds_mut().add(fold);
+                self.finish_folding();
+            }
+            None => self.set_status_message("No foldable region at cursor".to_string()),
+        }
+    }
+
+    /// Fold the regions nested `depth` levels deep or more, expanding the others
+    fn fold_from_depth(&mut self, ranges: &[FoldRange], depth: usize) {
+        let folds: Vec<Fold> = ranges
+            .iter()
+            .zip(fold_depths(ranges))
+            .filter(|(_, range_depth)| *range_depth >= depth)
+            .filter_map(|(range, _)| self.fold_for_range(*range))
+            .collect();
+        let count = folds.len();
+
+        let state = self.active_folds_mut();
+        state.clear();
+        for fold in folds {
+            state.add(fold);
+        }
+        self.finish_folding();
+        self.set_status_message(format!("Folded {} regions", count));
+    }
+
+    /// The byte range hidden by folding a line range of the active buffer
+    ///
+    /// The last line of a buffer without a trailing newline is never hidden,
+    /// so that a fold always ends at the start of a line.
+    fn fold_for_range(&self, range: FoldRange) -> Option<Fold> {
+        let buffer = &self.active_state().buffer;
+        let start = buffer.line_start_offset(range.start_line + 1)?;
+        let end = buffer
+            .line_start_offset(range.end_line + 1)
+            .or_else(|| buffer.line_start_offset(range.end_line))?;
+        (start < end).then(|| Fold::new(start, end))
+    }
+
+    /// Move cursors out of the regions just folded and update the viewport
+    fn finish_folding(&mut self) {
+        let split_id = self.split_manager.active_split();
+        let buffer_id = self.active_buffer();
+        let folds = self
+            .split_view_states
+            .get(&split_id)
+            .and_then(|vs| vs.folds.get(&buffer_id))
+            .cloned()
+            .unwrap_or_default();
+
+        let moves: Vec<Event> = self
+            .active_state()
+            .cursors
+            .iter()
+            .filter_map(|(cursor_id, cursor)| {
+                let fold = folds.hiding(cursor.position)?;
+                Some(Event::MoveCursor {
+                    cursor_id,
+                    old_position: cursor.position,
+                    new_position: fold.header_end(),
+                    old_anchor: cursor.anchor,
+                    new_anchor: None,
+                    old_sticky_column: cursor.sticky_column,
+                    new_sticky_column: 0,
+                })
+            })
+            .collect();
+        for event in moves {
+            self.apply_event_to_active_buffer(&event);
+        }
+        self.sync_active_hidden_ranges();
+    }
+
+    fn active_folds_mut(&mut self) -> &mut FoldState {
+        let split_id = self.split_manager.active_split();
+        let buffer_id = self.active_buffer();
+        self.split_view_states
+            .get_mut(&split_id)
+            .expect("active split has a view state")
+            .folds
+            .entry(buffer_id)
+            .or_default()
+    }
+
+    fn sync_active_hidden_ranges(&mut self) {
+        let split_id = self.split_manager.active_split();
+        let buffer_id = self.active_buffer();
+        if let Some(view_state) = self.split_view_states.get_mut(&split_id) {
+            view_state.sync_hidden_ranges(buffer_id);
+        }
+    }
+
+    /// Shift the folds of the active buffer in every split after an edit
+    pub(super) fn adjust_folds_for_event(&mut self, event: &Event) {
+        let adjustments = edit_adjustments(event);
+        if adjustments.is_empty() {
+            return;
+        }
+        let buffer_id = self.active_buffer();
+        for view_state in self.split_view_states.values_mut() {
+            if let Some(folds) = view_state.folds.get_mut(&buffer_id) {
+                for (position, old_len, new_len) in &adjustments {
+                    folds.adjust_for_edit(*position, *old_len, *new_len);
+                }
+            }
+        }
+    }
+
+    /// Expand folds that hide a cursor or no longer match the buffer's lines
+    ///
+    /// Called before rendering, so any cursor movement (search, goto line,
+    /// undo, mouse clicks) reveals the text it lands on.
+    pub(super) fn reveal_folded_cursors(&mut self) {
+        let active_split = self.split_manager.active_split();
+        for (split_id, view_state) in self.split_view_states.iter_mut() {
+            let Some(buffer_id) = self.split_manager.buffer_for_split(*split_id) else {
+                continue;
+            };
+            let Some(state) = self.buffers.get(&buffer_id) else {
+                continue;
+            };
+            let Some(folds) = view_state.folds.get_mut(&buffer_id) else {
+                continue;
+            };
+            if folds.is_empty() {
+                continue;
+            }
+
+            let cursors = if *split_id == active_split {
+                &state.cursors
+            } else {
+                &view_state.cursors
+            };
+            let positions: Vec<usize> = cursors.iter().map(|(_, c)| c.position).collect();
+            let buffer = &state.buffer;
+            let buffer_len = buffer.len();
+            let is_line_start =
+                |pos: usize| pos == 0 || buffer.slice_bytes(pos - 1..pos) == b"\n".as_slice();
+            folds.remove_where(|fold| {
+                fold.start == 0
+                    || fold.end > buffer_len
+                    || !is_line_start(fold.start)
+                    || !is_line_start(fold.end)
+                    || positions.iter().any(|&pos| fold.hides(pos))
+            });
+        }
+    }
+
+    /// Move cursor events out of folded regions
+    ///
+    /// A move landing inside a fold goes past it when moving forward and to
+    /// the fold's header line when moving backward. Vertical moves keep their
+    /// goal column on the line they land on.
+    pub(super) fn skip_folded_lines(&mut self, events: &mut [Event], vertical: bool) {
+        let split_id = self.split_manager.active_split();
+        let buffer_id = self.active_buffer();
+        let Some(folds) = self
+            .split_view_states
+            .get(&split_id)
+            .and_then(|vs| vs.folds.get(&buffer_id))
+            .filter(|folds| !folds.is_empty())
+            .cloned()
+        else {
+            return;
+        };
+        let estimated_line_length = self.config.editor.estimated_line_length;
+        let buffer = &mut self.active_state_mut().buffer;
+
+        for event in events.iter_mut() {
+            match event {
+                Event::Batch { events, .. } => {
+                    for event in events.iter_mut() {
+                        Self::skip_fold_for_move(
+                            buffer,
+                            &folds,
+                            event,
+                            vertical,
+                            estimated_line_length,
+                        );
+                    }
+                }
+                event => {
+                    Self::skip_fold_for_move(buffer, &folds, event, vertical, estimated_line_length)
+                }
+            }
+        }
+    }
+
+    fn skip_fold_for_move(
+        buffer: &mut Buffer,
+        folds: &FoldState,
+        event: &mut Event,
+        vertical: bool,
+        estimated_line_length: usize,
+    ) {
+        let Event::MoveCursor {
+            old_position,
+            new_position,
+            new_sticky_column,
+            ..
+        } = event
+        else {
+            return;
+        };
+        let Some(fold) = folds.hiding(*new_position) else {
+            return;
+        };
+        let forward = *new_position > *old_position;
+
+        *new_position = if !vertical {
+            if forward {
+                fold.end
+            } else {
+                fold.header_end()
+            }
+        } else {
+            let line_start = if forward {
+                fold.end
+            } else {
+                buffer
+                    .line_iterator(fold.header_end(), estimated_line_length)
+                    .current_position()
+            };
+            let line = buffer
+                .line_iterator(line_start, estimated_line_length)
+                .next()
+                .map(|(_, content)| content)
+                .unwrap_or_default();
+            line_start
+                + byte_offset_at_visual_column(line.trim_end_matches('\n'), *new_sticky_column)
+        };
+    }
+}
@@ -0,0 +1,485 @@
+//! Buffer formatting
+//!
+//! "Format Buffer" runs the external formatter configured for the buffer's
+//! language, or falls back to the language server's `textDocument/formatting`
+//! (`textDocument/rangeFormatting` when there is a selection). Either way the
+//! formatted text is diffed against the buffer and only the changed regions
+//! are replaced, as a single undoable batch of events, so cursors, markers and
+//! undo history survive formatting.
+//!
+//! Formatters run off the main thread. With format-on-save the buffer is saved
+//! when the result arrives, or unformatted if it takes too long.
+
+use super::*;
+use crate::config::{FormatterConfig, LanguageConfig};
+use crate::model::line_diff::diff_replacements;
+use crate::services::formatter::run_formatter;
+use std::time::{Duration, Instant};
+
+/// How long a save waits for formatting before saving the buffer unformatted
+const FORMAT_ON_SAVE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A formatting request, to the language server or an external formatter,
+/// waiting for its result
+#[derive(Debug, Clone)]
+pub(crate) struct PendingFormatRequest {
+    pub request_id: u64,
+    pub buffer_id: BufferId,
+    /// When a save was requested, if the buffer is saved once formatted
+    /// (format-on-save); after [`FORMAT_ON_SAVE_TIMEOUT`] it is saved as is
+    pub save_requested: Option<Instant>,
+    /// Content given to the external formatter, replaced by its output
+    /// (`None` for language server requests)
+    pub content: Option<String>,
+}
+
+impl Editor {
+    /// Language configuration for a buffer, detected from its file extension
+    fn buffer_language(&self, buffer_id: BufferId) -> Option<(String, &LanguageConfig)> {
+        let path = self.buffers.get(&buffer_id)?.buffer.file_path()?;
+        let language = detect_language(path, &self.config.languages)?;
+        let config = self.config.languages.get(&language)?;
+        Some((language, config))
+    }
+
+    /// Format the active buffer
+    pub fn format_buffer(&mut self) {
+        let buffer_id = self.active_buffer();
+        let Some((language, _)) = self.buffer_language(buffer_id) else {
+            self.set_status_message("No formatter for this buffer".to_string());
+            return;
+        };
+
+        match self.start_formatting(buffer_id, false) {
+            Ok(true) => {}
+            Ok(false) => {
+                self.set_status_message(format!("No formatter configured for {}", language))
+            }
+            Err(e) => self.set_status_message(format!("Format failed: {}", e)),
+        }
+    }
+
+    /// Format the active buffer before saving it, if its language has
+    /// `format_on_save` set
+    ///
+    /// Returns `true` if formatting was started; the buffer is then saved
+    /// when it is done (or after [`FORMAT_ON_SAVE_TIMEOUT`]), so the caller
+    /// must not save it now.
+    pub(crate) fn request_format_on_save(&mut self) -> bool {
+        let buffer_id = self.active_buffer();
+        if !matches!(self.buffer_language(buffer_id), Some((_, config)) if config.format_on_save) {
+            return false;
+        }
+        match self.start_formatting(buffer_id, true) {
+            Ok(started) => started,
+            Err(e) => {
+                self.finish_formatting(buffer_id, true, Err(e));
+                true
+            }
+        }
+    }
+
+    /// Start formatting a buffer with its external formatter, or else its
+    /// language server
+    ///
+    /// If the buffer is already being formatted, that request is saved after
+    /// too when `save_after` is set, instead of starting another. Returns
+    /// `false` if the buffer has no formatter.
+    fn start_formatting(&mut self, buffer_id: BufferId, save_after: bool) -> Result<bool, String> {
+        if let Some(pending) = self
+            .pending_format_requests
+            .iter_mut()
+            .find(|pending| pending.buffer_id == buffer_id)
+        {
+            if save_after && pending.save_requested.is_none() {
+                pending.save_requested = Some(Instant::now());
+            }
+            return Ok(true);
+        }
+
+        let formatter = self
+            .buffer_language(buffer_id)
+            .and_then(|(_, config)| config.formatter.clone());
+        match formatter {
+            Some(formatter) => self
+                .spawn_external_formatter(buffer_id, formatter, save_after)
+                .map(|()| true),
+            None => Ok(self.request_lsp_formatting(buffer_id, save_after)),
+        }
+    }
+
+    /// Pipe a buffer through an external formatter off the main thread; the
+    /// result arrives as [`AsyncMessage::FormatterFinished`]
+    fn spawn_external_formatter(
+        &mut self,
+        buffer_id: BufferId,
+        formatter: FormatterConfig,
+        save_after: bool,
+    ) -> Result<(), String> {
+        let (Some(runtime), Some(bridge)) = (&self.tokio_runtime, &self.async_bridge) else {
+            return Err("async runtime not available".to_string());
+        };
+        let state = self
+            .buffers
+            .get(&buffer_id)
+            .ok_or_else(|| "Buffer not found".to_string())?;
+        let content = state
+            .buffer
+            .to_string()
+            .ok_or_else(|| "buffer is not fully loaded".to_string())?;
+        let file_path = state.buffer.file_path().map(|p| p.to_path_buf());
+
+        let request_id = self.next_lsp_request_id;
+        self.next_lsp_request_id += 1;
+        let sender = bridge.sender();
+        let working_dir = self.working_dir.clone();
+        let input = content.clone();
+        runtime.spawn_blocking(move || {
+            let result = run_formatter(&formatter, &input, file_path.as_deref(), &working_dir);
+            let _ = sender.send(AsyncMessage::FormatterFinished { request_id, result });
+        });
+
+        self.pending_format_requests.push(PendingFormatRequest {
+            request_id,
+            buffer_id,
+            save_requested: save_after.then(Instant::now),
+            content: Some(content),
+        });
+        Ok(())
+    }
+
+    /// Send an LSP formatting request for a buffer
+    ///
+    /// Formats the primary selection if there is one, otherwise the whole
+    /// buffer. Returns `false` if no language server is available.
+    fn request_lsp_formatting(&mut self, buffer_id: BufferId, save_after: bool) -> bool {
+        let Some(uri) = self
+            .buffer_metadata
+            .get(&buffer_id)
+            .and_then(|meta| meta.file_uri())
+            .cloned()
+        else {
+            return false;
+        };
+        let Some((language, _)) = self.buffer_language(buffer_id) else {
+            return false;
+        };
+        let Some(state) = self.buffers.get(&buffer_id) else {
+            return false;
+        };
+
+        // Format-on-save always formats the whole buffer
+        let range = state
+            .cursors
+            .primary()
+            .selection_range()
+            .filter(|_| !save_after)
+            .map(|range| {
+                let (start_line, start_char) = state.buffer.position_to_lsp_position(range.start);
+                let (end_line, end_char) = state.buffer.position_to_lsp_position(range.end);
+                LspRange {
+                    start: Position::new(start_line as u32, start_char as u32),
+                    end: Position::new(end_line as u32, end_char as u32),
+                }
+            });
+        let tab_size = self.config.editor.tab_size as u32;
+        let feature = if range.is_some() {
+            LspFeature::RangeFormatting
+        } else {
+            LspFeature::Formatting
+        };
+
+        let Some(handle) = self
+            .lsp
+            .as_mut()
+            .and_then(|lsp| lsp.get_or_spawn_for(&language, feature))
+        else {
+            return false;
+        };
+
+        let request_id = self.next_lsp_request_id;
+        self.next_lsp_request_id += 1;
+        if let Err(e) = handle.formatting(request_id, uri, range, tab_size, true) {
+            tracing::warn!("Failed to send formatting request: {}", e);
+            return false;
+        }
+
+        self.pending_format_requests.push(PendingFormatRequest {
+            request_id,
+            buffer_id,
+            save_requested: save_after.then(Instant::now),
+            content: None,
+        });
+        self.lsp_status = "LSP: formatting...".to_string();
+        true
+    }
+
+    /// Take the pending formatting request `request_id`, if it is still
+    /// waiting for its result
+    fn take_pending_format_request(&mut self, request_id: u64) -> Option<PendingFormatRequest> {
+        let index = self
+            .pending_format_requests
+            .iter()
+            .position(|pending| pending.request_id == request_id)?;
+        Some(self.pending_format_requests.remove(index))
+    }
+
+    /// Handle a formatting response from the language server
+    pub(crate) fn handle_formatting_response(
+        &mut self,
+        request_id: u64,
+        result: Result<Vec<lsp_types::TextEdit>, String>,
+    ) {
+        let Some(pending) = self.take_pending_format_request(request_id) else {
+            tracing::debug!("Ignoring stale formatting response {}", request_id);
+            return;
+        };
+        self.lsp_status.clear();
+
+        let outcome = result.and_then(|edits| {
+            self.apply_lsp_formatting_edits(pending.buffer_id, edits)
+                .map_err(|e| e.to_string())
+        });
+        self.finish_formatting(pending.buffer_id, pending.save_requested.is_some(), outcome);
+    }
+
+    /// Handle the output of an external formatter
+    pub(crate) fn handle_formatter_finished(
+        &mut self,
+        request_id: u64,
+        result: Result<String, String>,
+    ) {
+        let Some(pending) = self.take_pending_format_request(request_id) else {
+            tracing::debug!("Ignoring stale formatter result {}", request_id);
+            return;
+        };
+
+        let outcome = result.and_then(|formatted| {
+            let content = pending.content.as_deref().unwrap_or_default();
+            let current = self
+                .buffers
+                .get(&pending.buffer_id)
+                .and_then(|state| state.buffer.to_string());
+            if current.as_deref() != Some(content) {
+                return Err("buffer changed while formatting".to_string());
+            }
+            self.apply_text_replacement(pending.buffer_id, content, &formatted, "Format Buffer")
+                .map_err(|e| e.to_string())
+        });
+        self.finish_formatting(pending.buffer_id, pending.save_requested.is_some(), outcome);
+    }
+
+    /// Save the buffers whose format-on-save took longer than
+    /// [`FORMAT_ON_SAVE_TIMEOUT`], unformatted
+    ///
+    /// A result arriving later is ignored. Returns whether any request timed
+    /// out.
+    pub(super) fn check_format_on_save_timeouts(&mut self) -> bool {
+        let (timed_out, pending): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.pending_format_requests)
+                .into_iter()
+                .partition(|pending| {
+                    pending
+                        .save_requested
+                        .is_some_and(|requested| requested.elapsed() >= FORMAT_ON_SAVE_TIMEOUT)
+                });
+        self.pending_format_requests = pending;
+
+        let any_timed_out = !timed_out.is_empty();
+        for pending in timed_out {
+            if pending.content.is_none() {
+                self.lsp_status.clear();
+            }
+            let error = format!("timed out after {}s", FORMAT_ON_SAVE_TIMEOUT.as_secs());
+            self.finish_formatting(pending.buffer_id, true, Err(error));
+        }
+        any_timed_out
+    }
+
+    /// Report the outcome of formatting, saving the buffer if that was
+    /// requested (even if formatting failed)
+    fn finish_formatting(
+        &mut self,
+        buffer_id: BufferId,
+        save_after: bool,
+        outcome: Result<bool, String>,
+    ) {
+        if save_after {
+            // Save even if formatting failed; the user asked for a save
+            let old_active = self.active_buffer();
+            self.set_active_buffer(buffer_id);
+            let saved = self.save();
+            self.set_active_buffer(old_active);
+            match (saved, outcome) {
+                (Err(e), _) => self.set_status_message(format!("Failed to save: {}", e)),
+                (Ok(()), Err(e)) => {
+                    self.set_status_message(format!("Saved (formatting failed: {})", e))
+                }
+                (Ok(()), Ok(_)) => {}
+            }
+        } else {
+            match outcome {
+                Ok(true) => self.set_status_message("Formatted".to_string()),
+                Ok(false) => self.set_status_message("Already formatted".to_string()),
+                Err(e) => self.set_status_message(format!("Format failed: {}", e)),
+            }
+        }
+    }
+
+    /// Apply LSP formatting edits to a buffer as a minimal diff
+    ///
+    /// Servers often return one edit replacing the whole document, so the
+    /// edits are applied to a copy of the text first and the result diffed
+    /// against the buffer. Returns whether the buffer changed.
+    fn apply_lsp_formatting_edits(
+        &mut self,
+        buffer_id: BufferId,
+        edits: Vec<lsp_types::TextEdit>,
+    ) -> io::Result<bool> {
+        let state = self
+            .buffers
+            .get(&buffer_id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Buffer not found"))?;
+        let content = state
+            .buffer
+            .to_string()
+            .ok_or_else(|| io::Error::other("buffer is not fully loaded"))?;
+
+        let byte_edits: Vec<(Range<usize>, String)> = edits
+            .into_iter()
+            .map(|edit| {
+                let start = state.buffer.lsp_position_to_byte(
+                    edit.range.start.line as usize,
+                    edit.range.start.character as usize,
+                );
+                let end = state.buffer.lsp_position_to_byte(
+                    edit.range.end.line as usize,
+                    edit.range.end.character as usize,
+                );
+                (start..end.max(start), edit.new_text)
+            })
+            .collect();
+        let formatted = apply_text_edits(&content, byte_edits).map_err(io::Error::other)?;
+
+        self.apply_text_replacement(buffer_id, &content, &formatted, "Format Buffer")
+    }
+
+    /// Replace `old` (the buffer's current content) with `new` by applying
+    /// only the changed regions as one undoable batch
+    ///
+    /// Returns whether anything changed.
+    pub(super) fn apply_text_replacement(
+        &mut self,
+        buffer_id: BufferId,
+        old: &str,
+        new: &str,
+        description: &str,
+    ) -> io::Result<bool> {
+        let replacements = diff_replacements(old.as_bytes(), new.as_bytes());
+        if replacements.is_empty() {
+            return Ok(false);
+        }
+
+        let state = self
+            .buffers
+            .get_mut(&buffer_id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Buffer not found"))?;
+        let cursor_id = state.cursors.primary_id();
+
+        // Replacements are sorted by position; emit them back to front so
+        // each event's offsets are still valid when it is applied
+        let mut events = Vec::new();
+        for replacement in replacements.into_iter().rev() {
+            let range = replacement.range;
+            if !range.is_empty() {
+                events.push(Event::Delete {
+                    range: range.clone(),
+                    deleted_text: state.get_text_range(range.start, range.end),
+                    cursor_id,
+                });
+            }
+            if !replacement.new_text.is_empty() {
+                events.push(Event::Insert {
+                    position: range.start,
+                    text: String::from_utf8_lossy(&replacement.new_text).into_owned(),
+                    cursor_id,
+                });
+            }
+        }
+
+        let batch = Event::Batch {
+            events,
+            description: description.to_string(),
+        };
+        self.apply_rename_batch_to_buffer(buffer_id, batch)?;
+        Ok(true)
+    }
+}
+
+/// Apply the edits of a formatting response to `content`
+///
+/// The edits must not overlap and must start and end on char boundaries of
+/// `content`; a server sending anything else gets the whole set rejected.
+fn apply_text_edits(
+    content: &str,
+    mut edits: Vec<(Range<usize>, String)>,
+) -> Result<String, String> {
+    // The sort is stable, so inserts at the same position keep the order the
+    // server sent them in
+    edits.sort_by_key(|(range, _)| range.start);
+
+    let mut previous_end = 0;
+    for (range, _) in &edits {
+        if range.start < previous_end {
+            return Err("formatting edits overlap".to_string());
+        }
+        if !content.is_char_boundary(range.start) || !content.is_char_boundary(range.end) {
+            return Err(format!(
+                "formatting edit {}..{} is not on character boundaries",
+                range.start, range.end
+            ));
+        }
+        previous_end = range.end;
+    }
+
+    // Apply from the end so earlier offsets stay valid
+    let mut formatted = content.to_string();
+    for (range, new_text) in edits.into_iter().rev() {
+        formatted.replace_range(range, &new_text);
+    }
+    Ok(formatted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(range: Range<usize>, text: &str) -> (Range<usize>, String) {
+        (range, text.to_string())
+    }
+
+    #[test]
+    fn test_apply_text_edits() {
+        let edits = vec![
+            edit(9..9, "!"),
+            edit(0..1, "H"),
+            edit(9..9, "?"),
+            edit(5..6, "_"),
+        ];
+        assert_eq!(
+            apply_text_edits("hello wörld", edits).unwrap(),
+            "Hello_wö!?rld"
+        );
+    }
+
+    #[test]
+    fn test_apply_text_edits_rejects_invalid_sets() {
+        // "ö" spans bytes 7..9
+        let mid_char = vec![edit(0..8, "x")];
+        assert!(apply_text_edits("hello wörld", mid_char).is_err());
+        let overlapping = vec![edit(0..5, "a"), edit(3..9, "b")];
+        assert!(apply_text_edits("hello wörld", overlapping).is_err());
+        let past_end = vec![edit(10..20, "")];
+        assert!(apply_text_edits("hello", past_end).is_err());
+    }
+}
@@ -0,0 +1,90 @@
+//! Reloading of user grammars
+//!
+//! The grammars directory is watched while the editor runs: when a grammar in
+//! it is added, edited or removed, the [`GrammarRegistry`] is loaded again and
+//! the buffers of files are highlighted with the new grammars, without
+//! restarting. The libraries of tree-sitter grammars stay loaded, so a rebuilt
+//! library is only picked up after a restart; their queries are read again.
+
+use super::*;
+use crate::primitives::grammar_registry::GrammarRegistry;
+use crate::primitives::highlight_engine::HighlightEngine;
+use crate::primitives::indent::IndentCalculator;
+
+impl Editor {
+    /// Watch the grammars directory, sending [`AsyncMessage::GrammarsChanged`]
+    /// when anything in it changes
+    ///
+    /// The directory is created if needed, so that the first grammars added
+    /// to it are picked up too.
+    pub(super) fn watch_grammars(
+        dir: &Path,
+        bridge: &AsyncBridge,
+    ) -> Option<notify::RecommendedWatcher> {
+        use notify::{RecursiveMode, Watcher};
+
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            tracing::warn!("Failed to create grammars directory {:?}: {}", dir, e);
+            return None;
+        }
+
+        let sender = bridge.sender();
+        let watcher_result = notify::recommended_watcher(
+            move |res: Result<notify::Event, notify::Error>| match res {
+                Ok(event) => {
+                    if matches!(
+                        event.kind,
+                        notify::EventKind::Modify(_)
+                            | notify::EventKind::Create(_)
+                            | notify::EventKind::Remove(_)
+                    ) {
+                        let _ = sender.send(AsyncMessage::GrammarsChanged);
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Grammar watcher error: {}", e);
+                }
+            },
+        );
+
+        match watcher_result {
+            Ok(mut watcher) => match watcher.watch(dir, RecursiveMode::Recursive) {
+                Ok(()) => Some(watcher),
+                Err(e) => {
+                    tracing::warn!("Failed to watch grammars directory {:?}: {}", dir, e);
+                    None
+                }
+            },
+            Err(e) => {
+                tracing::warn!("Failed to create grammar watcher: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Load the grammars again and re-highlight the buffers of files
+    pub fn reload_grammars(&mut self) {
+        let registry = Arc::new(GrammarRegistry::load(
+            &self.dir_context.grammars_dir(),
+            &self.config.languages,
+        ));
+
+        for state in self.buffers.values_mut() {
+            if let Some(path) = state.buffer.file_path().map(|p| p.to_path_buf()) {
+                state.highlighter = HighlightEngine::for_file(&path, &registry);
+                if let Some(language) = registry.language_for_file(&path) {
+                    state.semantic_highlighter.set_language(&language);
+                }
+                // Drop the indent queries compiled for the previous grammars
+                *state.indent_calculator.get_mut() = IndentCalculator::new();
+            }
+        }
+
+        let user_grammars = registry.user_syntaxes().len() + registry.tree_sitter_grammars().len();
+        self.grammar_registry = registry;
+        self.set_status_message(format!(
+            "Reloaded grammars ({} user grammars)",
+            user_grammars
+        ));
+    }
+}
@@ -0,0 +1,384 @@
+//! Hot exit
+//!
+//! With `editor.hot_exit` enabled, quitting doesn't ask about unsaved changes.
+//! Instead the session saved on quit stashes every modified or unnamed buffer
+//! in the project's hot exit directory, using the recovery service's chunked
+//! format (so only the modified regions of large files are written) plus the
+//! buffer's undo tree. Restoring the session reopens them as modified buffers.
+//!
+//! If a stashed file changed on disk in the meantime, the user is asked to
+//! keep the stashed changes, reload the file from disk, or merge the two with
+//! conflict markers.
+
+use super::*;
+use crate::model::buffer::TextBuffer;
+use crate::model::event::PersistedUndoTree;
+use crate::model::line_diff::merge_with_conflict_markers;
+use crate::services::recovery::{RecoveryChunk, RecoveryResult, RecoveryStorage};
+use crate::session::{SerializedHotExitBuffer, SerializedTabRef, Session};
+use serde::{Deserialize, Serialize};
+
+/// Undo tree of a stashed buffer, stored next to its content
+#[derive(Debug, Serialize, Deserialize)]
+struct StashedUndoHistory {
+    /// The tree, positioned at the stashed content
+    tree: PersistedUndoTree,
+    /// State matching the file on disk, see [`EventLog::to_stashed`]
+    file_state: Option<usize>,
+}
+
+impl Editor {
+    /// Whether quitting stashes unsaved changes instead of asking about them
+    pub(super) fn hot_exit_enabled(&self) -> bool {
+        self.config.editor.hot_exit && self.session_enabled
+    }
+
+    fn hot_exit_dir(&self) -> PathBuf {
+        self.dir_context
+            .hot_exit_dir_for(&self.working_dir, self.session_name.as_deref())
+    }
+
+    /// Stash every buffer with unsaved changes, recording the stashes (and
+    /// the tabs of unnamed buffers) in `session`
+    pub(super) fn stash_hot_exit_buffers(&mut self, session: &mut Session) {
+        let storage = RecoveryStorage::with_dir(self.hot_exit_dir());
+        // Anything stashed on the previous exit was restored with the session
+        if let Err(e) = storage.cleanup_all() {
+            tracing::warn!("Failed to clear old hot exit stashes: {}", e);
+        }
+
+        let buffer_ids: Vec<BufferId> = self
+            .buffers
+            .iter()
+            .filter(|(buffer_id, state)| {
+                let is_virtual = self
+                    .buffer_metadata
+                    .get(*buffer_id)
+                    .is_some_and(|metadata| metadata.is_virtual());
+                let unnamed = state.buffer.file_path().is_none();
+                !is_virtual
+                    && !self.terminal_buffers.contains_key(*buffer_id)
+                    && (state.buffer.is_modified() || (unnamed && !state.buffer.is_empty()))
+            })
+            .map(|(buffer_id, _)| *buffer_id)
+            .collect();
+
+        for buffer_id in buffer_ids {
+            match self.stash_buffer(&storage, buffer_id) {
+                Ok(stash) => {
+                    if stash.file_path.is_none() {
+                        self.add_unnamed_tabs(session, buffer_id, &stash.id);
+                    }
+                    session.hot_exit.push(stash);
+                }
+                Err(e) => tracing::warn!("Failed to stash buffer {:?}: {}", buffer_id, e),
+            }
+        }
+        tracing::debug!("Stashed {} buffers for hot exit", session.hot_exit.len());
+    }
+
+    /// Write a buffer's content and undo tree to the hot exit directory
+    fn stash_buffer(
+        &mut self,
+        storage: &RecoveryStorage,
+        buffer_id: BufferId,
+    ) -> io::Result<SerializedHotExitBuffer> {
+        let state = self
+            .buffers
+            .get_mut(&buffer_id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Buffer not found"))?;
+        let path = state.buffer.file_path().map(|p| p.to_path_buf());
+        let id = storage.get_buffer_id(path.as_deref());
+        let line_count = state.buffer.line_count();
+        let total_bytes = state.buffer.total_bytes();
+
+        if state.buffer.is_large_file() && path.is_some() {
+            // Only the modified regions, applied to the file on restore
+            let chunks = state
+                .buffer
+                .get_recovery_chunks()
+                .into_iter()
+                .map(|(offset, content)| RecoveryChunk::new(offset, 0, content))
+                .collect();
+            let original_size = state.buffer.original_file_size().unwrap_or(0);
+            storage.save_recovery(
+                &id,
+                chunks,
+                path.as_deref(),
+                None,
+                line_count,
+                original_size,
+                total_bytes,
+            )?;
+        } else {
+            let content = state
+                .buffer
+                .get_text_range_mut(0, total_bytes)
+                .map_err(io::Error::other)?;
+            let chunks = vec![RecoveryChunk::new(0, 0, content)];
+            storage.save_recovery(
+                &id,
+                chunks,
+                path.as_deref(),
+                None,
+                line_count,
+                0,
+                total_bytes,
+            )?;
+        }
+
+        if let Some(event_log) = self.event_logs.get(&buffer_id) {
+            let (tree, file_state) =
+                event_log.to_stashed(self.config.editor.persistent_undo_max_entries);
+            let history = StashedUndoHistory { tree, file_state };
+            let json = serde_json::to_string(&history).map_err(io::Error::other)?;
+            std::fs::write(undo_history_path(storage, &id), json)?;
+        }
+
+        Ok(SerializedHotExitBuffer {
+            id,
+            file_path: path,
+        })
+    }
+
+    /// Add tab references to an unnamed buffer to the splits showing it
+    fn add_unnamed_tabs(&self, session: &mut Session, buffer_id: BufferId, id: &str) {
+        for (split_id, view_state) in &self.split_view_states {
+            if !view_state.open_buffers.contains(&buffer_id) {
+                continue;
+            }
+            let Some(split_state) = session.split_states.get_mut(&split_id.0) else {
+                continue;
+            };
+            if self.split_manager.get_buffer_id(*split_id) == Some(buffer_id) {
+                split_state.active_tab_index = Some(split_state.open_tabs.len());
+            }
+            split_state
+                .open_tabs
+                .push(SerializedTabRef::Unnamed(id.to_string()));
+        }
+    }
+
+    /// Restore the buffers stashed by hot exit, adding the files inside the
+    /// working directory to `path_to_buffer`
+    ///
+    /// Returns the restored unnamed buffers by stash id.
+    pub(super) fn restore_hot_exit_buffers(
+        &mut self,
+        stashes: &[SerializedHotExitBuffer],
+        path_to_buffer: &mut HashMap<PathBuf, BufferId>,
+    ) -> HashMap<String, BufferId> {
+        let mut unnamed_buffers = HashMap::new();
+        if stashes.is_empty() {
+            return unnamed_buffers;
+        }
+
+        let mut service = RecoveryService::with_storage_dir(self.hot_exit_dir());
+        service.set_fs(Arc::clone(&self.fs_manager));
+        for stash in stashes {
+            match self.restore_stashed_buffer(&service, stash) {
+                Ok(Some(buffer_id)) => match &stash.file_path {
+                    Some(path) => {
+                        if let Ok(rel_path) = path.strip_prefix(&self.working_dir) {
+                            path_to_buffer.insert(rel_path.to_path_buf(), buffer_id);
+                        }
+                    }
+                    None => {
+                        unnamed_buffers.insert(stash.id.clone(), buffer_id);
+                    }
+                },
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!("Failed to restore stashed buffer {}: {}", stash.id, e);
+                    self.set_status_message(format!("Failed to restore stashed buffer: {}", e));
+                }
+            }
+        }
+
+        // From here on the buffers are covered by crash recovery
+        if let Err(e) = service.discard_all_recovery() {
+            tracing::warn!("Failed to clear hot exit stashes: {}", e);
+        }
+
+        self.prompt_hot_exit_conflict();
+        unnamed_buffers
+    }
+
+    /// Restore one stashed buffer, returning it (or `None` if the stash can
+    /// no longer be applied)
+    fn restore_stashed_buffer(
+        &mut self,
+        service: &RecoveryService,
+        stash: &SerializedHotExitBuffer,
+    ) -> io::Result<Option<BufferId>> {
+        let entry = service
+            .storage()
+            .load_entry(&stash.id)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Stash not found"))?;
+        let changed_on_disk = entry.original_file_modified(service.storage().fs());
+
+        let (content, chunks) = match service.load_recovery(&entry)? {
+            RecoveryResult::Recovered { content, .. } => (Some(content), Vec::new()),
+            RecoveryResult::RecoveredChunks { chunks, .. } => (None, chunks),
+            RecoveryResult::OriginalFileModified { original_path, .. } => {
+                // The modified regions of a large file can't be applied to
+                // a different version of it
+                self.set_status_message(format!(
+                    "Stashed changes to {} dropped: the file changed on disk",
+                    original_path.display()
+                ));
+                return Ok(None);
+            }
+            RecoveryResult::Corrupted { reason, .. } => {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, reason));
+            }
+            RecoveryResult::NotFound { id } => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Stash {} not found", id),
+                ));
+            }
+        };
+
+        let buffer_id = match &stash.file_path {
+            Some(path) => self.open_file(path)?,
+            None => self.new_buffer(),
+        };
+        let Some(state) = self.buffers.get_mut(&buffer_id) else {
+            return Ok(None);
+        };
+        match content {
+            Some(content) => {
+                let total = state.buffer.total_bytes();
+                state.buffer.delete(0..total);
+                state.buffer.insert(0, &String::from_utf8_lossy(&content));
+            }
+            None => {
+                // Apply in reverse order so earlier offsets stay valid
+                for chunk in chunks.into_iter().rev() {
+                    if chunk.original_len > 0 {
+                        state
+                            .buffer
+                            .delete(chunk.offset..chunk.offset + chunk.original_len);
+                    }
+                    state
+                        .buffer
+                        .insert(chunk.offset, &String::from_utf8_lossy(&chunk.content));
+                }
+            }
+        }
+        state.buffer.set_modified(true);
+
+        // No undo state matches a file that changed on disk
+        let event_log = std::fs::read_to_string(undo_history_path(service.storage(), &stash.id))
+            .ok()
+            .and_then(|json| serde_json::from_str::<StashedUndoHistory>(&json).ok())
+            .and_then(|history| {
+                let file_state = history.file_state.filter(|_| !changed_on_disk);
+                EventLog::from_stashed(history.tree, file_state)
+            })
+            .unwrap_or_default();
+        self.event_logs.insert(buffer_id, event_log);
+
+        if let Some(path) = &stash.file_path {
+            self.notify_lsp_file_changed(path);
+            if changed_on_disk {
+                self.hot_exit_conflicts.push(buffer_id);
+            }
+        }
+        tracing::debug!("Restored stashed buffer {} as {:?}", stash.id, buffer_id);
+        Ok(Some(buffer_id))
+    }
+
+    /// Ask how to resolve the next stashed file that changed on disk
+    fn prompt_hot_exit_conflict(&mut self) {
+        let Some(&buffer_id) = self.hot_exit_conflicts.first() else {
+            return;
+        };
+        let name = self.get_buffer_display_name(buffer_id);
+        self.start_prompt(
+            format!(
+                "'{}' changed on disk since exit. (k)eep stashed changes, (r)eload, (m)erge? ",
+                name
+            ),
+            PromptType::ConfirmHotExitConflict { buffer_id },
+        );
+    }
+
+    /// Resolve a stashed file that changed on disk as answered in the prompt,
+    /// then ask about the next one
+    pub(super) fn resolve_hot_exit_conflict(&mut self, buffer_id: BufferId, input: &str) {
+        self.hot_exit_conflicts.retain(|id| *id != buffer_id);
+        if self.buffers.contains_key(&buffer_id) {
+            match input.trim().to_lowercase().chars().next() {
+                Some('r') => {
+                    self.set_active_buffer(buffer_id);
+                    if let Err(e) = self.revert_file() {
+                        self.set_status_message(format!("Failed to reload: {}", e));
+                    }
+                }
+                Some('m') => self.merge_hot_exit_conflict(buffer_id),
+                _ => self.set_status_message("Kept stashed changes".to_string()),
+            }
+        }
+        self.prompt_hot_exit_conflict();
+    }
+
+    /// Merge a stashed buffer with its file on disk, wrapping the regions
+    /// where they differ in conflict markers
+    fn merge_hot_exit_conflict(&mut self, buffer_id: BufferId) {
+        let Some(state) = self.buffers.get(&buffer_id) else {
+            return;
+        };
+        let (Some(path), Some(stashed)) = (
+            state.buffer.file_path().map(|p| p.to_path_buf()),
+            state.buffer.to_string(),
+        ) else {
+            self.set_status_message("Cannot merge this buffer".to_string());
+            return;
+        };
+        // Load the file like the buffer was (decoded, with LF line endings)
+        // so that only real changes are marked as conflicts
+        let on_disk = TextBuffer::load_from_fs(
+            Arc::clone(&self.fs_manager),
+            &path,
+            usize::MAX,
+            Some(state.buffer.encoding()),
+        )
+        .and_then(|buffer| {
+            buffer
+                .to_string()
+                .ok_or_else(|| io::Error::other("file not fully loaded"))
+        });
+        let on_disk = match on_disk {
+            Ok(text) => text,
+            Err(e) => {
+                self.set_status_message(format!("Failed to read {}: {}", path.display(), e));
+                return;
+            }
+        };
+
+        let merged = merge_with_conflict_markers(
+            stashed.as_bytes(),
+            on_disk.as_bytes(),
+            "stashed",
+            "on disk",
+        );
+        let merged = String::from_utf8_lossy(&merged);
+        self.set_active_buffer(buffer_id);
+        match self.apply_text_replacement(buffer_id, &stashed, &merged, "Merge with file on disk") {
+            Ok(true) => self.set_status_message(
+                "Merged with the file on disk; resolve the conflict markers".to_string(),
+            ),
+            Ok(false) => {
+                self.set_status_message("No differences from the file on disk".to_string())
+            }
+            Err(e) => self.set_status_message(format!("Failed to merge: {}", e)),
+        }
+    }
+}
+
+/// Path of the undo tree stashed with `id`
+fn undo_history_path(storage: &RecoveryStorage, id: &str) -> PathBuf {
+    storage.base_dir().join(format!("{}.undo.json", id))
+}
@@ -1,4 +1,5 @@
 use super::normalize_path;
+use super::snippets::CompletionInsert;
 use super::*;
 use crate::services::plugins::hooks::HookArgs;
 impl Editor {
@@ -202,6 +203,18 @@ impl Editor {
                     // Handle built-in mode commands
                     let buffer_id = self.active_buffer();
                     return self.close_buffer(buffer_id);
+                } else if let Some(command) = command_name.strip_prefix("undo-history-") {
+                    // Built-in undo history panel commands
+                    self.handle_undo_history_command(command);
+                    return Ok(());
+                } else if let Some(command) = command_name.strip_prefix("project-search-") {
+                    // Built-in project search panel commands
+                    self.handle_project_search_command(command);
+                    return Ok(());
+                } else if let Some(command) = command_name.strip_prefix("debug-panel-") {
+                    // Built-in debug panel commands
+                    self.handle_debug_panel_command(command);
+                    return Ok(());
                 } else if command_name == "revert-buffer" {
                     // Refresh the buffer (for virtual buffers, this would re-query data)
                     self.set_status_message("Refreshing buffer...".to_string());
@@ -715,10 +728,14 @@ impl Editor {
                         let history_item = match prompt_type {
                             PromptType::Search
                             | PromptType::ReplaceSearch
-                            | PromptType::QueryReplaceSearch => {
+                            | PromptType::QueryReplaceSearch
+                            | PromptType::ProjectSearch
+                            | PromptType::ProjectReplaceSearch => {
                                 self.search_history.navigate_prev(&current_input)
                             }
-                            PromptType::Replace { .. } | PromptType::QueryReplace { .. } => {
+                            PromptType::Replace { .. }
+                            | PromptType::QueryReplace { .. }
+                            | PromptType::ProjectReplace { .. } => {
                                 self.replace_history.navigate_prev(&current_input)
                             }
                             _ => None,
@@ -796,8 +813,14 @@ impl Editor {
                         let history_item = match prompt_type {
                             PromptType::Search
                             | PromptType::ReplaceSearch
-                            | PromptType::QueryReplaceSearch => self.search_history.navigate_next(),
-                            PromptType::Replace { .. } | PromptType::QueryReplace { .. } => {
+                            | PromptType::QueryReplaceSearch
+                            | PromptType::ProjectSearch
+                            | PromptType::ProjectReplaceSearch => {
+                                self.search_history.navigate_next()
+                            }
+                            PromptType::Replace { .. }
+                            | PromptType::QueryReplace { .. }
+                            | PromptType::ProjectReplace { .. } => {
                                 self.replace_history.navigate_next()
                             }
                             _ => None,
@@ -1009,8 +1032,12 @@ impl Editor {
         match action {
             Action::Quit => self.quit(),
             Action::Save => {
-                // Check if buffer has a file path - if not, redirect to SaveAs
-                if self.active_state().buffer.file_path().is_none() {
+                let buffer_id = self.active_buffer();
+                if let Some(&key) = self.macro_edit_buffers.get(&buffer_id) {
+                    // Saving a macro definition updates the macro itself
+                    self.save_macro_buffer(buffer_id, key);
+                } else if self.active_state().buffer.file_path().is_none() {
+                    // No file path - redirect to SaveAs
                     self.start_prompt_with_initial_text(
                         "Save as: ".to_string(),
                         PromptType::SaveFileAs,
@@ -1022,7 +1049,7 @@ impl Editor {
                         "File changed on disk. (o)verwrite, (C)ancel? ".to_string(),
                         PromptType::ConfirmSaveConflict,
                     );
-                } else {
+                } else if !self.request_format_on_save() {
                     self.save()?;
                 }
             }
@@ -1051,6 +1078,27 @@ impl Editor {
                 self.prefill_open_file_prompt();
                 self.init_file_open_state();
             }
+            Action::SaveSessionAs => self.start_save_session_as_prompt(),
+            Action::SwitchSession => self.start_switch_session_prompt(),
+            Action::RenameSession => self.start_rename_session_prompt(),
+            Action::DeleteSession => self.start_delete_session_prompt(),
+            Action::InstallPluginPackage => self.start_install_plugin_package_prompt(),
+            Action::UpdatePluginPackages => self.update_plugin_packages(),
+            Action::RemovePluginPackage => {
+                self.start_plugin_package_prompt(PromptType::RemovePluginPackage)
+            }
+            Action::EnablePluginPackage => {
+                self.start_plugin_package_prompt(PromptType::EnablePluginPackage)
+            }
+            Action::DisablePluginPackage => {
+                self.start_plugin_package_prompt(PromptType::DisablePluginPackage)
+            }
+            Action::PinPluginPackage => {
+                self.start_plugin_package_prompt(PromptType::PinPluginPackage)
+            }
+            Action::UnpinPluginPackage => {
+                self.start_plugin_package_prompt(PromptType::UnpinPluginPackage)
+            }
             Action::SwitchProject => {
                 self.start_prompt("Switch project: ".to_string(), PromptType::SwitchProject);
                 self.init_folder_open_state();
@@ -1147,6 +1195,40 @@ impl Editor {
                 // Update modified status based on event log position
                 self.update_modified_from_event_log();
             }
+            Action::SwitchUndoBranch => {
+                if self.is_editing_disabled() {
+                    self.set_status_message("Editing disabled in this buffer".to_string());
+                    return Ok(());
+                }
+                self.switch_undo_branch();
+            }
+            Action::UndoHistory => {
+                self.open_undo_history();
+            }
+            Action::ProjectSearch => {
+                self.start_project_search_prompt(false);
+            }
+            Action::ProjectReplace => {
+                self.start_project_search_prompt(true);
+            }
+            Action::ExpandSelection => self.expand_selection(),
+            Action::ShrinkSelection => self.shrink_selection(),
+            Action::Fold => self.run_fold_command(folding::FoldCommand::Fold),
+            Action::Unfold => self.unfold_at_cursor(),
+            Action::FoldAll => self.run_fold_command(folding::FoldCommand::FoldAll),
+            Action::UnfoldAll => self.unfold_all(),
+            Action::UnfoldToLevel => {
+                self.start_prompt("Unfold to level: ".to_string(), PromptType::FoldLevel)
+            }
+            Action::InsertSnippet => self.start_insert_snippet_prompt(),
+            Action::ToggleBreakpoint => self.toggle_breakpoint(),
+            Action::DebugStart => self.start_debugging(),
+            Action::DebugContinue => self.debug_continue(),
+            Action::DebugPause => self.debug_pause(),
+            Action::DebugStepOver => self.debug_step_over(),
+            Action::DebugStepInto => self.debug_step_into(),
+            Action::DebugStepOut => self.debug_step_out(),
+            Action::DebugStop => self.debug_stop(),
             Action::ShowHelp => {
                 self.open_help_manual();
             }
@@ -1302,6 +1384,13 @@ impl Editor {
             Action::LspRename => {
                 self.start_rename()?;
             }
+            Action::FormatBuffer => {
+                if self.is_editing_disabled() {
+                    self.set_status_message("Editing disabled in this buffer".to_string());
+                } else {
+                    self.format_buffer();
+                }
+            }
             Action::LspHover => {
                 self.request_hover()?;
             }
@@ -1370,11 +1459,13 @@ impl Editor {
                                                     )
                                                 {
                                                     if let Some(lsp) = self.lsp.as_mut() {
-                                                        if let Some(handle) =
-                                                            lsp.get_or_spawn(&lang_id)
+                                                        for server in lsp.get_or_spawn_all(&lang_id)
                                                         {
-                                                            let _ = handle
-                                                                .did_open(uri, content, lang_id);
+                                                            let _ = server.handle.did_open(
+                                                                uri.clone(),
+                                                                content.clone(),
+                                                                lang_id.clone(),
+                                                            );
                                                         }
                                                     }
                                                 }
@@ -1410,12 +1501,16 @@ impl Editor {
                     let suggestions: Vec<crate::input::commands::Suggestion> = running_servers
                         .iter()
                         .map(|lang| {
-                            let description = if let Some(lsp) = &self.lsp {
-                                lsp.get_config(lang)
-                                    .map(|c| format!("Command: {}", c.command))
-                            } else {
-                                None
-                            };
+                            let description = self.lsp.as_ref().and_then(|lsp| {
+                                let commands: Vec<&str> = lsp
+                                    .get_configs(lang)
+                                    .iter()
+                                    .filter(|c| c.enabled)
+                                    .map(|c| c.command.as_str())
+                                    .collect();
+                                (!commands.is_empty())
+                                    .then(|| format!("Command: {}", commands.join(", ")))
+                            });
                             crate::input::commands::Suggestion {
                                 text: lang.clone(),
                                 description,
@@ -1460,6 +1555,12 @@ impl Editor {
             Action::SelectTheme => {
                 self.start_select_theme_prompt();
             }
+            Action::ReopenWithEncoding => {
+                self.start_encoding_prompt(PromptType::ReopenWithEncoding);
+            }
+            Action::SaveWithEncoding => {
+                self.start_encoding_prompt(PromptType::SaveWithEncoding);
+            }
             Action::SelectKeybindingMap => {
                 self.start_select_keybinding_map_prompt();
             }
@@ -1565,6 +1666,13 @@ impl Editor {
             Action::FileExplorerNewDirectory => self.file_explorer_new_directory(),
             Action::FileExplorerDelete => self.file_explorer_delete(),
             Action::FileExplorerRename => self.file_explorer_rename(),
+            Action::FileExplorerUndo => self.file_explorer_undo(),
+            Action::FileExplorerCopy => self.file_explorer_copy(),
+            Action::FileExplorerCut => self.file_explorer_cut(),
+            Action::FileExplorerPaste => self.file_explorer_paste(),
+            Action::FileExplorerDuplicate => self.file_explorer_duplicate(),
+            Action::FileExplorerExtendSelectionUp => self.file_explorer_extend_selection_up(),
+            Action::FileExplorerExtendSelectionDown => self.file_explorer_extend_selection_down(),
             Action::FileExplorerToggleHidden => self.file_explorer_toggle_hidden(),
             Action::FileExplorerToggleGitignored => self.file_explorer_toggle_gitignored(),
             Action::RemoveSecondaryCursors => {
@@ -1743,12 +1851,21 @@ impl Editor {
             Action::IndentSelection => {
                 self.indent_selection();
             }
+            Action::InsertTab if self.snippet_session_active() => {
+                self.next_snippet_tabstop();
+            }
+            Action::DedentSelection if self.snippet_session_active() => {
+                self.prev_snippet_tabstop();
+            }
             Action::DedentSelection => {
                 self.dedent_selection();
             }
             Action::ToggleComment => {
                 self.toggle_comment();
             }
+            Action::ToggleBlockComment => {
+                self.toggle_block_comment();
+            }
             Action::GoToMatchingBracket => {
                 self.goto_matching_bracket();
             }
@@ -1866,6 +1983,10 @@ impl Editor {
             Action::PlayMacro(key) => {
                 self.play_macro(key);
             }
+            Action::PlayNamedMacro(name) => match self.macros.find_by_name(&name) {
+                Some(key) => self.play_macro(key),
+                None => self.set_status_message(format!("No macro named '{}'", name)),
+            },
             Action::ToggleMacroRecording(key) => {
                 self.toggle_macro_recording(key);
             }
@@ -1879,7 +2000,16 @@ impl Editor {
                 self.start_prompt("Record macro (0-9): ".to_string(), PromptType::RecordMacro);
             }
             Action::PromptPlayMacro => {
-                self.start_prompt("Play macro (0-9): ".to_string(), PromptType::PlayMacro);
+                self.start_prompt(
+                    "Play macro (0-9 or name): ".to_string(),
+                    PromptType::PlayMacro,
+                );
+            }
+            Action::PromptShowMacro => {
+                self.start_prompt(
+                    "Show macro (0-9 or name): ".to_string(),
+                    PromptType::ShowMacro,
+                );
             }
             Action::PlayLastMacro => {
                 if let Some(key) = self.last_macro_register {
@@ -2084,6 +2214,39 @@ impl Editor {
                                 ));
                             }
                         }
+                        PromptType::SaveSessionAs => {
+                            self.save_session_as(&input);
+                        }
+                        PromptType::SwitchSession => {
+                            self.switch_session(&input);
+                        }
+                        PromptType::RenameSession => {
+                            self.rename_session(&input);
+                        }
+                        PromptType::DeleteSession => {
+                            self.delete_session(&input);
+                        }
+                        PromptType::PluginPermission => {
+                            self.answer_plugin_permission(input == "allow");
+                        }
+                        PromptType::InstallPluginPackage => {
+                            self.install_plugin_package(&input);
+                        }
+                        PromptType::RemovePluginPackage => {
+                            self.remove_plugin_package(&input);
+                        }
+                        PromptType::EnablePluginPackage => {
+                            self.set_plugin_package_enabled(&input, true);
+                        }
+                        PromptType::DisablePluginPackage => {
+                            self.set_plugin_package_enabled(&input, false);
+                        }
+                        PromptType::PinPluginPackage => {
+                            self.pin_plugin_package(&input);
+                        }
+                        PromptType::UnpinPluginPackage => {
+                            self.unpin_plugin_package(&input);
+                        }
                         PromptType::SaveFileAs => {
                             // Resolve path: if relative, make it relative to working_dir
                             let input_path = Path::new(&input);
@@ -2102,8 +2265,22 @@ impl Editor {
                                 before_len
                             );
 
+                            // Saving a file under another name renames it as far
+                            // as the language servers are concerned
+                            let renamed_from = self
+                                .active_state()
+                                .buffer
+                                .file_path()
+                                .filter(|old_path| *old_path != full_path)
+                                .map(|old_path| old_path.to_path_buf());
+                            let renames = renamed_from.as_ref().map(|old_path| {
+                                self.lsp_will_rename_files(&[(old_path.clone(), full_path.clone())])
+                            });
+
                             // Save the buffer to the new file
-                            match self.active_state_mut().buffer.save_to_file(&full_path) {
+                            let saved = self.active_state_mut().buffer.save_to_file(&full_path);
+                            let renamed = saved.is_ok();
+                            match saved {
                                 Ok(()) => {
                                     // Debug: log event log state after buffer save
                                     let after_save_idx = self.active_event_log().current_index();
@@ -2119,6 +2296,12 @@ impl Editor {
                                         &self.working_dir,
                                     );
                                     self.buffer_metadata.insert(self.active_buffer(), metadata);
+                                    if let Some(old_path) = &renamed_from {
+                                        self.lsp_reopen_renamed_buffer(
+                                            self.active_buffer(),
+                                            old_path,
+                                        );
+                                    }
 
                                     // Mark the event log position as saved (for undo modified tracking)
                                     self.active_event_log_mut().mark_saved();
@@ -2127,13 +2310,12 @@ impl Editor {
                                         self.active_event_log().current_index(),
                                         self.active_event_log().len()
                                     );
+                                    self.persist_undo_history(self.active_buffer());
 
                                     // Record the file modification time so auto-revert won't trigger
                                     // for our own save. This is critical for preserving undo history.
-                                    if let Ok(metadata) = std::fs::metadata(&full_path) {
-                                        if let Ok(mtime) = metadata.modified() {
-                                            self.file_mod_times.insert(full_path.clone(), mtime);
-                                        }
+                                    if let Some(mtime) = self.file_mtime(&full_path) {
+                                        self.file_mod_times.insert(full_path.clone(), mtime);
                                     }
 
                                     // Notify LSP of the new file if applicable
@@ -2178,6 +2360,9 @@ impl Editor {
                                     self.set_status_message(format!("Error saving file: {}", e));
                                 }
                             }
+                            if let Some(renames) = renames {
+                                self.lsp_did_rename_files(renames, renamed);
+                            }
                         }
                         PromptType::Search => {
                             self.perform_search(&input);
@@ -2199,6 +2384,24 @@ impl Editor {
                                 self.perform_replace(&search, &input);
                             }
                         }
+                        PromptType::ProjectSearch => {
+                            self.run_project_search(input, None);
+                        }
+                        PromptType::ProjectReplaceSearch => {
+                            self.start_prompt(
+                                format!("Replace '{}' in project with: ", input),
+                                PromptType::ProjectReplace { search: input },
+                            );
+                        }
+                        PromptType::ProjectReplace { search } => {
+                            self.run_project_search(search, Some(input));
+                        }
+                        PromptType::ProjectSearchInclude => {
+                            self.set_project_search_globs(&input, true);
+                        }
+                        PromptType::ProjectSearchExclude => {
+                            self.set_project_search_globs(&input, false);
+                        }
                         PromptType::QueryReplaceSearch => {
                             self.perform_search(&input);
                             self.start_prompt(
@@ -2244,6 +2447,7 @@ impl Editor {
                                 self.set_status_message(format!("Invalid line number: {}", input));
                             }
                         },
+                        PromptType::FoldLevel => self.handle_fold_level_input(&input),
                         PromptType::SetBackgroundFile => {
                             if let Err(e) = self.load_ansi_background(&input) {
                                 self.set_status_message(format!(
@@ -2323,17 +2527,20 @@ impl Editor {
                                 self.set_status_message("No register specified".to_string());
                             }
                         }
-                        PromptType::PlayMacro => {
-                            if let Some(c) = input.trim().chars().next() {
-                                if c.is_ascii_digit() {
-                                    self.play_macro(c);
+                        PromptType::PlayMacro | PromptType::ShowMacro => {
+                            if input.trim().is_empty() {
+                                self.set_status_message("No register specified".to_string());
+                            } else if let Some(key) = self.macros.resolve(&input) {
+                                if matches!(prompt_type, PromptType::PlayMacro) {
+                                    self.play_macro(key);
                                 } else {
-                                    self.set_status_message(
-                                        "Macro register must be 0-9".to_string(),
-                                    );
+                                    self.show_macro_in_buffer(key);
                                 }
                             } else {
-                                self.set_status_message("No register specified".to_string());
+                                self.set_status_message(format!(
+                                    "No macro named '{}'",
+                                    input.trim()
+                                ));
                             }
                         }
                         PromptType::SetBookmark => {
@@ -2386,8 +2593,10 @@ impl Editor {
                             let input_lower = input.trim().to_lowercase();
                             if input_lower == "o" || input_lower == "overwrite" {
                                 // Force save despite conflict
-                                if let Err(e) = self.save() {
-                                    self.set_status_message(format!("Failed to save: {}", e));
+                                if !self.request_format_on_save() {
+                                    if let Err(e) = self.save() {
+                                        self.set_status_message(format!("Failed to save: {}", e));
+                                    }
                                 }
                             } else {
                                 self.set_status_message("Save cancelled".to_string());
@@ -2466,6 +2675,9 @@ impl Editor {
                                 self.set_status_message("Quit cancelled".to_string());
                             }
                         }
+                        PromptType::ConfirmHotExitConflict { buffer_id } => {
+                            self.resolve_hot_exit_conflict(buffer_id, &input);
+                        }
                         PromptType::LspRename {
                             original_text,
                             start_pos,
@@ -2487,6 +2699,17 @@ impl Editor {
                             // Perform file explorer rename with the new name from the prompt
                             self.perform_file_explorer_rename(original_path, original_name, input);
                         }
+                        PromptType::ConfirmFileExplorerDelete { paths } => {
+                            let input_lower = input.trim().to_lowercase();
+                            if input_lower == "y" || input_lower == "yes" {
+                                self.perform_file_explorer_delete(paths);
+                            } else {
+                                self.set_status_message("Delete cancelled".to_string());
+                            }
+                        }
+                        PromptType::FileExplorerPasteConflict => {
+                            self.resolve_paste_conflict(&input);
+                        }
                         PromptType::StopLspServer => {
                             // Stop the selected LSP server
                             let language = input.trim();
@@ -2496,7 +2719,9 @@ impl Editor {
                                         // Update config to disable auto-start for this language
                                         if let Some(lsp_config) = self.config.lsp.get_mut(language)
                                         {
-                                            lsp_config.auto_start = false;
+                                            for server in lsp_config.servers_mut() {
+                                                server.auto_start = false;
+                                            }
                                             if let Err(e) = self.save_config() {
                                                 tracing::warn!(
                                                     "Failed to save config after disabling LSP auto-start: {}",
@@ -2529,6 +2754,15 @@ impl Editor {
                         PromptType::SelectTheme => {
                             self.apply_theme(input.trim());
                         }
+                        PromptType::InsertSnippet => {
+                            self.handle_insert_snippet_input(&input);
+                        }
+                        PromptType::ReopenWithEncoding => {
+                            self.reopen_with_encoding(input.trim());
+                        }
+                        PromptType::SaveWithEncoding => {
+                            self.save_with_encoding(input.trim());
+                        }
                         PromptType::SelectKeybindingMap => {
                             self.apply_keybinding_map(input.trim());
                         }
@@ -2577,14 +2811,16 @@ impl Editor {
                 }
 
                 // If it's a completion popup, insert the selected item
-                let completion_text = if let Some(popup) = self.active_state().popups.top() {
+                let completion_insert = if let Some(popup) = self.active_state().popups.top() {
                     if let Some(title) = &popup.title {
                         if title == "Completion" {
-                            if let Some(item) = popup.selected_item() {
-                                item.data.clone()
-                            } else {
-                                None
-                            }
+                            let index = popup.selected_index().unwrap_or(0);
+                            self.completion_inserts.get(index).cloned().or_else(|| {
+                                popup
+                                    .selected_item()
+                                    .and_then(|item| item.data.clone())
+                                    .map(CompletionInsert::Text)
+                            })
                         } else {
                             None
                         }
@@ -2595,8 +2831,21 @@ impl Editor {
                     None
                 };
 
+                // Snippets replace the typed word and select their first placeholder
+                if let Some(CompletionInsert::Snippet(body)) = &completion_insert {
+                    use crate::primitives::word_navigation::find_completion_word_start;
+
+                    let state = self.active_state();
+                    let cursor_pos = state.cursors.primary().position;
+                    let word_start = find_completion_word_start(&state.buffer, cursor_pos);
+                    let body = body.clone();
+                    self.hide_popup();
+                    self.insert_snippet(&body, word_start..cursor_pos);
+                    return Ok(());
+                }
+
                 // Now perform the completion if we have text
-                if let Some(text) = completion_text {
+                if let Some(CompletionInsert::Text(text)) = completion_insert {
                     use crate::primitives::word_navigation::find_completion_word_start;
 
                     let (cursor_id, cursor_pos, word_start) = {
@@ -2741,6 +2990,7 @@ impl Editor {
                         | Action::IndentSelection
                         | Action::DedentSelection
                         | Action::ToggleComment
+                        | Action::ToggleBlockComment
                 );
 
                 if is_editing_action && self.is_editing_disabled() {
@@ -2876,7 +3126,7 @@ impl Editor {
                     self.previous_click_time = Some(std::time::Instant::now());
                     self.previous_click_position = Some((col, row));
                 }
-                self.handle_mouse_click(col, row)?;
+                self.handle_mouse_click(col, row, mouse_event.modifiers)?;
                 needs_render = true;
             }
             MouseEventKind::Drag(MouseButton::Left) => {
@@ -2887,6 +3137,13 @@ impl Editor {
                 // Check if we were dragging a separator to trigger terminal resize
                 let was_dragging_separator = self.mouse_state.dragging_separator.is_some();
 
+                // Drop dragged file explorer items into the directory under the mouse
+                let dragged_items = self.mouse_state.dragging_explorer_items.take();
+                let drop_target = self.mouse_state.explorer_drop_target.take();
+                if let (Some(items), Some(target)) = (dragged_items, drop_target) {
+                    self.file_explorer_move_to(items, target);
+                }
+
                 // Stop dragging and clear drag state
                 self.mouse_state.dragging_scrollbar = None;
                 self.mouse_state.drag_start_row = None;
@@ -3438,7 +3695,12 @@ impl Editor {
         Ok(())
     }
     /// Handle mouse click (down event)
-    pub(super) fn handle_mouse_click(&mut self, col: u16, row: u16) -> std::io::Result<()> {
+    pub(super) fn handle_mouse_click(
+        &mut self,
+        col: u16,
+        row: u16,
+        modifiers: crossterm::event::KeyModifiers,
+    ) -> std::io::Result<()> {
         // Check if click is on suggestions (command palette, autocomplete)
         if let Some((inner_rect, start_idx, _visible_count, total_count)) =
             &self.cached_layout.suggestions_area.clone()
@@ -3556,7 +3818,7 @@ impl Editor {
                 && row >= explorer_area.y
                 && row < explorer_area.y + explorer_area.height
             {
-                self.handle_file_explorer_click(col, row, explorer_area)?;
+                self.handle_file_explorer_click(col, row, explorer_area, modifiers)?;
                 return Ok(());
             }
         }
@@ -3745,6 +4007,26 @@ impl Editor {
 
     /// Handle mouse drag event
     pub(super) fn handle_mouse_drag(&mut self, col: u16, row: u16) -> std::io::Result<()> {
+        // If dragging file explorer items, track the directory they would be dropped into
+        if let Some(items) = self.mouse_state.dragging_explorer_items.clone() {
+            let target = self.file_explorer_drop_target(col, row, &items);
+            match &target {
+                Some(dir) => {
+                    let name = dir.file_name().unwrap_or(dir.as_os_str());
+                    self.set_status_message(format!(
+                        "Drop to move into {}",
+                        name.to_string_lossy()
+                    ));
+                }
+                None if self.mouse_state.explorer_drop_target.is_some() => {
+                    self.status_message = None;
+                }
+                None => {}
+            }
+            self.mouse_state.explorer_drop_target = target;
+            return Ok(());
+        }
+
         // If dragging scrollbar, update scroll position
         if let Some(dragging_split_id) = self.mouse_state.dragging_scrollbar {
             // Find the buffer and scrollbar rect for this split
@@ -4241,13 +4523,12 @@ impl Editor {
             .map(|vt| vt.tokens.clone());
 
         // Get mutable references to both buffer and view state
-        let buffer = self
-            .buffers
-            .get_mut(&self.active_buffer())
-            .map(|s| &mut s.buffer);
+        let buffer_id = self.active_buffer();
+        let buffer = self.buffers.get_mut(&buffer_id).map(|s| &mut s.buffer);
         let view_state = self.split_view_states.get_mut(&active_split);
 
         if let (Some(buffer), Some(view_state)) = (buffer, view_state) {
+            view_state.sync_hidden_ranges(buffer_id);
             let top_byte_before = view_state.viewport.top_byte;
             if let Some(tokens) = view_transform_tokens {
                 // Use view-aware scrolling with the transform's tokens
@@ -4832,6 +5113,7 @@ impl Editor {
         col: u16,
         row: u16,
         explorer_area: ratatui::layout::Rect,
+        modifiers: crossterm::event::KeyModifiers,
     ) -> std::io::Result<()> {
         // Check if click is on the title bar (first row)
         if row == explorer_area.y {
@@ -4847,33 +5129,50 @@ impl Editor {
         // Focus file explorer
         self.key_context = crate::input::keybindings::KeyContext::FileExplorer;
 
-        // Calculate which item was clicked (accounting for border and title)
-        // The file explorer has a 1-line border at top and bottom
-        let relative_row = row.saturating_sub(explorer_area.y + 1); // +1 for top border
+        let Some(node_id) = self.file_explorer_node_at(row, explorer_area) else {
+            return Ok(());
+        };
 
         if let Some(ref mut explorer) = self.file_explorer {
-            let display_nodes = explorer.get_display_nodes();
-            let scroll_offset = explorer.get_scroll_offset();
-            let clicked_index = (relative_row as usize) + scroll_offset;
-
-            if clicked_index < display_nodes.len() {
-                let (node_id, _indent) = display_nodes[clicked_index];
-
-                // Select this node
-                explorer.set_selected(Some(node_id));
-
-                // Check if it's a file or directory
-                let node = explorer.tree().get_node(node_id);
-                if let Some(node) = node {
-                    if node.is_dir() {
-                        // Toggle expand/collapse using the existing method
-                        self.file_explorer_toggle_expand();
-                    } else if node.is_file() {
-                        // Open the file using the existing method
-                        self.file_explorer_open_file()?;
-                        // Switch focus back to editor after opening file
-                        self.key_context = crate::input::keybindings::KeyContext::Normal;
-                    }
+            // Ctrl+click adds to or removes from the selection, Shift+click
+            // selects a range
+            if modifiers.contains(crossterm::event::KeyModifiers::CONTROL) {
+                explorer.toggle_marked(node_id);
+                return Ok(());
+            }
+            if modifiers.contains(crossterm::event::KeyModifiers::SHIFT) {
+                explorer.select_range_to(node_id);
+                return Ok(());
+            }
+
+            // Clicking outside the selection selects just this node
+            if !explorer.is_marked(node_id) {
+                explorer.clear_marks();
+            }
+            explorer.set_selected(Some(node_id));
+
+            // Dragging from here moves the selected items
+            let root_id = explorer.tree().root_id();
+            let dragged: Vec<std::path::PathBuf> = explorer
+                .selected_nodes()
+                .into_iter()
+                .filter(|&id| id != root_id)
+                .filter_map(|id| explorer.tree().get_node(id))
+                .map(|node| node.entry.path.clone())
+                .collect();
+            self.mouse_state.dragging_explorer_items = (!dragged.is_empty()).then_some(dragged);
+
+            // Check if it's a file or directory
+            let node = explorer.tree().get_node(node_id);
+            if let Some(node) = node {
+                if node.is_dir() {
+                    // Toggle expand/collapse using the existing method
+                    self.file_explorer_toggle_expand();
+                } else if node.is_file() {
+                    // Open the file using the existing method
+                    self.file_explorer_open_file()?;
+                    // Switch focus back to editor after opening file
+                    self.key_context = crate::input::keybindings::KeyContext::Normal;
                 }
             }
         }
@@ -4881,6 +5180,54 @@ impl Editor {
         Ok(())
     }
 
+    /// The file explorer node shown at `row`
+    fn file_explorer_node_at(
+        &self,
+        row: u16,
+        explorer_area: ratatui::layout::Rect,
+    ) -> Option<crate::view::file_tree::NodeId> {
+        let explorer = self.file_explorer.as_ref()?;
+        // The file explorer has a 1-line border at top and bottom
+        let relative_row = row.saturating_sub(explorer_area.y + 1); // +1 for top border
+        let index = relative_row as usize + explorer.get_scroll_offset();
+        explorer
+            .get_display_nodes()
+            .get(index)
+            .map(|&(node_id, _indent)| node_id)
+    }
+
+    /// Directory that dragged file explorer items would be dropped into at
+    /// (`col`, `row`): the directory under the mouse, or the directory of the
+    /// file under it. None when the items would not move.
+    fn file_explorer_drop_target(
+        &self,
+        col: u16,
+        row: u16,
+        items: &[std::path::PathBuf],
+    ) -> Option<std::path::PathBuf> {
+        let area = self.cached_layout.file_explorer_area?;
+        if col < area.x
+            || col >= area.x + area.width
+            || row <= area.y
+            || row >= area.y + area.height
+        {
+            return None;
+        }
+        let node_id = self.file_explorer_node_at(row, area)?;
+        let node = self.file_explorer.as_ref()?.tree().get_node(node_id)?;
+        let target = if node.is_dir() {
+            node.entry.path.clone()
+        } else {
+            node.entry.path.parent()?.to_path_buf()
+        };
+
+        let moves = items
+            .iter()
+            .any(|item| item.parent() != Some(target.as_path()));
+        let into_itself = items.iter().any(|item| target.starts_with(item));
+        (moves && !into_itself).then_some(target)
+    }
+
     /// Compute hover target for menu dropdown chain (main dropdown and submenus)
     fn compute_menu_dropdown_hover(
         &self,
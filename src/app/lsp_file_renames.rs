@@ -0,0 +1,156 @@
+//! Keeping language servers in step with renamed files
+//!
+//! Before files are renamed or moved from the file explorer, or a buffer is
+//! saved under another name, the language servers registered for them are
+//! sent `workspace/willRenameFiles`. The `WorkspaceEdit` they answer with
+//! (updating module declarations or imports, say) is applied when it comes
+//! back, to the files where they are by then. Once the files are renamed,
+//! buffers open on them are closed in their servers and opened again under
+//! their new URIs, and the servers are sent `workspace/didRenameFiles`.
+
+use super::*;
+use crate::services::lsp::manager::RenamedFile;
+
+/// Renames the language servers were told are about to happen
+pub(super) struct LspFileRenames {
+    renames: Vec<RenamedFile>,
+    /// The willRenameFiles requests sent
+    request_ids: Vec<u64>,
+}
+
+impl Editor {
+    /// Send `workspace/willRenameFiles` for files about to be renamed (old
+    /// path, new path), to the servers registered for them
+    ///
+    /// Pass the result to [`Self::lsp_did_rename_files`] once the files are
+    /// renamed, or were not.
+    pub(super) fn lsp_will_rename_files(&mut self, moves: &[(PathBuf, PathBuf)]) -> LspFileRenames {
+        let renames: Vec<RenamedFile> = moves
+            .iter()
+            .map(|(from, to)| RenamedFile {
+                old_path: from.clone(),
+                new_path: to.clone(),
+                is_dir: self
+                    .fs_manager
+                    .block_on(self.fs_manager.is_dir(from))
+                    .unwrap_or(false),
+            })
+            .collect();
+
+        let mut request_ids = Vec::new();
+        if let Some(lsp) = &self.lsp {
+            for server in lsp.all_servers() {
+                let files = server.will_rename_files(&renames);
+                if files.is_empty() {
+                    continue;
+                }
+                let request_id = self.next_lsp_request_id;
+                self.next_lsp_request_id += 1;
+                match server.handle.will_rename_files(request_id, files) {
+                    Ok(()) => request_ids.push(request_id),
+                    Err(e) => {
+                        tracing::warn!(
+                            "Failed to send willRenameFiles to LSP {}: {}",
+                            server.name,
+                            e
+                        )
+                    }
+                }
+            }
+        }
+        LspFileRenames {
+            renames,
+            request_ids,
+        }
+    }
+
+    /// Finish renames started with [`Self::lsp_will_rename_files`]: when the
+    /// files were `renamed`, send `workspace/didRenameFiles` and keep the
+    /// willRenameFiles requests to apply their edits; otherwise drop them
+    pub(super) fn lsp_did_rename_files(&mut self, renames: LspFileRenames, renamed: bool) {
+        if !renamed {
+            // Edits answering requests no longer pending are not applied
+            return;
+        }
+
+        let moves: Vec<(PathBuf, PathBuf)> = renames
+            .renames
+            .iter()
+            .map(|rename| (rename.old_path.clone(), rename.new_path.clone()))
+            .collect();
+        for request_id in renames.request_ids {
+            self.pending_file_renames.insert(request_id, moves.clone());
+        }
+
+        if let Some(lsp) = &self.lsp {
+            for server in lsp.all_servers() {
+                let files = server.did_rename_files(&renames.renames);
+                if files.is_empty() {
+                    continue;
+                }
+                if let Err(e) = server.handle.did_rename_files(files) {
+                    tracing::warn!(
+                        "Failed to send didRenameFiles to LSP {}: {}",
+                        server.name,
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    /// Re-point a buffer whose file was renamed from `old_path` in the
+    /// language servers: close the document under its old URI and open the
+    /// buffer under its new one
+    pub(super) fn lsp_reopen_renamed_buffer(&mut self, buffer_id: BufferId, old_path: &Path) {
+        let old_uri = url::Url::from_file_path(old_path)
+            .ok()
+            .and_then(|url| url.as_str().parse::<lsp_types::Uri>().ok());
+        let old_language = detect_language(old_path, &self.config.languages);
+        if let (Some(lsp), Some(uri), Some(language)) = (&self.lsp, old_uri, old_language) {
+            for server in lsp.servers(&language) {
+                if let Err(e) = server.handle.did_close(uri.clone()) {
+                    tracing::warn!("Failed to send didClose to LSP {}: {}", server.name, e);
+                }
+            }
+        }
+
+        let Some(mut metadata) = self.buffer_metadata.remove(&buffer_id) else {
+            return;
+        };
+        if metadata.lsp_enabled {
+            if let Some(path) = metadata.file_path().cloned() {
+                self.notify_lsp_file_opened(&path, buffer_id, &mut metadata);
+            }
+        }
+        self.buffer_metadata.insert(buffer_id, metadata);
+    }
+
+    /// Handle a `workspace/willRenameFiles` response: apply the edits to the
+    /// renamed files
+    pub fn handle_will_rename_files_response(
+        &mut self,
+        request_id: u64,
+        result: Result<Option<lsp_types::WorkspaceEdit>, String>,
+    ) -> io::Result<()> {
+        let Some(moves) = self.pending_file_renames.remove(&request_id) else {
+            return Ok(());
+        };
+        match result {
+            Ok(Some(workspace_edit)) => {
+                let total_changes = self.apply_workspace_edit(workspace_edit, &moves)?;
+                if total_changes > 0 {
+                    self.set_status_message(format!(
+                        "Updated {} references to renamed files",
+                        total_changes
+                    ));
+                }
+            }
+            Ok(None) => {}
+            Err(error) => {
+                tracing::warn!("willRenameFiles failed: {}", error);
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,14 +1,30 @@
 mod async_messages;
+mod comments;
+mod debugger;
+mod encoding;
 mod file_explorer;
 pub mod file_open;
 mod file_open_input;
+mod file_operations;
+mod folding;
+mod formatting;
+mod grammars;
 mod help;
+mod hot_exit;
 mod input;
+mod lsp_file_renames;
+mod named_sessions;
 mod plugin_commands;
+mod plugin_packages;
+mod plugin_permissions;
+mod project_search;
 mod render;
+mod selection_range;
 pub mod session;
+mod snippets;
 mod terminal;
 pub mod types;
+mod undo_history;
 
 use std::path::Component;
 
@@ -44,9 +60,12 @@ pub(crate) fn normalize_path(path: &std::path::Path) -> std::path::PathBuf {
     }
 }
 
+use self::file_operations::{FileClipboard, FileOperation, PendingPaste};
 use self::types::{
-    Bookmark, CachedLayout, EventLineInfo, InteractiveReplaceState, LspMessageEntry,
-    LspProgressInfo, MacroRecordingState, MouseState, SearchState, DEFAULT_BACKGROUND_FILE,
+    Bookmark, CachedLayout, DebugPanel, DebugSession, DebugState, EventLineInfo,
+    InteractiveReplaceState, LspMessageEntry, LspProgressInfo, MacroRecordingState, MouseState,
+    PluginPermissionRequest, ProjectSearchPanel, SearchState, UndoHistoryPanel,
+    DEFAULT_BACKGROUND_FILE,
 };
 use crate::config::{Config, DirectoryContext};
 use crate::input::actions::action_to_events as convert_action_to_events;
@@ -54,18 +73,26 @@ use crate::input::buffer_mode::ModeRegistry;
 use crate::input::command_registry::CommandRegistry;
 use crate::input::commands::Suggestion;
 use crate::input::keybindings::{Action, KeyContext, KeybindingResolver};
+use crate::input::macros::{Macro, MacroStore};
 use crate::input::multi_cursor::{
     add_cursor_above, add_cursor_at_next_match, add_cursor_below, AddCursorResult,
 };
 use crate::input::position_history::PositionHistory;
+use crate::input::snippets::SnippetStore;
+use crate::model::encoding::TextEncoding;
 use crate::model::event::{CursorId, Event, EventLog, SplitDirection, SplitId};
 use crate::services::async_bridge::{AsyncBridge, AsyncMessage};
 use crate::services::fs::{FsBackend, FsManager, LocalFsBackend};
 use crate::services::lsp::client::LspServerConfig;
-use crate::services::lsp::manager::{detect_language, LspManager, LspSpawnResult};
+use crate::services::lsp::manager::{detect_language, LspFeature, LspManager, LspSpawnResult};
 use crate::services::plugins::api::{BufferSavedDiff, PluginCommand};
+use crate::services::plugins::packages::PluginPackages;
+use crate::services::plugins::permissions::PermissionKind;
 use crate::services::plugins::PluginManager;
 use crate::services::recovery::{RecoveryConfig, RecoveryService};
+use crate::services::remote::{RemoteConnection, RemoteFsBackend};
+use crate::services::trash::Trash;
+use crate::services::undo_store::{UndoHistoryStore, UndoHistoryWriter};
 use crate::state::EditorState;
 use crate::view::file_tree::{FileTree, FileTreeView};
 use crate::view::prompt::{Prompt, PromptType};
@@ -98,6 +125,30 @@ fn uri_to_path(uri: &lsp_types::Uri) -> Result<PathBuf, String> {
         .map_err(|_| "URI is not a file path".to_string())
 }
 
+/// The text edits of an event as `(position, old_len, new_len)`, in order
+fn edit_adjustments(event: &Event) -> Vec<(usize, usize, usize)> {
+    match event {
+        Event::Insert { position, text, .. } => {
+            vec![(*position, 0, text.len())]
+        }
+        Event::Delete { range, .. } => {
+            vec![(range.start, range.len(), 0)]
+        }
+        Event::Batch { events, .. } => {
+            // Collect all edits from the batch
+            events
+                .iter()
+                .filter_map(|e| match e {
+                    Event::Insert { position, text, .. } => Some((*position, 0, text.len())),
+                    Event::Delete { range, .. } => Some((range.start, range.len(), 0)),
+                    _ => None,
+                })
+                .collect()
+        }
+        _ => vec![],
+    }
+}
+
 /// The main editor struct - manages multiple buffers, clipboard, and rendering
 pub struct Editor {
     /// All open buffers
@@ -146,6 +197,11 @@ pub struct Editor {
     /// This is used by Open Folder to do a clean context switch
     restart_with_dir: Option<PathBuf>,
 
+    /// If set, the restart loads this session (None for the default session)
+    /// instead of the working directory's default one
+    /// This is used by Switch Session to swap the whole layout
+    restart_with_session: Option<Option<String>>,
+
     /// Status message (shown in status bar)
     status_message: Option<String>,
 
@@ -199,6 +255,20 @@ pub struct Editor {
     /// This is the runtime value that can be modified by dragging the border
     file_explorer_width_percent: f32,
 
+    /// Trash that files deleted from the file explorer are moved to
+    /// (None for remote projects, whose deletes are permanent)
+    file_trash: Option<Trash>,
+
+    /// File operations done from the file explorer, most recent last, which
+    /// can be undone
+    file_operations: Vec<FileOperation>,
+
+    /// Files copied or cut in the file explorer, to be pasted
+    file_clipboard: Option<FileClipboard>,
+
+    /// Paste waiting for the user to resolve a name conflict
+    pending_paste: Option<PendingPaste>,
+
     /// Whether mouse capture is enabled
     mouse_enabled: bool,
 
@@ -234,6 +304,13 @@ pub struct Editor {
     /// Pending LSP go-to-definition request ID (if any)
     pending_goto_definition_request: Option<u64>,
 
+    /// Formatting requests waiting for their result (at most one per buffer)
+    pending_format_requests: Vec<formatting::PendingFormatRequest>,
+
+    /// Renames (old path, new path) done while their LSP willRenameFiles
+    /// requests are pending, by request ID
+    pending_file_renames: HashMap<u64, Vec<(PathBuf, PathBuf)>>,
+
     /// Pending LSP hover request ID (if any)
     pending_hover_request: Option<u64>,
 
@@ -252,6 +329,15 @@ pub struct Editor {
     /// Pending LSP inlay hints request ID (if any)
     pending_inlay_hints_request: Option<u64>,
 
+    /// Pending LSP folding range request ID and the fold command waiting for it
+    pending_folding_range_request: Option<(u64, folding::FoldCommand)>,
+
+    /// Pending LSP selection range request ID and the cursors it was sent for
+    pending_selection_range_request: Option<(u64, Vec<CursorId>)>,
+
+    /// What each item of the open completion popup inserts
+    completion_inserts: Vec<snippets::CompletionInsert>,
+
     /// Hover symbol range (byte offsets) - for highlighting the symbol under hover
     /// Format: (start_byte_offset, end_byte_offset)
     hover_symbol_range: Option<(usize, usize)>,
@@ -266,6 +352,9 @@ pub struct Editor {
     /// Search state (if search is active)
     search_state: Option<SearchState>,
 
+    /// Background search over a partially loaded (large file) buffer, if running
+    streaming_search: Option<crate::services::streaming_search::StreamingSearch>,
+
     /// Search highlight namespace (for efficient bulk removal)
     search_namespace: crate::view::overlay::OverlayNamespace,
 
@@ -311,9 +400,9 @@ pub struct Editor {
     /// LSP progress tracking (token -> progress info)
     lsp_progress: std::collections::HashMap<String, LspProgressInfo>,
 
-    /// LSP server statuses (language -> status)
+    /// LSP server statuses ((language, server) -> status)
     lsp_server_statuses:
-        std::collections::HashMap<String, crate::services::async_bridge::LspServerStatus>,
+        std::collections::HashMap<(String, String), crate::services::async_bridge::LspServerStatus>,
 
     /// LSP window messages (recent messages from window/showMessage)
     lsp_window_messages: Vec<LspMessageEntry>,
@@ -321,14 +410,17 @@ pub struct Editor {
     /// LSP log messages (recent messages from window/logMessage)
     lsp_log_messages: Vec<LspMessageEntry>,
 
-    /// Diagnostic result IDs per URI (for incremental pull diagnostics)
-    /// Maps URI string to last result_id received from server
-    diagnostic_result_ids: HashMap<String, String>,
+    /// Diagnostic result IDs per URI and server (for incremental pull diagnostics)
+    /// Maps (URI string, server name) to last result_id received from the server
+    diagnostic_result_ids: HashMap<(String, String), String>,
 
     /// Stored LSP diagnostics per URI
-    /// Maps file URI string to Vec of diagnostics for that file
+    /// Maps file URI string to Vec of diagnostics for that file, merged from all servers
     stored_diagnostics: HashMap<String, Vec<lsp_types::Diagnostic>>,
 
+    /// LSP diagnostics per URI by the server that reported them
+    diagnostics_by_server: crate::services::lsp::diagnostics::DiagnosticsByServer,
+
     /// Event broadcaster for control events (observable by external systems)
     event_broadcaster: crate::model::control_event::EventBroadcaster,
 
@@ -342,12 +434,41 @@ pub struct Editor {
     /// Whether to confirm each replacement (interactive/query-replace mode)
     search_confirm_each: bool,
 
-    /// Macro storage (key -> list of recorded actions)
-    macros: HashMap<char, Vec<Action>>,
+    /// Recorded macros, persisted in the data directory
+    macros: MacroStore,
+
+    /// Buffers showing an editable macro definition (buffer -> register);
+    /// saving one updates the macro
+    macro_edit_buffers: HashMap<BufferId, char>,
 
     /// Macro recording state (Some(key) if recording, None otherwise)
     macro_recording: Option<MacroRecordingState>,
 
+    /// User snippets, loaded from the config directory
+    snippets: SnippetStore,
+
+    /// Tabstops of the snippet being filled in, if any
+    snippet_session: Option<snippets::SnippetSession>,
+
+    /// The undo history panel, if open
+    undo_history_panel: Option<UndoHistoryPanel>,
+
+    /// The project search panel, if open
+    project_search_panel: Option<ProjectSearchPanel>,
+
+    /// Globs limiting which files project search looks at
+    project_search_include: Vec<String>,
+    project_search_exclude: Vec<String>,
+
+    /// The current (or last) debug session
+    debug_session: Option<DebugSession>,
+
+    /// The debug panel, if open
+    debug_panel: Option<DebugPanel>,
+
+    /// Id of the most recently started debug session
+    next_debug_session_id: u64,
+
     /// Last recorded macro register (for F12 to replay)
     last_macro_register: Option<char>,
 
@@ -380,6 +501,10 @@ pub struct Editor {
     /// File watcher for auto-revert functionality
     file_watcher: Option<notify::RecommendedWatcher>,
 
+    /// Watcher of the grammars directory, reloading grammars when they change
+    /// (only kept alive, never read)
+    _grammar_watcher: Option<notify::RecommendedWatcher>,
+
     /// Directories currently being watched (to avoid duplicate watches)
     /// We watch directories instead of files to handle atomic saves (temp+rename)
     watched_dirs: HashSet<PathBuf>,
@@ -401,6 +526,32 @@ pub struct Editor {
     /// Recovery service for auto-save and crash recovery
     recovery_service: RecoveryService,
 
+    /// Store for undo histories kept across restarts (None if disabled)
+    undo_store: Option<UndoHistoryStore>,
+
+    /// Writes undo histories to the store in the background
+    undo_writer: Option<UndoHistoryWriter>,
+
+    /// Whether the session is saved on quit, which hot exit relies on
+    session_enabled: bool,
+
+    /// Name of the active named session (None for the working directory's
+    /// default session)
+    session_name: Option<String>,
+
+    /// Stashed buffers whose file changed on disk since hot exit, awaiting
+    /// the user's choice of how to resolve them
+    hot_exit_conflicts: Vec<BufferId>,
+
+    /// Permission requests of sandboxed plugins waiting to be shown
+    pending_plugin_permissions: std::collections::VecDeque<PluginPermissionRequest>,
+
+    /// Permission request shown in the prompt
+    active_plugin_permission: Option<PluginPermissionRequest>,
+
+    /// Capabilities the user denied to plugins during this session
+    denied_plugin_permissions: HashSet<(String, PermissionKind)>,
+
     /// Last auto-save time for rate limiting
     last_auto_save: std::time::Instant,
 
@@ -504,6 +655,37 @@ impl Editor {
         )
     }
 
+    /// Create an editor for a project on a remote host
+    ///
+    /// Files are accessed, and LSP servers and terminals run, on the remote
+    /// end of `remote`. `working_dir` is a path on the remote host.
+    pub fn with_remote(
+        config: Config,
+        width: u16,
+        height: u16,
+        working_dir: PathBuf,
+        remote: Arc<RemoteConnection>,
+        dir_context: DirectoryContext,
+        plugins_enabled: bool,
+    ) -> io::Result<Self> {
+        let fs_backend = Arc::new(RemoteFsBackend::new(Arc::clone(&remote)));
+        let mut editor = Self::with_options(
+            config,
+            width,
+            height,
+            Some(working_dir),
+            Some(fs_backend),
+            plugins_enabled,
+            dir_context,
+        )?;
+        if let Some(lsp) = editor.lsp.as_mut() {
+            lsp.set_remote(Arc::clone(&remote));
+        }
+        editor.terminal_manager.set_remote(remote);
+        editor.file_trash = None;
+        Ok(editor)
+    }
+
     /// Create a new editor with custom options
     /// This is primarily used for testing with slow or mock backends
     /// to verify editor behavior under various I/O conditions
@@ -522,16 +704,38 @@ impl Editor {
         let working_dir = working_dir
             .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
 
+        // Create Tokio runtime for async I/O (LSP, file watching, git, etc.)
+        let tokio_runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2) // Small pool for I/O tasks
+            .thread_name("editor-async")
+            .enable_all()
+            .build()
+            .ok();
+
+        // Initialize filesystem manager for buffers and the file explorer
+        // Use provided backend or create default LocalFsBackend
+        let fs_backend = fs_backend.unwrap_or_else(|| Arc::new(LocalFsBackend::new()));
+        let mut fs_manager = FsManager::new(fs_backend);
+        if let Some(ref runtime) = tokio_runtime {
+            fs_manager = fs_manager.with_runtime(runtime.handle().clone());
+        }
+        let fs_manager = Arc::new(fs_manager);
+
         // Canonicalize working_dir to resolve symlinks and normalize path components
         // This ensures consistent path comparisons throughout the editor
-        let working_dir = working_dir.canonicalize().unwrap_or_else(|_| working_dir);
+        let working_dir = fs_manager
+            .block_on(fs_manager.canonicalize(&working_dir))
+            .unwrap_or(working_dir);
 
         // Load theme from config
         let theme = crate::view::theme::Theme::from_name(&config.theme);
 
         // Load grammar registry for TextMate syntax highlighting
         let grammar_registry =
-            Arc::new(crate::primitives::grammar_registry::GrammarRegistry::load());
+            Arc::new(crate::primitives::grammar_registry::GrammarRegistry::load(
+                &dir_context.grammars_dir(),
+                &config.languages,
+            ));
         tracing::info!(
             "Loaded grammar registry with {} syntaxes",
             grammar_registry.available_syntaxes().len()
@@ -563,17 +767,12 @@ impl Editor {
             .ok()
             .and_then(|u| u.as_str().parse::<lsp_types::Uri>().ok());
 
-        // Create Tokio runtime for async I/O (LSP, file watching, git, etc.)
-        let tokio_runtime = tokio::runtime::Builder::new_multi_thread()
-            .worker_threads(2) // Small pool for I/O tasks
-            .thread_name("editor-async")
-            .enable_all()
-            .build()
-            .ok();
-
         // Create async bridge for communication
         let async_bridge = AsyncBridge::new();
 
+        // Reload grammars when they are edited
+        let grammar_watcher = Self::watch_grammars(&dir_context.grammars_dir(), &async_bridge);
+
         if tokio_runtime.is_none() {
             tracing::warn!("Failed to create Tokio runtime - async features disabled");
         }
@@ -588,7 +787,7 @@ impl Editor {
 
         // Configure LSP servers from config
         for (language, lsp_config) in &config.lsp {
-            lsp.set_language_config(language.clone(), lsp_config.clone());
+            lsp.set_language_servers(language.clone(), lsp_config.servers().to_vec());
         }
 
         // Initialize split manager with the initial buffer
@@ -601,10 +800,10 @@ impl Editor {
         initial_view_state.viewport.line_wrap_enabled = config.editor.line_wrap;
         split_view_states.insert(initial_split_id, initial_view_state);
 
-        // Initialize filesystem manager for file explorer
-        // Use provided backend or create default LocalFsBackend
-        let fs_backend = fs_backend.unwrap_or_else(|| Arc::new(LocalFsBackend::new()));
-        let fs_manager = Arc::new(FsManager::new(fs_backend));
+        // The initial buffer is saved to the editor's filesystem too
+        for state in buffers.values_mut() {
+            state.buffer.set_fs(Arc::clone(&fs_manager));
+        }
 
         // Initialize command registry (always available, used by both plugins and core)
         let command_registry = Arc::new(RwLock::new(CommandRegistry::new()));
@@ -615,6 +814,8 @@ impl Editor {
         // Load TypeScript plugins from multiple directories:
         // 1. Next to the executable (for cargo-dist installations)
         // 2. In the working directory (for development/local usage)
+        // 3. In the config directory (user-installed, sandboxed)
+        // 4. From the installed plugin packages (sandboxed)
         if plugin_manager.is_active() {
             let mut plugin_dirs: Vec<std::path::PathBuf> = vec![];
 
@@ -644,7 +845,7 @@ impl Editor {
             // Load from all found plugin directories
             for plugin_dir in plugin_dirs {
                 tracing::info!("Loading TypeScript plugins from: {:?}", plugin_dir);
-                let errors = plugin_manager.load_plugins_from_dir(&plugin_dir);
+                let errors = plugin_manager.load_plugins_from_dir(&plugin_dir, false);
                 if !errors.is_empty() {
                     for err in &errors {
                         tracing::error!("TypeScript plugin load error: {}", err);
@@ -658,6 +859,32 @@ impl Editor {
                     );
                 }
             }
+
+            // Plugins installed by the user run sandboxed, limited to the
+            // capabilities declared in their manifests
+            let user_plugin_dir = dir_context.plugins_dir();
+            if user_plugin_dir.is_dir() {
+                tracing::info!("Loading user plugins from: {:?}", user_plugin_dir);
+                for err in plugin_manager.load_plugins_from_dir(&user_plugin_dir, true) {
+                    tracing::error!("TypeScript plugin load error: {}", err);
+                }
+            }
+
+            let packages = PluginPackages::new(
+                dir_context.plugin_registry_path(),
+                dir_context.plugin_packages_dir(),
+            );
+            match packages.enabled_plugin_dirs() {
+                Ok(dirs) => {
+                    for (name, dir) in dirs {
+                        tracing::info!("Loading plugin package '{}' from: {:?}", name, dir);
+                        for err in plugin_manager.load_plugins_from_dir(&dir, true) {
+                            tracing::error!("TypeScript plugin load error: {}", err);
+                        }
+                    }
+                }
+                Err(e) => tracing::error!("Failed to read the plugin registry: {}", e),
+            }
         }
 
         // Extract config values before moving config into the struct
@@ -665,6 +892,18 @@ impl Editor {
         let recovery_enabled = config.editor.recovery_enabled;
         let auto_save_interval_secs = config.editor.auto_save_interval_secs;
         let check_for_updates = config.check_for_updates;
+        let undo_store = config.editor.persistent_undo.then(|| {
+            UndoHistoryStore::new(
+                dir_context.undo_history_dir(),
+                config.editor.persistent_undo_max_entries,
+                config.editor.persistent_undo_max_files,
+            )
+        });
+        let undo_writer = undo_store.clone().and_then(|store| {
+            UndoHistoryWriter::spawn(store)
+                .map_err(|e| tracing::warn!("Failed to start undo history writer: {}", e))
+                .ok()
+        });
 
         // Start periodic update checker if enabled
         let update_checker = if check_for_updates {
@@ -694,6 +933,7 @@ impl Editor {
             clipboard: crate::services::clipboard::Clipboard::new(),
             should_quit: false,
             restart_with_dir: None,
+            restart_with_session: None,
             status_message: None,
             plugin_status_message: None,
             prompt: None,
@@ -707,10 +947,14 @@ impl Editor {
             split_manager,
             split_view_states,
             file_explorer: None,
-            fs_manager,
+            fs_manager: Arc::clone(&fs_manager),
             file_explorer_visible: false,
             file_explorer_sync_in_progress: false,
             file_explorer_width_percent: file_explorer_width,
+            file_trash: Some(Trash::new(dir_context.trash_dir())),
+            file_operations: Vec::new(),
+            file_clipboard: None,
+            pending_paste: None,
             mouse_enabled: true,
             mouse_cursor_position: None,
             gpm_active: false,
@@ -722,16 +966,22 @@ impl Editor {
             next_lsp_request_id: 0,
             pending_completion_request: None,
             pending_goto_definition_request: None,
+            pending_format_requests: Vec::new(),
+            pending_file_renames: HashMap::new(),
             pending_hover_request: None,
             pending_references_request: None,
             pending_references_symbol: String::new(),
             pending_signature_help_request: None,
             pending_code_actions_request: None,
             pending_inlay_hints_request: None,
+            pending_folding_range_request: None,
+            pending_selection_range_request: None,
+            completion_inserts: Vec::new(),
             hover_symbol_range: None,
             hover_symbol_overlay: None,
             mouse_hover_screen_position: None,
             search_state: None,
+            streaming_search: None,
             search_namespace: crate::view::overlay::OverlayNamespace::from_string(
                 "search".to_string(),
             ),
@@ -772,6 +1022,7 @@ impl Editor {
             lsp_window_messages: Vec::new(),
             lsp_log_messages: Vec::new(),
             diagnostic_result_ids: HashMap::new(),
+            diagnostics_by_server: Default::default(),
             stored_diagnostics: HashMap::new(),
             event_broadcaster: crate::model::control_event::EventBroadcaster::default(),
             bookmarks: HashMap::new(),
@@ -779,8 +1030,24 @@ impl Editor {
             search_whole_word: false,
             search_use_regex: false,
             search_confirm_each: false,
-            macros: HashMap::new(),
+            macros: {
+                let path = dir_context.macros_path();
+                MacroStore::load_from_file(&path).unwrap_or_else(|e| {
+                    tracing::warn!("Failed to load macros: {}", e);
+                    MacroStore::new()
+                })
+            },
+            macro_edit_buffers: HashMap::new(),
             macro_recording: None,
+            snippets: SnippetStore::load_from_dir(&dir_context.snippets_dir()),
+            snippet_session: None,
+            undo_history_panel: None,
+            project_search_panel: None,
+            project_search_include: Vec::new(),
+            project_search_exclude: Vec::new(),
+            debug_session: None,
+            debug_panel: None,
+            next_debug_session_id: 0,
             last_macro_register: None,
             #[cfg(feature = "plugins")]
             pending_plugin_actions: Vec::new(),
@@ -791,6 +1058,7 @@ impl Editor {
             pending_close_buffer: None,
             auto_revert_enabled: true,
             file_watcher: None,
+            _grammar_watcher: grammar_watcher,
             watched_dirs: HashSet::new(),
             file_mod_times: HashMap::new(),
             file_rapid_change_counts: HashMap::new(),
@@ -802,8 +1070,22 @@ impl Editor {
                     auto_save_interval_secs,
                     ..RecoveryConfig::default()
                 };
-                RecoveryService::with_config_and_dir(recovery_config, dir_context.recovery_dir())
+                let mut recovery_service = RecoveryService::with_config_and_dir(
+                    recovery_config,
+                    dir_context.recovery_dir(),
+                );
+                // Recovered files are read through the editor's filesystem
+                recovery_service.set_fs(fs_manager);
+                recovery_service
             },
+            undo_store,
+            undo_writer,
+            session_enabled: false,
+            session_name: None,
+            hot_exit_conflicts: Vec::new(),
+            pending_plugin_permissions: std::collections::VecDeque::new(),
+            active_plugin_permission: None,
+            denied_plugin_permissions: HashSet::new(),
             last_auto_save: std::time::Instant::now(),
             active_custom_contexts: HashSet::new(),
             warning_log: None,
@@ -926,13 +1208,12 @@ impl Editor {
             .collect()
     }
 
-    /// Check if LSP server for a given language is running (ready)
+    /// Check if an LSP server for a given language is running (ready)
     pub fn is_lsp_server_ready(&self, language: &str) -> bool {
         use crate::services::async_bridge::LspServerStatus;
         self.lsp_server_statuses
-            .get(language)
-            .map(|status| matches!(status, LspServerStatus::Running))
-            .unwrap_or(false)
+            .iter()
+            .any(|((lang, _), status)| lang == language && *status == LspServerStatus::Running)
     }
 
     /// Get the LSP status string (displayed in status bar)
@@ -1193,14 +1474,17 @@ impl Editor {
     ///
     /// If the file doesn't exist, creates an unsaved buffer with that filename.
     pub fn open_file_no_focus(&mut self, path: &Path) -> io::Result<BufferId> {
+        let fs = Arc::clone(&self.fs_manager);
+
         // Determine if we're opening a non-existent file (for creating new files)
-        let file_exists = path.exists();
+        let file_exists = fs.block_on(fs.exists(path));
 
         // Canonicalize the path to resolve symlinks and normalize path components
         // This ensures consistent path representation throughout the editor
         // For non-existent files, we need to canonicalize the parent directory and append the filename
         let canonical_path = if file_exists {
-            path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+            fs.block_on(fs.canonicalize(path))
+                .unwrap_or_else(|_| path.to_path_buf())
         } else {
             // For non-existent files, canonicalize parent dir and append filename
             if let Some(parent) = path.parent() {
@@ -1208,8 +1492,7 @@ impl Editor {
                     // No parent means just a filename, use working dir
                     self.working_dir.clone()
                 } else {
-                    parent
-                        .canonicalize()
+                    fs.block_on(fs.canonicalize(parent))
                         .unwrap_or_else(|_| parent.to_path_buf())
                 };
                 if let Some(filename) = path.file_name() {
@@ -1254,12 +1537,12 @@ impl Editor {
 
         // Create the editor state - either load from file or create empty buffer
         let mut state = if file_exists {
-            EditorState::from_file(
+            EditorState::from_file_with_encoding(
                 path,
-                self.terminal_width,
-                self.terminal_height,
                 self.config.editor.large_file_threshold_bytes as usize,
                 &self.grammar_registry,
+                None,
+                fs,
             )?
         } else {
             // File doesn't exist - create empty buffer with the file path set
@@ -1270,6 +1553,7 @@ impl Editor {
             );
             // Set the file path so saving will create the file
             new_state.buffer.set_file_path(path.to_path_buf());
+            new_state.buffer.set_fs(fs);
             new_state
         };
         // Note: line_wrap_enabled is set on SplitViewState.viewport when the split is created
@@ -1284,6 +1568,9 @@ impl Editor {
 
         self.buffers.insert(buffer_id, state);
         self.event_logs.insert(buffer_id, EventLog::new());
+        if file_exists {
+            self.restore_undo_history(buffer_id);
+        }
 
         // Create metadata for this buffer
         let mut metadata = BufferMetadata::with_file(path.to_path_buf(), &self.working_dir);
@@ -1414,11 +1701,13 @@ impl Editor {
         let buffer_id = BufferId(self.next_buffer_id);
         self.next_buffer_id += 1;
 
-        let state = EditorState::new(
+        let mut state = EditorState::new(
             self.terminal_width,
             self.terminal_height,
             self.config.editor.large_file_threshold_bytes as usize,
         );
+        // New buffers are saved to the editor's filesystem
+        state.buffer.set_fs(Arc::clone(&self.fs_manager));
         // Note: line_wrap_enabled is set on SplitViewState.viewport when the split is created
         self.buffers.insert(buffer_id, state);
         self.event_logs.insert(buffer_id, EventLog::new());
@@ -1642,6 +1931,7 @@ impl Editor {
         if self.settings_state.is_none() {
             match crate::view::settings::SettingsState::new(SCHEMA_JSON, &self.config) {
                 Ok(mut state) => {
+                    self.add_plugin_settings_pages(&mut state);
                     state.show();
                     self.settings_state = Some(state);
                 }
@@ -1925,6 +2215,8 @@ impl Editor {
 
     /// Internal helper to close a buffer (shared by close_buffer and force_close_buffer)
     fn close_buffer_internal(&mut self, id: BufferId) -> io::Result<()> {
+        self.persist_undo_history(id);
+
         // If it's the last buffer, create a new empty buffer and focus file explorer
         let is_last_buffer = self.buffers.len() == 1;
         let replacement_buffer = if is_last_buffer {
@@ -1946,6 +2238,28 @@ impl Editor {
         self.event_logs.remove(&id);
         self.seen_byte_ranges.remove(&id);
         self.buffer_metadata.remove(&id);
+        self.macro_edit_buffers.remove(&id);
+        if self
+            .undo_history_panel
+            .as_ref()
+            .is_some_and(|panel| panel.buffer_id == id || panel.source_buffer == id)
+        {
+            self.undo_history_panel = None;
+        }
+        if self
+            .project_search_panel
+            .as_ref()
+            .is_some_and(|panel| panel.buffer_id == id)
+        {
+            self.project_search_panel = None;
+        }
+        if self
+            .debug_panel
+            .as_ref()
+            .is_some_and(|panel| panel.buffer_id == id)
+        {
+            self.debug_panel = None;
+        }
 
         // Remove buffer from panel_ids mapping if it was a panel buffer
         // This prevents stale entries when the same panel_id is reused later
@@ -2467,28 +2781,7 @@ impl Editor {
 
     /// Adjust cursors in other splits that share the same buffer after an edit
     fn adjust_other_split_cursors_for_event(&mut self, event: &Event) {
-        // Find the edit parameters from the event
-        let adjustments = match event {
-            Event::Insert { position, text, .. } => {
-                vec![(*position, 0, text.len())]
-            }
-            Event::Delete { range, .. } => {
-                vec![(range.start, range.len(), 0)]
-            }
-            Event::Batch { events, .. } => {
-                // Collect all edits from the batch
-                events
-                    .iter()
-                    .filter_map(|e| match e {
-                        Event::Insert { position, text, .. } => Some((*position, 0, text.len())),
-                        Event::Delete { range, .. } => Some((range.start, range.len(), 0)),
-                        _ => None,
-                    })
-                    .collect()
-            }
-            _ => vec![],
-        };
-
+        let adjustments = edit_adjustments(event);
         if adjustments.is_empty() {
             return;
         }
@@ -2708,7 +3001,7 @@ impl Editor {
 
         // Get LSP client for this language
         if let Some(lsp) = &mut self.lsp {
-            if let Some(client) = lsp.get_or_spawn(&language) {
+            if let Some(client) = lsp.get_or_spawn_for(&language, LspFeature::InlayHints) {
                 let request_id = self.next_lsp_request_id;
                 self.next_lsp_request_id += 1;
                 self.pending_inlay_hints_request = Some(request_id);
@@ -3033,8 +3326,10 @@ impl Editor {
             _ => {}
         }
 
-        // 2. Adjust cursors in other splits that share the same buffer
+        // 2. Adjust cursors in other splits that share the same buffer, and
+        // folds in every split
         self.adjust_other_split_cursors_for_event(event);
+        self.adjust_folds_for_event(event);
 
         // 3. Clear search highlights on edit (Insert/Delete events)
         // This preserves highlights while navigating but clears them when modifying text
@@ -3235,6 +3530,7 @@ impl Editor {
         let view_state = self.split_view_states.get_mut(&active_split);
 
         if let Some(view_state) = view_state {
+            view_state.sync_hidden_ranges(buffer_id);
             if let Some(tokens) = view_transform_tokens {
                 // Use view-aware scrolling with the transform's tokens
                 let view_lines: Vec<_> = ViewLineIterator::new(&tokens).collect();
@@ -3270,6 +3566,7 @@ impl Editor {
         let view_state = self.split_view_states.get_mut(&active_split);
 
         if let (Some(buffer), Some(view_state)) = (buffer, view_state) {
+            view_state.sync_hidden_ranges(buffer_id);
             view_state.viewport.scroll_to(buffer, top_line);
             // Skip ensure_visible so the explicit scroll position isn't undone during render
             view_state.viewport.set_skip_ensure_visible();
@@ -3302,6 +3599,7 @@ impl Editor {
                 // Now scroll the viewport
                 let buffer = &mut self.buffers.get_mut(&buffer_id).unwrap().buffer;
                 if let Some(view_state) = self.split_view_states.get_mut(&active_split) {
+                    view_state.sync_hidden_ranges(buffer_id);
                     view_state.viewport.scroll_to(buffer, new_top);
                 }
             }
@@ -3569,23 +3867,33 @@ impl Editor {
             .file_path()
             .map(|p| p.to_path_buf());
         self.active_state_mut().buffer.save()?;
+        if let Some(metadata) = self.buffer_metadata.get_mut(&self.active_buffer()) {
+            metadata.orphaned = false;
+        }
         self.status_message = Some("Saved".to_string());
 
         // Mark the event log position as saved (for undo modified tracking)
         self.active_event_log_mut().mark_saved();
+        self.persist_undo_history(self.active_buffer());
 
         // Update file modification time after save
         if let Some(ref p) = path {
-            if let Ok(metadata) = std::fs::metadata(p) {
-                if let Ok(mtime) = metadata.modified() {
-                    self.file_mod_times.insert(p.clone(), mtime);
-                }
+            if let Some(mtime) = self.file_mtime(p) {
+                self.file_mod_times.insert(p.clone(), mtime);
             }
         }
 
         // Notify LSP of save
         self.notify_lsp_save();
 
+        // Pick up edits to the user's snippet files
+        if path
+            .as_ref()
+            .is_some_and(|p| p.starts_with(self.dir_context.snippets_dir()))
+        {
+            self.reload_snippets();
+        }
+
         // Delete recovery file (buffer is now saved)
         let _ = self.delete_buffer_recovery(self.active_buffer());
 
@@ -3630,6 +3938,22 @@ impl Editor {
             return Ok(false);
         }
 
+        self.reload_active_file(&path, None)?;
+
+        self.status_message = Some("Reverted to saved file".to_string());
+        Ok(true)
+    }
+
+    /// Replace the active buffer with the file at `path` freshly loaded from
+    /// disk, decoded with `encoding` (or the detected encoding if `None`)
+    ///
+    /// Cursors and scroll position are kept (clamped to the new content);
+    /// undo history is cleared.
+    pub(crate) fn reload_active_file(
+        &mut self,
+        path: &Path,
+        encoding: Option<TextEncoding>,
+    ) -> io::Result<()> {
         // Save scroll position (from SplitViewState) and cursor positions before reloading
         let active_split = self.split_manager.active_split();
         let (old_top_byte, old_left_column) = self
@@ -3640,12 +3964,12 @@ impl Editor {
         let old_cursors = self.active_state().cursors.clone();
 
         // Load the file content fresh from disk
-        let mut new_state = EditorState::from_file(
-            &path,
-            self.terminal_width,
-            self.terminal_height,
+        let mut new_state = EditorState::from_file_with_encoding(
+            path,
             self.config.editor.large_file_threshold_bytes as usize,
             &self.grammar_registry,
+            encoding,
+            Arc::clone(&self.fs_manager),
         )?;
 
         // Restore cursor positions (clamped to valid range for new file size)
@@ -3681,17 +4005,14 @@ impl Editor {
         self.seen_byte_ranges.remove(&buffer_id);
 
         // Update the file modification time
-        if let Ok(metadata) = std::fs::metadata(&path) {
-            if let Ok(mtime) = metadata.modified() {
-                self.file_mod_times.insert(path.clone(), mtime);
-            }
+        if let Some(mtime) = self.file_mtime(path) {
+            self.file_mod_times.insert(path.to_path_buf(), mtime);
         }
 
         // Notify LSP that the file was changed
-        self.notify_lsp_file_changed(&path);
+        self.notify_lsp_file_changed(path);
 
-        self.status_message = Some("Reverted to saved file".to_string());
-        Ok(true)
+        Ok(())
     }
 
     /// Toggle auto-revert mode
@@ -3806,7 +4127,13 @@ impl Editor {
         };
 
         // Check file size
-        let file_size = std::fs::metadata(path).ok().map(|m| m.len()).unwrap_or(0);
+        let file_size = self
+            .fs_manager
+            .block_on(self.fs_manager.get_entry(path))
+            .ok()
+            .and_then(|entry| entry.metadata)
+            .and_then(|m| m.size)
+            .unwrap_or(0);
         if file_size > self.config.editor.large_file_threshold_bytes {
             let reason = format!("File too large ({} bytes)", file_size);
             tracing::warn!(
@@ -3832,7 +4159,6 @@ impl Editor {
         };
 
         let enable_inlay_hints = self.config.editor.enable_inlay_hints;
-        let previous_result_id = self.diagnostic_result_ids.get(uri.as_str()).cloned();
 
         // Get buffer line count for inlay hints
         let (last_line, last_char) = self
@@ -3861,33 +4187,53 @@ impl Editor {
 
         match lsp.try_spawn(&language) {
             LspSpawnResult::Spawned => {
-                if let Some(client) = lsp.get_or_spawn(&language) {
+                for server in lsp.get_or_spawn_all(&language) {
                     // Send didOpen
-                    tracing::info!("Sending didOpen to LSP for: {}", uri.as_str());
-                    if let Err(e) = client.did_open(uri.clone(), text, language.clone()) {
-                        tracing::warn!("Failed to send didOpen to LSP: {}", e);
-                        return;
+                    tracing::info!(
+                        "Sending didOpen to LSP {} for: {}",
+                        server.name,
+                        uri.as_str()
+                    );
+                    if let Err(e) =
+                        server
+                            .handle
+                            .did_open(uri.clone(), text.clone(), language.clone())
+                    {
+                        tracing::warn!("Failed to send didOpen to LSP {}: {}", server.name, e);
+                        continue;
                     }
-                    tracing::info!("Successfully sent didOpen to LSP");
+                    tracing::info!("Successfully sent didOpen to LSP {}", server.name);
 
                     // Request pull diagnostics
+                    if server.provides(LspFeature::PullDiagnostics) == Some(false) {
+                        continue;
+                    }
+                    let previous_result_id = self
+                        .diagnostic_result_ids
+                        .get(&(uri.as_str().to_string(), server.name.clone()))
+                        .cloned();
                     let request_id = self.next_lsp_request_id;
                     self.next_lsp_request_id += 1;
-                    if let Err(e) =
-                        client.document_diagnostic(request_id, uri.clone(), previous_result_id)
-                    {
+                    if let Err(e) = server.handle.document_diagnostic(
+                        request_id,
+                        uri.clone(),
+                        previous_result_id,
+                    ) {
                         tracing::debug!(
                             "Failed to request pull diagnostics (server may not support): {}",
                             e
                         );
                     } else {
                         tracing::info!(
-                            "Requested pull diagnostics for {} (request_id={})",
+                            "Requested pull diagnostics for {} from {} (request_id={})",
                             uri.as_str(),
+                            server.name,
                             request_id
                         );
                     }
+                }
 
+                if let Some(client) = lsp.get_or_spawn_for(&language, LspFeature::InlayHints) {
                     // Request inlay hints
                     if enable_inlay_hints {
                         let request_id = self.next_lsp_request_id;
@@ -3924,6 +4270,15 @@ impl Editor {
         }
     }
 
+    /// Modification time of a file, or None if it can't be read
+    fn file_mtime(&self, path: &Path) -> Option<std::time::SystemTime> {
+        self.fs_manager
+            .block_on(self.fs_manager.get_entry(path))
+            .ok()?
+            .metadata?
+            .modified
+    }
+
     /// Add a file to the file watcher (called when opening files)
     /// We watch the parent directory instead of the file itself to handle
     /// atomic saves (temp file + rename) which change the file's inode
@@ -3931,10 +4286,8 @@ impl Editor {
         use notify::{RecursiveMode, Watcher};
 
         // Record current modification time
-        if let Ok(metadata) = std::fs::metadata(path) {
-            if let Ok(mtime) = metadata.modified() {
-                self.file_mod_times.insert(path.to_path_buf(), mtime);
-            }
+        if let Some(mtime) = self.file_mtime(path) {
+            self.file_mod_times.insert(path.to_path_buf(), mtime);
         }
 
         // Add parent directory to watcher if auto-revert is enabled
@@ -3974,13 +4327,16 @@ impl Editor {
 
                         // Use full document sync - send the entire new content
                         if let Some(content) = content {
-                            if let Some(client) = lsp.get_or_spawn(&language) {
+                            for server in lsp.get_or_spawn_all(&language) {
                                 let content_change = TextDocumentContentChangeEvent {
                                     range: None, // None means full document replacement
                                     range_length: None,
-                                    text: content,
+                                    text: content.clone(),
                                 };
-                                if let Err(e) = client.did_change(lsp_uri, vec![content_change]) {
+                                if let Err(e) = server
+                                    .handle
+                                    .did_change(lsp_uri.clone(), vec![content_change])
+                                {
                                     tracing::warn!("Failed to notify LSP of file change: {}", e);
                                 }
                             }
@@ -4016,9 +4372,9 @@ impl Editor {
             // Check if the file actually changed (compare mod times)
             // We use optimistic concurrency: check mtime, and if we decide to revert,
             // re-check to handle the race where a save completed between our checks.
-            let current_mtime = match std::fs::metadata(&path).and_then(|m| m.modified()) {
-                Ok(mtime) => mtime,
-                Err(_) => continue, // Can't read file, skip
+            let current_mtime = match self.file_mtime(&path) {
+                Some(mtime) => mtime,
+                None => continue, // Can't read file, skip
             };
 
             let dominated_by_stored = self
@@ -4084,10 +4440,8 @@ impl Editor {
         };
 
         // Get current file modification time
-        let current_mtime = match std::fs::metadata(path).and_then(|m| m.modified()) {
-            Ok(mtime) => mtime,
-            Err(_) => return None, // File doesn't exist or can't read metadata
-        };
+        // (None if the file doesn't exist or its metadata can't be read)
+        let current_mtime = self.file_mtime(path)?;
 
         // Compare with our recorded modification time
         match self.file_mod_times.get(path) {
@@ -4115,6 +4469,13 @@ impl Editor {
         self.restart_with_dir.take()
     }
 
+    /// Take the session the restart should load, clearing the request
+    /// Returns Some(name) if the restart is a session switch (with a None
+    /// name for the default session)
+    pub fn take_restart_session(&mut self) -> Option<Option<String>> {
+        self.restart_with_session.take()
+    }
+
     /// Request the editor to restart with a new working directory
     /// This triggers a clean shutdown and restart with the new project root
     pub fn request_restart(&mut self, new_working_dir: PathBuf) {
@@ -4134,9 +4495,9 @@ impl Editor {
 
     /// Request the editor to quit
     pub fn quit(&mut self) {
-        // Check for unsaved buffers
+        // Check for unsaved buffers (hot exit stashes them instead)
         let modified_count = self.count_modified_buffers();
-        if modified_count > 0 {
+        if modified_count > 0 && !self.hot_exit_enabled() {
             // Prompt user for confirmation
             let msg = if modified_count == 1 {
                 "1 buffer has unsaved changes. (d)iscard and quit, (C)ancel? ".to_string()
@@ -4152,6 +4513,22 @@ impl Editor {
         }
     }
 
+    /// Set whether the session is saved on quit
+    pub fn set_session_enabled(&mut self, enabled: bool) {
+        self.session_enabled = enabled;
+    }
+
+    /// Set the named session to save and restore (None for the working
+    /// directory's default session)
+    pub fn set_session_name(&mut self, name: Option<String>) {
+        self.session_name = name;
+    }
+
+    /// Get the name of the active named session
+    pub fn session_name(&self) -> Option<&str> {
+        self.session_name.as_deref()
+    }
+
     /// Count the number of modified buffers
     fn count_modified_buffers(&self) -> usize {
         self.buffers
@@ -4722,6 +5099,10 @@ impl Editor {
 
     /// Cancel the current prompt and return to normal mode
     pub fn cancel_prompt(&mut self) {
+        let cancelled_paste = matches!(
+            self.prompt.as_ref().map(|prompt| &prompt.prompt_type),
+            Some(PromptType::FileExplorerPasteConflict)
+        );
         // Determine prompt type and reset appropriate history navigation
         if let Some(ref prompt) = self.prompt {
             match &prompt.prompt_type {
@@ -4762,6 +5143,9 @@ impl Editor {
         self.prompt = None;
         self.pending_search_range = None;
         self.status_message = Some("Canceled".to_string());
+        if cancelled_paste {
+            self.cancel_paste();
+        }
     }
 
     /// Get the confirmed input and prompt type, consuming the prompt
@@ -4780,7 +5164,18 @@ impl Editor {
                     | PromptType::SaveFileAs
                     | PromptType::StopLspServer
                     | PromptType::SelectTheme
+                    | PromptType::InsertSnippet
+                    | PromptType::ReopenWithEncoding
+                    | PromptType::SaveWithEncoding
                     | PromptType::SwitchToTab
+                    | PromptType::SwitchSession
+                    | PromptType::DeleteSession
+                    | PromptType::PluginPermission
+                    | PromptType::RemovePluginPackage
+                    | PromptType::EnablePluginPackage
+                    | PromptType::DisablePluginPackage
+                    | PromptType::PinPluginPackage
+                    | PromptType::UnpinPluginPackage
             ) {
                 // Use the selected suggestion if any
                 if let Some(selected_idx) = prompt.selected_suggestion {
@@ -4824,12 +5219,18 @@ impl Editor {
 
             // Add to appropriate history based on prompt type
             match prompt.prompt_type {
-                PromptType::Search | PromptType::ReplaceSearch | PromptType::QueryReplaceSearch => {
+                PromptType::Search
+                | PromptType::ReplaceSearch
+                | PromptType::QueryReplaceSearch
+                | PromptType::ProjectSearch
+                | PromptType::ProjectReplaceSearch => {
                     self.search_history.push(final_input.clone());
                     // Reset navigation state
                     self.search_history.reset_navigation();
                 }
-                PromptType::Replace { .. } | PromptType::QueryReplace { .. } => {
+                PromptType::Replace { .. }
+                | PromptType::QueryReplace { .. }
+                | PromptType::ProjectReplace { .. } => {
                     self.replace_history.push(final_input.clone());
                     // Reset navigation state
                     self.replace_history.reset_navigation();
@@ -4946,7 +5347,20 @@ impl Editor {
                     },
                 );
             }
-            PromptType::SwitchToTab | PromptType::SelectTheme | PromptType::StopLspServer => {
+            PromptType::SwitchToTab
+            | PromptType::SwitchSession
+            | PromptType::DeleteSession
+            | PromptType::PluginPermission
+            | PromptType::RemovePluginPackage
+            | PromptType::EnablePluginPackage
+            | PromptType::DisablePluginPackage
+            | PromptType::PinPluginPackage
+            | PromptType::UnpinPluginPackage
+            | PromptType::SelectTheme
+            | PromptType::InsertSnippet
+            | PromptType::ReopenWithEncoding
+            | PromptType::SaveWithEncoding
+            | PromptType::StopLspServer => {
                 // Filter suggestions using fuzzy matching
                 use crate::input::fuzzy::fuzzy_match;
 
@@ -4995,11 +5409,16 @@ impl Editor {
 
         let messages = bridge.try_recv_all();
         let needs_render = !messages.is_empty();
+        let mut grammars_changed = false;
 
         for message in messages {
             match message {
-                AsyncMessage::LspDiagnostics { uri, diagnostics } => {
-                    self.handle_lsp_diagnostics(uri, diagnostics);
+                AsyncMessage::LspDiagnostics {
+                    uri,
+                    server,
+                    diagnostics,
+                } => {
+                    self.handle_lsp_diagnostics(uri, server, diagnostics);
                 }
                 AsyncMessage::LspInitialized { language } => {
                     tracing::info!("LSP server initialized for language: {}", language);
@@ -5062,6 +5481,21 @@ impl Editor {
                         tracing::error!("Error handling rename response: {}", e);
                     }
                 }
+                AsyncMessage::LspWillRenameFiles { request_id, result } => {
+                    if let Err(e) = self.handle_will_rename_files_response(request_id, result) {
+                        tracing::error!("Error handling willRenameFiles response: {}", e);
+                    }
+                }
+                AsyncMessage::LspFormatting {
+                    request_id,
+                    uri: _,
+                    result,
+                } => {
+                    self.handle_formatting_response(request_id, result);
+                }
+                AsyncMessage::FormatterFinished { request_id, result } => {
+                    self.handle_formatter_finished(request_id, result);
+                }
                 AsyncMessage::LspHover {
                     request_id,
                     contents,
@@ -5093,11 +5527,18 @@ impl Editor {
                 AsyncMessage::LspPulledDiagnostics {
                     request_id: _,
                     uri,
+                    server,
                     result_id,
                     diagnostics,
                     unchanged,
                 } => {
-                    self.handle_lsp_pulled_diagnostics(uri, result_id, diagnostics, unchanged);
+                    self.handle_lsp_pulled_diagnostics(
+                        uri,
+                        server,
+                        result_id,
+                        diagnostics,
+                        unchanged,
+                    );
                 }
                 AsyncMessage::LspInlayHints {
                     request_id,
@@ -5106,12 +5547,30 @@ impl Editor {
                 } => {
                     self.handle_lsp_inlay_hints(request_id, uri, hints);
                 }
+                AsyncMessage::LspFoldingRanges {
+                    request_id,
+                    uri,
+                    ranges,
+                } => {
+                    self.handle_lsp_folding_ranges(request_id, uri, ranges);
+                }
+                AsyncMessage::LspSelectionRanges {
+                    request_id,
+                    uri,
+                    ranges,
+                } => {
+                    self.handle_lsp_selection_ranges(request_id, uri, ranges);
+                }
                 AsyncMessage::LspServerQuiescent { language } => {
                     self.handle_lsp_server_quiescent(language);
                 }
                 AsyncMessage::FileChanged { path } => {
                     self.handle_async_file_changed(path);
                 }
+                AsyncMessage::GrammarsChanged => {
+                    // A save can report several changes: reload once
+                    grammars_changed = true;
+                }
                 AsyncMessage::GitStatusChanged { status } => {
                     tracing::info!("Git status changed: {}", status);
                     // TODO: Handle git status changes
@@ -5179,12 +5638,69 @@ impl Editor {
                 } => {
                     self.handle_lsp_log_message(language, message_type, message);
                 }
-                AsyncMessage::LspStatusUpdate { language, status } => {
-                    self.handle_lsp_status_update(language, status);
+                AsyncMessage::LspStatusUpdate {
+                    language,
+                    server,
+                    status,
+                } => {
+                    self.handle_lsp_status_update(language, server, status);
                 }
                 AsyncMessage::FileOpenDirectoryLoaded(result) => {
                     self.handle_file_open_directory_loaded(result);
                 }
+                AsyncMessage::PluginPackageFinished { name, result } => {
+                    self.handle_plugin_package_finished(name, result);
+                }
+                AsyncMessage::SearchProgress {
+                    search_id,
+                    matches,
+                    bytes_searched,
+                    total_bytes,
+                } => {
+                    self.handle_search_progress(search_id, matches, bytes_searched, total_bytes);
+                }
+                AsyncMessage::SearchFinished { search_id, error } => {
+                    self.handle_search_finished(search_id, error);
+                }
+                AsyncMessage::ProjectSearchProgress { search_id, files } => {
+                    self.handle_project_search_progress(search_id, files);
+                }
+                AsyncMessage::ProjectSearchFinished {
+                    search_id,
+                    truncated,
+                    error,
+                } => {
+                    self.handle_project_search_finished(search_id, truncated, error);
+                }
+                AsyncMessage::DapStopped {
+                    session_id,
+                    reason,
+                    frames,
+                } => {
+                    self.handle_dap_stopped(session_id, reason, frames);
+                }
+                AsyncMessage::DapVariables {
+                    session_id,
+                    frame_id,
+                    scopes,
+                } => {
+                    self.handle_dap_variables(session_id, frame_id, scopes);
+                }
+                AsyncMessage::DapContinued { session_id } => {
+                    self.handle_dap_continued(session_id);
+                }
+                AsyncMessage::DapOutput { session_id, output } => {
+                    self.handle_dap_output(session_id, output);
+                }
+                AsyncMessage::DapError { session_id, error } => {
+                    self.handle_dap_error(session_id, error);
+                }
+                AsyncMessage::DapTerminated {
+                    session_id,
+                    exit_code,
+                } => {
+                    self.handle_dap_terminated(session_id, exit_code);
+                }
                 AsyncMessage::TerminalOutput { terminal_id } => {
                     // Terminal output received - check if we should auto-jump back to terminal mode
                     tracing::trace!("Terminal output received for {:?}", terminal_id);
@@ -5227,6 +5743,10 @@ impl Editor {
             }
         }
 
+        if grammars_changed {
+            self.reload_grammars();
+        }
+
         // Update plugin state snapshot BEFORE processing commands
         // This ensures plugins have access to current editor state (cursor positions, etc.)
         #[cfg(feature = "plugins")]
@@ -5235,6 +5755,9 @@ impl Editor {
         // Process TypeScript plugin commands
         let processed_any_commands = self.process_plugin_commands();
 
+        // Show queued plugin permission requests, and deny dismissed ones
+        let permission_prompts_changed = self.process_plugin_permission_prompts();
+
         // Process pending plugin action completions
         #[cfg(feature = "plugins")]
         self.process_pending_plugin_actions();
@@ -5242,6 +5765,9 @@ impl Editor {
         // Process pending LSP server restarts (with exponential backoff)
         self.process_pending_lsp_restarts();
 
+        // Save the buffers whose format-on-save is taking too long
+        let format_timed_out = self.check_format_on_save_timeouts();
+
         // Check and clear the plugin render request flag
         #[cfg(feature = "plugins")]
         let plugin_render = {
@@ -5259,7 +5785,11 @@ impl Editor {
         }
 
         // Trigger render if any async messages, plugin commands were processed, or plugin requested render
-        needs_render || processed_any_commands || plugin_render
+        needs_render
+            || processed_any_commands
+            || permission_prompts_changed
+            || plugin_render
+            || format_timed_out
     }
 
     /// Update LSP status bar string from active progress operations
@@ -5287,11 +5817,24 @@ impl Editor {
     fn update_lsp_status_from_server_statuses(&mut self) {
         use crate::services::async_bridge::LspServerStatus;
 
-        // Collect all server statuses
+        // Collect all server statuses, naming the server too for languages
+        // with several servers
         let mut statuses: Vec<(String, LspServerStatus)> = self
             .lsp_server_statuses
             .iter()
-            .map(|(lang, status)| (lang.clone(), *status))
+            .map(|((lang, server), status)| {
+                let servers = self
+                    .lsp_server_statuses
+                    .keys()
+                    .filter(|(other, _)| other == lang)
+                    .count();
+                let label = if servers > 1 {
+                    format!("{}/{}", lang, server)
+                } else {
+                    lang.clone()
+                };
+                (label, *status)
+            })
             .collect();
 
         if statuses.is_empty() {
@@ -5731,6 +6274,21 @@ impl Editor {
                 self.handle_set_clipboard(text);
             }
 
+            // ==================== Permission Commands ====================
+            PluginCommand::RequestPluginPermission {
+                plugin,
+                kind,
+                target,
+                request_id,
+            } => {
+                self.handle_request_plugin_permission(PluginPermissionRequest {
+                    plugin,
+                    kind,
+                    target,
+                    request_id,
+                });
+            }
+
             // ==================== Deprecated Commands ====================
             PluginCommand::SpawnProcess {
                 command,
@@ -6090,11 +6648,13 @@ impl Editor {
         self.pending_completion_request = None;
         self.lsp_status.clear();
 
-        if items.is_empty() {
-            tracing::debug!("No completion items received");
-            return Ok(());
-        }
+        self.show_completion_popup(&items);
+        Ok(())
+    }
 
+    /// Show the completion popup for the LSP `items` and the user snippets
+    /// matching the word at the cursor
+    fn show_completion_popup(&mut self, items: &[lsp_types::CompletionItem]) {
         // Get the partial word at cursor to filter completions
         use crate::primitives::word_navigation::find_completion_word_start;
         let (word_start, cursor_pos) = {
@@ -6130,48 +6690,65 @@ impl Editor {
                 .collect()
         };
 
-        if filtered_items.is_empty() {
-            tracing::debug!("No completion items match prefix '{}'", prefix);
-            return Ok(());
-        }
-
         // Convert CompletionItem to PopupListItem
         use crate::view::popup::PopupListItem;
 
-        let popup_items: Vec<PopupListItem> = filtered_items
-            .iter()
-            .map(|item| {
-                let text = item.label.clone();
-                let detail = item.detail.clone();
-                let icon = match item.kind {
-                    Some(lsp_types::CompletionItemKind::FUNCTION)
-                    | Some(lsp_types::CompletionItemKind::METHOD) => Some("λ".to_string()),
-                    Some(lsp_types::CompletionItemKind::VARIABLE) => Some("v".to_string()),
-                    Some(lsp_types::CompletionItemKind::STRUCT)
-                    | Some(lsp_types::CompletionItemKind::CLASS) => Some("S".to_string()),
-                    Some(lsp_types::CompletionItemKind::CONSTANT) => Some("c".to_string()),
-                    Some(lsp_types::CompletionItemKind::KEYWORD) => Some("k".to_string()),
-                    _ => None,
-                };
+        let (mut popup_items, mut inserts): (Vec<PopupListItem>, Vec<snippets::CompletionInsert>) =
+            filtered_items
+                .iter()
+                .map(|item| {
+                    let text = item.label.clone();
+                    let detail = item.detail.clone();
+                    let icon = match item.kind {
+                        Some(lsp_types::CompletionItemKind::FUNCTION)
+                        | Some(lsp_types::CompletionItemKind::METHOD) => Some("λ".to_string()),
+                        Some(lsp_types::CompletionItemKind::VARIABLE) => Some("v".to_string()),
+                        Some(lsp_types::CompletionItemKind::STRUCT)
+                        | Some(lsp_types::CompletionItemKind::CLASS) => Some("S".to_string()),
+                        Some(lsp_types::CompletionItemKind::CONSTANT) => Some("c".to_string()),
+                        Some(lsp_types::CompletionItemKind::KEYWORD) => Some("k".to_string()),
+                        _ => None,
+                    };
 
-                let mut list_item = PopupListItem::new(text);
-                if let Some(detail) = detail {
-                    list_item = list_item.with_detail(detail);
-                }
-                if let Some(icon) = icon {
-                    list_item = list_item.with_icon(icon);
-                }
-                // Store the insert_text or label as data
-                let data = item
-                    .insert_text
-                    .clone()
-                    .or_else(|| Some(item.label.clone()));
-                if let Some(data) = data {
-                    list_item = list_item.with_data(data);
-                }
-                list_item
-            })
-            .collect();
+                    let mut list_item = PopupListItem::new(text);
+                    if let Some(detail) = detail {
+                        list_item = list_item.with_detail(detail);
+                    }
+                    if let Some(icon) = icon {
+                        list_item = list_item.with_icon(icon);
+                    }
+                    // Store the text to insert (text edit, insert_text or label) as data
+                    let text = match &item.text_edit {
+                        Some(lsp_types::CompletionTextEdit::Edit(edit)) => edit.new_text.clone(),
+                        Some(lsp_types::CompletionTextEdit::InsertAndReplace(edit)) => {
+                            edit.new_text.clone()
+                        }
+                        None => item
+                            .insert_text
+                            .clone()
+                            .unwrap_or_else(|| item.label.clone()),
+                    };
+                    list_item = list_item.with_data(text.clone());
+                    let insert =
+                        if item.insert_text_format == Some(lsp_types::InsertTextFormat::SNIPPET) {
+                            snippets::CompletionInsert::Snippet(text)
+                        } else {
+                            snippets::CompletionInsert::Text(text)
+                        };
+                    (list_item, insert)
+                })
+                .unzip();
+
+        for (item, insert) in self.snippet_completion_items(&prefix) {
+            popup_items.push(item);
+            inserts.push(insert);
+        }
+
+        if popup_items.is_empty() {
+            tracing::debug!("No completion items match prefix '{}'", prefix);
+            return;
+        }
+        self.completion_inserts = inserts;
 
         // Show the popup
         use crate::model::event::{
@@ -6200,9 +6777,10 @@ impl Editor {
         self.active_state_mut()
             .apply(&crate::model::event::Event::ShowPopup { popup: popup_data });
 
-        tracing::info!("Showing completion popup with {} items", items.len());
-
-        Ok(())
+        tracing::info!(
+            "Showing completion popup with {} items",
+            self.completion_inserts.len()
+        );
     }
 
     /// Handle LSP go-to-definition response
@@ -6301,7 +6879,8 @@ impl Editor {
         }
     }
 
-    /// Send a cancel request to the LSP server for a specific request ID
+    /// Send a cancel request for a specific request ID to the LSP servers of
+    /// the active buffer (those not handling it ignore it)
     fn send_lsp_cancel_request(&mut self, request_id: u64) {
         // Get the current file path to determine language
         let metadata = self.buffer_metadata.get(&self.active_buffer());
@@ -6310,8 +6889,8 @@ impl Editor {
         if let Some(path) = file_path {
             if let Some(language) = detect_language(path, &self.config.languages) {
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
-                        if let Err(e) = handle.cancel_request(request_id) {
+                    for server in lsp.get_or_spawn_all(&language) {
+                        if let Err(e) = server.handle.cancel_request(request_id) {
                             tracing::warn!("Failed to send LSP cancel request: {}", e);
                         } else {
                             tracing::debug!("Sent $/cancelRequest for request_id={}", request_id);
@@ -6339,16 +6918,18 @@ impl Editor {
             (None, None)
         };
 
+        let mut requested = false;
         if let (Some(uri), Some(path)) = (uri, file_path) {
             // Detect language from file extension
             if let Some(language) = detect_language(path, &self.config.languages) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) = lsp.get_or_spawn_for(&language, LspFeature::Completion) {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_completion_request = Some(request_id);
                         self.lsp_status = "LSP: completion...".to_string();
+                        requested = true;
 
                         let _ = handle.completion(
                             request_id,
@@ -6367,6 +6948,11 @@ impl Editor {
             }
         }
 
+        // Without a language server, complete user snippets only
+        if !requested {
+            self.show_completion_popup(&[]);
+        }
+
         Ok(())
     }
 
@@ -6392,7 +6978,9 @@ impl Editor {
             if let Some(language) = detect_language(path, &self.config.languages) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) =
+                        lsp.get_or_spawn_for(&language, LspFeature::GotoDefinition)
+                    {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_goto_definition_request = Some(request_id);
@@ -6450,7 +7038,7 @@ impl Editor {
             if let Some(language) = detect_language(path, &self.config.languages) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) = lsp.get_or_spawn_for(&language, LspFeature::Hover) {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_hover_request = Some(request_id);
@@ -6506,7 +7094,7 @@ impl Editor {
             if let Some(language) = detect_language(path, &self.config.languages) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) = lsp.get_or_spawn_for(&language, LspFeature::Hover) {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_hover_request = Some(request_id);
@@ -6771,7 +7359,7 @@ impl Editor {
             if let Some(language) = detect_language(path, &self.config.languages) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) = lsp.get_or_spawn_for(&language, LspFeature::References) {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_references_request = Some(request_id);
@@ -6821,7 +7409,8 @@ impl Editor {
             if let Some(language) = detect_language(path, &self.config.languages) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) = lsp.get_or_spawn_for(&language, LspFeature::SignatureHelp)
+                    {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_signature_help_request = Some(request_id);
@@ -6999,7 +7588,7 @@ impl Editor {
             if let Some(language) = detect_language(path, &self.config.languages) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) = lsp.get_or_spawn_for(&language, LspFeature::CodeActions) {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_code_actions_request = Some(request_id);
@@ -7259,6 +7848,99 @@ impl Editor {
         Ok(changes)
     }
 
+    /// Apply a workspace edit from a language server, returning the number of
+    /// text edits made
+    ///
+    /// Documents under the old path of a rename in `renames` (old path, new
+    /// path) are edited where they were moved to.
+    fn apply_workspace_edit(
+        &mut self,
+        workspace_edit: lsp_types::WorkspaceEdit,
+        renames: &[(PathBuf, PathBuf)],
+    ) -> io::Result<usize> {
+        let renamed_path = |path: PathBuf| {
+            for (from, to) in renames {
+                match path.strip_prefix(from) {
+                    Ok(relative) if relative.as_os_str().is_empty() => return to.clone(),
+                    Ok(relative) => return to.join(relative),
+                    Err(_) => {}
+                }
+            }
+            path
+        };
+
+        let mut total_changes = 0;
+
+        // Handle changes (map of URI -> Vec<TextEdit>)
+        if let Some(changes) = workspace_edit.changes {
+            for (uri, edits) in changes {
+                if let Ok(path) = uri_to_path(&uri) {
+                    let buffer_id = self.open_file(&renamed_path(path))?;
+                    total_changes += self.apply_lsp_text_edits(buffer_id, edits)?;
+                }
+            }
+        }
+
+        // Handle document_changes (TextDocumentEdit[])
+        // This is what rust-analyzer sends instead of changes
+        if let Some(document_changes) = workspace_edit.document_changes {
+            use lsp_types::DocumentChanges;
+
+            let text_edits = match document_changes {
+                DocumentChanges::Edits(edits) => edits,
+                DocumentChanges::Operations(ops) => {
+                    // Extract TextDocumentEdit from operations
+                    ops.into_iter()
+                        .filter_map(|op| {
+                            if let lsp_types::DocumentChangeOperation::Edit(edit) = op {
+                                Some(edit)
+                            } else {
+                                None
+                            }
+                        })
+                        .collect()
+                }
+            };
+
+            for text_doc_edit in text_edits {
+                let uri = text_doc_edit.text_document.uri;
+
+                if let Ok(path) = uri_to_path(&uri) {
+                    let path = renamed_path(path);
+                    let buffer_id = self.open_file(&path)?;
+
+                    // Extract TextEdit from OneOf<TextEdit, AnnotatedTextEdit>
+                    let edits: Vec<lsp_types::TextEdit> = text_doc_edit
+                        .edits
+                        .into_iter()
+                        .map(|one_of| match one_of {
+                            lsp_types::OneOf::Left(text_edit) => text_edit,
+                            lsp_types::OneOf::Right(annotated) => annotated.text_edit,
+                        })
+                        .collect();
+
+                    // Log the edits for debugging
+                    tracing::info!("Applying {} edits from LSP for {:?}:", edits.len(), path);
+                    for (i, edit) in edits.iter().enumerate() {
+                        tracing::info!(
+                            "  Edit {}: line {}:{}-{}:{} -> {:?}",
+                            i,
+                            edit.range.start.line,
+                            edit.range.start.character,
+                            edit.range.end.line,
+                            edit.range.end.character,
+                            edit.new_text
+                        );
+                    }
+
+                    total_changes += self.apply_lsp_text_edits(buffer_id, edits)?;
+                }
+            }
+        }
+
+        Ok(total_changes)
+    }
+
     /// Handle rename response from LSP
     pub fn handle_rename_response(
         &mut self,
@@ -7280,78 +7962,7 @@ impl Editor {
                     })
                 );
 
-                // Apply the workspace edit
-                let mut total_changes = 0;
-
-                // Handle changes (map of URI -> Vec<TextEdit>)
-                if let Some(changes) = workspace_edit.changes {
-                    for (uri, edits) in changes {
-                        if let Ok(path) = uri_to_path(&uri) {
-                            let buffer_id = self.open_file(&path)?;
-                            total_changes += self.apply_lsp_text_edits(buffer_id, edits)?;
-                        }
-                    }
-                }
-
-                // Handle document_changes (TextDocumentEdit[])
-                // This is what rust-analyzer sends instead of changes
-                if let Some(document_changes) = workspace_edit.document_changes {
-                    use lsp_types::DocumentChanges;
-
-                    let text_edits = match document_changes {
-                        DocumentChanges::Edits(edits) => edits,
-                        DocumentChanges::Operations(ops) => {
-                            // Extract TextDocumentEdit from operations
-                            ops.into_iter()
-                                .filter_map(|op| {
-                                    if let lsp_types::DocumentChangeOperation::Edit(edit) = op {
-                                        Some(edit)
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .collect()
-                        }
-                    };
-
-                    for text_doc_edit in text_edits {
-                        let uri = text_doc_edit.text_document.uri;
-
-                        if let Ok(path) = uri_to_path(&uri) {
-                            let buffer_id = self.open_file(&path)?;
-
-                            // Extract TextEdit from OneOf<TextEdit, AnnotatedTextEdit>
-                            let edits: Vec<lsp_types::TextEdit> = text_doc_edit
-                                .edits
-                                .into_iter()
-                                .map(|one_of| match one_of {
-                                    lsp_types::OneOf::Left(text_edit) => text_edit,
-                                    lsp_types::OneOf::Right(annotated) => annotated.text_edit,
-                                })
-                                .collect();
-
-                            // Log the edits for debugging
-                            tracing::info!(
-                                "Applying {} edits from rust-analyzer for {:?}:",
-                                edits.len(),
-                                path
-                            );
-                            for (i, edit) in edits.iter().enumerate() {
-                                tracing::info!(
-                                    "  Edit {}: line {}:{}-{}:{} -> {:?}",
-                                    i,
-                                    edit.range.start.line,
-                                    edit.range.start.character,
-                                    edit.range.end.line,
-                                    edit.range.end.character,
-                                    edit.new_text
-                                );
-                            }
-
-                            total_changes += self.apply_lsp_text_edits(buffer_id, edits)?;
-                        }
-                    }
-                }
+                let total_changes = self.apply_workspace_edit(workspace_edit, &[])?;
 
                 self.status_message =
                     Some(format!("Renamed successfully ({} changes)", total_changes));
@@ -7531,13 +8142,15 @@ impl Editor {
         );
 
         if let Some(lsp) = &mut self.lsp {
-            if let Some(client) = lsp.get_or_spawn(&language) {
-                if let Err(e) = client.did_change(uri, changes) {
-                    tracing::warn!("Failed to send didChange to LSP: {}", e);
+            let servers = lsp.get_or_spawn_all(&language);
+            for server in servers {
+                if let Err(e) = server.handle.did_change(uri.clone(), changes.clone()) {
+                    tracing::warn!("Failed to send didChange to LSP {}: {}", server.name, e);
                 } else {
-                    tracing::trace!("Successfully sent batched didChange to LSP");
+                    tracing::trace!("Successfully sent batched didChange to LSP {}", server.name);
                 }
-            } else {
+            }
+            if servers.is_empty() {
                 tracing::warn!(
                     "send_lsp_changes_for_buffer: failed to get or spawn LSP client for {}",
                     language
@@ -7646,7 +8259,7 @@ impl Editor {
             if let Some(language) = detect_language(path, &self.config.languages) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) = lsp.get_or_spawn_for(&language, LspFeature::Rename) {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.lsp_status = "LSP: rename...".to_string();
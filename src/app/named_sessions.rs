@@ -0,0 +1,268 @@
+//! Named sessions
+//!
+//! Besides the default session of the working directory, the user can save
+//! the editor state under a name ("review-pr-123", "feature-x", ...) and
+//! switch between these sessions. Switching restarts the editor the same way
+//! Switch Project does: the current session is saved, and a fresh editor
+//! restores the whole split layout, buffers, terminals and bookmarks of the
+//! other session.
+
+use super::*;
+use crate::session::{validate_session_name, Session, SessionError};
+
+impl Editor {
+    /// Report that named sessions are unavailable (returns false) when this
+    /// instance doesn't save its session
+    fn check_sessions_enabled(&mut self) -> bool {
+        if !self.session_enabled {
+            self.set_status_message(
+                "Sessions are disabled (started with a file or --no-session)".to_string(),
+            );
+        }
+        self.session_enabled
+    }
+
+    fn named_sessions(&mut self) -> Option<Vec<String>> {
+        match Session::list_named(&self.working_dir) {
+            Ok(names) => Some(names),
+            Err(e) => {
+                self.set_status_message(format!("Failed to list sessions: {}", e));
+                None
+            }
+        }
+    }
+
+    fn session_display_name(name: Option<&str>) -> String {
+        match name {
+            Some(name) => format!("'{}'", name),
+            None => "the default session".to_string(),
+        }
+    }
+
+    /// Open the Save Session As prompt
+    pub(super) fn start_save_session_as_prompt(&mut self) {
+        if !self.check_sessions_enabled() {
+            return;
+        }
+        self.start_prompt("Save session as: ".to_string(), PromptType::SaveSessionAs);
+    }
+
+    /// Save the current state as the session `name` and make it the active
+    /// session
+    pub(super) fn save_session_as(&mut self, name: &str) {
+        let name = name.trim();
+        if let Err(e) = validate_session_name(name) {
+            self.set_status_message(e.to_string());
+            return;
+        }
+
+        let previous = self.session_name.replace(name.to_string());
+        match self.save_session() {
+            Ok(()) => self.set_status_message(format!("Saved session '{}'", name)),
+            Err(e) => {
+                self.session_name = previous;
+                self.set_status_message(format!("Failed to save session: {}", e));
+            }
+        }
+    }
+
+    /// Open the Switch Session prompt listing the default session and the
+    /// named sessions of the working directory
+    pub(super) fn start_switch_session_prompt(&mut self) {
+        if !self.check_sessions_enabled() {
+            return;
+        }
+        let Some(names) = self.named_sessions() else {
+            return;
+        };
+
+        let current = self.session_name.clone();
+        let suggestion = |text: String, value: String, is_current: bool| Suggestion {
+            text,
+            description: is_current.then(|| "(current)".to_string()),
+            value: Some(value),
+            disabled: false,
+            keybinding: None,
+            source: None,
+        };
+        let mut suggestions = vec![suggestion(
+            "(default)".to_string(),
+            String::new(),
+            current.is_none(),
+        )];
+        suggestions.extend(names.into_iter().map(|name| {
+            let is_current = current.as_deref() == Some(name.as_str());
+            suggestion(name.clone(), name, is_current)
+        }));
+
+        self.prompt = Some(crate::view::prompt::Prompt::with_suggestions(
+            "Switch to session: ".to_string(),
+            PromptType::SwitchSession,
+            suggestions,
+        ));
+    }
+
+    /// Switch to the session `name` (the default session if empty)
+    pub(super) fn switch_session(&mut self, name: &str) {
+        let name = name.trim();
+        let target = if name.is_empty() {
+            None
+        } else {
+            let Some(names) = self.named_sessions() else {
+                return;
+            };
+            if !names.iter().any(|n| n == name) {
+                self.set_status_message(format!("No session named '{}'", name));
+                return;
+            }
+            Some(name.to_string())
+        };
+
+        if target == self.session_name {
+            self.set_status_message(format!(
+                "Already in {}",
+                Self::session_display_name(target.as_deref())
+            ));
+            return;
+        }
+        // Without hot exit, unsaved changes would be lost by the restart
+        if self.count_modified_buffers() > 0 && !self.hot_exit_enabled() {
+            self.set_status_message(
+                "Save or discard unsaved changes before switching sessions".to_string(),
+            );
+            return;
+        }
+
+        tracing::info!("Switching to session {:?}", target);
+        self.restart_with_session = Some(target);
+        self.request_restart(self.working_dir.clone());
+    }
+
+    /// Open the Rename Session prompt for the active named session
+    pub(super) fn start_rename_session_prompt(&mut self) {
+        if !self.check_sessions_enabled() {
+            return;
+        }
+        let Some(current) = self.session_name.clone() else {
+            self.set_status_message(
+                "The default session can't be renamed; use Save Session As".to_string(),
+            );
+            return;
+        };
+        self.start_prompt_with_initial_text(
+            "Rename session to: ".to_string(),
+            PromptType::RenameSession,
+            current,
+        );
+    }
+
+    /// Rename the active named session to `new_name`
+    pub(super) fn rename_session(&mut self, new_name: &str) {
+        let new_name = new_name.trim();
+        let Some(old_name) = self.session_name.clone() else {
+            return;
+        };
+        if new_name == old_name {
+            return;
+        }
+        if let Err(e) = validate_session_name(new_name) {
+            self.set_status_message(e.to_string());
+            return;
+        }
+        let Some(names) = self.named_sessions() else {
+            return;
+        };
+        if names.iter().any(|n| n == new_name) {
+            self.set_status_message(format!("A session named '{}' already exists", new_name));
+            return;
+        }
+
+        // Save under the new name first so a failure leaves the old session
+        // intact
+        self.session_name = Some(new_name.to_string());
+        if let Err(e) = self.save_session() {
+            self.session_name = Some(old_name);
+            self.set_status_message(format!("Failed to rename session: {}", e));
+            return;
+        }
+        match self.remove_named_session(&old_name, false) {
+            // The old session may never have been saved
+            Ok(()) | Err(SessionError::NotFound(_)) => {}
+            Err(e) => tracing::warn!("Failed to delete session '{}': {}", old_name, e),
+        }
+        self.set_status_message(format!("Renamed session '{}' to '{}'", old_name, new_name));
+    }
+
+    /// Open the Delete Session prompt listing the other named sessions
+    pub(super) fn start_delete_session_prompt(&mut self) {
+        if !self.check_sessions_enabled() {
+            return;
+        }
+        let Some(names) = self.named_sessions() else {
+            return;
+        };
+
+        let suggestions: Vec<Suggestion> = names
+            .into_iter()
+            .filter(|name| self.session_name.as_deref() != Some(name.as_str()))
+            .map(|name| Suggestion {
+                text: name.clone(),
+                description: None,
+                value: Some(name),
+                disabled: false,
+                keybinding: None,
+                source: None,
+            })
+            .collect();
+        if suggestions.is_empty() {
+            self.set_status_message("No other named sessions to delete".to_string());
+            return;
+        }
+
+        self.prompt = Some(crate::view::prompt::Prompt::with_suggestions(
+            "Delete session: ".to_string(),
+            PromptType::DeleteSession,
+            suggestions,
+        ));
+    }
+
+    /// Delete the named session `name`
+    pub(super) fn delete_session(&mut self, name: &str) {
+        let name = name.trim();
+        if self.session_name.as_deref() == Some(name) {
+            self.set_status_message(
+                "Can't delete the active session; switch to another one first".to_string(),
+            );
+            return;
+        }
+        match self.remove_named_session(name, true) {
+            Ok(()) => self.set_status_message(format!("Deleted session '{}'", name)),
+            Err(e) => self.set_status_message(format!("Failed to delete session: {}", e)),
+        }
+    }
+
+    /// Remove the session file of `name` along with its hot exit stashes, and
+    /// its terminal scrollback if `with_terminals` is set (a renamed session
+    /// keeps referring to the files of its running terminals)
+    fn remove_named_session(&self, name: &str, with_terminals: bool) -> Result<(), SessionError> {
+        let result = Session::delete_named(&self.working_dir, name);
+
+        let mut dirs = vec![self
+            .dir_context
+            .hot_exit_dir_for(&self.working_dir, Some(name))];
+        if with_terminals {
+            dirs.push(
+                self.dir_context
+                    .terminal_dir_for(&self.working_dir, Some(name)),
+            );
+        }
+        for dir in dirs {
+            if dir.exists() {
+                if let Err(e) = std::fs::remove_dir_all(&dir) {
+                    tracing::warn!("Failed to remove {}: {}", dir.display(), e);
+                }
+            }
+        }
+        result
+    }
+}
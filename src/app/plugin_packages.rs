@@ -0,0 +1,323 @@
+//! Plugin packages
+//!
+//! Commands installing, updating, removing, enabling, disabling and pinning
+//! plugin packages (see `services::plugins::packages`). Installs and updates
+//! run git off the main thread and report back through
+//! `AsyncMessage::PluginPackageFinished`; the plugins of a package are then
+//! (re)loaded sandboxed.
+//!
+//! Plugins may also declare settings in their manifest: opening the settings
+//! UI adds a page for each loaded plugin that does.
+
+use super::*;
+use crate::services::plugins::packages::{
+    PackageChange, PluginPackage, PluginRegistry, PluginSource,
+};
+use crate::view::settings::SettingsState;
+
+impl Editor {
+    fn plugin_packages(&self) -> PluginPackages {
+        PluginPackages::new(
+            self.dir_context.plugin_registry_path(),
+            self.dir_context.plugin_packages_dir(),
+        )
+    }
+
+    fn plugin_package_registry(&mut self) -> Option<PluginRegistry> {
+        match self.plugin_packages().registry() {
+            Ok(registry) => Some(registry),
+            Err(e) => {
+                self.set_status_message(format!("Failed to read the plugin registry: {}", e));
+                None
+            }
+        }
+    }
+
+    /// Run `task` off the main thread; it reports each package it is done
+    /// with through the callback it is given
+    fn spawn_plugin_package_task<F>(&mut self, task: F)
+    where
+        F: FnOnce(&PluginPackages, &dyn Fn(String, Result<PackageChange, String>)) + Send + 'static,
+    {
+        let (Some(runtime), Some(bridge)) = (&self.tokio_runtime, &self.async_bridge) else {
+            self.set_status_message("Async runtime not available".to_string());
+            return;
+        };
+        let packages = self.plugin_packages();
+        let sender = bridge.sender();
+        runtime.spawn_blocking(move || {
+            task(
+                &packages,
+                &|name: String, result: Result<PackageChange, String>| {
+                    let _ = sender.send(AsyncMessage::PluginPackageFinished { name, result });
+                },
+            );
+        });
+    }
+
+    /// Open the Install Plugin prompt
+    pub(super) fn start_install_plugin_package_prompt(&mut self) {
+        self.start_prompt(
+            "Install plugin (git URL[#ref] or directory): ".to_string(),
+            PromptType::InstallPluginPackage,
+        );
+    }
+
+    /// Install the package of `spec`, a git URL or a local directory
+    pub(super) fn install_plugin_package(&mut self, spec: &str) {
+        let spec = spec.trim().to_string();
+        if spec.is_empty() {
+            return;
+        }
+        self.set_status_message(format!("Installing plugin from {}...", spec));
+        self.spawn_plugin_package_task(move |packages, report| {
+            match packages.install(&spec, None) {
+                Ok(name) => report(name, Ok(PackageChange::Installed)),
+                Err(e) => report(spec, Err(e.to_string())),
+            }
+        });
+    }
+
+    /// Update all the installed packages
+    pub(super) fn update_plugin_packages(&mut self) {
+        let Some(registry) = self.plugin_package_registry() else {
+            return;
+        };
+        let names: Vec<String> = registry.packages.into_keys().collect();
+        if names.is_empty() {
+            self.set_status_message("No plugin packages installed".to_string());
+            return;
+        }
+
+        self.set_status_message(format!("Updating {} plugin package(s)...", names.len()));
+        self.spawn_plugin_package_task(move |packages, report| {
+            for name in names {
+                let result = packages.update(&name).map_err(|e| e.to_string());
+                report(name, result);
+            }
+        });
+    }
+
+    /// Handle the end of the install or update of a package
+    pub(super) fn handle_plugin_package_finished(
+        &mut self,
+        name: String,
+        result: Result<PackageChange, String>,
+    ) {
+        let message = match result {
+            Err(e) => format!("Plugin package {}: {}", name, e),
+            Ok(PackageChange::Unchanged) => format!("Plugin package '{}' is up to date", name),
+            Ok(PackageChange::Pinned) => {
+                format!("Plugin package '{}' is pinned, not updated", name)
+            }
+            Ok(change) => {
+                let done = match change {
+                    PackageChange::Updated(commit) => {
+                        format!("Updated plugin package '{}' to {:.7}", name, commit)
+                    }
+                    _ => format!("Installed plugin package '{}'", name),
+                };
+                match self.reload_plugin_package(&name) {
+                    Ok(()) => done,
+                    Err(e) => format!("{}, but it failed to load: {}", done, e),
+                }
+            }
+        };
+        self.set_status_message(message);
+    }
+
+    /// (Re)load the plugins of a package, if it is enabled
+    fn reload_plugin_package(&mut self, name: &str) -> Result<(), String> {
+        let packages = self.plugin_packages();
+        let registry = packages.registry().map_err(|e| e.to_string())?;
+        let package = registry
+            .packages
+            .get(name)
+            .ok_or_else(|| format!("No plugin package named '{}'", name))?;
+        let dir = packages.plugin_dir(name, package);
+
+        self.unload_plugins_in(&dir);
+        // The settings UI picks up the settings of the package's plugins
+        // when next opened
+        self.settings_state = None;
+        if !package.enabled {
+            return Ok(());
+        }
+        let errors = self.plugin_manager.load_plugins_from_dir(&dir, true);
+        for err in &errors {
+            tracing::error!("TypeScript plugin load error: {}", err);
+        }
+        match errors.into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Unload the plugins loaded from `dir`
+    fn unload_plugins_in(&mut self, dir: &Path) {
+        #[cfg(feature = "plugins")]
+        for plugin in self.plugin_manager.list_plugins() {
+            if plugin.path.parent() == Some(dir) {
+                if let Err(e) = self.plugin_manager.unload_plugin(&plugin.name) {
+                    tracing::warn!("Failed to unload plugin '{}': {}", plugin.name, e);
+                }
+            }
+        }
+        #[cfg(not(feature = "plugins"))]
+        let _ = dir;
+    }
+
+    /// Open a prompt selecting one of the packages the command applies to
+    pub(super) fn start_plugin_package_prompt(&mut self, prompt_type: PromptType) {
+        let (message, kind) = match prompt_type {
+            PromptType::RemovePluginPackage => ("Remove plugin: ", "installed"),
+            PromptType::EnablePluginPackage => ("Enable plugin: ", "disabled"),
+            PromptType::DisablePluginPackage => ("Disable plugin: ", "enabled"),
+            PromptType::PinPluginPackage => ("Pin plugin: ", "unpinned git"),
+            PromptType::UnpinPluginPackage => ("Unpin plugin: ", "pinned"),
+            _ => return,
+        };
+        let applies = |package: &PluginPackage| match prompt_type {
+            PromptType::EnablePluginPackage => !package.enabled,
+            PromptType::DisablePluginPackage => package.enabled,
+            PromptType::PinPluginPackage => {
+                !package.pinned && matches!(package.source, PluginSource::Git { .. })
+            }
+            PromptType::UnpinPluginPackage => package.pinned,
+            _ => true,
+        };
+        let Some(registry) = self.plugin_package_registry() else {
+            return;
+        };
+
+        let suggestions: Vec<Suggestion> = registry
+            .packages
+            .iter()
+            .filter(|(_, package)| applies(package))
+            .map(|(name, package)| Suggestion {
+                text: name.clone(),
+                description: Some(describe_plugin_package(package)),
+                value: Some(name.clone()),
+                disabled: false,
+                keybinding: None,
+                source: None,
+            })
+            .collect();
+        if suggestions.is_empty() {
+            self.set_status_message(format!("No {} plugin packages", kind));
+            return;
+        }
+
+        self.prompt = Some(crate::view::prompt::Prompt::with_suggestions(
+            message.to_string(),
+            prompt_type,
+            suggestions,
+        ));
+    }
+
+    /// Uninstall the package `name`
+    pub(super) fn remove_plugin_package(&mut self, name: &str) {
+        let name = name.trim();
+        let packages = self.plugin_packages();
+        let Some(registry) = self.plugin_package_registry() else {
+            return;
+        };
+        let Some(package) = registry.packages.get(name) else {
+            self.set_status_message(format!("No plugin package named '{}'", name));
+            return;
+        };
+
+        self.unload_plugins_in(&packages.plugin_dir(name, package));
+        self.settings_state = None;
+        match packages.remove(name) {
+            Ok(()) => self.set_status_message(format!("Removed plugin package '{}'", name)),
+            Err(e) => self.set_status_message(format!("Failed to remove plugin package: {}", e)),
+        }
+    }
+
+    /// Enable or disable the package `name`, loading or unloading its plugins
+    pub(super) fn set_plugin_package_enabled(&mut self, name: &str, enabled: bool) {
+        let name = name.trim();
+        if let Err(e) = self.plugin_packages().set_enabled(name, enabled) {
+            self.set_status_message(e.to_string());
+            return;
+        }
+        let done = if enabled { "Enabled" } else { "Disabled" };
+        let message = match self.reload_plugin_package(name) {
+            Ok(()) => format!("{} plugin package '{}'", done, name),
+            Err(e) => format!(
+                "{} plugin package '{}', but it failed to load: {}",
+                done, name, e
+            ),
+        };
+        self.set_status_message(message);
+    }
+
+    /// Pin the package `name` at its current commit
+    pub(super) fn pin_plugin_package(&mut self, name: &str) {
+        let name = name.trim();
+        match self.plugin_packages().pin(name, None) {
+            Ok(commit) => self
+                .set_status_message(format!("Pinned plugin package '{}' at {:.7}", name, commit)),
+            Err(e) => self.set_status_message(format!("Failed to pin plugin package: {}", e)),
+        }
+    }
+
+    /// Let updates move the package `name` again
+    pub(super) fn unpin_plugin_package(&mut self, name: &str) {
+        let name = name.trim();
+        match self.plugin_packages().unpin(name) {
+            Ok(()) => self.set_status_message(format!("Unpinned plugin package '{}'", name)),
+            Err(e) => self.set_status_message(e.to_string()),
+        }
+    }
+
+    /// Add a settings page for each loaded plugin declaring settings in its
+    /// manifest
+    pub(super) fn add_plugin_settings_pages(&self, state: &mut SettingsState) {
+        #[cfg(feature = "plugins")]
+        {
+            use crate::services::plugins::permissions::PluginManifest;
+
+            let mut plugins = self.plugin_manager.list_plugins();
+            plugins.sort_by(|a, b| a.name.cmp(&b.name));
+            for plugin in plugins {
+                let schema = match PluginManifest::load(&plugin.path) {
+                    Ok(manifest) => manifest.settings,
+                    Err(e) => {
+                        tracing::warn!("Failed to read the manifest of '{}': {}", plugin.name, e);
+                        None
+                    }
+                };
+                if let Some(schema) = schema {
+                    if let Err(e) = state.add_plugin_settings(&plugin.name, &schema) {
+                        tracing::warn!("Invalid settings schema of '{}': {}", plugin.name, e);
+                    }
+                }
+            }
+        }
+        #[cfg(not(feature = "plugins"))]
+        let _ = state;
+    }
+}
+
+/// Source and state of a package, shown next to its name in prompts
+fn describe_plugin_package(package: &PluginPackage) -> String {
+    let mut description = match &package.source {
+        PluginSource::Git {
+            url,
+            rev: Some(rev),
+        } => format!("{}#{}", url, rev),
+        PluginSource::Git { url, rev: None } => url.clone(),
+        PluginSource::Path { path } => path.display().to_string(),
+    };
+    if package.pinned {
+        if let Some(commit) = &package.commit {
+            description.push_str(&format!(" (pinned at {:.7})", commit));
+        }
+    }
+    if !package.enabled {
+        description.push_str(" (disabled)");
+    }
+    description
+}
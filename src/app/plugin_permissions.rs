@@ -0,0 +1,126 @@
+//! Plugin permission prompts
+//!
+//! A sandboxed plugin using a capability its manifest declares, but that the
+//! user hasn't granted yet, asks through `RequestPluginPermission`. Requests
+//! are queued and shown one at a time in a prompt once no other prompt is
+//! open. Allowing stores the grant in the config (`plugin_permissions`);
+//! denying, or dismissing the prompt, refuses the capability until the editor
+//! restarts.
+
+use super::*;
+use crate::services::plugins::api::PluginResponse;
+
+impl Editor {
+    /// Answer a plugin's permission request right away if the user already
+    /// decided, otherwise queue it for a prompt
+    pub(super) fn handle_request_plugin_permission(&mut self, request: PluginPermissionRequest) {
+        let granted = self
+            .config
+            .plugin_permissions
+            .get(&request.plugin)
+            .is_some_and(|grants| grants.get(request.kind));
+        if granted {
+            self.respond_plugin_permission(&request, true);
+        } else if self
+            .denied_plugin_permissions
+            .contains(&(request.plugin.clone(), request.kind))
+        {
+            self.respond_plugin_permission(&request, false);
+        } else {
+            self.pending_plugin_permissions.push_back(request);
+        }
+    }
+
+    /// Deny the shown request if its prompt was dismissed, then show the next
+    /// queued request when no prompt is open. Returns true if the screen
+    /// needs a redraw.
+    pub(super) fn process_plugin_permission_prompts(&mut self) -> bool {
+        let prompt_shown = self
+            .prompt
+            .as_ref()
+            .is_some_and(|prompt| prompt.prompt_type == PromptType::PluginPermission);
+        let dismissed = self.active_plugin_permission.is_some() && !prompt_shown;
+        if dismissed {
+            self.answer_plugin_permission(false);
+        }
+
+        if self.active_plugin_permission.is_some() || self.prompt.is_some() {
+            return dismissed;
+        }
+        let Some(request) = self.pending_plugin_permissions.pop_front() else {
+            return dismissed;
+        };
+
+        let what = if request.target.is_empty() {
+            request.kind.description().to_string()
+        } else {
+            format!("{} ({})", request.kind.description(), request.target)
+        };
+        let suggestion = |text: &str, value: &str, description: &str| Suggestion {
+            text: text.to_string(),
+            description: Some(description.to_string()),
+            value: Some(value.to_string()),
+            disabled: false,
+            keybinding: None,
+            source: None,
+        };
+        self.prompt = Some(crate::view::prompt::Prompt::with_suggestions(
+            format!("Plugin '{}' wants to {}: ", request.plugin, what),
+            PromptType::PluginPermission,
+            vec![
+                suggestion("Allow", "allow", "Remember for this plugin"),
+                suggestion("Deny", "deny", "Until the editor restarts"),
+            ],
+        ));
+        self.active_plugin_permission = Some(request);
+        true
+    }
+
+    /// Answer the shown permission request, along with the queued requests
+    /// of the same plugin for the same capability
+    pub(super) fn answer_plugin_permission(&mut self, granted: bool) {
+        let Some(request) = self.active_plugin_permission.take() else {
+            return;
+        };
+        let key = (request.plugin.clone(), request.kind);
+
+        if granted {
+            self.config
+                .plugin_permissions
+                .entry(request.plugin.clone())
+                .or_default()
+                .set(request.kind, true);
+            match self.save_config() {
+                Ok(()) => self.set_status_message(format!(
+                    "Allowed plugin '{}' to {}",
+                    request.plugin,
+                    request.kind.description()
+                )),
+                // The grant still holds for this session
+                Err(e) => self.set_status_message(e),
+            }
+        } else {
+            self.denied_plugin_permissions.insert(key.clone());
+            self.set_status_message(format!(
+                "Denied plugin '{}' permission to {}",
+                request.plugin,
+                request.kind.description()
+            ));
+        }
+
+        let (same, other): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending_plugin_permissions)
+            .into_iter()
+            .partition(|pending| pending.plugin == key.0 && pending.kind == key.1);
+        self.pending_plugin_permissions = other.into();
+        for pending in std::iter::once(&request).chain(&same) {
+            self.respond_plugin_permission(pending, granted);
+        }
+    }
+
+    fn respond_plugin_permission(&self, request: &PluginPermissionRequest, granted: bool) {
+        self.send_plugin_response(PluginResponse::PluginPermission {
+            request_id: request.request_id,
+            granted,
+        });
+    }
+}
@@ -0,0 +1,641 @@
+//! Project-wide search and replace panel
+//!
+//! "Search in Project" and "Replace in Project" prompt for a pattern (and a
+//! replacement), using the same case / whole word / regex toggles as buffer
+//! search, and search every file in the working directory on a background
+//! thread (see [`crate::services::project_search`]). Results are listed in a
+//! panel below the active split, grouped by file, with a preview of each
+//! replaced line.
+//!
+//! In the panel, Enter opens the match under the cursor, `r` replaces the
+//! match (or all matches of the file) under the cursor and `R` replaces every
+//! match. Replacements are applied to buffers as one undoable edit per file,
+//! opening files that aren't open yet; nothing is saved automatically. `i`
+//! and `x` set include and exclude globs, `g` searches again and `q` closes
+//! the panel.
+
+use super::*;
+use crate::primitives::text_property::TextPropertyEntry;
+use crate::services::project_search::{FileMatches, ProjectSearch, ProjectSearchQuery};
+
+/// Display name of the project search panel buffer
+const PROJECT_SEARCH_BUFFER_NAME: &str = "*Project Search*";
+
+impl Editor {
+    /// Prompt for a pattern to search the project for, and a replacement if
+    /// `replace` is set
+    pub(crate) fn start_project_search_prompt(&mut self, replace: bool) {
+        let selected_text = {
+            let state = self.active_state_mut();
+            state
+                .cursors
+                .primary()
+                .selection_range()
+                .map(|range| state.get_text_range(range.start, range.end))
+                .filter(|text| !text.is_empty() && !text.contains('\n'))
+        };
+        let from_history = selected_text.is_none();
+        let default_text =
+            selected_text.or_else(|| self.search_history.last().map(|s| s.to_string()));
+
+        let (message, prompt_type) = if replace {
+            ("Replace in project: ", PromptType::ProjectReplaceSearch)
+        } else {
+            ("Search in project: ", PromptType::ProjectSearch)
+        };
+        self.start_prompt(message.to_string(), prompt_type);
+
+        if let Some(text) = default_text {
+            if let Some(prompt) = self.prompt.as_mut() {
+                prompt.set_input(text.clone());
+                prompt.selection_anchor = Some(0);
+                prompt.cursor_pos = text.len();
+            }
+            if from_history {
+                self.search_history.init_at_last();
+            }
+        }
+    }
+
+    /// Search the project for `pattern` with the current search options and
+    /// show the results in the project search panel
+    pub(crate) fn run_project_search(&mut self, pattern: String, replacement: Option<String>) {
+        let query = ProjectSearchQuery {
+            pattern,
+            case_sensitive: self.search_case_sensitive,
+            whole_word: self.search_whole_word,
+            use_regex: self.search_use_regex,
+            replacement,
+            include: self.project_search_include.clone(),
+            exclude: self.project_search_exclude.clone(),
+        };
+        self.start_project_search(query);
+    }
+
+    /// Run `query`, replacing the results shown in the panel
+    fn start_project_search(&mut self, query: ProjectSearchQuery) {
+        if query.pattern.is_empty() {
+            return;
+        }
+        if let Err(e) = query.regex() {
+            self.set_status_message(format!("Invalid regex: {}", e));
+            return;
+        }
+        let (Some(runtime), Some(bridge)) = (&self.tokio_runtime, &self.async_bridge) else {
+            self.set_status_message("Async runtime not available".to_string());
+            return;
+        };
+
+        // Unsaved edits in open buffers are searched instead of the files on disk
+        let open_buffers = self
+            .buffers
+            .values()
+            .filter_map(|state| {
+                let path = state.buffer.file_path()?.to_path_buf();
+                Some((path, state.buffer.to_string()?))
+            })
+            .collect();
+
+        let search = ProjectSearch::spawn(
+            runtime,
+            bridge.sender(),
+            self.working_dir.clone(),
+            query.clone(),
+            open_buffers,
+            self.config.editor.large_file_threshold_bytes,
+        );
+
+        self.open_project_search_panel();
+        if let Some(panel) = self.project_search_panel.as_mut() {
+            panel.query = query;
+            panel.files.clear();
+            panel.search = Some(search);
+            panel.truncated = false;
+            panel.error = None;
+        }
+        self.refresh_project_search_panel(true);
+        self.set_status_message(
+            "Searching project... Enter open, r replace, R replace all, q close".to_string(),
+        );
+    }
+
+    /// Open the project search panel in a split below the active split, or
+    /// focus it if it's already open
+    fn open_project_search_panel(&mut self) {
+        if let Some(panel) = &self.project_search_panel {
+            let (split_id, buffer_id) = (panel.split_id, panel.buffer_id);
+            self.focus_split(split_id, buffer_id);
+            return;
+        }
+
+        let source_split = self.split_manager.active_split();
+        let buffer_id = self.create_virtual_buffer(
+            PROJECT_SEARCH_BUFFER_NAME.to_string(),
+            "project-search".to_string(),
+            true,
+        );
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            state.margins.set_line_numbers(false);
+            state.editing_disabled = true;
+        }
+
+        // Keep the panel out of the source split's tabs
+        if let Some(view_state) = self.split_view_states.get_mut(&source_split) {
+            view_state.remove_buffer(buffer_id);
+        }
+
+        self.save_current_split_view_state();
+        let split_id =
+            match self
+                .split_manager
+                .split_active(SplitDirection::Horizontal, buffer_id, 0.6)
+            {
+                Ok(split_id) => {
+                    let mut view_state = SplitViewState::with_buffer(
+                        self.terminal_width,
+                        self.terminal_height,
+                        buffer_id,
+                    );
+                    view_state.viewport.line_wrap_enabled = false;
+                    self.split_view_states.insert(split_id, view_state);
+                    split_id
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to split for project search: {}", e);
+                    self.set_active_buffer(buffer_id);
+                    source_split
+                }
+            };
+
+        self.project_search_panel = Some(ProjectSearchPanel {
+            buffer_id,
+            split_id,
+            source_split,
+            query: ProjectSearchQuery::default(),
+            files: Vec::new(),
+            search: None,
+            truncated: false,
+            error: None,
+        });
+    }
+
+    /// Add matches found by the running search to the panel
+    pub(super) fn handle_project_search_progress(
+        &mut self,
+        search_id: u64,
+        files: Vec<FileMatches>,
+    ) {
+        let Some(panel) = self.project_search_panel.as_mut() else {
+            return;
+        };
+        if panel.search.as_ref().map(|s| s.id()) != Some(search_id) {
+            return;
+        }
+        panel.files.extend(files);
+        self.refresh_project_search_panel(false);
+    }
+
+    /// Mark the running search as finished
+    pub(super) fn handle_project_search_finished(
+        &mut self,
+        search_id: u64,
+        truncated: bool,
+        error: Option<String>,
+    ) {
+        let Some(panel) = self.project_search_panel.as_mut() else {
+            return;
+        };
+        if panel.search.as_ref().map(|s| s.id()) != Some(search_id) {
+            return;
+        }
+        panel.search = None;
+        panel.truncated = truncated;
+        let message = match &error {
+            Some(error) => format!("Project search failed: {}", error),
+            None => {
+                let matches: usize = panel.files.iter().map(|f| f.matches.len()).sum();
+                format!(
+                    "Found {} match{} in {} file{}",
+                    matches,
+                    if matches == 1 { "" } else { "es" },
+                    panel.files.len(),
+                    if panel.files.len() == 1 { "" } else { "s" },
+                )
+            }
+        };
+        panel.error = error;
+        self.refresh_project_search_panel(false);
+        self.set_status_message(message);
+    }
+
+    /// Run a project search panel command
+    pub(super) fn handle_project_search_command(&mut self, command: &str) {
+        if self.project_search_panel.is_none() {
+            return;
+        }
+        match command {
+            "open" => self.open_selected_project_match(),
+            "replace" => {
+                if let Some((file, index)) = self.selected_project_match() {
+                    self.replace_project_matches(&[(file, index)]);
+                }
+            }
+            "replace-all" => {
+                let count = self
+                    .project_search_panel
+                    .as_ref()
+                    .map_or(0, |panel| panel.files.len());
+                let targets: Vec<_> = (0..count).map(|file| (file, None)).collect();
+                self.replace_project_matches(&targets);
+            }
+            "include" | "exclude" => {
+                let (message, globs, prompt_type) = if command == "include" {
+                    (
+                        "Files to include (globs, comma separated): ",
+                        &self.project_search_include,
+                        PromptType::ProjectSearchInclude,
+                    )
+                } else {
+                    (
+                        "Files to exclude (globs, comma separated): ",
+                        &self.project_search_exclude,
+                        PromptType::ProjectSearchExclude,
+                    )
+                };
+                let current = globs.join(", ");
+                self.start_prompt(message.to_string(), prompt_type);
+                if let Some(prompt) = self.prompt.as_mut() {
+                    prompt.set_input(current);
+                }
+            }
+            "refresh" => self.rerun_project_search(),
+            "close" => self.close_project_search(),
+            _ => {}
+        }
+    }
+
+    /// Set the include or exclude globs from a comma-separated list and search again
+    pub(crate) fn set_project_search_globs(&mut self, input: &str, include: bool) {
+        let globs: Vec<String> = input
+            .split(',')
+            .map(str::trim)
+            .filter(|glob| !glob.is_empty())
+            .map(str::to_string)
+            .collect();
+        if include {
+            self.project_search_include = globs;
+        } else {
+            self.project_search_exclude = globs;
+        }
+        self.rerun_project_search();
+    }
+
+    /// Search again for the panel's query with the current globs
+    fn rerun_project_search(&mut self) {
+        let Some(panel) = &self.project_search_panel else {
+            return;
+        };
+        let query = ProjectSearchQuery {
+            include: self.project_search_include.clone(),
+            exclude: self.project_search_exclude.clone(),
+            ..panel.query.clone()
+        };
+        self.start_project_search(query);
+    }
+
+    /// The file and (unless a file header is selected) match on the panel
+    /// line under the cursor
+    fn selected_project_match(&self) -> Option<(usize, Option<usize>)> {
+        let panel = self.project_search_panel.as_ref()?;
+        let state = self.buffers.get(&panel.buffer_id)?;
+        let position = state.cursors.primary().position;
+        let properties = state.text_properties.get_at(position);
+        let file = properties
+            .iter()
+            .find_map(|property| property.get_as::<usize>("file"))?;
+        let index = properties
+            .iter()
+            .find_map(|property| property.get_as::<usize>("match"));
+        Some((file, index))
+    }
+
+    /// Open the file of the selected match in the source split, with the
+    /// cursor on the match
+    fn open_selected_project_match(&mut self) {
+        let Some((file, index)) = self.selected_project_match() else {
+            return;
+        };
+        let Some(panel) = &self.project_search_panel else {
+            return;
+        };
+        let Some(file_matches) = panel.files.get(file) else {
+            return;
+        };
+        let path = file_matches.path.clone();
+        let position = file_matches.matches[index.unwrap_or(0)].range.start;
+        let source_split = panel.source_split;
+
+        if let Some(buffer_id) = self.split_manager.get_buffer_id(source_split) {
+            self.focus_split(source_split, buffer_id);
+        }
+        let buffer_id = match self.open_file(&path) {
+            Ok(buffer_id) => buffer_id,
+            Err(e) => {
+                self.set_status_message(format!("Failed to open {}: {}", path.display(), e));
+                return;
+            }
+        };
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            let position = state
+                .buffer
+                .snap_to_char_boundary(position.min(state.buffer.len()));
+            let cursor = state.cursors.primary();
+            let event = Event::MoveCursor {
+                cursor_id: state.cursors.primary_id(),
+                old_position: cursor.position,
+                new_position: position,
+                old_anchor: cursor.anchor,
+                new_anchor: None,
+                old_sticky_column: cursor.sticky_column,
+                new_sticky_column: 0,
+            };
+            state.apply(&event);
+        }
+    }
+
+    /// Replace the given matches (`None` meaning every match in the file),
+    /// as one undoable edit per file
+    fn replace_project_matches(&mut self, targets: &[(usize, Option<usize>)]) {
+        let Some(panel) = &self.project_search_panel else {
+            return;
+        };
+        if panel.query.replacement.is_none() {
+            self.set_status_message(
+                "No replacement text - use Replace in Project to set one".to_string(),
+            );
+            return;
+        }
+        if panel.search.is_some() {
+            self.set_status_message("Wait for the search to finish before replacing".to_string());
+            return;
+        }
+
+        let mut replaced = 0;
+        let mut files = 0;
+        let mut skipped = 0;
+        let mut failed = Vec::new();
+        // Go through files from the last so removing emptied ones keeps the
+        // other indices valid
+        let mut targets = targets.to_vec();
+        targets.sort_by(|a, b| b.cmp(a));
+        for (file, index) in targets {
+            match self.replace_in_project_file(file, index) {
+                Ok((done, stale)) => {
+                    replaced += done;
+                    skipped += stale;
+                    files += (done > 0) as usize;
+                }
+                Err(e) => failed.push(e.to_string()),
+            }
+        }
+
+        self.refresh_project_search_panel(false);
+        let mut message = format!(
+            "Replaced {} match{} in {} file{}",
+            replaced,
+            if replaced == 1 { "" } else { "es" },
+            files,
+            if files == 1 { "" } else { "s" },
+        );
+        if skipped > 0 {
+            message.push_str(&format!(", skipped {} changed since the search", skipped));
+        }
+        if let Some(error) = failed.first() {
+            message.push_str(&format!(" (failed: {})", error));
+        }
+        self.set_status_message(message);
+    }
+
+    /// Replace one match (or all of them) in a result file's buffer, opening
+    /// the file if needed, and drop the handled matches from the results
+    ///
+    /// Returns the number of matches replaced and the number skipped because
+    /// the text at their position no longer matches.
+    fn replace_in_project_file(
+        &mut self,
+        file: usize,
+        index: Option<usize>,
+    ) -> io::Result<(usize, usize)> {
+        let Some(file_matches) = self
+            .project_search_panel
+            .as_ref()
+            .and_then(|panel| panel.files.get(file))
+        else {
+            return Ok((0, 0));
+        };
+        let path = file_matches.path.clone();
+        let selected: Vec<_> = match index {
+            Some(index) => file_matches
+                .matches
+                .get(index)
+                .cloned()
+                .into_iter()
+                .collect(),
+            None => file_matches.matches.clone(),
+        };
+
+        let buffer_id = self.open_file_no_focus(&path)?;
+        let state = self
+            .buffers
+            .get_mut(&buffer_id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Buffer not found"))?;
+        if state.editing_disabled {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{} is read-only", path.display()),
+            ));
+        }
+
+        // Replace from the end so earlier offsets stay valid
+        let cursor_id = state.cursors.primary_id();
+        let buffer_len = state.buffer.len();
+        let mut events = Vec::new();
+        let mut applied = Vec::new();
+        for found in selected.iter().rev() {
+            let range = found.range.clone();
+            if range.end > buffer_len || state.get_text_range(range.start, range.end) != found.text
+            {
+                continue;
+            }
+            applied.push(found);
+            if !range.is_empty() {
+                events.push(Event::Delete {
+                    range: range.clone(),
+                    deleted_text: found.text.clone(),
+                    cursor_id,
+                });
+            }
+            let replacement = found.replacement.clone().unwrap_or_default();
+            if !replacement.is_empty() {
+                events.push(Event::Insert {
+                    position: range.start,
+                    text: replacement,
+                    cursor_id,
+                });
+            }
+        }
+        let replaced = applied.len();
+        let stale = selected.len() - replaced;
+        if !events.is_empty() {
+            let batch = Event::Batch {
+                events,
+                description: "Replace in Project".to_string(),
+            };
+            self.apply_rename_batch_to_buffer(buffer_id, batch)?;
+        }
+
+        // Drop the handled matches and move the remaining ones past the edits
+        if let Some(panel) = self.project_search_panel.as_mut() {
+            let file_matches = &mut panel.files[file];
+            let handled: Vec<_> = selected.iter().map(|m| m.range.start).collect();
+            let mut remaining = Vec::new();
+            for mut found in file_matches.matches.drain(..) {
+                if handled.contains(&found.range.start) {
+                    continue;
+                }
+                // Only the replacements actually made move the text
+                let shift: isize = applied
+                    .iter()
+                    .filter(|m| m.range.end <= found.range.start)
+                    .map(|m| {
+                        m.replacement.as_ref().map_or(0, |r| r.len()) as isize
+                            - m.text.len() as isize
+                    })
+                    .sum();
+                found.range = (found.range.start as isize + shift) as usize
+                    ..(found.range.end as isize + shift) as usize;
+                remaining.push(found);
+            }
+            file_matches.matches = remaining;
+            if file_matches.matches.is_empty() {
+                panel.files.remove(file);
+            }
+        }
+        Ok((replaced, stale))
+    }
+
+    /// Close the project search panel, cancelling a running search
+    fn close_project_search(&mut self) {
+        let Some(panel) = self.project_search_panel.take() else {
+            return;
+        };
+        if panel.split_id != panel.source_split {
+            self.handle_close_split(panel.split_id);
+        }
+        if let Err(e) = self.close_buffer(panel.buffer_id) {
+            tracing::warn!("Failed to close project search panel: {}", e);
+        }
+    }
+
+    /// Rebuild the panel's content from the results, moving the cursor to the
+    /// first match if `reset_cursor` is set
+    fn refresh_project_search_panel(&mut self, reset_cursor: bool) {
+        let Some(panel) = &self.project_search_panel else {
+            return;
+        };
+        let query = &panel.query;
+
+        let mut options = Vec::new();
+        if query.case_sensitive {
+            options.push("case");
+        }
+        if query.whole_word {
+            options.push("word");
+        }
+        if query.use_regex {
+            options.push("regex");
+        }
+        let mut header = format!("Search: {:?}", query.pattern);
+        if let Some(replacement) = &query.replacement {
+            header.push_str(&format!("  Replace: {:?}", replacement));
+        }
+        if !options.is_empty() {
+            header.push_str(&format!("  [{}]", options.join(", ")));
+        }
+        let mut entries = vec![TextPropertyEntry::text(format!("{}\n", header))];
+        if !query.include.is_empty() || !query.exclude.is_empty() {
+            entries.push(TextPropertyEntry::text(format!(
+                "Include: {}  Exclude: {}\n",
+                query.include.join(", "),
+                query.exclude.join(", ")
+            )));
+        }
+
+        let matches: usize = panel.files.iter().map(|f| f.matches.len()).sum();
+        let status = if let Some(error) = &panel.error {
+            format!("Error: {}", error)
+        } else {
+            let mut status = format!(
+                "{} match{} in {} file{}",
+                matches,
+                if matches == 1 { "" } else { "es" },
+                panel.files.len(),
+                if panel.files.len() == 1 { "" } else { "s" },
+            );
+            if panel.search.is_some() {
+                status.push_str(" (searching...)");
+            } else if panel.truncated {
+                status.push_str(" (stopped at the result limit)");
+            }
+            status
+        };
+        entries.push(TextPropertyEntry::text(format!("{}\n", status)));
+
+        let header_len: usize = entries.iter().map(|e| e.text.len()).sum();
+        let mut offset = header_len;
+        let mut first_match = None;
+        for (file, file_matches) in panel.files.iter().enumerate() {
+            let display_path = file_matches
+                .path
+                .strip_prefix(&self.working_dir)
+                .unwrap_or(&file_matches.path);
+            let line = format!("\n{}\n", display_path.display());
+            offset += line.len();
+            entries.push(
+                TextPropertyEntry::text(line).with_property("file", serde_json::Value::from(file)),
+            );
+
+            for (index, found) in file_matches.matches.iter().enumerate() {
+                first_match.get_or_insert(offset);
+                let location = format!("{}:{}", found.line + 1, found.column + 1);
+                let mut line = format!("  {:>9}  {}\n", location, found.context);
+                if let Some(replaced) = found.replaced_context() {
+                    line.push_str(&format!("  {:>9}  {}\n", "->", replaced));
+                }
+                offset += line.len();
+                entries.push(
+                    TextPropertyEntry::text(line)
+                        .with_property("file", serde_json::Value::from(file))
+                        .with_property("match", serde_json::Value::from(index)),
+                );
+            }
+        }
+
+        let (buffer_id, split_id) = (panel.buffer_id, panel.split_id);
+        if let Err(e) = self.set_virtual_buffer_content(buffer_id, entries) {
+            tracing::warn!("Failed to update project search panel: {}", e);
+            return;
+        }
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            // Keep the cursor where it was, unless it's still in the header
+            // when the first results come in
+            let cursor = &mut state.cursors.primary_mut().position;
+            if reset_cursor || *cursor < header_len {
+                *cursor = first_match.unwrap_or(0);
+            }
+            if let Some(view_state) = self.split_view_states.get_mut(&split_id) {
+                view_state.cursors = state.cursors.clone();
+            }
+        }
+    }
+}
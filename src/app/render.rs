@@ -9,6 +9,9 @@ impl Editor {
         // NOTE: Viewport sync with cursor is handled by split_rendering.rs which knows the
         // correct content area dimensions. Don't sync here with incorrect EditorState viewport size.
 
+        // Expand folds that a cursor moved into since the last frame
+        self.reveal_folded_cursors();
+
         // Prepare all buffers for rendering (pre-load viewport data for lazy loading)
         // Each split may have a different viewport position on the same buffer
         for (split_id, view_state) in &self.split_view_states {
@@ -40,6 +43,9 @@ impl Editor {
                     | PromptType::Replace { .. }
                     | PromptType::QueryReplaceSearch
                     | PromptType::QueryReplace { .. }
+                    | PromptType::ProjectSearch
+                    | PromptType::ProjectReplaceSearch
+                    | PromptType::ProjectReplace { .. }
             )
         });
 
@@ -167,6 +173,11 @@ impl Editor {
                     .get(&split_id)
                     .map(|vs| vs.viewport.top_byte)
                     .unwrap_or(0);
+                let hidden_ranges = self
+                    .split_view_states
+                    .get(&split_id)
+                    .map(|vs| vs.hidden_ranges(buffer_id))
+                    .unwrap_or_default();
 
                 if let Some(state) = self.buffers.get_mut(&buffer_id) {
                     // Fire render_start hook once per buffer
@@ -186,6 +197,7 @@ impl Editor {
                             self.config.editor.estimated_line_length,
                             visible_count,
                             is_binary,
+                            &hidden_ranges,
                         );
                     let viewport_start = viewport_top_byte;
                     let viewport_end = base_tokens
@@ -409,7 +421,13 @@ impl Editor {
         let display_name = self
             .buffer_metadata
             .get(&self.active_buffer())
-            .map(|m| m.display_name.clone())
+            .map(|m| {
+                if m.orphaned {
+                    format!("{} [deleted]", m.display_name)
+                } else {
+                    m.display_name.clone()
+                }
+            })
             .unwrap_or_else(|| "[No Name]".to_string());
         let status_message = self.status_message.clone();
         let plugin_status_message = self.plugin_status_message.clone();
@@ -1012,15 +1030,22 @@ impl Editor {
         // Store the pending confirmation
         self.pending_lsp_confirmation = Some(language.to_string());
 
-        // Get the server command for display
-        let server_info = if let Some(lsp) = &self.lsp {
-            if let Some(config) = lsp.get_config(language) {
-                format!("{} ({})", language, config.command)
-            } else {
-                language.to_string()
-            }
-        } else {
+        // Get the server commands for display
+        let commands: Vec<&str> = self
+            .lsp
+            .as_ref()
+            .map(|lsp| {
+                lsp.get_configs(language)
+                    .iter()
+                    .filter(|config| config.enabled)
+                    .map(|config| config.command.as_str())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let server_info = if commands.is_empty() {
             language.to_string()
+        } else {
+            format!("{} ({})", language, commands.join(", "))
         };
 
         let popup = PopupData {
@@ -1200,45 +1225,63 @@ impl Editor {
 
         // Send didOpen to LSP
         if let Some(lsp) = &mut self.lsp {
-            if let Some(client) = lsp.get_or_spawn(language) {
-                tracing::info!("Sending didOpen to newly started LSP for: {}", uri.as_str());
-                if let Err(e) = client.did_open(uri.clone(), text, file_language) {
-                    tracing::warn!("Failed to send didOpen to LSP: {}", e);
-                } else {
-                    tracing::info!("Successfully sent didOpen to LSP after confirmation");
+            for server in lsp.get_or_spawn_all(language) {
+                tracing::info!(
+                    "Sending didOpen to newly started LSP {} for: {}",
+                    server.name,
+                    uri.as_str()
+                );
+                if let Err(e) =
+                    server
+                        .handle
+                        .did_open(uri.clone(), text.clone(), file_language.clone())
+                {
+                    tracing::warn!("Failed to send didOpen to LSP {}: {}", server.name, e);
+                    continue;
+                }
+                tracing::info!("Successfully sent didOpen to LSP after confirmation");
+
+                // Request pull diagnostics
+                if server.provides(LspFeature::PullDiagnostics) == Some(false) {
+                    continue;
+                }
+                let previous_result_id = self
+                    .diagnostic_result_ids
+                    .get(&(uri.as_str().to_string(), server.name.clone()))
+                    .cloned();
+                let request_id = self.next_lsp_request_id;
+                self.next_lsp_request_id += 1;
+
+                if let Err(e) =
+                    server
+                        .handle
+                        .document_diagnostic(request_id, uri.clone(), previous_result_id)
+                {
+                    tracing::debug!(
+                        "Failed to request pull diagnostics (server may not support): {}",
+                        e
+                    );
+                }
+            }
 
-                    // Request pull diagnostics
-                    let previous_result_id = self.diagnostic_result_ids.get(uri.as_str()).cloned();
+            // Request inlay hints if enabled
+            if self.config.editor.enable_inlay_hints {
+                if let Some(client) = lsp.get_or_spawn_for(language, LspFeature::InlayHints) {
                     let request_id = self.next_lsp_request_id;
                     self.next_lsp_request_id += 1;
+                    self.pending_inlay_hints_request = Some(request_id);
+
+                    let last_line = line_count.saturating_sub(1) as u32;
+                    let last_char = 10000u32;
 
                     if let Err(e) =
-                        client.document_diagnostic(request_id, uri.clone(), previous_result_id)
+                        client.inlay_hints(request_id, uri.clone(), 0, 0, last_line, last_char)
                     {
                         tracing::debug!(
-                            "Failed to request pull diagnostics (server may not support): {}",
+                            "Failed to request inlay hints (server may not support): {}",
                             e
                         );
-                    }
-
-                    // Request inlay hints if enabled
-                    if self.config.editor.enable_inlay_hints {
-                        let request_id = self.next_lsp_request_id;
-                        self.next_lsp_request_id += 1;
-                        self.pending_inlay_hints_request = Some(request_id);
-
-                        let last_line = line_count.saturating_sub(1) as u32;
-                        let last_char = 10000u32;
-
-                        if let Err(e) =
-                            client.inlay_hints(request_id, uri.clone(), 0, 0, last_line, last_char)
-                        {
-                            tracing::debug!(
-                                "Failed to request inlay hints (server may not support): {}",
-                                e
-                            );
-                            self.pending_inlay_hints_request = None;
-                        }
+                        self.pending_inlay_hints_request = None;
                     }
                 }
             }
@@ -1508,14 +1551,16 @@ impl Editor {
         );
 
         if let Some(lsp) = &mut self.lsp {
-            if let Some(client) = lsp.get_or_spawn(&language) {
+            let servers = lsp.get_or_spawn_all(&language);
+            for server in servers {
                 // Send didSave with the full text content
-                if let Err(e) = client.did_save(uri, Some(full_text)) {
-                    tracing::warn!("Failed to send didSave to LSP: {}", e);
+                if let Err(e) = server.handle.did_save(uri.clone(), Some(full_text.clone())) {
+                    tracing::warn!("Failed to send didSave to LSP {}: {}", server.name, e);
                 } else {
-                    tracing::info!("Successfully sent didSave to LSP");
+                    tracing::info!("Successfully sent didSave to LSP {}", server.name);
                 }
-            } else {
+            }
+            if servers.is_empty() {
                 tracing::warn!(
                     "notify_lsp_save: failed to get or spawn LSP client for {}",
                     language
@@ -1541,14 +1586,27 @@ impl Editor {
             .map(|vs| vs.viewport.height)
             .unwrap_or(24);
 
-        convert_action_to_events(
+        let vertical = matches!(
+            action,
+            Action::MoveUp
+                | Action::MoveDown
+                | Action::SelectUp
+                | Action::SelectDown
+                | Action::MovePageUp
+                | Action::MovePageDown
+                | Action::SelectPageUp
+                | Action::SelectPageDown
+        );
+        let mut events = convert_action_to_events(
             self.active_state_mut(),
             action,
             tab_size,
             auto_indent,
             estimated_line_length,
             viewport_height,
-        )
+        )?;
+        self.skip_folded_lines(&mut events, vertical);
+        Some(events)
     }
 
     // === Search and Replace Methods ===
@@ -1561,6 +1619,7 @@ impl Editor {
 
         // Also clear search state
         self.search_state = None;
+        self.streaming_search = None;
     }
 
     /// Update search highlights in visible viewport only (for incremental search)
@@ -1681,29 +1740,14 @@ impl Editor {
 
         let search_range = self.pending_search_range.take();
 
-        let buffer_content = {
-            let state = self.active_state();
-            match state.buffer.to_string() {
-                Some(t) => t,
-                None => {
-                    self.set_status_message("Buffer not fully loaded".to_string());
-                    return;
-                }
-            }
-        };
+        // A new search supersedes any streaming search still in flight
+        self.streaming_search = None;
 
         // Get search settings
         let case_sensitive = self.search_case_sensitive;
         let whole_word = self.search_whole_word;
         let use_regex = self.search_use_regex;
 
-        // Determine search boundaries
-        let (search_start, search_end) = if let Some(ref range) = search_range {
-            (range.start, range.end)
-        } else {
-            (0, buffer_content.len())
-        };
-
         // Build regex pattern
         let regex_pattern = if use_regex {
             if whole_word {
@@ -1733,6 +1777,22 @@ impl Editor {
             }
         };
 
+        // Large files are only partially loaded; search those in the background
+        let buffer_content = match self.active_state().buffer.to_string() {
+            Some(t) => t,
+            None => {
+                self.start_streaming_search(query, &regex_pattern, search_range);
+                return;
+            }
+        };
+
+        // Determine search boundaries
+        let (search_start, search_end) = if let Some(ref range) = search_range {
+            (range.start, range.end)
+        } else {
+            (0, buffer_content.len())
+        };
+
         // Find all matches within the search range
         let search_slice = &buffer_content[search_start..search_end];
         let matches: Vec<usize> = regex
@@ -1787,6 +1847,7 @@ impl Editor {
             current_match_index: Some(current_match_index),
             wrap_search: search_range.is_none(), // Only wrap if not searching in selection
             search_range,
+            origin: cursor_pos,
         });
 
         let msg = if self.search_state.as_ref().unwrap().search_range.is_some() {
@@ -1807,6 +1868,171 @@ impl Editor {
         self.set_status_message(msg);
     }
 
+    /// Start a chunked background search of a buffer that isn't fully loaded
+    ///
+    /// Matches are delivered through `AsyncMessage::SearchProgress` and merged
+    /// into `search_state` as they arrive (see `handle_search_progress`).
+    fn start_streaming_search(
+        &mut self,
+        query: &str,
+        regex_pattern: &str,
+        search_range: Option<Range<usize>>,
+    ) {
+        let regex = match regex::bytes::RegexBuilder::new(regex_pattern)
+            .case_insensitive(!self.search_case_sensitive)
+            .build()
+        {
+            Ok(r) => r,
+            Err(e) => {
+                self.search_state = None;
+                self.set_status_message(format!("Invalid regex: {}", e));
+                return;
+            }
+        };
+
+        let (Some(runtime), Some(bridge)) = (&self.tokio_runtime, &self.async_bridge) else {
+            self.set_status_message("Async runtime not available".to_string());
+            return;
+        };
+
+        let state = self.active_state();
+        let range = search_range.clone().unwrap_or(0..state.buffer.len());
+        let regions = state.buffer.content_regions(range.clone());
+        let origin = state.cursors.primary().position;
+
+        self.streaming_search = Some(crate::services::streaming_search::StreamingSearch::spawn(
+            runtime,
+            bridge.sender(),
+            Arc::clone(state.buffer.fs()),
+            regions,
+            range.start,
+            regex,
+        ));
+
+        self.search_state = Some(SearchState {
+            query: query.to_string(),
+            matches: Vec::new(),
+            current_match_index: None,
+            wrap_search: search_range.is_none(),
+            search_range,
+            origin,
+        });
+        self.set_status_message(format!("Searching for '{}'...", query));
+    }
+
+    /// Merge a batch of matches from the streaming search into the search state
+    pub(super) fn handle_search_progress(
+        &mut self,
+        search_id: u64,
+        matches: Vec<usize>,
+        bytes_searched: usize,
+        total_bytes: usize,
+    ) {
+        if self.streaming_search.as_ref().map(|s| s.id()) != Some(search_id) {
+            return;
+        }
+        let Some(search_state) = self.search_state.as_mut() else {
+            return;
+        };
+
+        search_state.matches.extend(matches);
+
+        // Jump to the first match at or after the cursor as soon as we have it
+        let mut jump_to = None;
+        if search_state.current_match_index.is_none() {
+            if let Some(index) = search_state
+                .matches
+                .iter()
+                .position(|&pos| pos >= search_state.origin)
+            {
+                search_state.current_match_index = Some(index);
+                jump_to = Some(search_state.matches[index]);
+            }
+        }
+
+        let percent = (bytes_searched * 100)
+            .checked_div(total_bytes)
+            .unwrap_or(100);
+        let msg = format!(
+            "Searching for '{}'... {}% ({} match{})",
+            search_state.query,
+            percent,
+            search_state.matches.len(),
+            if search_state.matches.len() == 1 {
+                ""
+            } else {
+                "es"
+            }
+        );
+
+        if let Some(match_pos) = jump_to {
+            self.move_cursor_to_search_match(match_pos);
+        }
+        self.set_status_message(msg);
+    }
+
+    /// Finalize the search state once the streaming search has completed
+    pub(super) fn handle_search_finished(&mut self, search_id: u64, error: Option<String>) {
+        if self.streaming_search.as_ref().map(|s| s.id()) != Some(search_id) {
+            return;
+        }
+        self.streaming_search = None;
+
+        if let Some(e) = error {
+            self.search_state = None;
+            self.set_status_message(format!("Search failed: {}", e));
+            return;
+        }
+        let Some(search_state) = self.search_state.as_mut() else {
+            return;
+        };
+
+        let num_matches = search_state.matches.len();
+        let query = search_state.query.clone();
+        let in_selection = search_state.search_range.is_some();
+
+        if num_matches == 0 {
+            self.search_state = None;
+            let msg = if in_selection {
+                format!("No matches found for '{}' in selection", query)
+            } else {
+                format!("No matches found for '{}'", query)
+            };
+            self.set_status_message(msg);
+            return;
+        }
+
+        // No match after the cursor: wrap around to the first one
+        if search_state.current_match_index.is_none() {
+            search_state.current_match_index = Some(0);
+            let match_pos = search_state.matches[0];
+            self.move_cursor_to_search_match(match_pos);
+        }
+
+        self.set_status_message(format!(
+            "Found {} match{} for '{}'{}",
+            num_matches,
+            if num_matches == 1 { "" } else { "es" },
+            query,
+            if in_selection { " in selection" } else { "" }
+        ));
+    }
+
+    /// Move the primary cursor to a search match and scroll it into view
+    fn move_cursor_to_search_match(&mut self, match_pos: usize) {
+        let active_split = self.split_manager.active_split();
+        let active_buffer = self.active_buffer();
+        let state = self.active_state_mut();
+        state.cursors.primary_mut().position = match_pos;
+        state.cursors.primary_mut().anchor = None;
+        if let Some(view_state) = self.split_view_states.get_mut(&active_split) {
+            let state = self.buffers.get_mut(&active_buffer).unwrap();
+            view_state
+                .viewport
+                .ensure_visible(&mut state.buffer, state.cursors.primary());
+        }
+    }
+
     /// Find the next match
     pub(super) fn find_next(&mut self) {
         if let Some(ref mut search_state) = self.search_state {
@@ -2572,152 +2798,6 @@ impl Editor {
         self.set_status_message(format!("Dedented {} line(s)", lines_dedented));
     }
 
-    /// Toggle comment on the current line or selection
-    pub(super) fn toggle_comment(&mut self) {
-        // Determine comment prefix based on file extension
-        let comment_prefix = if let Some(metadata) = self.buffer_metadata.get(&self.active_buffer())
-        {
-            if let Some(path) = metadata.file_path() {
-                match path.extension().and_then(|e| e.to_str()) {
-                    Some("rs") | Some("c") | Some("cpp") | Some("h") | Some("hpp") | Some("js")
-                    | Some("ts") | Some("jsx") | Some("tsx") | Some("java") | Some("go")
-                    | Some("swift") | Some("kt") | Some("scala") => "// ",
-                    Some("py") | Some("rb") | Some("sh") | Some("bash") | Some("zsh")
-                    | Some("pl") | Some("r") | Some("yml") | Some("yaml") | Some("toml") => "# ",
-                    Some("lua") | Some("sql") => "-- ",
-                    Some("html") | Some("xml") => "<!-- ",
-                    Some("css") | Some("scss") | Some("sass") => "/* ",
-                    Some("vim") => "\" ",
-                    Some("lisp") | Some("el") | Some("clj") => ";; ",
-                    _ => "// ",
-                }
-            } else {
-                "// "
-            }
-        } else {
-            "// "
-        };
-
-        let estimated_line_length = self.config.editor.estimated_line_length;
-
-        let state = self.active_state_mut();
-        let cursor = state.cursors.primary().clone();
-        let cursor_id = state.cursors.primary_id();
-
-        let (start_pos, end_pos) = if let Some(range) = cursor.selection_range() {
-            (range.start, range.end)
-        } else {
-            let iter = state
-                .buffer
-                .line_iterator(cursor.position, estimated_line_length);
-            let line_start = iter.current_position();
-            (line_start, cursor.position)
-        };
-
-        // Find all line starts in the range
-        let buffer_len = state.buffer.len();
-        let mut line_starts = Vec::new();
-        let mut iter = state.buffer.line_iterator(start_pos, estimated_line_length);
-        let mut current_pos = iter.current_position();
-        line_starts.push(current_pos);
-
-        loop {
-            if let Some((_, content)) = iter.next() {
-                current_pos += content.len();
-                if current_pos > end_pos || current_pos > buffer_len {
-                    break;
-                }
-                let next_iter = state
-                    .buffer
-                    .line_iterator(current_pos, estimated_line_length);
-                let next_start = next_iter.current_position();
-                if next_start != *line_starts.last().unwrap() {
-                    line_starts.push(next_start);
-                }
-                iter = state
-                    .buffer
-                    .line_iterator(current_pos, estimated_line_length);
-            } else {
-                break;
-            }
-        }
-
-        // Determine if we should comment or uncomment
-        // If all lines are commented, uncomment; otherwise comment
-        let all_commented = line_starts.iter().all(|&line_start| {
-            let line_bytes = state
-                .buffer
-                .slice_bytes(line_start..buffer_len.min(line_start + comment_prefix.len() + 10));
-            let line_str = String::from_utf8_lossy(&line_bytes);
-            let trimmed = line_str.trim_start();
-            trimmed.starts_with(comment_prefix.trim())
-        });
-
-        let mut events = Vec::new();
-
-        if all_commented {
-            // Uncomment: remove comment prefix from each line
-            for &line_start in line_starts.iter().rev() {
-                let line_bytes = state
-                    .buffer
-                    .slice_bytes(line_start..buffer_len.min(line_start + 100));
-                let line_str = String::from_utf8_lossy(&line_bytes);
-
-                // Find where the comment prefix starts (after leading whitespace)
-                let leading_ws: usize = line_str
-                    .chars()
-                    .take_while(|c| c.is_whitespace() && *c != '\n')
-                    .map(|c| c.len_utf8())
-                    .sum();
-                let rest = &line_str[leading_ws..];
-
-                if rest.starts_with(comment_prefix.trim()) {
-                    let remove_len = if rest.starts_with(comment_prefix) {
-                        comment_prefix.len()
-                    } else {
-                        comment_prefix.trim().len()
-                    };
-                    let deleted_text = String::from_utf8_lossy(&state.buffer.slice_bytes(
-                        line_start + leading_ws..line_start + leading_ws + remove_len,
-                    ))
-                    .to_string();
-                    events.push(Event::Delete {
-                        range: (line_start + leading_ws)..(line_start + leading_ws + remove_len),
-                        deleted_text,
-                        cursor_id,
-                    });
-                }
-            }
-        } else {
-            // Comment: add comment prefix to each line
-            for &line_start in line_starts.iter().rev() {
-                events.push(Event::Insert {
-                    position: line_start,
-                    text: comment_prefix.to_string(),
-                    cursor_id,
-                });
-            }
-        }
-
-        if events.is_empty() {
-            return;
-        }
-
-        let action_desc = if all_commented {
-            "Uncomment"
-        } else {
-            "Comment"
-        };
-        let batch = Event::Batch {
-            events,
-            description: format!("{} lines", action_desc),
-        };
-
-        self.active_event_log_mut().append(batch.clone());
-        self.apply_event_to_active_buffer(&batch);
-        self.set_status_message(format!("{}ed {} line(s)", action_desc, line_starts.len()));
-    }
-
     /// Go to matching bracket
     pub(super) fn goto_matching_bracket(&mut self) {
         let state = self.active_state_mut();
@@ -2985,7 +3065,16 @@ impl Editor {
         if let Some(state) = self.macro_recording.take() {
             let action_count = state.actions.len();
             let key = state.key;
-            self.macros.insert(key, state.actions);
+            // Re-recording a register keeps the name it was given
+            let name = self.macros.get(key).and_then(|m| m.name.clone());
+            self.macros.insert(
+                key,
+                Macro {
+                    name,
+                    actions: state.actions,
+                },
+            );
+            self.save_macros();
             self.last_macro_register = Some(key);
             self.set_status_message(format!("Macro '{}' saved ({} actions)", key, action_count));
         } else {
@@ -2995,7 +3084,7 @@ impl Editor {
 
     /// Play back a recorded macro
     pub(super) fn play_macro(&mut self, key: char) {
-        if let Some(actions) = self.macros.get(&key).cloned() {
+        if let Some(actions) = self.macros.get(key).map(|m| m.actions.clone()) {
             if actions.is_empty() {
                 self.set_status_message(format!("Macro '{}' is empty", key));
                 return;
@@ -3026,11 +3115,13 @@ impl Editor {
                 Action::StartMacroRecording
                 | Action::StopMacroRecording
                 | Action::PlayMacro(_)
+                | Action::PlayNamedMacro(_)
                 | Action::ToggleMacroRecording(_)
                 | Action::ShowMacro(_)
                 | Action::ListMacros
                 | Action::PromptRecordMacro
                 | Action::PromptPlayMacro
+                | Action::PromptShowMacro
                 | Action::PlayLastMacro => {}
                 _ => {
                     state.actions.push(action.clone());
@@ -3039,34 +3130,26 @@ impl Editor {
         }
     }
 
-    /// Show a macro in a buffer as JSON
+    /// Show a macro in a buffer as editable JSON
+    ///
+    /// Saving the buffer parses it and updates the macro (see
+    /// [`Editor::save_macro_buffer`]).
     pub(super) fn show_macro_in_buffer(&mut self, key: char) {
         // Get macro data and cache what we need before any mutable borrows
-        let (json, actions_len) = match self.macros.get(&key) {
-            Some(actions) => {
-                let json = match serde_json::to_string_pretty(actions) {
-                    Ok(json) => json,
-                    Err(e) => {
-                        self.set_status_message(format!("Failed to serialize macro: {}", e));
-                        return;
-                    }
-                };
-                (json, actions.len())
-            }
+        let (content, actions_len) = match self.macros.get(key) {
+            Some(mac) => match mac.to_definition(key) {
+                Ok(content) => (content, mac.actions.len()),
+                Err(e) => {
+                    self.set_status_message(format!("Failed to serialize macro: {}", e));
+                    return;
+                }
+            },
             None => {
                 self.set_status_message(format!("No macro recorded for '{}'", key));
                 return;
             }
         };
 
-        // Create header with macro info
-        let content = format!(
-            "// Macro '{}' ({} actions)\n// This buffer can be saved as a .json file for persistence\n\n{}",
-            key,
-            actions_len,
-            json
-        );
-
         // Create a new buffer for the macro
         let buffer_id = BufferId(self.next_buffer_id);
         self.next_buffer_id += 1;
@@ -3098,17 +3181,73 @@ impl Editor {
             lsp_disabled_reason: Some("Virtual macro buffer".to_string()),
             read_only: false, // Allow editing for saving
             binary: false,
+            orphaned: false,
         };
         self.buffer_metadata.insert(buffer_id, metadata);
+        self.macro_edit_buffers.insert(buffer_id, key);
 
         // Switch to the new buffer
         self.set_active_buffer(buffer_id);
         self.set_status_message(format!(
-            "Macro '{}' shown in buffer ({} actions) - save as .json for persistence",
+            "Macro '{}' shown in buffer ({} actions) - save to update it",
             key, actions_len
         ));
     }
 
+    /// Parse the macro definition in a buffer opened by
+    /// [`Editor::show_macro_in_buffer`] and store it back in its register
+    pub(super) fn save_macro_buffer(&mut self, buffer_id: BufferId, key: char) {
+        let Some(text) = self
+            .buffers
+            .get(&buffer_id)
+            .and_then(|state| state.buffer.to_string())
+        else {
+            return;
+        };
+        let mac = match Macro::parse_definition(&text) {
+            Ok(mac) => mac,
+            Err(e) => {
+                self.set_status_message(format!("Invalid macro definition: {}", e));
+                return;
+            }
+        };
+        if let Some(other) = mac
+            .name
+            .as_deref()
+            .and_then(|name| self.macros.find_by_name(name))
+            .filter(|other| *other != key)
+        {
+            self.set_status_message(format!(
+                "Macro name '{}' is already used by macro '{}'",
+                mac.name.as_deref().unwrap_or_default(),
+                other
+            ));
+            return;
+        }
+
+        let action_count = mac.actions.len();
+        self.macros.insert(key, mac);
+        self.save_macros();
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            state.buffer.clear_modified();
+        }
+        if let Some(event_log) = self.event_logs.get_mut(&buffer_id) {
+            event_log.mark_saved();
+        }
+        self.set_status_message(format!(
+            "Macro '{}' updated ({} actions)",
+            key, action_count
+        ));
+    }
+
+    /// Persist all macros to the data directory
+    pub(super) fn save_macros(&self) {
+        let path = self.dir_context.macros_path();
+        if let Err(e) = self.macros.save_to_file(&path) {
+            tracing::warn!("Failed to save macros: {}", e);
+        }
+    }
+
     /// List all recorded macros in a buffer
     pub(super) fn list_macros_in_buffer(&mut self) {
         if self.macros.is_empty() {
@@ -3118,24 +3257,30 @@ impl Editor {
 
         // Build a summary of all macros
         let mut content =
-            String::from("// Recorded Macros\n// Use ShowMacro(key) to see details\n\n");
+            String::from("// Recorded Macros\n// Use Show Macro to see and edit details\n\n");
 
-        let mut keys: Vec<char> = self.macros.keys().copied().collect();
-        keys.sort();
+        for (key, mac) in self.macros.iter() {
+            let actions = &mac.actions;
+            let name = mac
+                .name
+                .as_ref()
+                .map(|name| format!(" ({})", name))
+                .unwrap_or_default();
+            content.push_str(&format!(
+                "Macro '{}'{}: {} actions\n",
+                key,
+                name,
+                actions.len()
+            ));
 
-        for key in keys {
-            if let Some(actions) = self.macros.get(&key) {
-                content.push_str(&format!("Macro '{}': {} actions\n", key, actions.len()));
-
-                // Show first few actions as preview
-                for (i, action) in actions.iter().take(5).enumerate() {
-                    content.push_str(&format!("  {}. {:?}\n", i + 1, action));
-                }
-                if actions.len() > 5 {
-                    content.push_str(&format!("  ... and {} more actions\n", actions.len() - 5));
-                }
-                content.push('\n');
+            // Show first few actions as preview
+            for (i, action) in actions.iter().take(5).enumerate() {
+                content.push_str(&format!("  {}. {:?}\n", i + 1, action));
+            }
+            if actions.len() > 5 {
+                content.push_str(&format!("  ... and {} more actions\n", actions.len() - 5));
             }
+            content.push('\n');
         }
 
         // Create a new buffer for the macro list
@@ -3169,6 +3314,7 @@ impl Editor {
             lsp_disabled_reason: Some("Virtual macro list buffer".to_string()),
             read_only: true,
             binary: false,
+            orphaned: false,
         };
         self.buffer_metadata.insert(buffer_id, metadata);
 
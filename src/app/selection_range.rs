@@ -0,0 +1,235 @@
+//! Structural expand and shrink selection
+//!
+//! Expand Selection grows every cursor's selection to the next enclosing
+//! range, taken from `textDocument/selectionRange` when the buffer's language
+//! server supports it and from the tree-sitter parse of the buffer otherwise;
+//! see [`crate::primitives::selection_range`]. Buffers with neither keep the
+//! word-by-word expansion. Each structural expansion is pushed on the cursor's
+//! stack in [`EditorState::selection_history`], which Shrink Selection pops.
+
+use super::*;
+use crate::model::cursor::Cursor;
+use crate::primitives::selection_range::{
+    next_enclosing_range, syntax_selection_ranges, SelectionStep,
+};
+use std::ops::Range;
+
+impl Editor {
+    /// Expand the selection of every cursor in the active buffer
+    pub(crate) fn expand_selection(&mut self) {
+        if let Some(pending) = self.request_lsp_selection_ranges() {
+            self.pending_selection_range_request = Some(pending);
+            return;
+        }
+        self.apply_expand_selection(&HashMap::new());
+    }
+
+    /// Restore the selection of every cursor from before its last expansion
+    pub(crate) fn shrink_selection(&mut self) {
+        let state = self.active_state_mut();
+        let cursors: Vec<(CursorId, Cursor)> = state
+            .cursors
+            .iter()
+            .map(|(id, cursor)| (id, *cursor))
+            .collect();
+
+        let mut events = Vec::new();
+        for (cursor_id, cursor) in cursors {
+            let Some(history) = state.selection_history.get_mut(&cursor_id) else {
+                continue;
+            };
+            // The stack only applies while the cursor still has the selection
+            // its last expansion produced
+            let step = match history.last() {
+                Some(step) if cursor.selection_range() == Some(step.expanded.clone()) => {
+                    history.pop()
+                }
+                _ => {
+                    history.clear();
+                    None
+                }
+            };
+            if let Some(step) = step {
+                events.push(Event::MoveCursor {
+                    cursor_id,
+                    old_position: cursor.position,
+                    new_position: step.position,
+                    old_anchor: cursor.anchor,
+                    new_anchor: step.anchor,
+                    old_sticky_column: cursor.sticky_column,
+                    new_sticky_column: 0,
+                });
+            }
+        }
+        self.apply_selection_events(events, "Shrink selection");
+    }
+
+    /// Handle LSP selection range response
+    pub(super) fn handle_lsp_selection_ranges(
+        &mut self,
+        request_id: u64,
+        uri: String,
+        ranges: Vec<lsp_types::SelectionRange>,
+    ) {
+        let cursor_ids = match self.pending_selection_range_request.take() {
+            Some((pending_id, cursor_ids)) if pending_id == request_id => cursor_ids,
+            pending => {
+                tracing::debug!(
+                    "Ignoring stale selection range response (request_id={})",
+                    request_id
+                );
+                self.pending_selection_range_request = pending;
+                return;
+            }
+        };
+
+        // The expansion applies to the buffer it was requested for
+        if self.find_buffer_by_uri(&uri) != Some(self.active_buffer()) {
+            return;
+        }
+
+        let buffer = &self.active_state().buffer;
+        let to_bytes = |range: &lsp_types::Range| {
+            buffer.lsp_position_to_byte(range.start.line as usize, range.start.character as usize)
+                ..buffer.lsp_position_to_byte(range.end.line as usize, range.end.character as usize)
+        };
+        // One response per position: a range and its chain of parents
+        let lsp_ranges: HashMap<CursorId, Vec<Range<usize>>> = cursor_ids
+            .into_iter()
+            .zip(&ranges)
+            .map(|(cursor_id, selection_range)| {
+                let mut chain = Vec::new();
+                let mut current = Some(selection_range);
+                while let Some(range) = current {
+                    chain.push(to_bytes(&range.range));
+                    current = range.parent.as_deref();
+                }
+                (cursor_id, chain)
+            })
+            .collect();
+        tracing::debug!("Received selection ranges for {} cursors", lsp_ranges.len());
+        self.apply_expand_selection(&lsp_ranges);
+    }
+
+    /// Expand each cursor with its LSP ranges, or with the tree-sitter parse
+    fn apply_expand_selection(&mut self, lsp_ranges: &HashMap<CursorId, Vec<Range<usize>>>) {
+        let language = self.active_state().highlighter.language().copied();
+        if language.is_none() && lsp_ranges.is_empty() {
+            if let Some(events) = self.action_to_events(Action::ExpandSelection) {
+                self.apply_selection_events(events, "Expand selection");
+            }
+            return;
+        }
+
+        let state = self.active_state_mut();
+        let source = language.and_then(|_| state.buffer.to_string());
+        let cursors: Vec<(CursorId, Cursor)> = state
+            .cursors
+            .iter()
+            .map(|(id, cursor)| (id, *cursor))
+            .collect();
+        state
+            .selection_history
+            .retain(|cursor_id, _| cursors.iter().any(|(id, _)| id == cursor_id));
+
+        let mut events = Vec::new();
+        for (cursor_id, cursor) in cursors {
+            let selection = cursor
+                .selection_range()
+                .unwrap_or(cursor.position..cursor.position);
+            let next = lsp_ranges
+                .get(&cursor_id)
+                .and_then(|ranges| next_enclosing_range(ranges, &selection))
+                .or_else(|| {
+                    let ranges = syntax_selection_ranges(
+                        language.as_ref()?,
+                        source.as_ref()?,
+                        selection.clone(),
+                    )?;
+                    next_enclosing_range(&ranges, &selection)
+                });
+            let Some(next) = next else {
+                continue;
+            };
+
+            // Selecting something else starts a new chain of expansions
+            let history = state.selection_history.entry(cursor_id).or_default();
+            if history
+                .last()
+                .is_some_and(|step| step.expanded != selection)
+            {
+                history.clear();
+            }
+            history.push(SelectionStep {
+                position: cursor.position,
+                anchor: cursor.anchor,
+                expanded: next.clone(),
+            });
+
+            events.push(Event::MoveCursor {
+                cursor_id,
+                old_position: cursor.position,
+                new_position: next.end,
+                old_anchor: cursor.anchor,
+                new_anchor: Some(next.start),
+                old_sticky_column: cursor.sticky_column,
+                new_sticky_column: 0,
+            });
+        }
+        self.apply_selection_events(events, "Expand selection");
+    }
+
+    fn apply_selection_events(&mut self, mut events: Vec<Event>, description: &str) {
+        let event = match events.len() {
+            0 => return,
+            1 => events.remove(0),
+            _ => Event::Batch {
+                events,
+                description: description.to_string(),
+            },
+        };
+        self.active_event_log_mut().append(event.clone());
+        self.apply_event_to_active_buffer(&event);
+    }
+
+    /// Send `textDocument/selectionRange` for the cursors of the active buffer
+    /// if its server is ready; returns the request ID and the cursors in the
+    /// order of the requested positions
+    fn request_lsp_selection_ranges(&mut self) -> Option<(u64, Vec<CursorId>)> {
+        let metadata = self.buffer_metadata.get(&self.active_buffer())?;
+        let uri = metadata.file_uri()?.clone();
+        let language = detect_language(metadata.file_path()?, &self.config.languages)?;
+
+        let state = self.active_state();
+        let (cursor_ids, positions): (Vec<CursorId>, Vec<lsp_types::Position>) = state
+            .cursors
+            .iter()
+            .map(|(cursor_id, cursor)| {
+                let start = cursor
+                    .selection_range()
+                    .map_or(cursor.position, |range| range.start);
+                let (line, character) = state.buffer.position_to_lsp_position(start);
+                (
+                    cursor_id,
+                    lsp_types::Position::new(line as u32, character as u32),
+                )
+            })
+            .unzip();
+
+        let lsp = self.lsp.as_mut()?;
+        if !lsp.is_server_ready(&language) {
+            return None;
+        }
+        let client = lsp.get_or_spawn_for(&language, LspFeature::SelectionRanges)?;
+        if !client.is_initialized() {
+            return None;
+        }
+        let request_id = self.next_lsp_request_id;
+        if let Err(e) = client.selection_ranges(request_id, uri, positions) {
+            tracing::debug!("Failed to request selection ranges: {}", e);
+            return None;
+        }
+        self.next_lsp_request_id += 1;
+        Some((request_id, cursor_ids))
+    }
+}
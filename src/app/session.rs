@@ -32,11 +32,12 @@ use crate::model::event::{BufferId, SplitDirection, SplitId};
 use crate::services::terminal::TerminalId;
 use crate::session::{
     FileExplorerState, SearchOptions, SerializedBookmark, SerializedCursor, SerializedFileState,
-    SerializedScroll, SerializedSplitDirection, SerializedSplitNode, SerializedSplitViewState,
-    SerializedTabRef, SerializedTerminalSession, SerializedViewMode, Session,
-    SessionConfigOverrides, SessionError, SessionHistories, SESSION_VERSION,
+    SerializedFold, SerializedScroll, SerializedSplitDirection, SerializedSplitNode,
+    SerializedSplitViewState, SerializedTabRef, SerializedTerminalSession, SerializedViewMode,
+    Session, SessionConfigOverrides, SessionError, SessionHistories, SESSION_VERSION,
 };
 use crate::state::ViewMode;
+use crate::view::folding::Fold;
 use crate::view::split::{SplitNode, SplitViewState};
 
 use super::types::Bookmark;
@@ -122,7 +123,9 @@ impl Editor {
                     .get(&terminal_id)
                     .cloned()
                     .unwrap_or_else(|| {
-                        let root = self.dir_context.terminal_dir_for(&self.working_dir);
+                        let root = self
+                            .dir_context
+                            .terminal_dir_for(&self.working_dir, self.session_name.as_deref());
                         root.join(format!("fresh-terminal-{}.log", terminal_id.0))
                     });
                 let backing_path = self
@@ -130,7 +133,9 @@ impl Editor {
                     .get(&terminal_id)
                     .cloned()
                     .unwrap_or_else(|| {
-                        let root = self.dir_context.terminal_dir_for(&self.working_dir);
+                        let root = self
+                            .dir_context
+                            .terminal_dir_for(&self.working_dir, self.session_name.as_deref());
                         root.join(format!("fresh-terminal-{}.txt", terminal_id.0))
                     });
 
@@ -250,6 +255,7 @@ impl Editor {
         Session {
             version: SESSION_VERSION,
             working_dir: self.working_dir.clone(),
+            name: self.session_name.clone(),
             split_layout,
             active_split_id: self.split_manager.active_split().0,
             split_states,
@@ -259,6 +265,7 @@ impl Editor {
             search_options,
             bookmarks,
             terminals,
+            hot_exit: Vec::new(),
             saved_at: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or_default()
@@ -268,12 +275,16 @@ impl Editor {
 
     /// Save the current session to disk
     ///
+    /// With hot exit enabled, buffers with unsaved changes are stashed too.
     /// Ensures all active terminals have their visible screen synced to
     /// backing files before capturing the session.
     pub fn save_session(&mut self) -> Result<(), SessionError> {
         // Ensure all terminal backing files have complete state before saving
         self.sync_all_terminal_backing_files();
-        let session = self.capture_session();
+        let mut session = self.capture_session();
+        if self.config.editor.hot_exit {
+            self.stash_hot_exit_buffers(&mut session);
+        }
         session.save()
     }
 
@@ -320,11 +331,20 @@ impl Editor {
     }
 
     /// Try to load and apply a session for the current working directory
+    /// (the named session set with [`Editor::set_session_name`], if any)
     ///
     /// Returns true if a session was successfully loaded and applied.
     pub fn try_restore_session(&mut self) -> Result<bool, SessionError> {
-        tracing::debug!("Attempting to restore session for {:?}", self.working_dir);
-        match Session::load(&self.working_dir)? {
+        tracing::debug!(
+            "Attempting to restore session {:?} for {:?}",
+            self.session_name,
+            self.working_dir
+        );
+        let session = match &self.session_name {
+            Some(name) => Session::load_named(&self.working_dir, name)?,
+            None => Session::load(&self.working_dir)?,
+        };
+        match session {
             Some(session) => {
                 tracing::info!("Found session, applying...");
                 self.apply_session(&session)?;
@@ -441,6 +461,9 @@ impl Editor {
             }
         }
 
+        // Restore buffers stashed by hot exit
+        let unnamed_buffers = self.restore_hot_exit_buffers(&session.hot_exit, &mut path_to_buffer);
+
         // 6. Rebuild split layout from the saved tree
         // Map old split IDs to new ones as we create splits
         let mut split_id_map: HashMap<usize, SplitId> = HashMap::new();
@@ -448,6 +471,7 @@ impl Editor {
             &session.split_layout,
             &path_to_buffer,
             &terminal_buffer_map,
+            &unnamed_buffers,
             &session.split_states,
             &mut split_id_map,
             true, // is_first_leaf - the first leaf reuses the existing split
@@ -499,7 +523,9 @@ impl Editor {
         terminal: &SerializedTerminalSession,
     ) -> Option<BufferId> {
         // Resolve paths (accept absolute; otherwise treat as relative to terminals dir)
-        let terminals_root = self.dir_context.terminal_dir_for(&self.working_dir);
+        let terminals_root = self
+            .dir_context
+            .terminal_dir_for(&self.working_dir, self.session_name.as_deref());
         let log_path = if terminal.log_path.is_absolute() {
             terminal.log_path.clone()
         } else {
@@ -617,6 +643,7 @@ impl Editor {
         node: &SerializedSplitNode,
         path_to_buffer: &HashMap<PathBuf, BufferId>,
         terminal_buffers: &HashMap<usize, BufferId>,
+        unnamed_buffers: &HashMap<String, BufferId>,
         split_states: &HashMap<usize, SerializedSplitViewState>,
         split_id_map: &mut HashMap<usize, SplitId>,
         is_first_leaf: bool,
@@ -652,6 +679,7 @@ impl Editor {
                     split_states,
                     path_to_buffer,
                     terminal_buffers,
+                    unnamed_buffers,
                 );
             }
             SerializedSplitNode::Terminal {
@@ -683,6 +711,7 @@ impl Editor {
                     split_states,
                     path_to_buffer,
                     terminal_buffers,
+                    unnamed_buffers,
                 );
             }
             SerializedSplitNode::Split {
@@ -697,6 +726,7 @@ impl Editor {
                     first,
                     path_to_buffer,
                     terminal_buffers,
+                    unnamed_buffers,
                     split_states,
                     split_id_map,
                     is_first_leaf,
@@ -736,6 +766,7 @@ impl Editor {
                             second,
                             path_to_buffer,
                             terminal_buffers,
+                            unnamed_buffers,
                             split_states,
                             split_id_map,
                             false,
@@ -757,6 +788,7 @@ impl Editor {
         split_states: &HashMap<usize, SerializedSplitViewState>,
         path_to_buffer: &HashMap<PathBuf, BufferId>,
         terminal_buffers: &HashMap<usize, BufferId>,
+        unnamed_buffers: &HashMap<String, BufferId>,
     ) {
         // Try to find the saved state for this split
         let Some(split_state) = split_states.get(&saved_split_id) else {
@@ -790,6 +822,13 @@ impl Editor {
                             view_state.viewport.line_wrap_enabled = false;
                         }
                     }
+                    SerializedTabRef::Unnamed(id) => {
+                        if let Some(&buffer_id) = unnamed_buffers.get(id) {
+                            if !view_state.open_buffers.contains(&buffer_id) {
+                                view_state.open_buffers.push(buffer_id);
+                            }
+                        }
+                    }
                 }
             }
 
@@ -798,6 +837,7 @@ impl Editor {
                     active_buffer_id = match tab {
                         SerializedTabRef::File(rel) => path_to_buffer.get(rel).copied(),
                         SerializedTabRef::Terminal(index) => terminal_buffers.get(index).copied(),
+                        SerializedTabRef::Unnamed(id) => unnamed_buffers.get(id).copied(),
                     };
                 }
             }
@@ -842,6 +882,12 @@ impl Editor {
                         // This prevents ensure_visible from overwriting the restored scroll position
                         view_state.viewport.set_skip_resize_sync();
 
+                        // Restore folds; stale ones are dropped when first rendered
+                        let folds = view_state.folds.entry(active_id).or_default();
+                        for fold in &file_state.folds {
+                            folds.add(Fold::new(fold.start.min(max_pos), fold.end.min(max_pos)));
+                        }
+
                         tracing::trace!(
                             "Restored SplitViewState for {:?}: cursor={}, top_byte={}",
                             rel_path,
@@ -1050,6 +1096,20 @@ fn serialize_split_view_state(
                                 top_view_line_offset: view_state.viewport.top_view_line_offset,
                                 left_column: view_state.viewport.left_column,
                             },
+                            folds: view_state
+                                .folds
+                                .get(&active_id)
+                                .map(|state| {
+                                    state
+                                        .folds()
+                                        .iter()
+                                        .map(|fold| SerializedFold {
+                                            start: fold.start,
+                                            end: fold.end,
+                                        })
+                                        .collect()
+                                })
+                                .unwrap_or_default(),
                         },
                     );
                 }
@@ -0,0 +1,442 @@
+//! Snippet insertion and tabstop navigation
+//!
+//! Snippets come from completion items in snippet format and from the user's
+//! snippet files (see [`crate::input::snippets`]). After inserting a snippet
+//! with placeholders, Tab and Shift+Tab move between its tabstops; a tabstop
+//! that appears several times (a mirror) gets one cursor per occurrence, so
+//! typing edits all of them. The session ends at the final tabstop or when
+//! the cursor leaves the snippet.
+//!
+//! Tabstop ranges are tracked with markers in the buffer's [`MarkerList`], so
+//! they follow edits made while the snippet is active.
+
+use super::*;
+use crate::model::event::CursorId;
+use crate::model::marker::{MarkerId, MarkerList};
+use crate::primitives::snippet::Snippet;
+use crate::services::lsp::manager::detect_language;
+use crate::view::popup::PopupListItem;
+
+/// What accepting an item of the completion popup inserts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum CompletionInsert {
+    Text(String),
+    /// Snippet syntax, expanded with [`Editor::insert_snippet`]
+    Snippet(String),
+}
+
+/// The tabstops of an inserted snippet being filled in
+#[derive(Debug)]
+pub(crate) struct SnippetSession {
+    buffer_id: BufferId,
+    /// Start and end markers of each occurrence of each tabstop, in
+    /// navigation order; the last tabstop is the final cursor position (`$0`)
+    tabstops: Vec<Vec<(MarkerId, MarkerId)>>,
+    current: usize,
+}
+
+impl SnippetSession {
+    fn ranges(&self, markers: &MarkerList, tabstop: usize) -> Vec<Range<usize>> {
+        self.tabstops[tabstop]
+            .iter()
+            .filter_map(|(start, end)| {
+                let start = markers.get_position(*start)?;
+                let end = markers.get_position(*end)?;
+                // Placeholders nested in an edited one collapse
+                Some(start.min(end)..end)
+            })
+            .collect()
+    }
+
+    /// Byte range covering every tabstop
+    fn extent(&self, markers: &MarkerList) -> Option<Range<usize>> {
+        let ranges: Vec<Range<usize>> = (0..self.tabstops.len())
+            .flat_map(|tabstop| self.ranges(markers, tabstop))
+            .collect();
+        let start = ranges.iter().map(|r| r.start).min()?;
+        let end = ranges.iter().map(|r| r.end).max()?;
+        Some(start..end)
+    }
+
+    /// Re-create the markers so that only the current tabstop grows when text
+    /// is typed at its edges; the others keep their extent
+    fn rebuild_markers(&mut self, markers: &mut MarkerList) {
+        for tabstop in 0..self.tabstops.len() {
+            let ranges = self.ranges(markers, tabstop);
+            for (start, end) in self.tabstops[tabstop].drain(..) {
+                markers.delete(start);
+                markers.delete(end);
+            }
+            let active = tabstop == self.current;
+            self.tabstops[tabstop] = ranges
+                .into_iter()
+                .map(|range| {
+                    if active {
+                        (
+                            markers.create_exact(range.start, true),
+                            markers.create(range.end, false),
+                        )
+                    } else if range.is_empty() {
+                        (
+                            markers.create(range.start, false),
+                            markers.create(range.end, false),
+                        )
+                    } else {
+                        (
+                            markers.create(range.start, false),
+                            markers.create_exact(range.end, true),
+                        )
+                    }
+                })
+                .collect();
+        }
+    }
+
+    fn delete_markers(&self, markers: &mut MarkerList) {
+        for (start, end) in self.tabstops.iter().flatten() {
+            markers.delete(*start);
+            markers.delete(*end);
+        }
+    }
+}
+
+impl Editor {
+    /// Insert a snippet in place of `replace` in the active buffer
+    ///
+    /// Tabs in the body become `tab_size` spaces and lines after the first are
+    /// indented like the line the snippet is inserted on. If the snippet has placeholders, the first one is selected
+    /// and a snippet session starts.
+    pub(crate) fn insert_snippet(&mut self, body: &str, replace: Range<usize>) {
+        self.end_snippet_session();
+
+        let estimated_line_length = self.config.editor.estimated_line_length;
+        let tab = " ".repeat(self.config.editor.tab_size);
+        let variables = self.snippet_variables();
+        let (indent, deleted_text) = {
+            let state = self.active_state_mut();
+            let line_start = state
+                .buffer
+                .line_iterator(replace.start, estimated_line_length)
+                .current_position();
+            let indent: String = state
+                .get_text_range(line_start, replace.start)
+                .chars()
+                .take_while(|c| *c == ' ' || *c == '\t')
+                .collect();
+            let deleted_text = state.get_text_range(replace.start, replace.end);
+            (indent, deleted_text)
+        };
+        let snippet = Snippet::parse(body, |name| variables.get(name).cloned())
+            .expand_tabs(&tab)
+            .with_indent(&indent);
+
+        let cursor_id = self.active_state().cursors.primary_id();
+        let mut events = Vec::new();
+        if !replace.is_empty() {
+            events.push(Event::Delete {
+                range: replace.clone(),
+                deleted_text,
+                cursor_id,
+            });
+        }
+        events.push(Event::Insert {
+            position: replace.start,
+            text: snippet.text.clone(),
+            cursor_id,
+        });
+        let batch = Event::Batch {
+            events,
+            description: "Insert snippet".to_string(),
+        };
+        self.active_event_log_mut().append(batch.clone());
+        self.apply_event_to_active_buffer(&batch);
+
+        let offset = |range: &Range<usize>| replace.start + range.start..replace.start + range.end;
+        if !snippet.has_placeholders() {
+            let final_ranges: Vec<Range<usize>> =
+                snippet.tabstops[0].ranges.iter().map(offset).collect();
+            self.select_ranges(&final_ranges);
+            return;
+        }
+
+        let buffer_id = self.active_buffer();
+        let markers = &mut self.active_state_mut().marker_list;
+        let tabstops = snippet
+            .tabstops
+            .iter()
+            .map(|tabstop| {
+                tabstop
+                    .ranges
+                    .iter()
+                    .map(|range| {
+                        let range = offset(range);
+                        (
+                            markers.create(range.start, false),
+                            markers.create(range.end, false),
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+        self.snippet_session = Some(SnippetSession {
+            buffer_id,
+            tabstops,
+            current: 0,
+        });
+        self.select_snippet_tabstop(0);
+    }
+
+    /// Whether a snippet session is active at the cursor; ends the session if
+    /// the cursor has left the snippet or another buffer is active
+    pub(super) fn snippet_session_active(&mut self) -> bool {
+        let Some(session) = &self.snippet_session else {
+            return false;
+        };
+        let inside = session.buffer_id == self.active_buffer() && {
+            let state = self.active_state();
+            let cursor = state.cursors.primary().position;
+            session
+                .extent(&state.marker_list)
+                .is_some_and(|extent| extent.contains(&cursor) || extent.end == cursor)
+        };
+        if !inside {
+            self.end_snippet_session();
+        }
+        inside
+    }
+
+    /// Move to the next tabstop of the active snippet
+    pub(super) fn next_snippet_tabstop(&mut self) {
+        if let Some(current) = self.snippet_session.as_ref().map(|s| s.current) {
+            self.select_snippet_tabstop(current + 1);
+        }
+    }
+
+    /// Move to the previous tabstop of the active snippet
+    pub(super) fn prev_snippet_tabstop(&mut self) {
+        if let Some(current) = self.snippet_session.as_ref().map(|s| s.current) {
+            self.select_snippet_tabstop(current.saturating_sub(1));
+        }
+    }
+
+    /// Select every occurrence of a tabstop, ending the session at the last one
+    fn select_snippet_tabstop(&mut self, tabstop: usize) {
+        let Some(mut session) = self.snippet_session.take() else {
+            return;
+        };
+        let Some(state) = self.buffers.get_mut(&session.buffer_id) else {
+            return;
+        };
+        session.current = tabstop.min(session.tabstops.len() - 1);
+        session.rebuild_markers(&mut state.marker_list);
+        let ranges = session.ranges(&state.marker_list, session.current);
+        let is_final = session.current == session.tabstops.len() - 1;
+
+        if is_final {
+            session.delete_markers(&mut state.marker_list);
+        } else {
+            self.snippet_session = Some(session);
+        }
+        self.select_ranges(&ranges);
+    }
+
+    /// End the active snippet session, if any
+    pub(super) fn end_snippet_session(&mut self) {
+        if let Some(session) = self.snippet_session.take() {
+            if let Some(state) = self.buffers.get_mut(&session.buffer_id) {
+                session.delete_markers(&mut state.marker_list);
+            }
+        }
+    }
+
+    /// Replace the cursors of the active buffer with one cursor selecting each
+    /// range; the first range gets the primary cursor
+    fn select_ranges(&mut self, ranges: &[Range<usize>]) {
+        let Some((last, rest)) = ranges.split_last() else {
+            return;
+        };
+        let state = self.active_state();
+        let primary_id = state.cursors.primary_id();
+        let primary = *state.cursors.primary();
+        let anchor = |range: &Range<usize>| (!range.is_empty()).then_some(range.start);
+
+        let mut events: Vec<Event> = state
+            .cursors
+            .iter()
+            .filter(|(id, _)| *id != primary_id)
+            .map(|(cursor_id, cursor)| Event::RemoveCursor {
+                cursor_id,
+                position: cursor.position,
+                anchor: cursor.anchor,
+            })
+            .collect();
+        // Added cursors become primary, so the existing cursor takes the last
+        // range and the first range is added last
+        events.push(Event::MoveCursor {
+            cursor_id: primary_id,
+            old_position: primary.position,
+            new_position: last.end,
+            old_anchor: primary.anchor,
+            new_anchor: anchor(last),
+            old_sticky_column: primary.sticky_column,
+            new_sticky_column: 0,
+        });
+        let first_new_id = state.cursors.iter().map(|(id, _)| id.0).max().unwrap_or(0) + 1;
+        for (next_id, range) in (first_new_id..).zip(rest.iter().rev()) {
+            events.push(Event::AddCursor {
+                cursor_id: CursorId(next_id),
+                position: range.end,
+                anchor: anchor(range),
+            });
+        }
+
+        let batch = Event::Batch {
+            events,
+            description: "Select snippet tabstop".to_string(),
+        };
+        self.active_event_log_mut().append(batch.clone());
+        self.apply_event_to_active_buffer(&batch);
+    }
+
+    /// Values of the snippet variables for the active buffer and cursor
+    fn snippet_variables(&mut self) -> HashMap<&'static str, String> {
+        let mut variables = HashMap::new();
+        let estimated_line_length = self.config.editor.estimated_line_length;
+        let state = self.active_state_mut();
+        let cursor = *state.cursors.primary();
+
+        if let Some(range) = cursor.selection_range() {
+            variables.insert(
+                "TM_SELECTED_TEXT",
+                state.get_text_range(range.start, range.end),
+            );
+        }
+        let mut iter = state
+            .buffer
+            .line_iterator(cursor.position, estimated_line_length);
+        if let Some((_, line)) = iter.next() {
+            let line = line.trim_end_matches(['\n', '\r']).to_string();
+            variables.insert("TM_CURRENT_LINE", line);
+        }
+        if let Some(position) = state.buffer.offset_to_position(cursor.position) {
+            variables.insert("TM_LINE_INDEX", position.line.to_string());
+            variables.insert("TM_LINE_NUMBER", (position.line + 1).to_string());
+        }
+
+        let path = self
+            .buffer_metadata
+            .get(&self.active_buffer())
+            .and_then(|meta| meta.file_path())
+            .cloned();
+        if let Some(path) = path {
+            let file_name =
+                |p: Option<&std::ffi::OsStr>| p.map(|s| s.to_string_lossy().to_string());
+            if let Some(name) = file_name(path.file_name()) {
+                variables.insert("TM_FILENAME", name);
+            }
+            if let Some(stem) = file_name(path.file_stem()) {
+                variables.insert("TM_FILENAME_BASE", stem);
+            }
+            if let Some(dir) = path.parent() {
+                variables.insert("TM_DIRECTORY", dir.display().to_string());
+            }
+            variables.insert("TM_FILEPATH", path.display().to_string());
+        }
+        variables
+    }
+
+    /// Language of the active buffer, as named in the `languages` config
+    fn active_language(&self) -> Option<String> {
+        let path = self
+            .buffer_metadata
+            .get(&self.active_buffer())?
+            .file_path()?;
+        detect_language(path, &self.config.languages)
+    }
+
+    /// Completion items for the user snippets whose prefix starts with `prefix`
+    /// (compared case-insensitively; `prefix` must be lowercase)
+    pub(super) fn snippet_completion_items(
+        &self,
+        prefix: &str,
+    ) -> Vec<(PopupListItem, CompletionInsert)> {
+        let language = self.active_language();
+        let mut items = Vec::new();
+        for snippet in self.snippets.for_language(language.as_deref()) {
+            for trigger in &snippet.prefixes {
+                if !trigger.to_lowercase().starts_with(prefix) {
+                    continue;
+                }
+                let detail = snippet.description.as_ref().unwrap_or(&snippet.name);
+                let item = PopupListItem::new(trigger.clone())
+                    .with_detail(detail.clone())
+                    .with_icon("s".to_string())
+                    .with_data(snippet.body.clone());
+                items.push((item, CompletionInsert::Snippet(snippet.body.clone())));
+            }
+        }
+        items
+    }
+
+    /// Open the Insert Snippet prompt listing the snippets of the active buffer
+    pub(super) fn start_insert_snippet_prompt(&mut self) {
+        let language = self.active_language();
+        let suggestions: Vec<Suggestion> = self
+            .snippets
+            .for_language(language.as_deref())
+            .map(|snippet| Suggestion {
+                text: snippet.name.clone(),
+                description: Some(match &snippet.description {
+                    Some(description) => {
+                        format!("{} - {}", snippet.prefixes.join(", "), description)
+                    }
+                    None => snippet.prefixes.join(", "),
+                }),
+                value: Some(snippet.name.clone()),
+                disabled: false,
+                keybinding: None,
+                source: None,
+            })
+            .collect();
+
+        if suggestions.is_empty() {
+            self.set_status_message(format!(
+                "No snippets for {} (add them to {})",
+                language.as_deref().unwrap_or("this buffer"),
+                self.dir_context.snippets_dir().display()
+            ));
+            return;
+        }
+        self.prompt = Some(crate::view::prompt::Prompt::with_suggestions(
+            "Insert snippet: ".to_string(),
+            PromptType::InsertSnippet,
+            suggestions,
+        ));
+    }
+
+    /// Insert the user snippet named `name` at the cursor (replacing the
+    /// selection)
+    pub(super) fn handle_insert_snippet_input(&mut self, name: &str) {
+        let language = self.active_language();
+        let body = self
+            .snippets
+            .for_language(language.as_deref())
+            .find(|snippet| snippet.name == name.trim())
+            .map(|snippet| snippet.body.clone());
+        let Some(body) = body else {
+            self.set_status_message(format!("No snippet named '{}'", name.trim()));
+            return;
+        };
+        let cursor = *self.active_state().cursors.primary();
+        let replace = cursor
+            .selection_range()
+            .unwrap_or(cursor.position..cursor.position);
+        self.insert_snippet(&body, replace);
+    }
+
+    /// Reload the user snippet files
+    pub(super) fn reload_snippets(&mut self) {
+        self.snippets = SnippetStore::load_from_dir(&self.dir_context.snippets_dir());
+        self.set_status_message(format!("Loaded {} snippets", self.snippets.len()));
+    }
+}
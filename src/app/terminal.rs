@@ -39,7 +39,9 @@ impl Editor {
         }
 
         // Prepare persistent storage paths under the user's data directory
-        let terminal_root = self.dir_context.terminal_dir_for(&self.working_dir);
+        let terminal_root = self
+            .dir_context
+            .terminal_dir_for(&self.working_dir, self.session_name.as_deref());
         let _ = std::fs::create_dir_all(&terminal_root);
         // Precompute paths using the next terminal ID so we capture from the first byte
         let predicted_terminal_id = self.terminal_manager.next_terminal_id();
@@ -128,7 +130,9 @@ impl Editor {
             .get(&terminal_id)
             .cloned()
             .unwrap_or_else(|| {
-                let root = self.dir_context.terminal_dir_for(&self.working_dir);
+                let root = self
+                    .dir_context
+                    .terminal_dir_for(&self.working_dir, self.session_name.as_deref());
                 let _ = std::fs::create_dir_all(&root);
                 root.join(format!("fresh-terminal-{}.txt", terminal_id.0))
             });
@@ -192,7 +196,9 @@ impl Editor {
             .get(&terminal_id)
             .cloned()
             .unwrap_or_else(|| {
-                let root = self.dir_context.terminal_dir_for(&self.working_dir);
+                let root = self
+                    .dir_context
+                    .terminal_dir_for(&self.working_dir, self.session_name.as_deref());
                 let _ = std::fs::create_dir_all(&root);
                 root.join(format!("fresh-terminal-{}.txt", terminal_id.0))
             });
@@ -2,6 +2,10 @@ use crate::app::file_open::SortMode;
 use crate::input::keybindings::Action;
 use crate::model::event::{BufferId, SplitDirection, SplitId};
 use crate::services::async_bridge::LspMessageType;
+use crate::services::dap::async_handler::DapHandle;
+use crate::services::dap::protocol::{ScopeVariables, StackFrame};
+use crate::services::plugins::permissions::PermissionKind;
+use crate::services::project_search::{FileMatches, ProjectSearch, ProjectSearchQuery};
 use ratatui::layout::Rect;
 use std::collections::HashMap;
 use std::ops::Range;
@@ -34,6 +38,9 @@ pub(super) struct SearchState {
     pub wrap_search: bool,
     /// Optional search range (for search in selection)
     pub search_range: Option<Range<usize>>,
+    /// Cursor position when the search started; a streaming search jumps to
+    /// the first match at or after it as soon as that match arrives
+    pub origin: usize,
 }
 
 /// A bookmark in the editor (position in a specific buffer)
@@ -101,6 +108,10 @@ pub struct BufferMetadata {
     /// Whether the buffer contains binary content
     /// Binary buffers are automatically read-only and render unprintable chars as code points
     pub binary: bool,
+
+    /// Whether the buffer's file was deleted while the buffer had unsaved changes
+    /// (saving the buffer creates the file again)
+    pub orphaned: bool,
 }
 
 impl BufferMetadata {
@@ -147,6 +158,7 @@ impl BufferMetadata {
             lsp_disabled_reason: None,
             read_only: false,
             binary: false,
+            orphaned: false,
         }
     }
 
@@ -175,9 +187,17 @@ impl BufferMetadata {
             lsp_disabled_reason: None,
             read_only: false,
             binary: false,
+            orphaned: false,
         }
     }
 
+    /// Point a file-backed buffer's metadata at the path its file was moved to
+    pub fn set_file_path(&mut self, path: PathBuf, working_dir: &Path) {
+        let moved = Self::with_file(path, working_dir);
+        self.kind = moved.kind;
+        self.display_name = moved.display_name;
+    }
+
     /// Compute display name relative to working_dir when possible, otherwise absolute
     fn display_name_for_path(path: &Path, working_dir: &Path) -> String {
         // Canonicalize working_dir to normalize platform-specific prefixes
@@ -222,6 +242,7 @@ impl BufferMetadata {
             lsp_disabled_reason: Some("Virtual buffer".to_string()),
             read_only,
             binary: false,
+            orphaned: false,
         }
     }
 
@@ -241,6 +262,89 @@ pub(super) struct MacroRecordingState {
     pub actions: Vec<Action>,
 }
 
+/// State of the open undo history panel
+#[derive(Debug, Clone)]
+pub(super) struct UndoHistoryPanel {
+    /// The panel's virtual buffer
+    pub buffer_id: BufferId,
+    /// Split showing the panel
+    pub split_id: SplitId,
+    /// Buffer whose history is shown
+    pub source_buffer: BufferId,
+    /// Split showing the source buffer
+    pub source_split: SplitId,
+    /// Undo state of the source buffer when the panel was opened, restored
+    /// when the panel is closed without jumping
+    pub original_state: usize,
+}
+
+/// State of the open project search panel
+#[derive(Debug)]
+pub(super) struct ProjectSearchPanel {
+    /// The panel's virtual buffer
+    pub buffer_id: BufferId,
+    /// Split showing the panel
+    pub split_id: SplitId,
+    /// Split the panel was opened from, where matches are opened
+    pub source_split: SplitId,
+    /// The query the results are for
+    pub query: ProjectSearchQuery,
+    /// Matches found so far, grouped by file
+    pub files: Vec<FileMatches>,
+    /// The running search, until it finishes
+    pub search: Option<ProjectSearch>,
+    /// Whether the search stopped at the match limit
+    pub truncated: bool,
+    /// Error that ended the search
+    pub error: Option<String>,
+}
+
+/// Where a debug session is at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum DebugState {
+    /// The debuggee is starting or running
+    Running,
+    /// The debuggee is stopped, e.g. at a breakpoint
+    Stopped { reason: String },
+    /// The session is over
+    Ended { exit_code: Option<i64> },
+}
+
+/// The current (or last) debug session
+#[derive(Debug)]
+pub(super) struct DebugSession {
+    /// Handle to the session task
+    pub handle: DapHandle,
+    /// Language whose debug adapter runs the session
+    pub language: String,
+    pub state: DebugState,
+    /// Call stack of the stopped thread, innermost frame first
+    pub frames: Vec<StackFrame>,
+    /// Index of the frame whose variables are shown
+    pub selected_frame: usize,
+    /// Variables of the selected frame, by scope
+    pub scopes: Vec<ScopeVariables>,
+    /// Output of the debuggee, most recent last
+    pub output: Vec<String>,
+}
+
+impl DebugSession {
+    pub fn is_active(&self) -> bool {
+        !matches!(self.state, DebugState::Ended { .. })
+    }
+}
+
+/// The open debug panel
+#[derive(Debug, Clone)]
+pub(super) struct DebugPanel {
+    /// The panel's virtual buffer
+    pub buffer_id: BufferId,
+    /// Split showing the panel
+    pub split_id: SplitId,
+    /// Split the panel was opened from, where source locations are shown
+    pub source_split: SplitId,
+}
+
 /// LSP progress information
 #[derive(Debug, Clone)]
 pub(super) struct LspProgressInfo {
@@ -260,6 +364,16 @@ pub(super) struct LspMessageEntry {
     pub timestamp: std::time::Instant,
 }
 
+/// A sandboxed plugin's request for a capability, waiting for the user
+#[derive(Debug, Clone)]
+pub(super) struct PluginPermissionRequest {
+    pub plugin: String,
+    pub kind: PermissionKind,
+    /// What the capability is used for (command, path, language)
+    pub target: String,
+    pub request_id: u64,
+}
+
 /// Types of UI elements that can be hovered over
 #[derive(Debug, Clone, PartialEq)]
 pub enum HoverTarget {
@@ -337,6 +451,10 @@ pub(super) struct MouseState {
     pub drag_selection_split: Option<SplitId>,
     /// The buffer byte position where the selection anchor is
     pub drag_selection_anchor: Option<usize>,
+    /// Paths of the file explorer items being dragged
+    pub dragging_explorer_items: Option<Vec<std::path::PathBuf>>,
+    /// Directory the dragged file explorer items would be dropped into
+    pub explorer_drop_target: Option<std::path::PathBuf>,
 }
 
 /// Mapping from visual row to buffer positions for mouse click handling
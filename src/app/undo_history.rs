@@ -0,0 +1,343 @@
+//! Undo tree navigation
+//!
+//! The event log keeps every branch of edits (see [`EventLog`]). "Switch Undo
+//! Branch" picks the branch redo follows where history forks, and the undo
+//! history panel lists the whole tree: `p` previews the selected state in the
+//! source buffer, Enter jumps to it and `q` closes the panel, going back to
+//! the state the buffer was in when it was opened.
+//!
+//! Undo trees are also persisted across restarts through the
+//! [`UndoHistoryStore`]: when a file is saved, closed, or left unmodified at
+//! exit, and restored when the file is reopened with the same content.
+
+use super::*;
+use crate::model::event::LogEntry;
+use crate::primitives::text_property::TextPropertyEntry;
+
+/// Display name of the undo history panel buffer
+const UNDO_HISTORY_BUFFER_NAME: &str = "*Undo History*";
+
+/// Longest edited text shown for a state in the panel
+const MAX_SUMMARY_CHARS: usize = 40;
+
+impl Editor {
+    /// Make redo follow the next branch at the nearest fork in the active
+    /// buffer's undo history
+    pub(crate) fn switch_undo_branch(&mut self) {
+        match self.active_event_log_mut().switch_branch() {
+            Some((events, selected, count)) => {
+                for event in &events {
+                    self.apply_event_to_active_buffer(event);
+                }
+                self.update_modified_from_event_log();
+                self.set_status_message(format!("Redo follows branch {} of {}", selected, count));
+            }
+            None => self.set_status_message("No undo branches here".to_string()),
+        }
+    }
+
+    /// Open the undo history panel for the active buffer in a split below it
+    pub(crate) fn open_undo_history(&mut self) {
+        if let Some(panel) = self.undo_history_panel.clone() {
+            if self.active_buffer() == panel.buffer_id
+                || self.active_buffer() == panel.source_buffer
+            {
+                self.refresh_undo_history();
+                self.focus_split(panel.split_id, panel.buffer_id);
+                return;
+            }
+            // Showing another buffer's history replaces the old panel
+            self.close_undo_history(false);
+        }
+
+        let source_buffer = self.active_buffer();
+        let source_split = self.split_manager.active_split();
+        let original_state = self.active_event_log().current_index();
+
+        let buffer_id = self.create_virtual_buffer(
+            UNDO_HISTORY_BUFFER_NAME.to_string(),
+            "undo-history".to_string(),
+            true,
+        );
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            state.margins.set_line_numbers(false);
+            state.editing_disabled = true;
+        }
+
+        // Keep the panel out of the source split's tabs
+        if let Some(view_state) = self.split_view_states.get_mut(&source_split) {
+            view_state.remove_buffer(buffer_id);
+        }
+
+        self.save_current_split_view_state();
+        let split_id =
+            match self
+                .split_manager
+                .split_active(SplitDirection::Horizontal, buffer_id, 0.7)
+            {
+                Ok(split_id) => {
+                    let mut view_state = SplitViewState::with_buffer(
+                        self.terminal_width,
+                        self.terminal_height,
+                        buffer_id,
+                    );
+                    view_state.viewport.line_wrap_enabled = false;
+                    self.split_view_states.insert(split_id, view_state);
+                    split_id
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to split for undo history: {}", e);
+                    self.set_active_buffer(buffer_id);
+                    source_split
+                }
+            };
+
+        self.undo_history_panel = Some(UndoHistoryPanel {
+            buffer_id,
+            split_id,
+            source_buffer,
+            source_split,
+            original_state,
+        });
+        self.refresh_undo_history();
+        self.set_status_message("Undo history: Enter jump, p preview, q close".to_string());
+    }
+
+    /// Run an undo history panel command (`jump`, `preview` or `close`)
+    pub(super) fn handle_undo_history_command(&mut self, command: &str) {
+        if self.undo_history_panel.is_none() {
+            return;
+        }
+        match command {
+            "jump" | "preview" => {
+                let Some(target) = self.selected_undo_history_state() else {
+                    return;
+                };
+                self.move_source_to_undo_state(target);
+                self.refresh_undo_history();
+                if command == "jump" {
+                    self.close_undo_history(false);
+                    self.set_status_message(format!("Jumped to undo state {}", target));
+                } else {
+                    self.set_status_message(format!(
+                        "Previewing undo state {} - Enter to keep, q to go back",
+                        target
+                    ));
+                }
+            }
+            "close" => {
+                self.close_undo_history(true);
+            }
+            _ => {}
+        }
+    }
+
+    /// The undo state on the panel line under the cursor
+    fn selected_undo_history_state(&self) -> Option<usize> {
+        let panel = self.undo_history_panel.as_ref()?;
+        let state = self.buffers.get(&panel.buffer_id)?;
+        let position = state.cursors.primary().position;
+        state
+            .text_properties
+            .get_at(position)
+            .into_iter()
+            .find_map(|property| property.get_as::<usize>("state"))
+    }
+
+    /// Move the source buffer to a state in its undo tree, applying the edits
+    /// with the source split focused so its cursors and view follow
+    fn move_source_to_undo_state(&mut self, target: usize) {
+        let Some(panel) = self.undo_history_panel.clone() else {
+            return;
+        };
+        let Some(event_log) = self.event_logs.get_mut(&panel.source_buffer) else {
+            return;
+        };
+        let events = event_log.jump_to(target);
+
+        let previous_split = self.split_manager.active_split();
+        let previous_buffer = self.active_buffer();
+        self.focus_split(panel.source_split, panel.source_buffer);
+        for event in &events {
+            self.apply_event_to_active_buffer(event);
+        }
+        self.update_modified_from_event_log();
+        self.focus_split(previous_split, previous_buffer);
+    }
+
+    /// Close the undo history panel and focus the source buffer, optionally
+    /// returning it to the state it was in when the panel was opened
+    fn close_undo_history(&mut self, restore: bool) {
+        let Some(panel) = self.undo_history_panel.clone() else {
+            return;
+        };
+        if restore {
+            self.move_source_to_undo_state(panel.original_state);
+        }
+        self.undo_history_panel = None;
+
+        if panel.split_id != panel.source_split {
+            self.handle_close_split(panel.split_id);
+        }
+        if self.buffers.contains_key(&panel.source_buffer) {
+            self.focus_split(panel.source_split, panel.source_buffer);
+        }
+        if let Err(e) = self.close_buffer(panel.buffer_id) {
+            tracing::warn!("Failed to close undo history panel: {}", e);
+        }
+    }
+
+    /// Rebuild the panel's content from the source buffer's undo tree
+    fn refresh_undo_history(&mut self) {
+        let Some(panel) = self.undo_history_panel.clone() else {
+            return;
+        };
+        let Some(event_log) = self.event_logs.get(&panel.source_buffer) else {
+            return;
+        };
+
+        let current = event_log.current_history_state();
+        let saved = event_log.saved_index();
+        let mut entries = Vec::new();
+        let mut current_line_start = 0;
+        let mut offset = 0;
+        for item in event_log.history() {
+            let marker = if item.state == current { '*' } else { ' ' };
+            let mut line = format!(
+                "{}{} {:>4}  {}",
+                "  ".repeat(item.depth),
+                marker,
+                item.state,
+                describe_undo_state(event_log.entry_for_state(item.state)),
+            );
+            if saved == Some(item.state) {
+                line.push_str("  (saved)");
+            }
+            line.push('\n');
+
+            if item.state == current {
+                current_line_start = offset;
+            }
+            offset += line.len();
+            entries.push(
+                TextPropertyEntry::text(line)
+                    .with_property("state", serde_json::Value::from(item.state)),
+            );
+        }
+
+        if let Err(e) = self.set_virtual_buffer_content(panel.buffer_id, entries) {
+            tracing::warn!("Failed to update undo history panel: {}", e);
+            return;
+        }
+        if let Some(state) = self.buffers.get_mut(&panel.buffer_id) {
+            state.cursors.primary_mut().position = current_line_start;
+        }
+        if let Some(view_state) = self.split_view_states.get_mut(&panel.split_id) {
+            if let Some(state) = self.buffers.get(&panel.buffer_id) {
+                view_state.cursors = state.cursors.clone();
+            }
+        }
+    }
+}
+
+impl Editor {
+    /// Restore a freshly opened file's undo history saved in an earlier
+    /// session, if the file's content hasn't changed since
+    pub(super) fn restore_undo_history(&mut self, buffer_id: BufferId) {
+        let Some(store) = &self.undo_store else {
+            return;
+        };
+        let Some(state) = self.buffers.get(&buffer_id) else {
+            return;
+        };
+        // Large files that aren't fully loaded are never persisted
+        let (Some(path), Some(content)) = (state.buffer.file_path(), state.buffer.to_string())
+        else {
+            return;
+        };
+        match store.load(path, &content) {
+            Ok(Some(event_log)) => {
+                tracing::debug!(
+                    "Restored undo history for {:?} ({} edits)",
+                    path,
+                    event_log.len()
+                );
+                self.event_logs.insert(buffer_id, event_log);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to load undo history for {:?}: {}", path, e),
+        }
+    }
+
+    /// Persist a buffer's undo history in the background, if its file is
+    /// saved and unmodified
+    pub(super) fn persist_undo_history(&self, buffer_id: BufferId) {
+        let Some(writer) = &self.undo_writer else {
+            return;
+        };
+        let (Some(state), Some(event_log)) = (
+            self.buffers.get(&buffer_id),
+            self.event_logs.get(&buffer_id),
+        ) else {
+            return;
+        };
+        // The history is anchored at the saved state, which only matches the
+        // buffer's content while it is unmodified
+        if state.buffer.is_modified() {
+            return;
+        }
+        let (Some(path), Some(content)) = (state.buffer.file_path(), state.buffer.to_string())
+        else {
+            return;
+        };
+        writer.queue(path, content, event_log);
+    }
+
+    /// Persist the undo history of every unmodified file buffer (on exit),
+    /// waiting until all queued histories are written
+    pub fn save_undo_histories(&mut self) {
+        for buffer_id in self.buffers.keys() {
+            self.persist_undo_history(*buffer_id);
+        }
+        if let Some(writer) = &mut self.undo_writer {
+            writer.finish();
+        }
+    }
+}
+
+/// One-line summary of the edit leading to an undo state
+fn describe_undo_state(entry: Option<&LogEntry>) -> String {
+    let Some(entry) = entry else {
+        return "original".to_string();
+    };
+    let time = chrono::DateTime::from_timestamp_millis(entry.timestamp as i64)
+        .map(|time| {
+            time.with_timezone(&chrono::Local)
+                .format("%H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_default();
+    let change = match &entry.event {
+        Event::Insert { text, .. } => format!("insert {}", quote_edit(text)),
+        Event::Delete { deleted_text, .. } => format!("delete {}", quote_edit(deleted_text)),
+        Event::Batch { description, .. } => description.clone(),
+        Event::AddCursor { .. } => "add cursor".to_string(),
+        Event::RemoveCursor { .. } => "remove cursor".to_string(),
+        _ => "edit".to_string(),
+    };
+    format!("{}  {}", time, change)
+}
+
+/// Quote edited text for display, escaping newlines and truncating it
+fn quote_edit(text: &str) -> String {
+    let mut quoted: String = text
+        .chars()
+        .take(MAX_SUMMARY_CHARS)
+        .collect::<String>()
+        .escape_debug()
+        .to_string();
+    if text.chars().count() > MAX_SUMMARY_CHARS {
+        quoted.push_str("...");
+    }
+    format!("\"{}\"", quoted)
+}
@@ -1,4 +1,5 @@
-use crate::services::lsp::client::LspServerConfig;
+use crate::services::lsp::client::{LspServerConfig, LspServers};
+use crate::services::plugins::permissions::PermissionKind;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
@@ -157,9 +158,25 @@ pub struct Config {
     #[serde(default)]
     pub languages: HashMap<String, LanguageConfig>,
 
-    /// LSP server configurations by language
+    /// LSP server configurations by language: a server, or a list of
+    /// servers run together on the language's files
     #[serde(default)]
-    pub lsp: HashMap<String, LspServerConfig>,
+    pub lsp: HashMap<String, LspServers>,
+
+    /// Debug adapter configurations by language
+    #[serde(default)]
+    pub debug: HashMap<String, DebugAdapterConfig>,
+
+    /// Capabilities granted to sandboxed plugins (those in the plugins
+    /// directory of the config dir), by plugin name. Entries are added when
+    /// you allow a plugin's request; remove one to revoke its grants.
+    #[serde(default)]
+    pub plugin_permissions: HashMap<String, PluginPermissionGrants>,
+
+    /// Settings of plugins, by plugin name. Plugins declare their settings
+    /// in their manifest, and the settings UI shows a page for each.
+    #[serde(default)]
+    pub plugin_settings: HashMap<String, serde_json::Value>,
 
     /// Menu bar configuration
     #[serde(default)]
@@ -244,6 +261,29 @@ pub struct EditorConfig {
     #[serde(default = "default_auto_save_interval")]
     pub auto_save_interval_secs: u32,
 
+    /// Whether to keep undo history across editor restarts
+    /// When enabled, a file's undo history is saved when the file is saved or
+    /// closed, and restored when the file is reopened with the same content.
+    #[serde(default = "default_true")]
+    pub persistent_undo: bool,
+
+    /// Maximum number of edits kept in a file's persisted undo history
+    /// The edits nearest to the saved state are kept.
+    #[serde(default = "default_persistent_undo_max_entries")]
+    pub persistent_undo_max_entries: usize,
+
+    /// Maximum number of files whose undo history is persisted
+    /// Histories of the least recently saved files are deleted beyond this.
+    #[serde(default = "default_persistent_undo_max_files")]
+    pub persistent_undo_max_files: usize,
+
+    /// Whether to quit without asking about unsaved changes (hot exit)
+    /// Modified and unnamed buffers are stashed with the session on quit and
+    /// restored, still modified and with their undo history, the next time
+    /// the project is opened.
+    #[serde(default = "default_false")]
+    pub hot_exit: bool,
+
     /// Number of bytes to look back/forward from the viewport for syntax highlighting context.
     /// Larger values improve accuracy for multi-line constructs (strings, comments, nested blocks)
     /// but may slow down highlighting for very large files.
@@ -311,6 +351,14 @@ fn default_auto_save_interval() -> u32 {
     2 // Auto-save every 2 seconds for fast recovery
 }
 
+fn default_persistent_undo_max_entries() -> usize {
+    10_000
+}
+
+fn default_persistent_undo_max_files() -> usize {
+    200
+}
+
 fn default_highlight_context_bytes() -> usize {
     10_000 // 10KB context for accurate syntax highlighting
 }
@@ -340,6 +388,10 @@ impl Default for EditorConfig {
             enable_inlay_hints: true,
             recovery_enabled: true,
             auto_save_interval_secs: default_auto_save_interval(),
+            persistent_undo: true,
+            persistent_undo_max_entries: default_persistent_undo_max_entries(),
+            persistent_undo_max_files: default_persistent_undo_max_files(),
+            hot_exit: false,
             highlight_context_bytes: default_highlight_context_bytes(),
             mouse_hover_enabled: true,
             mouse_hover_delay_ms: default_mouse_hover_delay(),
@@ -463,13 +515,21 @@ pub struct LanguageConfig {
     pub extensions: Vec<String>,
 
     /// Tree-sitter grammar name
+    /// A grammar library of this name in the `tree-sitter` folder of the
+    /// grammars directory is loaded at runtime, with its queries
     #[serde(default)]
     pub grammar: String,
 
-    /// Comment prefix
+    /// Line comment prefix (e.g., "//")
     #[serde(default)]
     pub comment_prefix: Option<String>,
 
+    /// Block comment delimiters (optional)
+    /// Used by "Toggle Block Comment", and by "Toggle Comment" in languages
+    /// without a line comment prefix
+    #[serde(default)]
+    pub block_comment: Option<BlockCommentConfig>,
+
     /// Whether to auto-indent
     #[serde(default = "default_true")]
     pub auto_indent: bool,
@@ -479,9 +539,129 @@ pub struct LanguageConfig {
     pub highlighter: HighlighterPreference,
 
     /// Path to custom TextMate grammar file (optional)
-    /// If specified, this grammar will be used when highlighter is "textmate"
+    /// Relative paths are relative to the grammars directory. The grammar
+    /// highlights the files with the extensions of this language
     #[serde(default)]
     pub textmate_grammar: Option<std::path::PathBuf>,
+
+    /// External formatter used by "Format Buffer" (optional)
+    /// If not set, the language server's formatting support is used instead
+    #[serde(default)]
+    pub formatter: Option<FormatterConfig>,
+
+    /// Whether to format the buffer automatically before saving
+    #[serde(default)]
+    pub format_on_save: bool,
+}
+
+/// Block comment delimiters for a language
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct BlockCommentConfig {
+    /// Text that opens a block comment (e.g., "/*")
+    pub start: String,
+
+    /// Text that closes a block comment (e.g., "*/")
+    pub end: String,
+}
+
+/// External formatter command for a language
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct FormatterConfig {
+    /// Command to run (e.g., "rustfmt", "prettier", "black")
+    pub command: String,
+
+    /// Arguments to pass to the command
+    /// `$FILE` is replaced with the path of the file being formatted
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Whether the formatter reads the source from stdin and writes the result to stdout
+    /// If false, the buffer is written to a temporary file which the formatter
+    /// rewrites in place (pass it with `$FILE` in args)
+    #[serde(default = "default_true")]
+    pub stdin: bool,
+}
+
+/// Debug adapter used by "Start Debugging" for a language
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DebugAdapterConfig {
+    /// Command to start the debug adapter (e.g., "lldb-dap", "python3")
+    pub command: String,
+
+    /// Arguments to pass to the command
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Whether to launch a new debuggee or attach to a running one
+    #[serde(default)]
+    pub request: DebugRequest,
+
+    /// Adapter-specific arguments of the launch or attach request
+    /// (e.g., `{"program": "$FILE"}`). `$FILE` in strings is replaced with
+    /// the path of the file being debugged.
+    #[serde(default)]
+    pub configuration: serde_json::Value,
+}
+
+/// Capabilities the user granted to a sandboxed plugin, within the limits
+/// declared by its manifest
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PluginPermissionGrants {
+    /// Run the commands listed in the manifest
+    #[serde(default)]
+    pub process: bool,
+
+    /// Read files in the scopes listed in the manifest
+    #[serde(default)]
+    pub fs_read: bool,
+
+    /// Write files in the scopes listed in the manifest
+    #[serde(default)]
+    pub fs_write: bool,
+
+    /// Send LSP requests
+    #[serde(default)]
+    pub lsp: bool,
+
+    /// Write to the clipboard
+    #[serde(default)]
+    pub clipboard: bool,
+}
+
+impl PluginPermissionGrants {
+    /// Whether `kind` is granted
+    pub fn get(&self, kind: PermissionKind) -> bool {
+        match kind {
+            PermissionKind::Process => self.process,
+            PermissionKind::FsRead => self.fs_read,
+            PermissionKind::FsWrite => self.fs_write,
+            PermissionKind::Lsp => self.lsp,
+            PermissionKind::Clipboard => self.clipboard,
+        }
+    }
+
+    /// Grant or revoke `kind`
+    pub fn set(&mut self, kind: PermissionKind, granted: bool) {
+        let flag = match kind {
+            PermissionKind::Process => &mut self.process,
+            PermissionKind::FsRead => &mut self.fs_read,
+            PermissionKind::FsWrite => &mut self.fs_write,
+            PermissionKind::Lsp => &mut self.lsp,
+            PermissionKind::Clipboard => &mut self.clipboard,
+        };
+        *flag = granted;
+    }
+}
+
+/// How a debug session starts its debuggee
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DebugRequest {
+    /// Start a new process
+    #[default]
+    Launch,
+    /// Connect to a running process
+    Attach,
 }
 
 /// Preference for which syntax highlighting backend to use
@@ -551,6 +731,9 @@ impl Default for Config {
             active_keybinding_map: default_keybinding_map_name(),
             languages: Self::default_languages(),
             lsp: Self::default_lsp_config(),
+            debug: HashMap::new(),
+            plugin_permissions: HashMap::new(),
+            plugin_settings: HashMap::new(),
             menu: MenuConfig::default(),
         }
     }
@@ -732,9 +915,15 @@ impl Config {
                 extensions: vec!["rs".to_string()],
                 grammar: "rust".to_string(),
                 comment_prefix: Some("//".to_string()),
+                block_comment: Some(BlockCommentConfig {
+                    start: "/*".to_string(),
+                    end: "*/".to_string(),
+                }),
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                formatter: None,
+                format_on_save: false,
             },
         );
 
@@ -744,9 +933,15 @@ impl Config {
                 extensions: vec!["js".to_string(), "jsx".to_string()],
                 grammar: "javascript".to_string(),
                 comment_prefix: Some("//".to_string()),
+                block_comment: Some(BlockCommentConfig {
+                    start: "/*".to_string(),
+                    end: "*/".to_string(),
+                }),
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                formatter: None,
+                format_on_save: false,
             },
         );
 
@@ -756,9 +951,15 @@ impl Config {
                 extensions: vec!["ts".to_string(), "tsx".to_string()],
                 grammar: "typescript".to_string(),
                 comment_prefix: Some("//".to_string()),
+                block_comment: Some(BlockCommentConfig {
+                    start: "/*".to_string(),
+                    end: "*/".to_string(),
+                }),
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                formatter: None,
+                format_on_save: false,
             },
         );
 
@@ -768,9 +969,12 @@ impl Config {
                 extensions: vec!["py".to_string()],
                 grammar: "python".to_string(),
                 comment_prefix: Some("#".to_string()),
+                block_comment: None,
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                formatter: None,
+                format_on_save: false,
             },
         );
 
@@ -780,9 +984,15 @@ impl Config {
                 extensions: vec!["c".to_string(), "h".to_string()],
                 grammar: "c".to_string(),
                 comment_prefix: Some("//".to_string()),
+                block_comment: Some(BlockCommentConfig {
+                    start: "/*".to_string(),
+                    end: "*/".to_string(),
+                }),
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                formatter: None,
+                format_on_save: false,
             },
         );
 
@@ -799,9 +1009,15 @@ impl Config {
                 ],
                 grammar: "cpp".to_string(),
                 comment_prefix: Some("//".to_string()),
+                block_comment: Some(BlockCommentConfig {
+                    start: "/*".to_string(),
+                    end: "*/".to_string(),
+                }),
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                formatter: None,
+                format_on_save: false,
             },
         );
 
@@ -811,9 +1027,15 @@ impl Config {
                 extensions: vec!["cs".to_string()],
                 grammar: "c_sharp".to_string(),
                 comment_prefix: Some("//".to_string()),
+                block_comment: Some(BlockCommentConfig {
+                    start: "/*".to_string(),
+                    end: "*/".to_string(),
+                }),
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                formatter: None,
+                format_on_save: false,
             },
         );
 
@@ -821,7 +1043,7 @@ impl Config {
     }
 
     /// Create default LSP configurations
-    fn default_lsp_config() -> HashMap<String, LspServerConfig> {
+    fn default_lsp_config() -> HashMap<String, LspServers> {
         let mut lsp = HashMap::new();
 
         // rust-analyzer (installed via rustup or package manager)
@@ -841,7 +1063,10 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                priority: 0,
+            }
+            .into(),
         );
 
         // pylsp (installed via pip)
@@ -854,7 +1079,10 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                priority: 0,
+            }
+            .into(),
         );
 
         // typescript-language-server (installed via npm)
@@ -866,9 +1094,11 @@ impl Config {
             auto_start: false,
             process_limits: crate::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
+            name: None,
+            priority: 0,
         };
-        lsp.insert("javascript".to_string(), ts_lsp.clone());
-        lsp.insert("typescript".to_string(), ts_lsp);
+        lsp.insert("javascript".to_string(), ts_lsp.clone().into());
+        lsp.insert("typescript".to_string(), ts_lsp.into());
 
         // vscode-html-languageserver-bin (installed via npm)
         lsp.insert(
@@ -880,7 +1110,10 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                priority: 0,
+            }
+            .into(),
         );
 
         // vscode-css-languageserver-bin (installed via npm)
@@ -893,7 +1126,10 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                priority: 0,
+            }
+            .into(),
         );
 
         // clangd (installed via package manager)
@@ -906,7 +1142,10 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                priority: 0,
+            }
+            .into(),
         );
         lsp.insert(
             "cpp".to_string(),
@@ -917,7 +1156,10 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                priority: 0,
+            }
+            .into(),
         );
 
         // gopls (installed via go install)
@@ -930,7 +1172,10 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                priority: 0,
+            }
+            .into(),
         );
 
         // vscode-json-languageserver (installed via npm)
@@ -943,7 +1188,10 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                priority: 0,
+            }
+            .into(),
         );
 
         // csharp-language-server (installed via dotnet tool install -g csharp-ls)
@@ -956,7 +1204,10 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                priority: 0,
+            }
+            .into(),
         );
 
         lsp
@@ -1114,6 +1365,20 @@ impl Config {
                         when: None,
                         checkbox: None,
                     },
+                    MenuItem::Action {
+                        label: "Find in Project...".to_string(),
+                        action: "project_search".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Replace in Project...".to_string(),
+                        action: "project_replace".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
                         label: "Delete Line".to_string(),
@@ -1159,6 +1424,48 @@ impl Config {
                     },
                     // Note: Compose Mode removed from menu - markdown_compose plugin provides this
                     MenuItem::Separator { separator: true },
+                    MenuItem::Submenu {
+                        label: "Folding".to_string(),
+                        items: vec![
+                            MenuItem::Action {
+                                label: "Fold".to_string(),
+                                action: "fold".to_string(),
+                                args: HashMap::new(),
+                                when: None,
+                                checkbox: None,
+                            },
+                            MenuItem::Action {
+                                label: "Unfold".to_string(),
+                                action: "unfold".to_string(),
+                                args: HashMap::new(),
+                                when: None,
+                                checkbox: None,
+                            },
+                            MenuItem::Separator { separator: true },
+                            MenuItem::Action {
+                                label: "Fold All".to_string(),
+                                action: "fold_all".to_string(),
+                                args: HashMap::new(),
+                                when: None,
+                                checkbox: None,
+                            },
+                            MenuItem::Action {
+                                label: "Unfold All".to_string(),
+                                action: "unfold_all".to_string(),
+                                args: HashMap::new(),
+                                when: None,
+                                checkbox: None,
+                            },
+                            MenuItem::Action {
+                                label: "Unfold to Level...".to_string(),
+                                action: "unfold_to_level".to_string(),
+                                args: HashMap::new(),
+                                when: None,
+                                checkbox: None,
+                            },
+                        ],
+                    },
+                    MenuItem::Separator { separator: true },
                     MenuItem::Action {
                         label: "Set Background...".to_string(),
                         action: "set_background".to_string(),
@@ -1339,6 +1646,13 @@ impl Config {
                         when: None,
                         checkbox: None,
                     },
+                    MenuItem::Action {
+                        label: "Shrink Selection".to_string(),
+                        action: "shrink_selection".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
                         label: "Add Cursor Above".to_string(),
@@ -1506,6 +1820,70 @@ impl Config {
                     },
                 ],
             },
+            // Debug menu (Debug Adapter Protocol sessions)
+            Menu {
+                label: "Debug".to_string(),
+                items: vec![
+                    MenuItem::Action {
+                        label: "Start Debugging".to_string(),
+                        action: "debug_start".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Stop Debugging".to_string(),
+                        action: "debug_stop".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Separator { separator: true },
+                    MenuItem::Action {
+                        label: "Continue".to_string(),
+                        action: "debug_continue".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Pause".to_string(),
+                        action: "debug_pause".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Step Over".to_string(),
+                        action: "debug_step_over".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Step Into".to_string(),
+                        action: "debug_step_into".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Step Out".to_string(),
+                        action: "debug_step_out".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Separator { separator: true },
+                    MenuItem::Action {
+                        label: "Toggle Breakpoint".to_string(),
+                        action: "toggle_breakpoint".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                ],
+            },
             // Explorer menu (file explorer operations)
             Menu {
                 label: "Explorer".to_string(),
@@ -1547,6 +1925,15 @@ impl Config {
                         ),
                         checkbox: None,
                     },
+                    MenuItem::Action {
+                        label: "Duplicate".to_string(),
+                        action: "file_explorer_duplicate".to_string(),
+                        args: HashMap::new(),
+                        when: Some(
+                            crate::view::ui::context_keys::FILE_EXPLORER_FOCUSED.to_string(),
+                        ),
+                        checkbox: None,
+                    },
                     MenuItem::Action {
                         label: "Delete".to_string(),
                         action: "file_explorer_delete".to_string(),
@@ -1557,6 +1944,44 @@ impl Config {
                         checkbox: None,
                     },
                     MenuItem::Separator { separator: true },
+                    MenuItem::Action {
+                        label: "Copy".to_string(),
+                        action: "file_explorer_copy".to_string(),
+                        args: HashMap::new(),
+                        when: Some(
+                            crate::view::ui::context_keys::FILE_EXPLORER_FOCUSED.to_string(),
+                        ),
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Cut".to_string(),
+                        action: "file_explorer_cut".to_string(),
+                        args: HashMap::new(),
+                        when: Some(
+                            crate::view::ui::context_keys::FILE_EXPLORER_FOCUSED.to_string(),
+                        ),
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Paste".to_string(),
+                        action: "file_explorer_paste".to_string(),
+                        args: HashMap::new(),
+                        when: Some(
+                            crate::view::ui::context_keys::FILE_EXPLORER_FOCUSED.to_string(),
+                        ),
+                        checkbox: None,
+                    },
+                    MenuItem::Separator { separator: true },
+                    MenuItem::Action {
+                        label: "Undo File Operation".to_string(),
+                        action: "file_explorer_undo".to_string(),
+                        args: HashMap::new(),
+                        when: Some(
+                            crate::view::ui::context_keys::FILE_EXPLORER_FOCUSED.to_string(),
+                        ),
+                        checkbox: None,
+                    },
+                    MenuItem::Separator { separator: true },
                     MenuItem::Action {
                         label: "Refresh".to_string(),
                         action: "file_explorer_refresh".to_string(),
@@ -1770,15 +2195,61 @@ impl DirectoryContext {
         self.data_dir.join("replace_history.json")
     }
 
+    /// Get the keyboard macros file path
+    pub fn macros_path(&self) -> std::path::PathBuf {
+        self.data_dir.join("macros.json")
+    }
+
+    /// Get the directory holding persisted undo histories
+    pub fn undo_history_dir(&self) -> std::path::PathBuf {
+        self.data_dir.join("undo")
+    }
+
+    /// Get the directory holding buffers stashed on hot exit for a session
+    /// (the default one if `session_name` is None) of a working directory
+    pub fn hot_exit_dir_for(
+        &self,
+        working_dir: &std::path::Path,
+        session_name: Option<&str>,
+    ) -> std::path::PathBuf {
+        let encoded = crate::session::encode_path_for_filename(working_dir);
+        let dir = self.data_dir.join("hot_exit").join(encoded);
+        match session_name {
+            Some(name) => dir.join("named").join(name),
+            None => dir,
+        }
+    }
+
+    /// Get the trash directory files deleted from the file explorer go to
+    ///
+    /// On Linux and the BSDs this is the desktop's home trash
+    /// (`$XDG_DATA_HOME/Trash`), elsewhere a trash directory of Fresh's own.
+    pub fn trash_dir(&self) -> std::path::PathBuf {
+        #[cfg(all(unix, not(target_os = "macos")))]
+        if let Some(xdg_data_dir) = self.data_dir.parent() {
+            return xdg_data_dir.join("Trash");
+        }
+        self.data_dir.join("trash")
+    }
+
     /// Get the terminals root directory
     pub fn terminals_dir(&self) -> std::path::PathBuf {
         self.data_dir.join("terminals")
     }
 
-    /// Get the terminal directory for a specific working directory
-    pub fn terminal_dir_for(&self, working_dir: &std::path::Path) -> std::path::PathBuf {
+    /// Get the terminal directory for a session (the default one if
+    /// `session_name` is None) of a specific working directory
+    pub fn terminal_dir_for(
+        &self,
+        working_dir: &std::path::Path,
+        session_name: Option<&str>,
+    ) -> std::path::PathBuf {
         let encoded = crate::session::encode_path_for_filename(working_dir);
-        self.terminals_dir().join(encoded)
+        let dir = self.terminals_dir().join(encoded);
+        match session_name {
+            Some(name) => dir.join("named").join(name),
+            None => dir,
+        }
     }
 
     /// Get the config file path
@@ -1800,6 +2271,21 @@ impl DirectoryContext {
     pub fn plugins_dir(&self) -> std::path::PathBuf {
         self.config_dir.join("plugins")
     }
+
+    /// Get the plugin package registry file path
+    pub fn plugin_registry_path(&self) -> std::path::PathBuf {
+        self.config_dir.join("plugin-packages.json")
+    }
+
+    /// Get the directory holding the clones of git plugin packages
+    pub fn plugin_packages_dir(&self) -> std::path::PathBuf {
+        self.config_dir.join("plugin-packages")
+    }
+
+    /// Get the user snippets directory path
+    pub fn snippets_dir(&self) -> std::path::PathBuf {
+        self.config_dir.join("snippets")
+    }
 }
 
 #[cfg(test)]
@@ -1888,7 +2374,10 @@ mod tests {
 
         // User's rust override should be present
         assert!(loaded.lsp.contains_key("rust"));
-        assert_eq!(loaded.lsp["rust"].command, "custom-rust-analyzer");
+        assert_eq!(
+            loaded.lsp["rust"].servers()[0].command,
+            "custom-rust-analyzer"
+        );
 
         // Default LSP servers should also be present (merged from defaults)
         assert!(
@@ -1910,6 +2399,33 @@ mod tests {
         assert!(loaded.languages.contains_key("typescript"));
     }
 
+    #[test]
+    fn test_lsp_config_accepts_a_list_of_servers() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "lsp": {
+                    "python": [
+                        { "command": "pyright-langserver", "args": ["--stdio"], "priority": 1 },
+                        { "name": "ruff", "command": "/usr/bin/ruff", "args": ["server"] }
+                    ],
+                    "rust": { "command": "rust-analyzer" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let python = config.lsp["python"].servers();
+        assert_eq!(python.len(), 2);
+        assert_eq!(python[0].server_name(), "pyright-langserver");
+        assert_eq!(python[0].priority, 1);
+        assert_eq!(python[1].server_name(), "ruff");
+        assert_eq!(python[1].priority, 0);
+
+        let rust = config.lsp["rust"].servers();
+        assert_eq!(rust.len(), 1);
+        assert_eq!(rust[0].server_name(), "rust-analyzer");
+    }
+
     #[test]
     fn test_empty_config_gets_all_defaults() {
         let temp_dir = tempfile::tempdir().unwrap();
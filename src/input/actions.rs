@@ -1630,6 +1630,17 @@ pub fn action_to_events(
         | Action::SaveAs
         | Action::Open
         | Action::SwitchProject
+        | Action::SaveSessionAs
+        | Action::SwitchSession
+        | Action::RenameSession
+        | Action::DeleteSession
+        | Action::InstallPluginPackage
+        | Action::UpdatePluginPackages
+        | Action::RemovePluginPackage
+        | Action::EnablePluginPackage
+        | Action::DisablePluginPackage
+        | Action::PinPluginPackage
+        | Action::UnpinPluginPackage
         | Action::New
         | Action::Close
         | Action::CloseTab
@@ -1661,6 +1672,8 @@ pub fn action_to_events(
         | Action::ToggleMaximizeSplit
         | Action::Undo
         | Action::Redo
+        | Action::SwitchUndoBranch
+        | Action::UndoHistory
         | Action::GoToMatchingBracket
         | Action::JumpToNextError
         | Action::JumpToPreviousError
@@ -1669,6 +1682,8 @@ pub fn action_to_events(
         | Action::IndentSelection
         | Action::DedentSelection
         | Action::ToggleComment
+        | Action::ToggleBlockComment
+        | Action::FormatBuffer
         | Action::SetBookmark(_)
         | Action::JumpToBookmark(_)
         | Action::ClearBookmark(_)
@@ -1680,11 +1695,13 @@ pub fn action_to_events(
         | Action::StartMacroRecording
         | Action::StopMacroRecording
         | Action::PlayMacro(_)
+        | Action::PlayNamedMacro(_)
         | Action::ToggleMacroRecording(_)
         | Action::ShowMacro(_)
         | Action::ListMacros
         | Action::PromptRecordMacro
         | Action::PromptPlayMacro
+        | Action::PromptShowMacro
         | Action::PlayLastMacro
         | Action::PromptSetBookmark
         | Action::PromptJumpToBookmark
@@ -1739,6 +1756,13 @@ pub fn action_to_events(
         | Action::FileExplorerNewDirectory
         | Action::FileExplorerDelete
         | Action::FileExplorerRename
+        | Action::FileExplorerUndo
+        | Action::FileExplorerCopy
+        | Action::FileExplorerCut
+        | Action::FileExplorerPaste
+        | Action::FileExplorerDuplicate
+        | Action::FileExplorerExtendSelectionUp
+        | Action::FileExplorerExtendSelectionDown
         | Action::FileExplorerToggleHidden
         | Action::FileExplorerToggleGitignored
         | Action::LspCompletion
@@ -1754,6 +1778,13 @@ pub fn action_to_events(
         | Action::ToggleMouseHover
         | Action::ToggleLineNumbers
         | Action::ToggleMouseCapture
+        | Action::Fold
+        | Action::Unfold
+        | Action::FoldAll
+        | Action::UnfoldAll
+        | Action::UnfoldToLevel
+        | Action::InsertSnippet
+        | Action::ShrinkSelection
         | Action::DumpConfig
         | Action::Search
         | Action::FindInSelection
@@ -1761,6 +1792,16 @@ pub fn action_to_events(
         | Action::FindPrevious
         | Action::Replace
         | Action::QueryReplace
+        | Action::ProjectSearch
+        | Action::ProjectReplace
+        | Action::ToggleBreakpoint
+        | Action::DebugStart
+        | Action::DebugContinue
+        | Action::DebugPause
+        | Action::DebugStepOver
+        | Action::DebugStepInto
+        | Action::DebugStepOut
+        | Action::DebugStop
         | Action::MenuActivate
         | Action::MenuClose
         | Action::MenuLeft
@@ -1778,6 +1819,8 @@ pub fn action_to_events(
         | Action::SelectKeybindingMap
         | Action::Revert
         | Action::ToggleAutoRevert
+        | Action::ReopenWithEncoding
+        | Action::SaveWithEncoding
         | Action::OpenTerminal
         | Action::CloseTerminal
         | Action::FocusTerminal
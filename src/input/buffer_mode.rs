@@ -91,6 +91,93 @@ impl ModeRegistry {
 
         registry.register(special_mode);
 
+        // Built-in mode for the undo history panel
+        let undo_history_mode = BufferMode::new("undo-history")
+            .with_parent("special")
+            .with_binding(KeyCode::Enter, KeyModifiers::NONE, "undo-history-jump")
+            .with_binding(
+                KeyCode::Char('p'),
+                KeyModifiers::NONE,
+                "undo-history-preview",
+            )
+            .with_binding(KeyCode::Char('q'), KeyModifiers::NONE, "undo-history-close")
+            .with_binding(KeyCode::Esc, KeyModifiers::NONE, "undo-history-close");
+
+        registry.register(undo_history_mode);
+
+        // Built-in mode for the project search panel
+        let project_search_mode = BufferMode::new("project-search")
+            .with_parent("special")
+            .with_binding(KeyCode::Enter, KeyModifiers::NONE, "project-search-open")
+            .with_binding(
+                KeyCode::Char('r'),
+                KeyModifiers::NONE,
+                "project-search-replace",
+            )
+            .with_binding(
+                KeyCode::Char('R'),
+                KeyModifiers::SHIFT,
+                "project-search-replace-all",
+            )
+            .with_binding(
+                KeyCode::Char('i'),
+                KeyModifiers::NONE,
+                "project-search-include",
+            )
+            .with_binding(
+                KeyCode::Char('x'),
+                KeyModifiers::NONE,
+                "project-search-exclude",
+            )
+            .with_binding(
+                KeyCode::Char('g'),
+                KeyModifiers::NONE,
+                "project-search-refresh",
+            )
+            .with_binding(
+                KeyCode::Char('q'),
+                KeyModifiers::NONE,
+                "project-search-close",
+            )
+            .with_binding(KeyCode::Esc, KeyModifiers::NONE, "project-search-close");
+
+        registry.register(project_search_mode);
+
+        // Built-in mode for the debug panel
+        let debug_panel_mode = BufferMode::new("debug-panel")
+            .with_parent("special")
+            .with_binding(
+                KeyCode::Enter,
+                KeyModifiers::NONE,
+                "debug-panel-select-frame",
+            )
+            .with_binding(
+                KeyCode::Char('c'),
+                KeyModifiers::NONE,
+                "debug-panel-continue",
+            )
+            .with_binding(
+                KeyCode::Char('n'),
+                KeyModifiers::NONE,
+                "debug-panel-step-over",
+            )
+            .with_binding(
+                KeyCode::Char('s'),
+                KeyModifiers::NONE,
+                "debug-panel-step-into",
+            )
+            .with_binding(
+                KeyCode::Char('o'),
+                KeyModifiers::NONE,
+                "debug-panel-step-out",
+            )
+            .with_binding(KeyCode::Char('p'), KeyModifiers::NONE, "debug-panel-pause")
+            .with_binding(KeyCode::Char('x'), KeyModifiers::NONE, "debug-panel-stop")
+            .with_binding(KeyCode::Char('q'), KeyModifiers::NONE, "debug-panel-close")
+            .with_binding(KeyCode::Esc, KeyModifiers::NONE, "debug-panel-close");
+
+        registry.register(debug_panel_mode);
+
         registry
     }
 
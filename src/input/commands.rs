@@ -142,6 +142,94 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Save Session As".to_string(),
+            description: "Save the layout and open files as a named session".to_string(),
+            action: Action::SaveSessionAs,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Switch Session".to_string(),
+            description: "Switch to another session of this project".to_string(),
+            action: Action::SwitchSession,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Rename Session".to_string(),
+            description: "Rename the active named session".to_string(),
+            action: Action::RenameSession,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Delete Session".to_string(),
+            description: "Delete a named session of this project".to_string(),
+            action: Action::DeleteSession,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Install Plugin".to_string(),
+            description: "Install a plugin package from a git URL (append #ref for a branch, tag or commit) or a local directory".to_string(),
+            action: Action::InstallPluginPackage,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Update Plugins".to_string(),
+            description: "Update the installed plugin packages that aren't pinned".to_string(),
+            action: Action::UpdatePluginPackages,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Remove Plugin".to_string(),
+            description: "Uninstall a plugin package".to_string(),
+            action: Action::RemovePluginPackage,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Enable Plugin".to_string(),
+            description: "Enable a disabled plugin package".to_string(),
+            action: Action::EnablePluginPackage,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Disable Plugin".to_string(),
+            description: "Disable a plugin package without uninstalling it".to_string(),
+            action: Action::DisablePluginPackage,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Pin Plugin".to_string(),
+            description: "Keep a plugin package at its current commit when updating".to_string(),
+            action: Action::PinPluginPackage,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Unpin Plugin".to_string(),
+            description: "Let updates move a pinned plugin package again".to_string(),
+            action: Action::UnpinPluginPackage,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Save File".to_string(),
             description: "Save the current buffer to disk".to_string(),
@@ -198,6 +286,22 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Reopen with Encoding".to_string(),
+            description: "Reload the file from disk using a different text encoding".to_string(),
+            action: Action::ReopenWithEncoding,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Save with Encoding".to_string(),
+            description: "Save the file in a different text encoding".to_string(),
+            action: Action::SaveWithEncoding,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Quit".to_string(),
             description: "Exit the editor".to_string(),
@@ -223,6 +327,22 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Switch Undo Branch".to_string(),
+            description: "Choose which undone branch of edits redo follows".to_string(),
+            action: Action::SwitchUndoBranch,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Undo History".to_string(),
+            description: "Show the undo tree to preview and jump to any state".to_string(),
+            action: Action::UndoHistory,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Copy".to_string(),
             description: "Copy selection to clipboard".to_string(),
@@ -338,12 +458,20 @@ pub fn get_all_commands() -> Vec<Command> {
         },
         Command {
             name: "Expand Selection".to_string(),
-            description: "Expand the current selection by one word".to_string(),
+            description: "Expand the selection to the enclosing syntax node".to_string(),
             action: Action::ExpandSelection,
             contexts: vec![KeyContext::Normal],
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Shrink Selection".to_string(),
+            description: "Undo the last Expand Selection".to_string(),
+            action: Action::ShrinkSelection,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         // Multi-cursor
         Command {
             name: "Add Cursor Above".to_string(),
@@ -579,7 +707,7 @@ pub fn get_all_commands() -> Vec<Command> {
         },
         Command {
             name: "File Explorer: Delete".to_string(),
-            description: "Delete the selected file or directory".to_string(),
+            description: "Move the selected file or directory to the trash".to_string(),
             action: Action::FileExplorerDelete,
             contexts: vec![KeyContext::FileExplorer],
             custom_contexts: vec![],
@@ -593,6 +721,47 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "File Explorer: Undo File Operation".to_string(),
+            description: "Undo the last delete, rename or creation in the file explorer"
+                .to_string(),
+            action: Action::FileExplorerUndo,
+            contexts: vec![KeyContext::FileExplorer],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "File Explorer: Copy".to_string(),
+            description: "Copy the selected files for pasting elsewhere".to_string(),
+            action: Action::FileExplorerCopy,
+            contexts: vec![KeyContext::FileExplorer],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "File Explorer: Cut".to_string(),
+            description: "Cut the selected files to move them on paste".to_string(),
+            action: Action::FileExplorerCut,
+            contexts: vec![KeyContext::FileExplorer],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "File Explorer: Paste".to_string(),
+            description: "Paste copied or cut files into the selected directory".to_string(),
+            action: Action::FileExplorerPaste,
+            contexts: vec![KeyContext::FileExplorer],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "File Explorer: Duplicate".to_string(),
+            description: "Copy the selected files next to themselves".to_string(),
+            action: Action::FileExplorerDuplicate,
+            contexts: vec![KeyContext::FileExplorer],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Toggle Hidden Files".to_string(),
             description: "Show or hide hidden files in the file explorer".to_string(),
@@ -686,6 +855,137 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Search in Project".to_string(),
+            description: "Search all files in the project".to_string(),
+            action: Action::ProjectSearch,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Replace in Project".to_string(),
+            description: "Preview and apply replacements across all files in the project"
+                .to_string(),
+            action: Action::ProjectReplace,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        // Code folding
+        Command {
+            name: "Fold".to_string(),
+            description: "Fold the region around the cursor".to_string(),
+            action: Action::Fold,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Unfold".to_string(),
+            description: "Unfold the folded region on the current line".to_string(),
+            action: Action::Unfold,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Fold All".to_string(),
+            description: "Fold every region in the buffer".to_string(),
+            action: Action::FoldAll,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Unfold All".to_string(),
+            description: "Unfold every folded region in the buffer".to_string(),
+            action: Action::UnfoldAll,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Insert Snippet".to_string(),
+            description: "Insert a snippet for the current language".to_string(),
+            action: Action::InsertSnippet,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Unfold to Level".to_string(),
+            description: "Fold regions nested deeper than a given level".to_string(),
+            action: Action::UnfoldToLevel,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        // Debugging
+        Command {
+            name: "Toggle Breakpoint".to_string(),
+            description: "Add or remove a breakpoint on the current line".to_string(),
+            action: Action::ToggleBreakpoint,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Start Debugging".to_string(),
+            description: "Debug the current file with its language's debug adapter".to_string(),
+            action: Action::DebugStart,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Debug: Continue".to_string(),
+            description: "Resume the stopped debuggee".to_string(),
+            action: Action::DebugContinue,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Debug: Pause".to_string(),
+            description: "Interrupt the running debuggee".to_string(),
+            action: Action::DebugPause,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Debug: Step Over".to_string(),
+            description: "Run to the next line".to_string(),
+            action: Action::DebugStepOver,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Debug: Step Into".to_string(),
+            description: "Step into the call on the current line".to_string(),
+            action: Action::DebugStepInto,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Debug: Step Out".to_string(),
+            description: "Run until the current function returns".to_string(),
+            action: Action::DebugStepOut,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Stop Debugging".to_string(),
+            description: "End the debug session and terminate the debuggee".to_string(),
+            action: Action::DebugStop,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         // Navigation
         Command {
             name: "Go to Line".to_string(),
@@ -801,6 +1101,24 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Toggle Block Comment".to_string(),
+            description: "Wrap the selection or current line in a block comment, or unwrap it"
+                .to_string(),
+            action: Action::ToggleBlockComment,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Format Buffer".to_string(),
+            description: "Format the buffer (or selection) with the language's formatter"
+                .to_string(),
+            action: Action::FormatBuffer,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Indent Selection".to_string(),
             description: "Increase indentation of selected lines".to_string(),
@@ -886,12 +1204,20 @@ pub fn get_all_commands() -> Vec<Command> {
         },
         Command {
             name: "Play Macro".to_string(),
-            description: "Play macro from a register (0-9)".to_string(),
+            description: "Play macro from a register (0-9) or by name".to_string(),
             action: Action::PromptPlayMacro,
             contexts: vec![KeyContext::Normal],
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Show Macro".to_string(),
+            description: "Open a macro as editable JSON; save the buffer to update it".to_string(),
+            action: Action::PromptShowMacro,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Play Last Macro".to_string(),
             description: "Play the last recorded macro (F12)".to_string(),
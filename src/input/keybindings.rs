@@ -242,6 +242,7 @@ pub enum Action {
     SelectWord,
     SelectLine,
     ExpandSelection,
+    ShrinkSelection,
 
     // Block/rectangular selection (column-wise)
     BlockSelectLeft,
@@ -281,12 +282,25 @@ pub enum Action {
     SaveAs,
     Open,
     SwitchProject,
+    SaveSessionAs,
+    SwitchSession,
+    RenameSession,
+    DeleteSession,
+    InstallPluginPackage,
+    UpdatePluginPackages,
+    RemovePluginPackage,
+    EnablePluginPackage,
+    DisablePluginPackage,
+    PinPluginPackage,
+    UnpinPluginPackage,
     New,
     Close,
     CloseTab,
     Quit,
     Revert,
     ToggleAutoRevert,
+    ReopenWithEncoding,
+    SaveWithEncoding,
 
     // Navigation
     GotoLine,
@@ -299,6 +313,8 @@ pub enum Action {
     IndentSelection,
     DedentSelection,
     ToggleComment,
+    ToggleBlockComment,
+    FormatBuffer,
 
     // Bookmarks
     SetBookmark(char),
@@ -316,11 +332,13 @@ pub enum Action {
     StartMacroRecording,
     StopMacroRecording,
     PlayMacro(char),
+    PlayNamedMacro(String),
     ToggleMacroRecording(char),
     ShowMacro(char),
     ListMacros,
     PromptRecordMacro,
     PromptPlayMacro,
+    PromptShowMacro,
     PlayLastMacro,
 
     // Bookmarks (prompt-based)
@@ -330,6 +348,8 @@ pub enum Action {
     // Undo/redo
     Undo,
     Redo,
+    SwitchUndoBranch,
+    UndoHistory,
 
     // View
     ScrollUp,
@@ -423,6 +443,13 @@ pub enum Action {
     FileExplorerNewDirectory,
     FileExplorerDelete,
     FileExplorerRename,
+    FileExplorerUndo,
+    FileExplorerCopy,
+    FileExplorerCut,
+    FileExplorerPaste,
+    FileExplorerDuplicate,
+    FileExplorerExtendSelectionUp,
+    FileExplorerExtendSelectionDown,
     FileExplorerToggleHidden,
     FileExplorerToggleGitignored,
 
@@ -445,6 +472,16 @@ pub enum Action {
     SetBackground,
     SetBackgroundBlend,
 
+    // Code folding
+    Fold,
+    Unfold,
+    FoldAll,
+    UnfoldAll,
+    UnfoldToLevel,
+
+    // Snippets
+    InsertSnippet,
+
     // Config operations
     DumpConfig,
 
@@ -454,7 +491,19 @@ pub enum Action {
     FindNext,
     FindPrevious,
     Replace,
-    QueryReplace, // Interactive replace (y/n/!/q for each match)
+    QueryReplace,   // Interactive replace (y/n/!/q for each match)
+    ProjectSearch,  // Search all files in the project
+    ProjectReplace, // Replace in all files of the project
+
+    // Debugging
+    ToggleBreakpoint,
+    DebugStart,
+    DebugContinue,
+    DebugPause,
+    DebugStepOver,
+    DebugStepInto,
+    DebugStepOut,
+    DebugStop,
 
     // Menu navigation
     MenuActivate,     // Open menu bar (Alt or F10)
@@ -539,6 +588,7 @@ impl Action {
             "select_word" => Some(Action::SelectWord),
             "select_line" => Some(Action::SelectLine),
             "expand_selection" => Some(Action::ExpandSelection),
+            "shrink_selection" => Some(Action::ShrinkSelection),
 
             // Block/rectangular selection
             "block_select_left" => Some(Action::BlockSelectLeft),
@@ -570,12 +620,25 @@ impl Action {
             "save_as" => Some(Action::SaveAs),
             "open" => Some(Action::Open),
             "switch_project" => Some(Action::SwitchProject),
+            "save_session_as" => Some(Action::SaveSessionAs),
+            "switch_session" => Some(Action::SwitchSession),
+            "rename_session" => Some(Action::RenameSession),
+            "delete_session" => Some(Action::DeleteSession),
+            "install_plugin" => Some(Action::InstallPluginPackage),
+            "update_plugins" => Some(Action::UpdatePluginPackages),
+            "remove_plugin" => Some(Action::RemovePluginPackage),
+            "enable_plugin" => Some(Action::EnablePluginPackage),
+            "disable_plugin" => Some(Action::DisablePluginPackage),
+            "pin_plugin" => Some(Action::PinPluginPackage),
+            "unpin_plugin" => Some(Action::UnpinPluginPackage),
             "new" => Some(Action::New),
             "close" => Some(Action::Close),
             "close_tab" => Some(Action::CloseTab),
             "quit" => Some(Action::Quit),
             "revert" => Some(Action::Revert),
             "toggle_auto_revert" => Some(Action::ToggleAutoRevert),
+            "reopen_with_encoding" => Some(Action::ReopenWithEncoding),
+            "save_with_encoding" => Some(Action::SaveWithEncoding),
             "goto_line" => Some(Action::GotoLine),
             "goto_matching_bracket" => Some(Action::GoToMatchingBracket),
             "jump_to_next_error" => Some(Action::JumpToNextError),
@@ -585,6 +648,8 @@ impl Action {
             "indent_selection" => Some(Action::IndentSelection),
             "dedent_selection" => Some(Action::DedentSelection),
             "toggle_comment" => Some(Action::ToggleComment),
+            "toggle_block_comment" => Some(Action::ToggleBlockComment),
+            "format_buffer" => Some(Action::FormatBuffer),
 
             "set_bookmark" => {
                 if let Some(serde_json::Value::String(c)) = args.get("char") {
@@ -619,6 +684,8 @@ impl Action {
             "play_macro" => {
                 if let Some(serde_json::Value::String(c)) = args.get("char") {
                     c.chars().next().map(Action::PlayMacro)
+                } else if let Some(serde_json::Value::String(name)) = args.get("name") {
+                    Some(Action::PlayNamedMacro(name.clone()))
                 } else {
                     None
                 }
@@ -640,12 +707,15 @@ impl Action {
             "list_macros" => Some(Action::ListMacros),
             "prompt_record_macro" => Some(Action::PromptRecordMacro),
             "prompt_play_macro" => Some(Action::PromptPlayMacro),
+            "prompt_show_macro" => Some(Action::PromptShowMacro),
             "play_last_macro" => Some(Action::PlayLastMacro),
             "prompt_set_bookmark" => Some(Action::PromptSetBookmark),
             "prompt_jump_to_bookmark" => Some(Action::PromptJumpToBookmark),
 
             "undo" => Some(Action::Undo),
             "redo" => Some(Action::Redo),
+            "switch_undo_branch" => Some(Action::SwitchUndoBranch),
+            "undo_history" => Some(Action::UndoHistory),
 
             "scroll_up" => Some(Action::ScrollUp),
             "scroll_down" => Some(Action::ScrollDown),
@@ -722,6 +792,13 @@ impl Action {
             "file_explorer_new_directory" => Some(Action::FileExplorerNewDirectory),
             "file_explorer_delete" => Some(Action::FileExplorerDelete),
             "file_explorer_rename" => Some(Action::FileExplorerRename),
+            "file_explorer_undo" => Some(Action::FileExplorerUndo),
+            "file_explorer_copy" => Some(Action::FileExplorerCopy),
+            "file_explorer_cut" => Some(Action::FileExplorerCut),
+            "file_explorer_paste" => Some(Action::FileExplorerPaste),
+            "file_explorer_duplicate" => Some(Action::FileExplorerDuplicate),
+            "file_explorer_extend_selection_up" => Some(Action::FileExplorerExtendSelectionUp),
+            "file_explorer_extend_selection_down" => Some(Action::FileExplorerExtendSelectionDown),
             "file_explorer_toggle_hidden" => Some(Action::FileExplorerToggleHidden),
             "file_explorer_toggle_gitignored" => Some(Action::FileExplorerToggleGitignored),
 
@@ -744,6 +821,13 @@ impl Action {
             "select_theme" => Some(Action::SelectTheme),
             "select_keybinding_map" => Some(Action::SelectKeybindingMap),
 
+            "fold" => Some(Action::Fold),
+            "unfold" => Some(Action::Unfold),
+            "fold_all" => Some(Action::FoldAll),
+            "unfold_all" => Some(Action::UnfoldAll),
+            "unfold_to_level" => Some(Action::UnfoldToLevel),
+            "insert_snippet" => Some(Action::InsertSnippet),
+
             "dump_config" => Some(Action::DumpConfig),
 
             "search" => Some(Action::Search),
@@ -752,6 +836,17 @@ impl Action {
             "find_previous" => Some(Action::FindPrevious),
             "replace" => Some(Action::Replace),
             "query_replace" => Some(Action::QueryReplace),
+            "project_search" => Some(Action::ProjectSearch),
+            "project_replace" => Some(Action::ProjectReplace),
+
+            "toggle_breakpoint" => Some(Action::ToggleBreakpoint),
+            "debug_start" => Some(Action::DebugStart),
+            "debug_continue" => Some(Action::DebugContinue),
+            "debug_pause" => Some(Action::DebugPause),
+            "debug_step_over" => Some(Action::DebugStepOver),
+            "debug_step_into" => Some(Action::DebugStepInto),
+            "debug_step_out" => Some(Action::DebugStepOut),
+            "debug_stop" => Some(Action::DebugStop),
 
             "menu_activate" => Some(Action::MenuActivate),
             "menu_close" => Some(Action::MenuClose),
@@ -1517,6 +1612,7 @@ impl KeybindingResolver {
             Action::SelectWord => "Select word under cursor".to_string(),
             Action::SelectLine => "Select current line".to_string(),
             Action::ExpandSelection => "Expand selection".to_string(),
+            Action::ShrinkSelection => "Shrink selection".to_string(),
             Action::BlockSelectLeft => "Block select left".to_string(),
             Action::BlockSelectRight => "Block select right".to_string(),
             Action::BlockSelectUp => "Block select up".to_string(),
@@ -1542,12 +1638,25 @@ impl KeybindingResolver {
             Action::SaveAs => "Save file as...".to_string(),
             Action::Open => "Open file".to_string(),
             Action::SwitchProject => "Switch project".to_string(),
+            Action::SaveSessionAs => "Save session as...".to_string(),
+            Action::SwitchSession => "Switch session".to_string(),
+            Action::RenameSession => "Rename session".to_string(),
+            Action::DeleteSession => "Delete session".to_string(),
+            Action::InstallPluginPackage => "Install plugin".to_string(),
+            Action::UpdatePluginPackages => "Update plugins".to_string(),
+            Action::RemovePluginPackage => "Remove plugin".to_string(),
+            Action::EnablePluginPackage => "Enable plugin".to_string(),
+            Action::DisablePluginPackage => "Disable plugin".to_string(),
+            Action::PinPluginPackage => "Pin plugin".to_string(),
+            Action::UnpinPluginPackage => "Unpin plugin".to_string(),
             Action::New => "New file".to_string(),
             Action::Close => "Close file".to_string(),
             Action::CloseTab => "Close tab".to_string(),
             Action::Quit => "Quit editor".to_string(),
             Action::Revert => "Revert to saved file".to_string(),
             Action::ToggleAutoRevert => "Toggle auto-revert mode".to_string(),
+            Action::ReopenWithEncoding => "Reopen file with encoding".to_string(),
+            Action::SaveWithEncoding => "Save file with encoding".to_string(),
             Action::GotoLine => "Go to line number".to_string(),
             Action::GoToMatchingBracket => "Go to matching bracket".to_string(),
             Action::JumpToNextError => "Jump to next error/diagnostic".to_string(),
@@ -1558,6 +1667,8 @@ impl KeybindingResolver {
             Action::IndentSelection => "Indent selection".to_string(),
             Action::DedentSelection => "Dedent selection".to_string(),
             Action::ToggleComment => "Toggle comment".to_string(),
+            Action::ToggleBlockComment => "Toggle block comment".to_string(),
+            Action::FormatBuffer => "Format buffer".to_string(),
             Action::SetBookmark(c) => format!("Set bookmark '{}'", c),
             Action::JumpToBookmark(c) => format!("Jump to bookmark '{}'", c),
             Action::ClearBookmark(c) => format!("Clear bookmark '{}'", c),
@@ -1569,16 +1680,20 @@ impl KeybindingResolver {
             Action::StartMacroRecording => "Start macro recording".to_string(),
             Action::StopMacroRecording => "Stop macro recording".to_string(),
             Action::PlayMacro(c) => format!("Play macro '{}'", c),
+            Action::PlayNamedMacro(name) => format!("Play macro '{}'", name),
             Action::ToggleMacroRecording(c) => format!("Toggle macro recording for '{}'", c),
             Action::ShowMacro(c) => format!("Show macro '{}' in buffer", c),
             Action::ListMacros => "List all recorded macros".to_string(),
             Action::PromptRecordMacro => "Record macro (prompts for register)".to_string(),
             Action::PromptPlayMacro => "Play macro (prompts for register)".to_string(),
+            Action::PromptShowMacro => "Show macro (prompts for register)".to_string(),
             Action::PlayLastMacro => "Play last recorded macro".to_string(),
             Action::PromptSetBookmark => "Set bookmark (prompts for register)".to_string(),
             Action::PromptJumpToBookmark => "Jump to bookmark (prompts for register)".to_string(),
             Action::Undo => "Undo".to_string(),
             Action::Redo => "Redo".to_string(),
+            Action::SwitchUndoBranch => "Switch undo branch".to_string(),
+            Action::UndoHistory => "Undo history".to_string(),
             Action::ScrollUp => "Scroll up".to_string(),
             Action::ScrollDown => "Scroll down".to_string(),
             Action::ShowHelp => "Show manual".to_string(),
@@ -1648,6 +1763,17 @@ impl KeybindingResolver {
             Action::FileExplorerNewDirectory => "File explorer: new directory".to_string(),
             Action::FileExplorerDelete => "File explorer: delete".to_string(),
             Action::FileExplorerRename => "File explorer: rename".to_string(),
+            Action::FileExplorerUndo => "File explorer: undo file operation".to_string(),
+            Action::FileExplorerCopy => "File explorer: copy".to_string(),
+            Action::FileExplorerCut => "File explorer: cut".to_string(),
+            Action::FileExplorerPaste => "File explorer: paste".to_string(),
+            Action::FileExplorerDuplicate => "File explorer: duplicate".to_string(),
+            Action::FileExplorerExtendSelectionUp => {
+                "File explorer: extend selection up".to_string()
+            }
+            Action::FileExplorerExtendSelectionDown => {
+                "File explorer: extend selection down".to_string()
+            }
             Action::FileExplorerToggleHidden => "File explorer: toggle hidden files".to_string(),
             Action::FileExplorerToggleGitignored => {
                 "File explorer: toggle gitignored files".to_string()
@@ -1667,6 +1793,12 @@ impl KeybindingResolver {
             Action::ToggleMouseCapture => "Toggle mouse support".to_string(),
             Action::SetBackground => "Set ANSI background file".to_string(),
             Action::SetBackgroundBlend => "Set background blend ratio".to_string(),
+            Action::Fold => "Fold region at cursor".to_string(),
+            Action::Unfold => "Unfold region at cursor".to_string(),
+            Action::FoldAll => "Fold all regions".to_string(),
+            Action::UnfoldAll => "Unfold all regions".to_string(),
+            Action::UnfoldToLevel => "Unfold to nesting level".to_string(),
+            Action::InsertSnippet => "Insert a snippet".to_string(),
             Action::DumpConfig => "Dump config to file".to_string(),
             Action::Search => "Search for text in buffer".to_string(),
             Action::FindInSelection => "Search within selection".to_string(),
@@ -1674,6 +1806,16 @@ impl KeybindingResolver {
             Action::FindPrevious => "Find previous search match".to_string(),
             Action::Replace => "Replace text in buffer".to_string(),
             Action::QueryReplace => "Interactive replace (y/n/!/q for each match)".to_string(),
+            Action::ProjectSearch => "Search in project".to_string(),
+            Action::ProjectReplace => "Replace in project".to_string(),
+            Action::ToggleBreakpoint => "Toggle breakpoint".to_string(),
+            Action::DebugStart => "Start debugging".to_string(),
+            Action::DebugContinue => "Continue debuggee".to_string(),
+            Action::DebugPause => "Pause debuggee".to_string(),
+            Action::DebugStepOver => "Step over".to_string(),
+            Action::DebugStepInto => "Step into".to_string(),
+            Action::DebugStepOut => "Step out".to_string(),
+            Action::DebugStop => "Stop debugging".to_string(),
             Action::MenuActivate => "Activate menu bar".to_string(),
             Action::MenuClose => "Close menu".to_string(),
             Action::MenuLeft => "Navigate to previous menu".to_string(),
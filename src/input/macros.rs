@@ -0,0 +1,212 @@
+//! Keyboard macro storage
+//!
+//! Recorded macros are kept in registers (`0`-`9`) and may be given a name, so
+//! they can be played from a keybinding (`play_macro` with `{"name": ...}`)
+//! or the Play Macro prompt. The whole store is persisted as JSON in the data
+//! directory, so macros survive restarts regardless of session restore.
+//!
+//! A single macro can be shown in a buffer as an editable JSON definition (see
+//! [`Macro::to_definition`]); saving that buffer parses it back with
+//! [`Macro::parse_definition`].
+
+use crate::input::keybindings::Action;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// A recorded keyboard macro
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Macro {
+    /// Optional name, usable instead of the register to play the macro
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Actions replayed in order
+    pub actions: Vec<Action>,
+}
+
+impl Macro {
+    pub fn new(actions: Vec<Action>) -> Self {
+        Self {
+            name: None,
+            actions,
+        }
+    }
+
+    /// Render the macro as an editable JSON definition, headed by `//`
+    /// comment lines describing it
+    pub fn to_definition(&self, key: char) -> serde_json::Result<String> {
+        // Always include "name" so users can see where to set it
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "name": self.name,
+            "actions": self.actions,
+        }))?;
+        Ok(format!(
+            "// Macro '{}' ({} actions)\n// Edit the definition below and save to update the macro\n\n{}\n",
+            key,
+            self.actions.len(),
+            json
+        ))
+    }
+
+    /// Parse a definition produced by [`Macro::to_definition`]
+    ///
+    /// Lines starting with `//` are ignored. A bare array of actions is also
+    /// accepted. Empty names are treated as no name.
+    pub fn parse_definition(text: &str) -> Result<Self, String> {
+        let json: String = text
+            .lines()
+            .filter(|line| !line.trim_start().starts_with("//"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut parsed = match serde_json::from_str::<Macro>(&json) {
+            Ok(parsed) => parsed,
+            Err(e) => match serde_json::from_str::<Vec<Action>>(&json) {
+                Ok(actions) => Macro::new(actions),
+                Err(_) => return Err(e.to_string()),
+            },
+        };
+        parsed.name = parsed
+            .name
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
+        Ok(parsed)
+    }
+}
+
+/// All recorded macros, by register
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MacroStore {
+    macros: BTreeMap<char, Macro>,
+}
+
+impl MacroStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: char) -> Option<&Macro> {
+        self.macros.get(&key)
+    }
+
+    /// Store a macro in a register, replacing any previous one
+    pub fn insert(&mut self, key: char, mac: Macro) {
+        self.macros.insert(key, mac);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.macros.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.macros.len()
+    }
+
+    /// Macros in register order
+    pub fn iter(&self) -> impl Iterator<Item = (char, &Macro)> {
+        self.macros.iter().map(|(key, mac)| (*key, mac))
+    }
+
+    /// Register of the macro with the given name
+    pub fn find_by_name(&self, name: &str) -> Option<char> {
+        self.iter()
+            .find(|(_, mac)| mac.name.as_deref() == Some(name))
+            .map(|(key, _)| key)
+    }
+
+    /// Resolve user input to a register: either a register itself or the
+    /// name of a macro
+    pub fn resolve(&self, input: &str) -> Option<char> {
+        let input = input.trim();
+        let mut chars = input.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_digit() => Some(c),
+            _ => self.find_by_name(input),
+        }
+    }
+
+    /// Save the store as JSON
+    pub fn save_to_file(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, json)
+    }
+
+    /// Load the store from a file; a missing file yields an empty store
+    pub fn load_from_file(path: &Path) -> std::io::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+        let json = std::fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(std::io::Error::other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Macro {
+        Macro {
+            name: Some("shout".to_string()),
+            actions: vec![Action::InsertChar('!'), Action::MoveLineEnd],
+        }
+    }
+
+    #[test]
+    fn test_definition_round_trip() {
+        let mac = sample();
+        let text = mac.to_definition('3').unwrap();
+        assert!(text.starts_with("// Macro '3' (2 actions)"));
+        assert_eq!(Macro::parse_definition(&text).unwrap(), mac);
+
+        // Unnamed macros show an explicit null name
+        let unnamed = Macro::new(vec![Action::MoveLeft]);
+        let text = unnamed.to_definition('0').unwrap();
+        assert!(text.contains("\"name\": null"));
+        assert_eq!(Macro::parse_definition(&text).unwrap(), unnamed);
+    }
+
+    #[test]
+    fn test_parse_definition_variants() {
+        let mac = Macro::parse_definition("// old format\n[\"MoveLeft\"]").unwrap();
+        assert_eq!(mac, Macro::new(vec![Action::MoveLeft]));
+
+        let mac = Macro::parse_definition(r#"{"name": "  ", "actions": []}"#).unwrap();
+        assert_eq!(mac.name, None);
+
+        assert!(Macro::parse_definition(r#"{"actions": ["NoSuchAction"]}"#).is_err());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut store = MacroStore::new();
+        store.insert('3', sample());
+        assert_eq!(store.resolve("3"), Some('3'));
+        assert_eq!(store.resolve("7"), Some('7'));
+        assert_eq!(store.resolve(" shout "), Some('3'));
+        assert_eq!(store.resolve("whisper"), None);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("data").join("macros.json");
+        assert!(MacroStore::load_from_file(&path).unwrap().is_empty());
+
+        let mut store = MacroStore::new();
+        store.insert('3', sample());
+        store.insert('1', Macro::new(vec![Action::Undo]));
+        store.save_to_file(&path).unwrap();
+
+        let loaded = MacroStore::load_from_file(&path).unwrap();
+        assert_eq!(loaded, store);
+        assert_eq!(
+            loaded.iter().map(|(key, _)| key).collect::<Vec<_>>(),
+            vec!['1', '3']
+        );
+    }
+}
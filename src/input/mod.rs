@@ -9,5 +9,7 @@ pub mod commands;
 pub mod fuzzy;
 pub mod input_history;
 pub mod keybindings;
+pub mod macros;
 pub mod multi_cursor;
 pub mod position_history;
+pub mod snippets;
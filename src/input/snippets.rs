@@ -0,0 +1,221 @@
+//! User-defined snippets
+//!
+//! Snippets are read from the `snippets` directory of the config directory, in
+//! the VS Code snippet file format. A `<language>.json` file holds snippets for
+//! one language (named as in the `languages` config section); a
+//! `*.code-snippets` file holds snippets for the languages listed in each
+//! snippet's `scope`, or for every language if it has none.
+//!
+//! Bodies use the snippet syntax parsed by [`crate::primitives::snippet`].
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// A snippet from a user snippet file
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnippetDefinition {
+    pub name: String,
+    /// Words that trigger the snippet in completion
+    pub prefixes: Vec<String>,
+    /// Body in snippet syntax
+    pub body: String,
+    pub description: Option<String>,
+    /// Languages the snippet applies to (empty for all)
+    pub languages: Vec<String>,
+}
+
+/// A snippet as written in a snippet file
+#[derive(Deserialize)]
+struct RawSnippet {
+    #[serde(default)]
+    prefix: Option<OneOrMany>,
+    body: OneOrMany,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+/// A string or a list of strings (body lines, or several prefixes)
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            OneOrMany::One(value) => vec![value],
+            OneOrMany::Many(values) => values,
+        }
+    }
+}
+
+/// All user snippets
+#[derive(Debug, Clone, Default)]
+pub struct SnippetStore {
+    snippets: Vec<SnippetDefinition>,
+}
+
+impl SnippetStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snippets.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.snippets.len()
+    }
+
+    /// Snippets available in a buffer of `language` (`None` for buffers
+    /// without a known language, which only get global snippets)
+    pub fn for_language<'a>(
+        &'a self,
+        language: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a SnippetDefinition> + 'a {
+        self.snippets.iter().filter(move |snippet| {
+            snippet.languages.is_empty()
+                || language.is_some_and(|lang| snippet.languages.iter().any(|l| l == lang))
+        })
+    }
+
+    /// Parse the contents of a snippet file
+    ///
+    /// `language` is the language of a `<language>.json` file, or `None` for a
+    /// `.code-snippets` file. Lines starting with `//` are ignored.
+    pub fn parse_file(
+        text: &str,
+        language: Option<&str>,
+    ) -> Result<Vec<SnippetDefinition>, String> {
+        let json: String = text
+            .lines()
+            .filter(|line| !line.trim_start().starts_with("//"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let raw: BTreeMap<String, RawSnippet> =
+            serde_json::from_str(&json).map_err(|e| e.to_string())?;
+
+        Ok(raw
+            .into_iter()
+            .map(|(name, raw)| {
+                let languages = match (language, raw.scope) {
+                    (Some(language), _) => vec![language.to_string()],
+                    (None, Some(scope)) => scope
+                        .split(',')
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .collect(),
+                    (None, None) => Vec::new(),
+                };
+                SnippetDefinition {
+                    name,
+                    prefixes: raw.prefix.map(OneOrMany::into_vec).unwrap_or_default(),
+                    body: raw.body.into_vec().join("\n"),
+                    description: raw.description,
+                    languages,
+                }
+            })
+            .collect())
+    }
+
+    /// Load all snippet files in `dir`; a missing directory yields an empty
+    /// store and files that fail to parse are skipped with a warning
+    pub fn load_from_dir(dir: &Path) -> Self {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Self::new();
+        };
+        let mut paths: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+        paths.sort();
+
+        let mut snippets = Vec::new();
+        for path in paths {
+            let language = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("json") => path.file_stem().and_then(|stem| stem.to_str()),
+                Some("code-snippets") => None,
+                _ => continue,
+            };
+            let parsed = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| Self::parse_file(&text, language));
+            match parsed {
+                Ok(parsed) => snippets.extend(parsed),
+                Err(e) => tracing::warn!("Failed to load snippets from {:?}: {}", path, e),
+            }
+        }
+        Self { snippets }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_language_file() {
+        let text = r##"// Rust snippets
+{
+    "Print": {
+        "prefix": "pr",
+        "body": ["println!(\"$1\");", "$0"],
+        "description": "Print a line"
+    },
+    "Test": {
+        "prefix": ["test", "tfn"],
+        "body": "#[test]\nfn ${1:name}() {}"
+    }
+}"##;
+        let snippets = SnippetStore::parse_file(text, Some("rust")).unwrap();
+
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].name, "Print");
+        assert_eq!(snippets[0].prefixes, vec!["pr"]);
+        assert_eq!(snippets[0].body, "println!(\"$1\");\n$0");
+        assert_eq!(snippets[0].description.as_deref(), Some("Print a line"));
+        assert_eq!(snippets[1].prefixes, vec!["test", "tfn"]);
+        assert_eq!(snippets[1].languages, vec!["rust"]);
+
+        assert!(SnippetStore::parse_file("{\"x\": {}}", None).is_err());
+    }
+
+    #[test]
+    fn test_load_from_dir_and_scopes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        assert!(SnippetStore::load_from_dir(&dir.join("missing")).is_empty());
+
+        std::fs::write(
+            dir.join("python.json"),
+            r#"{"Main": {"prefix": "main", "body": "if __name__ == '__main__':"}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("shared.code-snippets"),
+            r#"{
+                "Todo": {"prefix": "todo", "body": "TODO: $0"},
+                "Fixme": {"prefix": "fixme", "body": "FIXME", "scope": "rust, python"}
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let store = SnippetStore::load_from_dir(dir);
+        assert_eq!(store.len(), 3);
+
+        let names = |language| {
+            store
+                .for_language(language)
+                .map(|s| s.name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(Some("python")), vec!["Main", "Fixme", "Todo"]);
+        assert_eq!(names(Some("rust")), vec!["Fixme", "Todo"]);
+        assert_eq!(names(None), vec!["Todo"]);
+    }
+}
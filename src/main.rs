@@ -9,17 +9,20 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
+use fresh::services::fs::FsManager;
 #[cfg(target_os = "linux")]
 use fresh::services::gpm::{gpm_to_crossterm, GpmClient};
+use fresh::services::remote::{self, RemoteConnection, RemoteFsBackend, RemoteTarget};
 use fresh::services::tracing_setup;
 use fresh::{
     app::Editor, config, config::DirectoryContext, services::release_checker,
-    services::signal_handler, services::warning_log::WarningLogHandle,
+    services::signal_handler, services::warning_log::WarningLogHandle, session,
 };
 use ratatui::Terminal;
 use std::{
     io::{self, stdout},
     path::PathBuf,
+    sync::Arc,
     time::Duration,
 };
 
@@ -29,7 +32,7 @@ use std::{
 #[command(about = "A terminal text editor with multi-cursor support", long_about = None)]
 #[command(version)]
 struct Args {
-    /// File to open
+    /// File or directory to open, or [user@]host:path to edit over SSH
     #[arg(value_name = "FILE")]
     file: Option<PathBuf>,
 
@@ -52,6 +55,19 @@ struct Args {
     /// Don't restore previous session (start fresh)
     #[arg(long)]
     no_session: bool,
+
+    /// Save and restore the named session NAME instead of the working
+    /// directory's default session
+    #[arg(long, value_name = "NAME", conflicts_with = "no_session")]
+    session: Option<String>,
+
+    /// Command that starts the agent on the remote host when editing over SSH
+    #[arg(long, value_name = "COMMAND", default_value = remote::DEFAULT_AGENT_COMMAND)]
+    remote_agent_command: String,
+
+    /// Serve a remote editor on stdin/stdout (started by the editor over SSH)
+    #[arg(long, hide = true)]
+    remote_agent: bool,
 }
 
 /// Parsed file location from CLI argument in file:line:col format
@@ -66,6 +82,7 @@ struct IterationOutcome {
     loop_result: io::Result<()>,
     update_result: Option<release_checker::ReleaseCheckResult>,
     restart_dir: Option<PathBuf>,
+    restart_session: Option<Option<String>>,
 }
 
 struct SetupState {
@@ -78,6 +95,7 @@ struct SetupState {
     show_file_explorer: bool,
     dir_context: DirectoryContext,
     current_working_dir: Option<PathBuf>,
+    remote: Option<Arc<RemoteConnection>>,
     #[cfg(target_os = "linux")]
     gpm_client: Option<GpmClient>,
     #[cfg(not(target_os = "linux"))]
@@ -243,6 +261,47 @@ fn parse_file_location(input: &str) -> FileLocation {
     }
 }
 
+/// A project opened on a remote host
+struct RemoteProject {
+    connection: Arc<RemoteConnection>,
+    /// Canonical path on the remote host
+    path: PathBuf,
+    is_dir: bool,
+}
+
+/// Connect to the remote host of a `[user@]host:path` argument
+fn connect_remote(target: &RemoteTarget, agent_command: &str) -> io::Result<RemoteProject> {
+    let with_context = |e: io::Error| {
+        io::Error::new(
+            e.kind(),
+            format!("Failed to open {}: {}", target.destination, e),
+        )
+    };
+
+    tracing::info!(
+        "Connecting to {} for {}",
+        target.destination,
+        target.path.display()
+    );
+    let connection = Arc::new(
+        RemoteConnection::connect_ssh(&target.destination, agent_command).map_err(with_context)?,
+    );
+    let fs = FsManager::new(Arc::new(RemoteFsBackend::new(Arc::clone(&connection))));
+    // An empty path is the remote home directory
+    let path = if target.path.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        target.path.clone()
+    };
+    let path = fs.block_on(fs.canonicalize(&path)).map_err(with_context)?;
+    let is_dir = fs.block_on(fs.is_dir(&path)).map_err(with_context)?;
+    Ok(RemoteProject {
+        connection,
+        path,
+        is_dir,
+    })
+}
+
 fn initialize_app(args: &Args) -> io::Result<SetupState> {
     let log_file = args
         .log_file
@@ -281,6 +340,24 @@ fn initialize_app(args: &Args) -> io::Result<SetupState> {
         config::Config::load_or_default()
     };
 
+    // A [user@]host:path argument opens a project on a remote host. Connect
+    // before taking over the terminal, so ssh can ask for credentials.
+    let remote_target = args
+        .file
+        .as_ref()
+        .filter(|path| !path.exists())
+        .and_then(|path| RemoteTarget::parse(&path.to_string_lossy()));
+    let remote_project = match remote_target {
+        Some(target) => match connect_remote(&target, &args.remote_agent_command) {
+            Ok(project) => Some(project),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return Err(e);
+            }
+        },
+        None => None,
+    };
+
     enable_raw_mode()?;
     stdout().execute(EnterAlternateScreen)?;
 
@@ -320,9 +397,18 @@ fn initialize_app(args: &Args) -> io::Result<SetupState> {
     let file_location = args
         .file
         .as_ref()
+        .filter(|_| remote_project.is_none())
         .map(|p| parse_file_location(p.to_string_lossy().as_ref()));
 
-    let (working_dir, file_to_open, show_file_explorer) = if let Some(ref loc) = file_location {
+    let (working_dir, file_to_open, show_file_explorer) = if let Some(ref project) = remote_project
+    {
+        if project.is_dir {
+            (Some(project.path.clone()), None, true)
+        } else {
+            let parent = project.path.parent().map(PathBuf::from);
+            (parent, Some(project.path.clone()), false)
+        }
+    } else if let Some(ref loc) = file_location {
         if loc.path.is_dir() {
             (Some(loc.path.clone()), None, true)
         } else {
@@ -345,6 +431,7 @@ fn initialize_app(args: &Args) -> io::Result<SetupState> {
         show_file_explorer,
         dir_context,
         current_working_dir,
+        remote: remote_project.map(|project| project.connection),
         gpm_client,
     })
 }
@@ -364,14 +451,17 @@ fn run_editor_iteration(
     if let Err(e) = editor.end_recovery_session() {
         tracing::warn!("Failed to end recovery session: {}", e);
     }
+    editor.save_undo_histories();
 
     let update_result = editor.get_update_result().cloned();
     let restart_dir = editor.take_restart_dir();
+    let restart_session = editor.take_restart_session();
 
     Ok(IterationOutcome {
         loop_result,
         update_result,
         restart_dir,
+        restart_session,
     })
 }
 
@@ -379,6 +469,16 @@ fn main() -> io::Result<()> {
     // Parse command-line arguments
     let args = Args::parse();
 
+    if args.remote_agent {
+        return remote::agent::run_stdio();
+    }
+
+    if let Some(name) = &args.session {
+        if let Err(e) = session::validate_session_name(name) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, e.to_string()));
+        }
+    }
+
     let SetupState {
         config,
         mut warning_log_handle,
@@ -389,6 +489,7 @@ fn main() -> io::Result<()> {
         show_file_explorer,
         dir_context,
         current_working_dir: initial_working_dir,
+        remote,
         #[cfg(target_os = "linux")]
         gpm_client,
         #[cfg(not(target_os = "linux"))]
@@ -404,20 +505,41 @@ fn main() -> io::Result<()> {
     // Track whether we should restore session on restart (for project switching)
     let mut restore_session_on_restart = false;
 
+    // Named session to save and restore (None for the working directory's default)
+    let mut session_name = args.session.clone();
+
+    // Track whether the last restart switched sessions rather than projects
+    let mut switched_session = false;
+
     // Main editor loop - supports restarting with a new working directory
     // Returns (loop_result, last_update_result) tuple
     let (result, last_update_result) = loop {
         let first_run = is_first_run;
-        let session_enabled = !args.no_session && file_to_open.is_none();
-
-        let mut editor = Editor::with_working_dir(
-            config.clone(),
-            terminal_width,
-            terminal_height,
-            current_working_dir.clone(),
-            dir_context.clone(),
-            !args.no_plugins,
-        )?;
+        // Sessions are keyed by local paths, so remote projects don't use them
+        let session_enabled = !args.no_session && file_to_open.is_none() && remote.is_none();
+
+        let mut editor = match &remote {
+            Some(remote) => Editor::with_remote(
+                config.clone(),
+                terminal_width,
+                terminal_height,
+                current_working_dir.clone().unwrap_or_default(),
+                Arc::clone(remote),
+                dir_context.clone(),
+                !args.no_plugins,
+            )?,
+            None => Editor::with_working_dir(
+                config.clone(),
+                terminal_width,
+                terminal_height,
+                current_working_dir.clone(),
+                dir_context.clone(),
+                !args.no_plugins,
+            )?,
+        };
+
+        editor.set_session_enabled(session_enabled);
+        editor.set_session_name(session_name.clone());
 
         #[cfg(target_os = "linux")]
         if gpm_client.is_some() {
@@ -449,14 +571,21 @@ fn main() -> io::Result<()> {
                 }
             }
 
-            editor.show_file_explorer();
-            editor.set_status_message(format!(
-                "Switched to project: {}",
-                current_working_dir
-                    .as_ref()
-                    .map(|p| p.display().to_string())
-                    .unwrap_or_else(|| ".".to_string())
-            ));
+            if switched_session {
+                editor.set_status_message(match &session_name {
+                    Some(name) => format!("Switched to session: {}", name),
+                    None => "Switched to the default session".to_string(),
+                });
+            } else {
+                editor.show_file_explorer();
+                editor.set_status_message(format!(
+                    "Switched to project: {}",
+                    current_working_dir
+                        .as_ref()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_else(|| ".".to_string())
+                ));
+            }
         }
 
         if let Err(e) = editor.start_recovery_session() {
@@ -473,6 +602,7 @@ fn main() -> io::Result<()> {
 
         let update_result = iteration.update_result;
         let restart_dir = iteration.restart_dir;
+        let restart_session = iteration.restart_session;
         let loop_result = iteration.loop_result;
 
         drop(editor);
@@ -485,6 +615,9 @@ fn main() -> io::Result<()> {
             current_working_dir = Some(new_dir);
             is_first_run = false;
             restore_session_on_restart = true; // Restore session for the new project
+                                               // A new project starts in its default session
+            switched_session = restart_session.is_some();
+            session_name = restart_session.flatten();
             terminal.clear()?;
             continue;
         }
@@ -1,13 +1,15 @@
 /// Text buffer that uses PieceTree with integrated line tracking
 /// Architecture where the tree is the single source of truth for text and line information
+use crate::model::encoding::{detect_encoding, Encoding, TextEncoding};
 use crate::model::piece_tree::{
     BufferData, BufferLocation, Cursor, PieceInfo, PieceRangeIter, PieceTree, Position,
     StringBuffer, TreeStats,
 };
 use crate::model::piece_tree_diff::PieceTreeDiff;
+use crate::services::fs::{FsFileWrite, FsManager};
 use anyhow::{Context, Result};
 use regex::bytes::Regex;
-use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::io::{self, Write};
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -22,6 +24,9 @@ pub const LOAD_CHUNK_SIZE: usize = 1024 * 1024;
 /// Chunk alignment for lazy loading (64 KB)
 pub const CHUNK_ALIGNMENT: usize = 64 * 1024;
 
+/// Chunk size for streaming a large file's unloaded regions when saving (64 KB)
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
 /// Line ending format used in the file
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LineEnding {
@@ -101,6 +106,35 @@ impl LineNumber {
     }
 }
 
+/// A contiguous run of document content, as returned by
+/// [`TextBuffer::content_regions`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContentRegion {
+    /// Bytes that are already in memory
+    Loaded(Vec<u8>),
+    /// Bytes that still live on disk and must be read by the consumer
+    OnDisk {
+        file_path: PathBuf,
+        file_offset: usize,
+        bytes: usize,
+    },
+}
+
+impl ContentRegion {
+    /// Number of document bytes covered by this region
+    pub fn len(&self) -> usize {
+        match self {
+            ContentRegion::Loaded(data) => data.len(),
+            ContentRegion::OnDisk { bytes, .. } => *bytes,
+        }
+    }
+
+    /// Whether this region covers no bytes
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 /// A text buffer that manages document content using a piece table
 /// with integrated line tracking
 pub struct TextBuffer {
@@ -139,10 +173,17 @@ pub struct TextBuffer {
     /// Line ending format detected from the file (or default for new files)
     line_ending: LineEnding,
 
+    /// Encoding of the file on disk. The buffer itself always holds UTF-8;
+    /// content is decoded on load and encoded again on save.
+    encoding: TextEncoding,
+
     /// The file size on disk after the last save.
     /// Used for chunked recovery to know the original file size for reconstruction.
     /// Updated when loading from file or after saving.
     saved_file_size: Option<usize>,
+
+    /// Filesystem the buffer's file is loaded from and saved to
+    fs: Arc<FsManager>,
 }
 
 impl TextBuffer {
@@ -161,7 +202,9 @@ impl TextBuffer {
             large_file: false,
             is_binary: false,
             line_ending: LineEnding::default(),
+            encoding: TextEncoding::default(),
             saved_file_size: None,
+            fs: FsManager::local(),
         }
     }
 
@@ -192,7 +235,9 @@ impl TextBuffer {
             large_file: false,
             is_binary: false,
             line_ending: LineEnding::default(),
+            encoding: TextEncoding::default(),
             saved_file_size: Some(bytes), // Treat initial content as "saved" state
+            fs: FsManager::local(),
         }
     }
 
@@ -216,7 +261,9 @@ impl TextBuffer {
             large_file: false,
             is_binary: false,
             line_ending: LineEnding::default(),
+            encoding: TextEncoding::default(),
             saved_file_size: None,
+            fs: FsManager::local(),
         }
     }
 
@@ -224,12 +271,36 @@ impl TextBuffer {
     pub fn load_from_file<P: AsRef<Path>>(
         path: P,
         large_file_threshold: usize,
+    ) -> io::Result<Self> {
+        Self::load_from_file_with_encoding(path, large_file_threshold, None)
+    }
+
+    /// Load a text buffer from a file, decoding it with `encoding` or, if
+    /// `None`, with the encoding detected from its content
+    pub fn load_from_file_with_encoding<P: AsRef<Path>>(
+        path: P,
+        large_file_threshold: usize,
+        encoding: Option<TextEncoding>,
+    ) -> io::Result<Self> {
+        Self::load_from_fs(FsManager::local(), path, large_file_threshold, encoding)
+    }
+
+    /// Load a text buffer from a file on `fs`, decoding it with `encoding`
+    /// or, if `None`, with the encoding detected from its content
+    ///
+    /// The buffer keeps using `fs` to load the rest of large files and to
+    /// save. Only plain UTF-8 files can be lazily loaded; large files in any
+    /// other encoding are decoded in full.
+    pub fn load_from_fs<P: AsRef<Path>>(
+        fs: Arc<FsManager>,
+        path: P,
+        large_file_threshold: usize,
+        encoding: Option<TextEncoding>,
     ) -> io::Result<Self> {
         let path = path.as_ref();
 
         // Get file size to determine loading strategy
-        let metadata = std::fs::metadata(path)?;
-        let file_size = metadata.len() as usize;
+        let file_size = Self::file_size(&fs, path)?;
 
         // Use threshold parameter or default
         let threshold = if large_file_threshold > 0 {
@@ -240,18 +311,85 @@ impl TextBuffer {
 
         // Choose loading strategy based on file size
         if file_size >= threshold {
-            Self::load_large_file(path, file_size)
+            let encoding = match encoding {
+                Some(encoding) => encoding,
+                None => Self::detect_file_encoding(&Self::read_sample(&fs, path, file_size)?),
+            };
+            if encoding.is_plain_utf8() {
+                return Self::load_large_file(fs, path, file_size);
+            }
+            Self::load_small_file(fs, path, Some(encoding))
         } else {
-            Self::load_small_file(path)
+            Self::load_small_file(fs, path, encoding)
+        }
+    }
+
+    /// Size of a file in bytes
+    fn file_size(fs: &FsManager, path: &Path) -> io::Result<usize> {
+        let entry = fs.block_on(fs.get_entry(path))?;
+        Ok(entry.metadata.and_then(|m| m.size).unwrap_or(0) as usize)
+    }
+
+    /// Read the first 8KB of a file, used to detect its format
+    fn read_sample(fs: &FsManager, path: &Path, file_size: usize) -> io::Result<Vec<u8>> {
+        let sample_size = file_size.min(8 * 1024);
+        fs.block_on(fs.read_range(path, 0, sample_size))
+    }
+
+    /// Detect the encoding of file content
+    ///
+    /// UTF-16 is recognized before the binary check since its text is full
+    /// of NUL bytes; other binary content is kept as raw (UTF-8) bytes.
+    fn detect_file_encoding(bytes: &[u8]) -> TextEncoding {
+        let encoding = detect_encoding(bytes);
+        match encoding.encoding {
+            Encoding::Utf16Le | Encoding::Utf16Be => encoding,
+            _ if Self::detect_binary(bytes) => TextEncoding::UTF8,
+            _ => encoding,
         }
     }
 
+    /// Decode file content to the text a buffer would hold for it: decoded
+    /// to UTF-8 with line endings normalized to LF
+    ///
+    /// Returns `None` for binary content and for invalid UTF-8, which buffers
+    /// keep as raw bytes. Used to search files without opening them.
+    pub fn decode_file_text(bytes: Vec<u8>) -> Option<String> {
+        let encoding = Self::detect_file_encoding(&bytes);
+        let contents = if encoding.is_plain_utf8() {
+            bytes
+        } else {
+            encoding.decode(&bytes).0.into_bytes()
+        };
+        if Self::detect_binary(&contents) {
+            return None;
+        }
+        String::from_utf8(Self::normalize_line_endings(contents)).ok()
+    }
+
     /// Load a small file with full eager loading and line indexing
-    fn load_small_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+    fn load_small_file<P: AsRef<Path>>(
+        fs: Arc<FsManager>,
+        path: P,
+        encoding: Option<TextEncoding>,
+    ) -> io::Result<Self> {
         let path = path.as_ref();
-        let mut file = std::fs::File::open(path)?;
-        let mut contents = Vec::new();
-        file.read_to_end(&mut contents)?;
+        let mut contents = fs.block_on(fs.read_file(path))?;
+
+        // Decode to UTF-8 unless the file already is UTF-8; plain UTF-8 files
+        // are kept byte for byte, even if they contain invalid sequences
+        let encoding = encoding.unwrap_or_else(|| Self::detect_file_encoding(&contents));
+        if !encoding.is_plain_utf8() {
+            let (text, had_errors) = encoding.decode(&contents);
+            if had_errors {
+                tracing::warn!(
+                    "{:?} is not valid {}; invalid sequences were replaced",
+                    path,
+                    encoding
+                );
+            }
+            contents = text.into_bytes();
+        }
 
         // Detect if this is a binary file
         let is_binary = Self::detect_binary(&contents);
@@ -268,11 +406,17 @@ impl TextBuffer {
         buffer.large_file = false;
         buffer.is_binary = is_binary;
         buffer.line_ending = line_ending;
+        buffer.encoding = encoding;
+        buffer.fs = fs;
         Ok(buffer)
     }
 
     /// Load a large file with unloaded buffer (no line indexing, lazy loading)
-    fn load_large_file<P: AsRef<Path>>(path: P, file_size: usize) -> io::Result<Self> {
+    fn load_large_file<P: AsRef<Path>>(
+        fs: Arc<FsManager>,
+        path: P,
+        file_size: usize,
+    ) -> io::Result<Self> {
         use crate::model::piece_tree::{BufferData, BufferLocation};
 
         let path = path.as_ref();
@@ -280,10 +424,7 @@ impl TextBuffer {
         // Read a sample of the file to detect if it's binary and line ending format
         // We read the first 8KB for both binary and line ending detection
         let (is_binary, line_ending) = {
-            let mut file = std::fs::File::open(path)?;
-            let sample_size = file_size.min(8 * 1024);
-            let mut sample = vec![0u8; sample_size];
-            file.read_exact(&mut sample)?;
+            let sample = Self::read_sample(&fs, path, file_size)?;
             let is_binary = Self::detect_binary(&sample);
             let line_ending = Self::detect_line_ending(&sample);
             (is_binary, line_ending)
@@ -325,7 +466,9 @@ impl TextBuffer {
             large_file: true,
             is_binary,
             line_ending,
+            encoding: TextEncoding::UTF8,
             saved_file_size: Some(file_size),
+            fs,
         })
     }
 
@@ -346,20 +489,28 @@ impl TextBuffer {
     /// This uses incremental saving for large files: instead of loading the entire
     /// file into memory, it streams unmodified regions directly from the source file
     /// and only keeps edited regions in memory.
+    ///
+    /// Content is written in the buffer's encoding. Encoding fails, leaving the
+    /// file untouched, if the buffer contains characters the encoding cannot
+    /// represent.
     pub fn save_to_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
         let dest_path = path.as_ref();
         let total = self.total_bytes();
+        let fs = Arc::clone(&self.fs);
 
-        // Get original file metadata (permissions, owner, etc.) before writing
-        // so we can preserve it after creating/renaming the temp file
-        let original_metadata = std::fs::metadata(dest_path).ok();
+        // Check for an original file before writing, so its permissions, owner,
+        // etc. can be preserved after creating/renaming the temp file
+        let replaces_file = fs.block_on(fs.exists(dest_path));
+
+        // Non-UTF-8 content is encoded up front, before touching the disk
+        let encoded = match self.encoding.encoding {
+            Encoding::Utf8 => None,
+            _ => Some(self.encoded_content()?),
+        };
 
         if total == 0 {
-            // Empty file - just create it
-            std::fs::File::create(dest_path)?;
-            if let Some(ref meta) = original_metadata {
-                Self::restore_file_metadata(dest_path, meta)?;
-            }
+            // Empty file - just create it (with a byte order mark, if any)
+            fs.block_on(fs.write_file(dest_path, self.encoding.bom_bytes()))?;
             self.file_path = Some(dest_path.to_path_buf());
             self.mark_saved_snapshot();
             self.saved_file_size = Some(0);
@@ -368,13 +519,51 @@ impl TextBuffer {
 
         // Use a temp file to avoid corrupting the original if something goes wrong
         let temp_path = dest_path.with_extension("tmp");
-        let mut out_file = std::fs::File::create(&temp_path)?;
+        let mut out_file =
+            io::BufWriter::with_capacity(STREAM_CHUNK_SIZE, FsFileWriter::create(&fs, &temp_path)?);
+
+        match &encoded {
+            Some(encoded) => out_file.write_all(encoded)?,
+            None => {
+                out_file.write_all(self.encoding.bom_bytes())?;
+                self.write_pieces(&mut out_file)?;
+            }
+        }
+
+        // Ensure all data is written
+        out_file.flush()?;
+        drop(out_file);
+        fs.block_on(fs.sync_file(&temp_path))?;
+
+        // Restore original file permissions/owner before renaming
+        if replaces_file {
+            fs.block_on(fs.copy_permissions(dest_path, &temp_path))?;
+        }
 
-        // Cache for open source files (for streaming unloaded regions)
-        let mut source_file_cache: Option<(PathBuf, std::fs::File)> = None;
+        // Atomically replace the original file
+        fs.block_on(fs.rename(&temp_path, dest_path))?;
 
+        // Update saved file size to match the file on disk
+        let new_size = Self::file_size(&fs, dest_path)?;
+        tracing::debug!(
+            "Buffer::save: updating saved_file_size from {:?} to {}",
+            self.saved_file_size,
+            new_size
+        );
+        self.saved_file_size = Some(new_size);
+
+        self.file_path = Some(dest_path.to_path_buf());
+        self.mark_saved_snapshot();
+        Ok(())
+    }
+
+    /// Write the buffer's pieces to `out_file` as UTF-8
+    ///
+    /// Loaded pieces are converted to the buffer's line endings; unloaded
+    /// regions of large files are streamed from the source file as-is.
+    fn write_pieces(&self, out_file: &mut impl Write) -> io::Result<()> {
         // Iterate through all pieces and write them
-        for piece_view in self.piece_tree.iter_pieces_in_range(0, total) {
+        for piece_view in self.piece_tree.iter_pieces_in_range(0, self.total_bytes()) {
             let buffer_id = piece_view.location.buffer_id();
             let buffer = self.buffers.get(buffer_id).ok_or_else(|| {
                 io::Error::new(
@@ -400,82 +589,48 @@ impl TextBuffer {
                     // Stream from source file without loading into memory
                     // NOTE: Unloaded regions come directly from the original file and already
                     // have the correct line endings, so we don't need to convert them
-                    let source_file = match &mut source_file_cache {
-                        Some((cached_path, file)) if cached_path == file_path => file,
-                        _ => {
-                            let file = std::fs::File::open(file_path)?;
-                            source_file_cache = Some((file_path.clone(), file));
-                            &mut source_file_cache.as_mut().unwrap().1
-                        }
-                    };
-
-                    // Seek to the right position in source file
-                    let read_offset = *file_offset + piece_view.buffer_offset;
-                    source_file.seek(SeekFrom::Start(read_offset as u64))?;
-
-                    // Stream in chunks to avoid large memory allocation
-                    const STREAM_CHUNK_SIZE: usize = 64 * 1024; // 64KB chunks
+                    let mut read_offset = *file_offset + piece_view.buffer_offset;
                     let mut remaining = piece_view.bytes;
-                    let mut chunk_buf = vec![0u8; STREAM_CHUNK_SIZE.min(remaining)];
 
+                    // Stream in chunks to avoid large memory allocation
                     while remaining > 0 {
-                        let to_read = remaining.min(chunk_buf.len());
-                        source_file.read_exact(&mut chunk_buf[..to_read])?;
-                        out_file.write_all(&chunk_buf[..to_read])?;
+                        let to_read = remaining.min(STREAM_CHUNK_SIZE);
+                        let chunk = self.fs.block_on(self.fs.read_range(
+                            file_path,
+                            read_offset as u64,
+                            to_read,
+                        ))?;
+                        out_file.write_all(&chunk)?;
+                        read_offset += to_read;
                         remaining -= to_read;
                     }
                 }
             }
         }
-
-        // Ensure all data is written
-        out_file.sync_all()?;
-        drop(out_file);
-
-        // Restore original file permissions/owner before renaming
-        if let Some(ref meta) = original_metadata {
-            Self::restore_file_metadata(&temp_path, meta)?;
-        }
-
-        // Atomically replace the original file
-        std::fs::rename(&temp_path, dest_path)?;
-
-        // Update saved file size to match the file on disk
-        let new_size = std::fs::metadata(dest_path)?.len() as usize;
-        tracing::debug!(
-            "Buffer::save: updating saved_file_size from {:?} to {}",
-            self.saved_file_size,
-            new_size
-        );
-        self.saved_file_size = Some(new_size);
-
-        self.file_path = Some(dest_path.to_path_buf());
-        self.mark_saved_snapshot();
         Ok(())
     }
 
-    /// Restore file metadata (permissions, owner/group) from original file
-    fn restore_file_metadata(path: &Path, original_meta: &std::fs::Metadata) -> io::Result<()> {
-        // Restore permissions (works cross-platform)
-        std::fs::set_permissions(path, original_meta.permissions())?;
-
-        // On Unix, also restore owner and group
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::MetadataExt;
-            let uid = original_meta.uid();
-            let gid = original_meta.gid();
-            // Use libc to set owner/group - ignore errors since we may not have permission
-            // (e.g., only root can chown to a different user)
-            unsafe {
-                use std::os::unix::ffi::OsStrExt;
-                let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
-                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
-                libc::chown(c_path.as_ptr(), uid, gid);
-            }
-        }
-
-        Ok(())
+    /// The buffer's content with its line endings, encoded in its encoding
+    fn encoded_content(&self) -> io::Result<Vec<u8>> {
+        let bytes = self.get_all_text().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Large files cannot be saved as {}", self.encoding),
+            )
+        })?;
+        let text = String::from_utf8(Self::convert_line_endings(&bytes, self.line_ending))
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Buffer contains invalid UTF-8 and cannot be saved as {}",
+                        self.encoding
+                    ),
+                )
+            })?;
+        self.encoding
+            .encode(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
     }
 
     /// Get the total number of bytes in the document
@@ -1027,7 +1182,7 @@ impl TextBuffer {
                         self.buffers
                             .get_mut(new_buffer_id)
                             .context("Chunk buffer not found")?
-                            .load()
+                            .load(&self.fs)
                             .context("Failed to load chunk")?;
 
                         // Restart iteration with the modified tree
@@ -1038,7 +1193,7 @@ impl TextBuffer {
                         self.buffers
                             .get_mut(buffer_id)
                             .context("Buffer not found")?
-                            .load()
+                            .load(&self.fs)
                             .context("Failed to load buffer")?;
                     }
                 }
@@ -1132,6 +1287,70 @@ impl TextBuffer {
         Ok(())
     }
 
+    /// Describe a byte range of the document as a list of content regions
+    ///
+    /// Loaded pieces are copied, unloaded pieces are returned as file ranges so
+    /// that the caller can read them itself (e.g. on a background thread)
+    /// without forcing the whole document into memory. Adjacent on-disk pieces
+    /// that are contiguous in the file are merged into a single region.
+    pub fn content_regions(&self, range: Range<usize>) -> Vec<ContentRegion> {
+        let end = range.end.min(self.len());
+        let mut regions: Vec<ContentRegion> = Vec::new();
+        if range.start >= end {
+            return regions;
+        }
+
+        for piece_view in self.piece_tree.iter_pieces_in_range(range.start, end) {
+            let Some(buffer) = self.buffers.get(piece_view.location.buffer_id()) else {
+                continue;
+            };
+
+            let read_start = range.start.max(piece_view.doc_offset);
+            let read_end = end.min(piece_view.doc_offset + piece_view.bytes);
+            if read_end <= read_start {
+                continue;
+            }
+            let buffer_start = piece_view.buffer_offset + (read_start - piece_view.doc_offset);
+            let len = read_end - read_start;
+
+            match &buffer.data {
+                BufferData::Loaded { data, .. } => {
+                    let slice = &data[buffer_start..(buffer_start + len).min(data.len())];
+                    if let Some(ContentRegion::Loaded(prev)) = regions.last_mut() {
+                        prev.extend_from_slice(slice);
+                    } else {
+                        regions.push(ContentRegion::Loaded(slice.to_vec()));
+                    }
+                }
+                BufferData::Unloaded {
+                    file_path,
+                    file_offset,
+                    ..
+                } => {
+                    let region_offset = file_offset + buffer_start;
+                    if let Some(ContentRegion::OnDisk {
+                        file_path: prev_path,
+                        file_offset: prev_offset,
+                        bytes: prev_bytes,
+                    }) = regions.last_mut()
+                    {
+                        if prev_path == file_path && *prev_offset + *prev_bytes == region_offset {
+                            *prev_bytes += len;
+                            continue;
+                        }
+                    }
+                    regions.push(ContentRegion::OnDisk {
+                        file_path: file_path.clone(),
+                        file_offset: region_offset,
+                        bytes: len,
+                    });
+                }
+            }
+        }
+
+        regions
+    }
+
     /// Get all text as a single Vec<u8>
     /// Returns None if any buffers are unloaded (lazy loading)
     /// CRATE-PRIVATE: External code should use get_text_range_mut() or DocumentModel methods
@@ -1182,6 +1401,17 @@ impl TextBuffer {
         self.file_path = Some(path);
     }
 
+    /// Filesystem the buffer loads from and saves to
+    pub fn fs(&self) -> &Arc<FsManager> {
+        &self.fs
+    }
+
+    /// Set the filesystem the buffer saves to (for buffers not loaded from a
+    /// file, which use the local filesystem by default)
+    pub fn set_fs(&mut self, fs: Arc<FsManager>) {
+        self.fs = fs;
+    }
+
     /// Check if the buffer has been modified since last save
     pub fn is_modified(&self) -> bool {
         self.modified
@@ -1289,6 +1519,19 @@ impl TextBuffer {
         self.recovery_pending = true;
     }
 
+    /// Get the encoding the buffer is saved in
+    pub fn encoding(&self) -> TextEncoding {
+        self.encoding
+    }
+
+    /// Set the encoding the buffer is saved in
+    pub fn set_encoding(&mut self, encoding: TextEncoding) {
+        self.encoding = encoding;
+        // Like line endings, the file on disk no longer matches the buffer
+        self.modified = true;
+        self.recovery_pending = true;
+    }
+
     /// Detect if the given bytes contain binary content.
     ///
     /// Binary content is detected by looking for:
@@ -2142,6 +2385,31 @@ impl TextBuffer {
     }
 }
 
+/// Writer that streams a file's contents to a filesystem backend
+struct FsFileWriter<'a> {
+    fs: &'a FsManager,
+    file: Box<dyn FsFileWrite + 'a>,
+}
+
+impl<'a> FsFileWriter<'a> {
+    /// Create (or truncate) the file at `path`
+    fn create(fs: &'a FsManager, path: &'a Path) -> io::Result<Self> {
+        let file = fs.block_on(fs.create_file(path))?;
+        Ok(Self { fs, file })
+    }
+}
+
+impl Write for FsFileWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.fs.block_on(self.file.write_all(buf))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 /// Type alias for backwards compatibility
 pub type Buffer = TextBuffer;
 
@@ -2697,7 +2965,7 @@ mod tests {
             assert!(!buffer.is_loaded());
 
             // Load the buffer
-            buffer.load().unwrap();
+            buffer.load(&FsManager::local()).unwrap();
 
             // Now it should be loaded
             assert!(buffer.is_loaded());
@@ -3172,6 +3440,153 @@ mod tests {
                 "Length should be original + edits"
             );
         }
+
+        /// Test that content_regions describes unloaded data as file ranges
+        /// and stitches edits in between without loading anything
+        #[test]
+        fn test_large_file_content_regions() {
+            let temp_dir = TempDir::new().unwrap();
+            let file_path = temp_dir.path().join("regions.txt");
+            std::fs::write(&file_path, b"0123456789").unwrap();
+
+            let mut buffer = TextBuffer::load_from_file(&file_path, 1).unwrap();
+            buffer.insert_bytes(4, b"XY".to_vec());
+
+            let regions = buffer.content_regions(2..10);
+            assert_eq!(
+                regions,
+                vec![
+                    ContentRegion::OnDisk {
+                        file_path: file_path.clone(),
+                        file_offset: 2,
+                        bytes: 2,
+                    },
+                    ContentRegion::Loaded(b"XY".to_vec()),
+                    ContentRegion::OnDisk {
+                        file_path: file_path.clone(),
+                        file_offset: 4,
+                        bytes: 4,
+                    },
+                ]
+            );
+            assert!(!buffer.buffers[0].is_loaded());
+        }
+    }
+
+    mod encoding_support {
+        use super::*;
+        use crate::model::encoding::{Encoding, TextEncoding};
+        use tempfile::TempDir;
+
+        fn utf16le_with_bom(text: &str) -> Vec<u8> {
+            let mut bytes = vec![0xFF, 0xFE];
+            bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+            bytes
+        }
+
+        #[test]
+        fn test_utf16_file_round_trip() {
+            let temp_dir = TempDir::new().unwrap();
+            let file_path = temp_dir.path().join("strings.rc");
+            let original = utf16le_with_bom("caf\u{e9}\r\nEND\r\n");
+            std::fs::write(&file_path, &original).unwrap();
+
+            let mut buffer = TextBuffer::load_from_file(&file_path, 0).unwrap();
+            assert_eq!(
+                buffer.encoding(),
+                TextEncoding::new(Encoding::Utf16Le, true)
+            );
+            assert_eq!(buffer.line_ending(), LineEnding::CRLF);
+            assert!(!buffer.is_binary());
+            assert_eq!(buffer.to_string().unwrap(), "caf\u{e9}\nEND\n");
+
+            buffer.save().unwrap();
+            assert_eq!(std::fs::read(&file_path).unwrap(), original);
+
+            buffer.insert_bytes(0, "\u{fc}\n".as_bytes().to_vec());
+            buffer.save().unwrap();
+            assert_eq!(
+                std::fs::read(&file_path).unwrap(),
+                utf16le_with_bom("\u{fc}\r\ncaf\u{e9}\r\nEND\r\n")
+            );
+        }
+
+        #[test]
+        fn test_latin1_file_round_trip() {
+            let temp_dir = TempDir::new().unwrap();
+            let file_path = temp_dir.path().join("legacy.c");
+            let original = b"/* caf\xE9 cr\xE8me */\n".to_vec();
+            std::fs::write(&file_path, &original).unwrap();
+
+            let mut buffer = TextBuffer::load_from_file(&file_path, 0).unwrap();
+            assert_eq!(
+                buffer.encoding(),
+                TextEncoding::new(Encoding::Latin1, false)
+            );
+            assert_eq!(buffer.to_string().unwrap(), "/* caf\u{e9} cr\u{e8}me */\n");
+
+            buffer.save().unwrap();
+            assert_eq!(std::fs::read(&file_path).unwrap(), original);
+        }
+
+        #[test]
+        fn test_save_fails_on_unmappable_character() {
+            let temp_dir = TempDir::new().unwrap();
+            let file_path = temp_dir.path().join("legacy.txt");
+            std::fs::write(&file_path, b"caf\xE9\n").unwrap();
+
+            let mut buffer = TextBuffer::load_from_file(&file_path, 0).unwrap();
+            buffer.insert_bytes(0, "\u{20ac}".as_bytes().to_vec());
+            let err = buffer.save().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(buffer.is_modified());
+            // The file on disk is untouched
+            assert_eq!(std::fs::read(&file_path).unwrap(), b"caf\xE9\n");
+        }
+
+        #[test]
+        fn test_set_encoding_converts_on_save() {
+            let temp_dir = TempDir::new().unwrap();
+            let file_path = temp_dir.path().join("notes.txt");
+            std::fs::write(&file_path, "h\u{e9}\n").unwrap();
+
+            let mut buffer = TextBuffer::load_from_file(&file_path, 0).unwrap();
+            assert_eq!(buffer.encoding(), TextEncoding::UTF8);
+            buffer.set_encoding(TextEncoding::new(Encoding::Utf16Le, true));
+            assert!(buffer.is_modified());
+            buffer.save().unwrap();
+            assert_eq!(
+                std::fs::read(&file_path).unwrap(),
+                utf16le_with_bom("h\u{e9}\n")
+            );
+        }
+
+        /// Large files in an encoding other than UTF-8 are decoded in full
+        /// instead of being lazily loaded
+        #[test]
+        fn test_large_non_utf8_file_is_decoded() {
+            let temp_dir = TempDir::new().unwrap();
+            let file_path = temp_dir.path().join("big.txt");
+            let original = utf16le_with_bom("line one\nline two\n");
+            std::fs::write(&file_path, &original).unwrap();
+
+            let buffer = TextBuffer::load_from_file(&file_path, 1).unwrap();
+            assert!(!buffer.is_large_file());
+            assert_eq!(buffer.to_string().unwrap(), "line one\nline two\n");
+
+            // Reopening with an explicit encoding overrides detection
+            let buffer = TextBuffer::load_from_file_with_encoding(
+                &file_path,
+                1,
+                Some(TextEncoding::new(Encoding::Latin1, false)),
+            )
+            .unwrap();
+            assert_eq!(
+                buffer.encoding(),
+                TextEncoding::new(Encoding::Latin1, false)
+            );
+            assert!(buffer.to_string().unwrap().starts_with("\u{ff}\u{fe}l\0"));
+        }
     }
 
     // ===== Offset to Position Tests =====
@@ -0,0 +1,528 @@
+//! Text encodings for files on disk
+//!
+//! Buffers always hold UTF-8 internally. Files in other encodings are decoded
+//! when loaded and encoded again when saved, so the bytes on disk round-trip
+//! unchanged. [`detect_encoding`] guesses the encoding of a file from a byte
+//! order mark or, failing that, from the content itself.
+
+use std::fmt;
+
+/// A character encoding supported for loading and saving files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// ISO-8859-1: every byte maps to the code point of the same value
+    Latin1,
+    Windows1252,
+    ShiftJis,
+    EucJp,
+}
+
+/// The encoding of a file, including whether it starts with a byte order mark
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TextEncoding {
+    pub encoding: Encoding,
+    pub bom: bool,
+}
+
+/// Error returned when text cannot be represented in the target encoding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnmappableCharacter {
+    pub ch: char,
+    pub encoding: TextEncoding,
+}
+
+impl fmt::Display for UnmappableCharacter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "character {:?} (U+{:04X}) cannot be encoded as {}",
+            self.ch, self.ch as u32, self.encoding
+        )
+    }
+}
+
+impl std::error::Error for UnmappableCharacter {}
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+impl TextEncoding {
+    pub const UTF8: TextEncoding = TextEncoding::new(Encoding::Utf8, false);
+
+    /// Every encoding a user can choose, in the order they are offered
+    pub const ALL: [TextEncoding; 10] = [
+        TextEncoding::new(Encoding::Utf8, false),
+        TextEncoding::new(Encoding::Utf8, true),
+        TextEncoding::new(Encoding::Utf16Le, true),
+        TextEncoding::new(Encoding::Utf16Be, true),
+        TextEncoding::new(Encoding::Utf16Le, false),
+        TextEncoding::new(Encoding::Utf16Be, false),
+        TextEncoding::new(Encoding::Latin1, false),
+        TextEncoding::new(Encoding::Windows1252, false),
+        TextEncoding::new(Encoding::ShiftJis, false),
+        TextEncoding::new(Encoding::EucJp, false),
+    ];
+
+    pub const fn new(encoding: Encoding, bom: bool) -> Self {
+        Self { encoding, bom }
+    }
+
+    /// Whether this is plain UTF-8 (no BOM), i.e. the bytes on disk are
+    /// exactly the bytes in the buffer
+    pub fn is_plain_utf8(&self) -> bool {
+        *self == Self::UTF8
+    }
+
+    /// Human-readable name, as shown in the status bar and prompts
+    pub fn display_name(&self) -> &'static str {
+        match (self.encoding, self.bom) {
+            (Encoding::Utf8, false) => "UTF-8",
+            (Encoding::Utf8, true) => "UTF-8 BOM",
+            (Encoding::Utf16Le, true) => "UTF-16 LE",
+            (Encoding::Utf16Be, true) => "UTF-16 BE",
+            (Encoding::Utf16Le, false) => "UTF-16 LE (no BOM)",
+            (Encoding::Utf16Be, false) => "UTF-16 BE (no BOM)",
+            (Encoding::Latin1, _) => "Latin-1",
+            (Encoding::Windows1252, _) => "Windows-1252",
+            (Encoding::ShiftJis, _) => "Shift-JIS",
+            (Encoding::EucJp, _) => "EUC-JP",
+        }
+    }
+
+    /// Parse a name as produced by [`TextEncoding::display_name`]
+    ///
+    /// Matching is case-insensitive and also accepts a few common aliases.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalized = name.trim().to_ascii_lowercase().replace('_', "-");
+        if let Some(encoding) = Self::ALL
+            .iter()
+            .find(|e| e.display_name().to_ascii_lowercase() == normalized)
+        {
+            return Some(*encoding);
+        }
+        let encoding = match normalized.as_str() {
+            "utf8" => Self::new(Encoding::Utf8, false),
+            "utf-16" | "utf16" | "utf-16le" | "utf16le" => Self::new(Encoding::Utf16Le, true),
+            "utf-16be" | "utf16be" => Self::new(Encoding::Utf16Be, true),
+            "latin1" | "iso-8859-1" => Self::new(Encoding::Latin1, false),
+            "cp1252" => Self::new(Encoding::Windows1252, false),
+            "shiftjis" | "sjis" => Self::new(Encoding::ShiftJis, false),
+            "eucjp" => Self::new(Encoding::EucJp, false),
+            _ => return None,
+        };
+        Some(encoding)
+    }
+
+    /// The byte order mark written at the start of the file, if any
+    pub fn bom_bytes(&self) -> &'static [u8] {
+        match (self.encoding, self.bom) {
+            (Encoding::Utf8, true) => UTF8_BOM,
+            (Encoding::Utf16Le, true) => UTF16LE_BOM,
+            (Encoding::Utf16Be, true) => UTF16BE_BOM,
+            _ => &[],
+        }
+    }
+
+    /// Decode file bytes to UTF-8 text
+    ///
+    /// A leading byte order mark matching this encoding is skipped. Invalid
+    /// sequences are replaced with U+FFFD; the second value reports whether
+    /// that happened (in which case saving would not reproduce the file).
+    pub fn decode(&self, bytes: &[u8]) -> (String, bool) {
+        let bom = self.bom_bytes();
+        let bytes = if !bom.is_empty() && bytes.starts_with(bom) {
+            &bytes[bom.len()..]
+        } else {
+            bytes
+        };
+
+        match self.encoding {
+            Encoding::Utf8 => match String::from_utf8(bytes.to_vec()) {
+                Ok(text) => (text, false),
+                Err(_) => (String::from_utf8_lossy(bytes).into_owned(), true),
+            },
+            Encoding::Utf16Le => decode_utf16(bytes, u16::from_le_bytes),
+            Encoding::Utf16Be => decode_utf16(bytes, u16::from_be_bytes),
+            Encoding::Latin1 => (bytes.iter().map(|&b| b as char).collect(), false),
+            Encoding::Windows1252 => decode_with(encoding_rs::WINDOWS_1252, bytes),
+            Encoding::ShiftJis => decode_with(encoding_rs::SHIFT_JIS, bytes),
+            Encoding::EucJp => decode_with(encoding_rs::EUC_JP, bytes),
+        }
+    }
+
+    /// Encode UTF-8 text to file bytes, including the byte order mark
+    ///
+    /// Fails on the first character the encoding cannot represent rather than
+    /// silently substituting it.
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, UnmappableCharacter> {
+        let mut out = self.bom_bytes().to_vec();
+        self.encode_into(text, &mut out)?;
+        Ok(out)
+    }
+
+    /// Encode UTF-8 text without a byte order mark, appending to `out`
+    pub fn encode_into(&self, text: &str, out: &mut Vec<u8>) -> Result<(), UnmappableCharacter> {
+        match self.encoding {
+            Encoding::Utf8 => out.extend_from_slice(text.as_bytes()),
+            Encoding::Utf16Le => out.extend(text.encode_utf16().flat_map(u16::to_le_bytes)),
+            Encoding::Utf16Be => out.extend(text.encode_utf16().flat_map(u16::to_be_bytes)),
+            Encoding::Latin1 => {
+                for ch in text.chars() {
+                    let byte = u8::try_from(ch as u32).map_err(|_| self.unmappable(ch))?;
+                    out.push(byte);
+                }
+            }
+            Encoding::Windows1252 => self.encode_with(encoding_rs::WINDOWS_1252, text, out)?,
+            Encoding::ShiftJis => self.encode_with(encoding_rs::SHIFT_JIS, text, out)?,
+            Encoding::EucJp => self.encode_with(encoding_rs::EUC_JP, text, out)?,
+        }
+        Ok(())
+    }
+
+    fn encode_with(
+        &self,
+        encoding: &'static encoding_rs::Encoding,
+        text: &str,
+        out: &mut Vec<u8>,
+    ) -> Result<(), UnmappableCharacter> {
+        let (bytes, _, had_unmappable) = encoding.encode(text);
+        if had_unmappable {
+            // encoding_rs substitutes HTML numeric character references;
+            // find the offending character so the user knows what to fix
+            let ch = text
+                .chars()
+                .find(|ch| {
+                    let mut buf = [0u8; 4];
+                    encoding.encode(ch.encode_utf8(&mut buf)).2
+                })
+                .unwrap_or(char::REPLACEMENT_CHARACTER);
+            return Err(self.unmappable(ch));
+        }
+        out.extend_from_slice(&bytes);
+        Ok(())
+    }
+
+    fn unmappable(&self, ch: char) -> UnmappableCharacter {
+        UnmappableCharacter {
+            ch,
+            encoding: *self,
+        }
+    }
+}
+
+impl fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.display_name())
+    }
+}
+
+fn decode_utf16(bytes: &[u8], to_unit: fn([u8; 2]) -> u16) -> (String, bool) {
+    let units = bytes
+        .chunks_exact(2)
+        .map(|pair| to_unit([pair[0], pair[1]]));
+    let mut had_errors = !bytes.len().is_multiple_of(2);
+    let mut text: String = char::decode_utf16(units)
+        .map(|r| {
+            r.unwrap_or_else(|_| {
+                had_errors = true;
+                char::REPLACEMENT_CHARACTER
+            })
+        })
+        .collect();
+    if !bytes.len().is_multiple_of(2) {
+        text.push(char::REPLACEMENT_CHARACTER);
+    }
+    (text, had_errors)
+}
+
+fn decode_with(encoding: &'static encoding_rs::Encoding, bytes: &[u8]) -> (String, bool) {
+    let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+    (text.into_owned(), had_errors)
+}
+
+/// Guess the encoding of file content
+///
+/// `bytes` may be just the beginning of a file; a multi-byte sequence cut off
+/// at the end does not count against an encoding. In order:
+/// 1. a byte order mark decides;
+/// 2. text with NUL bytes concentrated in every other position is UTF-16;
+/// 3. valid UTF-8 (including pure ASCII) is UTF-8;
+/// 4. Shift-JIS if it decodes cleanly and contains kana;
+/// 5. Windows-1252 if it uses bytes 0x80-0x9F (control codes in Latin-1);
+/// 6. otherwise Latin-1, which accepts any byte sequence.
+pub fn detect_encoding(bytes: &[u8]) -> TextEncoding {
+    if bytes.starts_with(UTF8_BOM) {
+        return TextEncoding::new(Encoding::Utf8, true);
+    }
+    if bytes.starts_with(UTF16LE_BOM) {
+        return TextEncoding::new(Encoding::Utf16Le, true);
+    }
+    if bytes.starts_with(UTF16BE_BOM) {
+        return TextEncoding::new(Encoding::Utf16Be, true);
+    }
+    if let Some(encoding) = detect_utf16_without_bom(bytes) {
+        return TextEncoding::new(encoding, false);
+    }
+    if is_utf8_prefix(bytes) {
+        return TextEncoding::UTF8;
+    }
+    if looks_like_shift_jis(bytes) {
+        return TextEncoding::new(Encoding::ShiftJis, false);
+    }
+    if bytes.iter().any(|b| (0x80..=0x9F).contains(b)) {
+        return TextEncoding::new(Encoding::Windows1252, false);
+    }
+    TextEncoding::new(Encoding::Latin1, false)
+}
+
+/// Detect BOM-less UTF-16 from the distribution of NUL bytes
+///
+/// Mostly-ASCII UTF-16 text has a zero in every other byte; real binary files
+/// have NULs scattered at both parities.
+fn detect_utf16_without_bom(bytes: &[u8]) -> Option<Encoding> {
+    let pairs = bytes.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let (mut even_zeros, mut odd_zeros) = (0usize, 0usize);
+    for pair in bytes.chunks_exact(2) {
+        even_zeros += (pair[0] == 0) as usize;
+        odd_zeros += (pair[1] == 0) as usize;
+    }
+
+    let dominant = |zeros: usize, other: usize| zeros * 10 >= pairs * 4 && other * 10 < pairs;
+    let encoding = if dominant(odd_zeros, even_zeros) {
+        Encoding::Utf16Le
+    } else if dominant(even_zeros, odd_zeros) {
+        Encoding::Utf16Be
+    } else {
+        return None;
+    };
+
+    // Reject if the content is not valid UTF-16 (e.g. unpaired surrogates)
+    let even_len = &bytes[..pairs * 2];
+    let (_, had_errors) = TextEncoding::new(encoding, false).decode(even_len);
+    (!had_errors).then_some(encoding)
+}
+
+/// Whether `bytes` is valid UTF-8, allowing a truncated sequence at the end
+fn is_utf8_prefix(bytes: &[u8]) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none() && bytes.len() - e.valid_up_to() < 4,
+    }
+}
+
+/// Whether `bytes` decodes cleanly as Shift-JIS and contains hiragana or
+/// katakana, which practically all Japanese text does
+///
+/// Requiring kana keeps Windows-1252 text with curly quotes (which often
+/// happens to be valid Shift-JIS) from being misdetected.
+fn looks_like_shift_jis(bytes: &[u8]) -> bool {
+    // Drop a possibly truncated lead byte at the end of a sample
+    let bytes = match bytes.last() {
+        Some(&b) if is_shift_jis_lead(b) && !trailing_lead_completes(bytes) => {
+            &bytes[..bytes.len() - 1]
+        }
+        _ => bytes,
+    };
+    if encoding_rs::SHIFT_JIS
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .is_none()
+    {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if is_shift_jis_lead(b) {
+            if b == 0x82 || b == 0x83 {
+                return true;
+            }
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+    false
+}
+
+fn is_shift_jis_lead(b: u8) -> bool {
+    matches!(b, 0x81..=0x9F | 0xE0..=0xFC)
+}
+
+/// Whether the last byte of `bytes`, a lead byte, is actually the trail byte
+/// of a preceding double-byte character
+fn trailing_lead_completes(bytes: &[u8]) -> bool {
+    let mut i = 0;
+    while i < bytes.len() {
+        if is_shift_jis_lead(bytes[i]) {
+            if i + 1 == bytes.len() {
+                return false;
+            }
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn test_detect_bom() {
+        assert_eq!(
+            detect_encoding(b"\xEF\xBB\xBFhi"),
+            TextEncoding::new(Encoding::Utf8, true)
+        );
+        assert_eq!(
+            detect_encoding(b"\xFF\xFEh\0i\0"),
+            TextEncoding::new(Encoding::Utf16Le, true)
+        );
+        assert_eq!(
+            detect_encoding(b"\xFE\xFF\0h\0i"),
+            TextEncoding::new(Encoding::Utf16Be, true)
+        );
+    }
+
+    #[test]
+    fn test_detect_utf16_without_bom() {
+        let le = utf16le("STRINGTABLE\r\nBEGIN\r\n");
+        assert_eq!(
+            detect_encoding(&le),
+            TextEncoding::new(Encoding::Utf16Le, false)
+        );
+
+        let be: Vec<u8> = "hello world"
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect();
+        assert_eq!(
+            detect_encoding(&be),
+            TextEncoding::new(Encoding::Utf16Be, false)
+        );
+    }
+
+    #[test]
+    fn test_detect_utf8_and_legacy() {
+        assert_eq!(detect_encoding(b"plain ascii\n"), TextEncoding::UTF8);
+        assert_eq!(
+            detect_encoding("caf\u{e9}\n".as_bytes()),
+            TextEncoding::UTF8
+        );
+        // A multi-byte character cut off at the end of a sample
+        assert_eq!(detect_encoding(b"caf\xC3"), TextEncoding::UTF8);
+
+        assert_eq!(
+            detect_encoding(b"caf\xE9 cr\xE8me\n"),
+            TextEncoding::new(Encoding::Latin1, false)
+        );
+        assert_eq!(
+            detect_encoding(b"\x93quoted\x94\n"),
+            TextEncoding::new(Encoding::Windows1252, false)
+        );
+
+        let (sjis, _, _) = encoding_rs::SHIFT_JIS.encode("こんにちは、世界\n");
+        assert_eq!(
+            detect_encoding(&sjis),
+            TextEncoding::new(Encoding::ShiftJis, false)
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let cases = [
+            (TextEncoding::new(Encoding::Utf8, true), "héllo\r\n"),
+            (TextEncoding::new(Encoding::Utf16Le, true), "héllo 😀\n"),
+            (TextEncoding::new(Encoding::Utf16Be, false), "héllo 😀\n"),
+            (
+                TextEncoding::new(Encoding::Latin1, false),
+                "caf\u{e9} \u{80}\n",
+            ),
+            (
+                TextEncoding::new(Encoding::Windows1252, false),
+                "\u{201c}x\u{201d}\n",
+            ),
+            (
+                TextEncoding::new(Encoding::ShiftJis, false),
+                "日本語テキスト\n",
+            ),
+            (
+                TextEncoding::new(Encoding::EucJp, false),
+                "日本語テキスト\n",
+            ),
+        ];
+        for (encoding, text) in cases {
+            let bytes = encoding.encode(text).unwrap();
+            assert!(bytes.starts_with(encoding.bom_bytes()));
+            assert_eq!(
+                encoding.decode(&bytes),
+                (text.to_string(), false),
+                "{}",
+                encoding
+            );
+        }
+    }
+
+    #[test]
+    fn test_latin1_decodes_every_byte() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let latin1 = TextEncoding::new(Encoding::Latin1, false);
+        let (text, had_errors) = latin1.decode(&bytes);
+        assert!(!had_errors);
+        assert_eq!(latin1.encode(&text).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_unmappable_character() {
+        let err = TextEncoding::new(Encoding::Latin1, false)
+            .encode("ok €")
+            .unwrap_err();
+        assert_eq!(err.ch, '€');
+
+        let err = TextEncoding::new(Encoding::ShiftJis, false)
+            .encode("abc é")
+            .unwrap_err();
+        assert_eq!(err.ch, 'é');
+    }
+
+    #[test]
+    fn test_invalid_input_reports_errors() {
+        let utf8 = TextEncoding::UTF8;
+        assert!(utf8.decode(b"\xFFabc").1);
+        let utf16 = TextEncoding::new(Encoding::Utf16Le, false);
+        assert!(utf16.decode(&[0x00, 0xD8, 0x41, 0x00]).1);
+    }
+
+    #[test]
+    fn test_from_name() {
+        for encoding in TextEncoding::ALL {
+            assert_eq!(
+                TextEncoding::from_name(encoding.display_name()),
+                Some(encoding)
+            );
+        }
+        assert_eq!(
+            TextEncoding::from_name("utf_16"),
+            Some(TextEncoding::new(Encoding::Utf16Le, true))
+        );
+        assert_eq!(
+            TextEncoding::from_name("latin1"),
+            Some(TextEncoding::new(Encoding::Latin1, false))
+        );
+        assert_eq!(TextEncoding::from_name("klingon"), None);
+    }
+}
@@ -507,12 +507,57 @@ pub struct Snapshot {
     pub cursor_positions: Vec<(CursorId, usize, Option<usize>)>,
 }
 
-/// The event log - append-only log of all events
+/// A state in the undo tree
+///
+/// State `0` is the initial state; log entry `i` leads to state `i + 1`.
+#[derive(Debug, Clone, Default)]
+struct UndoState {
+    /// State the entry leading here was applied to (`None` for state 0)
+    parent: Option<usize>,
+
+    /// States reached by applying one entry to this state, oldest first
+    children: Vec<usize>,
+
+    /// Child that redo follows: the branch last visited or created
+    redo_child: Option<usize>,
+}
+
+/// An undo tree in a form that can be saved, see [`EventLog::to_persisted`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PersistedUndoTree {
+    /// Entries in creation order, each with the state it was applied to
+    /// (0 for the initial state, `i + 1` for the state after entry `i`)
+    pub entries: Vec<(usize, LogEntry)>,
+
+    /// State matching the content of the saved file
+    pub saved: usize,
+}
+
+/// One line of the undo history tree, see [`EventLog::history`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryItem {
+    /// State reached after the edit (0 for the initial state)
+    pub state: usize,
+
+    /// Branch nesting depth, for indentation
+    pub depth: usize,
+}
+
+/// The event log - an undo tree of all events
+///
+/// Events are never discarded: making a new edit after undoing starts a new
+/// branch next to the undone one. Undo and redo move along the current
+/// branch, [`EventLog::switch_branch`] picks the branch redo follows and
+/// [`EventLog::jump_to`] moves to any state in the tree.
 pub struct EventLog {
-    /// All logged events
+    /// All logged events, in the order they were made
     entries: Vec<LogEntry>,
 
-    /// Current position in the log (for undo/redo)
+    /// Tree structure: `states[0]` is the initial state, `states[i + 1]` the
+    /// state after `entries[i]`
+    states: Vec<UndoState>,
+
+    /// Current state in the tree (for undo/redo)
     current_index: usize,
 
     /// Periodic snapshots for fast seeking
@@ -524,7 +569,7 @@ pub struct EventLog {
     /// Optional file for streaming events to disk
     stream_file: Option<std::fs::File>,
 
-    /// Index at which the buffer was last saved (for tracking modified status)
+    /// State at which the buffer was last saved (for tracking modified status)
     /// When current_index equals saved_at_index, the buffer is not modified
     saved_at_index: Option<usize>,
 }
@@ -534,6 +579,7 @@ impl EventLog {
     pub fn new() -> Self {
         Self {
             entries: Vec::new(),
+            states: vec![UndoState::default()],
             current_index: 0,
             snapshots: Vec::new(),
             snapshot_interval: 100,
@@ -549,25 +595,19 @@ impl EventLog {
     }
 
     /// Check if the buffer is at the saved position (not modified)
-    /// Returns true if we're at the saved position OR if all events between
-    /// saved_at_index and current_index are readonly (don't modify buffer content)
+    /// Returns true if we're at the saved position OR if all events on the
+    /// path between the saved state and the current state are readonly
+    /// (don't modify buffer content)
     pub fn is_at_saved_position(&self) -> bool {
         match self.saved_at_index {
             None => false,
             Some(saved_idx) if saved_idx == self.current_index => true,
+            Some(saved_idx) if saved_idx >= self.states.len() => false,
             Some(saved_idx) => {
-                // Check if all events between saved position and current position
-                // are readonly (don't modify buffer content)
-                let (start, end) = if saved_idx < self.current_index {
-                    (saved_idx, self.current_index)
-                } else {
-                    (self.current_index, saved_idx)
-                };
-
-                // All events in range [start, end) must be readonly
-                self.entries[start..end]
-                    .iter()
-                    .all(|entry| !entry.event.modifies_buffer())
+                let (up, down) = self.path(saved_idx, self.current_index);
+                up.iter()
+                    .chain(down.iter())
+                    .all(|state| !self.entries[state - 1].event.modifies_buffer())
             }
         }
     }
@@ -648,11 +688,6 @@ impl EventLog {
 
     /// Append an event to the log
     pub fn append(&mut self, event: Event) -> usize {
-        // If we're not at the end, truncate future events
-        if self.current_index < self.entries.len() {
-            self.entries.truncate(self.current_index);
-        }
-
         // Stream event to file if enabled
         if let Some(ref mut file) = self.stream_file {
             use std::io::Write;
@@ -672,9 +707,9 @@ impl EventLog {
             }
         }
 
-        let entry = LogEntry::new(event);
-        self.entries.push(entry);
-        self.current_index = self.entries.len();
+        // The new entry starts a new branch if we've undone before
+        let parent = self.current_index;
+        self.push_entry(LogEntry::new(event), parent);
 
         // Check if we should create a snapshot
         if self.entries.len() % self.snapshot_interval == 0 {
@@ -685,12 +720,25 @@ impl EventLog {
         self.current_index - 1
     }
 
-    /// Get the current event index
+    /// Add an entry as a child of `parent` and make its state current
+    fn push_entry(&mut self, entry: LogEntry, parent: usize) {
+        self.entries.push(entry);
+        let state = self.entries.len();
+        self.states.push(UndoState {
+            parent: Some(parent),
+            ..Default::default()
+        });
+        self.states[parent].children.push(state);
+        self.states[parent].redo_child = Some(state);
+        self.current_index = state;
+    }
+
+    /// Get the current state (0 for the initial state, `i + 1` after entry `i`)
     pub fn current_index(&self) -> usize {
         self.current_index
     }
 
-    /// Get the number of events in the log
+    /// Get the number of events in the log, across all branches
     pub fn len(&self) -> usize {
         self.entries.len()
     }
@@ -702,7 +750,7 @@ impl EventLog {
 
     /// Can we redo?
     pub fn can_redo(&self) -> bool {
-        self.current_index < self.entries.len()
+        self.states[self.current_index].redo_child.is_some()
     }
 
     /// Move back through events (for undo)
@@ -714,8 +762,9 @@ impl EventLog {
 
         // Keep moving backward until we find a write action
         while self.can_undo() && !found_write_action {
-            self.current_index -= 1;
-            let event = &self.entries[self.current_index].event;
+            let state = self.current_index;
+            self.step_to_parent();
+            let event = &self.entries[state - 1].event;
 
             // Check if this is a write action - we'll stop after processing it
             if event.is_write_action() {
@@ -740,8 +789,8 @@ impl EventLog {
         let mut found_write_action = false;
 
         // Keep moving forward to collect write action and subsequent readonly events
-        while self.can_redo() {
-            let event = self.entries[self.current_index].event.clone();
+        while let Some(child) = self.states[self.current_index].redo_child {
+            let event = self.entries[child - 1].event.clone();
 
             // If we've already found a write action and this is another write action, stop
             if found_write_action && event.is_write_action() {
@@ -749,7 +798,7 @@ impl EventLog {
                 break;
             }
 
-            self.current_index += 1;
+            self.current_index = child;
 
             // Mark if we found a write action
             if event.is_write_action() {
@@ -762,6 +811,197 @@ impl EventLog {
         events
     }
 
+    /// Move to the parent state, remembering the branch we came from for redo
+    fn step_to_parent(&mut self) {
+        let state = self.current_index;
+        if let Some(parent) = self.states[state].parent {
+            self.states[parent].redo_child = Some(state);
+            self.current_index = parent;
+        }
+    }
+
+    /// Ancestors of a state, from the state itself up to state 0
+    fn ancestors(&self, state: usize) -> Vec<usize> {
+        let mut chain = vec![state];
+        let mut current = state;
+        while let Some(parent) = self.states[current].parent {
+            chain.push(parent);
+            current = parent;
+        }
+        chain
+    }
+
+    /// The tree path from `from` to `to`: the states left going up to their
+    /// common ancestor, then the states entered going down, in order
+    fn path(&self, from: usize, to: usize) -> (Vec<usize>, Vec<usize>) {
+        let from_chain = self.ancestors(from);
+        let from_set: std::collections::HashSet<usize> = from_chain.iter().copied().collect();
+        let mut down = Vec::new();
+        let mut current = to;
+        while !from_set.contains(&current) {
+            down.push(current);
+            current = self.states[current]
+                .parent
+                .expect("state 0 is an ancestor of every state");
+        }
+        down.reverse();
+        let up = from_chain
+            .into_iter()
+            .take_while(|s| *s != current)
+            .collect();
+        (up, down)
+    }
+
+    /// Whether the entry leading to `state` is a write action
+    fn is_write_state(&self, state: usize) -> bool {
+        state > 0 && self.entries[state - 1].event.is_write_action()
+    }
+
+    /// Move to any state in the tree, returning the events to apply: the
+    /// inverses of the entries left, then the entries entered
+    ///
+    /// Redo afterwards continues along the path taken.
+    pub fn jump_to(&mut self, target: usize) -> Vec<Event> {
+        if target >= self.states.len() {
+            return Vec::new();
+        }
+        let (up, down) = self.path(self.current_index, target);
+        let mut events = Vec::new();
+        for _ in &up {
+            let state = self.current_index;
+            self.step_to_parent();
+            if let Some(inverse) = self.entries[state - 1].event.inverse() {
+                events.push(inverse);
+            }
+        }
+        for state in down {
+            if let Some(parent) = self.states[state].parent {
+                self.states[parent].redo_child = Some(state);
+            }
+            self.current_index = state;
+            events.push(self.entries[state - 1].event.clone());
+        }
+        events
+    }
+
+    /// Whether any entry in the subtree starting at `state` is a write action
+    fn has_writes(&self, state: usize) -> bool {
+        let mut stack = vec![state];
+        while let Some(state) = stack.pop() {
+            if self.is_write_state(state) {
+                return true;
+            }
+            stack.extend(&self.states[state].children);
+        }
+        false
+    }
+
+    /// Children of a state that lead to edits, ignoring branches that only
+    /// move the cursor
+    fn branches(&self, state: usize) -> Vec<usize> {
+        if self.states[state].children.len() < 2 {
+            return self.states[state].children.clone();
+        }
+        self.states[state]
+            .children
+            .iter()
+            .copied()
+            .filter(|child| self.has_writes(*child))
+            .collect()
+    }
+
+    /// The nearest state with another branch for redo to follow, reachable
+    /// from the current state without undoing an edit
+    fn branch_point(&self) -> Option<usize> {
+        let mut state = self.current_index;
+        loop {
+            let branches = self.branches(state);
+            let redo_child = self.states[state].redo_child;
+            if branches.len() > 1 || branches.iter().any(|b| Some(*b) != redo_child) {
+                return Some(state);
+            }
+            if state == 0 || self.is_write_state(state) {
+                return None;
+            }
+            state = self.states[state].parent?;
+        }
+    }
+
+    /// Make redo follow the next branch at the nearest branch point
+    ///
+    /// Returns the events to apply (undoing any cursor movement made since
+    /// the branch point), the 1-based number of the selected branch and the
+    /// branch count, or `None` if there is no branch to switch to.
+    pub fn switch_branch(&mut self) -> Option<(Vec<Event>, usize, usize)> {
+        let point = self.branch_point()?;
+        let branches = self.branches(point);
+        let events = self.jump_to(point);
+        let selected = self.states[point]
+            .redo_child
+            .and_then(|child| branches.iter().position(|b| *b == child))
+            .map(|i| (i + 1) % branches.len())
+            .unwrap_or(0);
+        self.states[point].redo_child = Some(branches[selected]);
+        Some((events, selected + 1, branches.len()))
+    }
+
+    /// The undo tree as a list for display
+    ///
+    /// Only the initial state and states reached by write actions are
+    /// listed. Each state is followed by its older branches, indented one
+    /// level deeper, and then by its newest branch at the same depth.
+    pub fn history(&self) -> Vec<HistoryItem> {
+        let mut items = Vec::new();
+        let mut stack = vec![(0, 0)];
+        while let Some((state, depth)) = stack.pop() {
+            items.push(HistoryItem { state, depth });
+            let children = self.visible_children(state);
+            if let Some((newest, older)) = children.split_last() {
+                // Pushed in reverse so they pop in display order
+                stack.push((*newest, depth));
+                for child in older.iter().rev() {
+                    stack.push((*child, depth + 1));
+                }
+            }
+        }
+        items
+    }
+
+    /// The nearest write-action descendants of a state, looking through
+    /// readonly entries
+    fn visible_children(&self, state: usize) -> Vec<usize> {
+        let mut result = Vec::new();
+        let mut stack: Vec<usize> = self.states[state].children.iter().rev().copied().collect();
+        while let Some(child) = stack.pop() {
+            if self.is_write_state(child) {
+                result.push(child);
+            } else {
+                stack.extend(self.states[child].children.iter().rev());
+            }
+        }
+        result
+    }
+
+    /// The state shown as current in [`EventLog::history`]: the current state
+    /// or, if that was reached by readonly entries, the state they started from
+    pub fn current_history_state(&self) -> usize {
+        let mut state = self.current_index;
+        while state > 0 && !self.is_write_state(state) {
+            state = self.states[state].parent.unwrap_or(0);
+        }
+        state
+    }
+
+    /// The state the buffer was last saved at, if any
+    pub fn saved_index(&self) -> Option<usize> {
+        self.saved_at_index
+    }
+
+    /// The entry that leads to a state (`None` for the initial state)
+    pub fn entry_for_state(&self, state: usize) -> Option<&LogEntry> {
+        state.checked_sub(1).and_then(|i| self.entries.get(i))
+    }
+
     /// Get all events from the log
     pub fn entries(&self) -> &[LogEntry] {
         &self.entries
@@ -784,6 +1024,7 @@ impl EventLog {
     /// Clear all events (for testing or reset)
     pub fn clear(&mut self) {
         self.entries.clear();
+        self.states = vec![UndoState::default()];
         self.current_index = 0;
         self.snapshots.clear();
     }
@@ -816,14 +1057,139 @@ impl EventLog {
                 continue;
             }
             let entry: LogEntry = serde_json::from_str(&line)?;
-            log.entries.push(entry);
+            let parent = log.current_index;
+            log.push_entry(entry, parent);
         }
 
-        log.current_index = log.entries.len();
-
         Ok(log)
     }
 
+    /// The undo tree for persisting, anchored at the saved state
+    ///
+    /// Only entries that modify the buffer are kept, and of those only the
+    /// `max_entries` nearest (in the tree) to the saved state; the topmost
+    /// state kept becomes the new initial state. Returns `None` if the log
+    /// has no saved state to anchor the tree to.
+    pub fn to_persisted(&self, max_entries: usize) -> Option<PersistedUndoTree> {
+        let saved = self.saved_at_index.filter(|s| *s < self.states.len())?;
+        Some(self.persisted_tree(saved, max_entries).0)
+    }
+
+    /// The undo tree of a buffer with unsaved changes, for hot exit
+    ///
+    /// Like [`EventLog::to_persisted`] but anchored at the current state, so
+    /// the tree's `saved` state matches the buffer's content rather than the
+    /// file's. Also returns the state matching the file, if it was kept.
+    pub fn to_stashed(&self, max_entries: usize) -> (PersistedUndoTree, Option<usize>) {
+        let (tree, new_ids) = self.persisted_tree(self.current_index, max_entries);
+        let file_state = self
+            .saved_at_index
+            .filter(|s| *s < self.states.len())
+            .and_then(|saved| new_ids.get(&self.nearest_modifying_state(saved)).copied());
+        (tree, file_state)
+    }
+
+    /// Rebuild a log from [`EventLog::to_stashed`] output, positioned at the
+    /// stashed state with `file_state` as the saved state
+    pub fn from_stashed(tree: PersistedUndoTree, file_state: Option<usize>) -> Option<Self> {
+        let mut log = Self::from_persisted(tree)?;
+        log.saved_at_index = file_state.filter(|s| *s < log.states.len());
+        Some(log)
+    }
+
+    /// The state itself if its entry modifies the buffer, otherwise its
+    /// nearest such ancestor (or state 0)
+    fn nearest_modifying_state(&self, mut state: usize) -> usize {
+        while state > 0 && !self.entries[state - 1].event.modifies_buffer() {
+            state = self.states[state].parent.unwrap_or(0);
+        }
+        state
+    }
+
+    /// The persisted tree kept around `anchor`, which becomes its `saved`
+    /// state, and the new ids of the kept states
+    fn persisted_tree(
+        &self,
+        anchor: usize,
+        max_entries: usize,
+    ) -> (PersistedUndoTree, std::collections::HashMap<usize, usize>) {
+        let modifies = |state: usize| state > 0 && self.entries[state - 1].event.modifies_buffer();
+
+        // Tree of the states that modify the buffer (plus state 0)
+        let mut parents: std::collections::HashMap<usize, usize> = Default::default();
+        let mut children: std::collections::HashMap<usize, Vec<usize>> = Default::default();
+        for state in (1..self.states.len()).filter(|s| modifies(*s)) {
+            let parent = self.nearest_modifying_state(self.states[state].parent.unwrap_or(0));
+            parents.insert(state, parent);
+            children.entry(parent).or_default().push(state);
+        }
+
+        // Breadth-first from the anchor, parents before newer children
+        let anchor = self.nearest_modifying_state(anchor);
+        let mut kept = std::collections::HashSet::from([anchor]);
+        let mut queue = std::collections::VecDeque::from([anchor]);
+        while let Some(state) = queue.pop_front() {
+            let neighbors = parents
+                .get(&state)
+                .into_iter()
+                .chain(children.get(&state).into_iter().flatten().rev());
+            for next in neighbors {
+                if kept.len() > max_entries {
+                    break;
+                }
+                if kept.insert(*next) {
+                    queue.push_back(*next);
+                }
+            }
+        }
+
+        // Renumber, with the topmost kept state as the new state 0
+        let mut order: Vec<usize> = kept.into_iter().collect();
+        order.sort_unstable();
+        let root = order
+            .iter()
+            .copied()
+            .find(|state| {
+                parents
+                    .get(state)
+                    .is_none_or(|p| order.binary_search(p).is_err())
+            })
+            .unwrap_or(0);
+        let mut new_ids = std::collections::HashMap::from([(root, 0)]);
+        let mut entries = Vec::new();
+        for state in order.into_iter().filter(|s| *s != root) {
+            new_ids.insert(state, entries.len() + 1);
+            entries.push((new_ids[&parents[&state]], self.entries[state - 1].clone()));
+        }
+
+        let tree = PersistedUndoTree {
+            entries,
+            saved: new_ids[&anchor],
+        };
+        (tree, new_ids)
+    }
+
+    /// Rebuild a log from a persisted undo tree, positioned at its saved
+    /// state; returns `None` if the tree is malformed
+    pub fn from_persisted(tree: PersistedUndoTree) -> Option<Self> {
+        let mut log = Self::new();
+        for (parent, entry) in tree.entries {
+            if parent >= log.states.len() {
+                return None;
+            }
+            log.push_entry(entry, parent);
+        }
+        if tree.saved >= log.states.len() {
+            return None;
+        }
+        for state in log.ancestors(tree.saved).windows(2) {
+            log.states[state[1]].redo_child = Some(state[0]);
+        }
+        log.current_index = tree.saved;
+        log.saved_at_index = Some(tree.saved);
+        Some(log)
+    }
+
     /// Set snapshot interval
     pub fn set_snapshot_interval(&mut self, interval: usize) {
         self.snapshot_interval = interval;
@@ -922,9 +1288,9 @@ mod tests {
                 assert_eq!(redo_count, events.len());
             }
 
-            /// Appending after undo should truncate redo history
+            /// Appending after undo should start a new branch, keeping the old one
             #[test]
-            fn append_after_undo_truncates(
+            fn append_after_undo_branches(
                 initial_events in prop::collection::vec(arb_event(), 2..10),
                 new_event in arb_event()
             ) {
@@ -941,9 +1307,13 @@ mod tests {
                 // Append new event
                 log.append(new_event);
 
-                // Should not be able to redo past the new event
-                assert_eq!(log.current_index(), index_after_undo + 1);
+                // Nothing to redo past the new event, and undoing returns to
+                // the branch point
                 assert!(!log.can_redo());
+                assert_eq!(log.len(), initial_events.len() + 1);
+                log.undo();
+                assert_eq!(log.current_index(), index_after_undo);
+                assert!(log.can_redo());
             }
         }
     }
@@ -1019,33 +1389,285 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_truncate_on_new_event_after_undo() {
-        let mut log = EventLog::new();
-
-        log.append(Event::Insert {
-            position: 0,
-            text: "a".to_string(),
+    fn insert(position: usize, text: &str) -> Event {
+        Event::Insert {
+            position,
+            text: text.to_string(),
             cursor_id: CursorId(0),
-        });
+        }
+    }
 
-        log.append(Event::Insert {
-            position: 1,
-            text: "b".to_string(),
+    fn move_cursor(old_position: usize, new_position: usize) -> Event {
+        Event::MoveCursor {
             cursor_id: CursorId(0),
-        });
+            old_position,
+            new_position,
+            old_anchor: None,
+            new_anchor: None,
+            old_sticky_column: 0,
+            new_sticky_column: 0,
+        }
+    }
+
+    fn inserted_text(events: &[Event]) -> Vec<&str> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                Event::Insert { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_new_event_after_undo_keeps_branch() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(insert(1, "b"));
 
         log.undo();
         assert_eq!(log.entries().len(), 2);
 
-        // Adding new event should truncate the future
-        log.append(Event::Insert {
-            position: 1,
-            text: "c".to_string(),
-            cursor_id: CursorId(0),
-        });
+        // Adding a new event starts a new branch instead of truncating
+        log.append(insert(1, "c"));
+        assert_eq!(log.entries().len(), 3);
+        assert_eq!(log.current_index(), 3);
+        assert!(!log.can_redo());
 
-        assert_eq!(log.entries().len(), 2);
+        // Redo after undo follows the newest branch
+        log.undo();
+        assert_eq!(log.current_index(), 1);
+        assert_eq!(inserted_text(&log.redo()), vec!["c"]);
+    }
+
+    #[test]
+    fn test_switch_branch() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(insert(1, "b"));
+        log.undo();
+        log.append(insert(1, "c"));
+
+        // No branch point between here and the last edit
+        assert!(log.switch_branch().is_none());
+
+        log.undo();
+        let (events, selected, count) = log.switch_branch().unwrap();
+        assert!(events.is_empty());
+        assert_eq!((selected, count), (1, 2));
+        assert_eq!(inserted_text(&log.redo()), vec!["b"]);
         assert_eq!(log.current_index(), 2);
+
+        // Cycling wraps around
+        log.undo();
+        assert_eq!(log.switch_branch().unwrap().1, 2);
+        assert_eq!(log.switch_branch().unwrap().1, 1);
+    }
+
+    #[test]
+    fn test_switch_branch_through_cursor_movement() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(insert(1, "b"));
+        log.undo();
+        // Moving the cursor after undo doesn't count as a branch, and
+        // switching undoes the movement to get back to the branch point
+        log.append(move_cursor(1, 0));
+        assert!(!log.can_redo());
+
+        let (events, selected, count) = log.switch_branch().unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            events[0],
+            Event::MoveCursor {
+                new_position: 1,
+                ..
+            }
+        ));
+        assert_eq!((selected, count), (1, 1));
+        assert_eq!(log.current_index(), 1);
+        assert_eq!(inserted_text(&log.redo()), vec!["b"]);
+    }
+
+    #[test]
+    fn test_jump_to() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(insert(1, "b"));
+        log.undo();
+        log.append(insert(1, "c"));
+        log.append(insert(2, "d"));
+
+        // From the tip of the "c" branch over to the "b" branch
+        let events = log.jump_to(2);
+        assert_eq!(log.current_index(), 2);
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], Event::Delete { deleted_text, .. } if deleted_text == "d"));
+        assert!(matches!(&events[1], Event::Delete { deleted_text, .. } if deleted_text == "c"));
+        assert_eq!(inserted_text(&events[2..]), vec!["b"]);
+
+        // Redo continues along the path taken
+        log.jump_to(0);
+        assert_eq!(inserted_text(&log.redo()), vec!["a"]);
+        assert_eq!(inserted_text(&log.redo()), vec!["b"]);
+        assert!(!log.can_redo());
+    }
+
+    #[test]
+    fn test_saved_position_across_branches() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.mark_saved();
+        log.append(insert(1, "b"));
+        log.undo();
+        assert!(log.is_at_saved_position());
+
+        log.append(move_cursor(1, 0));
+        assert!(log.is_at_saved_position());
+
+        log.jump_to(2);
+        assert!(!log.is_at_saved_position());
+        log.jump_to(3);
+        assert!(log.is_at_saved_position());
+    }
+
+    #[test]
+    fn test_history() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(move_cursor(1, 0));
+        log.append(insert(0, "b"));
+        log.undo();
+        log.append(insert(0, "c"));
+
+        let items: Vec<(usize, usize)> = log
+            .history()
+            .iter()
+            .map(|item| (item.state, item.depth))
+            .collect();
+        // The cursor movement is hidden; the older "b" branch is indented
+        assert_eq!(items, vec![(0, 0), (1, 0), (3, 1), (4, 0)]);
+        assert_eq!(log.current_history_state(), 4);
+
+        log.undo();
+        assert_eq!(log.current_index(), 2);
+        assert_eq!(log.current_history_state(), 1);
+    }
+
+    fn delete(position: usize, text: &str) -> Event {
+        Event::Delete {
+            range: position..position + text.len(),
+            deleted_text: text.to_string(),
+            cursor_id: CursorId(0),
+        }
+    }
+
+    #[test]
+    fn test_persisted_round_trip() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(move_cursor(1, 0));
+        log.append(insert(0, "b"));
+        log.undo();
+        log.append(insert(0, "c"));
+        log.undo();
+        log.mark_saved();
+
+        let tree = log.to_persisted(100).unwrap();
+        // The cursor movement is dropped; "b" and "c" are branches off "a"
+        assert_eq!(tree.entries.len(), 3);
+        assert_eq!(tree.saved, 1);
+
+        let mut restored = EventLog::from_persisted(tree).unwrap();
+        assert_eq!(restored.current_index(), 1);
+        assert!(restored.is_at_saved_position());
+        assert_eq!(inserted_text(&restored.redo()), vec!["c"]);
+        restored.undo();
+        assert_eq!(restored.switch_branch().unwrap().1, 1);
+        assert_eq!(inserted_text(&restored.redo()), vec!["b"]);
+        restored.undo();
+        let events = restored.undo();
+        assert!(matches!(&events[..], [Event::Delete { deleted_text, .. }] if deleted_text == "a"));
+        assert!(!restored.can_undo());
+    }
+
+    #[test]
+    fn test_persisted_keeps_entries_nearest_saved_state() {
+        let mut log = EventLog::new();
+        for (i, text) in ["a", "b", "c", "d", "e"].iter().enumerate() {
+            log.append(insert(i, text));
+        }
+        log.undo();
+        log.mark_saved();
+        log.append(delete(3, "d"));
+
+        // Saved after "d": keeps "c" and "d" before it and "e" and the
+        // delete after it; "b" becomes the initial state
+        let tree = log.to_persisted(4).unwrap();
+        let texts: Vec<String> = tree
+            .entries
+            .iter()
+            .map(|(_, entry)| match &entry.event {
+                Event::Insert { text, .. } => text.clone(),
+                Event::Delete { deleted_text, .. } => format!("-{}", deleted_text),
+                _ => String::new(),
+            })
+            .collect();
+        assert_eq!(texts, vec!["c", "d", "e", "-d"]);
+        assert_eq!(tree.saved, 2);
+
+        let mut restored = EventLog::from_persisted(tree).unwrap();
+        restored.undo();
+        restored.undo();
+        assert!(!restored.can_undo());
+    }
+
+    #[test]
+    fn test_persisted_requires_saved_state() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.saved_at_index = None;
+        assert!(log.to_persisted(10).is_none());
+
+        let malformed = PersistedUndoTree {
+            entries: vec![(5, LogEntry::new(insert(0, "a")))],
+            saved: 0,
+        };
+        assert!(EventLog::from_persisted(malformed).is_none());
+    }
+
+    #[test]
+    fn test_stashed_round_trip() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.mark_saved();
+        log.append(insert(1, "b"));
+        log.append(move_cursor(2, 1));
+
+        // Anchored at the unsaved "b", with the file at "a"
+        let (tree, file_state) = log.to_stashed(100);
+        assert_eq!(tree.entries.len(), 2);
+        assert_eq!(tree.saved, 2);
+        assert_eq!(file_state, Some(1));
+
+        let mut restored = EventLog::from_stashed(tree, file_state).unwrap();
+        assert_eq!(restored.current_index(), 2);
+        assert!(!restored.is_at_saved_position());
+        restored.undo();
+        assert!(restored.is_at_saved_position());
+    }
+
+    #[test]
+    fn test_stashed_drops_file_state_beyond_limit() {
+        let mut log = EventLog::new();
+        for (i, text) in ["a", "b", "c"].iter().enumerate() {
+            log.append(insert(i, text));
+        }
+
+        // The file's (initial) state is three edits away
+        let (tree, file_state) = log.to_stashed(1);
+        assert_eq!(file_state, None);
+        let restored = EventLog::from_stashed(tree, file_state).unwrap();
+        assert!(!restored.is_at_saved_position());
     }
 }
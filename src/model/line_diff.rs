@@ -299,6 +299,217 @@ pub fn merge_ranges(ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
     merged
 }
 
+/// A replacement of a byte range in an old text with new bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextReplacement {
+    /// Byte range in the old text
+    pub range: Range<usize>,
+    /// Bytes that replace the range
+    pub new_text: Vec<u8>,
+}
+
+/// Largest LCS table (old lines × new lines) computed before falling back to
+/// replacing the whole differing region in one piece
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+/// Compute a small set of replacements that turn `old` into `new`.
+///
+/// Unchanged lines are left alone so that cursors, markers and other
+/// position-based state on them are unaffected. Changed lines are diffed
+/// again word by word, so an edit in the middle of a line does not disturb
+/// positions elsewhere on it. Replacements are returned sorted by position
+/// and never overlap.
+pub fn diff_replacements(old: &[u8], new: &[u8]) -> Vec<TextReplacement> {
+    if old == new {
+        return Vec::new();
+    }
+
+    let old_lines: Vec<&[u8]> = old.split_inclusive(|&b| b == b'\n').collect();
+    let new_lines: Vec<&[u8]> = new.split_inclusive(|&b| b == b'\n').collect();
+
+    let mut replacements = Vec::new();
+    for (old_hunk, new_hunk) in changed_spans(&old_lines, &new_lines) {
+        let old_tokens = tokenize(&old[old_hunk.clone()]);
+        let new_tokens = tokenize(&new[new_hunk.clone()]);
+        for (old_span, new_span) in changed_spans(&old_tokens, &new_tokens) {
+            replacements.push(trim_replacement(
+                old,
+                old_hunk.start + old_span.start..old_hunk.start + old_span.end,
+                &new[new_hunk.start + new_span.start..new_hunk.start + new_span.end],
+            ));
+        }
+    }
+
+    replacements
+}
+
+/// Merge two versions of a text, keeping lines they share and wrapping each
+/// region where they differ in git-style conflict markers
+///
+/// `ours` goes first in each conflict, labelled `ours_label`, followed by
+/// `theirs`. Returns `ours` unchanged if the texts are equal.
+pub fn merge_with_conflict_markers(
+    ours: &[u8],
+    theirs: &[u8],
+    ours_label: &str,
+    theirs_label: &str,
+) -> Vec<u8> {
+    if ours == theirs {
+        return ours.to_vec();
+    }
+
+    let ours_lines: Vec<&[u8]> = ours.split_inclusive(|&b| b == b'\n').collect();
+    let theirs_lines: Vec<&[u8]> = theirs.split_inclusive(|&b| b == b'\n').collect();
+
+    // Conflict markers must start on their own line
+    let push_side = |merged: &mut Vec<u8>, text: &[u8]| {
+        merged.extend_from_slice(text);
+        if !text.is_empty() && !text.ends_with(b"\n") {
+            merged.push(b'\n');
+        }
+    };
+
+    let mut merged = Vec::with_capacity(ours.len() + theirs.len());
+    let mut ours_pos = 0;
+    for (ours_span, theirs_span) in changed_spans(&ours_lines, &theirs_lines) {
+        merged.extend_from_slice(&ours[ours_pos..ours_span.start]);
+        merged.extend_from_slice(format!("<<<<<<< {}\n", ours_label).as_bytes());
+        push_side(&mut merged, &ours[ours_span.clone()]);
+        merged.extend_from_slice(b"=======\n");
+        push_side(&mut merged, &theirs[theirs_span]);
+        merged.extend_from_slice(format!(">>>>>>> {}\n", theirs_label).as_bytes());
+        ours_pos = ours_span.end;
+    }
+    merged.extend_from_slice(&ours[ours_pos..]);
+    merged
+}
+
+/// Diff two sequences of byte slices and return the changed regions as
+/// (old byte range, new byte range) pairs, relative to the start of each
+/// sequence's concatenated bytes
+fn changed_spans(old: &[&[u8]], new: &[&[u8]]) -> Vec<(Range<usize>, Range<usize>)> {
+    // Byte offset of the start of each item (plus one past the end)
+    let offsets = |items: &[&[u8]]| {
+        let mut offsets = Vec::with_capacity(items.len() + 1);
+        let mut pos = 0;
+        offsets.push(pos);
+        for item in items {
+            pos += item.len();
+            offsets.push(pos);
+        }
+        offsets
+    };
+    let old_offsets = offsets(old);
+    let new_offsets = offsets(new);
+
+    // Trim common leading and trailing items before running the quadratic LCS
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let lcs = if old_mid.len().saturating_mul(new_mid.len()) <= MAX_LCS_CELLS {
+        longest_common_subsequence(old_mid, new_mid)
+    } else {
+        Vec::new()
+    };
+
+    // Walk the matched items; everything between two matches is one span
+    let mut spans = Vec::new();
+    let mut old_idx = 0;
+    let mut new_idx = 0;
+    let sentinel = LineMatch {
+        saved_idx: old_mid.len(),
+        current_idx: new_mid.len(),
+    };
+    for m in lcs.iter().copied().chain(std::iter::once(sentinel)) {
+        if m.saved_idx > old_idx || m.current_idx > new_idx {
+            spans.push((
+                old_offsets[prefix + old_idx]..old_offsets[prefix + m.saved_idx],
+                new_offsets[prefix + new_idx]..new_offsets[prefix + m.current_idx],
+            ));
+        }
+        old_idx = m.saved_idx + 1;
+        new_idx = m.current_idx + 1;
+    }
+    spans
+}
+
+/// Split text into words, runs of whitespace, and single other characters
+fn tokenize(text: &[u8]) -> Vec<&[u8]> {
+    #[derive(PartialEq)]
+    enum Class {
+        Word,
+        Space,
+        Other,
+    }
+    let class = |b: u8| {
+        if b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80 {
+            Class::Word
+        } else if b.is_ascii_whitespace() {
+            Class::Space
+        } else {
+            Class::Other
+        }
+    };
+
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    while start < text.len() {
+        let first = class(text[start]);
+        let mut end = start + 1;
+        if first != Class::Other {
+            while end < text.len() && class(text[end]) == first {
+                end += 1;
+            }
+        }
+        tokens.push(&text[start..end]);
+        start = end;
+    }
+    tokens
+}
+
+/// Shrink a replacement by the bytes its old and new text have in common at
+/// either end, keeping the boundaries on UTF-8 character boundaries
+fn trim_replacement(old: &[u8], range: Range<usize>, new_text: &[u8]) -> TextReplacement {
+    let old_text = &old[range.clone()];
+    let is_boundary = |text: &[u8], i: usize| i >= text.len() || (text[i] & 0xC0) != 0x80;
+
+    let mut head = old_text
+        .iter()
+        .zip(new_text)
+        .take_while(|(a, b)| a == b)
+        .count();
+    while head > 0 && !(is_boundary(old_text, head) && is_boundary(new_text, head)) {
+        head -= 1;
+    }
+
+    let max_tail = old_text.len().min(new_text.len()) - head;
+    let mut tail = old_text
+        .iter()
+        .rev()
+        .zip(new_text.iter().rev())
+        .take(max_tail)
+        .take_while(|(a, b)| a == b)
+        .count();
+    while tail > 0
+        && !(is_boundary(old_text, old_text.len() - tail)
+            && is_boundary(new_text, new_text.len() - tail))
+    {
+        tail -= 1;
+    }
+
+    TextReplacement {
+        range: range.start + head..range.end - tail,
+        new_text: new_text[head..new_text.len() - tail].to_vec(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -486,6 +697,81 @@ mod tests {
         assert!(!diff.equal);
         assert_eq!(diff.changed_lines, vec![0..1]);
     }
+
+    /// Apply replacements (sorted, non-overlapping) to `old`
+    fn apply_replacements(old: &[u8], replacements: &[TextReplacement]) -> Vec<u8> {
+        let mut result = old.to_vec();
+        for r in replacements.iter().rev() {
+            result.splice(r.range.clone(), r.new_text.iter().copied());
+        }
+        result
+    }
+
+    #[test]
+    fn test_merge_with_conflict_markers_wraps_differing_lines() {
+        let ours = b"one\ntwo\nthree\nfour\n";
+        let theirs = b"one\n2\nthree\nfour\nfive\n";
+        let merged = merge_with_conflict_markers(ours, theirs, "stashed", "disk");
+        assert_eq!(
+            String::from_utf8(merged).unwrap(),
+            "one\n<<<<<<< stashed\ntwo\n=======\n2\n>>>>>>> disk\nthree\nfour\n\
+             <<<<<<< stashed\n=======\nfive\n>>>>>>> disk\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_with_conflict_markers_without_trailing_newline() {
+        let merged = merge_with_conflict_markers(b"a\nb", b"a\nc", "ours", "theirs");
+        assert_eq!(
+            String::from_utf8(merged).unwrap(),
+            "a\n<<<<<<< ours\nb\n=======\nc\n>>>>>>> theirs\n"
+        );
+        assert_eq!(
+            merge_with_conflict_markers(b"same", b"same", "o", "t"),
+            b"same"
+        );
+    }
+
+    #[test]
+    fn test_diff_replacements_identical() {
+        assert!(diff_replacements(b"a\nb\n", b"a\nb\n").is_empty());
+    }
+
+    #[test]
+    fn test_diff_replacements_touches_only_changed_lines() {
+        let old = b"fn main() {\nlet x=1;\n    let y = 2;\n}\n";
+        let new = b"fn main() {\n    let x = 1;\n    let y = 2;\n}\n";
+        let replacements = diff_replacements(old, new);
+
+        // Only the inserted whitespace is touched, not the unchanged words
+        let insert = |at: usize, text: &[u8]| TextReplacement {
+            range: at..at,
+            new_text: text.to_vec(),
+        };
+        assert_eq!(
+            replacements,
+            vec![insert(12, b"    "), insert(17, b" "), insert(18, b" ")]
+        );
+        assert_eq!(apply_replacements(old, &replacements), new.to_vec());
+    }
+
+    #[test]
+    fn test_diff_replacements_inserted_and_removed_lines() {
+        let old = b"a\nb\nc\nd\n";
+        let new = b"a\nx\nc\nd\ne\n";
+        let replacements = diff_replacements(old, new);
+        assert_eq!(apply_replacements(old, &replacements), new.to_vec());
+        assert_eq!(replacements.len(), 2);
+    }
+
+    #[test]
+    fn test_diff_replacements_respects_utf8_boundaries() {
+        let old = "é\n".as_bytes();
+        let new = "è\n".as_bytes();
+        let replacements = diff_replacements(old, new);
+        assert_eq!(replacements[0].range, 0..2);
+        assert_eq!(apply_replacements(old, &replacements), new.to_vec());
+    }
 }
 
 #[cfg(test)]
@@ -510,6 +796,23 @@ mod proptests {
             prop_assert!(diff.changed_lines.is_empty());
         }
 
+        /// Applying the replacements to the old text always yields the new text
+        #[test]
+        fn diff_replacements_round_trip(
+            old in multiline_string(),
+            new in multiline_string()
+        ) {
+            let replacements = diff_replacements(&old, &new);
+            let mut result = old.clone();
+            for r in replacements.iter().rev() {
+                result.splice(r.range.clone(), r.new_text.iter().copied());
+            }
+            prop_assert_eq!(result, new);
+            for pair in replacements.windows(2) {
+                prop_assert!(pair[0].range.end <= pair[1].range.start);
+            }
+        }
+
         /// Diff should be symmetric in terms of detecting changes
         /// (though the specific changed_lines may differ)
         #[test]
@@ -10,7 +10,7 @@
 ///
 /// The Vec-based implementation is kept for compatibility and simplicity in
 /// situations where marker count is low (<100).
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::model::marker_tree::IntervalTree;
 
@@ -48,6 +48,10 @@ pub struct MarkerList {
     /// Track affinity for compatibility (though IntervalTree handles this through intervals)
     /// We don't strictly need this for the tree, but keep it for API compatibility
     _affinity_map: HashMap<MarkerId, bool>,
+
+    /// Markers created with `create_exact` that stay before text inserted at
+    /// their position
+    exact_left: HashSet<MarkerId>,
 }
 
 impl MarkerList {
@@ -56,6 +60,7 @@ impl MarkerList {
         Self {
             tree: IntervalTree::new(),
             _affinity_map: HashMap::new(),
+            exact_left: HashSet::new(),
         }
     }
 
@@ -93,10 +98,26 @@ impl MarkerList {
         id
     }
 
+    /// Create a marker that honours `left_affinity` for text inserted exactly at
+    /// its position
+    ///
+    /// Markers from [`create`](Self::create) always move past such text. Use
+    /// this for the start of a range that must grow when text is typed at its
+    /// start, like a snippet placeholder. Each exact left-affinity marker
+    /// costs O(log n) per insertion, so keep their number small.
+    pub fn create_exact(&mut self, position: usize, left_affinity: bool) -> MarkerId {
+        let id = self.create(position, left_affinity);
+        if left_affinity {
+            self.exact_left.insert(id);
+        }
+        id
+    }
+
     /// Delete a marker
     pub fn delete(&mut self, id: MarkerId) {
         self.tree.delete(id.0);
         self._affinity_map.remove(&id);
+        self.exact_left.remove(&id);
     }
 
     /// Get the current byte position of a marker
@@ -147,7 +168,19 @@ impl MarkerList {
             return;
         }
 
+        let pinned: Vec<MarkerId> = self
+            .exact_left
+            .iter()
+            .copied()
+            .filter(|id| self.get_position(*id) == Some(position))
+            .collect();
+
         self.tree.adjust_for_edit(position as u64, length as i64);
+
+        for id in pinned {
+            self.tree
+                .set_position(id.0, position as u64, position as u64);
+        }
     }
 
     /// Adjust all markers for a deletion
@@ -517,6 +550,31 @@ mod tests {
         list.check_invariants().unwrap();
     }
 
+    #[test]
+    fn test_exact_markers_honour_affinity() {
+        let mut list = MarkerList::new();
+
+        let start = list.create_exact(10, true);
+        let end = list.create_exact(10, false);
+        let plain = list.create(10, true);
+
+        // Typing into an empty range grows it
+        list.adjust_for_insert(10, 5);
+        assert_eq!(list.get_position(start), Some(10));
+        assert_eq!(list.get_position(end), Some(15));
+        assert_eq!(list.get_position(plain), Some(15));
+
+        // Insertions before the range still shift it
+        list.adjust_for_insert(0, 2);
+        assert_eq!(list.get_position(start), Some(12));
+        assert_eq!(list.get_position(end), Some(17));
+
+        list.delete(start);
+        assert_eq!(list.get_position(start), None);
+        list.adjust_for_insert(12, 1);
+        assert_eq!(list.get_position(end), Some(18));
+    }
+
     // Property-based tests
     #[cfg(test)]
     mod property_tests {
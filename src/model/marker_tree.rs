@@ -1,7 +1,6 @@
 use std::cell::{RefCell, RefMut};
 use std::cmp::{max, Ordering};
 use std::collections::HashMap;
-use std::mem;
 use std::rc::{Rc, Weak};
 
 /// Use a simple u64 for marker IDs
@@ -237,11 +236,31 @@ impl IntervalTree {
             return false;
         }
 
-        self.root = Self::delete_recursive(self.root.take(), start, id);
+        let mut moved = None;
+        self.root = Self::delete_recursive(self.root.take(), start, id, &mut moved);
+
+        // Deleting a node with two children moves its successor's marker into it
+        if let Some((moved_id, node)) = moved {
+            self.marker_map.insert(moved_id, node);
+        }
 
         self.marker_map.remove(&id).is_some()
     }
 
+    /// Moves a marker to a new interval, keeping its ID. Performance: O(log n)
+    pub fn set_position(&mut self, id: MarkerId, start: u64, end: u64) -> bool {
+        let Some(mut marker) = self.get_marker(id) else {
+            return false;
+        };
+        self.delete(id);
+        marker.interval = Interval { start, end };
+
+        let new_node = Node::new(marker, Weak::new());
+        self.root = Self::insert_recursive(self.root.take(), new_node.clone());
+        self.marker_map.insert(id, new_node);
+        true
+    }
+
     /// Adjusts all markers for a text edit (insertion or deletion).
     /// Performance: O(log n) due to lazy delta propagation.
     pub fn adjust_for_edit(&mut self, pos: u64, delta: i64) {
@@ -327,7 +346,12 @@ impl IntervalTree {
     }
 
     /// Recursive helper for delete
-    fn delete_recursive(root: NodePtr, start: u64, id: MarkerId) -> NodePtr {
+    fn delete_recursive(
+        root: NodePtr,
+        start: u64,
+        id: MarkerId,
+        moved: &mut Option<(MarkerId, Rc<RefCell<Node>>)>,
+    ) -> NodePtr {
         // Remove unnecessary 'mut'
         let root = match root {
             Some(r) => r,
@@ -341,20 +365,21 @@ impl IntervalTree {
 
         match start.cmp(&root_start) {
             Ordering::Less => {
-                root_mut.left = Self::delete_recursive(root_mut.left.take(), start, id);
+                root_mut.left = Self::delete_recursive(root_mut.left.take(), start, id, moved);
             }
             Ordering::Greater => {
-                root_mut.right = Self::delete_recursive(root_mut.right.take(), start, id);
+                root_mut.right = Self::delete_recursive(root_mut.right.take(), start, id, moved);
             }
             Ordering::Equal => match id.cmp(&root_id) {
                 Ordering::Less => {
-                    root_mut.left = Self::delete_recursive(root_mut.left.take(), start, id);
+                    root_mut.left = Self::delete_recursive(root_mut.left.take(), start, id, moved);
                 }
                 Ordering::Greater => {
-                    root_mut.right = Self::delete_recursive(root_mut.right.take(), start, id);
+                    root_mut.right =
+                        Self::delete_recursive(root_mut.right.take(), start, id, moved);
                 }
                 Ordering::Equal => {
-                    return Self::perform_node_deletion(root_mut, Rc::clone(&root));
+                    return Self::perform_node_deletion(root_mut, Rc::clone(&root), moved);
                 }
             },
         }
@@ -365,7 +390,11 @@ impl IntervalTree {
     }
 
     /// Handles the actual structural changes for deletion.
-    fn perform_node_deletion(mut node: RefMut<Node>, node_rc: Rc<RefCell<Node>>) -> NodePtr {
+    fn perform_node_deletion(
+        mut node: RefMut<Node>,
+        node_rc: Rc<RefCell<Node>>,
+        moved: &mut Option<(MarkerId, Rc<RefCell<Node>>)>,
+    ) -> NodePtr {
         if node.left.is_none() {
             let right = node.right.take();
             if let Some(ref r) = right {
@@ -386,9 +415,12 @@ impl IntervalTree {
                 (s.marker.interval.start, s.marker.id)
             };
 
-            mem::swap(&mut node.marker, &mut successor_rc.borrow_mut().marker);
-
-            node.right = Self::delete_recursive(node.right.take(), successor_start, successor_id);
+            // The successor's marker takes this node's place and its own node
+            // is removed from the right subtree
+            node.marker = successor_rc.borrow().marker.clone();
+            node.right =
+                Self::delete_recursive(node.right.take(), successor_start, successor_id, &mut None);
+            *moved = Some((successor_id, Rc::clone(&node_rc)));
 
             drop(node);
             Node::update_stats(&node_rc);
@@ -877,4 +909,24 @@ mod tests {
             "Marker at 40 should shift to 24"
         );
     }
+
+    #[test]
+    fn test_delete_node_with_two_children() {
+        let mut tree = IntervalTree::new();
+        let ids: Vec<MarkerId> = [3, 7, 8, 8, 16, 16]
+            .iter()
+            .map(|&pos| tree.insert(pos, pos))
+            .collect();
+
+        // Inner nodes have two children, so their successors move up
+        tree.delete(ids[2]);
+        tree.delete(ids[3]);
+        tree.adjust_for_edit(10, 2);
+
+        assert_eq!(get_pos(&tree, ids[0]), (3, 3));
+        assert_eq!(get_pos(&tree, ids[1]), (7, 7));
+        assert_eq!(get_pos(&tree, ids[4]), (18, 18));
+        assert_eq!(get_pos(&tree, ids[5]), (18, 18));
+        assert_eq!(tree.query(0, 100).len(), 4);
+    }
 }
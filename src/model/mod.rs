@@ -7,6 +7,7 @@ pub mod control_event;
 pub mod cursor;
 pub mod document_model;
 pub mod edit;
+pub mod encoding;
 pub mod event;
 pub mod line_diff;
 pub mod marker;
@@ -1,4 +1,5 @@
-use std::io::{self, Read, Seek, SeekFrom};
+use crate::services::fs::FsManager;
+use std::io;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -98,9 +99,9 @@ impl StringBuffer {
         }
     }
 
-    /// Load buffer data from file (for unloaded buffers)
+    /// Load buffer data from file on `fs` (for unloaded buffers)
     /// Returns error if buffer is not unloaded or if I/O fails
-    pub fn load(&mut self) -> io::Result<()> {
+    pub fn load(&mut self, fs: &FsManager) -> io::Result<()> {
         match &self.data {
             BufferData::Loaded { .. } => Ok(()), // Already loaded
             BufferData::Unloaded {
@@ -109,11 +110,7 @@ impl StringBuffer {
                 bytes,
             } => {
                 // Load from file
-                let mut file = std::fs::File::open(file_path)?;
-                file.seek(SeekFrom::Start(*file_offset as u64))?;
-
-                let mut buffer = vec![0u8; *bytes];
-                file.read_exact(&mut buffer)?;
+                let buffer = fs.block_on(fs.read_range(file_path, *file_offset as u64, *bytes))?;
 
                 // Replace with loaded data (no line indexing for lazy-loaded chunks)
                 self.data = BufferData::Loaded {
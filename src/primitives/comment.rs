@@ -0,0 +1,398 @@
+//! Comment tokens and comment toggling
+//!
+//! A language comments code with a line prefix (`//`, `#`), a pair of block
+//! delimiters (`/* */`, `<!-- -->`), or both. The tokens come from the
+//! language's [`LanguageConfig`](crate::config::LanguageConfig) when set there,
+//! and from the built-in defaults here otherwise. Languages embedded in another
+//! one, such as a `<script>` inside HTML, are resolved from the tree-sitter
+//! parse so that the code at the cursor is commented in its own syntax.
+//!
+//! The toggle functions only compute the edits; applying them is up to the
+//! caller.
+
+use crate::primitives::highlighter::Language;
+use std::ops::Range;
+use tree_sitter::Parser;
+
+/// Buffers larger than this are not parsed to find embedded languages
+const MAX_PARSE_BYTES: usize = 1024 * 1024;
+
+/// Comment syntax of a language
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommentTokens {
+    /// Line comment prefix, without trailing space (e.g., "//")
+    pub line: Option<String>,
+    /// Block comment delimiters (e.g., "/*" and "*/")
+    pub block: Option<(String, String)>,
+}
+
+impl CommentTokens {
+    fn new(line: Option<&str>, block: Option<(&str, &str)>) -> Self {
+        Self {
+            line: line.map(str::to_string),
+            block: block.map(|(start, end)| (start.to_string(), end.to_string())),
+        }
+    }
+
+    /// Built-in comment tokens for a tree-sitter language
+    ///
+    /// Grammars loaded at runtime have none: their tokens come from the config
+    /// of their language.
+    pub fn for_language(language: Language) -> Self {
+        match language {
+            Language::Rust
+            | Language::JavaScript
+            | Language::TypeScript
+            | Language::C
+            | Language::Cpp
+            | Language::Go
+            | Language::Java
+            | Language::CSharp
+            | Language::Php => Self::new(Some("//"), Some(("/*", "*/"))),
+            Language::Python | Language::Ruby | Language::Bash => Self::new(Some("#"), None),
+            Language::Lua => Self::new(Some("--"), Some(("--[[", "]]"))),
+            Language::HTML => Self::new(None, Some(("<!--", "-->"))),
+            Language::CSS => Self::new(None, Some(("/*", "*/"))),
+            Language::Json | Language::Dynamic(_) => Self::default(),
+        }
+    }
+
+    /// Built-in comment tokens for file types without a tree-sitter grammar
+    pub fn for_extension(extension: &str) -> Option<Self> {
+        let tokens = match extension {
+            "swift" | "kt" | "scala" | "scss" | "sass" | "less" => {
+                Self::new(Some("//"), Some(("/*", "*/")))
+            }
+            "pl" | "r" | "yml" | "yaml" | "toml" | "zsh" | "fish" | "conf" | "ini" => {
+                Self::new(Some("#"), None)
+            }
+            "sql" => Self::new(Some("--"), Some(("/*", "*/"))),
+            "xml" | "svg" | "vue" => Self::new(None, Some(("<!--", "-->"))),
+            "vim" => Self::new(Some("\""), None),
+            "lisp" | "el" | "clj" => Self::new(Some(";;"), None),
+            _ => return None,
+        };
+        Some(tokens)
+    }
+
+    /// Fill the tokens missing here from `defaults`
+    pub fn or_defaults(self, defaults: CommentTokens) -> Self {
+        Self {
+            line: self.line.or(defaults.line),
+            block: self.block.or(defaults.block),
+        }
+    }
+}
+
+/// Name of a tree-sitter language in the `languages` section of the config
+pub fn language_id(language: Language) -> &'static str {
+    match language {
+        Language::Rust => "rust",
+        Language::Python => "python",
+        Language::JavaScript => "javascript",
+        Language::TypeScript => "typescript",
+        Language::HTML => "html",
+        Language::CSS => "css",
+        Language::C => "c",
+        Language::Cpp => "cpp",
+        Language::Go => "go",
+        Language::Json => "json",
+        Language::Java => "java",
+        Language::CSharp => "csharp",
+        Language::Php => "php",
+        Language::Ruby => "ruby",
+        Language::Bash => "bash",
+        Language::Lua => "lua",
+        Language::Dynamic(grammar) => grammar.language_id.as_str(),
+    }
+}
+
+/// The language of the code at `position`
+///
+/// This is `language` itself unless `position` is inside code embedded in it:
+/// JavaScript in an HTML `<script>` element or CSS in a `<style>` element.
+pub fn language_at(language: Language, source: &str, position: usize) -> Language {
+    if !matches!(language, Language::HTML) || source.len() > MAX_PARSE_BYTES {
+        return language;
+    }
+
+    let mut parser = Parser::new();
+    if parser
+        .set_language(&language.tree_sitter_language())
+        .is_err()
+    {
+        return language;
+    }
+    let Some(tree) = parser.parse(source, None) else {
+        return language;
+    };
+
+    let mut current = tree
+        .root_node()
+        .descendant_for_byte_range(position, position);
+    while let Some(node) = current {
+        if node.kind() == "raw_text" {
+            match node.parent().map(|parent| parent.kind()) {
+                Some("script_element") => return Language::JavaScript,
+                Some("style_element") => return Language::CSS,
+                _ => {}
+            }
+        }
+        current = node.parent();
+    }
+    language
+}
+
+/// An edit that toggles a comment: `range` is replaced with `text`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommentEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+impl CommentEdit {
+    fn insert(position: usize, text: String) -> Self {
+        Self {
+            range: position..position,
+            text,
+        }
+    }
+
+    fn delete(range: Range<usize>) -> Self {
+        Self {
+            range,
+            text: String::new(),
+        }
+    }
+}
+
+/// Byte range of `line` without its leading and trailing whitespace,
+/// relative to the start of the line
+fn content_range(line: &str) -> Range<usize> {
+    let start = line.len() - line.trim_start().len();
+    let end = line.trim_end().len().max(start);
+    start..end
+}
+
+/// The lines that are not blank
+fn non_blank<'a>(lines: &[(usize, &'a str)]) -> Vec<(usize, &'a str)> {
+    lines
+        .iter()
+        .copied()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .collect()
+}
+
+/// Edits that comment or uncomment `lines` with a line comment `prefix`
+///
+/// Each line is given with its byte offset in the buffer. If every non-blank
+/// line is already commented the prefix is removed, otherwise it is added
+/// after each line's indentation. Returns the edits in reverse buffer order
+/// and whether they comment the lines.
+pub fn toggle_line_comments(lines: &[(usize, &str)], prefix: &str) -> (Vec<CommentEdit>, bool) {
+    let lines = non_blank(lines);
+    let uncomment = !lines.is_empty()
+        && lines
+            .iter()
+            .all(|(_, line)| line.trim_start().starts_with(prefix));
+
+    let mut edits = Vec::new();
+    for &(line_start, line) in lines.iter().rev() {
+        let indent = content_range(line).start;
+        let at = line_start + indent;
+        if uncomment {
+            let mut len = prefix.len();
+            if line[indent + len..].starts_with(' ') {
+                len += 1;
+            }
+            edits.push(CommentEdit::delete(at..at + len));
+        } else {
+            edits.push(CommentEdit::insert(at, format!("{} ", prefix)));
+        }
+    }
+    (edits, !uncomment)
+}
+
+/// Edits that comment or uncomment each of `lines` with a block comment, for
+/// languages without line comments
+///
+/// Same as [`toggle_line_comments`], with every non-blank line wrapped in its
+/// own block comment.
+pub fn toggle_line_block_comments(
+    lines: &[(usize, &str)],
+    delimiters: (&str, &str),
+) -> (Vec<CommentEdit>, bool) {
+    let lines = non_blank(lines);
+    let uncomment = !lines.is_empty()
+        && lines
+            .iter()
+            .all(|(_, line)| is_block_commented(line, delimiters));
+
+    let edits = lines
+        .iter()
+        .rev()
+        .flat_map(|&(line_start, line)| {
+            block_comment_edits(line, line_start, delimiters, uncomment)
+        })
+        .collect();
+    (edits, !uncomment)
+}
+
+/// Edits that wrap `text` (starting at byte `offset` of the buffer) in a
+/// block comment, or unwrap it if it already is one
+///
+/// Leading and trailing whitespace of `text` stays outside the comment.
+/// Returns the edits in reverse buffer order and whether they comment the text.
+pub fn toggle_block_comment(
+    text: &str,
+    offset: usize,
+    delimiters: (&str, &str),
+) -> (Vec<CommentEdit>, bool) {
+    let uncomment = is_block_commented(text, delimiters);
+    (
+        block_comment_edits(text, offset, delimiters, uncomment),
+        !uncomment,
+    )
+}
+
+fn is_block_commented(text: &str, (start, end): (&str, &str)) -> bool {
+    let inner = text.trim();
+    inner.len() >= start.len() + end.len() && inner.starts_with(start) && inner.ends_with(end)
+}
+
+fn block_comment_edits(
+    text: &str,
+    offset: usize,
+    (start, end): (&str, &str),
+    uncomment: bool,
+) -> Vec<CommentEdit> {
+    let content = content_range(text);
+    let content_start = offset + content.start;
+    let content_end = offset + content.end;
+
+    if !uncomment {
+        return vec![
+            CommentEdit::insert(content_end, format!(" {}", end)),
+            CommentEdit::insert(content_start, format!("{} ", start)),
+        ];
+    }
+
+    let inner = &text[content];
+    let mut open = start.len();
+    let mut close = end.len();
+    let body = &inner[open..inner.len() - close];
+    if body.starts_with(' ') {
+        open += 1;
+    }
+    if body.len() > 1 && body.ends_with(' ') {
+        close += 1;
+    }
+    vec![
+        CommentEdit::delete(content_end - close..content_end),
+        CommentEdit::delete(content_start..content_start + open),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply edits given in reverse buffer order to `text`
+    fn apply(text: &str, edits: &[CommentEdit]) -> String {
+        let mut text = text.to_string();
+        for edit in edits {
+            text.replace_range(edit.range.clone(), &edit.text);
+        }
+        text
+    }
+
+    fn lines(text: &str) -> Vec<(usize, &str)> {
+        let mut offset = 0;
+        text.split_inclusive('\n')
+            .map(|line| {
+                let start = offset;
+                offset += line.len();
+                (start, line)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_toggle_line_comments() {
+        let text = "fn main() {\n    call();\n\n}\n";
+        let (edits, commented) = toggle_line_comments(&lines(text), "//");
+        assert!(commented);
+        let commented_text = apply(text, &edits);
+        assert_eq!(commented_text, "// fn main() {\n    // call();\n\n// }\n");
+
+        let (edits, commented) = toggle_line_comments(&lines(&commented_text), "//");
+        assert!(!commented);
+        assert_eq!(apply(&commented_text, &edits), text);
+    }
+
+    #[test]
+    fn test_partially_commented_lines_are_commented() {
+        let text = "# a\nb\n";
+        let (edits, commented) = toggle_line_comments(&lines(text), "#");
+        assert!(commented);
+        assert_eq!(apply(text, &edits), "# # a\n# b\n");
+    }
+
+    #[test]
+    fn test_toggle_block_comment() {
+        let text = "  <p>hi</p>\n";
+        let (edits, commented) = toggle_block_comment(text, 0, ("<!--", "-->"));
+        assert!(commented);
+        let commented_text = apply(text, &edits);
+        assert_eq!(commented_text, "  <!-- <p>hi</p> -->\n");
+
+        let (edits, commented) = toggle_block_comment(&commented_text, 0, ("<!--", "-->"));
+        assert!(!commented);
+        assert_eq!(apply(&commented_text, &edits), text);
+    }
+
+    #[test]
+    fn test_toggle_line_block_comments() {
+        let text = "p { color: red; }\n\np { margin: 0; }\n";
+        let (edits, commented) = toggle_line_block_comments(&lines(text), ("/*", "*/"));
+        assert!(commented);
+        let commented_text = apply(text, &edits);
+        assert_eq!(
+            commented_text,
+            "/* p { color: red; } */\n\n/* p { margin: 0; } */\n"
+        );
+
+        let (edits, commented) = toggle_line_block_comments(&lines(&commented_text), ("/*", "*/"));
+        assert!(!commented);
+        assert_eq!(apply(&commented_text, &edits), text);
+    }
+
+    #[test]
+    fn test_uncomment_block_without_spaces() {
+        let text = "/*x*/";
+        let (edits, commented) = toggle_block_comment(text, 0, ("/*", "*/"));
+        assert!(!commented);
+        assert_eq!(apply(text, &edits), "x");
+    }
+
+    #[test]
+    fn test_language_at_script_in_html() {
+        let source =
+            "<p>text</p>\n<script>\nlet x = 1;\n</script>\n<style>\np { color: red; }\n</style>\n";
+        let script = source.find("let x").unwrap();
+        let style = source.find("color").unwrap();
+
+        assert!(matches!(
+            language_at(Language::HTML, source, script),
+            Language::JavaScript
+        ));
+        assert!(matches!(
+            language_at(Language::HTML, source, style),
+            Language::CSS
+        ));
+        assert!(matches!(
+            language_at(Language::HTML, source, 4),
+            Language::HTML
+        ));
+    }
+}
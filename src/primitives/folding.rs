@@ -0,0 +1,277 @@
+//! Fold range detection
+//!
+//! Foldable regions come from one of three sources, in order of preference:
+//! the language server (`textDocument/foldingRange`), the tree-sitter parse of
+//! the buffer, or indentation. All of them produce line-based [`FoldRange`]s:
+//! the header line stays visible and the lines after it, up to and including
+//! `end_line`, are hidden when the region is folded.
+
+use crate::primitives::highlighter::Language;
+use std::collections::BTreeMap;
+use tree_sitter::Parser;
+
+/// Buffers larger than this are not parsed for folding (indentation is used instead)
+const MAX_PARSE_BYTES: usize = 1024 * 1024;
+
+/// Tokens that close a block on their own line; such a line stays visible
+/// below a fold, like the closing brace of a function
+const CLOSING_TOKENS: &[&str] = &["}", "]", ")", "</", "end", "fi", "done", "esac"];
+
+/// A foldable region of lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FoldRange {
+    /// Line that stays visible when the region is folded
+    pub start_line: usize,
+    /// Last line hidden by the fold (inclusive)
+    pub end_line: usize,
+}
+
+impl FoldRange {
+    pub fn new(start_line: usize, end_line: usize) -> Self {
+        Self {
+            start_line,
+            end_line,
+        }
+    }
+
+    /// Whether `line` is the header or one of the hidden lines of this region
+    pub fn contains_line(&self, line: usize) -> bool {
+        line >= self.start_line && line <= self.end_line
+    }
+}
+
+/// Sort ranges and keep a single (the smallest) range per header line
+pub fn normalize_fold_ranges(ranges: impl IntoIterator<Item = FoldRange>) -> Vec<FoldRange> {
+    let mut by_start: BTreeMap<usize, usize> = BTreeMap::new();
+    for range in ranges {
+        if range.end_line <= range.start_line {
+            continue;
+        }
+        by_start
+            .entry(range.start_line)
+            .and_modify(|end| *end = (*end).min(range.end_line))
+            .or_insert(range.end_line);
+    }
+    by_start
+        .into_iter()
+        .map(|(start, end)| FoldRange::new(start, end))
+        .collect()
+}
+
+/// Compute fold ranges from a tree-sitter parse of `source`
+///
+/// Named nodes spanning several lines are foldable. Returns `None` if the
+/// source is too large or could not be parsed.
+pub fn tree_sitter_fold_ranges(language: &Language, source: &str) -> Option<Vec<FoldRange>> {
+    if source.len() > MAX_PARSE_BYTES {
+        return None;
+    }
+
+    let mut parser = Parser::new();
+    parser.set_language(&language.tree_sitter_language()).ok()?;
+    let tree = parser.parse(source, None)?;
+    let lines: Vec<&str> = source.split('\n').collect();
+
+    let root = tree.root_node();
+    let mut ranges = Vec::new();
+    let mut stack = vec![root];
+    let mut cursor = tree.walk();
+
+    while let Some(node) = stack.pop() {
+        stack.extend(node.named_children(&mut cursor));
+        // Wrapper nodes (and Python-style blocks starting with their first
+        // statement) are represented by their first child instead
+        if node.id() == root.id()
+            || node
+                .named_child(0)
+                .is_some_and(|child| child.start_byte() == node.start_byte())
+        {
+            continue;
+        }
+
+        let start_line = node.start_position().row;
+        let mut end_line = node.end_position().row;
+        // A node ending at column 0 only includes the previous line's newline
+        if node.end_position().column == 0 && end_line > start_line {
+            end_line -= 1;
+        }
+        // Keep the closing delimiter visible below the fold
+        if end_line > start_line && lines.get(end_line).is_some_and(|l| closes_block(l)) {
+            end_line -= 1;
+        }
+        if end_line > start_line {
+            ranges.push(FoldRange::new(start_line, end_line));
+        }
+    }
+
+    Some(normalize_fold_ranges(ranges))
+}
+
+/// Compute fold ranges from indentation
+///
+/// A non-blank line starts a region covering the following lines that are
+/// indented deeper than it (blank lines in between are included, trailing
+/// blank lines are not).
+pub fn indent_fold_ranges(source: &str, tab_size: usize) -> Vec<FoldRange> {
+    let mut ranges = Vec::new();
+    // Open regions: (header line, header indent)
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    let mut last_non_blank = 0;
+
+    for (line_idx, line) in source.split('\n').enumerate() {
+        let Some(indent) = indent_width(line, tab_size) else {
+            continue;
+        };
+        while let Some(&(header, header_indent)) = open.last() {
+            if header_indent < indent {
+                break;
+            }
+            open.pop();
+            if last_non_blank > header {
+                ranges.push(FoldRange::new(header, last_non_blank));
+            }
+        }
+        open.push((line_idx, indent));
+        last_non_blank = line_idx;
+    }
+    for (header, _) in open {
+        if last_non_blank > header {
+            ranges.push(FoldRange::new(header, last_non_blank));
+        }
+    }
+
+    normalize_fold_ranges(ranges)
+}
+
+/// Nesting depth of each range (0 for top-level regions)
+///
+/// `ranges` must be sorted by start line, as returned by the functions above.
+pub fn fold_depths(ranges: &[FoldRange]) -> Vec<usize> {
+    let mut enclosing: Vec<FoldRange> = Vec::new();
+    ranges
+        .iter()
+        .map(|range| {
+            while enclosing
+                .last()
+                .is_some_and(|outer| outer.end_line < range.end_line)
+            {
+                enclosing.pop();
+            }
+            let depth = enclosing.len();
+            enclosing.push(*range);
+            depth
+        })
+        .collect()
+}
+
+/// The innermost range whose header or hidden lines include `line`
+pub fn innermost_fold_range(
+    ranges: impl IntoIterator<Item = FoldRange>,
+    line: usize,
+) -> Option<FoldRange> {
+    ranges
+        .into_iter()
+        .filter(|range| range.contains_line(line))
+        .max_by_key(|range| (range.start_line, std::cmp::Reverse(range.end_line)))
+}
+
+/// Visual indentation of a line, or `None` if the line is blank
+fn indent_width(line: &str, tab_size: usize) -> Option<usize> {
+    let mut width = 0;
+    for ch in line.chars() {
+        match ch {
+            ' ' => width += 1,
+            '\t' => width += tab_size.max(1) - width % tab_size.max(1),
+            '\r' => {}
+            _ => return Some(width),
+        }
+    }
+    None
+}
+
+fn closes_block(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    CLOSING_TOKENS.iter().any(|token| {
+        trimmed.starts_with(token)
+            && (!token.chars().all(char::is_alphabetic)
+                || !trimmed[token.len()..].starts_with(|c: char| c.is_alphanumeric() || c == '_'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_sitter_fold_ranges_keep_closing_brace_visible() {
+        let source =
+            "fn main() {\n    if true {\n        a();\n    } else {\n        b();\n    }\n}\n";
+        let ranges = tree_sitter_fold_ranges(&Language::Rust, source).unwrap();
+
+        assert!(ranges.contains(&FoldRange::new(0, 5)));
+        // The `if` header folds only its own block, not the `else` branch
+        assert!(ranges.contains(&FoldRange::new(1, 2)));
+        assert!(ranges.contains(&FoldRange::new(3, 4)));
+    }
+
+    #[test]
+    fn test_tree_sitter_fold_ranges_without_closing_token() {
+        let source = "def f():\n    x = 1\n    return x\n\ny = 2\n";
+        let ranges = tree_sitter_fold_ranges(&Language::Python, source).unwrap();
+
+        assert_eq!(ranges, vec![FoldRange::new(0, 2)]);
+    }
+
+    #[test]
+    fn test_indent_fold_ranges() {
+        let source = "a:\n  b:\n    c\n\n  d\ne\n  f\n";
+        let ranges = indent_fold_ranges(source, 4);
+
+        assert_eq!(
+            ranges,
+            vec![
+                FoldRange::new(0, 4),
+                FoldRange::new(1, 2),
+                FoldRange::new(5, 6)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_indent_fold_ranges_with_tabs() {
+        let source = "a\n\tb\n\t\tc\n";
+        let ranges = indent_fold_ranges(source, 4);
+
+        assert_eq!(ranges, vec![FoldRange::new(0, 2), FoldRange::new(1, 2)]);
+    }
+
+    #[test]
+    fn test_fold_depths_and_innermost_range() {
+        let ranges = vec![
+            FoldRange::new(0, 10),
+            FoldRange::new(1, 4),
+            FoldRange::new(2, 3),
+            FoldRange::new(6, 9),
+        ];
+
+        assert_eq!(fold_depths(&ranges), vec![0, 1, 2, 1]);
+        assert_eq!(
+            innermost_fold_range(ranges.iter().copied(), 3),
+            Some(FoldRange::new(2, 3))
+        );
+        assert_eq!(
+            innermost_fold_range(ranges.iter().copied(), 5),
+            Some(FoldRange::new(0, 10))
+        );
+        assert_eq!(innermost_fold_range(ranges.iter().copied(), 11), None);
+    }
+
+    #[test]
+    fn test_closes_block_requires_word_boundary() {
+        assert!(closes_block("    }"));
+        assert!(closes_block("end"));
+        assert!(closes_block("  done;"));
+        assert!(!closes_block("  ending = 1"));
+        assert!(!closes_block("  let x = 1;"));
+    }
+}
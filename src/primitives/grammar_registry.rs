@@ -2,176 +2,178 @@
 //!
 //! This module handles discovery and loading of TextMate grammars from:
 //! 1. Built-in syntect grammars (100+ languages)
-//! 2. User-installed grammars in ~/.config/fresh/grammars/
+//! 2. User-installed grammars in the grammars directory (~/.config/fresh/grammars/)
+//! 3. The grammar files named by the `textmate_grammar` of languages
 //!
-//! User grammars use VSCode extension format for compatibility.
-
+//! User grammars are compiled into the syntax set after the built-in ones, so
+//! they take priority when both claim a file extension or a first line. The
+//! grammars directory holds VSCode extensions (a folder with a `package.json`
+//! contributing languages and grammars), folders of grammar files, or grammar
+//! files (see [`crate::primitives::textmate_grammar`] for the formats).
+//!
+//! The registry also holds the tree-sitter grammars loaded at runtime from the
+//! `tree-sitter` folder of the grammars directory (see
+//! [`crate::primitives::tree_sitter_grammar`]), which take priority over the
+//! built-in tree-sitter grammars for the extensions of their language.
+
+use crate::config::LanguageConfig;
+use crate::primitives::highlighter::Language;
+use crate::primitives::textmate_grammar::{is_grammar_file, load_grammar_file};
+use crate::primitives::tree_sitter_grammar::{self, TreeSitterGrammar, TREE_SITTER_DIR};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use syntect::parsing::{SyntaxReference, SyntaxSet, SyntaxSetBuilder};
+use syntect::parsing::{SyntaxDefinition, SyntaxReference, SyntaxSet};
 
 /// Registry of all available TextMate grammars
 pub struct GrammarRegistry {
     /// Combined syntax set (built-in + user grammars)
     syntax_set: Arc<SyntaxSet>,
-    /// Extension -> scope name mapping for user grammars (takes priority)
-    user_extensions: HashMap<String, String>,
+    /// Names of the user grammars in the syntax set
+    user_syntaxes: Vec<String>,
+    /// Tree-sitter grammars loaded at runtime
+    tree_sitter_grammars: Vec<&'static TreeSitterGrammar>,
 }
 
 impl GrammarRegistry {
-    /// Load grammar registry, scanning user grammars directory
-    pub fn load() -> Self {
-        let mut builder = SyntaxSetBuilder::new();
-        let mut user_extensions = HashMap::new();
-
-        // Add built-in syntect grammars
-        builder.add_plain_text_syntax();
-
-        // Load default syntect grammars
-        let defaults = SyntaxSet::load_defaults_newlines();
-        for _syntax in defaults.syntaxes() {
-            // SyntaxSetBuilder doesn't have a direct way to add from another set,
-            // so we'll just use the defaults as our base and add user grammars on top
+    /// Load the built-in grammars and the user grammars: those in
+    /// `grammars_dir`, then those of `languages`, and the tree-sitter
+    /// grammars of `languages`
+    pub fn load(grammars_dir: &Path, languages: &HashMap<String, LanguageConfig>) -> Self {
+        let mut user_syntaxes = Vec::new();
+        if grammars_dir.is_dir() {
+            Self::load_user_grammars(grammars_dir, &mut user_syntaxes);
         }
+        Self::load_language_grammars(grammars_dir, languages, &mut user_syntaxes);
+        let mut registry = Self::with_user_grammars(user_syntaxes);
+        registry.tree_sitter_grammars =
+            tree_sitter_grammar::load_grammars(&grammars_dir.join(TREE_SITTER_DIR), languages);
+        registry
+    }
 
-        // For now, use the defaults directly and add user grammars separately
-        // We'll merge them after loading user grammars
-        let mut syntax_set = SyntaxSet::load_defaults_newlines();
+    /// Registry of the built-in grammars only
+    pub fn builtin() -> Self {
+        Self::with_user_grammars(Vec::new())
+    }
 
-        // Scan user grammars directory
-        if let Some(grammars_dir) = Self::grammars_directory() {
-            if grammars_dir.exists() {
-                if let Some(user_set) =
-                    Self::load_user_grammars(&grammars_dir, &mut user_extensions)
-                {
-                    // Merge user syntaxes with defaults
-                    // Since SyntaxSet doesn't support merging, we need to rebuild
-                    let mut builder = SyntaxSetBuilder::new();
-                    builder.add_plain_text_syntax();
-
-                    // Add defaults first
-                    for _syntax in syntax_set.syntaxes() {
-                        // We can't directly add syntaxes, so we'll keep defaults as base
-                    }
-
-                    // For user grammars, we store the mappings and load them into a separate set
-                    // The find_syntax_for_file will check user_extensions first
-                    syntax_set = Self::merge_syntax_sets(syntax_set, user_set);
-                }
-            }
+    fn with_user_grammars(user: Vec<SyntaxDefinition>) -> Self {
+        let user_syntaxes: Vec<String> = user.iter().map(|syntax| syntax.name.clone()).collect();
+        let mut syntax_set = SyntaxSet::load_defaults_newlines();
+        if !user.is_empty() {
+            syntax_set = Self::merge_syntax_sets(syntax_set, user);
         }
 
         tracing::info!(
-            "Loaded {} syntaxes, {} user extension mappings",
+            "Loaded {} syntaxes, {} user grammars",
             syntax_set.syntaxes().len(),
-            user_extensions.len()
+            user_syntaxes.len()
         );
 
         Self {
             syntax_set: Arc::new(syntax_set),
-            user_extensions,
+            user_syntaxes,
+            tree_sitter_grammars: Vec::new(),
         }
     }
 
-    /// Get the grammars directory path
-    pub fn grammars_directory() -> Option<PathBuf> {
-        dirs::config_dir().map(|p| p.join("fresh/grammars"))
-    }
-
-    /// Merge two syntax sets (user grammars override defaults)
-    fn merge_syntax_sets(base: SyntaxSet, _user: SyntaxSet) -> SyntaxSet {
-        // syntect doesn't have a great API for merging, so we use a builder
-        let mut builder = SyntaxSetBuilder::new();
-        builder.add_plain_text_syntax();
-
-        // Unfortunately, SyntaxSetBuilder doesn't let us add from existing SyntaxSet
-        // directly. The best approach is to load from folders.
-        // For now, we'll just use the base set and rely on user_extensions mapping
-        // to find user grammars by scope name.
-
-        // In a more complete implementation, we'd serialize user syntaxes and reload
-        base
+    /// Add user grammars to a syntax set, after its own syntaxes (which the
+    /// lookups of syntect search last to first, so user grammars win)
+    fn merge_syntax_sets(base: SyntaxSet, user: Vec<SyntaxDefinition>) -> SyntaxSet {
+        let mut builder = base.into_builder();
+        for syntax in user {
+            builder.add(syntax);
+        }
+        builder.build()
     }
 
     /// Load user grammars from the grammars directory
-    fn load_user_grammars(
-        dir: &Path,
-        user_extensions: &mut HashMap<String, String>,
-    ) -> Option<SyntaxSet> {
-        let mut builder = SyntaxSetBuilder::new();
-        let mut found_any = false;
-
-        // Iterate through subdirectories looking for package.json
-        let entries = match std::fs::read_dir(dir) {
-            Ok(entries) => entries,
+    fn load_user_grammars(dir: &Path, syntaxes: &mut Vec<SyntaxDefinition>) {
+        let mut paths: Vec<PathBuf> = match std::fs::read_dir(dir) {
+            Ok(entries) => entries.flatten().map(|entry| entry.path()).collect(),
             Err(e) => {
                 tracing::warn!("Failed to read grammars directory {:?}: {}", dir, e);
-                return None;
+                return;
             }
         };
+        paths.sort();
 
-        for entry in entries.flatten() {
-            let path = entry.path();
+        for path in paths {
             if !path.is_dir() {
+                if is_grammar_file(&path) {
+                    Self::load_grammar(&path, syntaxes);
+                }
                 continue;
             }
 
             let package_json = path.join("package.json");
             if !package_json.exists() {
-                // Also check for direct .tmLanguage.json files
-                Self::load_direct_grammar(&path, &mut builder, user_extensions, &mut found_any);
+                // A folder of grammar files
+                Self::load_direct_grammars(&path, syntaxes);
                 continue;
             }
 
             // Parse package.json
             match Self::parse_package_json(&package_json) {
-                Ok(manifest) => {
-                    Self::process_manifest(&path, manifest, &mut builder, user_extensions);
-                    found_any = true;
-                }
+                Ok(manifest) => Self::process_manifest(&path, manifest, syntaxes),
                 Err(e) => {
                     tracing::warn!("Failed to parse {:?}: {}", package_json, e);
                 }
             }
         }
+    }
+
+    /// Load the grammar files of a folder
+    fn load_direct_grammars(dir: &Path, syntaxes: &mut Vec<SyntaxDefinition>) {
+        let mut paths: Vec<PathBuf> = match std::fs::read_dir(dir) {
+            Ok(entries) => entries.flatten().map(|entry| entry.path()).collect(),
+            Err(_) => return,
+        };
+        paths.sort();
+
+        for path in paths.iter().filter(|path| is_grammar_file(path)) {
+            Self::load_grammar(path, syntaxes);
+        }
+    }
 
-        if found_any {
-            Some(builder.build())
-        } else {
-            None
+    /// Load a grammar file, returning the index of its syntax
+    fn load_grammar(path: &Path, syntaxes: &mut Vec<SyntaxDefinition>) -> Option<usize> {
+        match load_grammar_file(path) {
+            Ok(syntax) => {
+                tracing::info!("Loaded grammar {} from {:?}", syntax.name, path);
+                syntaxes.push(syntax);
+                Some(syntaxes.len() - 1)
+            }
+            Err(e) => {
+                tracing::warn!("{}", e);
+                None
+            }
         }
     }
 
-    /// Load a grammar directly from a .tmLanguage.json file
-    fn load_direct_grammar(
-        dir: &Path,
-        builder: &mut SyntaxSetBuilder,
-        _user_extensions: &mut HashMap<String, String>,
-        found_any: &mut bool,
+    /// Load the grammar files named by the `textmate_grammar` of languages
+    /// (relative to the grammars directory), for the extensions of the
+    /// languages
+    fn load_language_grammars(
+        grammars_dir: &Path,
+        languages: &HashMap<String, LanguageConfig>,
+        syntaxes: &mut Vec<SyntaxDefinition>,
     ) {
-        // Look for .tmLanguage.json or .sublime-syntax files
-        let entries = match std::fs::read_dir(dir) {
-            Ok(e) => e,
-            Err(_) => return,
-        };
+        let mut languages: Vec<(&String, &LanguageConfig)> = languages.iter().collect();
+        languages.sort_by_key(|(name, _)| *name);
 
-        for entry in entries.flatten() {
-            let path = entry.path();
-            let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-
-            if file_name.ends_with(".tmLanguage.json")
-                || file_name.ends_with(".tmLanguage")
-                || file_name.ends_with(".sublime-syntax")
-            {
-                if let Err(e) = builder.add_from_folder(&dir, false) {
-                    tracing::warn!("Failed to load grammar from {:?}: {}", dir, e);
-                } else {
-                    tracing::info!("Loaded grammar from {:?}", dir);
-                    *found_any = true;
-                }
-                break;
+        for (name, language) in languages {
+            let Some(grammar) = &language.textmate_grammar else {
+                continue;
+            };
+            let path = grammars_dir.join(grammar);
+            if let Some(index) = Self::load_grammar(&path, syntaxes) {
+                let extensions = language
+                    .extensions
+                    .iter()
+                    .map(|ext| ext.trim_start_matches('.').to_string());
+                syntaxes[index].file_extensions.extend(extensions);
+                tracing::debug!("Mapped language {} to grammar {:?}", name, path);
             }
         }
     }
@@ -188,19 +190,19 @@ impl GrammarRegistry {
     fn process_manifest(
         package_dir: &Path,
         manifest: PackageManifest,
-        builder: &mut SyntaxSetBuilder,
-        user_extensions: &mut HashMap<String, String>,
+        syntaxes: &mut Vec<SyntaxDefinition>,
     ) {
         let contributes = match manifest.contributes {
             Some(c) => c,
             None => return,
         };
 
-        // Build language ID -> extensions mapping
-        let mut lang_extensions: HashMap<String, Vec<String>> = HashMap::new();
-        for lang in &contributes.languages {
-            lang_extensions.insert(lang.id.clone(), lang.extensions.clone());
-        }
+        // Build language ID -> contribution mapping
+        let languages: HashMap<&str, &LanguageContribution> = contributes
+            .languages
+            .iter()
+            .map(|lang| (lang.id.as_str(), lang))
+            .collect();
 
         // Process each grammar
         for grammar in &contributes.grammars {
@@ -211,45 +213,32 @@ impl GrammarRegistry {
                 continue;
             }
 
-            // Try to load the grammar
-            let grammar_dir = grammar_path.parent().unwrap_or(package_dir);
-            if let Err(e) = builder.add_from_folder(grammar_dir, false) {
-                tracing::warn!("Failed to load grammar {:?}: {}", grammar_path, e);
+            let Some(index) = Self::load_grammar(&grammar_path, syntaxes) else {
                 continue;
-            }
-
-            tracing::info!(
-                "Loaded grammar {} from {:?}",
-                grammar.scope_name,
-                grammar_path
-            );
+            };
+            let syntax = &mut syntaxes[index];
 
-            // Map extensions to scope name
-            if let Some(extensions) = lang_extensions.get(&grammar.language) {
-                for ext in extensions {
-                    let ext_clean = ext.trim_start_matches('.');
-                    user_extensions.insert(ext_clean.to_string(), grammar.scope_name.clone());
-                    tracing::debug!("Mapped extension .{} to {}", ext_clean, grammar.scope_name);
-                }
+            // Map the extensions, file names and first line of the language
+            // to the grammar
+            let Some(lang) = grammar.language.as_deref().and_then(|id| languages.get(id)) else {
+                continue;
+            };
+            for ext in &lang.extensions {
+                let ext_clean = ext.trim_start_matches('.');
+                syntax.file_extensions.push(ext_clean.to_string());
+                tracing::debug!("Mapped extension .{} to {}", ext_clean, grammar.scope_name);
+            }
+            syntax
+                .file_extensions
+                .extend(lang.filenames.iter().cloned());
+            if syntax.first_line_match.is_none() {
+                syntax.first_line_match = lang.first_line.clone();
             }
         }
     }
 
-    /// Find syntax for a file by path/extension
+    /// Find syntax for a file by path/extension, or by its first line
     pub fn find_syntax_for_file(&self, path: &Path) -> Option<&SyntaxReference> {
-        let ext = path.extension()?.to_str()?;
-
-        // Check user grammars first (higher priority)
-        if let Some(scope) = self.user_extensions.get(ext) {
-            if let Some(syntax) = self
-                .syntax_set
-                .find_syntax_by_scope(syntect::parsing::Scope::new(scope).ok()?)
-            {
-                return Some(syntax);
-            }
-        }
-
-        // Fall back to built-in syntect detection
         self.syntax_set.find_syntax_for_file(path).ok().flatten()
     }
 
@@ -283,25 +272,40 @@ impl GrammarRegistry {
             .collect()
     }
 
+    /// Names of the user grammars loaded
+    pub fn user_syntaxes(&self) -> &[String] {
+        &self.user_syntaxes
+    }
+
+    /// Tree-sitter grammars loaded at runtime
+    pub fn tree_sitter_grammars(&self) -> &[&'static TreeSitterGrammar] {
+        &self.tree_sitter_grammars
+    }
+
+    /// Tree-sitter language of a file: a grammar loaded at runtime for its
+    /// extension, or else a built-in grammar
+    pub fn language_for_file(&self, path: &Path) -> Option<Language> {
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        extension
+            .and_then(|ext| {
+                self.tree_sitter_grammars
+                    .iter()
+                    .find(|grammar| grammar.handles_extension(ext))
+                    .copied()
+            })
+            .map(Language::Dynamic)
+            .or_else(|| Language::from_path(path))
+    }
+
     /// Check if a syntax is available for an extension
     pub fn has_syntax_for_extension(&self, ext: &str) -> bool {
-        if self.user_extensions.contains_key(ext) {
-            return true;
-        }
-
-        // Check built-in syntaxes
-        let dummy_path = PathBuf::from(format!("file.{}", ext));
-        self.syntax_set
-            .find_syntax_for_file(&dummy_path)
-            .ok()
-            .flatten()
-            .is_some()
+        self.syntax_set.find_syntax_by_extension(ext).is_some()
     }
 }
 
 impl Default for GrammarRegistry {
     fn default() -> Self {
-        Self::load()
+        Self::builtin()
     }
 }
 
@@ -326,11 +330,16 @@ struct LanguageContribution {
     id: String,
     #[serde(default)]
     extensions: Vec<String>,
+    #[serde(default)]
+    filenames: Vec<String>,
+    #[serde(rename = "firstLine")]
+    first_line: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct GrammarContribution {
-    language: String,
+    /// None for injection grammars
+    language: Option<String>,
     #[serde(rename = "scopeName")]
     scope_name: String,
     path: String,
@@ -342,14 +351,14 @@ mod tests {
 
     #[test]
     fn test_registry_creation() {
-        let registry = GrammarRegistry::load();
+        let registry = GrammarRegistry::builtin();
         // Should have built-in syntaxes
         assert!(!registry.available_syntaxes().is_empty());
     }
 
     #[test]
     fn test_find_syntax_for_common_extensions() {
-        let registry = GrammarRegistry::load();
+        let registry = GrammarRegistry::builtin();
 
         // Test common extensions that syntect should support
         let test_cases = [
@@ -376,9 +385,133 @@ mod tests {
         }
     }
 
+    /// A TextMate grammar in JSON
+    fn widget_grammar(name: &str, scope: &str) -> String {
+        serde_json::json!({
+            "name": name,
+            "scopeName": scope,
+            "patterns": [{ "match": "\\bwidget\\b", "name": "keyword.other" }]
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_load_user_grammars() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let grammars_dir = temp.path().join("grammars");
+
+        // A VSCode extension
+        let extension = grammars_dir.join("widget-lang");
+        std::fs::create_dir_all(extension.join("syntaxes")).unwrap();
+        std::fs::write(
+            extension.join("package.json"),
+            serde_json::json!({
+                "contributes": {
+                    "languages": [{
+                        "id": "widget",
+                        "extensions": [".wdg", ".rs"],
+                        "filenames": ["Widgetfile"],
+                        "firstLine": "^#!.*\\bwidget\\b"
+                    }],
+                    "grammars": [{
+                        "language": "widget",
+                        "scopeName": "source.widget",
+                        "path": "./syntaxes/widget.tmLanguage.json"
+                    }]
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+        std::fs::write(
+            extension.join("syntaxes/widget.tmLanguage.json"),
+            widget_grammar("Widget", "source.widget"),
+        )
+        .unwrap();
+
+        // A grammar file in the grammars directory, and one named by a
+        // language
+        std::fs::write(
+            grammars_dir.join("gizmo.tmLanguage.json"),
+            widget_grammar("Gizmo", "source.gizmo"),
+        )
+        .unwrap();
+        std::fs::write(
+            temp.path().join("gadget.tmLanguage.json"),
+            widget_grammar("Gadget", "source.gadget"),
+        )
+        .unwrap();
+        let mut languages = HashMap::new();
+        languages.insert(
+            "gadget".to_string(),
+            serde_json::from_value::<LanguageConfig>(serde_json::json!({
+                "extensions": ["gdg"],
+                "textmate_grammar": temp.path().join("gadget.tmLanguage.json"),
+            }))
+            .unwrap(),
+        );
+
+        let registry = GrammarRegistry::load(&grammars_dir, &languages);
+        assert_eq!(registry.user_syntaxes(), ["Gizmo", "Widget", "Gadget"]);
+        let syntax_name = |path: &Path| registry.find_syntax_for_file(path).map(|s| s.name.clone());
+
+        assert_eq!(
+            syntax_name(Path::new("main.wdg")),
+            Some("Widget".to_string())
+        );
+        assert_eq!(
+            syntax_name(Path::new("Widgetfile")),
+            Some("Widget".to_string())
+        );
+        assert_eq!(
+            syntax_name(Path::new("part.gdg")),
+            Some("Gadget".to_string())
+        );
+        // User grammars take priority over the built-in ones
+        assert_eq!(syntax_name(Path::new("lib.rs")), Some("Widget".to_string()));
+        assert_eq!(
+            syntax_name(Path::new("main.py")),
+            Some("Python".to_string())
+        );
+        assert!(registry.find_syntax_by_scope("source.gizmo").is_some());
+
+        let script = temp.path().join("run-widget");
+        std::fs::write(&script, "#!/usr/bin/env widget\nwidget\n").unwrap();
+        assert_eq!(syntax_name(&script), Some("Widget".to_string()));
+    }
+
+    #[test]
+    fn test_language_for_file_falls_back_to_builtin_grammars() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let tree_sitter_dir = temp.path().join(TREE_SITTER_DIR);
+        std::fs::create_dir(&tree_sitter_dir).unwrap();
+        // Not a loadable library: the grammar is skipped
+        std::fs::write(
+            tree_sitter_grammar::library_path(&tree_sitter_dir, "zig"),
+            "not a library",
+        )
+        .unwrap();
+        let languages = HashMap::from([(
+            "zig".to_string(),
+            serde_json::from_value::<LanguageConfig>(serde_json::json!({
+                "extensions": ["zig"],
+                "grammar": "zig",
+            }))
+            .unwrap(),
+        )]);
+
+        let registry = GrammarRegistry::load(temp.path(), &languages);
+        assert!(registry.tree_sitter_grammars().is_empty());
+        assert!(registry.language_for_file(Path::new("main.zig")).is_none());
+        assert!(matches!(
+            registry.language_for_file(Path::new("main.rs")),
+            Some(Language::Rust)
+        ));
+    }
+
     #[test]
     fn test_syntax_set_arc() {
-        let registry = GrammarRegistry::load();
+        let registry = GrammarRegistry::builtin();
         let arc1 = registry.syntax_set_arc();
         let arc2 = registry.syntax_set_arc();
         // Both should point to the same data
@@ -387,7 +520,7 @@ mod tests {
 
     #[test]
     fn test_list_all_syntaxes() {
-        let registry = GrammarRegistry::load();
+        let registry = GrammarRegistry::builtin();
         let syntax_set = registry.syntax_set();
 
         let mut syntaxes: Vec<_> = syntax_set
@@ -314,7 +314,7 @@ impl HighlightEngine {
                 Self::textmate_for_file(path, registry)
             }
             HighlighterPreference::TreeSitter => {
-                if let Some(lang) = Language::from_path(path) {
+                if let Some(lang) = registry.language_for_file(path) {
                     if let Ok(highlighter) = Highlighter::new(lang) {
                         return Self::TreeSitter(highlighter);
                     }
@@ -329,7 +329,19 @@ impl HighlightEngine {
         let syntax_set = registry.syntax_set_arc();
 
         // Detect tree-sitter language for non-highlighting features
-        let ts_language = Language::from_path(path);
+        let ts_language = registry.language_for_file(path);
+
+        // A tree-sitter grammar installed by the user with a highlights query
+        // highlights its files, like user TextMate grammars take priority
+        // over the built-in ones
+        if let Some(lang @ Language::Dynamic(grammar)) = ts_language {
+            if !grammar.highlights_query.is_empty() {
+                match Highlighter::new(lang) {
+                    Ok(highlighter) => return Self::TreeSitter(highlighter),
+                    Err(e) => tracing::warn!("{}", e),
+                }
+            }
+        }
 
         // Find syntax by file extension
         if let Some(syntax) = registry.find_syntax_for_file(path) {
@@ -462,7 +474,7 @@ mod tests {
 
     #[test]
     fn test_textmate_backend_selection() {
-        let registry = GrammarRegistry::load();
+        let registry = GrammarRegistry::builtin();
 
         // Languages with TextMate grammars use TextMate for highlighting
         let engine = HighlightEngine::for_file(Path::new("test.rs"), &registry);
@@ -490,7 +502,7 @@ mod tests {
 
     #[test]
     fn test_tree_sitter_explicit_preference() {
-        let registry = GrammarRegistry::load();
+        let registry = GrammarRegistry::builtin();
 
         // Force tree-sitter for highlighting
         let engine = HighlightEngine::for_file_with_preference(
@@ -503,7 +515,7 @@ mod tests {
 
     #[test]
     fn test_unknown_extension() {
-        let registry = GrammarRegistry::load();
+        let registry = GrammarRegistry::builtin();
 
         // Unknown extension
         let engine = HighlightEngine::for_file(Path::new("test.unknown_xyz_123"), &registry);
@@ -522,7 +534,7 @@ mod tests {
         // - viewport_start > context_bytes (so parse_start > 0 after saturating_sub)
         // - parse_end = min(viewport_end + context_bytes, buffer.len()) = 0
         // - parse_end - parse_start would underflow (0 - positive = overflow)
-        let registry = GrammarRegistry::load();
+        let registry = GrammarRegistry::builtin();
 
         let mut engine = HighlightEngine::for_file(Path::new("test.rs"), &registry);
 
@@ -11,6 +11,7 @@
 
 use crate::config::LARGE_FILE_THRESHOLD_BYTES;
 use crate::model::buffer::Buffer;
+use crate::primitives::tree_sitter_grammar::TreeSitterGrammar;
 use crate::view::theme::Theme;
 use ratatui::style::Color;
 use std::ops::Range;
@@ -88,6 +89,27 @@ impl HighlightCategory {
         }
     }
 
+    /// Map a capture name of a highlights query (e.g. `function.method`) to
+    /// the closest category, by its first component
+    pub(crate) fn from_capture_name(name: &str) -> Option<Self> {
+        let mut parts = name.split('.');
+        match parts.next()? {
+            "attribute" | "tag" => Some(Self::Attribute),
+            "comment" => Some(Self::Comment),
+            "constant" | "boolean" | "character" | "label" => Some(Self::Constant),
+            "function" | "method" => Some(Self::Function),
+            "keyword" | "conditional" | "repeat" | "include" | "exception" => Some(Self::Keyword),
+            "number" | "float" => Some(Self::Number),
+            "operator" | "punctuation" => Some(Self::Operator),
+            "property" | "field" => Some(Self::Property),
+            "string" | "escape" | "embedded" => Some(Self::String),
+            "type" | "constructor" | "module" | "namespace" => Some(Self::Type),
+            "variable" if parts.next() == Some("builtin") => Some(Self::Constant),
+            "variable" | "parameter" => Some(Self::Variable),
+            _ => None,
+        }
+    }
+
     /// Get the color for this category from the theme
     pub fn color(&self, theme: &Theme) -> Color {
         match self {
@@ -144,10 +166,15 @@ pub enum Language {
     Bash,
     Lua,
     // Markdown,  // Disabled due to tree-sitter version conflict
+    /// A grammar loaded at runtime from a shared library
+    Dynamic(&'static TreeSitterGrammar),
 }
 
 impl Language {
-    /// Detect language from file extension
+    /// Detect language from file extension, among the built-in grammars
+    ///
+    /// Use [`GrammarRegistry::language_for_file`](crate::primitives::grammar_registry::GrammarRegistry::language_for_file)
+    /// to include the grammars loaded at runtime.
     pub fn from_path(path: &std::path::Path) -> Option<Self> {
         match path.extension()?.to_str()? {
             "rs" => Some(Language::Rust),
@@ -171,6 +198,29 @@ impl Language {
         }
     }
 
+    /// Get the tree-sitter grammar for this language
+    pub fn tree_sitter_language(&self) -> tree_sitter::Language {
+        match self {
+            Language::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Language::Python => tree_sitter_python::LANGUAGE.into(),
+            Language::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Language::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Language::HTML => tree_sitter_html::LANGUAGE.into(),
+            Language::CSS => tree_sitter_css::LANGUAGE.into(),
+            Language::C => tree_sitter_c::LANGUAGE.into(),
+            Language::Cpp => tree_sitter_cpp::LANGUAGE.into(),
+            Language::Go => tree_sitter_go::LANGUAGE.into(),
+            Language::Json => tree_sitter_json::LANGUAGE.into(),
+            Language::Java => tree_sitter_java::LANGUAGE.into(),
+            Language::CSharp => tree_sitter_c_sharp::LANGUAGE.into(),
+            Language::Php => tree_sitter_php::LANGUAGE_PHP.into(),
+            Language::Ruby => tree_sitter_ruby::LANGUAGE.into(),
+            Language::Bash => tree_sitter_bash::LANGUAGE.into(),
+            Language::Lua => tree_sitter_lua::LANGUAGE.into(),
+            Language::Dynamic(grammar) => grammar.language.clone(),
+        }
+    }
+
     /// Get tree-sitter highlight configuration for this language
     fn highlight_config(&self) -> Result<HighlightConfiguration, String> {
         match self {
@@ -615,6 +665,27 @@ impl Language {
                     "variable",
                 ]);
 
+                Ok(config)
+            }
+            Language::Dynamic(grammar) => {
+                let mut config = HighlightConfiguration::new(
+                    grammar.language.clone(),
+                    &grammar.name,
+                    &grammar.highlights_query,
+                    "", // injections query
+                    "", // locals query
+                )
+                .map_err(|e| {
+                    format!(
+                        "Failed to create {} highlight config: {e}",
+                        grammar.language_id
+                    )
+                })?;
+
+                // Recognize every capture of the query, so that an index maps
+                // back to its capture name in `highlight_category`
+                config.configure(&grammar.highlight_names);
+
                 Ok(config)
             } // Language::Markdown => {
               //     // Disabled due to tree-sitter version conflict
@@ -627,6 +698,10 @@ impl Language {
     fn highlight_category(&self, index: usize) -> Option<HighlightCategory> {
         match self {
             Language::TypeScript => HighlightCategory::from_typescript_index(index),
+            Language::Dynamic(grammar) => grammar
+                .highlight_names
+                .get(index)
+                .and_then(|name| HighlightCategory::from_capture_name(name)),
             _ => HighlightCategory::from_default_index(index),
         }
     }
@@ -913,6 +988,33 @@ mod tests {
         assert!(Language::from_path(path).is_none());
     }
 
+    #[test]
+    fn test_category_from_capture_name() {
+        let category = HighlightCategory::from_capture_name;
+        assert_eq!(
+            category("string.special.key"),
+            Some(HighlightCategory::String)
+        );
+        assert_eq!(category("escape"), Some(HighlightCategory::String));
+        assert_eq!(
+            category("function.method"),
+            Some(HighlightCategory::Function)
+        );
+        assert_eq!(
+            category("punctuation.bracket"),
+            Some(HighlightCategory::Operator)
+        );
+        assert_eq!(
+            category("variable.builtin"),
+            Some(HighlightCategory::Constant)
+        );
+        assert_eq!(
+            category("variable.parameter"),
+            Some(HighlightCategory::Variable)
+        );
+        assert_eq!(category("spell"), None);
+    }
+
     #[test]
     fn test_highlighter_basic() {
         let buffer = Buffer::from_str_test("fn main() {\n    println!(\"Hello\");\n}");
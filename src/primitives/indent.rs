@@ -145,6 +145,11 @@ impl IndentCalculator {
                 tree_sitter_c_sharp::LANGUAGE.into(),
                 include_str!("../../queries/csharp/indents.scm"),
             ),
+            Language::Dynamic(grammar) => (
+                grammar.name.as_str(),
+                grammar.language.clone(),
+                grammar.indents_query.as_deref()?,
+            ),
         };
 
         // Check if we already have this config
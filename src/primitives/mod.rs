@@ -5,15 +5,21 @@
 
 pub mod ansi;
 pub mod ansi_background;
+pub mod comment;
 pub mod display_width;
+pub mod folding;
 pub mod grammar_registry;
 pub mod highlight_engine;
 pub mod highlighter;
 pub mod indent;
 pub mod line_iterator;
 pub mod line_wrapping;
+pub mod selection_range;
 pub mod semantic_highlight;
+pub mod snippet;
 pub mod text_property;
+pub mod textmate_grammar;
 pub mod textmate_highlighter;
+pub mod tree_sitter_grammar;
 pub mod visual_layout;
 pub mod word_navigation;
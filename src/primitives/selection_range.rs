@@ -0,0 +1,129 @@
+//! Structural selection ranges
+//!
+//! Expanding the selection grows it to the smallest enclosing range from a
+//! chain of nested ranges around it: the ancestors of the syntax node at the
+//! selection in the tree-sitter parse, or the `textDocument/selectionRange`
+//! chain from the language server. Each expansion is remembered as a
+//! [`SelectionStep`] so that shrinking can restore the previous selection.
+
+use crate::primitives::highlighter::Language;
+use std::ops::Range;
+use tree_sitter::Parser;
+
+/// Buffers larger than this are not parsed for structural selection
+const MAX_PARSE_BYTES: usize = 1024 * 1024;
+
+/// One expansion of a cursor's selection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectionStep {
+    /// Cursor position before expanding
+    pub position: usize,
+    /// Cursor anchor before expanding
+    pub anchor: Option<usize>,
+    /// Selection the expansion produced
+    pub expanded: Range<usize>,
+}
+
+/// Byte ranges of the syntax nodes enclosing `selection`, innermost first
+///
+/// An empty selection at the end of a word starts from that word rather than
+/// from the token after it. Returns `None` if the source is too large or could
+/// not be parsed.
+pub fn syntax_selection_ranges(
+    language: &Language,
+    source: &str,
+    selection: Range<usize>,
+) -> Option<Vec<Range<usize>>> {
+    if source.len() > MAX_PARSE_BYTES || selection.end > source.len() {
+        return None;
+    }
+
+    let mut parser = Parser::new();
+    parser.set_language(&language.tree_sitter_language()).ok()?;
+    let tree = parser.parse(source, None)?;
+    let root = tree.root_node();
+
+    let mut node = root.descendant_for_byte_range(selection.start, selection.end)?;
+    let is_word = |node: &tree_sitter::Node| node.is_named() && node.child_count() == 0;
+    if selection.is_empty()
+        && selection.start > 0
+        && !(is_word(&node) && node.start_byte() < selection.start)
+    {
+        let before = root.descendant_for_byte_range(selection.start - 1, selection.start)?;
+        if is_word(&before) && before.end_byte() == selection.start {
+            node = before;
+        }
+    }
+
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut current = Some(node);
+    while let Some(node) = current {
+        let range = node.start_byte()..node.end_byte();
+        if ranges.last() != Some(&range) {
+            ranges.push(range);
+        }
+        current = node.parent();
+    }
+    Some(ranges)
+}
+
+/// The smallest of `ranges` that contains `selection` and is larger than it
+pub fn next_enclosing_range(
+    ranges: &[Range<usize>],
+    selection: &Range<usize>,
+) -> Option<Range<usize>> {
+    ranges
+        .iter()
+        .filter(|range| {
+            range.start <= selection.start && range.end >= selection.end && *range != selection
+        })
+        .min_by_key(|range| range.len())
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "fn main() {\n    let total = add(1, 2);\n}\n";
+
+    fn expand_all(selection: Range<usize>) -> Vec<&'static str> {
+        let mut selection = selection;
+        let mut texts = Vec::new();
+        while let Some(next) = syntax_selection_ranges(&Language::Rust, SOURCE, selection.clone())
+            .and_then(|ranges| next_enclosing_range(&ranges, &selection))
+        {
+            texts.push(&SOURCE[next.clone()]);
+            selection = next;
+        }
+        texts
+    }
+
+    #[test]
+    fn test_expand_from_identifier() {
+        let pos = SOURCE.find("add").unwrap() + 1;
+        let texts = expand_all(pos..pos);
+
+        assert_eq!(texts[0], "add");
+        assert_eq!(texts[1], "add(1, 2)");
+        assert_eq!(texts[2], "let total = add(1, 2);");
+        assert_eq!(texts[3], "{\n    let total = add(1, 2);\n}");
+        assert_eq!(texts[4], "fn main() {\n    let total = add(1, 2);\n}");
+        assert_eq!(*texts.last().unwrap(), SOURCE);
+    }
+
+    #[test]
+    fn test_end_of_word_selects_the_word() {
+        let pos = SOURCE.find("total").unwrap() + "total".len();
+        assert_eq!(expand_all(pos..pos)[0], "total");
+    }
+
+    #[test]
+    fn test_next_enclosing_range() {
+        let ranges = vec![3..7, 0..10, 3..7, 0..20];
+        assert_eq!(next_enclosing_range(&ranges, &(4..4)), Some(3..7));
+        assert_eq!(next_enclosing_range(&ranges, &(3..7)), Some(0..10));
+        assert_eq!(next_enclosing_range(&ranges, &(0..20)), None);
+        assert_eq!(next_enclosing_range(&ranges, &(8..12)), Some(0..20));
+    }
+}
@@ -74,6 +74,7 @@ fn get_locals_query(language: &Language) -> Option<&'static str> {
         Language::JavaScript | Language::TypeScript => Some(JS_LOCALS_QUERY),
         Language::Go => Some(GO_LOCALS_QUERY),
         Language::C | Language::Cpp => Some(C_LOCALS_QUERY),
+        Language::Dynamic(grammar) => grammar.locals_query.as_deref(),
         _ => None, // Other languages fall back to identifier matching
     }
 }
@@ -220,6 +221,7 @@ impl SemanticHighlighter {
             Language::HTML => tree_sitter_html::LANGUAGE.into(),
             Language::CSS => tree_sitter_css::LANGUAGE.into(),
             Language::CSharp => tree_sitter_c_sharp::LANGUAGE.into(),
+            Language::Dynamic(grammar) => grammar.language.clone(),
         };
 
         // Create parser
@@ -0,0 +1,468 @@
+//! Snippet parsing
+//!
+//! Implements the snippet syntax of the LSP specification, which is also the
+//! body syntax of VS Code snippet files: tabstops (`$1`, `${1}`), placeholders
+//! with default text (`${1:name}`, which may nest), choices (`${1|one,two|}`)
+//! and variables (`$TM_FILENAME`, `${TM_SELECTED_TEXT:default}`). Transforms
+//! (`${1/regex/format/}`) are accepted but not applied. Anything that does not
+//! parse is inserted literally.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+
+/// A parsed snippet, ready to insert
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    /// The text to insert
+    pub text: String,
+    /// Tabstops in navigation order (`$1`, `$2`, ..., then the final `$0`)
+    pub tabstops: Vec<Tabstop>,
+}
+
+/// A tabstop of a snippet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tabstop {
+    pub index: usize,
+    /// Byte ranges in the snippet text; mirrored tabstops have several
+    pub ranges: Vec<Range<usize>>,
+    /// Options of a choice tabstop (the first one is inserted)
+    pub choices: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    Tabstop { index: usize, children: Vec<Node> },
+    Choice { index: usize, options: Vec<String> },
+    Variable { name: String, default: Vec<Node> },
+}
+
+impl Snippet {
+    /// Parse a snippet, resolving variables with `resolve_variable`
+    ///
+    /// Unknown variables are replaced by their default (or nothing). A final
+    /// tabstop is added at the end if the snippet has no `$0`.
+    pub fn parse(source: &str, resolve_variable: impl Fn(&str) -> Option<String>) -> Self {
+        let nodes = Parser { source, pos: 0 }.parse_nodes(false);
+
+        // Mirrors (tabstops without text of their own) copy the text of the
+        // first occurrence of their tabstop that has some
+        let mut first_pass = Renderer::new(&resolve_variable, HashMap::new());
+        first_pass.render(&nodes);
+        let defaults = first_pass.placeholder_text();
+
+        let mut renderer = Renderer::new(&resolve_variable, defaults);
+        renderer.render(&nodes);
+
+        let end = renderer.text.len();
+        let mut final_stop = renderer.stops.remove(&0).unwrap_or_else(|| Tabstop {
+            index: 0,
+            ranges: std::iter::once(end..end).collect(),
+            choices: Vec::new(),
+        });
+        final_stop.ranges.truncate(1);
+
+        let mut tabstops: Vec<Tabstop> = renderer.stops.into_values().collect();
+        tabstops.push(final_stop);
+        Self {
+            text: renderer.text,
+            tabstops,
+        }
+    }
+
+    /// Whether the snippet has tabstops to visit besides the final one
+    pub fn has_placeholders(&self) -> bool {
+        self.tabstops.len() > 1
+    }
+
+    /// Indent every line after the first with `indent`
+    pub fn with_indent(self, indent: &str) -> Self {
+        if indent.is_empty() {
+            return self;
+        }
+        self.replace_char('\n', &format!("\n{}", indent))
+    }
+
+    /// Replace tab characters with `tab` (the buffer's indent unit)
+    pub fn expand_tabs(self, tab: &str) -> Self {
+        self.replace_char('\t', tab)
+    }
+
+    /// Replace every `from` character, keeping tabstop ranges in place
+    fn replace_char(mut self, from: char, to: &str) -> Self {
+        let hits: Vec<usize> = self.text.match_indices(from).map(|(i, _)| i).collect();
+        if hits.is_empty() {
+            return self;
+        }
+        let extra = to.len() - from.len_utf8();
+        let shift = |pos: usize| pos + extra * hits.partition_point(|&hit| hit < pos);
+        for tabstop in &mut self.tabstops {
+            for range in &mut tabstop.ranges {
+                *range = shift(range.start)..shift(range.end);
+            }
+        }
+        self.text = self.text.replace(from, to);
+        self
+    }
+}
+
+struct Renderer<'a> {
+    resolve_variable: &'a dyn Fn(&str) -> Option<String>,
+    defaults: HashMap<usize, String>,
+    text: String,
+    stops: BTreeMap<usize, Tabstop>,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(
+        resolve_variable: &'a dyn Fn(&str) -> Option<String>,
+        defaults: HashMap<usize, String>,
+    ) -> Self {
+        Self {
+            resolve_variable,
+            defaults,
+            text: String::new(),
+            stops: BTreeMap::new(),
+        }
+    }
+
+    fn render(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            match node {
+                Node::Text(text) => self.text.push_str(text),
+                Node::Tabstop { index, children } => {
+                    let start = self.text.len();
+                    if children.is_empty() {
+                        if let Some(default) = self.defaults.get(index) {
+                            self.text.push_str(default);
+                        }
+                    } else {
+                        self.render(children);
+                    }
+                    self.add_range(*index, start..self.text.len(), &[]);
+                }
+                Node::Choice { index, options } => {
+                    let start = self.text.len();
+                    if let Some(first) = options.first() {
+                        self.text.push_str(first);
+                    }
+                    self.add_range(*index, start..self.text.len(), options);
+                }
+                Node::Variable { name, default } => match (self.resolve_variable)(name) {
+                    Some(value) => self.text.push_str(&value),
+                    None => self.render(default),
+                },
+            }
+        }
+    }
+
+    fn add_range(&mut self, index: usize, range: Range<usize>, choices: &[String]) {
+        let tabstop = self.stops.entry(index).or_insert_with(|| Tabstop {
+            index,
+            ranges: Vec::new(),
+            choices: Vec::new(),
+        });
+        tabstop.ranges.push(range);
+        if tabstop.choices.is_empty() {
+            tabstop.choices = choices.to_vec();
+        }
+    }
+
+    /// Text of the first non-empty occurrence of each tabstop
+    fn placeholder_text(&self) -> HashMap<usize, String> {
+        self.stops
+            .values()
+            .filter_map(|stop| {
+                let range = stop.ranges.iter().find(|r| !r.is_empty())?;
+                Some((stop.index, self.text[range.clone()].to_string()))
+            })
+            .collect()
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.source.as_bytes().get(self.pos).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Parse nodes up to the end of input, or up to an unescaped `}` if
+    /// `nested` (which is left for the caller)
+    fn parse_nodes(&mut self, nested: bool) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        let mut text = String::new();
+        while let Some(byte) = self.peek() {
+            match byte {
+                b'\\'
+                    if matches!(
+                        self.source.as_bytes().get(self.pos + 1),
+                        Some(b'$' | b'}' | b'\\')
+                    ) =>
+                {
+                    text.push(self.source.as_bytes()[self.pos + 1] as char);
+                    self.pos += 2;
+                }
+                b'}' if nested => break,
+                b'$' => match self.parse_dollar() {
+                    Some(node) => {
+                        if !text.is_empty() {
+                            nodes.push(Node::Text(std::mem::take(&mut text)));
+                        }
+                        nodes.push(node);
+                    }
+                    None => {
+                        text.push('$');
+                        self.pos += 1;
+                    }
+                },
+                _ => {
+                    let ch = self.source[self.pos..].chars().next().unwrap_or_default();
+                    text.push(ch);
+                    self.pos += ch.len_utf8();
+                }
+            }
+        }
+        if !text.is_empty() {
+            nodes.push(Node::Text(text));
+        }
+        nodes
+    }
+
+    /// Parse a tabstop, placeholder, choice or variable starting at `$`,
+    /// leaving the position unchanged if there is none
+    fn parse_dollar(&mut self) -> Option<Node> {
+        let start = self.pos;
+        self.pos += 1;
+        let node = if self.eat(b'{') {
+            self.parse_braced()
+        } else if let Some(index) = self.parse_int() {
+            Some(Node::Tabstop {
+                index,
+                children: Vec::new(),
+            })
+        } else {
+            self.parse_name().map(|name| Node::Variable {
+                name,
+                default: Vec::new(),
+            })
+        };
+        if node.is_none() {
+            self.pos = start;
+        }
+        node
+    }
+
+    /// Parse the contents of `${...}` after the opening brace
+    fn parse_braced(&mut self) -> Option<Node> {
+        if let Some(index) = self.parse_int() {
+            let children = if self.eat(b':') {
+                self.parse_nodes(true)
+            } else if self.eat(b'|') {
+                let options = self.parse_choices()?;
+                self.eat(b'}').then_some(())?;
+                return Some(Node::Choice { index, options });
+            } else {
+                self.skip_transform()?;
+                Vec::new()
+            };
+            self.eat(b'}').then_some(())?;
+            return Some(Node::Tabstop { index, children });
+        }
+
+        let name = self.parse_name()?;
+        let default = if self.eat(b':') {
+            self.parse_nodes(true)
+        } else {
+            self.skip_transform()?;
+            Vec::new()
+        };
+        self.eat(b'}').then_some(())?;
+        Some(Node::Variable { name, default })
+    }
+
+    /// Parse `one,two|` (after the opening `|`)
+    fn parse_choices(&mut self) -> Option<Vec<String>> {
+        let mut options = Vec::new();
+        let mut current = String::new();
+        loop {
+            let ch = self.source[self.pos..].chars().next()?;
+            self.pos += ch.len_utf8();
+            match ch {
+                '\\' => {
+                    let escaped = self.source[self.pos..].chars().next()?;
+                    if !matches!(escaped, ',' | '|' | '\\') {
+                        current.push('\\');
+                    }
+                    current.push(escaped);
+                    self.pos += escaped.len_utf8();
+                }
+                ',' => options.push(std::mem::take(&mut current)),
+                '|' => {
+                    options.push(current);
+                    return Some(options);
+                }
+                _ => current.push(ch),
+            }
+        }
+    }
+
+    /// Skip a `/regex/format/options` transform, if present
+    fn skip_transform(&mut self) -> Option<()> {
+        if !self.eat(b'/') {
+            return Some(());
+        }
+        let bytes = self.source.as_bytes();
+        // Regex, then format (which may contain `${1:/upcase}`)
+        for _ in 0..2 {
+            let mut depth = 0usize;
+            loop {
+                match *bytes.get(self.pos)? {
+                    b'\\' => self.pos += 1,
+                    b'$' if bytes.get(self.pos + 1) == Some(&b'{') => {
+                        depth += 1;
+                        self.pos += 1;
+                    }
+                    b'}' if depth > 0 => depth -= 1,
+                    b'/' if depth == 0 => break,
+                    _ => {}
+                }
+                self.pos += 1;
+            }
+            self.pos += 1;
+        }
+        // Options
+        while self.peek()?.is_ascii_alphabetic() {
+            self.pos += 1;
+        }
+        Some(())
+    }
+
+    fn parse_int(&mut self) -> Option<usize> {
+        let digits = self.source[self.pos..]
+            .bytes()
+            .take_while(u8::is_ascii_digit)
+            .count();
+        let value = self.source[self.pos..self.pos + digits].parse().ok()?;
+        self.pos += digits;
+        Some(value)
+    }
+
+    fn parse_name(&mut self) -> Option<String> {
+        let rest = &self.source[self.pos..];
+        if !rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            return None;
+        }
+        let len = rest
+            .bytes()
+            .take_while(|b| b.is_ascii_alphanumeric() || *b == b'_')
+            .count();
+        self.pos += len;
+        Some(rest[..len].to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Snippet {
+        Snippet::parse(source, |name| {
+            (name == "TM_FILENAME").then(|| "main.rs".to_string())
+        })
+    }
+
+    fn ranges(snippet: &Snippet) -> Vec<(usize, Vec<&str>)> {
+        snippet
+            .tabstops
+            .iter()
+            .map(|stop| {
+                let texts = stop.ranges.iter().map(|r| &snippet.text[r.clone()]);
+                (stop.index, texts.collect())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_tabstops_and_placeholders() {
+        let snippet = parse("fn ${1:name}(${2}) {\n\t$0\n}");
+
+        assert_eq!(snippet.text, "fn name() {\n\t\n}");
+        assert_eq!(
+            ranges(&snippet),
+            vec![(1, vec!["name"]), (2, vec![""]), (0, vec![""])]
+        );
+        assert_eq!(snippet.tabstops[2].ranges[0].start, 13);
+    }
+
+    #[test]
+    fn test_final_tabstop_is_added_at_end() {
+        let snippet = parse("println!($1);");
+
+        assert_eq!(snippet.text, "println!();");
+        assert_eq!(snippet.tabstops[1].index, 0);
+        assert_eq!(snippet.tabstops[1].ranges, vec![11..11]);
+        assert!(snippet.has_placeholders());
+        assert!(!parse("plain").has_placeholders());
+    }
+
+    #[test]
+    fn test_mirrors_copy_placeholder_text() {
+        let snippet = parse("<$1>${1:div}</$1>");
+
+        assert_eq!(snippet.text, "<div>div</div>");
+        assert_eq!(ranges(&snippet)[0], (1, vec!["div", "div", "div"]));
+    }
+
+    #[test]
+    fn test_nested_placeholders_choices_and_variables() {
+        let snippet = parse("${1:a ${2:b}} ${3|x,y\\,z|} $TM_FILENAME ${UNKNOWN:dflt}");
+
+        assert_eq!(snippet.text, "a b x main.rs dflt");
+        assert_eq!(
+            ranges(&snippet),
+            vec![
+                (1, vec!["a b"]),
+                (2, vec!["b"]),
+                (3, vec!["x"]),
+                (0, vec![""])
+            ]
+        );
+        assert_eq!(snippet.tabstops[2].choices, vec!["x", "y,z"]);
+    }
+
+    #[test]
+    fn test_escapes_and_invalid_syntax_are_literal() {
+        let snippet = parse("\\$1 costs $ and ${oops \\} ${1/(.*)/${1:/upcase}/g}");
+
+        assert_eq!(snippet.text, "$1 costs $ and ${oops } ");
+        assert_eq!(ranges(&snippet)[0], (1, vec![""]));
+    }
+
+    #[test]
+    fn test_with_indent() {
+        let snippet = parse("if $1 {\n\t$0\n}").with_indent("    ");
+
+        assert_eq!(snippet.text, "if  {\n    \t\n    }");
+        assert_eq!(ranges(&snippet), vec![(1, vec![""]), (0, vec![""])]);
+        assert_eq!(snippet.tabstops[1].ranges[0].start, 11);
+
+        let snippet = parse("if $1 {\n\t$0\n}")
+            .expand_tabs("  ")
+            .with_indent("    ");
+        assert_eq!(snippet.text, "if  {\n      \n    }");
+        assert_eq!(snippet.tabstops[1].ranges[0].start, 12);
+    }
+}
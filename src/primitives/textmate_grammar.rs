@@ -0,0 +1,402 @@
+//! Loading of TextMate grammar files into syntect syntax definitions
+//!
+//! syntect only reads the `.sublime-syntax` format, so TextMate grammars
+//! (`.tmLanguage` property lists and the `.tmLanguage.json` files of VSCode
+//! extensions) are converted to it:
+//! - the top-level `patterns` become the `main` context, and each entry of the
+//!   `repository` a context of its own;
+//! - a `begin`/`end` rule matches its `begin` and pushes a context scoped with
+//!   its `name` and `contentName`, which its `end` match pops;
+//! - a `begin`/`while` rule is approximated as ending on the first line that
+//!   does not start with its `while`;
+//! - `#name`, `$self` and `source.other#name` includes become includes of the
+//!   matching contexts.
+//!
+//! Injection grammars and the `patterns` of captures are not supported.
+
+use serde_json::{Map, Value};
+use std::path::Path;
+use syntect::parsing::SyntaxDefinition;
+
+/// Prefix of the contexts made from repository entries, which keeps them apart
+/// from the contexts special to syntect (`main`, `prototype`)
+const REPOSITORY_PREFIX: &str = "repository.";
+
+/// Whether a file is a grammar file [`load_grammar_file`] can load
+pub fn is_grammar_file(path: &Path) -> bool {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    file_name.ends_with(".tmLanguage")
+        || file_name.ends_with(".tmLanguage.json")
+        || file_name.ends_with(".sublime-syntax")
+}
+
+/// Load a `.tmLanguage`, `.tmLanguage.json` or `.sublime-syntax` file
+pub fn load_grammar_file(path: &Path) -> Result<SyntaxDefinition, String> {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    // Named after the file when the grammar has no name
+    let fallback_name = file_name.split('.').next().unwrap_or(file_name);
+
+    if file_name.ends_with(".sublime-syntax") {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        return SyntaxDefinition::load_from_str(&content, true, Some(fallback_name))
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e));
+    }
+
+    let grammar: Value = if file_name.ends_with(".json") {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?
+    } else {
+        plist::from_file(path).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?
+    };
+    convert_grammar(&grammar, fallback_name)
+        .map_err(|e| format!("Failed to convert {}: {}", path.display(), e))
+}
+
+/// Convert a TextMate grammar (as JSON) to a syntax definition
+pub fn convert_grammar(grammar: &Value, fallback_name: &str) -> Result<SyntaxDefinition, String> {
+    let scope = grammar
+        .get("scopeName")
+        .and_then(Value::as_str)
+        .ok_or("the grammar has no scopeName")?;
+    let name = grammar
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or(fallback_name);
+
+    let repository = grammar.get("repository").and_then(Value::as_object);
+    let converter = Converter {
+        repository_keys: repository
+            .map(|repository| repository.keys().cloned().collect())
+            .unwrap_or_default(),
+    };
+
+    let mut contexts = Map::new();
+    contexts.insert(
+        "main".to_string(),
+        Value::Array(converter.patterns(grammar.get("patterns"))),
+    );
+    for (key, rule) in repository.into_iter().flatten() {
+        contexts.insert(
+            format!("{}{}", REPOSITORY_PREFIX, key),
+            Value::Array(converter.repository_entry(rule)),
+        );
+    }
+
+    let mut syntax = Map::new();
+    syntax.insert("name".to_string(), name.into());
+    syntax.insert("scope".to_string(), scope.into());
+    if let Some(file_types) = grammar.get("fileTypes").and_then(Value::as_array) {
+        syntax.insert("file_extensions".to_string(), file_types.clone().into());
+    }
+    if let Some(first_line) = grammar.get("firstLineMatch").and_then(Value::as_str) {
+        syntax.insert("first_line_match".to_string(), first_line.into());
+    }
+    syntax.insert("contexts".to_string(), Value::Object(contexts));
+
+    let mut yaml = String::new();
+    write_yaml(&Value::Object(syntax), &mut yaml);
+    SyntaxDefinition::load_from_str(&yaml, true, Some(name)).map_err(|e| e.to_string())
+}
+
+struct Converter {
+    repository_keys: Vec<String>,
+}
+
+impl Converter {
+    /// Convert a repository entry: a rule, or a list of patterns
+    fn repository_entry(&self, rule: &Value) -> Vec<Value> {
+        if rule.get("match").is_some() || rule.get("begin").is_some() {
+            self.rule(rule).into_iter().collect()
+        } else {
+            self.patterns(rule.get("patterns"))
+        }
+    }
+
+    fn patterns(&self, patterns: Option<&Value>) -> Vec<Value> {
+        patterns
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .flat_map(|rule| match rule.get("patterns") {
+                // A rule grouping patterns stands for them
+                Some(patterns) if rule.get("match").is_none() && rule.get("begin").is_none() => {
+                    self.patterns(Some(patterns))
+                }
+                _ => self.rule(rule).into_iter().collect(),
+            })
+            .collect()
+    }
+
+    fn rule(&self, rule: &Value) -> Option<Value> {
+        let str_field = |field: &str| rule.get(field).and_then(Value::as_str);
+
+        if let Some(include) = str_field("include") {
+            return self.include(include).map(|include| {
+                let mut item = Map::new();
+                item.insert("include".to_string(), include.into());
+                Value::Object(item)
+            });
+        }
+
+        if let Some(regex) = str_field("match") {
+            let mut item = Map::new();
+            item.insert("match".to_string(), regex.into());
+            if let Some(name) = str_field("name") {
+                item.insert("scope".to_string(), name.into());
+            }
+            if let Some(captures) = captures(rule.get("captures")) {
+                item.insert("captures".to_string(), captures);
+            }
+            return Some(Value::Object(item));
+        }
+
+        let begin = str_field("begin")?;
+        let end = match (str_field("end"), str_field("while")) {
+            (Some(end), _) => end.to_string(),
+            (None, Some(while_regex)) => format!("^(?!{})", while_regex),
+            (None, None) => return None,
+        };
+
+        let mut context = Vec::new();
+        if let Some(name) = str_field("name") {
+            context.push(single("meta_scope", name.into()));
+        }
+        if let Some(content_name) = str_field("contentName") {
+            context.push(single("meta_content_scope", content_name.into()));
+        }
+        let mut end_item = Map::new();
+        end_item.insert("match".to_string(), end.into());
+        if let Some(captures) =
+            captures(rule.get("endCaptures")).or_else(|| captures(rule.get("captures")))
+        {
+            end_item.insert("captures".to_string(), captures);
+        }
+        end_item.insert("pop".to_string(), true.into());
+        let patterns = self.patterns(rule.get("patterns"));
+        if rule.get("applyEndPatternLast").is_some_and(is_truthy) {
+            context.extend(patterns);
+            context.push(Value::Object(end_item));
+        } else {
+            context.push(Value::Object(end_item));
+            context.extend(patterns);
+        }
+
+        let mut item = Map::new();
+        item.insert("match".to_string(), begin.into());
+        if let Some(captures) =
+            captures(rule.get("beginCaptures")).or_else(|| captures(rule.get("captures")))
+        {
+            item.insert("captures".to_string(), captures);
+        }
+        item.insert("push".to_string(), Value::Array(context));
+        Some(Value::Object(item))
+    }
+
+    /// The context an include refers to, None for unknown repository entries
+    fn include(&self, include: &str) -> Option<String> {
+        match include {
+            "$self" | "$base" => Some("main".to_string()),
+            _ => match include.split_once('#') {
+                Some(("", key)) => self
+                    .repository_keys
+                    .iter()
+                    .any(|k| k == key)
+                    .then(|| format!("{}{}", REPOSITORY_PREFIX, key)),
+                Some((scope, key)) => Some(format!("scope:{}#{}{}", scope, REPOSITORY_PREFIX, key)),
+                None => Some(format!("scope:{}", include)),
+            },
+        }
+    }
+}
+
+fn single(key: &str, value: Value) -> Value {
+    let mut map = Map::new();
+    map.insert(key.to_string(), value);
+    Value::Object(map)
+}
+
+/// Convert TextMate captures (`{"1": {"name": "..."}}`) to syntect's
+/// (`{1: "..."}`)
+fn captures(captures: Option<&Value>) -> Option<Value> {
+    let captures: Map<String, Value> = captures?
+        .as_object()?
+        .iter()
+        .filter(|(index, _)| index.parse::<usize>().is_ok())
+        .filter_map(|(index, capture)| {
+            let name = capture.get("name")?.as_str()?;
+            Some((index.clone(), name.into()))
+        })
+        .collect();
+    (!captures.is_empty()).then_some(Value::Object(captures))
+}
+
+/// Property lists spell booleans as integers at times
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_i64() != Some(0),
+        _ => false,
+    }
+}
+
+/// Write a value as flow-style YAML, which JSON nearly is: strings are written
+/// as JSON strings (valid YAML double-quoted scalars), but numeric keys are
+/// left unquoted, since syntect reads capture indices as integers
+fn write_yaml(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            out.push('{');
+            for (i, (key, value)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                if key.parse::<usize>().is_ok() {
+                    out.push_str(key);
+                } else {
+                    out.push_str(&Value::String(key.clone()).to_string());
+                }
+                out.push_str(": ");
+                write_yaml(value, out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_yaml(item, out);
+            }
+            out.push(']');
+        }
+        _ => out.push_str(&value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syntect::parsing::{ParseState, ScopeStack, SyntaxSet, SyntaxSetBuilder};
+
+    /// A grammar for a small language: `let` keywords, `# comments`, and
+    /// strings with escapes
+    fn grammar() -> Value {
+        serde_json::json!({
+            "name": "Widget",
+            "scopeName": "source.widget",
+            "fileTypes": ["widget"],
+            "firstLineMatch": "^#!.*\\bwidget\\b",
+            "patterns": [
+                { "include": "#comments" },
+                { "match": "\\b(let)\\s+(\\w+)", "captures": {
+                    "1": { "name": "keyword.other.widget" },
+                    "2": { "name": "variable.other.widget" }
+                } },
+                { "include": "#strings" },
+                { "include": "#missing" }
+            ],
+            "repository": {
+                "comments": { "match": "#.*$", "name": "comment.line.widget" },
+                "strings": { "patterns": [ {
+                    "begin": "\"",
+                    "end": "\"",
+                    "name": "string.quoted.double.widget",
+                    "patterns": [ { "match": "\\\\.", "name": "constant.character.escape.widget" } ]
+                } ] }
+            }
+        })
+    }
+
+    fn build(syntax: SyntaxDefinition) -> SyntaxSet {
+        let mut builder = SyntaxSetBuilder::new();
+        builder.add_plain_text_syntax();
+        builder.add(syntax);
+        builder.build()
+    }
+
+    /// The scopes of each token of a line
+    fn scopes(set: &SyntaxSet, line: &str) -> Vec<(String, String)> {
+        let syntax = set.find_syntax_by_extension("widget").unwrap();
+        let mut state = ParseState::new(syntax);
+        let ops = state.parse_line(line, set).unwrap();
+        let mut stack = ScopeStack::new();
+        let mut tokens = Vec::new();
+        let mut start = 0;
+        let top = |stack: &ScopeStack| {
+            stack
+                .as_slice()
+                .last()
+                .map(|scope| scope.build_string())
+                .unwrap_or_default()
+        };
+        for (index, op) in ops {
+            if index > start {
+                tokens.push((line[start..index].to_string(), top(&stack)));
+                start = index;
+            }
+            stack.apply(&op).unwrap();
+        }
+        if start < line.len() {
+            tokens.push((line[start..].to_string(), top(&stack)));
+        }
+        tokens
+    }
+
+    #[test]
+    fn test_convert_grammar() {
+        let syntax = convert_grammar(&grammar(), "widget").unwrap();
+        assert_eq!(syntax.name, "Widget");
+        assert_eq!(syntax.file_extensions, vec!["widget".to_string()]);
+
+        let set = build(syntax);
+        assert_eq!(
+            set.find_syntax_by_first_line("#!/usr/bin/env widget\n")
+                .map(|s| s.name.as_str()),
+            Some("Widget")
+        );
+        let tokens = scopes(&set, "let x = \"a\\n\" # done\n");
+        let scope_of = |text: &str| {
+            tokens
+                .iter()
+                .find(|(t, _)| t == text)
+                .map(|(_, scope)| scope.as_str())
+        };
+        assert_eq!(scope_of("let"), Some("keyword.other.widget"));
+        assert_eq!(scope_of("x"), Some("variable.other.widget"));
+        assert_eq!(scope_of("\\n"), Some("constant.character.escape.widget"));
+        assert_eq!(scope_of("\"a"), Some("string.quoted.double.widget"));
+        assert_eq!(scope_of("# done"), Some("comment.line.widget"));
+    }
+
+    #[test]
+    fn test_load_grammar_files() {
+        let temp = tempfile::TempDir::new().unwrap();
+
+        let json = temp.path().join("widget.tmLanguage.json");
+        std::fs::write(&json, grammar().to_string()).unwrap();
+        assert!(is_grammar_file(&json));
+        assert_eq!(load_grammar_file(&json).unwrap().name, "Widget");
+
+        let plist_file = temp.path().join("widget.tmLanguage");
+        plist::to_file_xml(&plist_file, &grammar()).unwrap();
+        assert!(is_grammar_file(&plist_file));
+        assert_eq!(load_grammar_file(&plist_file).unwrap().name, "Widget");
+
+        let sublime = temp.path().join("gadget.sublime-syntax");
+        std::fs::write(
+            &sublime,
+            "%YAML 1.2\n---\nfile_extensions: [gadget]\nscope: source.gadget\ncontexts:\n  main: []\n",
+        )
+        .unwrap();
+        assert_eq!(load_grammar_file(&sublime).unwrap().name, "gadget");
+
+        assert!(!is_grammar_file(&temp.path().join("package.json")));
+        let invalid = temp.path().join("broken.tmLanguage.json");
+        std::fs::write(&invalid, "{\"name\": \"Broken\"}").unwrap();
+        assert!(load_grammar_file(&invalid).is_err());
+    }
+}
@@ -0,0 +1,399 @@
+//! Tree-sitter grammars loaded at runtime
+//!
+//! Besides the tree-sitter grammars compiled into Fresh, a language can use a
+//! grammar built as a shared library by the tree-sitter CLI. The library and
+//! the queries of a grammar are looked up, by the `grammar` name of the
+//! language, in the `tree-sitter` folder of the grammars directory:
+//!
+//! ```text
+//! ~/.config/fresh/grammars/tree-sitter/
+//! ├── zig.so                  # .dylib on macOS, .dll on Windows
+//! └── queries/zig/
+//!     ├── highlights.scm
+//!     ├── locals.scm          # optional
+//!     └── indents.scm         # optional
+//! ```
+//!
+//! The library must export the `tree_sitter_<name>` function generated by the
+//! tree-sitter CLI (with `-` in the name replaced by `_`). Libraries are never
+//! unloaded, and a loaded grammar lives until the process exits, so that
+//! [`Language`](crate::primitives::highlighter::Language) can refer to it and
+//! stay `Copy`.
+
+use crate::config::LanguageConfig;
+use libloading::{Library, Symbol};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use tree_sitter::{Query, LANGUAGE_VERSION, MIN_COMPATIBLE_LANGUAGE_VERSION};
+
+/// Folder of the grammars directory holding the tree-sitter grammars
+pub const TREE_SITTER_DIR: &str = "tree-sitter";
+
+/// Signature of the `tree_sitter_<name>` function of a grammar library
+type LanguageFn = unsafe extern "C" fn() -> *const tree_sitter::ffi::TSLanguage;
+
+// Libraries loaded so far, with their grammar. A library is never dropped:
+// the grammar, and the parsers and trees created with it, point into it.
+static LIBRARIES: Mutex<Vec<(PathBuf, Library, tree_sitter::Language)>> = Mutex::new(Vec::new());
+
+// Latest grammar loaded for each library and language. Loading an unchanged
+// grammar again returns the same one, and a changed one (e.g. an edited query)
+// replaces it, so that only the changed versions are leaked.
+static GRAMMARS: Mutex<Vec<&'static TreeSitterGrammar>> = Mutex::new(Vec::new());
+
+/// A tree-sitter grammar loaded from a shared library, with its queries
+#[derive(PartialEq)]
+pub struct TreeSitterGrammar {
+    /// Grammar name (the `grammar` of the language)
+    pub name: String,
+    /// Name of the language in the `languages` section of the config
+    pub language_id: String,
+    /// File extensions of the language, without the dot
+    pub extensions: Vec<String>,
+    /// Path of the shared library
+    pub library_path: PathBuf,
+    /// The grammar itself
+    pub language: tree_sitter::Language,
+    /// Highlights query (`highlights.scm`), empty if there is none
+    pub highlights_query: String,
+    /// Capture names of the highlights query, in the order of its captures
+    pub highlight_names: Vec<String>,
+    /// Locals query (`locals.scm`), for scope-aware semantic highlighting
+    pub locals_query: Option<String>,
+    /// Indents query (`indents.scm`), with `@indent` and `@dedent` captures
+    pub indents_query: Option<String>,
+}
+
+impl std::fmt::Debug for TreeSitterGrammar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TreeSitterGrammar")
+            .field("name", &self.name)
+            .field("language_id", &self.language_id)
+            .field("library_path", &self.library_path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TreeSitterGrammar {
+    /// Whether this grammar parses the files with `extension`
+    pub fn handles_extension(&self, extension: &str) -> bool {
+        self.extensions.iter().any(|ext| ext == extension)
+    }
+}
+
+/// Load the grammars of the `languages` that have a library in `dir`
+///
+/// Languages whose grammar has no library there use the built-in grammar of
+/// that name, if any. Grammars that fail to load are logged and skipped.
+pub fn load_grammars(
+    dir: &Path,
+    languages: &HashMap<String, LanguageConfig>,
+) -> Vec<&'static TreeSitterGrammar> {
+    let mut languages: Vec<(&String, &LanguageConfig)> = languages.iter().collect();
+    languages.sort_by_key(|(name, _)| *name);
+
+    let mut grammars = Vec::new();
+    for (id, config) in languages {
+        if config.grammar.is_empty() {
+            continue;
+        }
+        let library_path = library_path(dir, &config.grammar);
+        if !library_path.is_file() {
+            continue;
+        }
+        match load_grammar(dir, &library_path, id, config) {
+            Ok(grammar) => {
+                tracing::info!(
+                    "Loaded tree-sitter grammar {} for {} from {:?}",
+                    grammar.name,
+                    id,
+                    library_path
+                );
+                grammars.push(grammar);
+            }
+            Err(e) => tracing::warn!("{}", e),
+        }
+    }
+    grammars
+}
+
+/// Path of the library of the grammar `name` in `dir`
+pub fn library_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.{}", name, std::env::consts::DLL_EXTENSION))
+}
+
+/// Load a grammar and its queries for the language `language_id`
+fn load_grammar(
+    dir: &Path,
+    library_path: &Path,
+    language_id: &str,
+    config: &LanguageConfig,
+) -> Result<&'static TreeSitterGrammar, String> {
+    let language = load_library(library_path, &config.grammar)?;
+
+    let queries_dir = dir.join("queries").join(&config.grammar);
+    let (highlights_query, highlight_names) =
+        match read_query(&language, &queries_dir, "highlights")? {
+            Some((source, query)) => {
+                let names = query
+                    .capture_names()
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect();
+                (source, names)
+            }
+            None => (String::new(), Vec::new()),
+        };
+    let grammar = TreeSitterGrammar {
+        name: config.grammar.clone(),
+        language_id: language_id.to_string(),
+        extensions: config
+            .extensions
+            .iter()
+            .map(|ext| ext.trim_start_matches('.').to_string())
+            .collect(),
+        library_path: library_path.to_path_buf(),
+        highlights_query,
+        highlight_names,
+        locals_query: read_query(&language, &queries_dir, "locals")?.map(|(source, _)| source),
+        indents_query: read_query(&language, &queries_dir, "indents")?.map(|(source, _)| source),
+        language,
+    };
+
+    let mut grammars = GRAMMARS.lock().unwrap_or_else(PoisonError::into_inner);
+    let slot = grammars.iter().position(|loaded| {
+        loaded.library_path == grammar.library_path && loaded.language_id == grammar.language_id
+    });
+    if let Some(index) = slot {
+        if *grammars[index] == grammar {
+            return Ok(grammars[index]);
+        }
+    }
+    let grammar: &'static TreeSitterGrammar = Box::leak(Box::new(grammar));
+    match slot {
+        Some(index) => grammars[index] = grammar,
+        None => grammars.push(grammar),
+    }
+    Ok(grammar)
+}
+
+/// Load the grammar `name` from a shared library, or return it if that
+/// library was already loaded
+fn load_library(path: &Path, name: &str) -> Result<tree_sitter::Language, String> {
+    let mut libraries = LIBRARIES.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some((_, _, language)) = libraries.iter().find(|(loaded, _, _)| loaded == path) {
+        return Ok(language.clone());
+    }
+
+    let symbol = format!("tree_sitter_{}", name.replace('-', "_"));
+    // SAFETY: the library is a tree-sitter grammar, whose `tree_sitter_<name>`
+    // function takes no arguments and returns a pointer to its static
+    // TSLanguage. The library stays loaded while the grammar is in use.
+    let (library, raw) = unsafe {
+        let library = Library::new(path)
+            .map_err(|e| format!("Failed to load tree-sitter grammar {:?}: {}", path, e))?;
+        let raw = {
+            let language_fn: Symbol<LanguageFn> = library
+                .get(symbol.as_bytes())
+                .map_err(|e| format!("No {} in tree-sitter grammar {:?}: {}", symbol, path, e))?;
+            (*language_fn)()
+        };
+        (library, raw)
+    };
+    if raw.is_null() {
+        return Err(format!("{} returned no grammar in {:?}", symbol, path));
+    }
+
+    // SAFETY: `raw` points to the TSLanguage of the library, which is kept
+    // loaded below
+    let language = unsafe { tree_sitter::Language::from_raw(raw) };
+    let version = language.abi_version();
+    if !(MIN_COMPATIBLE_LANGUAGE_VERSION..=LANGUAGE_VERSION).contains(&version) {
+        return Err(format!(
+            "Tree-sitter grammar {:?} has ABI version {}, but Fresh supports versions {} to {}",
+            path, version, MIN_COMPATIBLE_LANGUAGE_VERSION, LANGUAGE_VERSION
+        ));
+    }
+
+    libraries.push((path.to_path_buf(), library, language.clone()));
+    Ok(language)
+}
+
+/// Read the query `<name>.scm` of `dir`, checking it against `language`
+///
+/// Returns the source and the parsed query, or `None` if the grammar has no
+/// such query.
+fn read_query(
+    language: &tree_sitter::Language,
+    dir: &Path,
+    name: &str,
+) -> Result<Option<(String, Query)>, String> {
+    let path = dir.join(format!("{}.scm", name));
+    let source = match std::fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {:?}: {}", path, e)),
+    };
+    let query =
+        Query::new(language, &source).map_err(|e| format!("Invalid query {:?}: {}", path, e))?;
+    Ok(Some((source, query)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn language(grammar: &str, extensions: &[&str]) -> LanguageConfig {
+        serde_json::from_value(serde_json::json!({
+            "extensions": extensions,
+            "grammar": grammar,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_load_grammars_without_library() {
+        let dir = TempDir::new().unwrap();
+        let languages = HashMap::from([
+            ("rust".to_string(), language("rust", &["rs"])),
+            ("zig".to_string(), language("zig", &["zig"])),
+        ]);
+
+        assert!(load_grammars(dir.path(), &languages).is_empty());
+    }
+
+    #[test]
+    fn test_load_grammars_skips_invalid_library() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(library_path(dir.path(), "zig"), b"not a library").unwrap();
+        let languages = HashMap::from([("zig".to_string(), language("zig", &["zig"]))]);
+
+        assert!(load_grammars(dir.path(), &languages).is_empty());
+        let error = load_grammar(
+            dir.path(),
+            &library_path(dir.path(), "zig"),
+            "zig",
+            &languages["zig"],
+        )
+        .unwrap_err();
+        assert!(
+            error.contains("Failed to load tree-sitter grammar"),
+            "{error}"
+        );
+    }
+
+    /// Build the tree-sitter-json fixture into `dir` as the grammar `json`,
+    /// with its highlights query
+    #[cfg(unix)]
+    fn build_json_grammar(dir: &Path) {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tree-sitter-json");
+        let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+        let status = std::process::Command::new(&compiler)
+            .args(["-shared", "-fPIC", "-I"])
+            .arg(fixture.join("src"))
+            .arg(fixture.join("src/parser.c"))
+            .arg("-o")
+            .arg(library_path(dir, "json"))
+            .status()
+            .unwrap_or_else(|e| panic!("Failed to run C compiler {compiler}: {e}"));
+        assert!(status.success(), "Failed to build the json grammar");
+
+        let queries = dir.join("queries/json");
+        std::fs::create_dir_all(&queries).unwrap();
+        std::fs::copy(
+            fixture.join("queries/highlights.scm"),
+            queries.join("highlights.scm"),
+        )
+        .unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_load_and_highlight_compiled_grammar() {
+        use crate::model::buffer::Buffer;
+        use crate::primitives::highlighter::{Highlighter, Language};
+        use crate::view::theme::Theme;
+
+        let dir = TempDir::new().unwrap();
+        build_json_grammar(dir.path());
+        let languages = HashMap::from([("jsonc".to_string(), language("json", &[".jsonc"]))]);
+
+        let grammars = load_grammars(dir.path(), &languages);
+        assert_eq!(grammars.len(), 1);
+        let grammar = grammars[0];
+        assert_eq!(grammar.language_id, "jsonc");
+        assert!(grammar.handles_extension("jsonc"));
+        assert!(grammar
+            .highlight_names
+            .iter()
+            .any(|name| name == "string.special.key"));
+
+        // Key, number and literal are highlighted through the captures of the
+        // loaded highlights.scm
+        let buffer = Buffer::from_str_test("{\"key\": [1, true]}");
+        let theme = Theme::dark();
+        let mut highlighter = Highlighter::new(Language::Dynamic(grammar)).unwrap();
+        let spans = highlighter.highlight_viewport(&buffer, 0, buffer.len(), &theme, 100_000);
+        let color_at = |offset: usize| {
+            spans
+                .iter()
+                .find(|span| span.range.contains(&offset))
+                .map(|span| span.color)
+        };
+        assert_eq!(color_at(2), Some(theme.syntax_string));
+        assert_eq!(color_at(9), Some(theme.syntax_constant));
+        assert_eq!(color_at(12), Some(theme.syntax_constant));
+        assert_eq!(color_at(0), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_reloading_grammar_reuses_or_replaces_it() {
+        let dir = TempDir::new().unwrap();
+        build_json_grammar(dir.path());
+        let languages = HashMap::from([("jsonc".to_string(), language("json", &["jsonc"]))]);
+        let cached = || {
+            GRAMMARS
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|grammar| grammar.library_path.starts_with(dir.path()))
+                .count()
+        };
+
+        let first = load_grammars(dir.path(), &languages)[0];
+        let again = load_grammars(dir.path(), &languages)[0];
+        assert!(std::ptr::eq(first, again));
+        assert_eq!(cached(), 1);
+
+        std::fs::write(
+            dir.path().join("queries/json/highlights.scm"),
+            "(number) @number\n",
+        )
+        .unwrap();
+        let edited = load_grammars(dir.path(), &languages)[0];
+        assert!(!std::ptr::eq(first, edited));
+        assert_eq!(edited.highlight_names, ["number"]);
+        assert_eq!(cached(), 1);
+    }
+
+    #[test]
+    fn test_read_query() {
+        let dir = TempDir::new().unwrap();
+        let rust: tree_sitter::Language = tree_sitter_rust::LANGUAGE.into();
+        std::fs::write(dir.path().join("locals.scm"), "(block) @local.scope\n").unwrap();
+        std::fs::write(dir.path().join("indents.scm"), "(no_such_node) @indent\n").unwrap();
+
+        let (source, query) = read_query(&rust, dir.path(), "locals").unwrap().unwrap();
+        assert_eq!(source, "(block) @local.scope\n");
+        assert_eq!(query.capture_names(), ["local.scope"]);
+        assert!(read_query(&rust, dir.path(), "highlights")
+            .unwrap()
+            .is_none());
+        assert!(read_query(&rust, dir.path(), "indents")
+            .unwrap_err()
+            .contains("Invalid query"));
+    }
+}
@@ -10,6 +10,8 @@
 //! - Computation should be sync (editing, rendering)
 //! - Main loop remains responsive and simple
 
+use crate::services::dap::protocol::{ScopeVariables, StackFrame};
+use crate::services::project_search::FileMatches;
 use crate::services::terminal::TerminalId;
 use crate::view::file_tree::{FileTreeView, NodeId};
 use lsp_types::{
@@ -24,6 +26,8 @@ pub enum AsyncMessage {
     /// LSP diagnostics received for a file
     LspDiagnostics {
         uri: String,
+        /// Name of the server that published them
+        server: String,
         diagnostics: Vec<Diagnostic>,
     },
 
@@ -56,6 +60,25 @@ pub enum AsyncMessage {
         result: Result<lsp_types::WorkspaceEdit, String>,
     },
 
+    /// LSP willRenameFiles response: edits to make for renamed files
+    LspWillRenameFiles {
+        request_id: u64,
+        result: Result<Option<lsp_types::WorkspaceEdit>, String>,
+    },
+
+    /// LSP formatting / range formatting response
+    LspFormatting {
+        request_id: u64,
+        uri: lsp_types::Uri,
+        result: Result<Vec<lsp_types::TextEdit>, String>,
+    },
+
+    /// External formatter finished (the formatted text, or why it failed)
+    FormatterFinished {
+        request_id: u64,
+        result: Result<String, String>,
+    },
+
     /// LSP hover response
     LspHover {
         request_id: u64,
@@ -90,6 +113,8 @@ pub enum AsyncMessage {
     LspPulledDiagnostics {
         request_id: u64,
         uri: String,
+        /// Name of the server that reported them
+        server: String,
         /// New result_id for incremental updates (None if server doesn't support)
         result_id: Option<String>,
         /// Diagnostics (empty if unchanged)
@@ -106,6 +131,21 @@ pub enum AsyncMessage {
         hints: Vec<InlayHint>,
     },
 
+    /// LSP folding ranges response (textDocument/foldingRange)
+    LspFoldingRanges {
+        request_id: u64,
+        uri: String,
+        ranges: Vec<lsp_types::FoldingRange>,
+    },
+
+    /// LSP selection ranges response (textDocument/selectionRange), one per
+    /// requested position
+    LspSelectionRanges {
+        request_id: u64,
+        uri: String,
+        ranges: Vec<lsp_types::SelectionRange>,
+    },
+
     /// LSP server status became quiescent (project fully loaded)
     /// This is a rust-analyzer specific notification (experimental/serverStatus)
     LspServerQuiescent { language: String },
@@ -113,6 +153,9 @@ pub enum AsyncMessage {
     /// File changed externally (future: file watching)
     FileChanged { path: String },
 
+    /// A file in the grammars directory changed
+    GrammarsChanged,
+
     /// Git status updated (future: git integration)
     GitStatusChanged { status: String },
 
@@ -165,6 +208,8 @@ pub enum AsyncMessage {
     /// LSP server status update
     LspStatusUpdate {
         language: String,
+        /// Name of the server among the servers of the language
+        server: String,
         status: LspServerStatus,
     },
     /// Generic notification from an LSP server
@@ -192,6 +237,72 @@ pub enum AsyncMessage {
     /// File open dialog: directory listing completed
     FileOpenDirectoryLoaded(std::io::Result<Vec<crate::services::fs::FsEntry>>),
 
+    /// Streaming buffer search found more matches
+    SearchProgress {
+        search_id: u64,
+        /// Match start offsets found since the previous progress message
+        matches: Vec<usize>,
+        /// Bytes searched so far
+        bytes_searched: usize,
+        /// Total bytes to search
+        total_bytes: usize,
+    },
+
+    /// Streaming buffer search completed (error is set if reading failed)
+    SearchFinished {
+        search_id: u64,
+        error: Option<String>,
+    },
+
+    /// Project search found matches in more files
+    ProjectSearchProgress {
+        search_id: u64,
+        files: Vec<FileMatches>,
+    },
+
+    /// Project search completed (truncated if it stopped at the match limit)
+    ProjectSearchFinished {
+        search_id: u64,
+        truncated: bool,
+        error: Option<String>,
+    },
+
+    /// Debuggee stopped (breakpoint, step, pause...) with its call stack
+    DapStopped {
+        session_id: u64,
+        reason: String,
+        frames: Vec<StackFrame>,
+    },
+
+    /// Variables of a stack frame of the stopped debuggee
+    DapVariables {
+        session_id: u64,
+        frame_id: i64,
+        scopes: Vec<ScopeVariables>,
+    },
+
+    /// Debuggee resumed running
+    DapContinued { session_id: u64 },
+
+    /// Output from the debuggee or the debug adapter
+    DapOutput { session_id: u64, output: String },
+
+    /// Debug request failed
+    DapError { session_id: u64, error: String },
+
+    /// Debug session ended
+    DapTerminated {
+        session_id: u64,
+        exit_code: Option<i64>,
+    },
+
+    /// Plugin package install or update finished (`name` is the typed
+    /// source when an install failed before naming the package)
+    PluginPackageFinished {
+        name: String,
+        result: Result<crate::services::plugins::packages::PackageChange, String>,
+    },
+
     /// Terminal output received (triggers redraw)
     TerminalOutput { terminal_id: TerminalId },
 
@@ -418,6 +529,7 @@ mod tests {
         sender
             .send(AsyncMessage::LspDiagnostics {
                 uri: "file:///test.rs".to_string(),
+                server: "rust-analyzer".to_string(),
                 diagnostics: diagnostics.clone(),
             })
             .unwrap();
@@ -428,9 +540,11 @@ mod tests {
         match &messages[0] {
             AsyncMessage::LspDiagnostics {
                 uri,
+                server,
                 diagnostics: diags,
             } => {
                 assert_eq!(uri, "file:///test.rs");
+                assert_eq!(server, "rust-analyzer");
                 assert_eq!(diags.len(), 1);
                 assert_eq!(diags[0].message, "test error");
             }
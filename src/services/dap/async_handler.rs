@@ -0,0 +1,682 @@
+//! Async DAP client using Tokio
+//!
+//! This mirrors the LSP client in `services::lsp::async_handler`:
+//! - DapTask: async task that owns the debug adapter process and drives the
+//!   debug session (handshake, breakpoints, stepping)
+//! - DapHandle: sync handle the editor uses to send commands to the task
+//! - Session events (stops, output, termination) are reported to the main
+//!   loop via AsyncBridge
+//!
+//! A reader task dispatches responses to waiting requests and forwards events
+//! to the session task, so a request can be awaited while events queue up.
+
+use crate::config::{DebugAdapterConfig, DebugRequest};
+use crate::services::async_bridge::{AsyncBridge, AsyncMessage};
+use crate::services::dap::protocol::{
+    read_message, write_message, Event, ProtocolMessage, Request, Response, Scope, ScopeVariables,
+    StackFrame, Variable,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{mpsc as std_mpsc, Arc, Mutex};
+use std::time::Duration;
+use tokio::io::BufReader;
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::{mpsc, oneshot};
+
+/// How long to wait for the adapter to answer a request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait for the adapter to acknowledge a disconnect
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Maximum number of stack frames fetched when a thread stops
+const MAX_STACK_FRAMES: usize = 50;
+
+type PendingRequests = Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>;
+
+/// Commands sent from the editor to the session task
+#[derive(Debug)]
+enum DapCommand {
+    /// Replace the breakpoints of a file (0-based lines)
+    SetBreakpoints {
+        path: PathBuf,
+        lines: Vec<usize>,
+    },
+    Continue,
+    StepOver,
+    StepInto,
+    StepOut,
+    Pause,
+    /// Load the variables of another frame of the stopped thread
+    SelectFrame {
+        frame_id: i64,
+    },
+    /// Disconnect and terminate the debuggee
+    Stop,
+}
+
+/// Write half of the adapter connection, shared by the session and reader tasks
+#[derive(Clone)]
+struct DapConnection {
+    stdin: Arc<tokio::sync::Mutex<ChildStdin>>,
+    next_seq: Arc<AtomicI64>,
+    pending: PendingRequests,
+}
+
+impl DapConnection {
+    fn new(stdin: ChildStdin) -> Self {
+        Self {
+            stdin: Arc::new(tokio::sync::Mutex::new(stdin)),
+            next_seq: Arc::new(AtomicI64::new(1)),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    async fn send(&self, message: ProtocolMessage) -> Result<(), String> {
+        let mut stdin = self.stdin.lock().await;
+        write_message(&mut *stdin, &message).await
+    }
+
+    /// Send a request, returning a receiver for its response body
+    async fn send_request(
+        &self,
+        command: &str,
+        arguments: Value,
+    ) -> Result<oneshot::Receiver<Result<Value, String>>, String> {
+        let seq = self.next_seq.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(seq, tx);
+
+        let request = ProtocolMessage::Request(Request {
+            seq,
+            command: command.to_string(),
+            arguments,
+        });
+        if let Err(e) = self.send(request).await {
+            self.pending.lock().unwrap().remove(&seq);
+            return Err(e);
+        }
+        Ok(rx)
+    }
+
+    /// Send a request and wait for its response body
+    async fn request(&self, command: &str, arguments: Value) -> Result<Value, String> {
+        self.request_with_timeout(command, arguments, REQUEST_TIMEOUT)
+            .await
+    }
+
+    async fn request_with_timeout(
+        &self,
+        command: &str,
+        arguments: Value,
+        timeout: Duration,
+    ) -> Result<Value, String> {
+        let rx = self.send_request(command, arguments).await?;
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(format!(
+                "Debug adapter exited before answering '{}'",
+                command
+            )),
+            Err(_) => Err(format!("Debug adapter timed out on '{}'", command)),
+        }
+    }
+
+    /// Decline a request sent by the adapter (e.g. `runInTerminal`)
+    async fn decline(&self, request: &Request) -> Result<(), String> {
+        let response = ProtocolMessage::Response(Response {
+            seq: self.next_seq.fetch_add(1, Ordering::SeqCst),
+            request_seq: request.seq,
+            success: false,
+            command: request.command.clone(),
+            message: Some(format!("'{}' is not supported", request.command)),
+            body: Value::Null,
+        });
+        self.send(response).await
+    }
+
+    /// Complete a pending request with the adapter's response
+    fn resolve(&self, response: Response) {
+        let sender = self.pending.lock().unwrap().remove(&response.request_seq);
+        if let Some(sender) = sender {
+            let result = if response.success {
+                Ok(response.body)
+            } else {
+                Err(response
+                    .message
+                    .unwrap_or_else(|| format!("'{}' failed", response.command)))
+            };
+            let _ = sender.send(result);
+        }
+    }
+
+    /// Fail every pending request (the adapter is gone)
+    fn fail_pending(&self) {
+        self.pending.lock().unwrap().clear();
+    }
+}
+
+/// Async task driving one debug session
+struct DapTask {
+    process: Child,
+    connection: DapConnection,
+    session_id: u64,
+    language: String,
+    async_tx: std_mpsc::Sender<AsyncMessage>,
+
+    /// Breakpoints by file (0-based lines), sent once the adapter is configured
+    breakpoints: HashMap<PathBuf, Vec<usize>>,
+
+    /// Whether the `initialized` event arrived and configuration is done
+    configured: bool,
+
+    /// Thread that last stopped, used for stepping
+    thread_id: Option<i64>,
+
+    /// Exit code from the `exited` event
+    exit_code: Option<i64>,
+}
+
+impl DapTask {
+    /// Start the adapter process with stderr logged to `stderr_log_path`
+    fn spawn_process(
+        command: &str,
+        args: &[String],
+        stderr_log_path: &std::path::Path,
+    ) -> Result<(Child, ChildStdin, ChildStdout), String> {
+        let stderr_file = std::fs::File::create(stderr_log_path).map_err(|e| {
+            format!(
+                "Failed to create debug adapter log file {:?}: {}",
+                stderr_log_path, e
+            )
+        })?;
+
+        let mut process = Command::new(command)
+            .args(args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::from(stderr_file))
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to start debug adapter '{}': {}", command, e))?;
+
+        let stdin = process
+            .stdin
+            .take()
+            .ok_or_else(|| "Failed to get stdin".to_string())?;
+        let stdout = process
+            .stdout
+            .take()
+            .ok_or_else(|| "Failed to get stdout".to_string())?;
+
+        Ok((process, stdin, stdout))
+    }
+
+    /// Spawn the reader task that dispatches responses and forwards events
+    ///
+    /// The event channel closes when the adapter's stdout does.
+    fn spawn_stdout_reader(
+        stdout: ChildStdout,
+        connection: DapConnection,
+    ) -> mpsc::UnboundedReceiver<Event> {
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut stdout = BufReader::new(stdout);
+            loop {
+                match read_message(&mut stdout).await {
+                    Ok(ProtocolMessage::Response(response)) => connection.resolve(response),
+                    Ok(ProtocolMessage::Event(event)) => {
+                        if event_tx.send(event).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(ProtocolMessage::Request(request)) => {
+                        tracing::debug!("Declining DAP reverse request '{}'", request.command);
+                        if let Err(e) = connection.decline(&request).await {
+                            tracing::warn!("Failed to answer DAP request: {}", e);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::info!("DAP reader exiting: {}", e);
+                        break;
+                    }
+                }
+            }
+            connection.fail_pending();
+        });
+        event_rx
+    }
+
+    /// Run the session until the debuggee terminates or the editor stops it
+    async fn run(
+        mut self,
+        request: DebugRequest,
+        arguments: Value,
+        mut command_rx: mpsc::Receiver<DapCommand>,
+        mut event_rx: mpsc::UnboundedReceiver<Event>,
+    ) {
+        if let Err(e) = self.start(request, arguments).await {
+            self.report_error(e);
+            self.finish().await;
+            return;
+        }
+
+        loop {
+            tokio::select! {
+                command = command_rx.recv() => match command {
+                    Some(DapCommand::Stop) | None => {
+                        let _ = self
+                            .connection
+                            .request_with_timeout(
+                                "disconnect",
+                                json!({"terminateDebuggee": true}),
+                                DISCONNECT_TIMEOUT,
+                            )
+                            .await;
+                        break;
+                    }
+                    Some(command) => {
+                        if let Err(e) = self.handle_command(command).await {
+                            self.report_error(e);
+                        }
+                    }
+                },
+                event = event_rx.recv() => match event {
+                    Some(event) => {
+                        if event.event == "terminated" {
+                            break;
+                        }
+                        if let Err(e) = self.handle_event(event).await {
+                            self.report_error(e);
+                        }
+                    }
+                    // The adapter closed its stdout
+                    None => break,
+                },
+            }
+        }
+
+        self.finish().await;
+    }
+
+    /// Initialize the adapter and ask it to launch or attach
+    ///
+    /// The launch/attach response isn't awaited here: adapters may hold it
+    /// back until configuration is done, which happens once the
+    /// `initialized` event arrives.
+    async fn start(&mut self, request: DebugRequest, arguments: Value) -> Result<(), String> {
+        self.connection
+            .request(
+                "initialize",
+                json!({
+                    "clientID": "fresh",
+                    "clientName": "Fresh",
+                    "adapterID": self.language,
+                    "linesStartAt1": true,
+                    "columnsStartAt1": true,
+                    "pathFormat": "path",
+                    "supportsRunInTerminalRequest": false,
+                }),
+            )
+            .await?;
+
+        let command = match request {
+            DebugRequest::Launch => "launch",
+            DebugRequest::Attach => "attach",
+        };
+        let response = self.connection.send_request(command, arguments).await?;
+        let async_tx = self.async_tx.clone();
+        let session_id = self.session_id;
+        tokio::spawn(async move {
+            if let Ok(Err(error)) = response.await {
+                let _ = async_tx.send(AsyncMessage::DapError {
+                    session_id,
+                    error: format!("{} failed: {}", command, error),
+                });
+            }
+        });
+        Ok(())
+    }
+
+    async fn handle_command(&mut self, command: DapCommand) -> Result<(), String> {
+        match command {
+            DapCommand::SetBreakpoints { path, lines } => {
+                self.breakpoints.insert(path.clone(), lines.clone());
+                if self.configured {
+                    self.send_breakpoints(&path, &lines).await?;
+                }
+            }
+            DapCommand::Continue => self.resume("continue").await?,
+            DapCommand::StepOver => self.resume("next").await?,
+            DapCommand::StepInto => self.resume("stepIn").await?,
+            DapCommand::StepOut => self.resume("stepOut").await?,
+            DapCommand::Pause => {
+                let thread_id = match self.thread_id {
+                    Some(thread_id) => thread_id,
+                    None => self.first_thread().await?,
+                };
+                self.connection
+                    .request("pause", json!({"threadId": thread_id}))
+                    .await?;
+            }
+            DapCommand::SelectFrame { frame_id } => self.send_variables(frame_id).await?,
+            DapCommand::Stop => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_event(&mut self, event: Event) -> Result<(), String> {
+        let body = &event.body;
+        match event.event.as_str() {
+            "initialized" => {
+                let breakpoints: Vec<_> = self
+                    .breakpoints
+                    .iter()
+                    .map(|(path, lines)| (path.clone(), lines.clone()))
+                    .collect();
+                for (path, lines) in breakpoints {
+                    self.send_breakpoints(&path, &lines).await?;
+                }
+                self.connection
+                    .request("configurationDone", Value::Null)
+                    .await?;
+                self.configured = true;
+            }
+            "stopped" => {
+                if let Some(thread_id) = body.get("threadId").and_then(Value::as_i64) {
+                    self.thread_id = Some(thread_id);
+                }
+                let thread_id = self.thread_id.unwrap_or(1);
+                let reason = body
+                    .get("description")
+                    .or_else(|| body.get("reason"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("paused")
+                    .to_string();
+
+                let stack = self
+                    .connection
+                    .request(
+                        "stackTrace",
+                        json!({"threadId": thread_id, "startFrame": 0, "levels": MAX_STACK_FRAMES}),
+                    )
+                    .await?;
+                let frames: Vec<StackFrame> =
+                    serde_json::from_value(stack.get("stackFrames").cloned().unwrap_or_default())
+                        .map_err(|e| format!("Invalid stack trace: {}", e))?;
+                let top_frame = frames.first().map(|frame| frame.id);
+
+                let _ = self.async_tx.send(AsyncMessage::DapStopped {
+                    session_id: self.session_id,
+                    reason,
+                    frames,
+                });
+                if let Some(frame_id) = top_frame {
+                    self.send_variables(frame_id).await?;
+                }
+            }
+            "continued" => {
+                let _ = self.async_tx.send(AsyncMessage::DapContinued {
+                    session_id: self.session_id,
+                });
+            }
+            "output" => {
+                let category = body
+                    .get("category")
+                    .and_then(Value::as_str)
+                    .unwrap_or("console");
+                let output = body.get("output").and_then(Value::as_str).unwrap_or("");
+                if category != "telemetry" && !output.is_empty() {
+                    let _ = self.async_tx.send(AsyncMessage::DapOutput {
+                        session_id: self.session_id,
+                        output: output.to_string(),
+                    });
+                }
+            }
+            "exited" => {
+                self.exit_code = body.get("exitCode").and_then(Value::as_i64);
+            }
+            other => {
+                tracing::debug!("Unhandled DAP event: {}", other);
+            }
+        }
+        Ok(())
+    }
+
+    /// Send a resume request (continue or a step) for the stopped thread
+    async fn resume(&mut self, command: &str) -> Result<(), String> {
+        let thread_id = self
+            .thread_id
+            .ok_or_else(|| "The debuggee is not stopped".to_string())?;
+        self.connection
+            .request(command, json!({"threadId": thread_id}))
+            .await?;
+        let _ = self.async_tx.send(AsyncMessage::DapContinued {
+            session_id: self.session_id,
+        });
+        Ok(())
+    }
+
+    /// Id of the debuggee's first thread, for pausing before any stop
+    async fn first_thread(&self) -> Result<i64, String> {
+        let threads = self.connection.request("threads", Value::Null).await?;
+        threads
+            .get("threads")
+            .and_then(Value::as_array)
+            .and_then(|threads| threads.first())
+            .and_then(|thread| thread.get("id"))
+            .and_then(Value::as_i64)
+            .ok_or_else(|| "The debuggee has no threads".to_string())
+    }
+
+    async fn send_breakpoints(
+        &self,
+        path: &std::path::Path,
+        lines: &[usize],
+    ) -> Result<(), String> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let breakpoints: Vec<Value> = lines.iter().map(|line| json!({"line": line + 1})).collect();
+        self.connection
+            .request(
+                "setBreakpoints",
+                json!({
+                    "source": {"name": name, "path": path.to_string_lossy()},
+                    "breakpoints": breakpoints,
+                }),
+            )
+            .await
+            .map(|_| ())
+    }
+
+    /// Fetch the scopes and variables of a frame and report them
+    async fn send_variables(&self, frame_id: i64) -> Result<(), String> {
+        let body = self
+            .connection
+            .request("scopes", json!({"frameId": frame_id}))
+            .await?;
+        let scopes: Vec<Scope> =
+            serde_json::from_value(body.get("scopes").cloned().unwrap_or_default())
+                .map_err(|e| format!("Invalid scopes: {}", e))?;
+
+        let mut result = Vec::new();
+        for scope in scopes {
+            let variables = if scope.expensive || scope.variables_reference == 0 {
+                Vec::new()
+            } else {
+                let body = self
+                    .connection
+                    .request(
+                        "variables",
+                        json!({"variablesReference": scope.variables_reference}),
+                    )
+                    .await?;
+                serde_json::from_value::<Vec<Variable>>(
+                    body.get("variables").cloned().unwrap_or_default(),
+                )
+                .map_err(|e| format!("Invalid variables: {}", e))?
+            };
+            result.push(ScopeVariables {
+                name: scope.name,
+                variables,
+            });
+        }
+
+        let _ = self.async_tx.send(AsyncMessage::DapVariables {
+            session_id: self.session_id,
+            frame_id,
+            scopes: result,
+        });
+        Ok(())
+    }
+
+    fn report_error(&self, error: String) {
+        tracing::warn!("Debug session error ({}): {}", self.language, error);
+        let _ = self.async_tx.send(AsyncMessage::DapError {
+            session_id: self.session_id,
+            error,
+        });
+    }
+
+    /// Stop the adapter and report the end of the session
+    async fn finish(mut self) {
+        let _ = self.process.kill().await;
+        let _ = self.async_tx.send(AsyncMessage::DapTerminated {
+            session_id: self.session_id,
+            exit_code: self.exit_code,
+        });
+    }
+}
+
+/// Synchronous handle to an async debug session
+pub struct DapHandle {
+    /// Channel for sending commands to the task
+    command_tx: mpsc::Sender<DapCommand>,
+
+    /// Id reported with every message from this session
+    session_id: u64,
+}
+
+impl DapHandle {
+    /// Start a debug adapter and begin a session
+    ///
+    /// `arguments` are sent with the launch or attach request. `breakpoints`
+    /// (0-based lines by file) are set once the adapter is ready.
+    pub fn spawn(
+        runtime: &tokio::runtime::Handle,
+        session_id: u64,
+        config: &DebugAdapterConfig,
+        arguments: Value,
+        language: String,
+        async_bridge: &AsyncBridge,
+        breakpoints: HashMap<PathBuf, Vec<usize>>,
+    ) -> Self {
+        let (command_tx, command_rx) = mpsc::channel(100);
+        let async_tx = async_bridge.sender();
+        let command = config.command.clone();
+        let args = config.args.clone();
+        let request = config.request;
+        let stderr_log_path =
+            std::env::temp_dir().join(format!("fresh-dap-{}-{}.log", language, std::process::id()));
+
+        runtime.spawn(async move {
+            let (process, stdin, stdout) =
+                match DapTask::spawn_process(&command, &args, &stderr_log_path) {
+                    Ok(spawned) => spawned,
+                    Err(error) => {
+                        tracing::error!("Failed to spawn debug adapter: {}", error);
+                        let _ = async_tx.send(AsyncMessage::DapError { session_id, error });
+                        let _ = async_tx.send(AsyncMessage::DapTerminated {
+                            session_id,
+                            exit_code: None,
+                        });
+                        return;
+                    }
+                };
+
+            let connection = DapConnection::new(stdin);
+            let event_rx = DapTask::spawn_stdout_reader(stdout, connection.clone());
+            let task = DapTask {
+                process,
+                connection,
+                session_id,
+                language,
+                async_tx,
+                breakpoints,
+                configured: false,
+                thread_id: None,
+                exit_code: None,
+            };
+            task.run(request, arguments, command_rx, event_rx).await;
+        });
+
+        Self {
+            command_tx,
+            session_id,
+        }
+    }
+
+    /// Id reported with this session's messages
+    pub fn session_id(&self) -> u64 {
+        self.session_id
+    }
+
+    /// Replace the breakpoints of a file (0-based lines)
+    pub fn set_breakpoints(&self, path: PathBuf, lines: Vec<usize>) {
+        self.send(DapCommand::SetBreakpoints { path, lines });
+    }
+
+    /// Resume the stopped thread
+    pub fn continue_execution(&self) {
+        self.send(DapCommand::Continue);
+    }
+
+    /// Step over the current line
+    pub fn step_over(&self) {
+        self.send(DapCommand::StepOver);
+    }
+
+    /// Step into the call on the current line
+    pub fn step_into(&self) {
+        self.send(DapCommand::StepInto);
+    }
+
+    /// Run until the current function returns
+    pub fn step_out(&self) {
+        self.send(DapCommand::StepOut);
+    }
+
+    /// Interrupt the running debuggee
+    pub fn pause(&self) {
+        self.send(DapCommand::Pause);
+    }
+
+    /// Load the variables of a frame of the stopped thread
+    pub fn select_frame(&self, frame_id: i64) {
+        self.send(DapCommand::SelectFrame { frame_id });
+    }
+
+    /// Disconnect from the adapter, terminating the debuggee
+    pub fn stop(&self) {
+        self.send(DapCommand::Stop);
+    }
+
+    fn send(&self, command: DapCommand) {
+        if let Err(e) = self.command_tx.try_send(command) {
+            tracing::warn!("Failed to send command to debug session: {}", e);
+        }
+    }
+}
+
+impl std::fmt::Debug for DapHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DapHandle")
+            .field("session_id", &self.session_id)
+            .finish()
+    }
+}
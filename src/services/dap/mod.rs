@@ -0,0 +1,4 @@
+//! DAP (Debug Adapter Protocol) integration
+
+pub mod async_handler;
+pub mod protocol;
@@ -0,0 +1,245 @@
+//! Debug Adapter Protocol message types and wire framing
+//!
+//! DAP uses the same `Content-Length` framing as LSP, but its messages are
+//! not JSON-RPC: every message carries a `seq` number and a `type` of
+//! `request`, `response` or `event`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// A message exchanged with a debug adapter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ProtocolMessage {
+    Request(Request),
+    Response(Response),
+    Event(Event),
+}
+
+/// A request, sent by the client or (for reverse requests) by the adapter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Request {
+    pub seq: i64,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub arguments: Value,
+}
+
+/// The response to a request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Response {
+    pub seq: i64,
+    pub request_seq: i64,
+    pub success: bool,
+    pub command: String,
+    /// Error message when `success` is false
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub body: Value,
+}
+
+/// An event sent by the adapter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub seq: i64,
+    pub event: String,
+    #[serde(default)]
+    pub body: Value,
+}
+
+/// A source file referenced by a stack frame
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Source {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+/// A frame of a stopped thread's call stack
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackFrame {
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub source: Option<Source>,
+    /// 1-based line number
+    pub line: i64,
+    /// 1-based column number
+    #[serde(default)]
+    pub column: i64,
+}
+
+/// A group of variables in a stack frame (locals, arguments, globals...)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scope {
+    pub name: String,
+    pub variables_reference: i64,
+    /// Whether fetching the scope's variables is expensive (e.g. globals)
+    #[serde(default)]
+    pub expensive: bool,
+}
+
+/// A variable and its value, formatted by the adapter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Variable {
+    pub name: String,
+    pub value: String,
+    #[serde(default, rename = "type")]
+    pub type_name: Option<String>,
+    /// Non-zero if the variable has children
+    #[serde(default)]
+    pub variables_reference: i64,
+}
+
+/// The variables of one scope of a stack frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopeVariables {
+    pub name: String,
+    pub variables: Vec<Variable>,
+}
+
+/// Read one message from the adapter's stdout
+pub async fn read_message<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+) -> Result<ProtocolMessage, String> {
+    let mut content_length: Option<usize> = None;
+
+    loop {
+        let mut line = String::new();
+        let bytes_read = reader
+            .read_line(&mut line)
+            .await
+            .map_err(|e| format!("Failed to read from debug adapter: {}", e))?;
+
+        if bytes_read == 0 {
+            return Err("Debug adapter closed stdout (EOF)".to_string());
+        }
+
+        if line == "\r\n" {
+            break;
+        }
+
+        if let Some(value) = line.strip_prefix("Content-Length: ") {
+            content_length = Some(
+                value
+                    .trim()
+                    .parse()
+                    .map_err(|e| format!("Invalid Content-Length: {}", e))?,
+            );
+        }
+    }
+
+    let content_length =
+        content_length.ok_or_else(|| "Missing Content-Length header".to_string())?;
+
+    let mut content = vec![0u8; content_length];
+    reader
+        .read_exact(&mut content)
+        .await
+        .map_err(|e| format!("Failed to read content: {}", e))?;
+
+    tracing::trace!(
+        "Received DAP message: {}",
+        String::from_utf8_lossy(&content)
+    );
+
+    serde_json::from_slice(&content).map_err(|e| format!("Failed to deserialize message: {}", e))
+}
+
+/// Write one message to the adapter's stdin
+pub async fn write_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &ProtocolMessage,
+) -> Result<(), String> {
+    let json = serde_json::to_string(message)
+        .map_err(|e| format!("Failed to serialize message: {}", e))?;
+
+    tracing::trace!("Sending DAP message: {}", json);
+
+    let framed = format!("Content-Length: {}\r\n\r\n{}", json.len(), json);
+    writer
+        .write_all(framed.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write to debug adapter: {}", e))?;
+    writer
+        .flush()
+        .await
+        .map_err(|e| format!("Failed to flush debug adapter stdin: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn test_message_round_trip() {
+        let request = ProtocolMessage::Request(Request {
+            seq: 1,
+            command: "next".to_string(),
+            arguments: json!({"threadId": 1}),
+        });
+
+        let mut bytes = Vec::new();
+        write_message(&mut bytes, &request).await.unwrap();
+        let text = String::from_utf8(bytes.clone()).unwrap();
+        assert!(text.starts_with("Content-Length: "));
+        assert!(text.contains(r#""type":"request""#));
+
+        let mut reader = BufReader::new(&bytes[..]);
+        match read_message(&mut reader).await.unwrap() {
+            ProtocolMessage::Request(read) => {
+                assert_eq!(read.seq, 1);
+                assert_eq!(read.command, "next");
+                assert_eq!(read.arguments, json!({"threadId": 1}));
+            }
+            other => panic!("Expected a request, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_response_and_event() {
+        let response = r#"{"seq":4,"type":"response","request_seq":2,"success":false,"command":"launch","message":"no program"}"#;
+        let event = r#"{"seq":5,"type":"event","event":"stopped","body":{"reason":"breakpoint","threadId":1}}"#;
+        let bytes = format!(
+            "Content-Length: {}\r\n\r\n{}Content-Length: {}\r\n\r\n{}",
+            response.len(),
+            response,
+            event.len(),
+            event
+        );
+        let mut reader = BufReader::new(bytes.as_bytes());
+
+        match read_message(&mut reader).await.unwrap() {
+            ProtocolMessage::Response(response) => {
+                assert_eq!(response.request_seq, 2);
+                assert!(!response.success);
+                assert_eq!(response.message.as_deref(), Some("no program"));
+            }
+            other => panic!("Expected a response, got {:?}", other),
+        }
+        match read_message(&mut reader).await.unwrap() {
+            ProtocolMessage::Event(event) => {
+                assert_eq!(event.event, "stopped");
+                assert_eq!(event.body["threadId"], 1);
+            }
+            other => panic!("Expected an event, got {:?}", other),
+        }
+        assert!(read_message(&mut reader).await.is_err());
+    }
+
+    #[test]
+    fn test_variable_type_field() {
+        let variable: Variable = serde_json::from_value(
+            json!({"name": "answer", "value": "42", "type": "int", "variablesReference": 0}),
+        )
+        .unwrap();
+        assert_eq!(variable.type_name.as_deref(), Some("int"));
+    }
+}
@@ -0,0 +1,219 @@
+//! External code formatter integration
+//!
+//! Runs the formatter command configured for a language (see
+//! [`FormatterConfig`]) over buffer content and returns the formatted text.
+//! The formatter either reads the content from stdin and writes the result to
+//! stdout, or is pointed at a temporary copy of the file (via `$FILE` in its
+//! arguments) which it rewrites in place.
+
+use crate::config::FormatterConfig;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// How long a formatter may run before it is killed
+const FORMATTER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Placeholder in formatter arguments that is replaced with the file path
+const FILE_PLACEHOLDER: &str = "$FILE";
+
+/// Format `content` with the external formatter described by `config`
+///
+/// `file_path` is the path of the buffer being formatted (if it has one); it
+/// is substituted for `$FILE` in the arguments and determines the extension
+/// of the temporary file used in non-stdin mode, since many formatters pick
+/// their parser from it. The formatter runs in `working_dir` so it can find
+/// project-level configuration files.
+pub fn run_formatter(
+    config: &FormatterConfig,
+    content: &str,
+    file_path: Option<&Path>,
+    working_dir: &Path,
+) -> Result<String, String> {
+    if config.stdin {
+        let args = substitute_file(&config.args, file_path);
+        let stdout = run_command(&config.command, &args, working_dir, Some(content))?;
+        String::from_utf8(stdout)
+            .map_err(|_| format!("'{}' produced invalid UTF-8", config.command))
+    } else {
+        let temp_dir = TempDir::create()?;
+        let file_name = file_path
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_os_string())
+            .unwrap_or_else(|| "untitled".into());
+        let temp_file = temp_dir.path.join(file_name);
+        std::fs::write(&temp_file, content)
+            .map_err(|e| format!("Failed to write temporary file: {}", e))?;
+
+        let args = substitute_file(&config.args, Some(&temp_file));
+        run_command(&config.command, &args, working_dir, None)?;
+        std::fs::read_to_string(&temp_file)
+            .map_err(|e| format!("Failed to read formatted file: {}", e))
+    }
+}
+
+/// Replace `$FILE` in each argument with `file_path`
+fn substitute_file(args: &[String], file_path: Option<&Path>) -> Vec<String> {
+    let file = file_path
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    args.iter()
+        .map(|arg| arg.replace(FILE_PLACEHOLDER, &file))
+        .collect()
+}
+
+/// Run `command`, optionally feeding `stdin`, and return its stdout
+///
+/// Fails if the command cannot be started, exits unsuccessfully, or does not
+/// finish within [`FORMATTER_TIMEOUT`].
+fn run_command(
+    command: &str,
+    args: &[String],
+    working_dir: &Path,
+    stdin: Option<&str>,
+) -> Result<Vec<u8>, String> {
+    let mut child = Command::new(command)
+        .args(args)
+        .current_dir(working_dir)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run '{}': {}", command, e))?;
+
+    // Write stdin and drain stdout/stderr on separate threads so a formatter
+    // that produces output before consuming all input cannot deadlock us
+    let stdin_thread = child.stdin.take().zip(stdin).map(|(mut pipe, input)| {
+        let input = input.to_owned();
+        std::thread::spawn(move || {
+            let _ = pipe.write_all(input.as_bytes());
+        })
+    });
+    let stdout_thread = child.stdout.take().map(|mut pipe| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = pipe.read_to_end(&mut buf);
+            buf
+        })
+    });
+    let stderr_thread = child.stderr.take().map(|mut pipe| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = pipe.read_to_end(&mut buf);
+            buf
+        })
+    });
+
+    let deadline = Instant::now() + FORMATTER_TIMEOUT;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "'{}' timed out after {}s",
+                    command,
+                    FORMATTER_TIMEOUT.as_secs()
+                ));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+            Err(e) => return Err(format!("Failed to wait for '{}': {}", command, e)),
+        }
+    };
+
+    if let Some(thread) = stdin_thread {
+        let _ = thread.join();
+    }
+    let stdout = stdout_thread
+        .and_then(|t| t.join().ok())
+        .unwrap_or_default();
+    let stderr = stderr_thread
+        .and_then(|t| t.join().ok())
+        .unwrap_or_default();
+
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        let first_line = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+        return Err(format!(
+            "'{}' failed ({}): {}",
+            command,
+            status,
+            first_line.trim()
+        ));
+    }
+
+    Ok(stdout)
+}
+
+/// Uniquely named temporary directory, removed on drop
+struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    fn create() -> Result<Self, String> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "fresh-format-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path)
+            .map_err(|e| format!("Failed to create temporary directory: {}", e))?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn formatter(command: &str, args: &[&str], stdin: bool) -> FormatterConfig {
+        FormatterConfig {
+            command: command.to_string(),
+            args: args.iter().map(|s| s.to_string()).collect(),
+            stdin,
+        }
+    }
+
+    #[test]
+    fn test_stdin_formatter() {
+        let config = formatter("tr", &["a-z", "A-Z"], true);
+        let result = run_formatter(&config, "hello\n", None, &std::env::temp_dir());
+        assert_eq!(result.unwrap(), "HELLO\n");
+    }
+
+    #[test]
+    fn test_file_formatter_rewrites_temp_copy() {
+        let config = formatter("sed", &["-i", "s/foo/bar/", "$FILE"], false);
+        let path = Path::new("/nonexistent/dir/main.rs");
+        let result = run_formatter(&config, "foo foo\n", Some(path), &std::env::temp_dir());
+        assert_eq!(result.unwrap(), "bar foo\n");
+    }
+
+    #[test]
+    fn test_failing_formatter_reports_stderr() {
+        let config = formatter("sh", &["-c", "echo 'syntax error' >&2; exit 3"], true);
+        let err = run_formatter(&config, "x", None, &std::env::temp_dir()).unwrap_err();
+        assert!(err.contains("syntax error"), "{}", err);
+    }
+
+    #[test]
+    fn test_missing_formatter() {
+        let config = formatter("fresh-no-such-formatter", &[], true);
+        assert!(run_formatter(&config, "x", None, &std::env::temp_dir()).is_err());
+    }
+}
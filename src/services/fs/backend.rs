@@ -1,10 +1,11 @@
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use std::io;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 /// Represents a file or directory entry
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FsEntry {
     pub path: PathBuf,
     pub name: String,
@@ -41,7 +42,7 @@ impl FsEntry {
 }
 
 /// Type of filesystem entry
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum FsEntryType {
     File,
     Directory,
@@ -49,7 +50,7 @@ pub enum FsEntryType {
 }
 
 /// Metadata about a filesystem entry
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FsMetadata {
     pub size: Option<u64>,
     pub modified: Option<SystemTime>,
@@ -99,6 +100,13 @@ impl Default for FsMetadata {
 /// This trait abstracts filesystem operations to support different backends
 /// (local fs, network fs, virtual fs, etc.) with async operations suitable
 /// for slow/network filesystems.
+///
+/// Buffers load and save synchronously, so the editor also drives these
+/// futures from synchronous code through [`FsManager::block_on`]. Futures
+/// that need a Tokio reactor (timers, sockets) are only guaranteed one when
+/// the manager was given a runtime handle.
+///
+/// [`FsManager::block_on`]: super::FsManager::block_on
 #[async_trait]
 pub trait FsBackend: Send + Sync {
     /// List entries in a directory (non-recursive)
@@ -136,6 +144,85 @@ pub trait FsBackend: Send + Sync {
 
     /// Get canonical (absolute, normalized) path
     async fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Read the entire contents of a file
+    async fn read_file(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Read exactly `len` bytes of a file starting at `offset`
+    ///
+    /// Used to lazily load parts of large files. Fails with
+    /// `UnexpectedEof` if the file is shorter than the requested range.
+    async fn read_range(&self, path: &Path, offset: u64, len: usize) -> io::Result<Vec<u8>>;
+
+    /// Write `contents` to a file, creating it or replacing its contents
+    async fn write_file(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+
+    /// Append `contents` to the end of an existing file
+    async fn append_file(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+
+    /// Create a file (or empty an existing one) and open it for writing
+    ///
+    /// Large files are saved by writing them in pieces, so they never have to
+    /// be held in memory at once. By default each piece is appended with
+    /// [`FsBackend::append_file`]; backends that can keep a file open should
+    /// return a handle writing to it directly.
+    async fn create_file<'a>(&'a self, path: &'a Path) -> io::Result<Box<dyn FsFileWrite + 'a>> {
+        self.write_file(path, &[]).await?;
+        Ok(Box::new(AppendingWriter {
+            backend: self,
+            path,
+        }))
+    }
+
+    /// Flush a file's written contents to stable storage
+    async fn sync_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Give `to` the permissions of `from` (and its owner, where supported)
+    ///
+    /// Saves write a temporary file and rename it over the original; this
+    /// keeps the original's permissions on the new file.
+    async fn copy_permissions(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Rename a file or directory, replacing `to` if it is a file
+    async fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Remove a file
+    async fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Remove a directory and everything in it
+    async fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// Create a directory; its parent must exist
+    async fn create_dir(&self, path: &Path) -> io::Result<()>;
+
+    /// Create a directory and any missing parents
+    async fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// Target of `path` if it is a symbolic link, `None` if it is anything else
+    async fn read_link(&self, path: &Path) -> io::Result<Option<PathBuf>>;
+
+    /// Create a symbolic link at `link` pointing to `target`
+    async fn symlink(&self, target: &Path, link: &Path) -> io::Result<()>;
+}
+
+/// A file opened for writing by [`FsBackend::create_file`]
+#[async_trait]
+pub trait FsFileWrite: Send {
+    /// Write all of `contents` after what was written so far
+    async fn write_all(&mut self, contents: &[u8]) -> io::Result<()>;
+}
+
+/// Writer appending each piece to the file, for backends without file handles
+struct AppendingWriter<'a, B: ?Sized> {
+    backend: &'a B,
+    path: &'a Path,
+}
+
+#[async_trait]
+impl<B: FsBackend + ?Sized> FsFileWrite for AppendingWriter<'_, B> {
+    async fn write_all(&mut self, contents: &[u8]) -> io::Result<()> {
+        self.backend.append_file(self.path, contents).await
+    }
 }
 
 #[cfg(test)]
@@ -1,7 +1,7 @@
-use super::backend::{FsBackend, FsEntry, FsEntryType, FsMetadata};
+use super::backend::{FsBackend, FsEntry, FsEntryType, FsFileWrite, FsMetadata};
 use async_trait::async_trait;
 use lru::LruCache;
-use std::io;
+use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -96,6 +96,11 @@ impl LocalFsBackend {
         Ok(metadata)
     }
 
+    /// Drop a path's cached metadata after it was changed
+    async fn invalidate(&self, path: &Path) {
+        self.metadata_cache.write().await.pop(path);
+    }
+
     /// Determine entry type from metadata
     fn entry_type_from_metadata(metadata: &std::fs::Metadata) -> FsEntryType {
         if metadata.is_symlink() {
@@ -169,17 +174,26 @@ impl FsBackend for LocalFsBackend {
         results
     }
 
+    // Single-path lookups and file content operations go through `blocking`
+    // rather than tokio::fs: they are also driven from the editor's
+    // synchronous buffer code, which has no runtime.
+
     async fn exists(&self, path: &Path) -> bool {
-        fs::try_exists(path).await.unwrap_or(false)
+        let path = path.to_path_buf();
+        blocking(move || path.try_exists()).await.unwrap_or(false)
     }
 
     async fn is_dir(&self, path: &Path) -> io::Result<bool> {
-        let metadata = fs::metadata(path).await?;
+        let path = path.to_path_buf();
+        let metadata = blocking(move || std::fs::metadata(path)).await?;
         Ok(metadata.is_dir())
     }
 
     async fn get_entry(&self, path: &Path) -> io::Result<FsEntry> {
-        let metadata = fs::metadata(path).await?;
+        let metadata = {
+            let path = path.to_path_buf();
+            blocking(move || std::fs::metadata(path)).await?
+        };
         let name = path
             .file_name()
             .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid path"))?
@@ -199,10 +213,233 @@ impl FsBackend for LocalFsBackend {
     }
 
     async fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
-        fs::canonicalize(path).await
+        let path = path.to_path_buf();
+        blocking(move || std::fs::canonicalize(path)).await
+    }
+
+    async fn read_file(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let path = path.to_path_buf();
+        blocking(move || std::fs::read(path)).await
+    }
+
+    async fn read_range(&self, path: &Path, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let path = path.to_path_buf();
+        blocking(move || {
+            let mut file = std::fs::File::open(path)?;
+            file.seek(SeekFrom::Start(offset))?;
+            let mut buffer = vec![0u8; len];
+            file.read_exact(&mut buffer)?;
+            Ok(buffer)
+        })
+        .await
+    }
+
+    async fn write_file(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        {
+            let path = path.to_path_buf();
+            let contents = contents.to_vec();
+            blocking(move || std::fs::write(path, contents)).await?;
+        }
+        self.invalidate(path).await;
+        Ok(())
+    }
+
+    async fn append_file(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        {
+            let path = path.to_path_buf();
+            let contents = contents.to_vec();
+            blocking(move || {
+                std::fs::OpenOptions::new()
+                    .append(true)
+                    .open(path)?
+                    .write_all(&contents)
+            })
+            .await?;
+        }
+        self.invalidate(path).await;
+        Ok(())
+    }
+
+    async fn create_file<'a>(&'a self, path: &'a Path) -> io::Result<Box<dyn FsFileWrite + 'a>> {
+        let file = {
+            let path = path.to_path_buf();
+            blocking(move || std::fs::File::create(path)).await?
+        };
+        self.invalidate(path).await;
+        Ok(Box::new(LocalFileWriter { file: Some(file) }))
+    }
+
+    async fn sync_file(&self, path: &Path) -> io::Result<()> {
+        let path = path.to_path_buf();
+        blocking(move || {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .open(path)?
+                .sync_all()
+        })
+        .await
+    }
+
+    async fn copy_permissions(&self, from: &Path, to: &Path) -> io::Result<()> {
+        {
+            let from = from.to_path_buf();
+            let to = to.to_path_buf();
+            blocking(move || copy_permissions(&from, &to)).await?;
+        }
+        self.invalidate(to).await;
+        Ok(())
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        {
+            let from = from.to_path_buf();
+            let to = to.to_path_buf();
+            blocking(move || std::fs::rename(from, to)).await?;
+        }
+        self.invalidate(from).await;
+        self.invalidate(to).await;
+        Ok(())
+    }
+
+    async fn remove_file(&self, path: &Path) -> io::Result<()> {
+        {
+            let path = path.to_path_buf();
+            blocking(move || std::fs::remove_file(path)).await?;
+        }
+        self.invalidate(path).await;
+        Ok(())
+    }
+
+    async fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        {
+            let path = path.to_path_buf();
+            blocking(move || std::fs::remove_dir_all(path)).await?;
+        }
+        self.invalidate(path).await;
+        Ok(())
+    }
+
+    async fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let path = path.to_path_buf();
+        blocking(move || std::fs::create_dir(path)).await
+    }
+
+    async fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let path = path.to_path_buf();
+        blocking(move || std::fs::create_dir_all(path)).await
+    }
+
+    async fn read_link(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+        let path = path.to_path_buf();
+        blocking(move || {
+            if !path.symlink_metadata()?.is_symlink() {
+                return Ok(None);
+            }
+            std::fs::read_link(path).map(Some)
+        })
+        .await
+    }
+
+    async fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        let target = target.to_path_buf();
+        let link = link.to_path_buf();
+        blocking(move || create_symlink(&target, &link)).await
+    }
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn create_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    // Windows has distinct file and directory links; a relative target is
+    // relative to the link's directory
+    let resolved = match link.parent() {
+        Some(parent) => parent.join(target),
+        None => target.to_path_buf(),
+    };
+    if resolved.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn create_symlink(_target: &Path, _link: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Symbolic links are not supported on this platform",
+    ))
+}
+
+/// A local file kept open while it is written
+struct LocalFileWriter {
+    /// The file, moved out while a write is running (and dropped if the
+    /// write failed)
+    file: Option<std::fs::File>,
+}
+
+#[async_trait]
+impl FsFileWrite for LocalFileWriter {
+    async fn write_all(&mut self, contents: &[u8]) -> io::Result<()> {
+        let mut file = self
+            .file
+            .take()
+            .ok_or_else(|| io::Error::other("a previous write to the file failed"))?;
+        let contents = contents.to_vec();
+        let file = blocking(move || file.write_all(&contents).map(|()| file)).await?;
+        self.file = Some(file);
+        Ok(())
     }
 }
 
+/// Run a blocking filesystem operation
+///
+/// Inside a Tokio runtime it runs on the runtime's blocking thread pool, so
+/// it doesn't hold up the tasks of the runtime's workers. Without one (when
+/// the editor drives the backend from synchronous code through
+/// [`FsManager::block_on`](super::FsManager::block_on)) it runs in place.
+async fn blocking<T, F>(operation: F) -> io::Result<T>
+where
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    match tokio::runtime::Handle::try_current() {
+        Ok(runtime) => runtime
+            .spawn_blocking(operation)
+            .await
+            .map_err(io::Error::other)?,
+        Err(_) => operation(),
+    }
+}
+
+/// Give `to` the permissions of `from`, and on Unix its owner and group
+fn copy_permissions(from: &Path, to: &Path) -> io::Result<()> {
+    let original_meta = std::fs::metadata(from)?;
+    std::fs::set_permissions(to, original_meta.permissions())?;
+
+    // On Unix, also restore owner and group
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let uid = original_meta.uid();
+        let gid = original_meta.gid();
+        // Use libc to set owner/group - ignore errors since we may not have permission
+        // (e.g., only root can chown to a different user)
+        unsafe {
+            use std::os::unix::ffi::OsStrExt;
+            let c_path = std::ffi::CString::new(to.as_os_str().as_bytes())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            libc::chown(c_path.as_ptr(), uid, gid);
+        }
+    }
+
+    Ok(())
+}
+
 // Clone implementation for LocalFsBackend to enable parallel operations
 impl Clone for LocalFsBackend {
     fn clone(&self) -> Self {
@@ -399,6 +636,101 @@ mod tests {
         assert!(duration.as_secs() < 1);
     }
 
+    #[tokio::test]
+    async fn test_write_and_read_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+
+        let backend = LocalFsBackend::new();
+        backend.write_file(&file_path, b"hello").await.unwrap();
+        backend.append_file(&file_path, b" world").await.unwrap();
+
+        assert_eq!(backend.read_file(&file_path).await.unwrap(), b"hello world");
+        assert_eq!(
+            backend.read_range(&file_path, 6, 5).await.unwrap(),
+            b"world"
+        );
+        assert!(backend.read_range(&file_path, 6, 10).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_file_writes_in_pieces() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        std_fs::write(&file_path, "old content").unwrap();
+
+        let backend = LocalFsBackend::new();
+        let mut file = backend.create_file(&file_path).await.unwrap();
+        assert_eq!(std_fs::read(&file_path).unwrap(), b"");
+        file.write_all(b"hello").await.unwrap();
+        file.write_all(b" world").await.unwrap();
+        drop(file);
+
+        assert_eq!(std_fs::read(&file_path).unwrap(), b"hello world");
+    }
+
+    #[test]
+    fn test_operations_without_runtime() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+
+        let backend = LocalFsBackend::new();
+        futures::executor::block_on(async {
+            let mut file = backend.create_file(&file_path).await.unwrap();
+            file.write_all(b"content").await.unwrap();
+            drop(file);
+            assert!(backend.exists(&file_path).await);
+            assert_eq!(backend.read_file(&file_path).await.unwrap(), b"content");
+        });
+    }
+
+    #[tokio::test]
+    async fn test_write_invalidates_cached_metadata() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        std_fs::write(&file_path, "content").unwrap();
+
+        let backend = LocalFsBackend::with_cache_settings(10, Duration::from_secs(10));
+        assert_eq!(
+            backend.read_metadata(&file_path).await.unwrap().size,
+            Some(7)
+        );
+
+        backend
+            .write_file(&file_path, b"new content")
+            .await
+            .unwrap();
+        assert_eq!(
+            backend.read_metadata(&file_path).await.unwrap().size,
+            Some(11)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rename_remove_and_create_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+        let backend = LocalFsBackend::new();
+
+        let dir_path = temp_path.join("a/b");
+        backend.create_dir_all(&dir_path).await.unwrap();
+        backend.create_dir(&temp_path.join("c")).await.unwrap();
+        assert!(backend.create_dir(&temp_path.join("d/e")).await.is_err());
+
+        let file_path = dir_path.join("file.txt");
+        let renamed_path = temp_path.join("c/renamed.txt");
+        backend.write_file(&file_path, b"content").await.unwrap();
+        backend.rename(&file_path, &renamed_path).await.unwrap();
+        assert!(!backend.exists(&file_path).await);
+        assert_eq!(std_fs::read(&renamed_path).unwrap(), b"content");
+
+        backend.remove_file(&renamed_path).await.unwrap();
+        assert!(!backend.exists(&renamed_path).await);
+
+        backend.remove_dir_all(&temp_path.join("a")).await.unwrap();
+        assert!(!backend.exists(&temp_path.join("a")).await);
+    }
+
     #[test]
     fn test_is_hidden_file() {
         assert!(is_hidden_file(Path::new(".hidden")));
@@ -1,9 +1,12 @@
-use super::backend::{FsBackend, FsEntry, FsMetadata};
+use super::backend::{FsBackend, FsEntry, FsFileWrite, FsMetadata};
+use super::local::LocalFsBackend;
 use std::collections::HashMap;
 use std::fmt;
+use std::future::Future;
 use std::io;
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use tokio::runtime::Handle;
 use tokio::sync::{oneshot, Mutex};
 
 /// Manages filesystem operations with request batching and deduplication
@@ -13,12 +16,17 @@ use tokio::sync::{oneshot, Mutex};
 /// - Request deduplication (multiple requests for the same path)
 /// - Batching of metadata requests
 /// - Centralized error handling
+///
+/// All file access of the editor - buffer loading and saving, recovery and
+/// the file explorer - goes through a manager, so any backend can serve it.
 pub struct FsManager {
     backend: Arc<dyn FsBackend>,
     /// Pending directory listing requests
     /// Map of path -> list of channels waiting for the result
     pending_dir_requests:
         Arc<Mutex<HashMap<PathBuf, Vec<oneshot::Sender<io::Result<Vec<FsEntry>>>>>>>,
+    /// Runtime that drives backend futures run from synchronous code
+    runtime: Option<Handle>,
 }
 
 impl fmt::Debug for FsManager {
@@ -26,6 +34,7 @@ impl fmt::Debug for FsManager {
         f.debug_struct("FsManager")
             .field("backend", &"<dyn FsBackend>")
             .field("pending_dir_requests", &"<mutex>")
+            .field("runtime", &self.runtime.is_some())
             .finish()
     }
 }
@@ -36,6 +45,35 @@ impl FsManager {
         Self {
             backend,
             pending_dir_requests: Arc::new(Mutex::new(HashMap::new())),
+            runtime: None,
+        }
+    }
+
+    /// Drive futures run through [`FsManager::block_on`] on `runtime`
+    ///
+    /// Needed for backends whose operations use Tokio timers or sockets.
+    pub fn with_runtime(mut self, runtime: Handle) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Shared manager for the local filesystem, used by buffers that weren't
+    /// given one
+    pub fn local() -> Arc<FsManager> {
+        static LOCAL: OnceLock<Arc<FsManager>> = OnceLock::new();
+        LOCAL
+            .get_or_init(|| Arc::new(FsManager::new(Arc::new(LocalFsBackend::new()))))
+            .clone()
+    }
+
+    /// Run a filesystem operation to completion from synchronous code
+    ///
+    /// The future is driven on the manager's runtime if it has one, and on
+    /// the calling thread otherwise. Must not be called from async code.
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        match &self.runtime {
+            Some(runtime) => runtime.block_on(future),
+            None => futures::executor::block_on(future),
         }
     }
 
@@ -97,8 +135,8 @@ impl FsManager {
     }
 
     /// Get metadata for a single path
-    pub async fn get_single_metadata(&self, path: &PathBuf) -> io::Result<FsMetadata> {
-        let results = self.backend.get_metadata_batch(&[path.clone()]).await;
+    pub async fn get_single_metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        let results = self.backend.get_metadata_batch(&[path.to_path_buf()]).await;
         results
             .into_iter()
             .next()
@@ -106,25 +144,168 @@ impl FsManager {
     }
 
     /// Check if a path exists
-    pub async fn exists(&self, path: &PathBuf) -> bool {
+    pub async fn exists(&self, path: &Path) -> bool {
         self.backend.exists(path).await
     }
 
     /// Check if a path is a directory
-    pub async fn is_dir(&self, path: &PathBuf) -> io::Result<bool> {
+    pub async fn is_dir(&self, path: &Path) -> io::Result<bool> {
         self.backend.is_dir(path).await
     }
 
     /// Get a complete entry for a path (with metadata)
-    pub async fn get_entry(&self, path: &PathBuf) -> io::Result<FsEntry> {
+    pub async fn get_entry(&self, path: &Path) -> io::Result<FsEntry> {
         self.backend.get_entry(path).await
     }
 
     /// Get canonical path
-    pub async fn canonicalize(&self, path: &PathBuf) -> io::Result<PathBuf> {
+    pub async fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
         self.backend.canonicalize(path).await
     }
 
+    /// Read the entire contents of a file
+    pub async fn read_file(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.backend.read_file(path).await
+    }
+
+    /// Read `len` bytes of a file starting at `offset`
+    pub async fn read_range(&self, path: &Path, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        self.backend.read_range(path, offset, len).await
+    }
+
+    /// Create or replace a file with `contents`
+    pub async fn write_file(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.backend.write_file(path, contents).await
+    }
+
+    /// Append `contents` to an existing file
+    pub async fn append_file(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.backend.append_file(path, contents).await
+    }
+
+    /// Create or empty a file and open it for writing in pieces
+    pub async fn create_file<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> io::Result<Box<dyn FsFileWrite + 'a>> {
+        self.backend.create_file(path).await
+    }
+
+    /// Flush a file's contents to stable storage
+    pub async fn sync_file(&self, path: &Path) -> io::Result<()> {
+        self.backend.sync_file(path).await
+    }
+
+    /// Give `to` the permissions (and owner, where supported) of `from`
+    pub async fn copy_permissions(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.backend.copy_permissions(from, to).await
+    }
+
+    /// Rename a file or directory
+    pub async fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.backend.rename(from, to).await
+    }
+
+    /// Remove a file
+    pub async fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.backend.remove_file(path).await
+    }
+
+    /// Remove a directory and its contents
+    pub async fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.backend.remove_dir_all(path).await
+    }
+
+    /// Create a directory
+    pub async fn create_dir(&self, path: &Path) -> io::Result<()> {
+        self.backend.create_dir(path).await
+    }
+
+    /// Create a directory and any missing parents
+    pub async fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.backend.create_dir_all(path).await
+    }
+
+    /// Target of `path` if it is a symbolic link, `None` if it is anything else
+    pub async fn read_link(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+        self.backend.read_link(path).await
+    }
+
+    /// Create a symbolic link at `link` pointing to `target`
+    pub async fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        self.backend.symlink(target, link).await
+    }
+
+    /// Copy a file, or a directory and everything in it
+    ///
+    /// Symbolic links are recreated as links, not followed. If the copy fails
+    /// halfway, the part of `to` it created is removed again.
+    pub async fn copy_recursive(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let existed = self.exists(to).await;
+        let result = self.copy_tree(from, to).await;
+        if result.is_err() && !existed {
+            let is_dir = matches!(self.read_link(to).await, Ok(None))
+                && self.is_dir(to).await.unwrap_or(false);
+            let _ = if is_dir {
+                self.remove_dir_all(to).await
+            } else {
+                self.remove_file(to).await
+            };
+        }
+        result
+    }
+
+    async fn copy_tree(&self, from: &Path, to: &Path) -> io::Result<()> {
+        if let Some(target) = self.read_link(from).await? {
+            return self.symlink(&target, to).await;
+        }
+        if !self.is_dir(from).await? {
+            return self.copy_file(from, to).await;
+        }
+
+        let mut pending = vec![(from.to_path_buf(), to.to_path_buf())];
+        while let Some((from_dir, to_dir)) = pending.pop() {
+            self.create_dir(&to_dir).await?;
+            for entry in self.list_dir(from_dir).await? {
+                let target = to_dir.join(&entry.name);
+                if entry.is_symlink() {
+                    match self.read_link(&entry.path).await? {
+                        Some(link) => self.symlink(&link, &target).await?,
+                        None => self.copy_file(&entry.path, &target).await?,
+                    }
+                } else if entry.is_dir() {
+                    pending.push((entry.path, target));
+                } else {
+                    self.copy_file(&entry.path, &target).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn copy_file(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let contents = self.read_file(from).await?;
+        self.write_file(to, &contents).await?;
+        // Permissions are best effort, not every backend supports them
+        let _ = self.copy_permissions(from, to).await;
+        Ok(())
+    }
+
+    /// Move a file or directory, copying it when it is on another filesystem
+    pub async fn move_path(&self, from: &Path, to: &Path) -> io::Result<()> {
+        match self.rename(from, to).await {
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+                self.copy_recursive(from, to).await?;
+                if self.is_dir(from).await? {
+                    self.remove_dir_all(from).await
+                } else {
+                    self.remove_file(from).await
+                }
+            }
+            result => result,
+        }
+    }
+
     /// List directory and fetch metadata for all entries in parallel
     ///
     /// This is a convenience method that combines `list_dir` with
@@ -159,6 +340,7 @@ impl Clone for FsManager {
         Self {
             backend: Arc::clone(&self.backend),
             pending_dir_requests: Arc::clone(&self.pending_dir_requests),
+            runtime: self.runtime.clone(),
         }
     }
 }
@@ -346,6 +528,45 @@ mod tests {
         assert_eq!(file1.metadata.as_ref().unwrap().size, Some(8));
     }
 
+    #[test]
+    fn test_block_on_without_runtime() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+
+        let manager = FsManager::new(Arc::new(LocalFsBackend::new()));
+        manager
+            .block_on(manager.write_file(&file_path, b"content"))
+            .unwrap();
+        assert_eq!(
+            manager.block_on(manager.read_file(&file_path)).unwrap(),
+            b"content"
+        );
+    }
+
+    #[test]
+    fn test_block_on_with_runtime() {
+        use crate::services::fs::SlowFsBackend;
+        use std::time::Duration;
+
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        std_fs::write(&file_path, "content").unwrap();
+
+        // The slow backend sleeps on Tokio timers, which need the runtime
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let backend = Arc::new(SlowFsBackend::with_uniform_delay(
+            Arc::new(LocalFsBackend::new()),
+            Duration::from_millis(10),
+        ));
+        let manager = FsManager::new(backend).with_runtime(runtime.handle().clone());
+        assert_eq!(
+            manager
+                .block_on(manager.read_range(&file_path, 3, 4))
+                .unwrap(),
+            b"tent"
+        );
+    }
+
     #[tokio::test]
     async fn test_concurrent_different_dirs() {
         let temp_dir = TempDir::new().unwrap();
@@ -381,4 +602,87 @@ mod tests {
             assert_eq!(result.len(), 3);
         }
     }
+
+    #[tokio::test]
+    async fn test_copy_recursive() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+        std_fs::create_dir_all(temp_path.join("src/nested")).unwrap();
+        std_fs::write(temp_path.join("src/a.txt"), "a").unwrap();
+        std_fs::write(temp_path.join("src/nested/b.txt"), "b").unwrap();
+
+        let backend = Arc::new(LocalFsBackend::new());
+        let manager = FsManager::new(backend);
+
+        manager
+            .copy_recursive(&temp_path.join("src"), &temp_path.join("dst"))
+            .await
+            .unwrap();
+        assert_eq!(
+            std_fs::read_to_string(temp_path.join("dst/a.txt")).unwrap(),
+            "a"
+        );
+        assert_eq!(
+            std_fs::read_to_string(temp_path.join("dst/nested/b.txt")).unwrap(),
+            "b"
+        );
+        assert!(temp_path.join("src/nested/b.txt").exists());
+
+        manager
+            .copy_recursive(&temp_path.join("src/a.txt"), &temp_path.join("c.txt"))
+            .await
+            .unwrap();
+        assert_eq!(
+            std_fs::read_to_string(temp_path.join("c.txt")).unwrap(),
+            "a"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_copy_recursive_keeps_symlinks_and_cleans_up() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+        std_fs::create_dir_all(temp_path.join("src/nested")).unwrap();
+        std_fs::write(temp_path.join("src/a.txt"), "a").unwrap();
+        symlink("a.txt", temp_path.join("src/file_link")).unwrap();
+        symlink("nested", temp_path.join("src/dir_link")).unwrap();
+
+        let manager = FsManager::new(Arc::new(LocalFsBackend::new()));
+        manager
+            .copy_recursive(&temp_path.join("src"), &temp_path.join("dst"))
+            .await
+            .unwrap();
+        assert_eq!(
+            std_fs::read_link(temp_path.join("dst/file_link")).unwrap(),
+            Path::new("a.txt")
+        );
+        assert_eq!(
+            std_fs::read_link(temp_path.join("dst/dir_link")).unwrap(),
+            Path::new("nested")
+        );
+
+        manager
+            .copy_recursive(
+                &temp_path.join("src/dir_link"),
+                &temp_path.join("src/dir_link copy"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            std_fs::read_link(temp_path.join("src/dir_link copy")).unwrap(),
+            Path::new("nested")
+        );
+
+        // A socket can't be read, so the copy fails halfway and is removed
+        let _socket =
+            std::os::unix::net::UnixListener::bind(temp_path.join("src/nested/socket")).unwrap();
+        assert!(manager
+            .copy_recursive(&temp_path.join("src"), &temp_path.join("partial"))
+            .await
+            .is_err());
+        assert!(!temp_path.join("partial").exists());
+    }
 }
@@ -8,7 +8,7 @@ pub mod local;
 pub mod manager;
 pub mod slow;
 
-pub use backend::{FsBackend, FsEntry, FsEntryType, FsMetadata};
+pub use backend::{FsBackend, FsEntry, FsEntryType, FsFileWrite, FsMetadata};
 pub use local::LocalFsBackend;
 pub use manager::FsManager;
 pub use slow::{BackendMetrics, SlowFsBackend, SlowFsConfig};
@@ -28,6 +28,16 @@ pub struct SlowFsConfig {
     pub get_entry_delay: Duration,
     /// Delay for canonicalize operations
     pub canonicalize_delay: Duration,
+    /// Delay for read_file and read_range operations
+    pub read_file_delay: Duration,
+    /// Delay for write_file, append_file, sync_file and copy_permissions operations
+    pub write_file_delay: Duration,
+    /// Delay for rename operations
+    pub rename_delay: Duration,
+    /// Delay for remove_file and remove_dir_all operations
+    pub remove_delay: Duration,
+    /// Delay for create_dir and create_dir_all operations
+    pub create_dir_delay: Duration,
 }
 
 impl SlowFsConfig {
@@ -40,6 +50,11 @@ impl SlowFsConfig {
             is_dir_delay: delay,
             get_entry_delay: delay,
             canonicalize_delay: delay,
+            read_file_delay: delay,
+            write_file_delay: delay,
+            rename_delay: delay,
+            remove_delay: delay,
+            create_dir_delay: delay,
         }
     }
 
@@ -57,6 +72,11 @@ impl SlowFsConfig {
             is_dir_delay: Duration::from_millis(30),
             get_entry_delay: Duration::from_millis(100),
             canonicalize_delay: Duration::from_millis(50),
+            read_file_delay: Duration::from_millis(200),
+            write_file_delay: Duration::from_millis(200),
+            rename_delay: Duration::from_millis(50),
+            remove_delay: Duration::from_millis(50),
+            create_dir_delay: Duration::from_millis(50),
         }
     }
 
@@ -69,6 +89,11 @@ impl SlowFsConfig {
             is_dir_delay: Duration::from_millis(10),
             get_entry_delay: Duration::from_millis(50),
             canonicalize_delay: Duration::from_millis(20),
+            read_file_delay: Duration::from_millis(50),
+            write_file_delay: Duration::from_millis(50),
+            rename_delay: Duration::from_millis(20),
+            remove_delay: Duration::from_millis(20),
+            create_dir_delay: Duration::from_millis(20),
         }
     }
 }
@@ -96,6 +121,16 @@ pub struct BackendMetrics {
     pub get_entry_calls: usize,
     /// Number of canonicalize calls
     pub canonicalize_calls: usize,
+    /// Number of read_file and read_range calls
+    pub read_file_calls: usize,
+    /// Number of write_file, append_file, sync_file and copy_permissions calls
+    pub write_file_calls: usize,
+    /// Number of rename calls
+    pub rename_calls: usize,
+    /// Number of remove_file and remove_dir_all calls
+    pub remove_calls: usize,
+    /// Number of create_dir and create_dir_all calls
+    pub create_dir_calls: usize,
     /// Total time spent in artificial delays
     pub total_delay_time: Duration,
 }
@@ -119,6 +154,11 @@ impl BackendMetrics {
             + self.is_dir_calls
             + self.get_entry_calls
             + self.canonicalize_calls
+            + self.read_file_calls
+            + self.write_file_calls
+            + self.rename_calls
+            + self.remove_calls
+            + self.create_dir_calls
     }
 }
 
@@ -218,6 +258,84 @@ impl FsBackend for SlowFsBackend {
         self.metrics.lock().await.canonicalize_calls += 1;
         self.inner.canonicalize(path).await
     }
+
+    async fn read_file(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.add_delay(self.config.read_file_delay).await;
+        self.metrics.lock().await.read_file_calls += 1;
+        self.inner.read_file(path).await
+    }
+
+    async fn read_range(&self, path: &Path, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        self.add_delay(self.config.read_file_delay).await;
+        self.metrics.lock().await.read_file_calls += 1;
+        self.inner.read_range(path, offset, len).await
+    }
+
+    async fn write_file(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.add_delay(self.config.write_file_delay).await;
+        self.metrics.lock().await.write_file_calls += 1;
+        self.inner.write_file(path, contents).await
+    }
+
+    async fn append_file(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.add_delay(self.config.write_file_delay).await;
+        self.metrics.lock().await.write_file_calls += 1;
+        self.inner.append_file(path, contents).await
+    }
+
+    async fn sync_file(&self, path: &Path) -> io::Result<()> {
+        self.add_delay(self.config.write_file_delay).await;
+        self.metrics.lock().await.write_file_calls += 1;
+        self.inner.sync_file(path).await
+    }
+
+    async fn copy_permissions(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.add_delay(self.config.write_file_delay).await;
+        self.metrics.lock().await.write_file_calls += 1;
+        self.inner.copy_permissions(from, to).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.add_delay(self.config.rename_delay).await;
+        self.metrics.lock().await.rename_calls += 1;
+        self.inner.rename(from, to).await
+    }
+
+    async fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.add_delay(self.config.remove_delay).await;
+        self.metrics.lock().await.remove_calls += 1;
+        self.inner.remove_file(path).await
+    }
+
+    async fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.add_delay(self.config.remove_delay).await;
+        self.metrics.lock().await.remove_calls += 1;
+        self.inner.remove_dir_all(path).await
+    }
+
+    async fn create_dir(&self, path: &Path) -> io::Result<()> {
+        self.add_delay(self.config.create_dir_delay).await;
+        self.metrics.lock().await.create_dir_calls += 1;
+        self.inner.create_dir(path).await
+    }
+
+    async fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.add_delay(self.config.create_dir_delay).await;
+        self.metrics.lock().await.create_dir_calls += 1;
+        self.inner.create_dir_all(path).await
+    }
+
+    async fn read_link(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+        self.add_delay(self.config.get_entry_delay).await;
+        self.metrics.lock().await.get_entry_calls += 1;
+        self.inner.read_link(path).await
+    }
+
+    async fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        self.add_delay(self.config.write_file_delay).await;
+        self.metrics.lock().await.write_file_calls += 1;
+        self.inner.symlink(target, link).await
+    }
 }
 
 #[cfg(test)]
@@ -307,6 +425,35 @@ mod tests {
         assert_eq!(metrics.metadata_items, 2);
     }
 
+    #[tokio::test]
+    async fn test_write_operations_tracked() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+        let file_path = temp_path.join("file.txt");
+
+        let local = Arc::new(LocalFsBackend::new());
+        let slow_config = SlowFsConfig {
+            write_file_delay: Duration::from_millis(50),
+            ..SlowFsConfig::none()
+        };
+        let slow = SlowFsBackend::new(local, slow_config);
+
+        let start = Instant::now();
+        slow.write_file(&file_path, b"test").await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        slow.rename(&file_path, &temp_path.join("renamed.txt"))
+            .await
+            .unwrap();
+        let _ = slow.read_file(&temp_path.join("renamed.txt")).await;
+
+        let metrics = slow.metrics().await;
+        assert_eq!(metrics.write_file_calls, 1);
+        assert_eq!(metrics.rename_calls, 1);
+        assert_eq!(metrics.read_file_calls, 1);
+        assert_eq!(metrics.total_calls(), 3);
+    }
+
     #[tokio::test]
     async fn test_reset_metrics() {
         let temp_dir = TempDir::new().unwrap();
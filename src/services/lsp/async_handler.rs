@@ -15,17 +15,18 @@ use crate::services::async_bridge::{
     AsyncBridge, AsyncMessage, LspMessageType, LspProgressValue, LspServerStatus,
 };
 use crate::services::process_limits::ProcessLimits;
+use crate::services::remote::{RemoteChild, RemoteConnection};
 use lsp_types::{
     notification::{
-        DidChangeTextDocument, DidOpenTextDocument, DidSaveTextDocument, Initialized, Notification,
-        PublishDiagnostics,
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidRenameFiles,
+        DidSaveTextDocument, Initialized, Notification, PublishDiagnostics,
     },
-    request::{Initialize, Request, Shutdown},
-    ClientCapabilities, DidChangeTextDocumentParams, DidOpenTextDocumentParams,
-    DidSaveTextDocumentParams, InitializeParams, InitializeResult, InitializedParams,
-    PublishDiagnosticsParams, ServerCapabilities, TextDocumentContentChangeEvent,
-    TextDocumentIdentifier, TextDocumentItem, Uri, VersionedTextDocumentIdentifier,
-    WindowClientCapabilities, WorkspaceFolder,
+    request::{Initialize, Request, Shutdown, WillRenameFiles},
+    ClientCapabilities, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DidSaveTextDocumentParams, FileRename, InitializeParams,
+    InitializeResult, InitializedParams, PublishDiagnosticsParams, RenameFilesParams,
+    ServerCapabilities, TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentItem,
+    Uri, VersionedTextDocumentIdentifier, WindowClientCapabilities, WorkspaceFolder,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -34,8 +35,8 @@ use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{mpsc as std_mpsc, Arc, Mutex};
 use std::time::Instant;
-use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
-use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
 use tokio::sync::{mpsc, oneshot};
 
 /// Grace period after didOpen before sending didChange (in milliseconds)
@@ -199,8 +200,10 @@ impl LspClientState {
 /// Create common LSP client capabilities with workDoneProgress support
 fn create_client_capabilities() -> ClientCapabilities {
     use lsp_types::{
-        GeneralClientCapabilities, RenameClientCapabilities, TextDocumentClientCapabilities,
-        WorkspaceClientCapabilities, WorkspaceEditClientCapabilities,
+        CompletionClientCapabilities, CompletionItemCapability, FoldingRangeClientCapabilities,
+        GeneralClientCapabilities, RenameClientCapabilities, SelectionRangeClientCapabilities,
+        TextDocumentClientCapabilities, WorkspaceClientCapabilities,
+        WorkspaceEditClientCapabilities, WorkspaceFileOperationsClientCapabilities,
     };
 
     ClientCapabilities {
@@ -214,15 +217,33 @@ fn create_client_capabilities() -> ClientCapabilities {
                 document_changes: Some(true),
                 ..Default::default()
             }),
+            file_operations: Some(WorkspaceFileOperationsClientCapabilities {
+                will_rename: Some(true),
+                did_rename: Some(true),
+                ..Default::default()
+            }),
             ..Default::default()
         }),
         text_document: Some(TextDocumentClientCapabilities {
+            completion: Some(CompletionClientCapabilities {
+                completion_item: Some(CompletionItemCapability {
+                    snippet_support: Some(true),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
             rename: Some(RenameClientCapabilities {
                 dynamic_registration: Some(true),
                 prepare_support: Some(true),
                 honors_change_annotations: Some(true),
                 ..Default::default()
             }),
+            // Folds hide whole lines, so column information is not needed
+            folding_range: Some(FoldingRangeClientCapabilities {
+                line_folding_only: Some(true),
+                ..Default::default()
+            }),
+            selection_range: Some(SelectionRangeClientCapabilities::default()),
             ..Default::default()
         }),
         general: Some(GeneralClientCapabilities {
@@ -262,6 +283,18 @@ enum LspCommand {
     /// Notify document saved
     DidSave { uri: Uri, text: Option<String> },
 
+    /// Notify document closed
+    DidClose { uri: Uri },
+
+    /// Request the edits to make before files are renamed
+    WillRenameFiles {
+        request_id: u64,
+        files: Vec<FileRename>,
+    },
+
+    /// Notify files renamed
+    DidRenameFiles { files: Vec<FileRename> },
+
     /// Request completion at position
     Completion {
         request_id: u64,
@@ -287,6 +320,15 @@ enum LspCommand {
         new_name: String,
     },
 
+    /// Request document formatting (or range formatting when `range` is set)
+    Formatting {
+        request_id: u64,
+        uri: Uri,
+        range: Option<lsp_types::Range>,
+        tab_size: u32,
+        insert_spaces: bool,
+    },
+
     /// Request hover documentation
     Hover {
         request_id: u64,
@@ -341,6 +383,16 @@ enum LspCommand {
         end_char: u32,
     },
 
+    /// Request folding ranges for a document
+    FoldingRange { request_id: u64, uri: Uri },
+
+    /// Request selection ranges (for expand selection) at positions in a document
+    SelectionRange {
+        request_id: u64,
+        uri: Uri,
+        positions: Vec<lsp_types::Position>,
+    },
+
     /// Cancel a pending request
     CancelRequest {
         /// Editor's request ID to cancel
@@ -358,10 +410,39 @@ enum LspCommand {
     Shutdown,
 }
 
+/// Stdin of an LSP server, which runs locally or on a remote host
+type ServerStdin = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// Stdout of an LSP server
+type ServerStdout = BufReader<Box<dyn AsyncRead + Send + Unpin>>;
+
+/// A running LSP server process; dropping it kills the server
+enum ServerProcess {
+    Local(Child),
+    Remote(RemoteChild),
+}
+
+impl ServerProcess {
+    async fn kill(&mut self) {
+        match self {
+            Self::Local(child) => {
+                let _ = child.kill().await;
+            }
+            Self::Remote(child) => {
+                let _ = child.kill();
+            }
+        }
+    }
+}
+
 /// Mutable state for LSP command processing
 struct LspState {
     /// Stdin for sending messages
-    stdin: ChildStdin,
+    stdin: ServerStdin,
+
+    /// Process ID reported to the server, which exits when that process
+    /// does. Not reported for servers on a remote host.
+    client_process_id: Option<u32>,
 
     /// Next request ID
     next_id: i64,
@@ -385,6 +466,9 @@ struct LspState {
     /// Language ID (for error reporting)
     language: String,
 
+    /// Name of the server among the servers of its language
+    server: String,
+
     /// Mapping from editor request_id to LSP JSON-RPC id for cancellation
     /// Key: editor request_id, Value: LSP JSON-RPC id
     active_requests: HashMap<u64, i64>,
@@ -564,7 +648,7 @@ impl LspState {
         });
 
         let params = InitializeParams {
-            process_id: Some(std::process::id()),
+            process_id: self.client_process_id,
             capabilities: create_client_capabilities(),
             workspace_folders,
             initialization_options,
@@ -591,6 +675,7 @@ impl LspState {
         // Send running status
         let _ = self.async_tx.send(AsyncMessage::LspStatusUpdate {
             language: self.language.clone(),
+            server: self.server.clone(),
             status: LspServerStatus::Running,
         });
 
@@ -699,6 +784,60 @@ impl LspState {
         self.send_notification::<DidSaveTextDocument>(params).await
     }
 
+    /// Handle did_close command
+    async fn handle_did_close(&mut self, uri: Uri) -> Result<(), String> {
+        let path = PathBuf::from(uri.path().as_str());
+
+        // Only documents the server was told about can be closed
+        if self.document_versions.remove(&path).is_none() {
+            return Ok(());
+        }
+        self.pending_opens.remove(&path);
+
+        tracing::trace!("LSP: did_close for {}", uri.as_str());
+
+        let params = DidCloseTextDocumentParams {
+            text_document: TextDocumentIdentifier { uri },
+        };
+
+        self.send_notification::<DidCloseTextDocument>(params).await
+    }
+
+    /// Handle will_rename_files request
+    async fn handle_will_rename_files(
+        &mut self,
+        request_id: u64,
+        files: Vec<FileRename>,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        tracing::trace!("LSP: willRenameFiles for {} files", files.len());
+
+        let params = RenameFilesParams { files };
+        let result = self
+            .send_request_sequential::<_, Option<lsp_types::WorkspaceEdit>>(
+                WillRenameFiles::METHOD,
+                Some(params),
+                pending,
+            )
+            .await;
+        if let Err(e) = &result {
+            tracing::error!("willRenameFiles request failed: {}", e);
+        }
+        let _ = self.async_tx.send(AsyncMessage::LspWillRenameFiles {
+            request_id,
+            result: result.clone(),
+        });
+        result.map(|_| ())
+    }
+
+    /// Handle did_rename_files command
+    async fn handle_did_rename_files(&mut self, files: Vec<FileRename>) -> Result<(), String> {
+        tracing::trace!("LSP: didRenameFiles for {} files", files.len());
+
+        self.send_notification::<DidRenameFiles>(RenameFilesParams { files })
+            .await
+    }
+
     /// Handle completion request
     async fn handle_completion(
         &mut self,
@@ -921,6 +1060,82 @@ impl LspState {
         }
     }
 
+    /// Handle formatting / range formatting request
+    async fn handle_formatting(
+        &mut self,
+        request_id: u64,
+        uri: Uri,
+        range: Option<lsp_types::Range>,
+        tab_size: u32,
+        insert_spaces: bool,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{
+            DocumentFormattingParams, DocumentRangeFormattingParams, FormattingOptions,
+            TextDocumentIdentifier, WorkDoneProgressParams,
+        };
+
+        tracing::trace!(
+            "LSP: formatting request for {} (range: {:?})",
+            uri.as_str(),
+            range
+        );
+
+        let options = FormattingOptions {
+            tab_size,
+            insert_spaces,
+            ..Default::default()
+        };
+        let text_document = TextDocumentIdentifier { uri: uri.clone() };
+
+        let response = match range {
+            Some(range) => {
+                let params = DocumentRangeFormattingParams {
+                    text_document,
+                    range,
+                    options,
+                    work_done_progress_params: WorkDoneProgressParams::default(),
+                };
+                self.send_request_sequential::<_, Value>(
+                    "textDocument/rangeFormatting",
+                    Some(params),
+                    pending,
+                )
+                .await
+            }
+            None => {
+                let params = DocumentFormattingParams {
+                    text_document,
+                    options,
+                    work_done_progress_params: WorkDoneProgressParams::default(),
+                };
+                self.send_request_sequential::<_, Value>(
+                    "textDocument/formatting",
+                    Some(params),
+                    pending,
+                )
+                .await
+            }
+        };
+
+        // A null result means there is nothing to change
+        let result = response.and_then(|value| {
+            serde_json::from_value::<Option<Vec<lsp_types::TextEdit>>>(value)
+                .map(Option::unwrap_or_default)
+                .map_err(|e| format!("Failed to parse formatting response: {}", e))
+        });
+        if let Err(e) = &result {
+            tracing::error!("Formatting request failed: {}", e);
+        }
+
+        let _ = self.async_tx.send(AsyncMessage::LspFormatting {
+            request_id,
+            uri,
+            result: result.clone(),
+        });
+        result.map(|_| ())
+    }
+
     /// Handle hover documentation request
     async fn handle_hover(
         &mut self,
@@ -1318,6 +1533,7 @@ impl LspState {
                     let _ = self.async_tx.send(AsyncMessage::LspPulledDiagnostics {
                         request_id,
                         uri: uri_string,
+                        server: self.server.clone(),
                         result_id,
                         diagnostics,
                         unchanged: false,
@@ -1339,6 +1555,7 @@ impl LspState {
                     let _ = self.async_tx.send(AsyncMessage::LspPulledDiagnostics {
                         request_id,
                         uri: uri_string,
+                        server: self.server.clone(),
                         result_id: Some(result_id),
                         diagnostics: Vec::new(),
                         unchanged: true,
@@ -1352,6 +1569,7 @@ impl LspState {
                     let _ = self.async_tx.send(AsyncMessage::LspPulledDiagnostics {
                         request_id,
                         uri: uri_string,
+                        server: self.server.clone(),
                         result_id: None,
                         diagnostics: Vec::new(),
                         unchanged: false,
@@ -1366,6 +1584,7 @@ impl LspState {
                 let _ = self.async_tx.send(AsyncMessage::LspPulledDiagnostics {
                     request_id,
                     uri: uri.as_str().to_string(),
+                    server: self.server.clone(),
                     result_id: None,
                     diagnostics: Vec::new(),
                     unchanged: false,
@@ -1453,6 +1672,117 @@ impl LspState {
         }
     }
 
+    /// Handle folding range request
+    async fn handle_folding_range(
+        &mut self,
+        request_id: u64,
+        uri: Uri,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{
+            FoldingRangeParams, PartialResultParams, TextDocumentIdentifier, WorkDoneProgressParams,
+        };
+
+        let supported = self
+            .capabilities
+            .as_ref()
+            .is_some_and(|caps| caps.folding_range_provider.is_some());
+        if !supported {
+            let _ = self.async_tx.send(AsyncMessage::LspFoldingRanges {
+                request_id,
+                uri: uri.as_str().to_string(),
+                ranges: Vec::new(),
+            });
+            return Ok(());
+        }
+
+        tracing::trace!("LSP: folding range request for {}", uri.as_str());
+
+        let params = FoldingRangeParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let result = self
+            .send_request_sequential::<_, Option<Vec<lsp_types::FoldingRange>>>(
+                "textDocument/foldingRange",
+                Some(params),
+                pending,
+            )
+            .await;
+        let ranges = match &result {
+            Ok(ranges) => ranges.clone().unwrap_or_default(),
+            Err(e) => {
+                tracing::error!("Folding range request failed: {}", e);
+                Vec::new()
+            }
+        };
+        let _ = self.async_tx.send(AsyncMessage::LspFoldingRanges {
+            request_id,
+            uri: uri.as_str().to_string(),
+            ranges,
+        });
+        result.map(|_| ())
+    }
+
+    /// Handle selection range request
+    async fn handle_selection_range(
+        &mut self,
+        request_id: u64,
+        uri: Uri,
+        positions: Vec<lsp_types::Position>,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{
+            PartialResultParams, SelectionRangeParams, TextDocumentIdentifier,
+            WorkDoneProgressParams,
+        };
+
+        let supported = self
+            .capabilities
+            .as_ref()
+            .is_some_and(|caps| caps.selection_range_provider.is_some());
+        if !supported {
+            let _ = self.async_tx.send(AsyncMessage::LspSelectionRanges {
+                request_id,
+                uri: uri.as_str().to_string(),
+                ranges: Vec::new(),
+            });
+            return Ok(());
+        }
+
+        tracing::trace!("LSP: selection range request for {}", uri.as_str());
+
+        let params = SelectionRangeParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            positions,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let result = self
+            .send_request_sequential::<_, Option<Vec<lsp_types::SelectionRange>>>(
+                "textDocument/selectionRange",
+                Some(params),
+                pending,
+            )
+            .await;
+        let ranges = match &result {
+            Ok(ranges) => ranges.clone().unwrap_or_default(),
+            Err(e) => {
+                tracing::error!("Selection range request failed: {}", e);
+                Vec::new()
+            }
+        };
+        let _ = self.async_tx.send(AsyncMessage::LspSelectionRanges {
+            request_id,
+            uri: uri.as_str().to_string(),
+            ranges,
+        });
+        result.map(|_| ())
+    }
+
     /// Handle a plugin-initiated request by forwarding it to the server
     async fn handle_plugin_request(
         &mut self,
@@ -1544,13 +1874,16 @@ impl LspState {
 /// Async LSP task that handles all I/O
 struct LspTask {
     /// Process handle
-    process: Child,
+    process: ServerProcess,
 
     /// Stdin for sending messages
-    stdin: ChildStdin,
+    stdin: ServerStdin,
 
     /// Stdout for receiving messages
-    stdout: BufReader<ChildStdout>,
+    stdout: ServerStdout,
+
+    /// Process ID reported to the server (see `LspState::client_process_id`)
+    client_process_id: Option<u32>,
 
     /// Next request ID
     next_id: i64,
@@ -1577,6 +1910,9 @@ struct LspTask {
     /// Language ID (for error reporting)
     language: String,
 
+    /// Name of the server among the servers of its language
+    server: String,
+
     /// Server command (for plugin identification)
     server_command: String,
 
@@ -1586,14 +1922,18 @@ struct LspTask {
 
 #[allow(dead_code)]
 impl LspTask {
-    /// Create a new LSP task
+    /// Create a new LSP task, running the server on the remote host if
+    /// `remote` is given
+    #[allow(clippy::too_many_arguments)]
     async fn spawn(
         command: &str,
         args: &[String],
         language: String,
+        server: String,
         async_tx: std_mpsc::Sender<AsyncMessage>,
         process_limits: &ProcessLimits,
         stderr_log_path: std::path::PathBuf,
+        remote: Option<Arc<RemoteConnection>>,
     ) -> Result<Self, String> {
         tracing::info!("Spawning async LSP server: {} {:?}", command, args);
         tracing::info!("Process limits: {:?}", process_limits);
@@ -1607,6 +1947,45 @@ impl LspTask {
             )
         })?;
 
+        let (process, stdin, stdout, client_process_id) = match remote {
+            Some(remote) => {
+                let (process, stdin, stdout) =
+                    Self::spawn_remote(&remote, command, args, stderr_file).await?;
+                (process, stdin, stdout, None)
+            }
+            None => {
+                let (process, stdin, stdout) =
+                    Self::spawn_local(command, args, process_limits, stderr_file)?;
+                (process, stdin, stdout, Some(std::process::id()))
+            }
+        };
+
+        Ok(Self {
+            process,
+            stdin,
+            stdout,
+            client_process_id,
+            next_id: 0,
+            pending: HashMap::new(),
+            capabilities: None,
+            document_versions: HashMap::new(),
+            pending_opens: HashMap::new(),
+            initialized: false,
+            async_tx,
+            language,
+            server,
+            server_command: command.to_string(),
+            stderr_log_path,
+        })
+    }
+
+    /// Start the server as a local child process
+    fn spawn_local(
+        command: &str,
+        args: &[String],
+        process_limits: &ProcessLimits,
+        stderr_file: std::fs::File,
+    ) -> Result<(ServerProcess, ServerStdin, ServerStdout), String> {
         let mut cmd = Command::new(command);
         cmd.args(args)
             .stdin(std::process::Stdio::piped())
@@ -1628,36 +2007,70 @@ impl LspTask {
             .take()
             .ok_or_else(|| "Failed to get stdin".to_string())?;
 
-        let stdout = BufReader::new(
-            process
-                .stdout
-                .take()
-                .ok_or_else(|| "Failed to get stdout".to_string())?,
-        );
+        let stdout = process
+            .stdout
+            .take()
+            .ok_or_else(|| "Failed to get stdout".to_string())?;
 
-        Ok(Self {
-            process,
-            stdin,
-            stdout,
-            next_id: 0,
-            pending: HashMap::new(),
-            capabilities: None,
-            document_versions: HashMap::new(),
-            pending_opens: HashMap::new(),
-            initialized: false,
-            async_tx,
-            language,
-            server_command: command.to_string(),
-            stderr_log_path,
-        })
+        Ok((
+            ServerProcess::Local(process),
+            Box::new(stdin),
+            BufReader::new(Box::new(stdout)),
+        ))
+    }
+
+    /// Start the server on the remote host, with its stdio tunneled through
+    /// the remote connection
+    ///
+    /// Process limits only apply to local servers.
+    async fn spawn_remote(
+        remote: &RemoteConnection,
+        command: &str,
+        args: &[String],
+        stderr_file: std::fs::File,
+    ) -> Result<(ServerProcess, ServerStdin, ServerStdout), String> {
+        let mut process = remote
+            .spawn_process(Some(command), args, None, None)
+            .await
+            .map_err(|e| {
+                format!(
+                    "Failed to spawn LSP process on {}: {}",
+                    remote.destination(),
+                    e
+                )
+            })?;
+
+        let stdin = process
+            .take_stdin()
+            .ok_or_else(|| "Failed to get stdin".to_string())?;
+
+        let stdout = process
+            .take_stdout()
+            .ok_or_else(|| "Failed to get stdout".to_string())?;
+
+        // Log the remote stderr to the local log file
+        if let Some(mut stderr) = process.take_stderr() {
+            let mut stderr_file = tokio::fs::File::from_std(stderr_file);
+            tokio::spawn(async move {
+                let _ = tokio::io::copy(&mut stderr, &mut stderr_file).await;
+            });
+        }
+
+        Ok((
+            ServerProcess::Remote(process),
+            Box::new(stdin),
+            BufReader::new(Box::new(stdout)),
+        ))
     }
 
     /// Spawn the stdout reader task that continuously reads and dispatches LSP messages
+    #[allow(clippy::too_many_arguments)]
     fn spawn_stdout_reader(
-        mut stdout: BufReader<ChildStdout>,
+        mut stdout: ServerStdout,
         pending: Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
         async_tx: std_mpsc::Sender<AsyncMessage>,
         language: String,
+        server: String,
         server_command: String,
         server_response_tx: mpsc::Sender<JsonRpcResponse>,
         stderr_log_path: std::path::PathBuf,
@@ -1674,6 +2087,7 @@ impl LspTask {
                             &pending,
                             &async_tx,
                             &language,
+                            &server,
                             &server_command,
                             &server_response_tx,
                         )
@@ -1693,6 +2107,7 @@ impl LspTask {
                             tracing::error!("Error reading from LSP server: {}", e);
                             let _ = async_tx.send(AsyncMessage::LspStatusUpdate {
                                 language: language.clone(),
+                                server: server.clone(),
                                 status: LspServerStatus::Error,
                             });
                             let _ = async_tx.send(AsyncMessage::LspError {
@@ -1716,6 +2131,7 @@ impl LspTask {
         // Create state struct for command processing
         let mut state = LspState {
             stdin: self.stdin,
+            client_process_id: self.client_process_id,
             next_id: self.next_id,
             capabilities: self.capabilities,
             document_versions: self.document_versions,
@@ -1723,6 +2139,7 @@ impl LspTask {
             initialized: self.initialized,
             async_tx: self.async_tx.clone(),
             language: self.language.clone(),
+            server: self.server.clone(),
             active_requests: HashMap::new(),
         };
 
@@ -1742,6 +2159,7 @@ impl LspTask {
             pending.clone(),
             async_tx.clone(),
             language_clone.clone(),
+            self.server.clone(),
             self.server_command.clone(),
             server_response_tx,
             self.stderr_log_path,
@@ -1767,6 +2185,7 @@ impl LspTask {
                             // Send initializing status
                             let _ = async_tx.send(AsyncMessage::LspStatusUpdate {
                                 language: language_clone.clone(),
+                                server: state.server.clone(),
                                 status: LspServerStatus::Initializing,
                             });
                             tracing::info!("Processing Initialize command");
@@ -1835,6 +2254,50 @@ impl LspTask {
                                 pending_commands.push(LspCommand::DidSave { uri, text });
                             }
                         }
+                        LspCommand::DidClose { uri } => {
+                            if state.initialized {
+                                tracing::info!("Processing DidClose for {}", uri.as_str());
+                                let _ = state.handle_did_close(uri).await;
+                            } else {
+                                // The document was never opened: drop what was
+                                // queued for it
+                                tracing::trace!(
+                                    "Dropping queued commands for {} closed before initialization",
+                                    uri.as_str()
+                                );
+                                pending_commands.retain(|cmd| {
+                                    !matches!(
+                                        cmd,
+                                        LspCommand::DidOpen { uri: queued, .. }
+                                            | LspCommand::DidChange { uri: queued, .. }
+                                            | LspCommand::DidSave { uri: queued, .. }
+                                            if *queued == uri
+                                    )
+                                });
+                            }
+                        }
+                        LspCommand::WillRenameFiles { request_id, files } => {
+                            if state.initialized {
+                                tracing::info!("Processing WillRenameFiles request");
+                                let _ = state
+                                    .handle_will_rename_files(request_id, files, &pending)
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, no edits for renamed files");
+                                let _ = state.async_tx.send(AsyncMessage::LspWillRenameFiles {
+                                    request_id,
+                                    result: Ok(None),
+                                });
+                            }
+                        }
+                        LspCommand::DidRenameFiles { files } => {
+                            // A server still initializing finds the files
+                            // where they are now
+                            if state.initialized {
+                                tracing::info!("Processing DidRenameFiles");
+                                let _ = state.handle_did_rename_files(files).await;
+                            }
+                        }
                         LspCommand::Completion {
                             request_id,
                             uri,
@@ -1903,6 +2366,37 @@ impl LspTask {
                                 });
                             }
                         }
+                        LspCommand::Formatting {
+                            request_id,
+                            uri,
+                            range,
+                            tab_size,
+                            insert_spaces,
+                        } => {
+                            if state.initialized {
+                                tracing::info!(
+                                    "Processing Formatting request for {}",
+                                    uri.as_str()
+                                );
+                                let _ = state
+                                    .handle_formatting(
+                                        request_id,
+                                        uri,
+                                        range,
+                                        tab_size,
+                                        insert_spaces,
+                                        &pending,
+                                    )
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, cannot format");
+                                let _ = state.async_tx.send(AsyncMessage::LspFormatting {
+                                    request_id,
+                                    uri,
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
                         LspCommand::Hover {
                             request_id,
                             uri,
@@ -2018,6 +2512,7 @@ impl LspTask {
                                 let _ = state.async_tx.send(AsyncMessage::LspPulledDiagnostics {
                                     request_id,
                                     uri: uri.as_str().to_string(),
+                                    server: state.server.clone(),
                                     result_id: None,
                                     diagnostics: Vec::new(),
                                     unchanged: false,
@@ -2059,6 +2554,42 @@ impl LspTask {
                                 });
                             }
                         }
+                        LspCommand::FoldingRange { request_id, uri } => {
+                            if state.initialized {
+                                let _ = state
+                                    .handle_folding_range(request_id, uri, &pending)
+                                    .await;
+                            } else {
+                                tracing::trace!(
+                                    "LSP not initialized, cannot get folding ranges"
+                                );
+                                let _ = state.async_tx.send(AsyncMessage::LspFoldingRanges {
+                                    request_id,
+                                    uri: uri.as_str().to_string(),
+                                    ranges: Vec::new(),
+                                });
+                            }
+                        }
+                        LspCommand::SelectionRange {
+                            request_id,
+                            uri,
+                            positions,
+                        } => {
+                            if state.initialized {
+                                let _ = state
+                                    .handle_selection_range(request_id, uri, positions, &pending)
+                                    .await;
+                            } else {
+                                tracing::trace!(
+                                    "LSP not initialized, cannot get selection ranges"
+                                );
+                                let _ = state.async_tx.send(AsyncMessage::LspSelectionRanges {
+                                    request_id,
+                                    uri: uri.as_str().to_string(),
+                                    ranges: Vec::new(),
+                                });
+                            }
+                        }
                         LspCommand::CancelRequest { request_id } => {
                             tracing::info!(
                                 "Processing CancelRequest for editor_id={}",
@@ -2144,7 +2675,7 @@ impl LspTask {
         });
 
         let params = InitializeParams {
-            process_id: Some(std::process::id()),
+            process_id: self.client_process_id,
             capabilities: create_client_capabilities(),
             workspace_folders,
             initialization_options,
@@ -2171,6 +2702,7 @@ impl LspTask {
         // Send running status
         let _ = self.async_tx.send(AsyncMessage::LspStatusUpdate {
             language: self.language.clone(),
+            server: self.server.clone(),
             status: LspServerStatus::Running,
         });
 
@@ -2317,7 +2849,7 @@ impl LspTask {
         self.write_message(&notification).await?;
 
         // Kill process
-        let _ = self.process.kill().await;
+        self.process.kill().await;
 
         Ok(())
     }
@@ -2484,6 +3016,7 @@ impl LspTask {
                     // Send to main loop
                     let _ = self.async_tx.send(AsyncMessage::LspDiagnostics {
                         uri: params.uri.to_string(),
+                        server: self.server.clone(),
                         diagnostics: params.diagnostics,
                     });
                 }
@@ -2678,9 +3211,7 @@ impl LspTask {
 }
 
 /// Standalone function to read a message from stdout (for reader task)
-async fn read_message_from_stdout(
-    stdout: &mut BufReader<ChildStdout>,
-) -> Result<JsonRpcMessage, String> {
+async fn read_message_from_stdout(stdout: &mut ServerStdout) -> Result<JsonRpcMessage, String> {
     // Read headers
     let mut content_length: Option<usize> = None;
 
@@ -2733,6 +3264,7 @@ async fn handle_message_dispatch(
     pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
     async_tx: &std_mpsc::Sender<AsyncMessage>,
     language: &str,
+    server: &str,
     server_command: &str,
     server_response_tx: &mpsc::Sender<JsonRpcResponse>,
 ) -> Result<(), String> {
@@ -2765,7 +3297,7 @@ async fn handle_message_dispatch(
         }
         JsonRpcMessage::Notification(notification) => {
             tracing::trace!("Received LSP notification: {}", notification.method);
-            handle_notification_dispatch(notification, async_tx, language).await?;
+            handle_notification_dispatch(notification, async_tx, language, server).await?;
         }
         JsonRpcMessage::Request(request) => {
             // Handle server-to-client requests - MUST respond to avoid timeouts
@@ -2871,6 +3403,7 @@ async fn handle_notification_dispatch(
     notification: JsonRpcNotification,
     async_tx: &std_mpsc::Sender<AsyncMessage>,
     language: &str,
+    server: &str,
 ) -> Result<(), String> {
     match notification.method.as_str() {
         PublishDiagnostics::METHOD => {
@@ -2887,6 +3420,7 @@ async fn handle_notification_dispatch(
                 // Send to main loop
                 let _ = async_tx.send(AsyncMessage::LspDiagnostics {
                     uri: params.uri.to_string(),
+                    server: server.to_string(),
                     diagnostics: params.diagnostics,
                 });
             }
@@ -3098,19 +3632,28 @@ pub struct LspHandle {
     /// Client state
     state: Arc<Mutex<LspClientState>>,
 
+    /// Capabilities the server reported when initialized
+    capabilities: Arc<Mutex<Option<ServerCapabilities>>>,
+
     /// Runtime handle for blocking operations
     runtime: tokio::runtime::Handle,
 }
 
 impl LspHandle {
     /// Spawn a new LSP server in an async task
+    ///
+    /// `server` names the server among the servers of `language`. With a
+    /// `remote` connection, the server runs on the remote host.
+    #[allow(clippy::too_many_arguments)]
     pub fn spawn(
         runtime: &tokio::runtime::Handle,
         command: &str,
         args: &[String],
         language: String,
+        server: String,
         async_bridge: &AsyncBridge,
         process_limits: ProcessLimits,
+        remote: Option<Arc<RemoteConnection>>,
     ) -> Result<Self, String> {
         let (command_tx, command_rx) = mpsc::channel(100); // Buffer up to 100 commands
         let async_tx = async_bridge.sender();
@@ -3120,12 +3663,17 @@ impl LspHandle {
         let state = Arc::new(Mutex::new(LspClientState::Starting));
 
         // Create stderr log path in system temp directory (cross-platform)
-        let stderr_log_path =
-            std::env::temp_dir().join(format!("fresh-lsp-{}-{}.log", language, std::process::id()));
+        let stderr_log_path = std::env::temp_dir().join(format!(
+            "fresh-lsp-{}-{}-{}.log",
+            language,
+            server,
+            std::process::id()
+        ));
 
         // Send starting status
         let _ = async_tx.send(AsyncMessage::LspStatusUpdate {
             language: language.clone(),
+            server: server.clone(),
             status: LspServerStatus::Starting,
         });
 
@@ -3136,9 +3684,11 @@ impl LspHandle {
                 &command,
                 &args,
                 language_clone.clone(),
+                server.clone(),
                 async_tx.clone(),
                 &process_limits,
                 stderr_log_path_clone.clone(),
+                remote,
             )
             .await
             {
@@ -3155,6 +3705,7 @@ impl LspHandle {
 
                     let _ = async_tx.send(AsyncMessage::LspStatusUpdate {
                         language: language_clone.clone(),
+                        server,
                         status: LspServerStatus::Error,
                     });
                     let _ = async_tx.send(AsyncMessage::LspError {
@@ -3169,6 +3720,7 @@ impl LspHandle {
         Ok(Self {
             command_tx,
             state,
+            capabilities: Arc::new(Mutex::new(None)),
             runtime: runtime.clone(),
         })
     }
@@ -3200,6 +3752,7 @@ impl LspHandle {
         }
 
         let state = self.state.clone();
+        let capabilities = self.capabilities.clone();
 
         // Create a channel for the response, but don't wait for it
         let (tx, rx) = oneshot::channel();
@@ -3216,8 +3769,11 @@ impl LspHandle {
         let runtime = self.runtime.clone();
         runtime.spawn(async move {
             match tokio::time::timeout(std::time::Duration::from_secs(10), rx).await {
-                Ok(Ok(Ok(_))) => {
+                Ok(Ok(Ok(result))) => {
                     // Successfully initialized
+                    if let Ok(mut c) = capabilities.lock() {
+                        *c = Some(result.capabilities);
+                    }
                     if let Ok(mut s) = state.lock() {
                         let _ = s.transition_to(LspClientState::Running);
                     }
@@ -3257,6 +3813,13 @@ impl LspHandle {
         *self.state.lock().unwrap()
     }
 
+    /// Check the capabilities of the server with `provides`
+    ///
+    /// Returns `None` until the server is initialized.
+    pub fn provides<T>(&self, provides: impl FnOnce(&ServerCapabilities) -> T) -> Option<T> {
+        self.capabilities.lock().unwrap().as_ref().map(provides)
+    }
+
     /// Notify document opened
     pub fn did_open(&self, uri: Uri, text: String, language_id: String) -> Result<(), String> {
         // Send command to LspTask which will queue it if not initialized yet
@@ -3291,6 +3854,27 @@ impl LspHandle {
             .map_err(|_| "Failed to send did_save command".to_string())
     }
 
+    /// Notify document closed
+    pub fn did_close(&self, uri: Uri) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::DidClose { uri })
+            .map_err(|_| "Failed to send did_close command".to_string())
+    }
+
+    /// Request the edits to make before files are renamed
+    pub fn will_rename_files(&self, request_id: u64, files: Vec<FileRename>) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::WillRenameFiles { request_id, files })
+            .map_err(|_| "Failed to send will_rename_files command".to_string())
+    }
+
+    /// Notify files renamed
+    pub fn did_rename_files(&self, files: Vec<FileRename>) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::DidRenameFiles { files })
+            .map_err(|_| "Failed to send did_rename_files command".to_string())
+    }
+
     /// Request completion at position
     pub fn completion(
         &self,
@@ -3347,6 +3931,26 @@ impl LspHandle {
             .map_err(|_| "Failed to send rename command".to_string())
     }
 
+    /// Request formatting of the whole document, or of `range` if given
+    pub fn formatting(
+        &self,
+        request_id: u64,
+        uri: Uri,
+        range: Option<lsp_types::Range>,
+        tab_size: u32,
+        insert_spaces: bool,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::Formatting {
+                request_id,
+                uri,
+                range,
+                tab_size,
+                insert_spaces,
+            })
+            .map_err(|_| "Failed to send formatting command".to_string())
+    }
+
     /// Request hover documentation
     pub fn hover(
         &self,
@@ -3468,6 +4072,29 @@ impl LspHandle {
             .map_err(|_| "Failed to send inlay_hints command".to_string())
     }
 
+    /// Request the folding ranges of a document
+    pub fn folding_ranges(&self, request_id: u64, uri: Uri) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::FoldingRange { request_id, uri })
+            .map_err(|_| "Failed to send folding_range command".to_string())
+    }
+
+    /// Request the selection ranges at `positions` in a document
+    pub fn selection_ranges(
+        &self,
+        request_id: u64,
+        uri: Uri,
+        positions: Vec<lsp_types::Position>,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::SelectionRange {
+                request_id,
+                uri,
+                positions,
+            })
+            .map_err(|_| "Failed to send selection_range command".to_string())
+    }
+
     /// Cancel a pending request by its editor request_id
     ///
     /// This sends a $/cancelRequest notification to the LSP server.
@@ -3703,8 +4330,10 @@ mod tests {
             "cat",
             &[],
             "test".to_string(),
+            "cat".to_string(),
             &async_bridge,
             ProcessLimits::unlimited(),
+            None,
         );
 
         // Should succeed in spawning
@@ -3729,8 +4358,10 @@ mod tests {
             "cat",
             &[],
             "test".to_string(),
+            "cat".to_string(),
             &async_bridge,
             ProcessLimits::unlimited(),
+            None,
         )
         .unwrap();
 
@@ -3755,8 +4386,10 @@ mod tests {
             "cat",
             &[],
             "test".to_string(),
+            "cat".to_string(),
             &async_bridge,
             ProcessLimits::unlimited(),
+            None,
         )
         .unwrap();
 
@@ -3787,8 +4420,10 @@ mod tests {
             "cat",
             &[],
             "test".to_string(),
+            "cat".to_string(),
             &async_bridge,
             ProcessLimits::unlimited(),
+            None,
         )
         .unwrap();
 
@@ -3820,8 +4455,10 @@ mod tests {
             "this-command-does-not-exist-12345",
             &[],
             "test".to_string(),
+            "this-command-does-not-exist-12345".to_string(),
             &async_bridge,
             ProcessLimits::unlimited(),
+            None,
         );
 
         // Should succeed in creating handle (error happens asynchronously)
@@ -3857,8 +4494,10 @@ mod tests {
                     "cat",
                     &[],
                     "test".to_string(),
+                    "cat".to_string(),
                     &async_bridge,
                     ProcessLimits::unlimited(),
+                    None,
                 )
                 .unwrap()
             });
@@ -3926,8 +4565,10 @@ mod tests {
             "cat", // Simple command that will exit immediately
             &[],
             "test".to_string(),
+            "cat".to_string(),
             &async_bridge,
             ProcessLimits::unlimited(),
+            None,
         )
         .unwrap();
 
@@ -3975,8 +4616,10 @@ mod tests {
             "bash",
             &["-c".to_string(), fake_lsp_script.to_string()],
             "fake".to_string(),
+            "bash".to_string(),
             &async_bridge,
             ProcessLimits::unlimited(),
+            None,
         )
         .unwrap();
 
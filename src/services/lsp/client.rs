@@ -478,6 +478,67 @@ pub struct LspServerConfig {
     /// For example, Deno requires `{"enable": true}` to enable completions.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub initialization_options: Option<serde_json::Value>,
+
+    /// Name of the server, shown as the source of its diagnostics.
+    /// Defaults to the file name of the command. Must be unique among the
+    /// servers of a language.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Priority of the server among the servers of its language. Requests
+    /// go to the highest priority server advertising the capability, while
+    /// document changes go to all of them.
+    #[serde(default)]
+    pub priority: i32,
+}
+
+impl LspServerConfig {
+    /// Name of the server: the configured one, or the file name of its command
+    pub fn server_name(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        std::path::Path::new(&self.command)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.command.clone())
+    }
+}
+
+/// The language servers of a language: a single server, or a list of
+/// servers all run on the language's files
+///
+/// The servers of a language start together, automatically if any of them
+/// has `auto_start` set.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum LspServers {
+    Single(LspServerConfig),
+    Multiple(Vec<LspServerConfig>),
+}
+
+impl LspServers {
+    /// The configured servers
+    pub fn servers(&self) -> &[LspServerConfig] {
+        match self {
+            Self::Single(server) => std::slice::from_ref(server),
+            Self::Multiple(servers) => servers,
+        }
+    }
+
+    /// The configured servers, for editing
+    pub fn servers_mut(&mut self) -> &mut [LspServerConfig] {
+        match self {
+            Self::Single(server) => std::slice::from_mut(server),
+            Self::Multiple(servers) => servers,
+        }
+    }
+}
+
+impl From<LspServerConfig> for LspServers {
+    fn from(server: LspServerConfig) -> Self {
+        Self::Single(server)
+    }
 }
 
 fn default_true() -> bool {
@@ -7,6 +7,7 @@ use crate::state::EditorState;
 use crate::view::overlay::{Overlay, OverlayFace, OverlayNamespace};
 use lsp_types::{Diagnostic, DiagnosticSeverity};
 use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
 use std::hash::{Hash, Hasher};
 use std::ops::Range;
 use std::sync::Mutex;
@@ -59,6 +60,46 @@ fn compute_diagnostic_hash(diagnostics: &[Diagnostic]) -> u64 {
     hasher.finish()
 }
 
+/// Diagnostics of each file, by the server that reported them
+///
+/// The servers of a language report diagnostics independently, each
+/// replacing its own previous ones: this merges them into the diagnostics
+/// of the file.
+#[derive(Debug, Default)]
+pub struct DiagnosticsByServer {
+    by_uri: HashMap<String, BTreeMap<String, Vec<Diagnostic>>>,
+}
+
+impl DiagnosticsByServer {
+    /// Replace the diagnostics `server` reported for `uri`, returning the
+    /// merged diagnostics of all servers for it
+    ///
+    /// Diagnostics without a source get the name of the server as source.
+    pub fn update(
+        &mut self,
+        uri: &str,
+        server: &str,
+        mut diagnostics: Vec<Diagnostic>,
+    ) -> Vec<Diagnostic> {
+        for diagnostic in &mut diagnostics {
+            diagnostic.source.get_or_insert_with(|| server.to_string());
+        }
+
+        let servers = self.by_uri.entry(uri.to_string()).or_default();
+        if diagnostics.is_empty() {
+            servers.remove(server);
+        } else {
+            servers.insert(server.to_string(), diagnostics);
+        }
+
+        let merged: Vec<Diagnostic> = servers.values().flatten().cloned().collect();
+        if servers.is_empty() {
+            self.by_uri.remove(uri);
+        }
+        merged
+    }
+}
+
 /// Apply LSP diagnostics to editor state with hash-based caching
 ///
 /// This is the recommended entry point that skips redundant work when diagnostics haven't changed.
@@ -162,7 +203,10 @@ pub fn apply_diagnostics_to_state(
         if let Some((range, face, priority)) =
             diagnostic_to_overlay(diagnostic, &state.buffer, theme)
         {
-            let message = diagnostic.message.clone();
+            let message = match &diagnostic.source {
+                Some(source) => format!("{}: {}", source, diagnostic.message),
+                None => diagnostic.message.clone(),
+            };
 
             let overlay = Overlay::with_namespace(&mut state.marker_list, range, face, ns.clone())
                 .with_priority_value(priority)
@@ -325,4 +369,41 @@ mod tests {
         assert_eq!(range.start, 3);
         assert_eq!(range.end, 8);
     }
+
+    fn diagnostic(line: u32, source: Option<&str>, message: &str) -> Diagnostic {
+        Diagnostic {
+            range: Range {
+                start: Position { line, character: 0 },
+                end: Position { line, character: 1 },
+            },
+            source: source.map(str::to_string),
+            message: message.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diagnostics_by_server_merges_servers() {
+        let mut store = DiagnosticsByServer::default();
+        let uri = "file:///test.py";
+
+        let merged = store.update(uri, "pyright", vec![diagnostic(0, None, "undefined")]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].source.as_deref(), Some("pyright"));
+
+        let merged = store.update(uri, "ruff", vec![diagnostic(1, Some("Ruff"), "unused")]);
+        assert_eq!(merged.len(), 2);
+        let sources: Vec<_> = merged.iter().map(|d| d.source.as_deref()).collect();
+        assert!(sources.contains(&Some("pyright")));
+        assert!(sources.contains(&Some("Ruff")));
+
+        // A server replaces its own diagnostics only
+        let merged = store.update(uri, "pyright", vec![]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].message, "unused");
+
+        let merged = store.update(uri, "ruff", vec![]);
+        assert!(merged.is_empty());
+        assert!(store.by_uri.is_empty());
+    }
 }
@@ -1,16 +1,28 @@
 //! LSP Manager - manages multiple language servers using async I/O
 //!
 //! This module provides a manager for multiple LSP servers that:
-//! - Spawns one server per language
+//! - Spawns the servers configured for each language
 //! - Uses async LspHandle for non-blocking I/O
-//! - Routes requests to appropriate servers
+//! - Routes requests to the server of a language providing them
 //! - Configured via config.json
+//!
+//! A language can have several servers (e.g. pyright and ruff for Python).
+//! Document sync goes to all of them, while each request goes to the highest
+//! priority server advertising the capability it needs. File renames go to
+//! every running server that registered for the files renamed.
 
 use crate::services::async_bridge::AsyncBridge;
 use crate::services::lsp::async_handler::LspHandle;
 use crate::services::lsp::client::LspServerConfig;
-use lsp_types::Uri;
+use crate::services::remote::RemoteConnection;
+use lsp_types::{
+    CodeActionProviderCapability, FileOperationFilter, FileOperationPatternKind,
+    FileOperationRegistrationOptions, FileRename, FoldingRangeProviderCapability,
+    HoverProviderCapability, OneOf, SelectionRangeProviderCapability, ServerCapabilities, Uri,
+};
 use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 /// Result of attempting to spawn an LSP server
@@ -25,6 +37,178 @@ pub enum LspSpawnResult {
     Failed,
 }
 
+/// A request type, routed to the servers providing it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LspFeature {
+    Completion,
+    GotoDefinition,
+    Hover,
+    References,
+    Rename,
+    Formatting,
+    RangeFormatting,
+    SignatureHelp,
+    CodeActions,
+    PullDiagnostics,
+    InlayHints,
+    FoldingRanges,
+    SelectionRanges,
+}
+
+impl LspFeature {
+    /// Whether a server with `capabilities` provides the feature
+    pub fn is_provided_by(self, capabilities: &ServerCapabilities) -> bool {
+        fn enabled<T>(provider: &Option<OneOf<bool, T>>) -> bool {
+            !matches!(provider, None | Some(OneOf::Left(false)))
+        }
+
+        match self {
+            Self::Completion => capabilities.completion_provider.is_some(),
+            Self::GotoDefinition => enabled(&capabilities.definition_provider),
+            Self::Hover => !matches!(
+                capabilities.hover_provider,
+                None | Some(HoverProviderCapability::Simple(false))
+            ),
+            Self::References => enabled(&capabilities.references_provider),
+            Self::Rename => enabled(&capabilities.rename_provider),
+            Self::Formatting => enabled(&capabilities.document_formatting_provider),
+            Self::RangeFormatting => enabled(&capabilities.document_range_formatting_provider),
+            Self::SignatureHelp => capabilities.signature_help_provider.is_some(),
+            Self::CodeActions => !matches!(
+                capabilities.code_action_provider,
+                None | Some(CodeActionProviderCapability::Simple(false))
+            ),
+            Self::PullDiagnostics => capabilities.diagnostic_provider.is_some(),
+            Self::InlayHints => enabled(&capabilities.inlay_hint_provider),
+            Self::FoldingRanges => !matches!(
+                capabilities.folding_range_provider,
+                None | Some(FoldingRangeProviderCapability::Simple(false))
+            ),
+            Self::SelectionRanges => !matches!(
+                capabilities.selection_range_provider,
+                None | Some(SelectionRangeProviderCapability::Simple(false))
+            ),
+        }
+    }
+}
+
+/// A running language server, one of the servers of a language
+pub struct LspServer {
+    /// Name of the server, unique among the servers of its language
+    pub name: String,
+
+    /// Priority of the server for requests
+    pub priority: i32,
+
+    /// Position of the server in the configuration of its language,
+    /// ordering servers of equal priority
+    position: usize,
+
+    /// Handle to the server
+    pub handle: LspHandle,
+}
+
+impl LspServer {
+    /// Whether the server provides `feature`, `None` until it is initialized
+    pub fn provides(&self, feature: LspFeature) -> Option<bool> {
+        self.handle
+            .provides(|capabilities| feature.is_provided_by(capabilities))
+    }
+
+    /// The renames, among `renames`, the server registered for
+    /// `workspace/willRenameFiles`
+    pub fn will_rename_files(&self, renames: &[RenamedFile]) -> Vec<FileRename> {
+        self.handle
+            .provides(|capabilities| {
+                registered_renames(
+                    file_operations(capabilities)?.will_rename.as_ref()?,
+                    renames,
+                )
+            })
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    /// The renames, among `renames`, the server registered for
+    /// `workspace/didRenameFiles`
+    pub fn did_rename_files(&self, renames: &[RenamedFile]) -> Vec<FileRename> {
+        self.handle
+            .provides(|capabilities| {
+                registered_renames(file_operations(capabilities)?.did_rename.as_ref()?, renames)
+            })
+            .flatten()
+            .unwrap_or_default()
+    }
+}
+
+/// A file or directory renamed (or moved), as told to language servers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenamedFile {
+    pub old_path: PathBuf,
+    pub new_path: PathBuf,
+    pub is_dir: bool,
+}
+
+fn file_operations(
+    capabilities: &ServerCapabilities,
+) -> Option<&lsp_types::WorkspaceFileOperationsServerCapabilities> {
+    capabilities.workspace.as_ref()?.file_operations.as_ref()
+}
+
+/// The renames matching the filters of a file operation registration
+fn registered_renames(
+    registration: &FileOperationRegistrationOptions,
+    renames: &[RenamedFile],
+) -> Option<Vec<FileRename>> {
+    let renames = renames
+        .iter()
+        .filter(|rename| {
+            registration
+                .filters
+                .iter()
+                .any(|filter| filter_matches(filter, &rename.old_path, rename.is_dir))
+        })
+        .filter_map(|rename| {
+            Some(FileRename {
+                old_uri: url::Url::from_file_path(&rename.old_path).ok()?.into(),
+                new_uri: url::Url::from_file_path(&rename.new_path).ok()?.into(),
+            })
+        })
+        .collect();
+    Some(renames)
+}
+
+/// Whether a file operation filter matches the file or directory at `path`
+fn filter_matches(filter: &FileOperationFilter, path: &Path, is_dir: bool) -> bool {
+    if filter
+        .scheme
+        .as_deref()
+        .is_some_and(|scheme| scheme != "file")
+    {
+        return false;
+    }
+    let pattern = &filter.pattern;
+    match pattern.matches {
+        Some(FileOperationPatternKind::File) if is_dir => return false,
+        Some(FileOperationPatternKind::Folder) if !is_dir => return false,
+        _ => {}
+    }
+    let ignore_case = pattern
+        .options
+        .as_ref()
+        .and_then(|options| options.ignore_case)
+        .unwrap_or(false);
+    // In the patterns of the specification, `*` and `?` stop at path separators
+    globset::GlobBuilder::new(&pattern.glob)
+        .literal_separator(true)
+        .case_insensitive(ignore_case)
+        .build()
+        .is_ok_and(|glob| glob.compile_matcher().is_match(path))
+}
+
+/// A server, by language and server name
+type ServerKey = (String, String);
+
 /// Constants for restart behavior
 const MAX_RESTARTS_IN_WINDOW: usize = 5;
 const RESTART_WINDOW_SECS: u64 = 180; // 3 minutes
@@ -32,11 +216,11 @@ const RESTART_BACKOFF_BASE_MS: u64 = 1000; // 1s, 2s, 4s, 8s...
 
 /// Manager for multiple language servers (async version)
 pub struct LspManager {
-    /// Map from language ID to LSP handle
-    handles: HashMap<String, LspHandle>,
+    /// Running servers by language ID, highest priority first
+    handles: HashMap<String, Vec<LspServer>>,
 
-    /// Configuration for each language
-    config: HashMap<String, LspServerConfig>,
+    /// Configuration of the servers of each language
+    config: HashMap<String, Vec<LspServerConfig>>,
 
     /// Root URI for workspace
     root_uri: Option<Uri>,
@@ -47,14 +231,17 @@ pub struct LspManager {
     /// Async bridge for communication
     async_bridge: Option<AsyncBridge>,
 
-    /// Restart attempt timestamps per language (for tracking restart frequency)
-    restart_attempts: HashMap<String, Vec<Instant>>,
+    /// Connection to the remote host servers run on, when editing remotely
+    remote: Option<Arc<RemoteConnection>>,
 
-    /// Languages currently in restart cooldown (gave up after too many restarts)
-    restart_cooldown: HashSet<String>,
+    /// Restart attempt timestamps per server (for tracking restart frequency)
+    restart_attempts: HashMap<ServerKey, Vec<Instant>>,
 
-    /// Scheduled restart times (language -> when to restart)
-    pending_restarts: HashMap<String, Instant>,
+    /// Servers currently in restart cooldown (gave up after too many restarts)
+    restart_cooldown: HashSet<ServerKey>,
+
+    /// Scheduled restart times (server -> when to restart)
+    pending_restarts: HashMap<ServerKey, Instant>,
 
     /// Languages that have been manually started by the user
     /// If a language is in this set, it will spawn even if auto_start=false in config
@@ -74,6 +261,7 @@ impl LspManager {
             root_uri,
             runtime: None,
             async_bridge: None,
+            remote: None,
             restart_attempts: HashMap::new(),
             restart_cooldown: HashSet::new(),
             pending_restarts: HashMap::new(),
@@ -98,29 +286,29 @@ impl LspManager {
         &self.allowed_languages
     }
 
-    /// Get the configuration for a specific language
-    pub fn get_config(&self, language: &str) -> Option<&LspServerConfig> {
-        self.config.get(language)
+    /// Get the configuration of the servers of a language
+    pub fn get_configs(&self, language: &str) -> &[LspServerConfig] {
+        self.config.get(language).map(Vec::as_slice).unwrap_or(&[])
     }
 
-    /// Try to spawn an LSP server, checking auto_start configuration
+    /// Try to spawn the LSP servers of a language, checking auto_start configuration
     ///
     /// This is the main entry point for spawning LSP servers on file open.
     /// It returns:
-    /// - `LspSpawnResult::Spawned` if the server was spawned or already running
-    /// - `LspSpawnResult::NotAutoStart` if auto_start is false and not manually allowed
+    /// - `LspSpawnResult::Spawned` if the servers were spawned or already running
+    /// - `LspSpawnResult::NotAutoStart` if no server has auto_start and the
+    ///   language was not manually allowed
     /// - `LspSpawnResult::Failed` if spawn failed or language is disabled
     pub fn try_spawn(&mut self, language: &str) -> LspSpawnResult {
-        // If handle already exists, return success
+        // If a server is already running, return success
         if self.handles.contains_key(language) {
             return LspSpawnResult::Spawned;
         }
 
         // Check if language is configured and enabled
-        let config = match self.config.get(language) {
-            Some(c) if c.enabled => c,
-            Some(_) => return LspSpawnResult::Failed, // Disabled
-            None => return LspSpawnResult::Failed,    // Not configured
+        let configs = match self.config.get(language) {
+            Some(configs) if configs.iter().any(|c| c.enabled) => configs,
+            _ => return LspSpawnResult::Failed, // Disabled or not configured
         };
 
         // Check if we have runtime and bridge
@@ -129,11 +317,12 @@ impl LspManager {
         }
 
         // Check if auto_start is enabled or language was manually allowed
-        if !config.auto_start && !self.allowed_languages.contains(language) {
+        let auto_start = configs.iter().any(|c| c.enabled && c.auto_start);
+        if !auto_start && !self.allowed_languages.contains(language) {
             return LspSpawnResult::NotAutoStart;
         }
 
-        // Spawn the server
+        // Spawn the servers
         if self.get_or_spawn(language).is_some() {
             LspSpawnResult::Spawned
         } else {
@@ -149,9 +338,19 @@ impl LspManager {
         self.async_bridge = Some(async_bridge);
     }
 
-    /// Set configuration for a language
+    /// Run language servers on a remote host, over the given connection
+    pub fn set_remote(&mut self, remote: Arc<RemoteConnection>) {
+        self.remote = Some(remote);
+    }
+
+    /// Set configuration for a language with a single server
     pub fn set_language_config(&mut self, language: String, config: LspServerConfig) {
-        self.config.insert(language, config);
+        self.set_language_servers(language, vec![config]);
+    }
+
+    /// Set the configuration of the servers of a language
+    pub fn set_language_servers(&mut self, language: String, servers: Vec<LspServerConfig>) {
+        self.config.insert(language, servers);
     }
 
     /// Set a new root URI for the workspace
@@ -187,13 +386,54 @@ impl LspManager {
         );
     }
 
-    /// Get or spawn an LSP handle for a language
+    /// Get or spawn the servers of a language, returning the handle of the
+    /// highest priority one
     pub fn get_or_spawn(&mut self, language: &str) -> Option<&mut LspHandle> {
-        // Return existing handle if available
-        if self.handles.contains_key(language) {
-            return self.handles.get_mut(language);
-        }
+        self.spawn_servers(language);
+        self.handles
+            .get_mut(language)?
+            .first_mut()
+            .map(|server| &mut server.handle)
+    }
+
+    /// Get or spawn the servers of a language, returning the handle of the
+    /// server `feature` requests go to
+    ///
+    /// That is the highest priority server advertising the feature, counting
+    /// servers not initialized yet as advertising it, or else the highest
+    /// priority server.
+    pub fn get_or_spawn_for(
+        &mut self,
+        language: &str,
+        feature: LspFeature,
+    ) -> Option<&mut LspHandle> {
+        self.spawn_servers(language);
+        let servers = self.handles.get_mut(language)?;
+        let index = servers
+            .iter()
+            .position(|server| server.provides(feature) != Some(false))
+            .unwrap_or(0);
+        servers.get_mut(index).map(|server| &mut server.handle)
+    }
+
+    /// Get or spawn all the servers of a language, for document sync
+    pub fn get_or_spawn_all(&mut self, language: &str) -> &[LspServer] {
+        self.spawn_servers(language);
+        self.handles.get(language).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// The running servers of a language, without spawning any
+    pub fn servers(&self, language: &str) -> &[LspServer] {
+        self.handles.get(language).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// The running servers of all languages
+    pub fn all_servers(&self) -> impl Iterator<Item = &LspServer> {
+        self.handles.values().flatten()
+    }
 
+    /// Spawn the enabled servers of a language that are not running
+    fn spawn_servers(&mut self, language: &str) {
         // Check if language was explicitly disabled by user (via stop command)
         // Don't auto-spawn disabled languages
         if self.disabled_languages.contains(language) {
@@ -201,102 +441,156 @@ impl LspManager {
                 "LSP for {} is disabled, not spawning (use manual restart to re-enable)",
                 language
             );
-            return None;
+            return;
         }
 
-        // Get config for this language
-        let config = self.config.get(language)?;
-
-        if !config.enabled {
-            return None;
-        }
+        let Some(configs) = self.config.get(language) else {
+            return;
+        };
 
         // Check we have runtime and bridge
-        let runtime = self.runtime.as_ref()?;
-        let async_bridge = self.async_bridge.as_ref()?;
-
-        // Spawn new handle
-        tracing::info!("Spawning async LSP server for language: {}", language);
-
-        match LspHandle::spawn(
-            runtime,
-            &config.command,
-            &config.args,
-            language.to_string(),
-            async_bridge,
-            config.process_limits.clone(),
-        ) {
-            Ok(handle) => {
-                // Initialize the handle (non-blocking)
-                // The handle will become ready asynchronously
-                if let Err(e) =
-                    handle.initialize(self.root_uri.clone(), config.initialization_options.clone())
-                {
-                    tracing::error!("Failed to send initialize command for {}: {}", language, e);
-                    return None;
-                }
+        let (Some(runtime), Some(async_bridge)) = (&self.runtime, &self.async_bridge) else {
+            return;
+        };
 
-                tracing::info!(
-                    "LSP initialization started for {}, will be ready asynchronously",
-                    language
-                );
-                self.handles.insert(language.to_string(), handle);
-                self.handles.get_mut(language)
+        for (position, config) in configs.iter().enumerate() {
+            let name = config.server_name();
+            let running = self
+                .handles
+                .get(language)
+                .is_some_and(|servers| servers.iter().any(|server| server.name == name));
+            if !config.enabled || running {
+                continue;
             }
-            Err(e) => {
-                tracing::error!("Failed to spawn LSP handle for {}: {}", language, e);
-                None
+
+            // Spawn new handle
+            tracing::info!(
+                "Spawning async LSP server {} for language: {}",
+                name,
+                language
+            );
+
+            let handle = match LspHandle::spawn(
+                runtime,
+                &config.command,
+                &config.args,
+                language.to_string(),
+                name.clone(),
+                async_bridge,
+                config.process_limits.clone(),
+                self.remote.clone(),
+            ) {
+                Ok(handle) => handle,
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to spawn LSP handle {} for {}: {}",
+                        name,
+                        language,
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            // Initialize the handle (non-blocking)
+            // The handle will become ready asynchronously
+            if let Err(e) =
+                handle.initialize(self.root_uri.clone(), config.initialization_options.clone())
+            {
+                tracing::error!(
+                    "Failed to send initialize command to {} for {}: {}",
+                    name,
+                    language,
+                    e
+                );
+                continue;
             }
+
+            tracing::info!(
+                "LSP initialization of {} started for {}, will be ready asynchronously",
+                name,
+                language
+            );
+            let servers = self.handles.entry(language.to_string()).or_default();
+            servers.push(LspServer {
+                name,
+                priority: config.priority,
+                position,
+                handle,
+            });
+            servers.sort_by_key(|server| (std::cmp::Reverse(server.priority), server.position));
+        }
+    }
+
+    /// Check if a server of a language is running
+    fn is_running(&self, language: &str, server: &str) -> bool {
+        self.handles
+            .get(language)
+            .is_some_and(|servers| servers.iter().any(|s| s.name == server))
+    }
+
+    /// Name a server in messages: by its language, and by its name too when
+    /// the language has several servers
+    fn server_label(&self, language: &str, server: &str) -> String {
+        if self.get_configs(language).len() > 1 {
+            format!("{} ({})", language, server)
+        } else {
+            language.to_string()
         }
     }
 
     /// Handle a server crash by scheduling a restart with exponential backoff
     ///
     /// Returns a message describing the action taken (for UI notification)
-    pub fn handle_server_crash(&mut self, language: &str) -> String {
+    pub fn handle_server_crash(&mut self, language: &str, server: &str) -> String {
         // Remove the crashed handle
-        if let Some(handle) = self.handles.remove(language) {
-            let _ = handle.shutdown(); // Best-effort cleanup
+        if let Some(servers) = self.handles.get_mut(language) {
+            if let Some(index) = servers.iter().position(|s| s.name == server) {
+                let _ = servers.remove(index).handle.shutdown(); // Best-effort cleanup
+            }
+            if servers.is_empty() {
+                self.handles.remove(language);
+            }
         }
 
+        let label = self.server_label(language, server);
+        let key = (language.to_string(), server.to_string());
+
         // Check if server was explicitly disabled by user (via stop command)
         // Don't auto-restart disabled servers
         if self.disabled_languages.contains(language) {
             return format!(
                 "LSP server for {} stopped. Use 'Restart LSP Server' command to start it again.",
-                language
+                label
             );
         }
 
         // Check if we're in cooldown
-        if self.restart_cooldown.contains(language) {
+        if self.restart_cooldown.contains(&key) {
             return format!(
                 "LSP server for {} crashed. Too many restarts - use 'Restart LSP Server' command to retry.",
-                language
+                label
             );
         }
 
         // Clean up old restart attempts outside the window
         let now = Instant::now();
         let window = Duration::from_secs(RESTART_WINDOW_SECS);
-        let attempts = self
-            .restart_attempts
-            .entry(language.to_string())
-            .or_default();
+        let attempts = self.restart_attempts.entry(key.clone()).or_default();
         attempts.retain(|t| now.duration_since(*t) < window);
 
         // Check if we've exceeded max restarts
         if attempts.len() >= MAX_RESTARTS_IN_WINDOW {
-            self.restart_cooldown.insert(language.to_string());
+            self.restart_cooldown.insert(key);
             tracing::warn!(
                 "LSP server for {} has crashed {} times in {} minutes, entering cooldown",
-                language,
+                label,
                 MAX_RESTARTS_IN_WINDOW,
                 RESTART_WINDOW_SECS / 60
             );
             return format!(
                 "LSP server for {} has crashed too many times ({} in {} min). Use 'Restart LSP Server' command to manually restart.",
-                language,
+                label,
                 MAX_RESTARTS_IN_WINDOW,
                 RESTART_WINDOW_SECS / 60
             );
@@ -308,12 +602,11 @@ impl LspManager {
         let restart_time = now + Duration::from_millis(delay_ms);
 
         // Schedule the restart
-        self.pending_restarts
-            .insert(language.to_string(), restart_time);
+        self.pending_restarts.insert(key, restart_time);
 
         tracing::info!(
             "LSP server for {} crashed (attempt {}/{}), will restart in {}ms",
-            language,
+            label,
             attempt_number + 1,
             MAX_RESTARTS_IN_WINDOW,
             delay_ms
@@ -321,7 +614,7 @@ impl LspManager {
 
         format!(
             "LSP server for {} crashed (attempt {}/{}), restarting in {}s...",
-            language,
+            label,
             attempt_number + 1,
             MAX_RESTARTS_IN_WINDOW,
             delay_ms / 1000
@@ -336,29 +629,32 @@ impl LspManager {
         let mut results = Vec::new();
 
         // Find restarts that are due
-        let due_restarts: Vec<String> = self
+        let due_restarts: Vec<ServerKey> = self
             .pending_restarts
             .iter()
             .filter(|(_, time)| **time <= now)
-            .map(|(lang, _)| lang.clone())
+            .map(|(key, _)| key.clone())
             .collect();
 
-        for language in due_restarts {
-            self.pending_restarts.remove(&language);
+        for key in due_restarts {
+            self.pending_restarts.remove(&key);
 
             // Record this restart attempt
             self.restart_attempts
-                .entry(language.clone())
+                .entry(key.clone())
                 .or_default()
                 .push(now);
 
             // Attempt to spawn the server
-            if self.get_or_spawn(&language).is_some() {
-                let message = format!("LSP server for {} restarted successfully", language);
+            let (language, server) = key;
+            let label = self.server_label(&language, &server);
+            self.spawn_servers(&language);
+            if self.is_running(&language, &server) {
+                let message = format!("LSP server for {} restarted successfully", label);
                 tracing::info!("{}", message);
                 results.push((language, true, message));
             } else {
-                let message = format!("Failed to restart LSP server for {}", language);
+                let message = format!("Failed to restart LSP server for {}", label);
                 tracing::error!("{}", message);
                 results.push((language, false, message));
             }
@@ -367,25 +663,31 @@ impl LspManager {
         results
     }
 
-    /// Check if a language server is in restart cooldown
+    /// Check if a server of a language is in restart cooldown
     pub fn is_in_cooldown(&self, language: &str) -> bool {
-        self.restart_cooldown.contains(language)
+        self.restart_cooldown
+            .iter()
+            .any(|(lang, _)| lang == language)
     }
 
-    /// Check if a language server has a pending restart
+    /// Check if a server of a language has a pending restart
     pub fn has_pending_restart(&self, language: &str) -> bool {
-        self.pending_restarts.contains_key(language)
+        self.pending_restarts
+            .keys()
+            .any(|(lang, _)| lang == language)
     }
 
-    /// Clear cooldown for a language and allow manual restart
+    /// Clear cooldown for the servers of a language and allow manual restart
     pub fn clear_cooldown(&mut self, language: &str) {
-        self.restart_cooldown.remove(language);
-        self.restart_attempts.remove(language);
-        self.pending_restarts.remove(language);
+        self.restart_cooldown.retain(|(lang, _)| lang != language);
+        self.restart_attempts
+            .retain(|(lang, _), _| lang != language);
+        self.pending_restarts
+            .retain(|(lang, _), _| lang != language);
         tracing::info!("Cleared restart cooldown for {}", language);
     }
 
-    /// Manually restart/start a language server (bypasses cooldown and auto_start check)
+    /// Manually restart/start the servers of a language (bypasses cooldown and auto_start check)
     ///
     /// This is used both to restart a crashed server and to manually start a server
     /// that has auto_start=false in its configuration.
@@ -401,12 +703,12 @@ impl LspManager {
         // Add to allowed languages so it stays active even if auto_start=false
         self.allowed_languages.insert(language.to_string());
 
-        // Remove existing handle
-        if let Some(handle) = self.handles.remove(language) {
-            let _ = handle.shutdown();
+        // Remove existing handles
+        for server in self.handles.remove(language).unwrap_or_default() {
+            let _ = server.handle.shutdown();
         }
 
-        // Spawn new server
+        // Spawn new servers
         if self.get_or_spawn(language).is_some() {
             let message = format!("LSP server for {} started", language);
             tracing::info!("{}", message);
@@ -418,53 +720,58 @@ impl LspManager {
         }
     }
 
-    /// Get the number of recent restart attempts for a language
+    /// Get the number of recent restart attempts for the servers of a language
     pub fn restart_attempt_count(&self, language: &str) -> usize {
         let now = Instant::now();
         let window = Duration::from_secs(RESTART_WINDOW_SECS);
         self.restart_attempts
-            .get(language)
-            .map(|attempts| {
+            .iter()
+            .filter(|((lang, _), _)| lang == language)
+            .map(|(_, attempts)| {
                 attempts
                     .iter()
                     .filter(|t| now.duration_since(**t) < window)
                     .count()
             })
-            .unwrap_or(0)
+            .sum()
     }
 
-    /// Get a list of currently running LSP server languages
+    /// Get a list of the languages with running LSP servers
     pub fn running_servers(&self) -> Vec<String> {
         self.handles.keys().cloned().collect()
     }
 
     /// Check if an LSP server for a language is running and ready to serve requests
     pub fn is_server_ready(&self, language: &str) -> bool {
-        self.handles
-            .get(language)
-            .map(|handle| handle.state().can_send_requests())
-            .unwrap_or(false)
+        self.handles.get(language).is_some_and(|servers| {
+            servers
+                .iter()
+                .any(|server| server.handle.state().can_send_requests())
+        })
     }
 
-    /// Shutdown a specific language server
+    /// Shutdown the servers of a language
     ///
-    /// This marks the server as disabled, preventing auto-restart until the user
+    /// This marks the language as disabled, preventing auto-restart until the user
     /// explicitly restarts it using the restart command.
     ///
-    /// Returns true if the server was found and shutdown, false otherwise
+    /// Returns true if a server was found and shutdown, false otherwise
     pub fn shutdown_server(&mut self, language: &str) -> bool {
-        if let Some(handle) = self.handles.remove(language) {
+        if let Some(servers) = self.handles.remove(language) {
             tracing::info!(
                 "Shutting down LSP server for {} (disabled until manual restart)",
                 language
             );
-            let _ = handle.shutdown();
+            for server in servers {
+                let _ = server.handle.shutdown();
+            }
             // Mark as disabled to prevent auto-restart
             self.disabled_languages.insert(language.to_string());
             // Cancel any pending restarts
-            self.pending_restarts.remove(language);
+            self.pending_restarts
+                .retain(|(lang, _), _| lang != language);
             // Remove from restart cooldown
-            self.restart_cooldown.remove(language);
+            self.restart_cooldown.retain(|(lang, _)| lang != language);
             // Also remove from allowed languages so it will require confirmation again
             // if user tries to start it later
             self.allowed_languages.remove(language);
@@ -477,9 +784,11 @@ impl LspManager {
 
     /// Shutdown all language servers
     pub fn shutdown_all(&mut self) {
-        for (language, handle) in self.handles.iter() {
-            tracing::info!("Shutting down LSP server for {}", language);
-            let _ = handle.shutdown();
+        for (language, servers) in self.handles.iter() {
+            for server in servers {
+                tracing::info!("Shutting down LSP server {} for {}", server.name, language);
+                let _ = server.handle.shutdown();
+            }
         }
         self.handles.clear();
     }
@@ -511,7 +820,6 @@ pub fn detect_language(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::Path;
 
     #[test]
     fn test_lsp_manager_new() {
@@ -537,13 +845,163 @@ mod tests {
             process_limits: crate::services::process_limits::ProcessLimits::unlimited(),
             auto_start: false,
             initialization_options: None,
+            name: None,
+            priority: 0,
         };
 
         manager.set_language_config("rust".to_string(), config);
 
         assert_eq!(manager.config.len(), 1);
         assert!(manager.config.contains_key("rust"));
-        assert!(manager.config.get("rust").unwrap().enabled);
+        assert!(manager.config["rust"][0].enabled);
+    }
+
+    fn server_config(command: &str, priority: i32) -> LspServerConfig {
+        LspServerConfig {
+            enabled: true,
+            command: command.to_string(),
+            args: vec![],
+            process_limits: crate::services::process_limits::ProcessLimits::unlimited(),
+            auto_start: false,
+            initialization_options: None,
+            name: None,
+            priority,
+        }
+    }
+
+    #[test]
+    fn test_lsp_manager_set_language_servers() {
+        let mut manager = LspManager::new(None);
+
+        manager.set_language_servers(
+            "python".to_string(),
+            vec![
+                server_config("pyright-langserver", 1),
+                server_config("ruff", 0),
+            ],
+        );
+
+        let configs = manager.get_configs("python");
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[0].server_name(), "pyright-langserver");
+        assert_eq!(configs[1].server_name(), "ruff");
+        assert!(manager.get_configs("rust").is_empty());
+        assert_eq!(manager.server_label("python", "ruff"), "python (ruff)");
+
+        manager.set_language_config("rust".to_string(), server_config("rust-analyzer", 0));
+        assert_eq!(manager.server_label("rust", "rust-analyzer"), "rust");
+    }
+
+    #[test]
+    fn test_lsp_manager_try_spawn_all_servers_disabled() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mut manager = LspManager::new(None);
+        manager.set_runtime(rt.handle().clone(), AsyncBridge::new());
+
+        let mut disabled = server_config("ruff", 0);
+        disabled.enabled = false;
+        manager.set_language_servers("python".to_string(), vec![disabled.clone(), disabled]);
+
+        assert_eq!(manager.try_spawn("python"), LspSpawnResult::Failed);
+        assert!(manager.get_or_spawn_all("python").is_empty());
+    }
+
+    #[test]
+    fn test_lsp_feature_is_provided_by() {
+        let capabilities = ServerCapabilities {
+            hover_provider: Some(HoverProviderCapability::Simple(true)),
+            rename_provider: Some(OneOf::Left(false)),
+            completion_provider: Some(Default::default()),
+            ..Default::default()
+        };
+
+        assert!(LspFeature::Hover.is_provided_by(&capabilities));
+        assert!(LspFeature::Completion.is_provided_by(&capabilities));
+        assert!(!LspFeature::Rename.is_provided_by(&capabilities));
+        assert!(!LspFeature::References.is_provided_by(&capabilities));
+        assert!(!LspFeature::PullDiagnostics.is_provided_by(&capabilities));
+    }
+
+    #[test]
+    fn test_file_operation_filter_matches() {
+        use lsp_types::{FileOperationPattern, FileOperationPatternOptions};
+
+        let filter = |glob: &str, matches, ignore_case| FileOperationFilter {
+            scheme: Some("file".to_string()),
+            pattern: FileOperationPattern {
+                glob: glob.to_string(),
+                matches,
+                options: Some(FileOperationPatternOptions {
+                    ignore_case: Some(ignore_case),
+                }),
+            },
+        };
+        let rust_files = filter("**/*.rs", Some(FileOperationPatternKind::File), false);
+        let folders = filter("**", Some(FileOperationPatternKind::Folder), false);
+        let src_files = filter("/project/src/*.{ts,tsx}", None, true);
+
+        assert!(filter_matches(
+            &rust_files,
+            Path::new("/project/src/lib.rs"),
+            false
+        ));
+        assert!(!filter_matches(
+            &rust_files,
+            Path::new("/project/src/lib.rs"),
+            true
+        ));
+        assert!(!filter_matches(
+            &rust_files,
+            Path::new("/project/README.md"),
+            false
+        ));
+        assert!(filter_matches(&folders, Path::new("/project/src"), true));
+        assert!(!filter_matches(
+            &folders,
+            Path::new("/project/src/lib.rs"),
+            false
+        ));
+        assert!(filter_matches(
+            &src_files,
+            Path::new("/project/src/App.TSX"),
+            false
+        ));
+        assert!(!filter_matches(
+            &src_files,
+            Path::new("/project/src/ui/app.ts"),
+            false
+        ));
+
+        let mut other_scheme = rust_files.clone();
+        other_scheme.scheme = Some("untitled".to_string());
+        assert!(!filter_matches(
+            &other_scheme,
+            Path::new("/project/src/lib.rs"),
+            false
+        ));
+
+        let registration = FileOperationRegistrationOptions {
+            filters: vec![rust_files],
+        };
+        let renames = [
+            RenamedFile {
+                old_path: PathBuf::from("/project/src/old.rs"),
+                new_path: PathBuf::from("/project/src/new.rs"),
+                is_dir: false,
+            },
+            RenamedFile {
+                old_path: PathBuf::from("/project/notes.md"),
+                new_path: PathBuf::from("/project/todo.md"),
+                is_dir: false,
+            },
+        ];
+        assert_eq!(
+            registered_renames(&registration, &renames),
+            Some(vec![FileRename {
+                old_uri: "file:///project/src/old.rs".to_string(),
+                new_uri: "file:///project/src/new.rs".to_string(),
+            }])
+        );
     }
 
     #[test]
@@ -560,6 +1018,8 @@ mod tests {
                 process_limits: crate::services::process_limits::ProcessLimits::unlimited(),
                 auto_start: false,
                 initialization_options: None,
+                name: None,
+                priority: 0,
             },
         );
 
@@ -599,6 +1059,8 @@ mod tests {
                 process_limits: crate::services::process_limits::ProcessLimits::unlimited(),
                 auto_start: false,
                 initialization_options: None,
+                name: None,
+                priority: 0,
             },
         );
 
@@ -624,9 +1086,12 @@ mod tests {
                 extensions: vec!["rs".to_string()],
                 grammar: "rust".to_string(),
                 comment_prefix: Some("//".to_string()),
+                block_comment: None,
                 auto_indent: true,
                 highlighter: crate::config::HighlighterPreference::Auto,
                 textmate_grammar: None,
+                formatter: None,
+                format_on_save: false,
             },
         );
         languages.insert(
@@ -635,9 +1100,12 @@ mod tests {
                 extensions: vec!["js".to_string(), "jsx".to_string()],
                 grammar: "javascript".to_string(),
                 comment_prefix: Some("//".to_string()),
+                block_comment: None,
                 auto_indent: true,
                 highlighter: crate::config::HighlighterPreference::Auto,
                 textmate_grammar: None,
+                formatter: None,
+                format_on_save: false,
             },
         );
         languages.insert(
@@ -646,9 +1114,12 @@ mod tests {
                 extensions: vec!["cs".to_string()],
                 grammar: "c_sharp".to_string(),
                 comment_prefix: Some("//".to_string()),
+                block_comment: None,
                 auto_indent: true,
                 highlighter: crate::config::HighlighterPreference::Auto,
                 textmate_grammar: None,
+                formatter: None,
+                format_on_save: false,
             },
         );
         languages
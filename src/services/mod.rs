@@ -5,15 +5,22 @@
 
 pub mod async_bridge;
 pub mod clipboard;
+pub mod dap;
+pub mod formatter;
 pub mod fs;
 #[cfg(target_os = "linux")]
 pub mod gpm;
 pub mod lsp;
 pub mod plugins;
 pub mod process_limits;
+pub mod project_search;
 pub mod recovery;
 pub mod release_checker;
+pub mod remote;
 pub mod signal_handler;
+pub mod streaming_search;
 pub mod terminal;
 pub mod tracing_setup;
+pub mod trash;
+pub mod undo_store;
 pub mod warning_log;
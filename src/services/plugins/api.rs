@@ -7,6 +7,7 @@ use crate::input::command_registry::CommandRegistry;
 use crate::input::commands::Command;
 use crate::model::event::{BufferId, SplitId};
 use crate::services::plugins::hooks::{HookCallback, HookRegistry};
+use crate::services::plugins::permissions::PermissionKind;
 use crate::view::overlay::{OverlayHandle, OverlayNamespace};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -29,6 +30,8 @@ pub enum PluginResponse {
         request_id: u64,
         result: Result<Value, String>,
     },
+    /// Response to RequestPluginPermission: whether the user allowed it
+    PluginPermission { request_id: u64, granted: bool },
 }
 
 /// Information about a cursor in the editor
@@ -607,6 +610,16 @@ pub enum PluginCommand {
     /// Set the internal clipboard content
     SetClipboard { text: String },
 
+    /// Ask the user to grant a capability to a sandboxed plugin
+    /// (answered with PluginResponse::PluginPermission)
+    RequestPluginPermission {
+        plugin: String,
+        kind: PermissionKind,
+        /// What the capability is used for (command, path, language)
+        target: String,
+        request_id: u64,
+    },
+
     /// Delete the current selection in the active buffer
     /// This deletes all selected text across all cursors
     DeleteSelection,
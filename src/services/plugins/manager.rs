@@ -75,17 +75,20 @@ impl PluginManager {
     }
 
     /// Load plugins from a directory.
-    pub fn load_plugins_from_dir(&self, dir: &Path) -> Vec<String> {
+    ///
+    /// Sandboxed plugins are restricted to the capabilities declared in their
+    /// manifests (see `permissions`).
+    pub fn load_plugins_from_dir(&self, dir: &Path, sandboxed: bool) -> Vec<String> {
         #[cfg(feature = "plugins")]
         {
             if let Some(ref manager) = self.inner {
-                return manager.load_plugins_from_dir(dir);
+                return manager.load_plugins_from_dir(dir, sandboxed);
             }
             Vec::new()
         }
         #[cfg(not(feature = "plugins"))]
         {
-            let _ = dir;
+            let _ = (dir, sandboxed);
             Vec::new()
         }
     }
@@ -168,6 +171,15 @@ impl PluginManager {
             .reload_plugin(name)
     }
 
+    /// Unload a plugin by name.
+    #[cfg(feature = "plugins")]
+    pub fn unload_plugin(&self, name: &str) -> anyhow::Result<()> {
+        self.inner
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Plugin system not active"))?
+            .unload_plugin(name)
+    }
+
     /// Check if any handlers are registered for a hook.
     pub fn has_hook_handlers(&self, hook_name: &str) -> bool {
         #[cfg(feature = "plugins")]
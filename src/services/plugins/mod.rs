@@ -1,8 +1,9 @@
 //! Plugin system
 //!
 //! The plugin system provides TypeScript/JavaScript plugin support using deno_core.
-//! When the `plugins` feature is disabled, only the type definitions (api, hooks, event_hooks)
-//! are available - the actual runtime is excluded to avoid deno dependencies.
+//! When the `plugins` feature is disabled, only the type definitions (api, hooks, event_hooks,
+//! permissions) and the package registry (packages) are available - the actual runtime is
+//! excluded to avoid deno dependencies.
 //!
 //! Use `PluginManager` as the main interface - it handles both enabled and disabled cases.
 
@@ -10,6 +11,8 @@ pub mod api;
 pub mod event_hooks;
 pub mod hooks;
 pub mod manager;
+pub mod packages;
+pub mod permissions;
 
 #[cfg(feature = "plugins")]
 pub mod process;
@@ -17,6 +20,8 @@ pub mod process;
 pub mod runtime;
 #[cfg(feature = "plugins")]
 pub mod thread;
+#[cfg(feature = "plugins")]
+pub mod timers;
 
 // Re-export the main interface
 pub use manager::PluginManager;
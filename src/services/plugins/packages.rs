@@ -0,0 +1,749 @@
+//! Plugin packages
+//!
+//! Besides the plugins dropped into `<config dir>/plugins`, the user can
+//! install plugin packages: a git repository, or a local directory, whose
+//! top-level `.ts`/`.js` files are plugins. Packages are recorded in a
+//! registry file (`<config dir>/plugin-packages.json`):
+//!
+//! ```json
+//! {
+//!   "packages": {
+//!     "fresh-todo": {
+//!       "source": { "type": "git", "url": "https://github.com/me/fresh-todo", "ref": "v1.2" },
+//!       "enabled": true,
+//!       "pinned": false,
+//!       "commit": "4f2c9e1..."
+//!     },
+//!     "my-tools": {
+//!       "source": { "type": "path", "path": "/home/me/src/my-tools" },
+//!       "enabled": false
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Git packages are cloned into `<config dir>/plugin-packages/<name>` and
+//! checked out at their `ref` (the remote's default branch if unset).
+//! Updating fetches the ref again, unless the package is pinned to the commit
+//! it's at. Local packages are loaded in place and never need updating.
+//!
+//! Package plugins run sandboxed, like the other plugins installed by the user
+//! (see `permissions`).
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Where a package comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PluginSource {
+    /// A git repository, at a branch, tag or commit
+    Git {
+        url: String,
+        #[serde(default, rename = "ref", skip_serializing_if = "Option::is_none")]
+        rev: Option<String>,
+    },
+    /// A local directory
+    Path { path: PathBuf },
+}
+
+impl PluginSource {
+    /// Parse a source as typed by the user: a git URL, optionally followed by
+    /// `#<ref>`, or the path of a local directory
+    pub fn parse(spec: &str) -> Result<Self, PackageError> {
+        let spec = spec.trim();
+        let (location, rev) = match spec.split_once('#') {
+            Some((location, rev)) => (location, Some(rev)),
+            None => (spec, None),
+        };
+        // Nothing git would take for an option
+        let option_like = location.starts_with('-') || rev.is_some_and(|rev| rev.starts_with('-'));
+        if location.is_empty() || rev.is_some_and(str::is_empty) || option_like {
+            return Err(PackageError::InvalidSource(spec.to_string()));
+        }
+
+        let is_git =
+            location.contains("://") || location.starts_with("git@") || location.ends_with(".git");
+        if is_git {
+            return Ok(Self::Git {
+                url: location.to_string(),
+                rev: rev.map(str::to_string),
+            });
+        }
+        if rev.is_some() {
+            return Err(PackageError::InvalidSource(format!(
+                "{} (only git sources take a #ref)",
+                spec
+            )));
+        }
+        let path = match location.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir()
+                .ok_or_else(|| PackageError::InvalidSource(spec.to_string()))?
+                .join(rest),
+            None => PathBuf::from(location),
+        };
+        let path = path
+            .canonicalize()
+            .map_err(|e| PackageError::InvalidSource(format!("{}: {}", spec, e)))?;
+        if !path.is_dir() {
+            return Err(PackageError::InvalidSource(format!(
+                "{} is not a directory",
+                spec
+            )));
+        }
+        Ok(Self::Path { path })
+    }
+
+    /// Package name derived from the source: the last component of its URL or
+    /// path, without `.git`
+    pub fn default_name(&self) -> String {
+        let location = match self {
+            Self::Git { url, .. } => url.as_str(),
+            Self::Path { path } => path.to_str().unwrap_or_default(),
+        };
+        let last = location
+            .trim_end_matches(['/', '\\'])
+            .rsplit(['/', '\\', ':'])
+            .next()
+            .unwrap_or_default();
+        last.strip_suffix(".git").unwrap_or(last).to_string()
+    }
+}
+
+/// An installed package
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginPackage {
+    pub source: PluginSource,
+    /// Whether the package's plugins are loaded
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Whether updates leave the package at its current commit
+    #[serde(default)]
+    pub pinned: bool,
+    /// Commit a git package is checked out at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// The registry file listing the installed packages
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginRegistry {
+    #[serde(default)]
+    pub packages: BTreeMap<String, PluginPackage>,
+}
+
+/// What an install or update did to a package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackageChange {
+    Installed,
+    /// Checked out at a new commit
+    Updated(String),
+    Unchanged,
+    /// Left alone because it is pinned
+    Pinned,
+}
+
+/// Package error types
+#[derive(Debug)]
+pub enum PackageError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Git(String),
+    InvalidSource(String),
+    InvalidName(String),
+    NotFound(String),
+    AlreadyExists(String),
+    NoPlugins(String),
+    NotGit(String),
+    InvalidRef(String),
+}
+
+impl std::fmt::Display for PackageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackageError::Io(e) => write!(f, "IO error: {}", e),
+            PackageError::Json(e) => write!(f, "Invalid plugin registry: {}", e),
+            PackageError::Git(e) => write!(f, "git failed: {}", e),
+            PackageError::InvalidSource(source) => {
+                write!(f, "Not a git URL or plugin directory: {}", source)
+            }
+            PackageError::InvalidName(name) => write!(f, "Invalid package name: '{}'", name),
+            PackageError::NotFound(name) => write!(f, "No plugin package named '{}'", name),
+            PackageError::AlreadyExists(name) => {
+                write!(f, "A plugin package named '{}' is already installed", name)
+            }
+            PackageError::NoPlugins(name) => {
+                write!(f, "Package '{}' has no .ts or .js plugin files", name)
+            }
+            PackageError::NotGit(name) => {
+                write!(
+                    f,
+                    "Package '{}' is a local directory, not a git repository",
+                    name
+                )
+            }
+            PackageError::InvalidRef(rev) => write!(f, "Invalid git ref: '{}'", rev),
+        }
+    }
+}
+
+impl std::error::Error for PackageError {}
+
+impl From<io::Error> for PackageError {
+    fn from(e: io::Error) -> Self {
+        PackageError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for PackageError {
+    fn from(e: serde_json::Error) -> Self {
+        PackageError::Json(e)
+    }
+}
+
+/// Check that a package name can be used as a directory name
+pub fn validate_package_name(name: &str) -> Result<(), PackageError> {
+    let valid = !name.is_empty()
+        && name.len() <= 100
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(PackageError::InvalidName(name.to_string()))
+    }
+}
+
+/// The plugin files of a package directory
+pub fn plugin_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && matches!(
+                    path.extension().and_then(|ext| ext.to_str()),
+                    Some("ts" | "js")
+                )
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// The installed packages: the registry file and the clones of git packages
+#[derive(Debug, Clone)]
+pub struct PluginPackages {
+    registry_path: PathBuf,
+    packages_dir: PathBuf,
+}
+
+impl PluginPackages {
+    pub fn new(registry_path: PathBuf, packages_dir: PathBuf) -> Self {
+        Self {
+            registry_path,
+            packages_dir,
+        }
+    }
+
+    /// Read the registry (empty if the file doesn't exist yet)
+    pub fn registry(&self) -> Result<PluginRegistry, PackageError> {
+        match std::fs::read_to_string(&self.registry_path) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(PluginRegistry::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save_registry(&self, registry: &PluginRegistry) -> Result<(), PackageError> {
+        if let Some(parent) = self.registry_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(registry)?;
+        std::fs::write(&self.registry_path, content)?;
+        Ok(())
+    }
+
+    /// Apply `change` to the registry entry of `name` and save the registry
+    fn update_entry<T>(
+        &self,
+        name: &str,
+        change: impl FnOnce(&mut PluginPackage) -> Result<T, PackageError>,
+    ) -> Result<T, PackageError> {
+        let mut registry = self.registry()?;
+        let package = registry
+            .packages
+            .get_mut(name)
+            .ok_or_else(|| PackageError::NotFound(name.to_string()))?;
+        let result = change(package)?;
+        self.save_registry(&registry)?;
+        Ok(result)
+    }
+
+    /// Directory holding the plugin files of a package
+    pub fn plugin_dir(&self, name: &str, package: &PluginPackage) -> PathBuf {
+        match &package.source {
+            PluginSource::Git { .. } => self.packages_dir.join(name),
+            PluginSource::Path { path } => path.clone(),
+        }
+    }
+
+    /// Plugin directories of the enabled packages, by package name
+    pub fn enabled_plugin_dirs(&self) -> Result<Vec<(String, PathBuf)>, PackageError> {
+        Ok(self
+            .registry()?
+            .packages
+            .iter()
+            .filter(|(_, package)| package.enabled)
+            .map(|(name, package)| (name.clone(), self.plugin_dir(name, package)))
+            .collect())
+    }
+
+    /// Install the package of `spec` (see `PluginSource::parse`) under `name`,
+    /// or the name derived from the source. Returns the package name.
+    pub fn install(&self, spec: &str, name: Option<&str>) -> Result<String, PackageError> {
+        let source = PluginSource::parse(spec)?;
+        let name = match name {
+            Some(name) => name.trim().to_string(),
+            None => source.default_name(),
+        };
+        validate_package_name(&name)?;
+        if self.registry()?.packages.contains_key(&name) {
+            return Err(PackageError::AlreadyExists(name));
+        }
+
+        let mut package = PluginPackage {
+            source,
+            enabled: true,
+            pinned: false,
+            commit: None,
+        };
+        let dir = self.plugin_dir(&name, &package);
+        if let PluginSource::Git { url, rev } = &package.source {
+            package.commit = Some(self.clone_package(&name, url, rev.as_deref())?);
+        }
+        if plugin_files(&dir).is_empty() {
+            if matches!(package.source, PluginSource::Git { .. }) {
+                let _ = std::fs::remove_dir_all(&dir);
+            }
+            return Err(PackageError::NoPlugins(name));
+        }
+
+        // Re-read the registry: another install may have finished meanwhile
+        let mut registry = self.registry()?;
+        registry.packages.insert(name.clone(), package);
+        self.save_registry(&registry)?;
+        Ok(name)
+    }
+
+    /// Clone a git package and check out `rev`, returning the commit
+    fn clone_package(
+        &self,
+        name: &str,
+        url: &str,
+        rev: Option<&str>,
+    ) -> Result<String, PackageError> {
+        std::fs::create_dir_all(&self.packages_dir)?;
+        let dir = self.packages_dir.join(name);
+        // Left over by an install that didn't complete: not in the registry
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+
+        let cloned =
+            git(&self.packages_dir, &["clone", "--quiet", "--", url, name]).and_then(|_| {
+                if let Some(rev) = rev {
+                    checkout(&dir, rev)?;
+                }
+                head_commit(&dir)
+            });
+        if cloned.is_err() {
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+        cloned
+    }
+
+    /// Update a package to the latest commit of its ref
+    ///
+    /// A git package whose clone is missing (e.g. a registry copied from
+    /// another machine) is cloned again, at its commit if it is pinned.
+    pub fn update(&self, name: &str) -> Result<PackageChange, PackageError> {
+        let registry = self.registry()?;
+        let package = registry
+            .packages
+            .get(name)
+            .ok_or_else(|| PackageError::NotFound(name.to_string()))?;
+        let PluginSource::Git { url, rev } = &package.source else {
+            return Ok(PackageChange::Unchanged);
+        };
+        let dir = self.plugin_dir(name, package);
+
+        let commit = if !dir.exists() {
+            let target = if package.pinned {
+                package.commit.as_deref().or(rev.as_deref())
+            } else {
+                rev.as_deref()
+            };
+            self.clone_package(name, url, target)?
+        } else if package.pinned {
+            return Ok(PackageChange::Pinned);
+        } else {
+            let rev = rev.as_deref().unwrap_or("HEAD");
+            check_ref(rev)?;
+            git(&dir, &["fetch", "--quiet", "--", "origin", rev])?;
+            git(&dir, &["checkout", "--quiet", "--detach", "FETCH_HEAD"])?;
+            head_commit(&dir)?
+        };
+
+        if package.commit.as_deref() == Some(commit.as_str()) {
+            return Ok(PackageChange::Unchanged);
+        }
+        self.update_entry(name, |package| {
+            package.commit = Some(commit.clone());
+            Ok(())
+        })?;
+        Ok(PackageChange::Updated(commit))
+    }
+
+    /// Uninstall a package, deleting its clone if it is a git package
+    pub fn remove(&self, name: &str) -> Result<(), PackageError> {
+        let mut registry = self.registry()?;
+        let package = registry
+            .packages
+            .remove(name)
+            .ok_or_else(|| PackageError::NotFound(name.to_string()))?;
+        if matches!(package.source, PluginSource::Git { .. }) {
+            if let Err(e) = std::fs::remove_dir_all(self.plugin_dir(name, &package)) {
+                if e.kind() != io::ErrorKind::NotFound {
+                    return Err(e.into());
+                }
+            }
+        }
+        self.save_registry(&registry)
+    }
+
+    /// Enable or disable a package
+    pub fn set_enabled(&self, name: &str, enabled: bool) -> Result<(), PackageError> {
+        self.update_entry(name, |package| {
+            package.enabled = enabled;
+            Ok(())
+        })
+    }
+
+    /// Pin a git package, at `rev` if given or else at its current commit, so
+    /// that updates leave it alone. Returns the commit.
+    pub fn pin(&self, name: &str, rev: Option<&str>) -> Result<String, PackageError> {
+        let packages_dir = self.packages_dir.clone();
+        self.update_entry(name, |package| {
+            let PluginSource::Git {
+                rev: source_rev, ..
+            } = &mut package.source
+            else {
+                return Err(PackageError::NotGit(name.to_string()));
+            };
+            let dir = packages_dir.join(name);
+            if let Some(rev) = rev {
+                checkout(&dir, rev)?;
+                *source_rev = Some(rev.to_string());
+            }
+            let commit = head_commit(&dir)?;
+            package.commit = Some(commit.clone());
+            package.pinned = true;
+            Ok(commit)
+        })
+    }
+
+    /// Let updates move a pinned package again
+    pub fn unpin(&self, name: &str) -> Result<(), PackageError> {
+        self.update_entry(name, |package| {
+            package.pinned = false;
+            Ok(())
+        })
+    }
+}
+
+/// Run git in `dir`, returning its trimmed output
+fn git(dir: &Path, args: &[&str]) -> Result<String, PackageError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        // Fail instead of waiting for credentials nobody can type
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .map_err(|e| PackageError::Git(e.to_string()))?;
+    if !output.status.success() {
+        return Err(PackageError::Git(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Check out a branch, tag or commit, fetching it if the clone doesn't have
+/// it (e.g. a branch other than the default one)
+fn checkout(dir: &Path, rev: &str) -> Result<(), PackageError> {
+    // `checkout` has no separator that keeps a ref from being an option
+    check_ref(rev)?;
+    if git(dir, &["checkout", "--quiet", "--detach", rev]).is_ok() {
+        return Ok(());
+    }
+    git(dir, &["fetch", "--quiet", "--", "origin", rev])?;
+    git(dir, &["checkout", "--quiet", "--detach", "FETCH_HEAD"])?;
+    Ok(())
+}
+
+/// Reject refs that git would parse as options, such as `--upload-pack=<cmd>`
+/// from a hand-edited registry
+fn check_ref(rev: &str) -> Result<(), PackageError> {
+    if rev.starts_with('-') {
+        return Err(PackageError::InvalidRef(rev.to_string()));
+    }
+    Ok(())
+}
+
+fn head_commit(dir: &Path) -> Result<String, PackageError> {
+    git(dir, &["rev-parse", "HEAD"])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn run_git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args([
+                "-c",
+                "user.name=Test",
+                "-c",
+                "user.email=test@example.com",
+                "-c",
+                "commit.gpgsign=false",
+            ])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    /// A git repository with a plugin file, committed
+    fn plugin_repo(root: &Path, name: &str) -> PathBuf {
+        let repo = root.join(name);
+        std::fs::create_dir_all(&repo).unwrap();
+        run_git(&repo, &["init", "--quiet"]);
+        commit_plugin(&repo, "v1");
+        repo
+    }
+
+    fn commit_plugin(repo: &Path, version: &str) {
+        std::fs::write(
+            repo.join("hello.ts"),
+            format!("editor.setStatus(\"{}\");\n", version),
+        )
+        .unwrap();
+        run_git(repo, &["add", "."]);
+        run_git(repo, &["commit", "--quiet", "-m", version]);
+    }
+
+    fn packages(root: &Path) -> PluginPackages {
+        PluginPackages::new(
+            root.join("config/plugin-packages.json"),
+            root.join("config/plugin-packages"),
+        )
+    }
+
+    fn file_url(path: &Path) -> String {
+        format!("file://{}", path.display())
+    }
+
+    #[test]
+    fn test_parse_source() {
+        let temp = TempDir::new().unwrap();
+
+        assert_eq!(
+            PluginSource::parse("https://example.com/me/fresh-todo.git#v1.2").unwrap(),
+            PluginSource::Git {
+                url: "https://example.com/me/fresh-todo.git".to_string(),
+                rev: Some("v1.2".to_string()),
+            }
+        );
+        let source = PluginSource::parse("git@example.com:me/fresh-todo.git").unwrap();
+        assert_eq!(source.default_name(), "fresh-todo");
+
+        let path = temp.path().canonicalize().unwrap();
+        let source = PluginSource::parse(path.to_str().unwrap()).unwrap();
+        assert_eq!(source, PluginSource::Path { path: path.clone() });
+        assert_eq!(
+            source.default_name(),
+            path.file_name().unwrap().to_str().unwrap()
+        );
+
+        let missing = temp.path().join("missing");
+        assert!(PluginSource::parse(missing.to_str().unwrap()).is_err());
+        assert!(PluginSource::parse(&format!("{}#main", path.display())).is_err());
+        assert!(PluginSource::parse("https://example.com/x.git#").is_err());
+    }
+
+    #[test]
+    fn test_install_update_and_pin_git_package() {
+        let temp = TempDir::new().unwrap();
+        let repo = plugin_repo(temp.path(), "hello-plugin");
+        let packages = packages(temp.path());
+
+        let name = packages.install(&file_url(&repo), None).unwrap();
+        assert_eq!(name, "hello-plugin");
+        let dirs = packages.enabled_plugin_dirs().unwrap();
+        assert_eq!(dirs.len(), 1);
+        assert_eq!(plugin_files(&dirs[0].1), vec![dirs[0].1.join("hello.ts")]);
+        assert!(matches!(
+            packages.install(&file_url(&repo), None),
+            Err(PackageError::AlreadyExists(_))
+        ));
+
+        assert_eq!(packages.update(&name).unwrap(), PackageChange::Unchanged);
+        commit_plugin(&repo, "v2");
+        let PackageChange::Updated(v2) = packages.update(&name).unwrap() else {
+            panic!("expected an update");
+        };
+        let content = std::fs::read_to_string(dirs[0].1.join("hello.ts")).unwrap();
+        assert!(content.contains("v2"));
+
+        assert_eq!(packages.pin(&name, None).unwrap(), v2);
+        commit_plugin(&repo, "v3");
+        assert_eq!(packages.update(&name).unwrap(), PackageChange::Pinned);
+        let content = std::fs::read_to_string(dirs[0].1.join("hello.ts")).unwrap();
+        assert!(content.contains("v2"));
+
+        packages.unpin(&name).unwrap();
+        assert!(matches!(
+            packages.update(&name).unwrap(),
+            PackageChange::Updated(_)
+        ));
+        let content = std::fs::read_to_string(dirs[0].1.join("hello.ts")).unwrap();
+        assert!(content.contains("v3"));
+
+        packages.remove(&name).unwrap();
+        assert!(!dirs[0].1.exists());
+        assert!(packages.registry().unwrap().packages.is_empty());
+    }
+
+    #[test]
+    fn test_install_at_ref_and_reclone() {
+        let temp = TempDir::new().unwrap();
+        let repo = plugin_repo(temp.path(), "hello-plugin");
+        run_git(&repo, &["tag", "v1"]);
+        commit_plugin(&repo, "v2");
+        let packages = packages(temp.path());
+
+        let spec = format!("{}#v1", file_url(&repo));
+        let name = packages.install(&spec, Some("hello")).unwrap();
+        let registry = packages.registry().unwrap();
+        let package = &registry.packages[&name];
+        let dir = packages.plugin_dir(&name, package);
+        let content = std::fs::read_to_string(dir.join("hello.ts")).unwrap();
+        assert!(content.contains("v1"));
+
+        // The tag doesn't move, so neither does the package
+        assert_eq!(packages.update(&name).unwrap(), PackageChange::Unchanged);
+
+        // A missing clone is restored at the same commit
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(packages.update(&name).unwrap(), PackageChange::Unchanged);
+        let content = std::fs::read_to_string(dir.join("hello.ts")).unwrap();
+        assert!(content.contains("v1"));
+    }
+
+    #[test]
+    fn test_option_like_url_and_ref_are_not_passed_as_options() {
+        let temp = TempDir::new().unwrap();
+        let marker = temp.path().join("pwned");
+        let upload_pack = format!("--upload-pack=touch {}", marker.display());
+        let packages = packages(temp.path());
+
+        assert!(matches!(
+            PluginSource::parse(&format!("{}/x.git", upload_pack)),
+            Err(PackageError::InvalidSource(_))
+        ));
+        assert!(matches!(
+            PluginSource::parse("https://example.com/x.git#--orphan=x"),
+            Err(PackageError::InvalidSource(_))
+        ));
+        assert!(packages.clone_package("evil", &upload_pack, None).is_err());
+
+        let repo = plugin_repo(temp.path(), "hello-plugin");
+        let name = packages.install(&file_url(&repo), None).unwrap();
+        assert!(matches!(
+            packages.pin(&name, Some("--orphan=evil")),
+            Err(PackageError::InvalidRef(_))
+        ));
+
+        // Refs of a copied or edited registry don't go through `parse`
+        let mut registry = packages.registry().unwrap();
+        registry.packages.get_mut(&name).unwrap().source = PluginSource::Git {
+            url: file_url(&repo),
+            rev: Some(upload_pack.clone()),
+        };
+        packages.save_registry(&registry).unwrap();
+        assert!(matches!(
+            packages.update(&name),
+            Err(PackageError::InvalidRef(_))
+        ));
+        assert!(!marker.exists());
+    }
+
+    #[test]
+    fn test_local_package() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("my-tools");
+        std::fs::create_dir_all(&dir).unwrap();
+        let packages = packages(temp.path());
+
+        // Nothing to load
+        assert!(matches!(
+            packages.install(dir.to_str().unwrap(), None),
+            Err(PackageError::NoPlugins(_))
+        ));
+
+        std::fs::write(dir.join("tools.js"), "").unwrap();
+        let name = packages.install(dir.to_str().unwrap(), None).unwrap();
+        assert_eq!(name, "my-tools");
+        assert_eq!(packages.update(&name).unwrap(), PackageChange::Unchanged);
+        assert!(matches!(
+            packages.pin(&name, None),
+            Err(PackageError::NotGit(_))
+        ));
+
+        packages.set_enabled(&name, false).unwrap();
+        assert!(packages.enabled_plugin_dirs().unwrap().is_empty());
+        packages.set_enabled(&name, true).unwrap();
+        assert_eq!(
+            packages.enabled_plugin_dirs().unwrap(),
+            vec![(name.clone(), dir.canonicalize().unwrap())]
+        );
+
+        // Removing a local package leaves its directory alone
+        packages.remove(&name).unwrap();
+        assert!(dir.join("tools.js").exists());
+        assert!(matches!(
+            packages.remove(&name),
+            Err(PackageError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,623 @@
+//! Plugin permissions
+//!
+//! Plugins installed by the user, in the plugins directory of the config dir
+//! (`<config dir>/plugins`) or as packages (see `packages`), run sandboxed:
+//! each one declares the capabilities it needs in a manifest, and every gated
+//! API call is checked against that manifest. The first use of a declared
+//! capability asks the user; grants are stored in the config
+//! (`plugin_permissions`), where the settings UI lists them for review and
+//! revocation. Bundled plugins and plugins of the working directory keep full
+//! access.
+//!
+//! The manifest is either a `<plugin>.manifest.json` sidecar:
+//!
+//! ```json
+//! { "permissions": { "process": ["git"], "fs_read": ["workspace"], "lsp": true } }
+//! ```
+//!
+//! The sidecar may also declare the plugin's settings as a JSON Schema
+//! (`"settings": { "properties": { ... } }`), which the settings UI shows on a
+//! page of its own.
+//!
+//! Without a sidecar, the permissions come from `@permission` lines in the
+//! comment header of the plugin file:
+//!
+//! ```text
+//! // @permission process git rg
+//! // @permission fs-read workspace plugin
+//! // @permission fs-write workspace
+//! // @permission lsp
+//! // @permission clipboard
+//! ```
+//!
+//! Filesystem scopes are `workspace` (the working directory), `plugin` (the
+//! plugin's own directory), `temp`, `~/...`, absolute paths, or `*` for any
+//! path; process lists name the allowed commands exactly, or `*` for any.
+//! Paths and scopes are resolved through the filesystem before they are
+//! compared, so a symlink can't lead out of a scope; the part of a path that
+//! doesn't exist yet is normalized lexically.
+//!
+//! The checks happen in the Rust ops behind the gated API, so calling an op
+//! directly doesn't skip them. The runtime attributes each call to a
+//! [`Caller`]: the plugin whose module, hook handler, command, mode binding or
+//! timer it is running. Calls no plugin can be found for are refused. The
+//! plugins still share one isolate, so a plugin that replaces another
+//! plugin's global functions runs as that plugin.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// A capability gated by the permission model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionKind {
+    /// Spawn external processes (spawnProcess, spawnBackgroundProcess)
+    Process,
+    /// Read files (readFile, readDir, openFile)
+    FsRead,
+    /// Write files (writeFile)
+    FsWrite,
+    /// Send LSP requests (sendLspRequest)
+    Lsp,
+    /// Write to the clipboard (copyToClipboard)
+    Clipboard,
+}
+
+impl PermissionKind {
+    /// Parse a permission name, as used in manifests (`fs-read` and
+    /// `fs_read` are both accepted)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.replace('-', "_").as_str() {
+            "process" => Some(Self::Process),
+            "fs_read" => Some(Self::FsRead),
+            "fs_write" => Some(Self::FsWrite),
+            "lsp" => Some(Self::Lsp),
+            "clipboard" => Some(Self::Clipboard),
+            _ => None,
+        }
+    }
+
+    /// Key of the permission in the `plugin_permissions` config entries
+    pub fn config_key(self) -> &'static str {
+        match self {
+            Self::Process => "process",
+            Self::FsRead => "fs_read",
+            Self::FsWrite => "fs_write",
+            Self::Lsp => "lsp",
+            Self::Clipboard => "clipboard",
+        }
+    }
+
+    /// What the permission allows, completing "wants to ..."
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Process => "run processes",
+            Self::FsRead => "read files",
+            Self::FsWrite => "write files",
+            Self::Lsp => "send LSP requests",
+            Self::Clipboard => "use the clipboard",
+        }
+    }
+}
+
+/// Capabilities declared by a plugin
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestPermissions {
+    /// Commands the plugin may spawn (`*` for any)
+    #[serde(default)]
+    pub process: Vec<String>,
+    /// Scopes the plugin may read files from
+    #[serde(default)]
+    pub fs_read: Vec<String>,
+    /// Scopes the plugin may write files to
+    #[serde(default)]
+    pub fs_write: Vec<String>,
+    /// Whether the plugin may send LSP requests
+    #[serde(default)]
+    pub lsp: bool,
+    /// Whether the plugin may write to the clipboard
+    #[serde(default)]
+    pub clipboard: bool,
+}
+
+/// Manifest of a sandboxed plugin
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginManifest {
+    #[serde(default)]
+    pub permissions: ManifestPermissions,
+    /// JSON Schema (an object with `properties`) of the plugin's settings,
+    /// edited in the settings UI and stored in the `plugin_settings` config
+    #[serde(default)]
+    pub settings: Option<serde_json::Value>,
+}
+
+impl PluginManifest {
+    /// Path of the sidecar manifest of a plugin file
+    pub fn sidecar_path(plugin_path: &Path) -> PathBuf {
+        plugin_path.with_extension("manifest.json")
+    }
+
+    /// Load the manifest of a plugin: the sidecar if there is one, otherwise
+    /// the `@permission` lines of its header. A plugin without either gets
+    /// no capabilities.
+    pub fn load(plugin_path: &Path) -> Result<Self, String> {
+        let sidecar = Self::sidecar_path(plugin_path);
+        let manifest = if sidecar.exists() {
+            let content = std::fs::read_to_string(&sidecar)
+                .map_err(|e| format!("Failed to read {}: {}", sidecar.display(), e))?;
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Invalid manifest {}: {}", sidecar.display(), e))?
+        } else {
+            let source = std::fs::read_to_string(plugin_path)
+                .map_err(|e| format!("Failed to read {}: {}", plugin_path.display(), e))?;
+            Self::from_header(&source)?
+        };
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Parse the `@permission` lines of the leading comments of a plugin
+    pub fn from_header(source: &str) -> Result<Self, String> {
+        let mut manifest = Self::default();
+        for line in source.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some(comment) = ["///", "//", "/**", "/*", "*/", "*"]
+                .iter()
+                .find_map(|prefix| line.strip_prefix(prefix))
+            else {
+                // The header ends at the first line of code
+                break;
+            };
+            let Some(directive) = comment.trim().strip_prefix("@permission") else {
+                continue;
+            };
+
+            let mut words = directive.split_whitespace();
+            let name = words
+                .next()
+                .ok_or_else(|| "@permission needs a permission name".to_string())?;
+            let kind = PermissionKind::from_name(name)
+                .ok_or_else(|| format!("Unknown permission '{}'", name))?;
+            let values = words.map(str::to_string);
+            let permissions = &mut manifest.permissions;
+            match kind {
+                PermissionKind::Process => permissions.process.extend(values),
+                PermissionKind::FsRead => permissions.fs_read.extend(values),
+                PermissionKind::FsWrite => permissions.fs_write.extend(values),
+                PermissionKind::Lsp => permissions.lsp = true,
+                PermissionKind::Clipboard => permissions.clipboard = true,
+            }
+        }
+        Ok(manifest)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some(settings) = &self.settings {
+            if !settings.get("properties").is_some_and(|p| p.is_object()) {
+                return Err("The settings schema needs an object of properties".to_string());
+            }
+        }
+        let scopes = self
+            .permissions
+            .fs_read
+            .iter()
+            .chain(&self.permissions.fs_write);
+        for scope in scopes {
+            let valid = matches!(scope.as_str(), "*" | "workspace" | "plugin" | "temp")
+                || scope == "~"
+                || scope.starts_with("~/")
+                || Path::new(scope).is_absolute();
+            if !valid {
+                return Err(format!("Invalid filesystem scope '{}'", scope));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Code running in the plugin runtime, which the gated API calls it makes
+/// are attributed to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Caller {
+    /// The editor's own scripts
+    Editor,
+    /// A plugin: its module, or a hook handler, command or timer it registered
+    Plugin(String),
+    /// Code no plugin is known for, e.g. an action nobody registered; its
+    /// gated calls are refused
+    Unattributed,
+}
+
+/// Outcome of a permission check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PermissionCheck {
+    /// The call may proceed
+    Allowed,
+    /// The call is refused, with the reason
+    Denied(String),
+    /// The manifest allows the call, but the user hasn't granted it yet
+    NeedsGrant,
+}
+
+/// Directories that filesystem scopes and relative paths resolve against
+#[derive(Debug, Clone)]
+pub struct ScopeContext {
+    /// The editor's working directory (`workspace`)
+    pub working_dir: PathBuf,
+    /// Directory relative paths are resolved against
+    pub cwd: PathBuf,
+}
+
+#[derive(Debug)]
+struct SandboxedPlugin {
+    manifest: PluginManifest,
+    /// Directory of the plugin file (`plugin` scope)
+    dir: PathBuf,
+}
+
+/// The sandboxed plugins and their manifests
+#[derive(Debug, Default)]
+pub struct PluginPermissions {
+    sandboxed: HashMap<String, SandboxedPlugin>,
+}
+
+impl PluginPermissions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restrict a plugin to the capabilities of its manifest
+    pub fn sandbox(&mut self, plugin: &str, manifest: PluginManifest, dir: PathBuf) {
+        self.sandboxed
+            .insert(plugin.to_string(), SandboxedPlugin { manifest, dir });
+    }
+
+    /// Forget a plugin (when it is unloaded)
+    pub fn remove(&mut self, plugin: &str) {
+        self.sandboxed.remove(plugin);
+    }
+
+    /// Check a call of `plugin` needing `kind` on `target` (the command of a
+    /// process, the path of a file operation)
+    ///
+    /// `granted` tells whether the user has granted `kind` to the plugin.
+    pub fn check(
+        &self,
+        plugin: &str,
+        kind: PermissionKind,
+        target: &str,
+        granted: bool,
+        ctx: &ScopeContext,
+    ) -> PermissionCheck {
+        let Some(sandboxed) = self.sandboxed.get(plugin) else {
+            return PermissionCheck::Allowed;
+        };
+        let permissions = &sandboxed.manifest.permissions;
+        let declared = match kind {
+            PermissionKind::Process => permissions
+                .process
+                .iter()
+                .any(|command| command == "*" || command == target),
+            PermissionKind::FsRead => path_in_scopes(target, &permissions.fs_read, sandboxed, ctx),
+            PermissionKind::FsWrite => {
+                path_in_scopes(target, &permissions.fs_write, sandboxed, ctx)
+            }
+            PermissionKind::Lsp => permissions.lsp,
+            PermissionKind::Clipboard => permissions.clipboard,
+        };
+
+        if !declared {
+            let what = if target.is_empty() {
+                kind.description().to_string()
+            } else {
+                format!("{} ({})", kind.description(), target)
+            };
+            PermissionCheck::Denied(format!(
+                "Plugin '{}' has no permission to {}: not declared in its manifest",
+                plugin, what
+            ))
+        } else if granted {
+            PermissionCheck::Allowed
+        } else {
+            PermissionCheck::NeedsGrant
+        }
+    }
+}
+
+/// Whether the user granted `kind` to `plugin` in the config (as JSON)
+pub fn granted_in_config(config: &serde_json::Value, plugin: &str, kind: PermissionKind) -> bool {
+    config
+        .get("plugin_permissions")
+        .and_then(|plugins| plugins.get(plugin))
+        .and_then(|grants| grants.get(kind.config_key()))
+        .and_then(serde_json::Value::as_bool)
+        .unwrap_or(false)
+}
+
+fn path_in_scopes(
+    path: &str,
+    scopes: &[String],
+    plugin: &SandboxedPlugin,
+    ctx: &ScopeContext,
+) -> bool {
+    let path = resolve(&ctx.cwd.join(path));
+    scopes.iter().any(|scope| {
+        let root = match scope.as_str() {
+            "*" => return true,
+            "workspace" => ctx.working_dir.clone(),
+            "plugin" => plugin.dir.clone(),
+            "temp" => std::env::temp_dir(),
+            "~" => match dirs::home_dir() {
+                Some(home) => home,
+                None => return false,
+            },
+            scope => match scope.strip_prefix("~/") {
+                Some(rest) => match dirs::home_dir() {
+                    Some(home) => home.join(rest),
+                    None => return false,
+                },
+                None => PathBuf::from(scope),
+            },
+        };
+        path.starts_with(resolve(&root))
+    })
+}
+
+/// Resolve `path` as the filesystem would, so that a symlink can't lead out
+/// of a scope
+///
+/// The deepest existing ancestor is canonicalized (following symlinks), and
+/// the rest, which doesn't exist yet (e.g. a file about to be written), is
+/// normalized on top of it.
+fn resolve(path: &Path) -> PathBuf {
+    let components: Vec<Component> = path.components().collect();
+    for existing in (1..=components.len()).rev() {
+        let ancestor: PathBuf = components[..existing].iter().collect();
+        if let Ok(canonical) = ancestor.canonicalize() {
+            let rest: PathBuf = components[existing..].iter().collect();
+            return normalize(&canonical.join(rest));
+        }
+    }
+    normalize(path)
+}
+
+/// Resolve `.` and `..` components without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> ScopeContext {
+        ScopeContext {
+            working_dir: PathBuf::from("/work/project"),
+            cwd: PathBuf::from("/work/project"),
+        }
+    }
+
+    fn sandboxed(source: &str) -> PluginPermissions {
+        let mut permissions = PluginPermissions::new();
+        permissions.sandbox(
+            "my_plugin",
+            PluginManifest::from_header(source).unwrap(),
+            PathBuf::from("/config/plugins"),
+        );
+        permissions
+    }
+
+    #[test]
+    fn test_manifest_from_header() {
+        let manifest = PluginManifest::from_header(
+            r#"/// <reference path="./lib/fresh.d.ts" />
+/**
+ * My plugin
+ * @permission process git rg
+ * @permission fs-read workspace
+ */
+// @permission clipboard
+const x = 1;
+// @permission lsp
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.permissions.process, vec!["git", "rg"]);
+        assert_eq!(manifest.permissions.fs_read, vec!["workspace"]);
+        assert!(manifest.permissions.fs_write.is_empty());
+        assert!(manifest.permissions.clipboard);
+        // Directives after the first line of code are ignored
+        assert!(!manifest.permissions.lsp);
+
+        assert!(PluginManifest::from_header("// @permission network").is_err());
+    }
+
+    #[test]
+    fn test_manifest_sidecar_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        let plugin_path = dir.path().join("my_plugin.ts");
+        std::fs::write(&plugin_path, "// @permission clipboard\n").unwrap();
+        assert!(
+            PluginManifest::load(&plugin_path)
+                .unwrap()
+                .permissions
+                .clipboard
+        );
+
+        std::fs::write(
+            PluginManifest::sidecar_path(&plugin_path),
+            r#"{ "permissions": { "process": ["git"], "fs_write": ["relative/dir"] } }"#,
+        )
+        .unwrap();
+        assert!(PluginManifest::load(&plugin_path)
+            .unwrap_err()
+            .contains("relative/dir"));
+
+        std::fs::write(
+            PluginManifest::sidecar_path(&plugin_path),
+            r#"{ "permissions": { "process": ["git"] } }"#,
+        )
+        .unwrap();
+        let manifest = PluginManifest::load(&plugin_path).unwrap();
+        assert_eq!(manifest.permissions.process, vec!["git"]);
+        assert!(!manifest.permissions.clipboard);
+    }
+
+    #[test]
+    fn test_unsandboxed_plugins_are_allowed() {
+        let permissions = sandboxed("");
+        assert_eq!(
+            permissions.check("other", PermissionKind::Process, "rm", false, &ctx()),
+            PermissionCheck::Allowed
+        );
+    }
+
+    #[test]
+    fn test_process_permission() {
+        let permissions = sandboxed("// @permission process git");
+
+        assert_eq!(
+            permissions.check("my_plugin", PermissionKind::Process, "git", false, &ctx()),
+            PermissionCheck::NeedsGrant
+        );
+        assert_eq!(
+            permissions.check("my_plugin", PermissionKind::Process, "git", true, &ctx()),
+            PermissionCheck::Allowed
+        );
+        assert!(matches!(
+            permissions.check(
+                "my_plugin",
+                PermissionKind::Process,
+                "/tmp/git",
+                true,
+                &ctx()
+            ),
+            PermissionCheck::Denied(_)
+        ));
+        assert!(matches!(
+            permissions.check("my_plugin", PermissionKind::Lsp, "", true, &ctx()),
+            PermissionCheck::Denied(_)
+        ));
+    }
+
+    #[test]
+    fn test_filesystem_scopes() {
+        let permissions =
+            sandboxed("// @permission fs-read workspace plugin\n// @permission fs-write /data\n");
+        let check = |kind, path| permissions.check("my_plugin", kind, path, true, &ctx());
+
+        assert_eq!(
+            check(PermissionKind::FsRead, "src/main.rs"),
+            PermissionCheck::Allowed
+        );
+        assert_eq!(
+            check(PermissionKind::FsRead, "/config/plugins/data.json"),
+            PermissionCheck::Allowed
+        );
+        assert!(matches!(
+            check(PermissionKind::FsRead, "../other/secret"),
+            PermissionCheck::Denied(_)
+        ));
+        assert!(matches!(
+            check(PermissionKind::FsRead, "/work/project/../../etc/passwd"),
+            PermissionCheck::Denied(_)
+        ));
+        assert_eq!(
+            check(PermissionKind::FsWrite, "/data/out.txt"),
+            PermissionCheck::Allowed
+        );
+        assert!(matches!(
+            check(PermissionKind::FsWrite, "src/main.rs"),
+            PermissionCheck::Denied(_)
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_filesystem_scopes_follow_symlinks() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        let outside = temp.path().join("outside");
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("secret"), "secret").unwrap();
+        std::os::unix::fs::symlink(&outside, project.join("escape")).unwrap();
+        std::os::unix::fs::symlink(&project, temp.path().join("link")).unwrap();
+
+        let permissions =
+            sandboxed("// @permission fs-read workspace\n// @permission fs-write workspace\n");
+        let ctx = ScopeContext {
+            working_dir: project.clone(),
+            cwd: project.clone(),
+        };
+        let check = |kind, path| permissions.check("my_plugin", kind, path, true, &ctx);
+
+        assert!(matches!(
+            check(PermissionKind::FsRead, "escape/secret"),
+            PermissionCheck::Denied(_)
+        ));
+        // A file that doesn't exist yet is checked against its real directory
+        assert!(matches!(
+            check(PermissionKind::FsWrite, "escape/new/file.txt"),
+            PermissionCheck::Denied(_)
+        ));
+        assert!(matches!(
+            check(PermissionKind::FsRead, "escape/../outside/secret"),
+            PermissionCheck::Denied(_)
+        ));
+        assert_eq!(
+            check(PermissionKind::FsWrite, "src/new.rs"),
+            PermissionCheck::Allowed
+        );
+        // A symlink into the scope is inside it
+        let via_link = temp.path().join("link/src/new.rs");
+        assert_eq!(
+            check(PermissionKind::FsRead, via_link.to_str().unwrap()),
+            PermissionCheck::Allowed
+        );
+    }
+
+    #[test]
+    fn test_granted_in_config() {
+        let config = serde_json::json!({
+            "plugin_permissions": { "my_plugin": { "process": true, "lsp": false } }
+        });
+        assert!(granted_in_config(
+            &config,
+            "my_plugin",
+            PermissionKind::Process
+        ));
+        assert!(!granted_in_config(
+            &config,
+            "my_plugin",
+            PermissionKind::Lsp
+        ));
+        assert!(!granted_in_config(
+            &config,
+            "other",
+            PermissionKind::Process
+        ));
+        assert!(!granted_in_config(
+            &serde_json::Value::Null,
+            "my_plugin",
+            PermissionKind::Process
+        ));
+    }
+}
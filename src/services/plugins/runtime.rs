@@ -45,6 +45,11 @@ use crate::model::event::SplitId;
 use crate::services::plugins::api::{
     EditorStateSnapshot, LayoutHints, PluginCommand, ViewTokenWire,
 };
+use crate::services::plugins::permissions::{
+    granted_in_config, Caller, PermissionCheck, PermissionKind, PluginManifest, PluginPermissions,
+    ScopeContext,
+};
+use crate::services::plugins::timers::PluginTimers;
 use anyhow::{anyhow, Result};
 use deno_core::{
     error::ModuleLoaderError, extension, op2, FastString, JsRuntime, ModuleLoadOptions,
@@ -160,6 +165,31 @@ struct TsRuntimeState {
     background_processes: Rc<RefCell<HashMap<u64, tokio::process::Child>>>,
     /// Next process ID for background processes
     next_process_id: Rc<RefCell<u64>>,
+    /// Pending setTimeout/setInterval timers
+    timers: Rc<RefCell<PluginTimers>>,
+    /// Manifests of the sandboxed plugins
+    permissions: Rc<RefCell<PluginPermissions>>,
+    /// Code the runtime is running, which gated calls are checked for
+    caller: Rc<RefCell<Caller>>,
+    /// Who registered each hook handler, command action or mode binding
+    /// (global JS function name -> caller)
+    callback_owners: Rc<RefCell<HashMap<String, Caller>>>,
+}
+
+impl TsRuntimeState {
+    /// Record that the running code registered the global function `name`
+    /// as a callback; a name claimed by two callers runs unattributed
+    fn claim_callback(&self, name: &str) {
+        let caller = self.caller.borrow().clone();
+        let mut owners = self.callback_owners.borrow_mut();
+        match owners.get_mut(name) {
+            Some(owner) if *owner != caller => *owner = Caller::Unattributed,
+            Some(_) => {}
+            None => {
+                owners.insert(name.to_string(), caller);
+            }
+        }
+    }
 }
 
 /// Display a transient message in the editor's status bar
@@ -265,15 +295,21 @@ fn op_fresh_debug(#[string] message: String) {
 /// Copies the provided text to both the internal and system clipboard.
 /// Uses OSC 52 and arboard for cross-platform compatibility.
 /// @param text - Text to copy to clipboard
-#[op2(fast)]
-fn op_fresh_set_clipboard(state: &mut OpState, #[string] text: String) {
+#[op2(async)]
+async fn op_fresh_set_clipboard(
+    state: Rc<RefCell<OpState>>,
+    #[string] text: String,
+) -> Result<(), JsErrorBox> {
+    await_permission(&state, PermissionKind::Clipboard, "").await?;
+    tracing::debug!("TypeScript plugin set_clipboard: {} chars", text.len());
+    let state = state.borrow();
     if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
         let runtime_state = runtime_state.borrow();
         let _ = runtime_state
             .command_sender
-            .send(PluginCommand::SetClipboard { text: text.clone() });
+            .send(PluginCommand::SetClipboard { text });
     }
-    tracing::debug!("TypeScript plugin set_clipboard: {} chars", text.len());
+    Ok(())
 }
 
 /// Get the buffer ID of the focused editor pane
@@ -939,7 +975,6 @@ fn op_fresh_insert_at_cursor(state: &mut OpState, #[string] text: String) -> boo
 /// @param action - JavaScript function name to call when command is triggered
 /// @param contexts - Comma-separated list of contexts, including both built-in (normal, prompt, popup,
 ///                   fileexplorer, menu) and custom plugin-defined contexts (e.g., "normal,config-editor")
-/// @param source - Plugin source name (empty string for builtin)
 /// @returns true if command was registered
 #[op2(fast)]
 fn op_fresh_register_command(
@@ -948,7 +983,6 @@ fn op_fresh_register_command(
     #[string] description: String,
     #[string] action: String,
     #[string] contexts: String,
-    #[string] source: String,
 ) -> bool {
     if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
         let runtime_state = runtime_state.borrow();
@@ -980,11 +1014,11 @@ fn op_fresh_register_command(
             }
         }
 
-        // Use the explicit source parameter
-        let command_source = if source.is_empty() {
-            crate::input::commands::CommandSource::Builtin
-        } else {
-            crate::input::commands::CommandSource::Plugin(source)
+        // Commands belong to the plugin registering them
+        runtime_state.claim_callback(&action);
+        let command_source = match &*runtime_state.caller.borrow() {
+            Caller::Plugin(plugin) => crate::input::commands::CommandSource::Plugin(plugin.clone()),
+            _ => crate::input::commands::CommandSource::Builtin,
         };
 
         let command = crate::input::commands::Command {
@@ -1043,7 +1077,13 @@ fn op_fresh_set_context(state: &mut OpState, #[string] name: String, active: boo
 /// @param column - Column number to jump to (0 for no jump)
 /// @returns true if file was opened
 #[op2(fast)]
-fn op_fresh_open_file(state: &mut OpState, #[string] path: String, line: u32, column: u32) -> bool {
+fn op_fresh_open_file(
+    state: &mut OpState,
+    #[string] path: String,
+    line: u32,
+    column: u32,
+) -> Result<bool, JsErrorBox> {
+    require_permission(state, PermissionKind::FsRead, &path)?;
     if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
         let runtime_state = runtime_state.borrow();
         let result = runtime_state
@@ -1057,9 +1097,9 @@ fn op_fresh_open_file(state: &mut OpState, #[string] path: String, line: u32, co
                     Some(column as usize)
                 },
             });
-        return result.is_ok();
+        return Ok(result.is_ok());
     }
-    false
+    Ok(false)
 }
 
 /// Get the ID of the focused split pane
@@ -1131,7 +1171,8 @@ fn op_fresh_open_file_in_split(
     #[string] path: String,
     line: u32,
     column: u32,
-) -> bool {
+) -> Result<bool, JsErrorBox> {
+    require_permission(state, PermissionKind::FsRead, &path)?;
     if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
         let runtime_state = runtime_state.borrow();
         let result = runtime_state
@@ -1146,9 +1187,9 @@ fn op_fresh_open_file_in_split(
                     Some(column as usize)
                 },
             });
-        return result.is_ok();
+        return Ok(result.is_ok());
     }
-    false
+    Ok(false)
 }
 
 /// Result from spawnProcess
@@ -1178,6 +1219,7 @@ struct SpawnResult {
 #[op2(async)]
 #[serde]
 async fn op_fresh_spawn_process(
+    state: Rc<RefCell<OpState>>,
     #[string] command: String,
     #[serde] args: Vec<String>,
     #[string] cwd: Option<String>,
@@ -1186,6 +1228,8 @@ async fn op_fresh_spawn_process(
     use tokio::io::{AsyncBufReadExt, BufReader};
     use tokio::process::Command;
 
+    await_permission(&state, PermissionKind::Process, &command).await?;
+
     // Check if we're in a tokio runtime context
     if tokio::runtime::Handle::try_current().is_err() {
         return Err(JsErrorBox::generic(
@@ -1296,6 +1340,8 @@ async fn op_fresh_spawn_background_process(
     use std::process::Stdio;
     use tokio::process::Command;
 
+    await_permission(&state, PermissionKind::Process, &command).await?;
+
     // Build the command
     let mut cmd = Command::new(&command);
     cmd.args(&args);
@@ -1409,6 +1455,181 @@ fn op_fresh_is_process_running(state: &mut OpState, #[bigint] process_id: u64) -
     }
 }
 
+// Timer ops are internal (no op_fresh_ prefix, so they stay out of the
+// generated EditorAPI): plugins use the setTimeout/setInterval globals that
+// wrap them, see timers.rs
+
+/// Schedule a timer; returns its id
+#[op2(fast)]
+fn op_timer_start(state: &mut OpState, delay_ms: f64, repeats: bool) -> u32 {
+    if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
+        let runtime_state = runtime_state.borrow();
+        let owner = runtime_state.caller.borrow().clone();
+        return runtime_state.timers.borrow_mut().schedule(
+            owner,
+            delay_ms,
+            repeats,
+            std::time::Instant::now(),
+        );
+    }
+    0
+}
+
+/// Cancel a timer; returns false if it wasn't scheduled
+#[op2(fast)]
+fn op_timer_cancel(state: &mut OpState, timer_id: u32) -> bool {
+    if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
+        let runtime_state = runtime_state.borrow();
+        return runtime_state.timers.borrow_mut().cancel(timer_id);
+    }
+    false
+}
+
+/// Permission asked of the user for a gated call
+struct PermissionRequest {
+    plugin: String,
+    kind: PermissionKind,
+    /// Receives the user's answer
+    receiver: tokio::sync::oneshot::Receiver<crate::services::plugins::api::PluginResponse>,
+}
+
+/// Check a gated call of the running code needing `kind` on `target` against
+/// the manifest of its plugin and the user's grants (see permissions.rs)
+///
+/// Returns the request sent to the user if the plugin declares the capability
+/// but the user hasn't granted it yet.
+fn check_permission(
+    state: &OpState,
+    kind: PermissionKind,
+    target: &str,
+) -> Result<Option<PermissionRequest>, JsErrorBox> {
+    let runtime_state = state
+        .try_borrow::<Rc<RefCell<TsRuntimeState>>>()
+        .ok_or_else(|| JsErrorBox::generic("Failed to get runtime state"))?;
+    let runtime_state = runtime_state.borrow();
+
+    let plugin = match &*runtime_state.caller.borrow() {
+        Caller::Editor => return Ok(None),
+        Caller::Plugin(plugin) => plugin.clone(),
+        Caller::Unattributed => {
+            return Err(JsErrorBox::generic(format!(
+                "Refused to {}: the call can't be attributed to a plugin",
+                kind.description()
+            )))
+        }
+    };
+
+    let (granted, working_dir) = {
+        let snapshot = runtime_state
+            .state_snapshot
+            .read()
+            .map_err(|_| JsErrorBox::generic("Editor state unavailable"))?;
+        (
+            granted_in_config(&snapshot.config, &plugin, kind),
+            snapshot.working_dir.clone(),
+        )
+    };
+    let ctx = ScopeContext {
+        cwd: std::env::current_dir().unwrap_or_else(|_| working_dir.clone()),
+        working_dir,
+    };
+    match runtime_state
+        .permissions
+        .borrow()
+        .check(&plugin, kind, target, granted, &ctx)
+    {
+        PermissionCheck::Allowed => return Ok(None),
+        PermissionCheck::Denied(reason) => return Err(JsErrorBox::generic(reason)),
+        PermissionCheck::NeedsGrant => {}
+    }
+
+    let request_id = {
+        let mut id = runtime_state.next_request_id.borrow_mut();
+        let current = *id;
+        *id += 1;
+        current
+    };
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    {
+        let mut pending = runtime_state.pending_responses.lock().unwrap();
+        pending.insert(request_id, tx);
+    }
+
+    if runtime_state
+        .command_sender
+        .send(PluginCommand::RequestPluginPermission {
+            plugin: plugin.clone(),
+            kind,
+            target: target.to_string(),
+            request_id,
+        })
+        .is_err()
+    {
+        let mut pending = runtime_state.pending_responses.lock().unwrap();
+        pending.remove(&request_id);
+        return Err(JsErrorBox::generic("Failed to request plugin permission"));
+    }
+
+    Ok(Some(PermissionRequest {
+        plugin,
+        kind,
+        receiver: rx,
+    }))
+}
+
+/// Check a gated call of an async op, waiting for the user's answer if the
+/// capability needs to be granted first
+async fn await_permission(
+    state: &Rc<RefCell<OpState>>,
+    kind: PermissionKind,
+    target: &str,
+) -> Result<(), JsErrorBox> {
+    let request = check_permission(&state.borrow(), kind, target)?;
+    let Some(request) = request else {
+        return Ok(());
+    };
+
+    let response = request
+        .receiver
+        .await
+        .map_err(|_| JsErrorBox::generic("Plugin permission request cancelled"))?;
+
+    match response {
+        crate::services::plugins::api::PluginResponse::PluginPermission { granted, .. } => {
+            if granted {
+                Ok(())
+            } else {
+                Err(JsErrorBox::generic(format!(
+                    "Plugin '{}' was denied permission to {}",
+                    request.plugin,
+                    request.kind.description()
+                )))
+            }
+        }
+        _ => Err(JsErrorBox::generic(
+            "Unexpected plugin response for permission request",
+        )),
+    }
+}
+
+/// Check a gated call of a sync op. Sync ops can't wait for the user: a
+/// capability that needs to be granted first is asked for, and the call fails.
+fn require_permission(
+    state: &OpState,
+    kind: PermissionKind,
+    target: &str,
+) -> Result<(), JsErrorBox> {
+    match check_permission(state, kind, target)? {
+        None => Ok(()),
+        Some(request) => Err(JsErrorBox::generic(format!(
+            "Plugin '{}' needs permission to {}; try again once it is allowed",
+            request.plugin,
+            request.kind.description()
+        ))),
+    }
+}
+
 /// Subscribe to an editor event
 ///
 /// Handler must be a global function name (not a closure).
@@ -1429,6 +1650,7 @@ fn op_fresh_on(
 ) -> bool {
     if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
         let runtime_state = runtime_state.borrow();
+        runtime_state.claim_callback(&handler_name);
         let mut handlers = runtime_state.event_handlers.borrow_mut();
         handlers
             .entry(event_name.clone())
@@ -1878,7 +2100,11 @@ fn op_fresh_set_prompt_suggestions(
 /// @param path - File path (absolute or relative to cwd)
 #[op2(async)]
 #[string]
-async fn op_fresh_read_file(#[string] path: String) -> Result<String, JsErrorBox> {
+async fn op_fresh_read_file(
+    state: Rc<RefCell<OpState>>,
+    #[string] path: String,
+) -> Result<String, JsErrorBox> {
+    await_permission(&state, PermissionKind::FsRead, &path).await?;
     tokio::fs::read_to_string(&path)
         .await
         .map_err(|e| JsErrorBox::generic(format!("Failed to read file {}: {}", path, e)))
@@ -1892,9 +2118,11 @@ async fn op_fresh_read_file(#[string] path: String) -> Result<String, JsErrorBox
 /// @param content - UTF-8 string to write
 #[op2(async)]
 async fn op_fresh_write_file(
+    state: Rc<RefCell<OpState>>,
     #[string] path: String,
     #[string] content: String,
 ) -> Result<(), JsErrorBox> {
+    await_permission(&state, PermissionKind::FsWrite, &path).await?;
     tokio::fs::write(&path, content)
         .await
         .map_err(|e| JsErrorBox::generic(format!("Failed to write file {}: {}", path, e)))
@@ -2097,6 +2325,11 @@ fn op_fresh_read_dir(
             std::path::PathBuf::from(&path)
         }
     };
+    require_permission(
+        state,
+        PermissionKind::FsRead,
+        &resolved_path.to_string_lossy(),
+    )?;
 
     let entries = std::fs::read_dir(&resolved_path)
         .map_err(|e| JsErrorBox::generic(format!("Failed to read directory {}: {}", path, e)))?;
@@ -2486,6 +2719,8 @@ async fn op_fresh_send_lsp_request(
     #[string] method: String,
     #[serde] params: Option<serde_json::Value>,
 ) -> Result<serde_json::Value, JsErrorBox> {
+    await_permission(&state, PermissionKind::Lsp, &language).await?;
+
     let receiver = {
         let state = state.borrow();
         let runtime_state = state
@@ -2562,6 +2797,9 @@ fn op_fresh_define_mode(
 ) -> bool {
     if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
         let runtime_state = runtime_state.borrow();
+        for (_, action) in &bindings {
+            runtime_state.claim_callback(action);
+        }
         let result = runtime_state
             .command_sender
             .send(PluginCommand::DefineMode {
@@ -2841,6 +3079,8 @@ extension!(
         op_fresh_spawn_background_process,
         op_fresh_kill_process,
         op_fresh_is_process_running,
+        op_timer_start,
+        op_timer_cancel,
         op_fresh_get_buffer_info,
         op_fresh_list_buffers,
         op_fresh_get_all_diagnostics,
@@ -2898,6 +3138,14 @@ pub struct TypeScriptRuntime {
     event_handlers: Rc<RefCell<HashMap<String, Vec<String>>>>,
     /// Pending response senders (shared with runtime state for delivering responses)
     pending_responses: PendingResponses,
+    /// Pending plugin timers (shared with runtime state)
+    timers: Rc<RefCell<PluginTimers>>,
+    /// Manifests of the sandboxed plugins (shared with runtime state)
+    permissions: Rc<RefCell<PluginPermissions>>,
+    /// Code being run, set by the entry points below (shared with runtime state)
+    caller: Rc<RefCell<Caller>>,
+    /// Owners of the registered callbacks (shared with runtime state)
+    callback_owners: Rc<RefCell<HashMap<String, Caller>>>,
 }
 
 impl TypeScriptRuntime {
@@ -2931,6 +3179,10 @@ impl TypeScriptRuntime {
 
         tracing::debug!("TypeScriptRuntime::with_state_and_responses: creating runtime state");
         let event_handlers = Rc::new(RefCell::new(HashMap::new()));
+        let timers = Rc::new(RefCell::new(PluginTimers::new()));
+        let permissions = Rc::new(RefCell::new(PluginPermissions::new()));
+        let caller = Rc::new(RefCell::new(Caller::Editor));
+        let callback_owners = Rc::new(RefCell::new(HashMap::new()));
         let runtime_state = Rc::new(RefCell::new(TsRuntimeState {
             state_snapshot,
             command_sender,
@@ -2939,6 +3191,10 @@ impl TypeScriptRuntime {
             next_request_id: Rc::new(RefCell::new(1)),
             background_processes: Rc::new(RefCell::new(HashMap::new())),
             next_process_id: Rc::new(RefCell::new(1)),
+            timers: Rc::clone(&timers),
+            permissions: Rc::clone(&permissions),
+            caller: Rc::clone(&caller),
+            callback_owners: Rc::clone(&callback_owners),
         }));
 
         tracing::debug!(
@@ -2966,6 +3222,8 @@ impl TypeScriptRuntime {
                 const core = Deno.core;
 
                 // Create the editor API object
+                // The gated methods are checked in their ops, for the plugin
+                // the runtime is running (see permissions.rs)
                 const editor = {
                     // Status and logging
                     setStatus(message) {
@@ -2993,7 +3251,8 @@ impl TypeScriptRuntime {
 
                     // Clipboard
                     copyToClipboard(text) {
-                        core.ops.op_fresh_set_clipboard(text);
+                        core.ops.op_fresh_set_clipboard(text)
+                            .catch((e) => core.ops.op_fresh_set_status(String(e.message || e)));
                     },
 
                     // Buffer queries
@@ -3097,9 +3356,7 @@ impl TypeScriptRuntime {
 
                     // Command registration
                     registerCommand(name, description, action, contexts = "") {
-                        // Pass the current plugin source (set by load_module_with_source)
-                        const source = globalThis.__PLUGIN_SOURCE__ || "";
-                        return core.ops.op_fresh_register_command(name, description, action, contexts, source);
+                        return core.ops.op_fresh_register_command(name, description, action, contexts);
                     },
 
                     unregisterCommand(name) {
@@ -3278,8 +3535,12 @@ impl TypeScriptRuntime {
                     },
                 };
 
-                // Make editor globally available
-                globalThis.editor = editor;
+                // Make editor globally available, frozen so that a plugin can't
+                // swap in code that other plugins would run
+                Object.defineProperty(globalThis, "editor", {
+                    value: Object.freeze(editor),
+                    enumerable: true,
+                });
 
                 // Pre-compiled event dispatcher for performance
                 // This avoids recompiling JavaScript code for each event emission
@@ -3295,6 +3556,67 @@ impl TypeScriptRuntime {
                         return true;
                     }
                 };
+
+                globalThis.__callAction = function(actionName) {
+                    const action = globalThis[actionName];
+                    if (typeof action !== 'function') {
+                        throw new Error('Action "' + actionName + '" is not defined as a global function');
+                    }
+                    return action();
+                };
+
+                // Timers: the callbacks live here, the schedule lives in Rust
+                // (see timers.rs) and the plugin thread fires due timers
+                // through __fireTimer
+                const timerCallbacks = new Map();
+
+                function startTimer(callback, delay, args, repeats) {
+                    if (typeof callback !== 'function') {
+                        throw new TypeError('Timer callback must be a function');
+                    }
+                    const id = core.ops.op_timer_start(Number(delay) || 0, repeats);
+                    timerCallbacks.set(id, { callback, args });
+                    return id;
+                }
+
+                function stopTimer(id) {
+                    if (timerCallbacks.delete(id)) {
+                        core.ops.op_timer_cancel(id);
+                    }
+                }
+
+                globalThis.setTimeout = (callback, delay = 0, ...args) =>
+                    startTimer(callback, delay, args, false);
+                globalThis.setInterval = (callback, delay = 0, ...args) =>
+                    startTimer(callback, delay, args, true);
+                globalThis.clearTimeout = stopTimer;
+                globalThis.clearInterval = stopTimer;
+
+                globalThis.__fireTimer = async function(id, repeats) {
+                    const timer = timerCallbacks.get(id);
+                    if (!timer) {
+                        return;
+                    }
+                    if (!repeats) {
+                        timerCallbacks.delete(id);
+                    }
+                    const result = timer.callback(...timer.args);
+                    if (result instanceof Promise) {
+                        await result;
+                    }
+                };
+
+                globalThis.__dropTimers = function(ids) {
+                    for (const id of ids) {
+                        timerCallbacks.delete(id);
+                    }
+                };
+
+                // The runtime calls these as the plugin owning the callback;
+                // keep plugins from replacing them
+                for (const name of ["__eventDispatcher", "__callAction", "__fireTimer", "__dropTimers"]) {
+                    Object.defineProperty(globalThis, name, { writable: false, configurable: false });
+                }
                 "#
                 .to_string(),
             )
@@ -3308,6 +3630,10 @@ impl TypeScriptRuntime {
             js_runtime,
             event_handlers,
             pending_responses,
+            timers,
+            permissions,
+            caller,
+            callback_owners,
         })
     }
 
@@ -3323,6 +3649,9 @@ impl TypeScriptRuntime {
             crate::services::plugins::api::PluginResponse::LspRequest { request_id, .. } => {
                 *request_id
             }
+            crate::services::plugins::api::PluginResponse::PluginPermission {
+                request_id, ..
+            } => *request_id,
         };
 
         let sender = {
@@ -3342,8 +3671,25 @@ impl TypeScriptRuntime {
         &self.pending_responses
     }
 
-    /// Execute JavaScript code directly
+    /// Execute JavaScript code directly, as the editor (with full access)
     pub async fn execute_script(&mut self, name: &'static str, code: &str) -> Result<()> {
+        self.execute_script_as(Caller::Editor, name, code).await
+    }
+
+    /// Execute JavaScript code whose gated calls are attributed to `caller`
+    async fn execute_script_as(
+        &mut self,
+        caller: Caller,
+        name: &'static str,
+        code: &str,
+    ) -> Result<()> {
+        let previous = self.caller.replace(caller);
+        let result = self.run_script(name, code).await;
+        self.caller.replace(previous);
+        result
+    }
+
+    async fn run_script(&mut self, name: &'static str, code: &str) -> Result<()> {
         // Code needs to be FastString for the IntoModuleCodeString trait
         let code_static: FastString = code.to_string().into();
         self.js_runtime
@@ -3359,27 +3705,28 @@ impl TypeScriptRuntime {
         Ok(())
     }
 
-    /// Load and execute a TypeScript/JavaScript module file
+    /// Load and execute a TypeScript/JavaScript module file of no plugin
+    /// (its gated API calls are refused)
     pub async fn load_module(&mut self, path: &str) -> Result<()> {
         self.load_module_with_source(path, "").await
     }
 
     /// Load and execute a TypeScript/JavaScript module file with explicit plugin source
+    ///
+    /// The module's code, and the callbacks it registers, run as that plugin.
     pub async fn load_module_with_source(&mut self, path: &str, plugin_source: &str) -> Result<()> {
-        // Set the plugin source as a global so registerCommand can use it
-        let set_source: FastString = format!(
-            "globalThis.__PLUGIN_SOURCE__ = {};",
-            if plugin_source.is_empty() {
-                "null".to_string()
-            } else {
-                format!("\"{}\"", plugin_source)
-            }
-        )
-        .into();
-        self.js_runtime
-            .execute_script("<set_plugin_source>", set_source)
-            .map_err(|e| anyhow!("Failed to set plugin source: {}", e))?;
+        let caller = if plugin_source.is_empty() {
+            Caller::Unattributed
+        } else {
+            Caller::Plugin(plugin_source.to_string())
+        };
+        let previous = self.caller.replace(caller);
+        let result = self.evaluate_module(path).await;
+        self.caller.replace(previous);
+        result
+    }
 
+    async fn evaluate_module(&mut self, path: &str) -> Result<()> {
         let module_specifier = deno_core::resolve_path(
             path,
             &std::env::current_dir().map_err(|e| anyhow!("Failed to get cwd: {}", e))?,
@@ -3404,39 +3751,41 @@ impl TypeScriptRuntime {
             .await
             .map_err(|e| anyhow!("Module evaluation error: {}", e))?;
 
-        // Clear the plugin source after loading
-        let clear_source: FastString = "globalThis.__PLUGIN_SOURCE__ = null;".to_string().into();
-        self.js_runtime
-            .execute_script("<clear_plugin_source>", clear_source)
-            .map_err(|e| anyhow!("Failed to clear plugin source: {}", e))?;
-
         Ok(())
     }
 
-    /// Execute a global function by name (for plugin actions)
+    /// Execute a global function by name (for plugin actions), as the plugin
+    /// that registered it
     pub async fn execute_action(&mut self, action_name: &str) -> Result<()> {
         let code = format!(
             r#"
             (async () => {{
-                if (typeof globalThis.{} === 'function') {{
-                    const result = globalThis.{}();
-                    if (result instanceof Promise) {{
-                        await result;
-                    }}
-                }} else {{
-                    throw new Error('Action "{}" is not defined as a global function');
+                const result = __callAction({});
+                if (result instanceof Promise) {{
+                    await result;
                 }}
             }})();
             "#,
-            action_name, action_name, action_name
+            serde_json::to_string(action_name).unwrap_or_else(|_| "\"\"".to_string())
         );
 
-        self.execute_script("<action>", &code).await
+        let owner = self.callback_owner(action_name);
+        self.execute_script_as(owner, "<action>", &code).await
+    }
+
+    /// Who registered the global function `name` as a callback
+    fn callback_owner(&self, name: &str) -> Caller {
+        self.callback_owners
+            .borrow()
+            .get(name)
+            .cloned()
+            .unwrap_or(Caller::Unattributed)
     }
 
     /// Emit an event to all registered handlers
     ///
-    /// This calls all global JavaScript functions registered for the given event.
+    /// This calls all global JavaScript functions registered for the given event,
+    /// each as the plugin that registered it.
     /// The event_data is passed as JSON to each handler.
     ///
     /// # Arguments
@@ -3467,16 +3816,17 @@ impl TypeScriptRuntime {
                     event_data
                 );
 
-                match self.js_runtime.execute_script("<emit>", script) {
+                let previous = self.caller.replace(self.callback_owner(handler_name));
+                let dispatched = self.js_runtime.execute_script("<emit>", script);
+                match dispatched {
                     Ok(_) => {
                         let call_elapsed = call_start.elapsed();
 
                         // Run event loop to process any async work (promises)
                         let event_loop_start = std::time::Instant::now();
-                        self.js_runtime
-                            .run_event_loop(Default::default())
-                            .await
-                            .map_err(|e| anyhow!("Event loop error in emit: {}", e))?;
+                        let event_loop = self.js_runtime.run_event_loop(Default::default()).await;
+                        self.caller.replace(previous);
+                        event_loop.map_err(|e| anyhow!("Event loop error in emit: {}", e))?;
                         let event_loop_elapsed = event_loop_start.elapsed();
 
                         tracing::trace!(
@@ -3488,6 +3838,7 @@ impl TypeScriptRuntime {
                         );
                     }
                     Err(e) => {
+                        self.caller.replace(previous);
                         tracing::error!(
                             "Failed to call event handler '{}' for '{}': {:?}",
                             handler_name,
@@ -3527,6 +3878,80 @@ impl TypeScriptRuntime {
             .unwrap_or(false)
     }
 
+    /// When the next plugin timer is due, if any timer is pending
+    pub fn next_timer_deadline(&self) -> Option<std::time::Instant> {
+        self.timers.borrow().next_deadline()
+    }
+
+    /// Run the callbacks of all timers that are due, each as the code that
+    /// created the timer
+    ///
+    /// A failing callback doesn't keep the other timers from firing; the
+    /// first error is returned.
+    pub async fn fire_due_timers(&mut self) -> Result<()> {
+        let due = self.timers.borrow_mut().take_due(std::time::Instant::now());
+        let mut first_error = None;
+        for timer in due {
+            let script = format!("__fireTimer({}, {})", timer.id, timer.repeats);
+            if let Err(e) = self
+                .execute_script_as(timer.owner, "<timer>", &script)
+                .await
+            {
+                tracing::warn!("Timer {} failed: {}", timer.id, e);
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Restrict the plugin `name` loaded from `path` to the capabilities of
+    /// its manifest; call before loading its module
+    pub fn sandbox_plugin(
+        &mut self,
+        name: &str,
+        path: &str,
+        manifest: PluginManifest,
+    ) -> Result<()> {
+        let module_specifier = deno_core::resolve_path(
+            path,
+            &std::env::current_dir().map_err(|e| anyhow!("Failed to get cwd: {}", e))?,
+        )
+        .map_err(|e| anyhow!("Failed to resolve module path '{}': {}", path, e))?;
+        let plugin_dir = module_specifier
+            .to_file_path()
+            .ok()
+            .and_then(|file| file.parent().map(Path::to_path_buf))
+            .unwrap_or_default();
+
+        self.permissions
+            .borrow_mut()
+            .sandbox(name, manifest, plugin_dir);
+        Ok(())
+    }
+
+    /// Forget a plugin that is unloaded: its handlers stay in the isolate,
+    /// but now run unattributed, so their gated calls are refused
+    pub fn forget_plugin(&mut self, name: &str) {
+        self.permissions.borrow_mut().remove(name);
+        let plugin = Caller::Plugin(name.to_string());
+        self.callback_owners
+            .borrow_mut()
+            .retain(|_, owner| *owner != plugin);
+    }
+
+    /// Cancel all timers created by a plugin
+    pub fn clear_plugin_timers(&mut self, plugin: &str) {
+        let ids = self.timers.borrow_mut().cancel_plugin(plugin);
+        if ids.is_empty() {
+            return;
+        }
+        tracing::debug!("Cancelled {} timers of plugin '{}'", ids.len(), plugin);
+        let script: FastString = format!("__dropTimers({:?});", ids).into();
+        if let Err(e) = self.js_runtime.execute_script("<drop_timers>", script) {
+            tracing::warn!("Failed to drop timers of plugin '{}': {}", plugin, e);
+        }
+    }
+
     /// Send a status message to the editor UI
     pub fn send_status(&mut self, message: String) {
         let op_state = self.js_runtime.op_state();
@@ -3555,6 +3980,8 @@ pub struct TsPluginInfo {
     pub path: PathBuf,
     /// Whether the plugin is enabled
     pub enabled: bool,
+    /// Whether the plugin is restricted to the capabilities of its manifest
+    pub sandboxed: bool,
 }
 
 /// TypeScript Plugin Manager - manages TypeScript plugins
@@ -3629,6 +4056,7 @@ impl TypeScriptPluginManager {
                 name: plugin_name,
                 path: path.to_path_buf(),
                 enabled: true,
+                sandboxed: false,
             },
         );
 
@@ -3644,6 +4072,10 @@ impl TypeScriptPluginManager {
             let prefix = format!("{}:", name);
             self.commands.read().unwrap().unregister_by_prefix(&prefix);
 
+            // Stop the plugin's timers so they don't outlive it
+            self.runtime.clear_plugin_timers(name);
+            self.runtime.forget_plugin(name);
+
             // Note: We can't truly unload JavaScript modules from V8,
             // but we can remove the plugin from our tracking
             // Future: could clear registered hooks for this plugin
@@ -3904,7 +4336,8 @@ mod tests {
     async fn test_execute_async_action() {
         let mut runtime = TypeScriptRuntime::new().unwrap();
 
-        // Define an async global function (using Promise.resolve instead of setTimeout)
+        // Define an async global function (timers only fire from the plugin
+        // thread loop, so the action awaits a plain promise)
         runtime
             .execute_script(
                 "<define_async_action>",
@@ -3927,6 +4360,155 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_timers() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let state_snapshot = Arc::new(RwLock::new(EditorStateSnapshot::new()));
+        let mut runtime = TypeScriptRuntime::with_state(state_snapshot, tx).unwrap();
+
+        runtime
+            .execute_script(
+                "<schedule_timers>",
+                r#"
+                setTimeout((a, b) => editor.setStatus("timeout " + (a + b)), 0, 1, 2);
+                const cancelled = setTimeout(() => editor.setStatus("cancelled"), 0);
+                clearTimeout(cancelled);
+                let ticks = 0;
+                const interval = setInterval(() => {
+                    ticks += 1;
+                    editor.setStatus("tick " + ticks);
+                    if (ticks === 2) {
+                        clearInterval(interval);
+                    }
+                }, 0);
+                "#,
+            )
+            .await
+            .unwrap();
+        assert!(runtime.next_timer_deadline().is_some());
+
+        for _ in 0..2 {
+            tokio::time::sleep(crate::services::plugins::timers::MIN_INTERVAL * 2).await;
+            runtime.fire_due_timers().await.unwrap();
+        }
+
+        let messages: Vec<String> = rx
+            .try_iter()
+            .filter_map(|cmd| match cmd {
+                PluginCommand::SetStatus { message } => Some(message),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(messages, vec!["timeout 3", "tick 1", "tick 2"]);
+        assert!(runtime.next_timer_deadline().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sandboxed_plugin_permissions() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let state_snapshot = Arc::new(RwLock::new(EditorStateSnapshot::new()));
+        state_snapshot.write().unwrap().config = serde_json::json!({
+            "plugin_permissions": { "sandboxed_plugin": { "process": true } }
+        });
+        let mut runtime = TypeScriptRuntime::with_state(state_snapshot, tx).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let plugin_path = dir.path().join("sandboxed_plugin.js");
+        std::fs::write(
+            &plugin_path,
+            r#"// @permission process echo
+            globalThis.sandboxed_run = async function() {
+                const attempts = {
+                    "ls": () => editor.spawnProcess("ls", []),
+                    "read": () => editor.readFile("Cargo.toml"),
+                    "direct op": () => Deno.core.ops.op_fresh_spawn_process("ls", [], null),
+                    "readDir": () => editor.readDir("/"),
+                    "openFile": () => editor.openFile("/etc/hosts", 0, 0),
+                    "patch editor": () => { editor.spawnProcess = null; },
+                };
+                for (const [name, attempt] of Object.entries(attempts)) {
+                    try {
+                        await attempt();
+                        editor.setStatus(name + " allowed");
+                    } catch (e) {
+                        editor.setStatus(name + " denied");
+                    }
+                }
+                editor.setStatus("sandbox globals: " + typeof globalThis.__unsandboxPlugin);
+                const result = await editor.spawnProcess("echo", ["hi"]);
+                editor.setStatus("echo: " + result.stdout.trim());
+            };
+            editor.registerCommand("Sandboxed Run", "", "sandboxed_run", "normal");
+
+            // Never registered, so nothing attributes it to the plugin
+            globalThis.unregistered_run = async function() {
+                try {
+                    await editor.spawnProcess("echo", ["hi"]);
+                    editor.setStatus("unattributed allowed");
+                } catch (e) {
+                    editor.setStatus("unattributed denied");
+                }
+            };
+            "#,
+        )
+        .unwrap();
+        let path = plugin_path.to_str().unwrap();
+        let manifest = PluginManifest::load(&plugin_path).unwrap();
+        runtime
+            .sandbox_plugin("sandboxed_plugin", path, manifest)
+            .unwrap();
+        runtime
+            .load_module_with_source(path, "sandboxed_plugin")
+            .await
+            .unwrap();
+
+        runtime.execute_action("sandboxed_run").await.unwrap();
+        runtime.execute_action("unregistered_run").await.unwrap();
+
+        let messages: Vec<String> = rx
+            .try_iter()
+            .filter_map(|cmd| match cmd {
+                PluginCommand::SetStatus { message } => Some(message),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "ls denied",
+                "read denied",
+                "direct op denied",
+                "readDir denied",
+                "openFile denied",
+                "patch editor denied",
+                "sandbox globals: undefined",
+                "echo: hi",
+                "unattributed denied",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clear_plugin_timers() {
+        let mut runtime = TypeScriptRuntime::new().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let plugin_path = dir.path().join("my_plugin.js");
+        std::fs::write(
+            &plugin_path,
+            r#"setInterval(() => { throw new Error("timer of unloaded plugin fired"); }, 0);"#,
+        )
+        .unwrap();
+        runtime
+            .load_module_with_source(plugin_path.to_str().unwrap(), "my_plugin")
+            .await
+            .unwrap();
+        assert!(runtime.next_timer_deadline().is_some());
+
+        runtime.clear_plugin_timers("my_plugin");
+        assert!(runtime.next_timer_deadline().is_none());
+    }
+
     #[tokio::test]
     async fn test_with_editor_state() {
         use crate::services::plugins::api::{BufferInfo, CursorInfo};
@@ -5788,6 +6370,7 @@ mod tests {
                 editor.setStatus("Spawn completed: exit=" + result.exit_code);
                 editor.debug("stdout: " + result.stdout);
             };
+            editor.registerCommand("Test Spawn", "", "test_spawn", "normal");
 
             editor.setStatus("Spawn test plugin loaded");
             "#,
@@ -5872,6 +6455,7 @@ mod tests {
 
                 editor.setStatus("Git log completed: exit=" + result.exit_code + ", lines=" + result.stdout.split("\n").length);
             };
+            editor.registerCommand("Test Git", "", "test_git", "normal");
 
             editor.setStatus("Git test plugin loaded");
             "#,
@@ -5967,6 +6551,7 @@ mod tests {
 
                 editor.debug("Step 4: Virtual buffer created with id=" + bufferId);
             };
+            editor.registerCommand("Test VBuf", "", "test_vbuf", "normal");
 
             editor.setStatus("VBuf test plugin loaded");
             "#,
@@ -8,10 +8,13 @@
 //! - Plugin thread owns JsRuntime and persistent tokio runtime
 //! - Results are sent back via the existing PluginCommand channel
 //! - Async operations complete naturally without runtime destruction
+//! - Plugin timers (setTimeout/setInterval) fire from the same loop, which
+//!   sleeps until the next timer is due when no request arrives first
 
 use crate::input::command_registry::CommandRegistry;
 use crate::services::plugins::api::{EditorStateSnapshot, PluginCommand};
 use crate::services::plugins::hooks::{hook_args_to_json, HookArgs};
+use crate::services::plugins::permissions::PluginManifest;
 use crate::services::plugins::runtime::{TsPluginInfo, TypeScriptRuntime};
 use anyhow::{anyhow, Result};
 use std::cell::RefCell;
@@ -33,6 +36,8 @@ pub enum PluginRequest {
     /// Load all plugins from a directory
     LoadPluginsFromDir {
         dir: PathBuf,
+        /// Restrict the plugins to the capabilities of their manifests
+        sandboxed: bool,
         response: oneshot::Sender<Vec<String>>,
     },
 
@@ -250,13 +255,15 @@ impl PluginThreadHandle {
         rx.recv().map_err(|_| anyhow!("Plugin thread closed"))?
     }
 
-    /// Load all plugins from a directory (blocking)
-    pub fn load_plugins_from_dir(&self, dir: &Path) -> Vec<String> {
+    /// Load all plugins from a directory (blocking), sandboxed or with full
+    /// access
+    pub fn load_plugins_from_dir(&self, dir: &Path, sandboxed: bool) -> Vec<String> {
         let (tx, rx) = oneshot::channel();
         if self
             .request_sender
             .send(PluginRequest::LoadPluginsFromDir {
                 dir: dir.to_path_buf(),
+                sandboxed,
                 response: tx,
             })
             .is_err()
@@ -403,6 +410,9 @@ fn respond_to_pending(
             request_id, ..
         } => *request_id,
         crate::services::plugins::api::PluginResponse::LspRequest { request_id, .. } => *request_id,
+        crate::services::plugins::api::PluginResponse::PluginPermission { request_id, .. } => {
+            *request_id
+        }
     };
 
     let sender = {
@@ -487,8 +497,22 @@ async fn plugin_thread_loop(
     tracing::info!("Plugin thread event loop started");
 
     loop {
-        // Wait for requests (async, no polling/sleeping)
-        match request_receiver.recv().await {
+        // Wait for requests (async, no polling), or for the next plugin timer
+        let next_timer = runtime.borrow().next_timer_deadline();
+        let request = match next_timer {
+            Some(deadline) => {
+                tokio::select! {
+                    request = request_receiver.recv() => request,
+                    _ = tokio::time::sleep_until(tokio::time::Instant::from_std(deadline)) => {
+                        fire_due_timers(Rc::clone(&runtime)).await;
+                        continue;
+                    }
+                }
+            }
+            None => request_receiver.recv().await,
+        };
+
+        match request {
             Some(PluginRequest::ExecuteAction {
                 action_name,
                 response,
@@ -550,6 +574,15 @@ async fn execute_action_with_hooks(
     let _ = response.send(result);
 }
 
+/// Fire the plugin timers that are due, surfacing failures like hook errors
+async fn fire_due_timers(runtime: Rc<RefCell<TypeScriptRuntime>>) {
+    if let Err(e) = runtime.borrow_mut().fire_due_timers().await {
+        let error_msg = format!("Plugin error in timer: {}", e);
+        tracing::error!("{}", error_msg);
+        runtime.borrow_mut().send_status(error_msg);
+    }
+}
+
 /// Run a hook with Rc<RefCell<TypeScriptRuntime>>
 async fn run_hook_internal_rc(
     runtime: Rc<RefCell<TypeScriptRuntime>>,
@@ -574,17 +607,22 @@ async fn handle_request(
 ) -> bool {
     match request {
         PluginRequest::LoadPlugin { path, response } => {
-            let result = load_plugin_internal(Rc::clone(&runtime), plugins, &path).await;
+            let result = load_plugin_internal(Rc::clone(&runtime), plugins, &path, false).await;
             let _ = response.send(result);
         }
 
-        PluginRequest::LoadPluginsFromDir { dir, response } => {
-            let errors = load_plugins_from_dir_internal(Rc::clone(&runtime), plugins, &dir).await;
+        PluginRequest::LoadPluginsFromDir {
+            dir,
+            sandboxed,
+            response,
+        } => {
+            let errors =
+                load_plugins_from_dir_internal(Rc::clone(&runtime), plugins, &dir, sandboxed).await;
             let _ = response.send(errors);
         }
 
         PluginRequest::UnloadPlugin { name, response } => {
-            let result = unload_plugin_internal(plugins, commands, &name);
+            let result = unload_plugin_internal(&runtime, plugins, commands, &name);
             let _ = response.send(result);
         }
 
@@ -646,6 +684,7 @@ async fn load_plugin_internal(
     runtime: Rc<RefCell<TypeScriptRuntime>>,
     plugins: &mut HashMap<String, TsPluginInfo>,
     path: &Path,
+    sandboxed: bool,
 ) -> Result<()> {
     let plugin_name = path
         .file_stem()
@@ -664,6 +703,16 @@ async fn load_plugin_internal(
         .to_str()
         .ok_or_else(|| anyhow!("Invalid path encoding"))?;
 
+    // Sandbox before evaluating the module: its top-level code is gated too.
+    // A plugin that fails to load stays sandboxed, as handlers it registered
+    // may still run.
+    if sandboxed {
+        let manifest = PluginManifest::load(path).map_err(|e| anyhow!(e))?;
+        runtime
+            .borrow_mut()
+            .sandbox_plugin(&plugin_name, path_str, manifest)?;
+    }
+
     let load_start = std::time::Instant::now();
     runtime
         .borrow_mut()
@@ -684,6 +733,7 @@ async fn load_plugin_internal(
             name: plugin_name.clone(),
             path: path.to_path_buf(),
             enabled: true,
+            sandboxed,
         },
     );
 
@@ -701,6 +751,7 @@ async fn load_plugins_from_dir_internal(
     runtime: Rc<RefCell<TypeScriptRuntime>>,
     plugins: &mut HashMap<String, TsPluginInfo>,
     dir: &Path,
+    sandboxed: bool,
 ) -> Vec<String> {
     tracing::debug!(
         "load_plugins_from_dir_internal: scanning directory {:?}",
@@ -724,7 +775,8 @@ async fn load_plugins_from_dir_internal(
                         "load_plugins_from_dir_internal: attempting to load {:?}",
                         path
                     );
-                    if let Err(e) = load_plugin_internal(Rc::clone(&runtime), plugins, &path).await
+                    if let Err(e) =
+                        load_plugin_internal(Rc::clone(&runtime), plugins, &path, sandboxed).await
                     {
                         let err = format!("Failed to load {:?}: {}", path, e);
                         tracing::error!("{}", err);
@@ -751,6 +803,7 @@ async fn load_plugins_from_dir_internal(
 
 /// Unload a plugin
 fn unload_plugin_internal(
+    runtime: &Rc<RefCell<TypeScriptRuntime>>,
     plugins: &mut HashMap<String, TsPluginInfo>,
     commands: &Arc<RwLock<CommandRegistry>>,
     name: &str,
@@ -762,6 +815,10 @@ fn unload_plugin_internal(
         let prefix = format!("{}:", name);
         commands.read().unwrap().unregister_by_prefix(&prefix);
 
+        // Stop the plugin's timers so they don't outlive it
+        runtime.borrow_mut().clear_plugin_timers(name);
+        runtime.borrow_mut().forget_plugin(name);
+
         Ok(())
     } else {
         Err(anyhow!("Plugin '{}' not found", name))
@@ -775,14 +832,13 @@ async fn reload_plugin_internal(
     commands: &Arc<RwLock<CommandRegistry>>,
     name: &str,
 ) -> Result<()> {
-    let path = plugins
+    let (path, sandboxed) = plugins
         .get(name)
-        .ok_or_else(|| anyhow!("Plugin '{}' not found", name))?
-        .path
-        .clone();
+        .map(|info| (info.path.clone(), info.sandboxed))
+        .ok_or_else(|| anyhow!("Plugin '{}' not found", name))?;
 
-    unload_plugin_internal(plugins, commands, name)?;
-    load_plugin_internal(runtime, plugins, &path).await?;
+    unload_plugin_internal(&runtime, plugins, commands, name)?;
+    load_plugin_internal(runtime, plugins, &path, sandboxed).await?;
 
     Ok(())
 }
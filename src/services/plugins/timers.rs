@@ -0,0 +1,279 @@
+//! Plugin timers
+//!
+//! Bookkeeping behind the `setTimeout`/`setInterval` globals of the plugin
+//! runtime. Timers don't run as pending async ops (the runtime drives its
+//! event loop to completion after every script, so a pending interval would
+//! never let it return). Instead they are kept here, the plugin thread sleeps
+//! until [`PluginTimers::next_deadline`], and the runtime fires whatever
+//! [`PluginTimers::take_due`] returns.
+//!
+//! Each timer remembers the plugin that created it, so that its callback
+//! runs with that plugin's permissions and unloading or reloading the plugin
+//! cancels it.
+
+use super::permissions::Caller;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Shortest delay of a `setTimeout`
+pub const MIN_TIMEOUT: Duration = Duration::from_millis(4);
+
+/// Shortest period of a `setInterval` (one frame), so that an interval of 0
+/// doesn't keep the plugin thread busy
+pub const MIN_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Longest delay, as in browsers (2^31 - 1 ms, about 24.8 days)
+pub const MAX_DELAY: Duration = Duration::from_millis(i32::MAX as u64);
+
+/// Identifier of a timer, returned to JS by `setTimeout`/`setInterval`
+pub type TimerId = u32;
+
+#[derive(Debug)]
+struct Timer {
+    /// Code that created the timer, which its callback runs as
+    owner: Caller,
+    deadline: Instant,
+    /// Period of an interval (None for a timeout)
+    interval: Option<Duration>,
+}
+
+/// A timer that is due, as returned by [`PluginTimers::take_due`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DueTimer {
+    pub id: TimerId,
+    /// Whether the timer stays scheduled (an interval)
+    pub repeats: bool,
+    /// Code that created the timer
+    pub owner: Caller,
+}
+
+/// Pending timers of the plugin runtime
+#[derive(Debug, Default)]
+pub struct PluginTimers {
+    timers: HashMap<TimerId, Timer>,
+    next_id: TimerId,
+}
+
+impl PluginTimers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Schedule a timer firing `delay_ms` after `now` (and then every
+    /// `delay_ms` if `repeats`), clamped to [`MIN_TIMEOUT`] or
+    /// [`MIN_INTERVAL`] and [`MAX_DELAY`]
+    pub fn schedule(
+        &mut self,
+        owner: Caller,
+        delay_ms: f64,
+        repeats: bool,
+        now: Instant,
+    ) -> TimerId {
+        let min = if repeats { MIN_INTERVAL } else { MIN_TIMEOUT };
+        let delay = if delay_ms.is_finite() && delay_ms > 0.0 {
+            Duration::from_micros((delay_ms.min(MAX_DELAY.as_millis() as f64) * 1000.0) as u64)
+        } else {
+            Duration::ZERO
+        }
+        .max(min);
+
+        self.next_id += 1;
+        let id = self.next_id;
+        self.timers.insert(
+            id,
+            Timer {
+                owner,
+                deadline: now + delay,
+                interval: repeats.then_some(delay),
+            },
+        );
+        id
+    }
+
+    /// Cancel a timer; returns false if it wasn't scheduled
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        self.timers.remove(&id).is_some()
+    }
+
+    /// Cancel all timers of a plugin, returning their ids
+    pub fn cancel_plugin(&mut self, plugin: &str) -> Vec<TimerId> {
+        let mut ids: Vec<TimerId> = self
+            .timers
+            .iter()
+            .filter(|(_, timer)| matches!(&timer.owner, Caller::Plugin(owner) if owner == plugin))
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort_unstable();
+        for id in &ids {
+            self.timers.remove(id);
+        }
+        ids
+    }
+
+    /// The earliest deadline of any timer
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.timers.values().map(|timer| timer.deadline).min()
+    }
+
+    /// Take the timers due at `now`, in deadline order
+    ///
+    /// Timeouts are removed; intervals are rescheduled one period later (or
+    /// one period after `now` if a whole period was missed, so missed ticks
+    /// aren't fired in a burst).
+    pub fn take_due(&mut self, now: Instant) -> Vec<DueTimer> {
+        let mut due: Vec<(Instant, TimerId)> = self
+            .timers
+            .iter()
+            .filter(|(_, timer)| timer.deadline <= now)
+            .map(|(id, timer)| (timer.deadline, *id))
+            .collect();
+        due.sort_unstable();
+
+        due.into_iter()
+            .map(|(_, id)| {
+                let timer = self.timers.get_mut(&id).expect("due timer exists");
+                match timer.interval {
+                    Some(interval) => {
+                        let next = timer.deadline + interval;
+                        timer.deadline = if next <= now { now + interval } else { next };
+                        DueTimer {
+                            id,
+                            repeats: true,
+                            owner: timer.owner.clone(),
+                        }
+                    }
+                    None => {
+                        let timer = self.timers.remove(&id).expect("due timer exists");
+                        DueTimer {
+                            id,
+                            repeats: false,
+                            owner: timer.owner,
+                        }
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Number of pending timers
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    /// Whether no timers are pending
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeout_fires_once() {
+        let mut timers = PluginTimers::new();
+        let now = Instant::now();
+        let id = timers.schedule(Caller::Editor, 100.0, false, now);
+
+        assert!(timers.take_due(now + Duration::from_millis(50)).is_empty());
+        assert_eq!(
+            timers.take_due(now + Duration::from_millis(100)),
+            vec![DueTimer {
+                id,
+                repeats: false,
+                owner: Caller::Editor
+            }]
+        );
+        assert!(timers.is_empty());
+    }
+
+    #[test]
+    fn test_interval_reschedules() {
+        let mut timers = PluginTimers::new();
+        let now = Instant::now();
+        let id = timers.schedule(Caller::Editor, 100.0, true, now);
+
+        let due = timers.take_due(now + Duration::from_millis(100));
+        assert_eq!(
+            due,
+            vec![DueTimer {
+                id,
+                repeats: true,
+                owner: Caller::Editor
+            }]
+        );
+        assert_eq!(
+            timers.next_deadline(),
+            Some(now + Duration::from_millis(200))
+        );
+
+        // Falling far behind doesn't queue up missed ticks
+        let late = now + Duration::from_millis(1000);
+        assert_eq!(timers.take_due(late).len(), 1);
+        assert!(timers.next_deadline().unwrap() > late);
+    }
+
+    #[test]
+    fn test_delays_are_clamped() {
+        let mut timers = PluginTimers::new();
+        let now = Instant::now();
+
+        timers.schedule(Caller::Editor, 0.0, false, now);
+        assert_eq!(timers.next_deadline(), Some(now + MIN_TIMEOUT));
+
+        let mut timers = PluginTimers::new();
+        timers.schedule(Caller::Editor, f64::NAN, true, now);
+        assert_eq!(timers.next_deadline(), Some(now + MIN_INTERVAL));
+
+        let mut timers = PluginTimers::new();
+        timers.schedule(Caller::Editor, 1e12, false, now);
+        assert_eq!(timers.next_deadline(), Some(now + MAX_DELAY));
+    }
+
+    #[test]
+    fn test_due_timers_in_deadline_order() {
+        let mut timers = PluginTimers::new();
+        let now = Instant::now();
+        let late = timers.schedule(Caller::Editor, 50.0, false, now);
+        let early = timers.schedule(Caller::Editor, 10.0, false, now);
+
+        let ids: Vec<TimerId> = timers
+            .take_due(now + Duration::from_millis(60))
+            .into_iter()
+            .map(|due| due.id)
+            .collect();
+        assert_eq!(ids, vec![early, late]);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut timers = PluginTimers::new();
+        let now = Instant::now();
+        let id = timers.schedule(Caller::Editor, 10.0, true, now);
+
+        assert!(timers.cancel(id));
+        assert!(!timers.cancel(id));
+        assert!(timers.take_due(now + Duration::from_secs(1)).is_empty());
+    }
+
+    #[test]
+    fn test_cancel_plugin() {
+        let mut timers = PluginTimers::new();
+        let now = Instant::now();
+        let a1 = timers.schedule(Caller::Plugin("a".to_string()), 10.0, false, now);
+        let b = timers.schedule(Caller::Plugin("b".to_string()), 10.0, true, now);
+        let a2 = timers.schedule(Caller::Plugin("a".to_string()), 20.0, true, now);
+
+        assert_eq!(timers.cancel_plugin("a"), vec![a1, a2]);
+        assert_eq!(timers.len(), 1);
+        assert_eq!(
+            timers.take_due(now + Duration::from_millis(20)),
+            vec![DueTimer {
+                id: b,
+                repeats: true,
+                owner: Caller::Plugin("b".to_string())
+            }]
+        );
+    }
+}
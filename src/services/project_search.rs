@@ -0,0 +1,490 @@
+//! Project-wide search
+//!
+//! Files are found with the `ignore` crate's walker, which skips hidden and
+//! gitignored files, and are searched on a background thread. Each file is
+//! searched as the text it would have in a buffer (decoded to UTF-8, line
+//! endings normalized), so match offsets can be used directly as buffer
+//! positions. Open buffers are searched instead of their files on disk, which
+//! makes unsaved edits visible to the search. Results are sent back to the
+//! main loop in batches via the async bridge.
+
+use crate::model::buffer::TextBuffer;
+use crate::services::async_bridge::AsyncMessage;
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+use regex::{Regex, RegexBuilder};
+use std::collections::HashMap;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+/// Search stops once this many matches have been found
+pub const MAX_PROJECT_MATCHES: usize = 10_000;
+
+/// Bytes of line text kept on each side of a match for display
+const CONTEXT_BYTES: usize = 200;
+
+/// How often found matches are sent to the editor while searching
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What to search for and where
+#[derive(Debug, Clone, Default)]
+pub struct ProjectSearchQuery {
+    pub pattern: String,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub use_regex: bool,
+    /// Text to replace matches with; in regex mode `$1` and `${name}` refer
+    /// to capture groups
+    pub replacement: Option<String>,
+    /// Only search files matching one of these globs (all files if empty)
+    pub include: Vec<String>,
+    /// Skip files matching any of these globs
+    pub exclude: Vec<String>,
+}
+
+impl ProjectSearchQuery {
+    /// Build the regex for the pattern and search options
+    pub fn regex(&self) -> Result<Regex, regex::Error> {
+        let pattern = if self.use_regex {
+            self.pattern.clone()
+        } else {
+            regex::escape(&self.pattern)
+        };
+        let pattern = if self.whole_word {
+            format!(r"\b(?:{})\b", pattern)
+        } else {
+            pattern
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .multi_line(true)
+            .build()
+    }
+}
+
+/// A single match in a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectMatch {
+    /// Byte range of the match in the file's text
+    pub range: Range<usize>,
+    /// Zero-based line of the start of the match
+    pub line: usize,
+    /// Zero-based column (in characters) of the start of the match
+    pub column: usize,
+    /// The matched text
+    pub text: String,
+    /// Text the match is replaced with, if the search has a replacement
+    pub replacement: Option<String>,
+    /// The part of the line around the match, for display
+    pub context: String,
+    /// Byte range of the match within `context` (cut off at the end of the line)
+    pub context_match: Range<usize>,
+}
+
+impl ProjectMatch {
+    /// The context line with the replacement applied
+    pub fn replaced_context(&self) -> Option<String> {
+        let replacement = self.replacement.as_ref()?;
+        Some(format!(
+            "{}{}{}",
+            &self.context[..self.context_match.start],
+            replacement,
+            &self.context[self.context_match.end..]
+        ))
+    }
+}
+
+/// All matches found in one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMatches {
+    pub path: PathBuf,
+    pub matches: Vec<ProjectMatch>,
+}
+
+/// Find all matches of `regex` in `text`, computing each match's replacement
+/// if `query` has one
+pub fn find_matches(query: &ProjectSearchQuery, regex: &Regex, text: &str) -> Vec<ProjectMatch> {
+    let mut matches = Vec::new();
+    let mut line = 0;
+    let mut counted_to = 0;
+
+    for captures in regex.captures_iter(text) {
+        let Some(found) = captures.get(0) else {
+            continue;
+        };
+        let range = found.range();
+
+        line += text[counted_to..range.start].matches('\n').count();
+        counted_to = range.start;
+        let line_start = text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = text[range.start..]
+            .find('\n')
+            .map_or(text.len(), |i| range.start + i);
+
+        let context_start = floor_char_boundary(
+            text,
+            range.start.saturating_sub(CONTEXT_BYTES).max(line_start),
+        );
+        let match_end = range.end.min(line_end);
+        let context_end = ceil_char_boundary(text, (match_end + CONTEXT_BYTES).min(line_end));
+
+        let replacement = query.replacement.as_ref().map(|replacement| {
+            if query.use_regex {
+                let mut expanded = String::new();
+                captures.expand(replacement, &mut expanded);
+                expanded
+            } else {
+                replacement.clone()
+            }
+        });
+
+        matches.push(ProjectMatch {
+            line,
+            column: text[line_start..range.start].chars().count(),
+            text: found.as_str().to_string(),
+            replacement,
+            context: text[context_start..context_end].to_string(),
+            context_match: range.start - context_start..match_end - context_start,
+            range,
+        });
+    }
+    matches
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn ceil_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+/// Walk the files under `root` matching the query's include and exclude
+/// globs and search them, calling `on_file` for each file with matches
+///
+/// `open_buffers` maps paths to the text of open buffers, which is searched
+/// instead of the file on disk. Files larger than `max_file_size` and binary
+/// files are skipped. Returns whether the search stopped early because
+/// [`MAX_PROJECT_MATCHES`] was reached.
+pub fn search_project(
+    root: &Path,
+    query: &ProjectSearchQuery,
+    open_buffers: &HashMap<PathBuf, String>,
+    max_file_size: u64,
+    cancel: &AtomicBool,
+    mut on_file: impl FnMut(FileMatches),
+) -> io::Result<bool> {
+    let regex = query
+        .regex()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+
+    let mut overrides = OverrideBuilder::new(root);
+    for glob in &query.include {
+        overrides.add(glob).map_err(invalid_glob)?;
+    }
+    for glob in &query.exclude {
+        overrides.add(&format!("!{}", glob)).map_err(invalid_glob)?;
+    }
+    let walker = WalkBuilder::new(root)
+        .overrides(overrides.build().map_err(invalid_glob)?)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+
+    let mut total = 0;
+    for entry in walker {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                tracing::debug!("Project search skipping entry: {}", e);
+                continue;
+            }
+        };
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+
+        let path = entry.path();
+        let text = match open_buffers.get(path) {
+            Some(text) => text.clone(),
+            None => match read_file_text(path, max_file_size) {
+                Some(text) => text,
+                None => continue,
+            },
+        };
+
+        let mut matches = find_matches(query, &regex, &text);
+        if matches.is_empty() {
+            continue;
+        }
+        let truncated = total + matches.len() >= MAX_PROJECT_MATCHES;
+        matches.truncate(MAX_PROJECT_MATCHES - total);
+        total += matches.len();
+        on_file(FileMatches {
+            path: path.to_path_buf(),
+            matches,
+        });
+        if truncated {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Read a file as buffer text, or `None` if it's too large, binary or unreadable
+fn read_file_text(path: &Path, max_file_size: u64) -> Option<String> {
+    let size = std::fs::metadata(path).ok()?.len();
+    if size > max_file_size {
+        return None;
+    }
+    let bytes = std::fs::read(path).ok()?;
+    TextBuffer::decode_file_text(bytes)
+}
+
+fn invalid_glob(error: ignore::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, error.to_string())
+}
+
+static NEXT_PROJECT_SEARCH_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Handle to a project search running on a background thread
+///
+/// Dropping the handle cancels the search.
+#[derive(Debug)]
+pub struct ProjectSearch {
+    id: u64,
+    cancel: Arc<AtomicBool>,
+}
+
+impl ProjectSearch {
+    /// Start searching the project under `root` on a blocking thread of `runtime`
+    ///
+    /// Matches are reported through [`AsyncMessage::ProjectSearchProgress`]
+    /// and completion through [`AsyncMessage::ProjectSearchFinished`], both
+    /// tagged with [`ProjectSearch::id`].
+    pub fn spawn(
+        runtime: &tokio::runtime::Runtime,
+        sender: mpsc::Sender<AsyncMessage>,
+        root: PathBuf,
+        query: ProjectSearchQuery,
+        open_buffers: HashMap<PathBuf, String>,
+        max_file_size: u64,
+    ) -> Self {
+        let id = NEXT_PROJECT_SEARCH_ID.fetch_add(1, Ordering::Relaxed);
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let thread_cancel = cancel.clone();
+        runtime.spawn_blocking(move || {
+            let mut pending = Vec::new();
+            let mut last_sent = Instant::now();
+            let result = search_project(
+                &root,
+                &query,
+                &open_buffers,
+                max_file_size,
+                &thread_cancel,
+                |file| {
+                    pending.push(file);
+                    if last_sent.elapsed() >= PROGRESS_INTERVAL {
+                        let _ = sender.send(AsyncMessage::ProjectSearchProgress {
+                            search_id: id,
+                            files: std::mem::take(&mut pending),
+                        });
+                        last_sent = Instant::now();
+                    }
+                },
+            );
+            if thread_cancel.load(Ordering::Relaxed) {
+                return;
+            }
+            if !pending.is_empty() {
+                let _ = sender.send(AsyncMessage::ProjectSearchProgress {
+                    search_id: id,
+                    files: pending,
+                });
+            }
+            let (truncated, error) = match result {
+                Ok(truncated) => (truncated, None),
+                Err(e) => (false, Some(e.to_string())),
+            };
+            let _ = sender.send(AsyncMessage::ProjectSearchFinished {
+                search_id: id,
+                truncated,
+                error,
+            });
+        });
+
+        Self { id, cancel }
+    }
+
+    /// Identifier used to tag this search's async messages
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Stop the search; no further messages will be sent
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for ProjectSearch {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(pattern: &str) -> ProjectSearchQuery {
+        ProjectSearchQuery {
+            pattern: pattern.to_string(),
+            case_sensitive: true,
+            ..Default::default()
+        }
+    }
+
+    fn search(root: &Path, query: &ProjectSearchQuery) -> Vec<FileMatches> {
+        search_in(root, query, &HashMap::new())
+    }
+
+    fn search_in(
+        root: &Path,
+        query: &ProjectSearchQuery,
+        open_buffers: &HashMap<PathBuf, String>,
+    ) -> Vec<FileMatches> {
+        let mut files = Vec::new();
+        search_project(
+            root,
+            query,
+            open_buffers,
+            u64::MAX,
+            &AtomicBool::new(false),
+            |file| files.push(file),
+        )
+        .unwrap();
+        files
+    }
+
+    fn names(root: &Path, files: &[FileMatches]) -> Vec<String> {
+        files
+            .iter()
+            .map(|f| f.path.strip_prefix(root).unwrap().display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_find_matches_positions_and_context() {
+        let text = "first line\nlet café = foo(1);\nfoo";
+        let matches = find_matches(&query("foo"), &query("foo").regex().unwrap(), text);
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].line, 1);
+        assert_eq!(matches[0].column, 11);
+        assert_eq!(&text[matches[0].range.clone()], "foo");
+        assert_eq!(matches[0].context, "let café = foo(1);");
+        assert_eq!(&matches[0].context[matches[0].context_match.clone()], "foo");
+        assert_eq!(matches[1].line, 2);
+        assert_eq!(matches[1].column, 0);
+    }
+
+    #[test]
+    fn test_options_and_replacements() {
+        let text = "Foo foo food";
+
+        let mut q = query("foo");
+        q.case_sensitive = false;
+        assert_eq!(find_matches(&q, &q.regex().unwrap(), text).len(), 3);
+        q.whole_word = true;
+        assert_eq!(find_matches(&q, &q.regex().unwrap(), text).len(), 2);
+
+        // Literal patterns and replacements are taken as-is
+        let mut q = query("f(o)o");
+        q.replacement = Some("$1".to_string());
+        let matches = find_matches(&q, &q.regex().unwrap(), "f(o)o");
+        assert_eq!(matches[0].replacement.as_deref(), Some("$1"));
+
+        // Regex replacements expand capture groups
+        let mut q = query(r"(\w+)\.unwrap\(\)");
+        q.use_regex = true;
+        q.replacement = Some("${1}?".to_string());
+        let matches = find_matches(&q, &q.regex().unwrap(), "let x = y.unwrap();");
+        assert_eq!(matches[0].replacement.as_deref(), Some("y?"));
+        assert_eq!(
+            matches[0].replaced_context().as_deref(),
+            Some("let x = y?;")
+        );
+    }
+
+    #[test]
+    fn test_search_project_respects_ignores_and_globs() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        std::fs::write(root.join("src/a.rs"), "needle\n").unwrap();
+        std::fs::write(root.join("src/b.txt"), "a needle\r\nneedle\r\n").unwrap();
+        std::fs::write(root.join("target/out.rs"), "needle\n").unwrap();
+        std::fs::write(root.join("data.bin"), b"needle\0\0\0").unwrap();
+        // The walker only applies .gitignore inside a git repository
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+
+        let files = search(root, &query("needle"));
+        assert_eq!(names(root, &files), vec!["src/a.rs", "src/b.txt"]);
+        // Offsets are in the normalized (LF) text
+        assert_eq!(files[1].matches[1].range, 9..15);
+
+        let mut q = query("needle");
+        q.include = vec!["*.rs".to_string()];
+        assert_eq!(names(root, &search(root, &q)), vec!["src/a.rs"]);
+
+        let mut q = query("needle");
+        q.exclude = vec!["*.rs".to_string()];
+        assert_eq!(names(root, &search(root, &q)), vec!["src/b.txt"]);
+    }
+
+    #[test]
+    fn test_open_buffers_take_precedence_over_disk() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("a.txt"), "old text\n").unwrap();
+        std::fs::write(root.join("b.txt"), "old text\n").unwrap();
+
+        let open_buffers = HashMap::from([(root.join("a.txt"), "new text\n".to_string())]);
+        let files = search_in(root, &query("new"), &open_buffers);
+        assert_eq!(names(root, &files), vec!["a.txt"]);
+        let files = search_in(root, &query("old"), &open_buffers);
+        assert_eq!(names(root, &files), vec!["b.txt"]);
+    }
+
+    #[test]
+    fn test_invalid_glob_is_an_error() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut q = query("x");
+        q.include = vec!["{".to_string()];
+        let result = search_project(
+            dir.path(),
+            &q,
+            &HashMap::new(),
+            u64::MAX,
+            &AtomicBool::new(false),
+            |_| {},
+        );
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+}
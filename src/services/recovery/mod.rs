@@ -58,9 +58,11 @@ pub use types::{
     RecoveryChunk, RecoveryEntry, RecoveryMetadata, RecoveryResult, SessionInfo, MAX_CHUNK_SIZE,
 };
 
+use crate::services::fs::FsManager;
 use std::collections::HashMap;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 /// Configuration for the recovery service
@@ -152,6 +154,11 @@ impl RecoveryService {
         &self.storage
     }
 
+    /// Set the filesystem original files are read from when recovering
+    pub fn set_fs(&mut self, fs: Arc<FsManager>) {
+        self.storage.set_fs(fs);
+    }
+
     // ========================================================================
     // Session management
     // ========================================================================
@@ -324,14 +331,15 @@ impl RecoveryService {
             // Large file recovery - return chunks to apply on top of original
             if let Some(ref original_path) = entry.metadata.original_path {
                 // Check if original file was modified since recovery was saved
-                if entry.original_file_modified() {
+                let fs = self.storage.fs();
+                if entry.original_file_modified(fs) {
                     return Ok(RecoveryResult::OriginalFileModified {
                         id: entry.id.clone(),
                         original_path: original_path.clone(),
                     });
                 }
 
-                if !original_path.exists() {
+                if !fs.block_on(fs.exists(original_path)) {
                     return Ok(RecoveryResult::Corrupted {
                         id: entry.id.clone(),
                         reason: format!(
@@ -8,18 +8,24 @@ use super::types::{
     RecoveryEntry, RecoveryMetadata, SessionInfo,
 };
 use crate::input::input_history::get_data_dir;
+use crate::services::fs::FsManager;
 use std::fs::{self, File};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::SystemTime;
 
 /// Recovery storage manager
 ///
 /// Handles all file I/O for the recovery system with atomic operations.
+/// Recovery files live on the local disk; the original files they apply to
+/// are accessed through `fs`.
 #[derive(Debug)]
 pub struct RecoveryStorage {
     /// Base directory for recovery files
     recovery_dir: PathBuf,
+    /// Filesystem of the original files
+    fs: Arc<FsManager>,
 }
 
 impl RecoveryStorage {
@@ -33,12 +39,25 @@ impl RecoveryStorage {
     /// Create a new recovery storage manager
     pub fn new() -> io::Result<Self> {
         let recovery_dir = Self::get_recovery_dir()?;
-        Ok(Self { recovery_dir })
+        Ok(Self::with_dir(recovery_dir))
     }
 
     /// Create a recovery storage with a custom directory (for testing)
     pub fn with_dir(recovery_dir: PathBuf) -> Self {
-        Self { recovery_dir }
+        Self {
+            recovery_dir,
+            fs: FsManager::local(),
+        }
+    }
+
+    /// Filesystem the original files are read from
+    pub fn fs(&self) -> &Arc<FsManager> {
+        &self.fs
+    }
+
+    /// Set the filesystem the original files are read from
+    pub fn set_fs(&mut self, fs: Arc<FsManager>) {
+        self.fs = fs;
     }
 
     /// Get the recovery directory path
@@ -220,9 +239,11 @@ impl RecoveryStorage {
 
         // Get original file's mtime if it exists
         let original_mtime = original_path.and_then(|p| {
-            fs::metadata(p)
+            self.fs
+                .block_on(self.fs.get_entry(p))
                 .ok()
-                .and_then(|m| m.modified().ok())
+                .and_then(|entry| entry.metadata)
+                .and_then(|m| m.modified)
                 .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
                 .map(|d| d.as_secs())
         });
@@ -347,7 +368,7 @@ impl RecoveryStorage {
         })?;
 
         // Read original file
-        let original_content = fs::read(original_file)?;
+        let original_content = self.fs.block_on(self.fs.read_file(original_file))?;
 
         tracing::debug!(
             "reconstruct_from_chunks: original_file={:?}, file_size_on_disk={}, expected_original_size={}",
@@ -604,9 +625,7 @@ impl RecoveryStorage {
 
 impl Default for RecoveryStorage {
     fn default() -> Self {
-        Self::new().unwrap_or_else(|_| Self {
-            recovery_dir: PathBuf::from("/tmp/fresh-recovery"),
-        })
+        Self::new().unwrap_or_else(|_| Self::with_dir(PathBuf::from("/tmp/fresh-recovery")))
     }
 }
 
@@ -617,9 +636,7 @@ mod tests {
 
     fn create_test_storage() -> (RecoveryStorage, TempDir) {
         let temp_dir = TempDir::new().unwrap();
-        let storage = RecoveryStorage {
-            recovery_dir: temp_dir.path().to_path_buf(),
-        };
+        let storage = RecoveryStorage::with_dir(temp_dir.path().to_path_buf());
         (storage, temp_dir)
     }
 
@@ -11,6 +11,7 @@
 //! For small files or new buffers, there's typically a single chunk containing
 //! the full content. For large files, only modified regions are stored as chunks.
 
+use crate::services::fs::FsManager;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::time::SystemTime;
@@ -274,14 +275,14 @@ pub struct RecoveryEntry {
 }
 
 impl RecoveryEntry {
-    /// Check if the original file has been modified since recovery was saved.
+    /// Check if the original file on `fs` has been modified since recovery was saved.
     /// Returns true if the file was modified (recovery may be invalid).
     /// Returns false if the file is unchanged or doesn't exist.
-    pub fn original_file_modified(&self) -> bool {
+    pub fn original_file_modified(&self, fs: &FsManager) -> bool {
         if let Some(ref path) = self.metadata.original_path {
             if let Some(saved_mtime) = self.metadata.original_mtime {
-                if let Ok(metadata) = std::fs::metadata(path) {
-                    if let Ok(mtime) = metadata.modified() {
+                if let Ok(entry) = fs.block_on(fs.get_entry(path)) {
+                    if let Some(mtime) = entry.metadata.and_then(|m| m.modified) {
                         let current_mtime = mtime
                             .duration_since(SystemTime::UNIX_EPOCH)
                             .map(|d| d.as_secs())
@@ -0,0 +1,459 @@
+//! The agent run on the remote host (`fresh --remote-agent`)
+//!
+//! It serves requests from the editor on stdin/stdout: filesystem operations
+//! go to a [`LocalFsBackend`], and spawned processes (LSP servers, terminal
+//! shells) have their stdio relayed over the same stream.
+
+use super::protocol::{
+    encode_frame, read_frame_async, Message, OutputStream, PtySize, RemoteError, Request, Response,
+    PROTOCOL_VERSION,
+};
+use crate::services::fs::{FsBackend, LocalFsBackend};
+use portable_pty::{native_pty_system, CommandBuilder, MasterPty};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{mpsc, oneshot};
+
+/// Size of process output reads relayed to the editor
+const OUTPUT_CHUNK_SIZE: usize = 16 * 1024;
+
+/// Serve the editor on this process's stdin and stdout until it disconnects
+pub fn run_stdio() -> io::Result<()> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(2)
+        .thread_name("remote-agent")
+        .enable_all()
+        .build()?;
+    runtime.block_on(serve(tokio::io::stdin(), tokio::io::stdout()))
+}
+
+/// Serve the editor on a pair of streams until the reader ends
+///
+/// Everything the agent started is killed when the editor disconnects.
+pub async fn serve<R, W>(mut reader: R, mut writer: W) -> io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let (outgoing, mut outgoing_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+    let writer_task = tokio::spawn(async move {
+        while let Some(frame) = outgoing_rx.recv().await {
+            writer.write_all(&frame).await?;
+            writer.flush().await?;
+        }
+        Ok::<_, io::Error>(())
+    });
+
+    let agent = Arc::new(Agent {
+        fs: LocalFsBackend::new(),
+        outgoing,
+        processes: Mutex::new(HashMap::new()),
+    });
+    agent.send(
+        &Message::Hello {
+            version: PROTOCOL_VERSION,
+        },
+        &[],
+    );
+
+    let result = loop {
+        match read_frame_async(&mut reader).await {
+            Ok(Some((Message::Request { id, request }, payload))) => {
+                let agent = Arc::clone(&agent);
+                tokio::spawn(async move {
+                    let (response, data) = match agent.handle(request, payload).await {
+                        Ok(result) => result,
+                        Err(e) => (
+                            Response::Error {
+                                error: RemoteError::from(&e),
+                            },
+                            Vec::new(),
+                        ),
+                    };
+                    agent.send(&Message::Response { id, response }, &data);
+                });
+            }
+            Ok(Some((Message::Stdin { process }, payload))) => agent.write_stdin(process, payload),
+            Ok(Some((Message::CloseStdin { process }, _))) => agent.close_stdin(process),
+            Ok(Some(_)) => {}
+            Ok(None) => break Ok(()),
+            Err(e) => break Err(e),
+        }
+    };
+
+    agent.kill_all();
+    drop(agent);
+    writer_task.abort();
+    result
+}
+
+/// A process started for the editor
+struct AgentProcess {
+    /// Input for the process; dropping it closes the process's stdin
+    stdin: Option<mpsc::UnboundedSender<Vec<u8>>>,
+    control: ProcessControl,
+}
+
+enum ProcessControl {
+    /// Piped process; send to kill it
+    Pipe { kill: Option<oneshot::Sender<()>> },
+    /// Process under a pseudo-terminal
+    Pty {
+        master: Box<dyn MasterPty + Send>,
+        child: Arc<Mutex<Box<dyn portable_pty::Child + Send + Sync>>>,
+    },
+}
+
+struct Agent {
+    fs: LocalFsBackend,
+    outgoing: mpsc::UnboundedSender<Vec<u8>>,
+    processes: Mutex<HashMap<u64, AgentProcess>>,
+}
+
+impl Agent {
+    fn send(&self, message: &Message, payload: &[u8]) {
+        let _ = self.outgoing.send(encode_frame(message, payload));
+    }
+
+    async fn handle(
+        self: &Arc<Self>,
+        request: Request,
+        payload: Vec<u8>,
+    ) -> io::Result<(Response, Vec<u8>)> {
+        let fs = &self.fs;
+        let response = match request {
+            Request::ReadDir { path } => Response::Entries {
+                entries: fs.read_dir(&path).await?,
+            },
+            Request::MetadataBatch { paths } => Response::MetadataBatch {
+                results: fs
+                    .get_metadata_batch(&paths)
+                    .await
+                    .into_iter()
+                    .map(|result| result.map_err(|e| RemoteError::from(&e)))
+                    .collect(),
+            },
+            Request::Exists { path } => Response::Bool {
+                value: fs.exists(&path).await,
+            },
+            Request::IsDir { path } => Response::Bool {
+                value: fs.is_dir(&path).await?,
+            },
+            Request::GetEntry { path } => Response::Entry {
+                entry: fs.get_entry(&path).await?,
+            },
+            Request::Canonicalize { path } => Response::Path {
+                path: fs.canonicalize(&path).await?,
+            },
+            Request::ReadFile { path } => return Ok((Response::Done, fs.read_file(&path).await?)),
+            Request::ReadRange { path, offset, len } => {
+                return Ok((Response::Done, fs.read_range(&path, offset, len).await?));
+            }
+            Request::WriteFile { path } => {
+                fs.write_file(&path, &payload).await?;
+                Response::Done
+            }
+            Request::AppendFile { path } => {
+                fs.append_file(&path, &payload).await?;
+                Response::Done
+            }
+            Request::SyncFile { path } => {
+                fs.sync_file(&path).await?;
+                Response::Done
+            }
+            Request::CopyPermissions { from, to } => {
+                fs.copy_permissions(&from, &to).await?;
+                Response::Done
+            }
+            Request::Rename { from, to } => {
+                fs.rename(&from, &to).await?;
+                Response::Done
+            }
+            Request::RemoveFile { path } => {
+                fs.remove_file(&path).await?;
+                Response::Done
+            }
+            Request::RemoveDirAll { path } => {
+                fs.remove_dir_all(&path).await?;
+                Response::Done
+            }
+            Request::CreateDir { path } => {
+                fs.create_dir(&path).await?;
+                Response::Done
+            }
+            Request::CreateDirAll { path } => {
+                fs.create_dir_all(&path).await?;
+                Response::Done
+            }
+            Request::ReadLink { path } => Response::Link {
+                target: fs.read_link(&path).await?,
+            },
+            Request::Symlink { target, link } => {
+                fs.symlink(&target, &link).await?;
+                Response::Done
+            }
+            Request::Spawn {
+                process,
+                command,
+                args,
+                cwd,
+                pty,
+            } => {
+                let command = command.unwrap_or_else(user_shell);
+                match pty {
+                    Some(size) => self.spawn_pty(process, &command, &args, cwd, size)?,
+                    None => self.spawn_piped(process, &command, &args, cwd)?,
+                }
+                Response::Spawned { command }
+            }
+            Request::Resize { process, size } => {
+                self.resize(process, size)?;
+                Response::Done
+            }
+            Request::Kill { process } => {
+                self.kill(process);
+                Response::Done
+            }
+        };
+        Ok((response, Vec::new()))
+    }
+
+    fn spawn_piped(
+        self: &Arc<Self>,
+        process: u64,
+        command: &str,
+        args: &[String],
+        cwd: Option<PathBuf>,
+    ) -> io::Result<()> {
+        let mut cmd = tokio::process::Command::new(command);
+        cmd.args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(cwd) = cwd {
+            cmd.current_dir(cwd);
+        }
+        let mut child = cmd.spawn()?;
+        let (Some(mut stdin), Some(stdout), Some(stderr)) =
+            (child.stdin.take(), child.stdout.take(), child.stderr.take())
+        else {
+            return Err(io::Error::other("Failed to capture process stdio"));
+        };
+
+        let (stdin_tx, mut stdin_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+        tokio::spawn(async move {
+            while let Some(data) = stdin_rx.recv().await {
+                if stdin.write_all(&data).await.is_err() || stdin.flush().await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let stdout_task =
+            tokio::spawn(Arc::clone(self).relay(process, OutputStream::Stdout, stdout));
+        let stderr_task =
+            tokio::spawn(Arc::clone(self).relay(process, OutputStream::Stderr, stderr));
+
+        let (kill_tx, kill_rx) = oneshot::channel::<()>();
+        self.processes.lock().unwrap().insert(
+            process,
+            AgentProcess {
+                stdin: Some(stdin_tx),
+                control: ProcessControl::Pipe {
+                    kill: Some(kill_tx),
+                },
+            },
+        );
+
+        let agent = Arc::clone(self);
+        tokio::spawn(async move {
+            let code = tokio::select! {
+                status = child.wait() => status.ok().and_then(|status| status.code()),
+                _ = kill_rx => {
+                    let _ = child.kill().await;
+                    None
+                }
+            };
+            // All output goes out before the exit
+            let _ = stdout_task.await;
+            let _ = stderr_task.await;
+            agent.exited(process, code);
+        });
+        Ok(())
+    }
+
+    /// Relay a process's output stream to the editor
+    async fn relay(
+        self: Arc<Self>,
+        process: u64,
+        stream: OutputStream,
+        mut output: impl AsyncRead + Unpin,
+    ) {
+        let mut buf = vec![0u8; OUTPUT_CHUNK_SIZE];
+        while let Ok(n) = output.read(&mut buf).await {
+            if n == 0 {
+                break;
+            }
+            self.send(&Message::Output { process, stream }, &buf[..n]);
+        }
+    }
+
+    fn spawn_pty(
+        self: &Arc<Self>,
+        process: u64,
+        command: &str,
+        args: &[String],
+        cwd: Option<PathBuf>,
+        size: PtySize,
+    ) -> io::Result<()> {
+        let pair = native_pty_system()
+            .openpty(pty_size(size))
+            .map_err(|e| io::Error::other(format!("Failed to open PTY: {}", e)))?;
+
+        let mut cmd = CommandBuilder::new(command);
+        cmd.args(args);
+        if let Some(cwd) = cwd {
+            cmd.cwd(cwd);
+        }
+        // ssh without a remote TTY doesn't set TERM
+        if std::env::var_os("TERM").is_none() {
+            cmd.env("TERM", "xterm-256color");
+        }
+        let child = pair
+            .slave
+            .spawn_command(cmd)
+            .map_err(|e| io::Error::other(format!("Failed to spawn {}: {}", command, e)))?;
+        // Only the child keeps the slave open, so reads end when it exits
+        drop(pair.slave);
+
+        let mut reader = pair
+            .master
+            .try_clone_reader()
+            .map_err(|e| io::Error::other(format!("Failed to get PTY reader: {}", e)))?;
+        let mut writer = pair
+            .master
+            .take_writer()
+            .map_err(|e| io::Error::other(format!("Failed to get PTY writer: {}", e)))?;
+        let child = Arc::new(Mutex::new(child));
+
+        let (stdin_tx, mut stdin_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+        std::thread::spawn(move || {
+            while let Some(data) = stdin_rx.blocking_recv() {
+                if writer.write_all(&data).is_err() || writer.flush().is_err() {
+                    break;
+                }
+            }
+        });
+
+        self.processes.lock().unwrap().insert(
+            process,
+            AgentProcess {
+                stdin: Some(stdin_tx),
+                control: ProcessControl::Pty {
+                    master: pair.master,
+                    child: Arc::clone(&child),
+                },
+            },
+        );
+
+        let agent = Arc::clone(self);
+        std::thread::spawn(move || {
+            let mut buf = vec![0u8; OUTPUT_CHUNK_SIZE];
+            while let Ok(n) = reader.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                agent.send(
+                    &Message::Output {
+                        process,
+                        stream: OutputStream::Stdout,
+                    },
+                    &buf[..n],
+                );
+            }
+            let code = child
+                .lock()
+                .ok()
+                .and_then(|mut child| child.wait().ok())
+                .map(|status| status.exit_code() as i32);
+            agent.exited(process, code);
+        });
+        Ok(())
+    }
+
+    fn exited(&self, process: u64, code: Option<i32>) {
+        self.processes.lock().unwrap().remove(&process);
+        self.send(&Message::Exited { process, code }, &[]);
+    }
+
+    fn write_stdin(&self, process: u64, data: Vec<u8>) {
+        let processes = self.processes.lock().unwrap();
+        if let Some(stdin) = processes.get(&process).and_then(|p| p.stdin.as_ref()) {
+            let _ = stdin.send(data);
+        }
+    }
+
+    fn close_stdin(&self, process: u64) {
+        if let Some(p) = self.processes.lock().unwrap().get_mut(&process) {
+            p.stdin = None;
+        }
+    }
+
+    fn resize(&self, process: u64, size: PtySize) -> io::Result<()> {
+        let processes = self.processes.lock().unwrap();
+        match processes.get(&process).map(|p| &p.control) {
+            Some(ProcessControl::Pty { master, .. }) => master
+                .resize(pty_size(size))
+                .map_err(|e| io::Error::other(format!("Failed to resize PTY: {}", e))),
+            Some(ProcessControl::Pipe { .. }) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Process has no terminal",
+            )),
+            // Already exited
+            None => Ok(()),
+        }
+    }
+
+    fn kill(&self, process: u64) {
+        if let Some(p) = self.processes.lock().unwrap().get_mut(&process) {
+            match &mut p.control {
+                ProcessControl::Pipe { kill } => {
+                    if let Some(kill) = kill.take() {
+                        let _ = kill.send(());
+                    }
+                }
+                ProcessControl::Pty { child, .. } => {
+                    if let Ok(mut child) = child.lock() {
+                        let _ = child.kill();
+                    }
+                }
+            }
+        }
+    }
+
+    fn kill_all(&self) {
+        let processes: Vec<u64> = self.processes.lock().unwrap().keys().copied().collect();
+        for process in processes {
+            self.kill(process);
+        }
+    }
+}
+
+fn pty_size(size: PtySize) -> portable_pty::PtySize {
+    portable_pty::PtySize {
+        rows: size.rows,
+        cols: size.cols,
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+/// The user's login shell, for terminals started without a command
+fn user_shell() -> String {
+    std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
+}
@@ -0,0 +1,429 @@
+//! Editor side of a connection to a remote agent
+//!
+//! The connection is driven by two plain threads, one writing frames and one
+//! reading them, so it works both from the editor's Tokio runtime and from
+//! synchronous code (via [`FsManager::block_on`]). Responses are delivered
+//! through oneshot channels and process output through unbounded channels,
+//! neither of which is tied to a runtime.
+//!
+//! [`FsManager::block_on`]: crate::services::fs::FsManager::block_on
+
+use super::process::{ProcessStreams, RemoteChild, RemoteOutput, RemoteStdin};
+use super::protocol::{
+    encode_frame, read_frame, Message, OutputStream, PtySize, Request, Response, PROTOCOL_VERSION,
+};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc as std_mpsc, Arc, Mutex};
+use std::thread;
+use tokio::sync::{mpsc, oneshot};
+
+/// Command run on the remote host to start the agent
+pub const DEFAULT_AGENT_COMMAND: &str = "fresh --remote-agent";
+
+/// Most recent agent stderr kept for error messages
+const MAX_STDERR_TAIL: usize = 4096;
+
+/// A connection to a remote agent
+///
+/// Dropping the connection ends the agent process (if the connection started
+/// it), which stops everything the agent was running.
+pub struct RemoteConnection {
+    shared: Arc<Shared>,
+    /// Human-readable name of the remote end, e.g. `user@host`
+    destination: String,
+    /// The local process carrying the connection (e.g. `ssh`)
+    child: Mutex<Option<Child>>,
+}
+
+/// A response frame: the response and its payload
+type ResponseFrame = (Response, Vec<u8>);
+
+/// State shared by the connection, its reader thread and remote processes
+pub(super) struct Shared {
+    outgoing: std_mpsc::Sender<Vec<u8>>,
+    pending: Mutex<HashMap<u64, oneshot::Sender<ResponseFrame>>>,
+    processes: Mutex<HashMap<u64, ProcessStreams>>,
+    next_id: AtomicU64,
+    closed: AtomicBool,
+}
+
+impl Shared {
+    /// Queue a frame for the writer thread
+    pub(super) fn send(&self, message: &Message, payload: &[u8]) -> io::Result<()> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(connection_closed());
+        }
+        self.outgoing
+            .send(encode_frame(message, payload))
+            .map_err(|_| connection_closed())
+    }
+
+    /// Send a request without waiting for its response
+    pub(super) fn send_request(&self, request: Request) -> io::Result<()> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.send(&Message::Request { id, request }, &[])
+    }
+
+    /// Stop routing output to a process's streams
+    pub(super) fn forget_process(&self, process: u64) {
+        if let Ok(mut processes) = self.processes.lock() {
+            processes.remove(&process);
+        }
+    }
+
+    /// Route an incoming frame to whoever is waiting for it
+    fn dispatch(&self, message: Message, payload: Vec<u8>) {
+        match message {
+            Message::Response { id, response } => {
+                let sender = self.pending.lock().ok().and_then(|mut p| p.remove(&id));
+                match sender {
+                    Some(sender) => {
+                        let _ = sender.send((response, payload));
+                    }
+                    None => tracing::trace!("Unawaited remote response {}", id),
+                }
+            }
+            Message::Output { process, stream } => {
+                if let Ok(processes) = self.processes.lock() {
+                    if let Some(streams) = processes.get(&process) {
+                        streams.deliver(stream, payload);
+                    }
+                }
+            }
+            Message::Exited { process, code } => {
+                tracing::debug!("Remote process {} exited with {:?}", process, code);
+                // Dropping the stream senders ends the output readers
+                self.forget_process(process);
+            }
+            other => tracing::warn!("Unexpected message from remote agent: {:?}", other),
+        }
+    }
+
+    /// Fail everything still waiting on the connection
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.clear();
+        }
+        if let Ok(mut processes) = self.processes.lock() {
+            processes.clear();
+        }
+    }
+}
+
+impl RemoteConnection {
+    /// Connect to `destination` (`[user@]host`) over `ssh`, starting the
+    /// agent there with `agent_command`
+    ///
+    /// ssh asks for passwords and host key confirmation on the terminal, so
+    /// this has to run before the editor takes over the screen.
+    pub fn connect_ssh(destination: &str, agent_command: &str) -> io::Result<Self> {
+        let mut command = Command::new("ssh");
+        // No remote TTY: the connection carries binary frames
+        command
+            .arg("-T")
+            .arg("--")
+            .arg(destination)
+            .arg(agent_command);
+        Self::spawn(command, destination.to_string())
+    }
+
+    /// Start a local process that speaks the protocol on its stdio (`ssh`,
+    /// or the agent itself for testing) and connect to it
+    pub fn spawn(mut command: Command, destination: String) -> io::Result<Self> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let (Some(stdin), Some(stdout), Some(stderr)) =
+            (child.stdin.take(), child.stdout.take(), child.stderr.take())
+        else {
+            return Err(io::Error::other("Failed to capture remote agent stdio"));
+        };
+
+        // Log the agent's stderr, keeping the end of it to explain failures
+        let stderr_tail = Arc::new(Mutex::new(String::new()));
+        let stderr_thread = {
+            let stderr_tail = Arc::clone(&stderr_tail);
+            let destination = destination.clone();
+            thread::spawn(move || {
+                let mut reader = io::BufReader::new(stderr);
+                let mut line = String::new();
+                while matches!(io::BufRead::read_line(&mut reader, &mut line), Ok(n) if n > 0) {
+                    tracing::warn!("Remote {}: {}", destination, line.trim_end());
+                    if let Ok(mut tail) = stderr_tail.lock() {
+                        tail.push_str(&line);
+                        if tail.len() > MAX_STDERR_TAIL {
+                            let cut = tail.len() - MAX_STDERR_TAIL;
+                            let cut = (cut..tail.len())
+                                .find(|i| tail.is_char_boundary(*i))
+                                .unwrap_or(0);
+                            tail.drain(..cut);
+                        }
+                    }
+                    line.clear();
+                }
+            })
+        };
+
+        match Self::new(stdout, stdin, destination) {
+            Ok(connection) => {
+                *connection.child.lock().unwrap() = Some(child);
+                Ok(connection)
+            }
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                let _ = stderr_thread.join();
+                let stderr = stderr_tail.lock().map(|t| t.trim().to_string());
+                match stderr {
+                    Ok(stderr) if !stderr.is_empty() => {
+                        Err(io::Error::new(e.kind(), format!("{}: {}", e, stderr)))
+                    }
+                    _ => Err(e),
+                }
+            }
+        }
+    }
+
+    /// Connect over an already established byte stream
+    ///
+    /// Waits for the agent's greeting, failing if the other end doesn't
+    /// speak the protocol.
+    pub fn new(
+        mut reader: impl Read + Send + 'static,
+        mut writer: impl Write + Send + 'static,
+        destination: String,
+    ) -> io::Result<Self> {
+        match read_frame(&mut reader)? {
+            Some((Message::Hello { version }, _)) if version == PROTOCOL_VERSION => {}
+            Some((Message::Hello { version }, _)) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!(
+                        "Remote agent speaks protocol version {}, expected {}",
+                        version, PROTOCOL_VERSION
+                    ),
+                ));
+            }
+            Some(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Remote agent didn't greet the editor",
+                ));
+            }
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Remote agent exited before connecting",
+                ));
+            }
+        }
+
+        let (outgoing, outgoing_rx) = std_mpsc::channel::<Vec<u8>>();
+        let shared = Arc::new(Shared {
+            outgoing,
+            pending: Mutex::new(HashMap::new()),
+            processes: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+        });
+
+        thread::Builder::new()
+            .name("remote-writer".to_string())
+            .spawn(move || {
+                for frame in outgoing_rx {
+                    if let Err(e) = writer.write_all(&frame).and_then(|_| writer.flush()) {
+                        tracing::error!("Failed to write to remote agent: {}", e);
+                        break;
+                    }
+                }
+            })?;
+
+        let reader_shared = Arc::clone(&shared);
+        let reader_destination = destination.clone();
+        thread::Builder::new()
+            .name("remote-reader".to_string())
+            .spawn(move || {
+                loop {
+                    match read_frame(&mut reader) {
+                        Ok(Some((message, payload))) => reader_shared.dispatch(message, payload),
+                        Ok(None) => {
+                            tracing::info!("Remote connection to {} closed", reader_destination);
+                            break;
+                        }
+                        Err(e) => {
+                            tracing::error!(
+                                "Failed to read from remote agent on {}: {}",
+                                reader_destination,
+                                e
+                            );
+                            break;
+                        }
+                    }
+                }
+                reader_shared.close();
+            })?;
+
+        Ok(Self {
+            shared,
+            destination,
+            child: Mutex::new(None),
+        })
+    }
+
+    /// Name of the remote end, e.g. `user@host`
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    /// Whether the connection has been lost
+    pub fn is_closed(&self) -> bool {
+        self.shared.closed.load(Ordering::SeqCst)
+    }
+
+    /// Send a request and wait for its response
+    ///
+    /// Error responses are returned as `Err`, with the remote error's kind.
+    pub async fn request(&self, request: Request, payload: &[u8]) -> io::Result<ResponseFrame> {
+        let id = self.shared.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        {
+            let mut pending = self
+                .shared
+                .pending
+                .lock()
+                .map_err(|_| io::Error::other("Remote connection state poisoned"))?;
+            // Checked under the lock so a closing reader can't miss this request
+            if self.is_closed() {
+                return Err(connection_closed());
+            }
+            pending.insert(id, tx);
+        }
+        if let Err(e) = self.shared.send(&Message::Request { id, request }, payload) {
+            if let Ok(mut pending) = self.shared.pending.lock() {
+                pending.remove(&id);
+            }
+            return Err(e);
+        }
+
+        match rx.await {
+            Ok((Response::Error { error }, _)) => Err(error.into()),
+            Ok(response) => Ok(response),
+            Err(_) => Err(connection_closed()),
+        }
+    }
+
+    /// Start a process on the remote host
+    ///
+    /// Without a command, the remote user's shell is started. With a `pty`
+    /// size the process runs under a pseudo-terminal; its stderr is then part
+    /// of stdout and the child has no separate stderr stream.
+    pub async fn spawn_process(
+        &self,
+        command: Option<&str>,
+        args: &[String],
+        cwd: Option<&Path>,
+        pty: Option<PtySize>,
+    ) -> io::Result<RemoteChild> {
+        let process = self.shared.next_id.fetch_add(1, Ordering::SeqCst);
+
+        // Register the streams first: output can arrive before the response
+        let (stdout_tx, stdout_rx) = mpsc::unbounded_channel();
+        let (stderr_tx, stderr_rx) = if pty.is_none() {
+            let (tx, rx) = mpsc::unbounded_channel();
+            (Some(tx), Some(rx))
+        } else {
+            (None, None)
+        };
+        self.shared
+            .processes
+            .lock()
+            .map_err(|_| io::Error::other("Remote connection state poisoned"))?
+            .insert(
+                process,
+                ProcessStreams {
+                    stdout: stdout_tx,
+                    stderr: stderr_tx,
+                },
+            );
+
+        let request = Request::Spawn {
+            process,
+            command: command.map(str::to_string),
+            args: args.to_vec(),
+            cwd: cwd.map(Path::to_path_buf),
+            pty,
+        };
+        let command = match self.request(request, &[]).await {
+            Ok((Response::Spawned { command }, _)) => command,
+            Ok((other, _)) => {
+                self.shared.forget_process(process);
+                return Err(unexpected_response(&other));
+            }
+            Err(e) => {
+                self.shared.forget_process(process);
+                return Err(e);
+            }
+        };
+
+        Ok(RemoteChild::new(
+            Arc::clone(&self.shared),
+            process,
+            command,
+            RemoteStdin::new(Arc::clone(&self.shared), process),
+            RemoteOutput::new(stdout_rx),
+            stderr_rx.map(RemoteOutput::new),
+        ))
+    }
+}
+
+impl Drop for RemoteConnection {
+    fn drop(&mut self) {
+        if let Ok(mut child) = self.child.lock() {
+            if let Some(mut child) = child.take() {
+                // Closing the pipes ends the agent; ssh may linger, so kill it
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for RemoteConnection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteConnection")
+            .field("destination", &self.destination)
+            .field("closed", &self.is_closed())
+            .finish()
+    }
+}
+
+impl ProcessStreams {
+    fn deliver(&self, stream: OutputStream, data: Vec<u8>) {
+        let sender = match stream {
+            OutputStream::Stdout => Some(&self.stdout),
+            OutputStream::Stderr => self.stderr.as_ref(),
+        };
+        if let Some(sender) = sender {
+            let _ = sender.send(data);
+        }
+    }
+}
+
+fn connection_closed() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "Remote connection closed")
+}
+
+/// Error for a response that doesn't match its request
+pub(super) fn unexpected_response(response: &Response) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Unexpected response from remote agent: {:?}", response),
+    )
+}
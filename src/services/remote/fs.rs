@@ -0,0 +1,210 @@
+//! Filesystem backend serving files from the remote host
+
+use super::connection::{unexpected_response, RemoteConnection};
+use super::protocol::{Request, Response};
+use crate::services::fs::{FsBackend, FsEntry, FsMetadata};
+use async_trait::async_trait;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Filesystem backend forwarding every operation to a remote agent
+pub struct RemoteFsBackend {
+    connection: Arc<RemoteConnection>,
+}
+
+impl RemoteFsBackend {
+    pub fn new(connection: Arc<RemoteConnection>) -> Self {
+        Self { connection }
+    }
+
+    /// Run a request that returns nothing
+    async fn done(&self, request: Request, payload: &[u8]) -> io::Result<()> {
+        match self.connection.request(request, payload).await? {
+            (Response::Done, _) => Ok(()),
+            (other, _) => Err(unexpected_response(&other)),
+        }
+    }
+
+    /// Run a request that returns file data
+    async fn data(&self, request: Request) -> io::Result<Vec<u8>> {
+        match self.connection.request(request, &[]).await? {
+            (Response::Done, data) => Ok(data),
+            (other, _) => Err(unexpected_response(&other)),
+        }
+    }
+
+    async fn boolean(&self, request: Request) -> io::Result<bool> {
+        match self.connection.request(request, &[]).await? {
+            (Response::Bool { value }, _) => Ok(value),
+            (other, _) => Err(unexpected_response(&other)),
+        }
+    }
+}
+
+#[async_trait]
+impl FsBackend for RemoteFsBackend {
+    async fn read_dir(&self, path: &Path) -> io::Result<Vec<FsEntry>> {
+        let request = Request::ReadDir {
+            path: path.to_path_buf(),
+        };
+        match self.connection.request(request, &[]).await? {
+            (Response::Entries { entries }, _) => Ok(entries),
+            (other, _) => Err(unexpected_response(&other)),
+        }
+    }
+
+    async fn get_metadata_batch(&self, paths: &[PathBuf]) -> Vec<io::Result<FsMetadata>> {
+        let request = Request::MetadataBatch {
+            paths: paths.to_vec(),
+        };
+        let error = match self.connection.request(request, &[]).await {
+            Ok((Response::MetadataBatch { results }, _)) => {
+                return results
+                    .into_iter()
+                    .map(|result| result.map_err(io::Error::from))
+                    .collect();
+            }
+            Ok((other, _)) => unexpected_response(&other),
+            Err(e) => e,
+        };
+        paths
+            .iter()
+            .map(|_| Err(io::Error::new(error.kind(), error.to_string())))
+            .collect()
+    }
+
+    async fn exists(&self, path: &Path) -> bool {
+        self.boolean(Request::Exists {
+            path: path.to_path_buf(),
+        })
+        .await
+        .unwrap_or(false)
+    }
+
+    async fn is_dir(&self, path: &Path) -> io::Result<bool> {
+        self.boolean(Request::IsDir {
+            path: path.to_path_buf(),
+        })
+        .await
+    }
+
+    async fn get_entry(&self, path: &Path) -> io::Result<FsEntry> {
+        let request = Request::GetEntry {
+            path: path.to_path_buf(),
+        };
+        match self.connection.request(request, &[]).await? {
+            (Response::Entry { entry }, _) => Ok(entry),
+            (other, _) => Err(unexpected_response(&other)),
+        }
+    }
+
+    async fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        let request = Request::Canonicalize {
+            path: path.to_path_buf(),
+        };
+        match self.connection.request(request, &[]).await? {
+            (Response::Path { path }, _) => Ok(path),
+            (other, _) => Err(unexpected_response(&other)),
+        }
+    }
+
+    async fn read_file(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.data(Request::ReadFile {
+            path: path.to_path_buf(),
+        })
+        .await
+    }
+
+    async fn read_range(&self, path: &Path, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        self.data(Request::ReadRange {
+            path: path.to_path_buf(),
+            offset,
+            len,
+        })
+        .await
+    }
+
+    async fn write_file(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let request = Request::WriteFile {
+            path: path.to_path_buf(),
+        };
+        self.done(request, contents).await
+    }
+
+    async fn append_file(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let request = Request::AppendFile {
+            path: path.to_path_buf(),
+        };
+        self.done(request, contents).await
+    }
+
+    async fn sync_file(&self, path: &Path) -> io::Result<()> {
+        let request = Request::SyncFile {
+            path: path.to_path_buf(),
+        };
+        self.done(request, &[]).await
+    }
+
+    async fn copy_permissions(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let request = Request::CopyPermissions {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        };
+        self.done(request, &[]).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let request = Request::Rename {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        };
+        self.done(request, &[]).await
+    }
+
+    async fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let request = Request::RemoveFile {
+            path: path.to_path_buf(),
+        };
+        self.done(request, &[]).await
+    }
+
+    async fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        let request = Request::RemoveDirAll {
+            path: path.to_path_buf(),
+        };
+        self.done(request, &[]).await
+    }
+
+    async fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let request = Request::CreateDir {
+            path: path.to_path_buf(),
+        };
+        self.done(request, &[]).await
+    }
+
+    async fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let request = Request::CreateDirAll {
+            path: path.to_path_buf(),
+        };
+        self.done(request, &[]).await
+    }
+
+    async fn read_link(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+        let request = Request::ReadLink {
+            path: path.to_path_buf(),
+        };
+        match self.connection.request(request, &[]).await? {
+            (Response::Link { target }, _) => Ok(target),
+            (other, _) => Err(unexpected_response(&other)),
+        }
+    }
+
+    async fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        let request = Request::Symlink {
+            target: target.to_path_buf(),
+            link: link.to_path_buf(),
+        };
+        self.done(request, &[]).await
+    }
+}
@@ -0,0 +1,107 @@
+//! Remote editing over SSH
+//!
+//! `fresh user@host:/path` starts a small agent on the remote host
+//! (`fresh --remote-agent`, run through `ssh`) and talks to it over the ssh
+//! session's stdio using the framed protocol in [`protocol`]:
+//!
+//! - [`RemoteFsBackend`] forwards every filesystem operation, so buffers,
+//!   saving, recovery and the file explorer all work on remote files.
+//! - [`RemoteConnection::spawn_process`] starts processes on the remote host
+//!   with their stdio tunneled through the same connection. LSP servers and
+//!   integrated terminals use it when the editor is connected to a remote.
+//!
+//! Any local process speaking the protocol on its stdio works as a transport,
+//! which is how the tests run the agent without ssh.
+
+pub mod agent;
+pub mod connection;
+pub mod fs;
+pub mod process;
+pub mod protocol;
+
+pub use connection::{RemoteConnection, DEFAULT_AGENT_COMMAND};
+pub use fs::RemoteFsBackend;
+pub use process::{RemoteChild, RemoteOutput, RemoteStdin};
+
+use std::path::PathBuf;
+
+/// A remote location given on the command line as `[user@]host:path`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteTarget {
+    /// ssh destination, `[user@]host`
+    pub destination: String,
+    /// Path on the remote host; relative paths are relative to the remote
+    /// home directory
+    pub path: PathBuf,
+}
+
+impl RemoteTarget {
+    /// Parse `[user@]host:path`
+    ///
+    /// The path must be absolute or start with `~`, so `file.txt:10` (a local
+    /// file and line) and Windows drive paths are not taken as remote targets.
+    pub fn parse(input: &str) -> Option<Self> {
+        let (destination, path) = input.split_once(':')?;
+        let host = destination.rsplit('@').next().unwrap_or(destination);
+        if host.len() < 2
+            || destination.starts_with('-')
+            || destination.contains(['/', '\\'])
+            || destination.contains(char::is_whitespace)
+        {
+            return None;
+        }
+
+        let path = if path == "~" {
+            PathBuf::new()
+        } else if let Some(relative) = path.strip_prefix("~/") {
+            PathBuf::from(relative)
+        } else if path.starts_with('/') {
+            PathBuf::from(path)
+        } else {
+            return None;
+        };
+
+        Some(Self {
+            destination: destination.to_string(),
+            path,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remote_target() {
+        assert_eq!(
+            RemoteTarget::parse("alice@build-box:/srv/project"),
+            Some(RemoteTarget {
+                destination: "alice@build-box".to_string(),
+                path: PathBuf::from("/srv/project"),
+            })
+        );
+        assert_eq!(
+            RemoteTarget::parse("devbox:~/src/app"),
+            Some(RemoteTarget {
+                destination: "devbox".to_string(),
+                path: PathBuf::from("src/app"),
+            })
+        );
+        assert_eq!(
+            RemoteTarget::parse("devbox:~").map(|t| t.path),
+            Some(PathBuf::new())
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_local_paths() {
+        assert_eq!(RemoteTarget::parse("src/main.rs"), None);
+        assert_eq!(RemoteTarget::parse("main.rs:10"), None);
+        assert_eq!(RemoteTarget::parse("main.rs:10:5"), None);
+        assert_eq!(RemoteTarget::parse("./dir/host:/path"), None);
+        assert_eq!(RemoteTarget::parse("C:/Users/me/file.txt"), None);
+        assert_eq!(RemoteTarget::parse(r"C:\Users\me\file.txt"), None);
+        assert_eq!(RemoteTarget::parse("-oProxyCommand=x:/path"), None);
+    }
+}
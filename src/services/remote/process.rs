@@ -0,0 +1,226 @@
+//! Processes running on the remote host
+//!
+//! A [`RemoteChild`] stands in for a local child process: its stdin and
+//! output streams implement both the blocking (`std::io`) and async (Tokio)
+//! I/O traits, so the same handle can back an LSP server (async) or the
+//! integrated terminal (blocking threads).
+
+use super::connection::Shared;
+use super::protocol::{Message, PtySize, Request};
+use std::io::{self, Read, Write};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::mpsc;
+
+/// Where a remote process's output is delivered
+pub(super) struct ProcessStreams {
+    pub(super) stdout: mpsc::UnboundedSender<Vec<u8>>,
+    pub(super) stderr: Option<mpsc::UnboundedSender<Vec<u8>>>,
+}
+
+/// A process started on the remote host
+///
+/// Like a Tokio child with `kill_on_drop`, the remote process is killed when
+/// the handle is dropped.
+pub struct RemoteChild {
+    shared: Arc<Shared>,
+    process: u64,
+    command: String,
+    stdin: Option<RemoteStdin>,
+    stdout: Option<RemoteOutput>,
+    stderr: Option<RemoteOutput>,
+}
+
+impl RemoteChild {
+    pub(super) fn new(
+        shared: Arc<Shared>,
+        process: u64,
+        command: String,
+        stdin: RemoteStdin,
+        stdout: RemoteOutput,
+        stderr: Option<RemoteOutput>,
+    ) -> Self {
+        Self {
+            shared,
+            process,
+            command,
+            stdin: Some(stdin),
+            stdout: Some(stdout),
+            stderr,
+        }
+    }
+
+    /// The command that was run (the shell, if none was given)
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// Take the process's stdin; dropping it closes the remote stdin
+    pub fn take_stdin(&mut self) -> Option<RemoteStdin> {
+        self.stdin.take()
+    }
+
+    /// Take the process's stdout (with stderr merged in, under a pty)
+    pub fn take_stdout(&mut self) -> Option<RemoteOutput> {
+        self.stdout.take()
+    }
+
+    /// Take the process's stderr; `None` for processes run under a pty
+    pub fn take_stderr(&mut self) -> Option<RemoteOutput> {
+        self.stderr.take()
+    }
+
+    /// Resize the process's pseudo-terminal
+    pub fn resize(&self, cols: u16, rows: u16) -> io::Result<()> {
+        self.shared.send_request(Request::Resize {
+            process: self.process,
+            size: PtySize { cols, rows },
+        })
+    }
+
+    /// Kill the process
+    pub fn kill(&self) -> io::Result<()> {
+        self.shared.send_request(Request::Kill {
+            process: self.process,
+        })
+    }
+}
+
+impl Drop for RemoteChild {
+    fn drop(&mut self) {
+        let _ = self.kill();
+    }
+}
+
+/// Stdin of a remote process
+///
+/// Writes are queued on the connection and never block.
+pub struct RemoteStdin {
+    shared: Arc<Shared>,
+    process: u64,
+}
+
+impl RemoteStdin {
+    pub(super) fn new(shared: Arc<Shared>, process: u64) -> Self {
+        Self { shared, process }
+    }
+}
+
+impl Write for RemoteStdin {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.shared.send(
+            &Message::Stdin {
+                process: self.process,
+            },
+            buf,
+        )?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsyncWrite for RemoteStdin {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(self.get_mut().write(buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for RemoteStdin {
+    fn drop(&mut self) {
+        let _ = self.shared.send(
+            &Message::CloseStdin {
+                process: self.process,
+            },
+            &[],
+        );
+    }
+}
+
+/// An output stream (stdout or stderr) of a remote process
+///
+/// Reads return end-of-file once the process has exited and all of its
+/// output was read. The blocking `Read` impl must not be used from inside a
+/// Tokio runtime.
+pub struct RemoteOutput {
+    receiver: mpsc::UnboundedReceiver<Vec<u8>>,
+    /// Received data not yet returned by a read
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl RemoteOutput {
+    pub(super) fn new(receiver: mpsc::UnboundedReceiver<Vec<u8>>) -> Self {
+        Self {
+            receiver,
+            buffer: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Copy buffered data into `out`, returning how much was copied
+    fn drain_buffer(&mut self, out: &mut [u8]) -> usize {
+        let available = &self.buffer[self.position..];
+        let n = available.len().min(out.len());
+        out[..n].copy_from_slice(&available[..n]);
+        self.position += n;
+        n
+    }
+
+    fn has_buffered(&self) -> bool {
+        self.position < self.buffer.len()
+    }
+
+    fn refill(&mut self, data: Vec<u8>) {
+        self.buffer = data;
+        self.position = 0;
+    }
+}
+
+impl Read for RemoteOutput {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while !self.has_buffered() {
+            match self.receiver.blocking_recv() {
+                Some(data) => self.refill(data),
+                None => return Ok(0),
+            }
+        }
+        Ok(self.drain_buffer(out))
+    }
+}
+
+impl AsyncRead for RemoteOutput {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        while !this.has_buffered() {
+            match this.receiver.poll_recv(cx) {
+                Poll::Ready(Some(data)) => this.refill(data),
+                Poll::Ready(None) => return Poll::Ready(Ok(())),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        let n = this.drain_buffer(buf.initialize_unfilled());
+        buf.advance(n);
+        Poll::Ready(Ok(()))
+    }
+}
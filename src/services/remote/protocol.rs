@@ -0,0 +1,384 @@
+//! Wire protocol spoken between the editor and the remote agent
+//!
+//! Every message is a frame:
+//!
+//! ```text
+//! [u32 header length][u32 payload length][JSON header][payload bytes]
+//! ```
+//!
+//! Lengths are big-endian. The header is a [`Message`]; file contents and
+//! process I/O travel as the raw payload so they don't need escaping.
+//!
+//! The agent greets the editor with [`Message::Hello`]. After that the editor
+//! sends [`Message::Request`]s, each answered by a [`Message::Response`] with
+//! the same id, in any order. Processes started with [`Request::Spawn`] get
+//! their input through [`Message::Stdin`] frames and report back with
+//! [`Message::Output`] frames followed by a single [`Message::Exited`].
+
+use crate::services::fs::{FsEntry, FsMetadata};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read};
+use std::path::PathBuf;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Version of the protocol; the editor refuses agents speaking another one
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Largest header accepted, to fail fast on a corrupted stream
+const MAX_HEADER_LEN: usize = 16 * 1024 * 1024;
+
+/// A protocol message (the header of a frame)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    /// First frame sent by the agent
+    Hello { version: u32 },
+    /// Editor to agent: perform an operation. Write requests carry the data
+    /// as the payload.
+    Request { id: u64, request: Request },
+    /// Agent to editor: result of the request with the same id. Read
+    /// responses carry the data as the payload.
+    Response { id: u64, response: Response },
+    /// Editor to agent: the payload is written to the process's stdin
+    Stdin { process: u64 },
+    /// Editor to agent: close the process's stdin
+    CloseStdin { process: u64 },
+    /// Agent to editor: the payload was read from the process's stdout or stderr
+    Output { process: u64, stream: OutputStream },
+    /// Agent to editor: the process exited; no more output follows
+    Exited { process: u64, code: Option<i32> },
+}
+
+/// Output stream of a remote process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Terminal size for processes run under a pseudo-terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PtySize {
+    pub cols: u16,
+    pub rows: u16,
+}
+
+/// Operations the agent performs for the editor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Request {
+    ReadDir {
+        path: PathBuf,
+    },
+    MetadataBatch {
+        paths: Vec<PathBuf>,
+    },
+    Exists {
+        path: PathBuf,
+    },
+    IsDir {
+        path: PathBuf,
+    },
+    GetEntry {
+        path: PathBuf,
+    },
+    Canonicalize {
+        path: PathBuf,
+    },
+    ReadFile {
+        path: PathBuf,
+    },
+    ReadRange {
+        path: PathBuf,
+        offset: u64,
+        len: usize,
+    },
+    WriteFile {
+        path: PathBuf,
+    },
+    AppendFile {
+        path: PathBuf,
+    },
+    SyncFile {
+        path: PathBuf,
+    },
+    CopyPermissions {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    RemoveFile {
+        path: PathBuf,
+    },
+    RemoveDirAll {
+        path: PathBuf,
+    },
+    CreateDir {
+        path: PathBuf,
+    },
+    CreateDirAll {
+        path: PathBuf,
+    },
+    ReadLink {
+        path: PathBuf,
+    },
+    Symlink {
+        target: PathBuf,
+        link: PathBuf,
+    },
+    /// Start a process with the id chosen by the editor. Without a command,
+    /// the remote user's shell is started. With `pty` set, the process runs
+    /// under a pseudo-terminal and its stderr is merged into stdout.
+    Spawn {
+        process: u64,
+        command: Option<String>,
+        args: Vec<String>,
+        cwd: Option<PathBuf>,
+        pty: Option<PtySize>,
+    },
+    /// Resize the pseudo-terminal of a process
+    Resize {
+        process: u64,
+        size: PtySize,
+    },
+    /// Kill a process
+    Kill {
+        process: u64,
+    },
+}
+
+/// Result of a [`Request`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum Response {
+    /// The operation succeeded without a value; file reads put the data in
+    /// the payload
+    Done,
+    Bool {
+        value: bool,
+    },
+    Path {
+        path: PathBuf,
+    },
+    /// Target of a symbolic link, `None` if the path is not one
+    Link {
+        target: Option<PathBuf>,
+    },
+    Entries {
+        entries: Vec<FsEntry>,
+    },
+    Entry {
+        entry: FsEntry,
+    },
+    MetadataBatch {
+        results: Vec<Result<FsMetadata, RemoteError>>,
+    },
+    /// The process was started; `command` is what actually ran
+    Spawned {
+        command: String,
+    },
+    Error {
+        error: RemoteError,
+    },
+}
+
+/// An I/O error that happened on the remote host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteError {
+    pub kind: String,
+    pub message: String,
+}
+
+impl From<&io::Error> for RemoteError {
+    fn from(error: &io::Error) -> Self {
+        Self {
+            kind: error_kind_name(error.kind()).to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<RemoteError> for io::Error {
+    fn from(error: RemoteError) -> Self {
+        io::Error::new(error_kind_from_name(&error.kind), error.message)
+    }
+}
+
+/// Error kinds the editor reacts to; anything else is sent as "other"
+const ERROR_KINDS: &[(io::ErrorKind, &str)] = &[
+    (io::ErrorKind::NotFound, "not_found"),
+    (io::ErrorKind::PermissionDenied, "permission_denied"),
+    (io::ErrorKind::AlreadyExists, "already_exists"),
+    (io::ErrorKind::InvalidInput, "invalid_input"),
+    (io::ErrorKind::InvalidData, "invalid_data"),
+    (io::ErrorKind::UnexpectedEof, "unexpected_eof"),
+    (io::ErrorKind::Unsupported, "unsupported"),
+];
+
+fn error_kind_name(kind: io::ErrorKind) -> &'static str {
+    ERROR_KINDS
+        .iter()
+        .find(|(k, _)| *k == kind)
+        .map(|(_, name)| *name)
+        .unwrap_or("other")
+}
+
+fn error_kind_from_name(name: &str) -> io::ErrorKind {
+    ERROR_KINDS
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(kind, _)| *kind)
+        .unwrap_or(io::ErrorKind::Other)
+}
+
+/// Encode a message and its payload as a frame
+pub fn encode_frame(message: &Message, payload: &[u8]) -> Vec<u8> {
+    // Serializing these types can't fail: all map keys are strings
+    let header = serde_json::to_vec(message).expect("protocol messages serialize to JSON");
+    let mut frame = Vec::with_capacity(8 + header.len() + payload.len());
+    frame.extend_from_slice(&(header.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&header);
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Read a frame, returning `None` if the stream ended cleanly before it
+pub fn read_frame(reader: &mut impl Read) -> io::Result<Option<(Message, Vec<u8>)>> {
+    let mut lengths = [0u8; 8];
+    match reader.read_exact(&mut lengths) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let (header_len, payload_len) = decode_lengths(&lengths)?;
+    let mut header = vec![0u8; header_len];
+    reader.read_exact(&mut header)?;
+    let mut payload = vec![0u8; payload_len];
+    reader.read_exact(&mut payload)?;
+    Ok(Some((decode_header(&header)?, payload)))
+}
+
+/// Read a frame from an async stream, returning `None` if the stream ended
+/// cleanly before it
+pub async fn read_frame_async(
+    reader: &mut (impl AsyncRead + Unpin),
+) -> io::Result<Option<(Message, Vec<u8>)>> {
+    let mut lengths = [0u8; 8];
+    match reader.read_exact(&mut lengths).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let (header_len, payload_len) = decode_lengths(&lengths)?;
+    let mut header = vec![0u8; header_len];
+    reader.read_exact(&mut header).await?;
+    let mut payload = vec![0u8; payload_len];
+    reader.read_exact(&mut payload).await?;
+    Ok(Some((decode_header(&header)?, payload)))
+}
+
+fn decode_lengths(lengths: &[u8; 8]) -> io::Result<(usize, usize)> {
+    let header_len = u32::from_be_bytes([lengths[0], lengths[1], lengths[2], lengths[3]]) as usize;
+    let payload_len = u32::from_be_bytes([lengths[4], lengths[5], lengths[6], lengths[7]]) as usize;
+    if header_len > MAX_HEADER_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Remote frame header too large ({} bytes)", header_len),
+        ));
+    }
+    Ok((header_len, payload_len))
+}
+
+fn decode_header(header: &[u8]) -> io::Result<Message> {
+    serde_json::from_slice(header).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid remote frame: {}", e),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_roundtrip() {
+        let message = Message::Request {
+            id: 7,
+            request: Request::WriteFile {
+                path: PathBuf::from("/tmp/file.txt"),
+            },
+        };
+        let frame = encode_frame(&message, b"contents");
+
+        let mut reader = io::Cursor::new(frame);
+        let (decoded, payload) = read_frame(&mut reader).unwrap().unwrap();
+        match decoded {
+            Message::Request {
+                id,
+                request: Request::WriteFile { path },
+            } => {
+                assert_eq!(id, 7);
+                assert_eq!(path, PathBuf::from("/tmp/file.txt"));
+            }
+            other => panic!("Unexpected message: {:?}", other),
+        }
+        assert_eq!(payload, b"contents");
+
+        // Clean end of stream
+        assert!(read_frame(&mut reader).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_async_frame_roundtrip() {
+        let mut stream = Vec::new();
+        stream.extend(encode_frame(&Message::Hello { version: 1 }, &[]));
+        stream.extend(encode_frame(
+            &Message::Output {
+                process: 3,
+                stream: OutputStream::Stderr,
+            },
+            b"warning",
+        ));
+
+        let mut reader = stream.as_slice();
+        let (hello, _) = read_frame_async(&mut reader).await.unwrap().unwrap();
+        assert!(matches!(hello, Message::Hello { version: 1 }));
+        let (output, payload) = read_frame_async(&mut reader).await.unwrap().unwrap();
+        assert!(matches!(
+            output,
+            Message::Output {
+                process: 3,
+                stream: OutputStream::Stderr
+            }
+        ));
+        assert_eq!(payload, b"warning");
+        assert!(read_frame_async(&mut reader).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_truncated_frame_is_an_error() {
+        let frame = encode_frame(&Message::Hello { version: 1 }, b"payload");
+        let mut reader = io::Cursor::new(&frame[..frame.len() - 2]);
+        assert!(read_frame(&mut reader).is_err());
+    }
+
+    #[test]
+    fn test_error_kind_survives_the_wire() {
+        let error = io::Error::new(io::ErrorKind::NotFound, "no such file");
+        let remote = RemoteError::from(&error);
+        let back: io::Error = remote.into();
+        assert_eq!(back.kind(), io::ErrorKind::NotFound);
+        assert!(back.to_string().contains("no such file"));
+
+        let error = io::Error::new(io::ErrorKind::Interrupted, "interrupted");
+        let back: io::Error = RemoteError::from(&error).into();
+        assert_eq!(back.kind(), io::ErrorKind::Other);
+    }
+}
@@ -0,0 +1,427 @@
+//! Streaming regex search over buffer content
+//!
+//! Large files are only partially loaded into memory, so searching them cannot
+//! rely on `TextBuffer::to_string()`. Instead the buffer is described as a list
+//! of [`ContentRegion`]s (in-memory pieces and on-disk file ranges), which are
+//! read chunk by chunk on a background thread and fed through a
+//! [`ChunkedMatcher`]. Matches are sent back to the main loop in batches via
+//! the async bridge as they are found.
+//!
+//! Matches that span chunk boundaries are handled by re-searching the tail of
+//! each chunk together with the next one, and by deferring any match that
+//! starts near (or reaches) the end of the data seen so far until more data
+//! has arrived, since a longer match may still start at or before it.
+
+use crate::model::buffer::{ContentRegion, LOAD_CHUNK_SIZE};
+use crate::services::async_bridge::AsyncMessage;
+use crate::services::fs::FsManager;
+use regex::bytes::Regex;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+
+/// Bytes at the end of each chunk that are searched again together with the
+/// next chunk, so that matches up to this length spanning a boundary are found
+const OVERLAP_BYTES: usize = 4096;
+
+/// Bytes of already-searched context kept in front of the resume point, so
+/// that anchors like `\b` and `^` see the real preceding text
+const CONTEXT_BYTES: usize = 4096;
+
+/// Upper bound on how long a deferred match may grow before it is reported
+/// as-is. Prevents patterns like `[^x]*` from buffering the whole file.
+const MAX_DEFERRED_MATCH: usize = LOAD_CHUNK_SIZE;
+
+/// Incremental matcher that finds all non-overlapping regex matches in a
+/// stream of byte chunks, including matches that cross chunk boundaries
+pub struct ChunkedMatcher {
+    regex: Regex,
+    /// Unconsumed tail of the previous chunk(s)
+    carry: Vec<u8>,
+    /// Document offset of `carry[0]`
+    carry_offset: usize,
+    /// Position within `carry` where the next search should begin
+    resume_at: usize,
+}
+
+impl ChunkedMatcher {
+    /// Create a matcher whose first byte corresponds to document offset `start_offset`
+    pub fn new(regex: Regex, start_offset: usize) -> Self {
+        Self {
+            regex,
+            carry: Vec::new(),
+            carry_offset: start_offset,
+            resume_at: 0,
+        }
+    }
+
+    /// Feed the next chunk of data and return the match ranges (as document
+    /// byte offsets) that are now known to be complete
+    ///
+    /// Pass `is_final = true` with the last chunk (which may be empty) to flush
+    /// any matches that were deferred at the end of the stream.
+    pub fn feed(&mut self, chunk: &[u8], is_final: bool) -> Vec<std::ops::Range<usize>> {
+        self.carry.extend_from_slice(chunk);
+        let window = &self.carry;
+        let window_len = window.len();
+
+        let mut found = Vec::new();
+        let mut pos = self.resume_at;
+        while pos <= window_len {
+            let Some(m) = self.regex.find_at(window, pos) else {
+                // A match may still begin near the end and continue into the
+                // next chunk, so only skip past what can no longer start one
+                pos = pos.max(window_len.saturating_sub(OVERLAP_BYTES));
+                break;
+            };
+            // A match touching the end of the data might still grow once more
+            // data arrives, and one starting near the end might lose to an
+            // alternative that starts earlier but needs the next chunk (`cd`
+            // in `abcde` for `abcdef|cd`). Defer both, unless the match is
+            // already unreasonably long, and search again from the earliest
+            // position that may start a match spanning the boundary.
+            let near_end = m.start() >= window_len.saturating_sub(OVERLAP_BYTES);
+            let may_change =
+                (m.end() == window_len || near_end) && m.end() - m.start() < MAX_DEFERRED_MATCH;
+            if !is_final && may_change {
+                pos = pos
+                    .max(window_len.saturating_sub(OVERLAP_BYTES))
+                    .min(m.start());
+                break;
+            }
+            found.push(self.carry_offset + m.start()..self.carry_offset + m.end());
+            pos = if m.end() == m.start() {
+                m.end() + 1
+            } else {
+                m.end()
+            };
+        }
+
+        // Keep everything from the resume point on, plus some context before it
+        let pos = pos.min(window_len);
+        let keep_from = pos.saturating_sub(CONTEXT_BYTES);
+        self.carry.drain(..keep_from);
+        self.carry_offset += keep_from;
+        self.resume_at = pos - keep_from;
+
+        found
+    }
+}
+
+/// Sequential reader over a list of content regions
+///
+/// On-disk regions are read from `fs` in chunks of at most `chunk_size` bytes,
+/// so only a bounded amount of the file is ever held in memory at once.
+pub struct RegionReader {
+    fs: Arc<FsManager>,
+    regions: std::vec::IntoIter<ContentRegion>,
+    current: Option<ContentRegion>,
+    /// Bytes of `current` already consumed
+    consumed: usize,
+    chunk_size: usize,
+}
+
+impl RegionReader {
+    pub fn new(fs: Arc<FsManager>, regions: Vec<ContentRegion>, chunk_size: usize) -> Self {
+        Self {
+            fs,
+            regions: regions.into_iter(),
+            current: None,
+            consumed: 0,
+            chunk_size: chunk_size.max(1),
+        }
+    }
+
+    /// Read the next chunk, or `Ok(None)` once all regions are exhausted
+    pub fn next_chunk(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            if self.current.is_none() {
+                match self.regions.next() {
+                    Some(region) => {
+                        self.current = Some(region);
+                        self.consumed = 0;
+                    }
+                    None => return Ok(None),
+                }
+            }
+
+            let region = self.current.as_ref().expect("current region set above");
+            let remaining = region.len() - self.consumed;
+            if remaining == 0 {
+                self.current = None;
+                continue;
+            }
+            let take = remaining.min(self.chunk_size);
+
+            let chunk = match region {
+                ContentRegion::Loaded(data) => data[self.consumed..self.consumed + take].to_vec(),
+                ContentRegion::OnDisk {
+                    file_path,
+                    file_offset,
+                    ..
+                } => self.fs.block_on(self.fs.read_range(
+                    file_path,
+                    (file_offset + self.consumed) as u64,
+                    take,
+                ))?,
+            };
+            self.consumed += take;
+            return Ok(Some(chunk));
+        }
+    }
+}
+
+/// Run a search over `regions` to completion (or cancellation)
+///
+/// On-disk regions are read from `fs`. `start_offset` is the document offset
+/// of the first region. `on_batch` is called after each chunk with the match
+/// start offsets found in that chunk and the number of bytes searched so far.
+pub fn search_regions<F>(
+    fs: Arc<FsManager>,
+    regions: Vec<ContentRegion>,
+    start_offset: usize,
+    regex: Regex,
+    cancel: &AtomicBool,
+    mut on_batch: F,
+) -> io::Result<()>
+where
+    F: FnMut(Vec<usize>, usize),
+{
+    let mut reader = RegionReader::new(fs, regions, LOAD_CHUNK_SIZE);
+    let mut matcher = ChunkedMatcher::new(regex, start_offset);
+    let mut bytes_searched = 0;
+
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let chunk = reader.next_chunk()?;
+        let is_final = chunk.is_none();
+        let chunk = chunk.unwrap_or_default();
+        bytes_searched += chunk.len();
+
+        let matches = matcher
+            .feed(&chunk, is_final)
+            .into_iter()
+            .map(|range| range.start)
+            .collect();
+        on_batch(matches, bytes_searched);
+
+        if is_final {
+            return Ok(());
+        }
+    }
+}
+
+static NEXT_SEARCH_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Handle to a search running on a background thread
+///
+/// Dropping the handle cancels the search.
+#[derive(Debug)]
+pub struct StreamingSearch {
+    id: u64,
+    cancel: Arc<AtomicBool>,
+}
+
+impl StreamingSearch {
+    /// Start searching `regions` on a blocking thread of `runtime`
+    ///
+    /// Progress is reported through [`AsyncMessage::SearchProgress`] and
+    /// completion through [`AsyncMessage::SearchFinished`], both tagged with
+    /// [`StreamingSearch::id`].
+    pub fn spawn(
+        runtime: &tokio::runtime::Runtime,
+        sender: mpsc::Sender<AsyncMessage>,
+        fs: Arc<FsManager>,
+        regions: Vec<ContentRegion>,
+        start_offset: usize,
+        regex: Regex,
+    ) -> Self {
+        let id = NEXT_SEARCH_ID.fetch_add(1, Ordering::Relaxed);
+        let cancel = Arc::new(AtomicBool::new(false));
+        let total_bytes = regions.iter().map(ContentRegion::len).sum();
+
+        let thread_cancel = cancel.clone();
+        runtime.spawn_blocking(move || {
+            let progress_sender = sender.clone();
+            let result = search_regions(
+                fs,
+                regions,
+                start_offset,
+                regex,
+                &thread_cancel,
+                |matches, bytes_searched| {
+                    let _ = progress_sender.send(AsyncMessage::SearchProgress {
+                        search_id: id,
+                        matches,
+                        bytes_searched,
+                        total_bytes,
+                    });
+                },
+            );
+            if !thread_cancel.load(Ordering::Relaxed) {
+                let _ = sender.send(AsyncMessage::SearchFinished {
+                    search_id: id,
+                    error: result.err().map(|e| e.to_string()),
+                });
+            }
+        });
+
+        Self { id, cancel }
+    }
+
+    /// Identifier used to tag this search's async messages
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Stop the search; no further messages will be sent after the current chunk
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for StreamingSearch {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn find_all_in_chunks(pattern: &str, text: &[u8], chunk_size: usize) -> Vec<usize> {
+        let mut matcher = ChunkedMatcher::new(Regex::new(pattern).unwrap(), 0);
+        let mut found = Vec::new();
+        for chunk in text.chunks(chunk_size) {
+            found.extend(matcher.feed(chunk, false).into_iter().map(|r| r.start));
+        }
+        found.extend(matcher.feed(&[], true).into_iter().map(|r| r.start));
+        found
+    }
+
+    fn find_all(pattern: &str, text: &[u8]) -> Vec<usize> {
+        Regex::new(pattern)
+            .unwrap()
+            .find_iter(text)
+            .map(|m| m.start())
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_spanning_chunk_boundaries() {
+        let text = b"needle hay needle hay hay needle needle";
+        for chunk_size in 1..text.len() {
+            assert_eq!(
+                find_all_in_chunks("needle", text, chunk_size),
+                find_all("needle", text),
+                "chunk size {}",
+                chunk_size
+            );
+        }
+    }
+
+    #[test]
+    fn test_greedy_match_is_not_split() {
+        let text = b"aaaa b aaaaaaa";
+        for chunk_size in 1..text.len() {
+            assert_eq!(
+                find_all_in_chunks("a+", text, chunk_size),
+                vec![0, 7],
+                "chunk size {}",
+                chunk_size
+            );
+        }
+    }
+
+    #[test]
+    fn test_word_boundary_uses_previous_chunk_context() {
+        let text = b"foobar bar";
+        assert_eq!(find_all_in_chunks(r"\bbar\b", text, 3), vec![7]);
+    }
+
+    #[test]
+    fn test_match_offsets_are_relative_to_start_offset() {
+        let mut matcher = ChunkedMatcher::new(Regex::new("x").unwrap(), 100);
+        // Matches near the end of the data are only reported once it is known
+        // that no earlier-starting match spans into the next chunk
+        assert!(matcher.feed(b"abxab", false).is_empty());
+        assert_eq!(matcher.feed(b"x", true), vec![102..103, 105..106]);
+    }
+
+    #[test]
+    fn test_alternation_across_chunk_boundary() {
+        let text = b"abcdef cd abcde abcdef";
+        for pattern in ["abcdef|cd", "cd|abcdef", "b|abc", "ef|abcdef|c"] {
+            for chunk_size in 1..text.len() {
+                assert_eq!(
+                    find_all_in_chunks(pattern, text, chunk_size),
+                    find_all(pattern, text),
+                    "pattern {:?}, chunk size {}",
+                    pattern,
+                    chunk_size
+                );
+            }
+        }
+
+        let mut matcher = ChunkedMatcher::new(Regex::new("abcdef|cd").unwrap(), 0);
+        assert!(matcher.feed(b"abcde", false).is_empty());
+        assert_eq!(matcher.feed(b"f", true), vec![0..6]);
+    }
+
+    #[test]
+    fn test_search_regions_reads_disk_and_memory() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"...match...").unwrap();
+        file.flush().unwrap();
+
+        let regions = vec![
+            ContentRegion::OnDisk {
+                file_path: file.path().to_path_buf(),
+                file_offset: 3,
+                bytes: 6,
+            },
+            ContentRegion::Loaded(b"tch match".to_vec()),
+        ];
+
+        let cancel = AtomicBool::new(false);
+        let mut found = Vec::new();
+        let mut last_progress = 0;
+        search_regions(
+            FsManager::local(),
+            regions,
+            10,
+            Regex::new("match").unwrap(),
+            &cancel,
+            |m, bytes| {
+                found.extend(m);
+                last_progress = bytes;
+            },
+        )
+        .unwrap();
+
+        // "match.." + "tch match" => "match..tch match"
+        assert_eq!(found, vec![10, 20]);
+        assert_eq!(last_progress, 15);
+    }
+
+    #[test]
+    fn test_search_regions_stops_when_cancelled() {
+        let cancel = AtomicBool::new(true);
+        let mut batches = 0;
+        search_regions(
+            FsManager::local(),
+            vec![ContentRegion::Loaded(b"abc".to_vec())],
+            0,
+            Regex::new("a").unwrap(),
+            &cancel,
+            |_, _| batches += 1,
+        )
+        .unwrap();
+        assert_eq!(batches, 0);
+    }
+}
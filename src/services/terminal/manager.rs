@@ -20,7 +20,9 @@
 
 use super::term::TerminalState;
 use crate::services::async_bridge::AsyncBridge;
-use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+use crate::services::remote::protocol::PtySize as RemotePtySize;
+use crate::services::remote::{RemoteChild, RemoteConnection};
+use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtySize};
 use std::collections::HashMap;
 use std::io::{Read, Write};
 use std::sync::atomic::AtomicBool;
@@ -48,6 +50,43 @@ enum TerminalCommand {
     Shutdown,
 }
 
+/// The shell behind a terminal: a local PTY, or one on the remote host
+enum TerminalProcess {
+    Local {
+        master: Box<dyn MasterPty + Send>,
+        child: Box<dyn portable_pty::Child + Send + Sync>,
+    },
+    Remote(RemoteChild),
+}
+
+impl TerminalProcess {
+    fn resize(&self, cols: u16, rows: u16) -> Result<(), String> {
+        match self {
+            Self::Local { master, .. } => master
+                .resize(PtySize {
+                    rows,
+                    cols,
+                    pixel_width: 0,
+                    pixel_height: 0,
+                })
+                .map_err(|e| e.to_string()),
+            Self::Remote(child) => child.resize(cols, rows).map_err(|e| e.to_string()),
+        }
+    }
+
+    fn kill(&mut self) {
+        match self {
+            Self::Local { child, .. } => {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            Self::Remote(child) => {
+                let _ = child.kill();
+            }
+        }
+    }
+}
+
 /// Handle to a running terminal session
 pub struct TerminalHandle {
     /// Terminal state (grid, cursor, etc.)
@@ -118,6 +157,8 @@ pub struct TerminalManager {
     next_id: usize,
     /// Async bridge for sending notifications to main loop
     async_bridge: Option<AsyncBridge>,
+    /// Connection to the remote host shells run on, when editing remotely
+    remote: Option<Arc<RemoteConnection>>,
 }
 
 impl TerminalManager {
@@ -127,6 +168,7 @@ impl TerminalManager {
             terminals: HashMap::new(),
             next_id: 0,
             async_bridge: None,
+            remote: None,
         }
     }
 
@@ -135,6 +177,11 @@ impl TerminalManager {
         self.async_bridge = Some(bridge);
     }
 
+    /// Run terminal shells on a remote host, over the given connection
+    pub fn set_remote(&mut self, remote: Arc<RemoteConnection>) {
+        self.remote = Some(remote);
+    }
+
     /// Peek at the next terminal ID that would be assigned.
     pub fn next_terminal_id(&self) -> TerminalId {
         TerminalId(self.next_id)
@@ -164,32 +211,10 @@ impl TerminalManager {
 
         // Try to spawn a real PTY-backed terminal first.
         let handle_result: Result<TerminalHandle, String> = (|| {
-            // Create PTY
-            let pty_system = native_pty_system();
-            let pty_pair = pty_system
-                .openpty(PtySize {
-                    rows,
-                    cols,
-                    pixel_width: 0,
-                    pixel_height: 0,
-                })
-                .map_err(|e| format!("Failed to open PTY: {}", e))?;
-
-            // Detect shell
-            let shell = detect_shell();
-            tracing::info!("Spawning terminal with shell: {}", shell);
-
-            // Build command
-            let mut cmd = CommandBuilder::new(&shell);
-            if let Some(ref dir) = cwd {
-                cmd.cwd(dir);
-            }
-
-            // Spawn the shell process
-            let mut child = pty_pair
-                .slave
-                .spawn_command(cmd)
-                .map_err(|e| format!("Failed to spawn shell: {}", e))?;
+            let (mut process, mut reader, mut master, shell) = match &self.remote {
+                Some(remote) => spawn_remote_shell(remote, cols, rows, cwd.as_deref())?,
+                None => spawn_local_shell(cols, rows, cwd.as_deref())?,
+            };
 
             // Create terminal state
             let state = Arc::new(Mutex::new(TerminalState::new(cols, rows)));
@@ -213,17 +238,6 @@ impl TerminalManager {
             let alive = Arc::new(AtomicBool::new(true));
             let alive_clone = alive.clone();
 
-            // Get master for I/O
-            let mut master = pty_pair
-                .master
-                .take_writer()
-                .map_err(|e| format!("Failed to get PTY writer: {}", e))?;
-
-            let mut reader = pty_pair
-                .master
-                .try_clone_reader()
-                .map_err(|e| format!("Failed to get PTY reader: {}", e))?;
-
             // Clone state for reader thread
             let state_clone = state.clone();
             let async_bridge = self.async_bridge.clone();
@@ -353,7 +367,6 @@ impl TerminalManager {
             });
 
             // Spawn writer thread
-            let pty_size_ref = pty_pair.master;
             thread::spawn(move || {
                 loop {
                     match command_rx.recv() {
@@ -365,12 +378,7 @@ impl TerminalManager {
                             let _ = master.flush();
                         }
                         Ok(TerminalCommand::Resize { cols, rows }) => {
-                            if let Err(e) = pty_size_ref.resize(PtySize {
-                                rows,
-                                cols,
-                                pixel_width: 0,
-                                pixel_height: 0,
-                            }) {
+                            if let Err(e) = process.resize(cols, rows) {
                                 tracing::warn!("Failed to resize PTY: {}", e);
                             }
                         }
@@ -380,8 +388,7 @@ impl TerminalManager {
                     }
                 }
                 // Clean up child process
-                let _ = child.kill();
-                let _ = child.wait();
+                process.kill();
             });
 
             // Create handle
@@ -473,6 +480,96 @@ impl Drop for TerminalManager {
     }
 }
 
+/// A started shell: its process, output reader, input writer and command
+type SpawnedShell = (
+    TerminalProcess,
+    Box<dyn Read + Send>,
+    Box<dyn Write + Send>,
+    String,
+);
+
+/// Start the user's shell under a local PTY
+fn spawn_local_shell(
+    cols: u16,
+    rows: u16,
+    cwd: Option<&std::path::Path>,
+) -> Result<SpawnedShell, String> {
+    // Create PTY
+    let pty_system = native_pty_system();
+    let pty_pair = pty_system
+        .openpty(PtySize {
+            rows,
+            cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .map_err(|e| format!("Failed to open PTY: {}", e))?;
+
+    // Detect shell
+    let shell = detect_shell();
+    tracing::info!("Spawning terminal with shell: {}", shell);
+
+    // Build command
+    let mut cmd = CommandBuilder::new(&shell);
+    if let Some(dir) = cwd {
+        cmd.cwd(dir);
+    }
+
+    // Spawn the shell process
+    let child = pty_pair
+        .slave
+        .spawn_command(cmd)
+        .map_err(|e| format!("Failed to spawn shell: {}", e))?;
+
+    // Get master for I/O
+    let writer = pty_pair
+        .master
+        .take_writer()
+        .map_err(|e| format!("Failed to get PTY writer: {}", e))?;
+
+    let reader = pty_pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| format!("Failed to get PTY reader: {}", e))?;
+
+    let process = TerminalProcess::Local {
+        master: pty_pair.master,
+        child,
+    };
+    Ok((process, reader, writer, shell))
+}
+
+/// Start the remote user's shell under a PTY on the remote host
+fn spawn_remote_shell(
+    remote: &RemoteConnection,
+    cols: u16,
+    rows: u16,
+    cwd: Option<&std::path::Path>,
+) -> Result<SpawnedShell, String> {
+    let pty = RemotePtySize { cols, rows };
+    let mut child = futures::executor::block_on(remote.spawn_process(None, &[], cwd, Some(pty)))
+        .map_err(|e| format!("Failed to spawn shell on {}: {}", remote.destination(), e))?;
+    let shell = child.command().to_string();
+    tracing::info!(
+        "Spawned terminal on {} with shell: {}",
+        remote.destination(),
+        shell
+    );
+
+    let writer = child
+        .take_stdin()
+        .ok_or_else(|| "Failed to get remote PTY writer".to_string())?;
+    let reader = child
+        .take_stdout()
+        .ok_or_else(|| "Failed to get remote PTY reader".to_string())?;
+    Ok((
+        TerminalProcess::Remote(child),
+        Box::new(reader),
+        Box::new(writer),
+        shell,
+    ))
+}
+
 /// Detect the user's shell
 pub fn detect_shell() -> String {
     // Try $SHELL environment variable first
@@ -0,0 +1,323 @@
+//! Trash
+//!
+//! Files and directories deleted from the file explorer are moved to a trash
+//! directory rather than removed, so that deleting can be undone. The trash
+//! follows the freedesktop.org trash specification: a trashed item goes to
+//! `files/`, next to a `.trashinfo` file in `info/` recording where it came
+//! from and when it was deleted, so desktop file managers list (and can
+//! restore) the items trashed by Fresh.
+
+use crate::services::fs::FsManager;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Entries counted at most when measuring what is about to be deleted
+const MAX_MEASURED_ENTRIES: usize = 10_000;
+
+/// A trash directory
+#[derive(Debug, Clone)]
+pub struct Trash {
+    dir: PathBuf,
+}
+
+/// An item moved to the trash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashedItem {
+    /// Where the item was
+    pub original: PathBuf,
+    /// Name of the item in the trash
+    pub name: String,
+}
+
+impl Trash {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn files_dir(&self) -> PathBuf {
+        self.dir.join("files")
+    }
+
+    fn info_path(&self, name: &str) -> PathBuf {
+        self.dir.join("info").join(format!("{}.trashinfo", name))
+    }
+
+    /// Move `path` to the trash
+    pub fn trash(&self, path: &Path) -> io::Result<TrashedItem> {
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Cannot trash {}", path.display()),
+                )
+            })?;
+        fs::create_dir_all(self.files_dir())?;
+        fs::create_dir_all(self.dir.join("info"))?;
+
+        // Reserve a name by creating its info file, as the specification
+        // requires, numbering names already taken
+        let mut name = file_name.to_string();
+        let mut info = None;
+        for n in 2.. {
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(self.info_path(&name))
+            {
+                Ok(file) => {
+                    info = Some(file);
+                    break;
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    name = format!("{}.{}", file_name, n);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        let mut info = info.expect("a name is eventually free");
+        let encoded_path = url::Url::from_file_path(path)
+            .map(|url| url.path().to_string())
+            .unwrap_or_else(|_| path.display().to_string());
+        let written = write!(
+            info,
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            encoded_path,
+            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+        );
+
+        let moved = written.and_then(|()| move_item(path, &self.files_dir().join(&name)));
+        if let Err(e) = moved {
+            let _ = fs::remove_file(self.info_path(&name));
+            return Err(e);
+        }
+        Ok(TrashedItem {
+            original: path.to_path_buf(),
+            name,
+        })
+    }
+
+    /// Move a trashed item back to where it was
+    pub fn restore(&self, item: &TrashedItem) -> io::Result<()> {
+        if item.original.symlink_metadata().is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", item.original.display()),
+            ));
+        }
+        if let Some(parent) = item.original.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        move_item(&self.files_dir().join(&item.name), &item.original)?;
+        let _ = fs::remove_file(self.info_path(&item.name));
+        Ok(())
+    }
+}
+
+/// Move a file or directory, copying it when it is on another filesystem
+fn move_item(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_item(from, to)?;
+            if from.is_dir() {
+                fs::remove_dir_all(from)
+            } else {
+                fs::remove_file(from)
+            }
+        }
+        result => result,
+    }
+}
+
+fn copy_item(from: &Path, to: &Path) -> io::Result<()> {
+    let metadata = from.symlink_metadata()?;
+    if metadata.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_item(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        #[cfg(unix)]
+        if metadata.file_type().is_symlink() {
+            return std::os::unix::fs::symlink(fs::read_link(from)?, to);
+        }
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+/// Size of a file or directory tree, as shown before deleting it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ItemSize {
+    pub files: usize,
+    pub dirs: usize,
+    pub bytes: u64,
+    /// Whether the tree was too large to be counted entirely
+    pub truncated: bool,
+}
+
+impl std::ops::Add for ItemSize {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            files: self.files + other.files,
+            dirs: self.dirs + other.dirs,
+            bytes: self.bytes + other.bytes,
+            truncated: self.truncated || other.truncated,
+        }
+    }
+}
+
+impl ItemSize {
+    /// Measure the tree at `path`, counting directories below it only
+    pub async fn measure(fs_manager: &FsManager, path: &Path) -> io::Result<Self> {
+        let mut size = Self::default();
+        let entry = fs_manager.get_entry(path).await?;
+        if !entry.is_dir() {
+            size.files = 1;
+            size.bytes = entry.metadata.and_then(|m| m.size).unwrap_or(0);
+            return Ok(size);
+        }
+
+        let mut pending = vec![path.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            for entry in fs_manager.list_dir_with_metadata(dir).await? {
+                if size.files + size.dirs >= MAX_MEASURED_ENTRIES {
+                    size.truncated = true;
+                    return Ok(size);
+                }
+                if entry.is_dir() {
+                    size.dirs += 1;
+                    pending.push(entry.path);
+                } else {
+                    size.files += 1;
+                    size.bytes += entry.metadata.and_then(|m| m.size).unwrap_or(0);
+                }
+            }
+        }
+        Ok(size)
+    }
+
+    /// Describe the size, e.g. "12 files, 3 folders, 48.0 KB"
+    pub fn describe(&self) -> String {
+        let more = if self.truncated { "over " } else { "" };
+        let mut parts = Vec::new();
+        if self.dirs > 0 || self.files != 1 {
+            parts.push(format!(
+                "{}{} {}",
+                more,
+                self.files,
+                plural(self.files, "file")
+            ));
+        }
+        if self.dirs > 0 {
+            parts.push(format!("{} {}", self.dirs, plural(self.dirs, "folder")));
+        }
+        parts.push(format!("{}{}", more, format_bytes(self.bytes)));
+        parts.join(", ")
+    }
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        noun.to_string()
+    } else {
+        format!("{}s", noun)
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_trash_and_restore() {
+        let temp = TempDir::new().unwrap();
+        let trash = Trash::new(temp.path().join("Trash"));
+        let dir = temp.path().join("project").join("src");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("main.rs"), "fn main() {}").unwrap();
+
+        let item = trash.trash(&dir).unwrap();
+        assert!(!dir.exists());
+        assert_eq!(item.name, "src");
+        assert!(temp.path().join("Trash/files/src/main.rs").exists());
+        let info = fs::read_to_string(temp.path().join("Trash/info/src.trashinfo")).unwrap();
+        assert!(info.starts_with("[Trash Info]\nPath=/"));
+        assert!(info.contains("DeletionDate="));
+
+        trash.restore(&item).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("main.rs")).unwrap(),
+            "fn main() {}"
+        );
+        assert!(!temp.path().join("Trash/info/src.trashinfo").exists());
+    }
+
+    #[test]
+    fn test_trash_numbers_names_taken() {
+        let temp = TempDir::new().unwrap();
+        let trash = Trash::new(temp.path().join("Trash"));
+        let file = temp.path().join("notes.txt");
+
+        fs::write(&file, "first").unwrap();
+        let first = trash.trash(&file).unwrap();
+        fs::write(&file, "second").unwrap();
+        let second = trash.trash(&file).unwrap();
+        assert_eq!(first.name, "notes.txt");
+        assert_eq!(second.name, "notes.txt.2");
+
+        // Restoring over an existing file fails
+        fs::write(&file, "third").unwrap();
+        assert!(trash.restore(&first).is_err());
+        fs::remove_file(&file).unwrap();
+        trash.restore(&first).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "first");
+    }
+
+    #[tokio::test]
+    async fn test_item_size() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("dir");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.txt"), vec![0u8; 1000]).unwrap();
+        fs::write(dir.join("sub/b.txt"), vec![0u8; 1048]).unwrap();
+
+        let fs_manager = FsManager::local();
+        let size = ItemSize::measure(&fs_manager, &dir).await.unwrap();
+        assert_eq!(
+            size,
+            ItemSize {
+                files: 2,
+                dirs: 1,
+                bytes: 2048,
+                truncated: false
+            }
+        );
+        assert_eq!(size.describe(), "2 files, 1 folder, 2.0 KB");
+
+        let file_size = ItemSize::measure(&fs_manager, &dir.join("a.txt"))
+            .await
+            .unwrap();
+        assert_eq!(file_size.describe(), "1000 B");
+    }
+}
@@ -0,0 +1,298 @@
+//! Persistent undo history
+//!
+//! A file's undo tree is saved to the data directory, in a file named after
+//! a hash of the file's path, together with a checksum of the file content it
+//! applies to. When the file is reopened and its content still matches, the
+//! tree is restored so edits from earlier sessions can be undone.
+//!
+//! Each history is capped to the edits nearest the saved state (see
+//! [`EventLog::to_persisted`]), and the histories of the least recently saved
+//! files are pruned once there are more than the configured number.
+//!
+//! The editor saves histories through an [`UndoHistoryWriter`], which does the
+//! checksumming and writing on a background thread.
+
+use crate::model::event::{EventLog, PersistedUndoTree};
+use crate::services::recovery::path_hash;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+
+/// Contents of a persisted undo history file
+#[derive(Debug, Serialize, Deserialize)]
+struct UndoHistoryFile {
+    /// The edited file
+    path: PathBuf,
+    /// Checksum of the content the saved state of the tree corresponds to
+    checksum: String,
+    /// The undo tree itself
+    tree: PersistedUndoTree,
+}
+
+/// Saves and restores undo histories in a directory
+#[derive(Debug, Clone)]
+pub struct UndoHistoryStore {
+    dir: PathBuf,
+    max_entries: usize,
+    max_files: usize,
+}
+
+impl UndoHistoryStore {
+    /// Create a store keeping up to `max_entries` edits per file and the
+    /// histories of up to `max_files` files
+    pub fn new(dir: PathBuf, max_entries: usize, max_files: usize) -> Self {
+        Self {
+            dir,
+            max_entries,
+            max_files,
+        }
+    }
+
+    fn history_path(&self, path: &Path) -> PathBuf {
+        self.dir.join(format!("{}.json", path_hash(path)))
+    }
+
+    /// Save the undo history of `path`, whose saved state has `content`
+    ///
+    /// A log without edits removes any previously saved history.
+    pub fn save(&self, path: &Path, content: &str, log: &EventLog) -> io::Result<()> {
+        self.save_tree(path, content, log.to_persisted(self.max_entries))
+    }
+
+    /// Save an undo tree taken from a log with [`EventLog::to_persisted`]
+    fn save_tree(
+        &self,
+        path: &Path,
+        content: &str,
+        tree: Option<PersistedUndoTree>,
+    ) -> io::Result<()> {
+        let history_path = self.history_path(path);
+        let tree = match tree {
+            Some(tree) if !tree.entries.is_empty() => tree,
+            _ => {
+                if history_path.exists() {
+                    std::fs::remove_file(&history_path)?;
+                }
+                return Ok(());
+            }
+        };
+
+        let file = UndoHistoryFile {
+            path: path.to_path_buf(),
+            checksum: content_checksum(content),
+            tree,
+        };
+        let json = serde_json::to_string(&file).map_err(io::Error::other)?;
+        std::fs::create_dir_all(&self.dir)?;
+        // Write atomically so a crash can't leave a truncated history
+        let temp_path = history_path.with_extension("json.tmp");
+        std::fs::write(&temp_path, json)?;
+        std::fs::rename(&temp_path, &history_path)?;
+
+        self.prune()
+    }
+
+    /// Load the undo history of `path` if it was saved for the same
+    /// `content`; the log is positioned at its saved state
+    pub fn load(&self, path: &Path, content: &str) -> io::Result<Option<EventLog>> {
+        let history_path = self.history_path(path);
+        if !history_path.exists() {
+            return Ok(None);
+        }
+        let json = std::fs::read_to_string(&history_path)?;
+        let file: UndoHistoryFile = serde_json::from_str(&json).map_err(io::Error::other)?;
+        if file.path != path || file.checksum != content_checksum(content) {
+            return Ok(None);
+        }
+        Ok(EventLog::from_persisted(file.tree))
+    }
+
+    /// Delete the oldest histories beyond the file limit
+    fn prune(&self) -> io::Result<()> {
+        let mut histories: Vec<(std::time::SystemTime, PathBuf)> = std::fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+                Some((modified, path))
+            })
+            .collect();
+        if histories.len() <= self.max_files {
+            return Ok(());
+        }
+
+        // Newest first
+        histories.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+        for (_, path) in histories.into_iter().skip(self.max_files) {
+            if let Err(e) = std::fs::remove_file(&path) {
+                tracing::warn!("Failed to prune undo history {:?}: {}", path, e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An undo history queued for an [`UndoHistoryWriter`]
+struct QueuedHistory {
+    path: PathBuf,
+    content: String,
+    tree: Option<PersistedUndoTree>,
+}
+
+/// Saves undo histories to a store on a background thread, in the order they
+/// are queued
+///
+/// Dropping the writer waits for the queued histories to be written.
+#[derive(Debug)]
+pub struct UndoHistoryWriter {
+    max_entries: usize,
+    sender: Option<mpsc::Sender<QueuedHistory>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl UndoHistoryWriter {
+    /// Start the thread writing to `store`
+    pub fn spawn(store: UndoHistoryStore) -> io::Result<Self> {
+        let max_entries = store.max_entries;
+        let (sender, receiver) = mpsc::channel::<QueuedHistory>();
+        let thread = std::thread::Builder::new()
+            .name("undo-history-writer".to_string())
+            .spawn(move || {
+                for history in receiver {
+                    if let Err(e) = store.save_tree(&history.path, &history.content, history.tree) {
+                        tracing::warn!("Failed to save undo history for {:?}: {}", history.path, e);
+                    }
+                }
+            })?;
+        Ok(Self {
+            max_entries,
+            sender: Some(sender),
+            thread: Some(thread),
+        })
+    }
+
+    /// Queue the undo history of `path`, whose saved state has `content`
+    ///
+    /// Only the part of the log that is persisted is copied here; hashing and
+    /// writing happen on the writer's thread.
+    pub fn queue(&self, path: &Path, content: String, log: &EventLog) {
+        let history = QueuedHistory {
+            path: path.to_path_buf(),
+            content,
+            tree: log.to_persisted(self.max_entries),
+        };
+        if let Some(sender) = &self.sender {
+            if sender.send(history).is_err() {
+                tracing::warn!("Undo history writer stopped; history for {:?} lost", path);
+            }
+        }
+    }
+
+    /// Wait until every queued history is written, then stop the thread
+    pub fn finish(&mut self) {
+        self.sender = None;
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                tracing::warn!("Undo history writer panicked");
+            }
+        }
+    }
+}
+
+impl Drop for UndoHistoryWriter {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// SHA-256 checksum of file content
+pub fn content_checksum(content: &str) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::event::{CursorId, Event};
+    use tempfile::TempDir;
+
+    fn edited_log() -> EventLog {
+        let mut log = EventLog::new();
+        log.append(Event::Insert {
+            position: 0,
+            text: "hello ".to_string(),
+            cursor_id: CursorId(0),
+        });
+        log.mark_saved();
+        log
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = UndoHistoryStore::new(temp_dir.path().join("undo"), 100, 10);
+        let path = Path::new("/project/notes.txt");
+
+        store.save(path, "hello world", &edited_log()).unwrap();
+
+        let mut log = store.load(path, "hello world").unwrap().unwrap();
+        assert!(log.is_at_saved_position());
+        assert_eq!(log.undo().len(), 1);
+
+        // Changed content or another path doesn't restore anything
+        assert!(store.load(path, "hello there").unwrap().is_none());
+        assert!(store
+            .load(Path::new("/project/other.txt"), "hello world")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_writer_saves_in_queue_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = UndoHistoryStore::new(temp_dir.path().join("undo"), 100, 10);
+        let path = Path::new("/project/notes.txt");
+
+        let mut writer = UndoHistoryWriter::spawn(store.clone()).unwrap();
+        writer.queue(path, "hello world".to_string(), &edited_log());
+        writer.queue(path, "hello there".to_string(), &edited_log());
+        writer.finish();
+
+        assert!(store.load(path, "hello world").unwrap().is_none());
+        assert!(store.load(path, "hello there").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_save_without_edits_removes_history() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = UndoHistoryStore::new(temp_dir.path().join("undo"), 100, 10);
+        let path = Path::new("/project/notes.txt");
+
+        store.save(path, "hello world", &edited_log()).unwrap();
+        store.save(path, "hello world", &EventLog::new()).unwrap();
+        assert!(store.load(path, "hello world").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_prune_keeps_newest_histories() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = UndoHistoryStore::new(temp_dir.path().join("undo"), 100, 2);
+        let paths: Vec<PathBuf> = (0..3)
+            .map(|i| PathBuf::from(format!("/project/{}.txt", i)))
+            .collect();
+
+        for path in &paths {
+            store.save(path, "hello world", &edited_log()).unwrap();
+            // Make sure modification times differ
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+
+        assert!(store.load(&paths[0], "hello world").unwrap().is_none());
+        assert!(store.load(&paths[1], "hello world").unwrap().is_some());
+        assert!(store.load(&paths[2], "hello world").unwrap().is_some());
+    }
+}
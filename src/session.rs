@@ -6,6 +6,7 @@
 //! - File explorer state
 //! - Search/replace history and options
 //! - Bookmarks
+//! - Buffers with unsaved changes stashed on quit (hot exit)
 //!
 //! ## Storage
 //!
@@ -18,6 +19,10 @@
 //!
 //! The encoding is fully reversible using `decode_filename_to_path()`.
 //!
+//! Besides this default session, a working directory can have any number of
+//! named sessions (e.g. "review-pr-123" and "feature-x" in the same repo),
+//! stored in `$XDG_DATA_HOME/fresh/sessions/named/{encoded_path}/{name}.json`.
+//!
 //! ## Crash Resistance
 //!
 //! Uses atomic writes: write to temp file, then rename.
@@ -43,6 +48,10 @@ pub struct Session {
     /// Working directory this session belongs to (for validation)
     pub working_dir: PathBuf,
 
+    /// Name of the session (None for the working directory's default session)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
     /// Split layout tree
     pub split_layout: SerializedSplitNode,
 
@@ -75,6 +84,10 @@ pub struct Session {
     #[serde(default)]
     pub terminals: Vec<SerializedTerminalSession>,
 
+    /// Buffers with unsaved changes stashed on quit (hot exit)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hot_exit: Vec<SerializedHotExitBuffer>,
+
     /// Timestamp when session was saved (Unix epoch seconds)
     pub saved_at: u64,
 }
@@ -155,6 +168,10 @@ pub struct SerializedFileState {
 
     /// Scroll position (byte offset)
     pub scroll: SerializedScroll,
+
+    /// Folded regions
+    #[serde(default)]
+    pub folds: Vec<SerializedFold>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -181,6 +198,14 @@ pub struct SerializedScroll {
     pub left_column: usize,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SerializedFold {
+    /// First hidden byte (start of the line after the fold's header line)
+    pub start: usize,
+    /// First byte after the last hidden line
+    pub end: usize,
+}
+
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub enum SerializedViewMode {
     #[default]
@@ -266,11 +291,26 @@ pub struct SerializedBookmark {
     pub position: usize,
 }
 
-/// Reference to an open tab (file path or terminal index)
+/// Reference to an open tab (file path, terminal index, or the stash id of
+/// an unnamed buffer kept by hot exit)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SerializedTabRef {
     File(PathBuf),
     Terminal(usize),
+    Unnamed(String),
+}
+
+/// A buffer stashed on quit by hot exit
+///
+/// The content and undo tree are stored under `id` in the project's hot exit
+/// directory (see `DirectoryContext::hot_exit_dir_for`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SerializedHotExitBuffer {
+    /// Id of the stash
+    pub id: String,
+    /// Absolute path of the file (None for unnamed buffers); files outside
+    /// the working directory are stashed too
+    pub file_path: Option<PathBuf>,
 }
 
 /// Persisted metadata for a terminal session
@@ -389,6 +429,39 @@ pub fn get_session_path(working_dir: &Path) -> io::Result<PathBuf> {
     Ok(get_sessions_dir()?.join(filename))
 }
 
+/// Get the directory holding the named sessions of a working directory
+pub fn get_named_sessions_dir(working_dir: &Path) -> io::Result<PathBuf> {
+    let canonical = working_dir
+        .canonicalize()
+        .unwrap_or_else(|_| working_dir.to_path_buf());
+    Ok(get_sessions_dir()?
+        .join("named")
+        .join(encode_path_for_filename(&canonical)))
+}
+
+/// Get the session file path for a named session of a working directory
+pub fn get_named_session_path(working_dir: &Path, name: &str) -> io::Result<PathBuf> {
+    Ok(get_named_sessions_dir(working_dir)?.join(format!("{}.json", name)))
+}
+
+/// Check that `name` can be used as a session name
+///
+/// Names become file names, so they are limited to letters, digits, spaces,
+/// `-`, `_` and `.`, and may not start with a `.`.
+pub fn validate_session_name(name: &str) -> Result<(), SessionError> {
+    let valid = !name.is_empty()
+        && name.len() <= 100
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ' '));
+    if valid {
+        Ok(())
+    } else {
+        Err(SessionError::InvalidName(name.to_string()))
+    }
+}
+
 /// Session error types
 #[derive(Debug)]
 pub enum SessionError {
@@ -396,6 +469,9 @@ pub enum SessionError {
     Json(serde_json::Error),
     WorkdirMismatch { expected: PathBuf, found: PathBuf },
     VersionTooNew { version: u32, max_supported: u32 },
+    InvalidName(String),
+    NotFound(String),
+    AlreadyExists(String),
 }
 
 impl std::fmt::Display for SessionError {
@@ -420,6 +496,11 @@ impl std::fmt::Display for SessionError {
                     version, max_supported
                 )
             }
+            SessionError::InvalidName(name) => write!(f, "Invalid session name: {:?}", name),
+            SessionError::NotFound(name) => write!(f, "No session named {:?}", name),
+            SessionError::AlreadyExists(name) => {
+                write!(f, "A session named {:?} already exists", name)
+            }
         }
     }
 }
@@ -449,7 +530,16 @@ impl From<serde_json::Error> for SessionError {
 impl Session {
     /// Load session for a working directory (if exists)
     pub fn load(working_dir: &Path) -> Result<Option<Session>, SessionError> {
-        let path = get_session_path(working_dir)?;
+        Self::load_from(&get_session_path(working_dir)?, working_dir)
+    }
+
+    /// Load a named session of a working directory (if exists)
+    pub fn load_named(working_dir: &Path, name: &str) -> Result<Option<Session>, SessionError> {
+        validate_session_name(name)?;
+        Self::load_from(&get_named_session_path(working_dir, name)?, working_dir)
+    }
+
+    fn load_from(path: &Path, working_dir: &Path) -> Result<Option<Session>, SessionError> {
         tracing::debug!("Looking for session at {:?}", path);
 
         if !path.exists() {
@@ -458,7 +548,7 @@ impl Session {
         }
 
         tracing::debug!("Loading session from {:?}", path);
-        let content = std::fs::read_to_string(&path)?;
+        let content = std::fs::read_to_string(path)?;
         let session: Session = serde_json::from_str(&content)?;
 
         tracing::debug!(
@@ -509,7 +599,13 @@ impl Session {
     /// 2. Sync to disk (fsync)
     /// 3. Atomically rename to the final path
     pub fn save(&self) -> Result<(), SessionError> {
-        let path = get_session_path(&self.working_dir)?;
+        let path = match &self.name {
+            Some(name) => {
+                validate_session_name(name)?;
+                get_named_session_path(&self.working_dir, name)?
+            }
+            None => get_session_path(&self.working_dir)?,
+        };
         tracing::debug!("Saving session to {:?}", path);
 
         // Ensure directory exists
@@ -547,11 +643,45 @@ impl Session {
         Ok(())
     }
 
+    /// Names of the named sessions of a working directory, sorted
+    pub fn list_named(working_dir: &Path) -> Result<Vec<String>, SessionError> {
+        let dir = get_named_sessions_dir(working_dir)?;
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut names: Vec<String> = std::fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                path.file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .map(|stem| stem.to_string())
+            })
+            .filter(|name| validate_session_name(name).is_ok())
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Delete a named session of a working directory
+    pub fn delete_named(working_dir: &Path, name: &str) -> Result<(), SessionError> {
+        validate_session_name(name)?;
+        let path = get_named_session_path(working_dir, name)?;
+        if !path.exists() {
+            return Err(SessionError::NotFound(name.to_string()));
+        }
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
     /// Create a new session with current timestamp
     pub fn new(working_dir: PathBuf) -> Self {
         Self {
             version: SESSION_VERSION,
             working_dir,
+            name: None,
             split_layout: SerializedSplitNode::Leaf {
                 file_path: None,
                 split_id: 0,
@@ -564,6 +694,7 @@ impl Session {
             search_options: SearchOptions::default(),
             bookmarks: HashMap::new(),
             terminals: Vec::new(),
+            hot_exit: Vec::new(),
             saved_at: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap_or_default()
@@ -718,6 +849,7 @@ mod tests {
                 top_view_line_offset: 2,
                 left_column: 10,
             },
+            folds: vec![SerializedFold { start: 20, end: 80 }],
         };
 
         let json = serde_json::to_string(&file_state).unwrap();
@@ -729,6 +861,8 @@ mod tests {
         assert_eq!(restored.additional_cursors.len(), 1);
         assert_eq!(restored.scroll.top_byte, 500);
         assert_eq!(restored.scroll.left_column, 10);
+        assert_eq!(restored.folds.len(), 1);
+        assert_eq!(restored.folds[0].end, 80);
     }
 
     #[test]
@@ -890,6 +1024,31 @@ mod tests {
         let _ = fs::remove_dir_all(&temp_dir);
     }
 
+    #[test]
+    fn test_named_session_serialization() {
+        let session = Session::new(PathBuf::from("/test"));
+        let json = serde_json::to_string(&session).unwrap();
+        assert!(!json.contains("\"name\""));
+
+        let mut named = session.clone();
+        named.name = Some("review-pr-123".to_string());
+        let json = serde_json::to_string(&named).unwrap();
+        let restored: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.name.as_deref(), Some("review-pr-123"));
+    }
+
+    #[test]
+    fn test_validate_session_name() {
+        assert!(validate_session_name("feature-x").is_ok());
+        assert!(validate_session_name("review pr_123.v2").is_ok());
+
+        assert!(validate_session_name("").is_err());
+        assert!(validate_session_name(".hidden").is_err());
+        assert!(validate_session_name("a/b").is_err());
+        assert!(validate_session_name("..").is_err());
+        assert!(validate_session_name(&"x".repeat(101)).is_err());
+    }
+
     #[test]
     fn test_session_version_check() {
         let session = Session::new(PathBuf::from("/test"));
@@ -3,17 +3,19 @@ use crate::model::cursor::{Cursor, Cursors};
 use crate::model::document_model::{
     DocumentCapabilities, DocumentModel, DocumentPosition, ViewportContent, ViewportLine,
 };
+use crate::model::encoding::TextEncoding;
 use crate::model::event::{
-    Event, MarginContentData, MarginPositionData, OverlayFace as EventOverlayFace, PopupData,
-    PopupPositionData,
+    CursorId, Event, MarginContentData, MarginPositionData, OverlayFace as EventOverlayFace,
+    PopupData, PopupPositionData,
 };
 use crate::model::marker::MarkerList;
 use crate::primitives::grammar_registry::GrammarRegistry;
 use crate::primitives::highlight_engine::HighlightEngine;
-use crate::primitives::highlighter::Language;
 use crate::primitives::indent::IndentCalculator;
+use crate::primitives::selection_range::SelectionStep;
 use crate::primitives::semantic_highlight::SemanticHighlighter;
 use crate::primitives::text_property::TextPropertyManager;
+use crate::services::fs::FsManager;
 use crate::view::margin::{MarginAnnotation, MarginContent, MarginManager, MarginPosition};
 use crate::view::overlay::{Overlay, OverlayFace, OverlayManager, UnderlineStyle};
 use crate::view::popup::{Popup, PopupContent, PopupListItem, PopupManager, PopupPosition};
@@ -21,6 +23,8 @@ use crate::view::virtual_text::VirtualTextManager;
 use anyhow::Result;
 use ratatui::style::{Color, Style};
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Display mode for a buffer
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -101,6 +105,9 @@ pub struct EditorState {
 
     /// Optional transformed view payload for current viewport (tokens + map)
     pub view_transform: Option<crate::services::plugins::api::ViewTransformPayload>,
+
+    /// Structural selection expansions of each cursor, popped by shrink selection
+    pub selection_history: HashMap<CursorId, Vec<SelectionStep>>,
 }
 
 impl EditorState {
@@ -130,6 +137,7 @@ impl EditorState {
             compose_prev_line_numbers: None,
             compose_column_guides: None,
             view_transform: None,
+            selection_history: HashMap::new(),
         }
     }
 
@@ -138,7 +146,7 @@ impl EditorState {
     pub fn set_language_from_name(&mut self, name: &str, registry: &GrammarRegistry) {
         let path = std::path::Path::new(name);
         self.highlighter = HighlightEngine::for_file(path, registry);
-        if let Some(language) = Language::from_path(path) {
+        if let Some(language) = registry.language_for_file(path) {
             self.semantic_highlighter.set_language(&language);
         }
         tracing::debug!(
@@ -159,7 +167,25 @@ impl EditorState {
         large_file_threshold: usize,
         registry: &GrammarRegistry,
     ) -> std::io::Result<Self> {
-        let buffer = Buffer::load_from_file(path, large_file_threshold)?;
+        Self::from_file_with_encoding(
+            path,
+            large_file_threshold,
+            registry,
+            None,
+            FsManager::local(),
+        )
+    }
+
+    /// Create an editor state from a file on `fs` decoded with `encoding`,
+    /// or with the detected encoding if `None`
+    pub fn from_file_with_encoding(
+        path: &std::path::Path,
+        large_file_threshold: usize,
+        registry: &GrammarRegistry,
+        encoding: Option<TextEncoding>,
+        fs: Arc<FsManager>,
+    ) -> std::io::Result<Self> {
+        let buffer = Buffer::load_from_fs(fs, path, large_file_threshold, encoding)?;
 
         // Create highlighter using HighlightEngine (tree-sitter preferred, TextMate fallback)
         let highlighter = HighlightEngine::for_file(path, registry);
@@ -170,7 +196,7 @@ impl EditorState {
         );
 
         // Initialize semantic highlighter with language if available
-        let language = Language::from_path(path);
+        let language = registry.language_for_file(path);
         let mut semantic_highlighter = SemanticHighlighter::new();
         if let Some(lang) = language {
             semantic_highlighter.set_language(&lang);
@@ -207,6 +233,7 @@ impl EditorState {
             compose_prev_line_numbers: None,
             compose_column_guides: None,
             view_transform: None,
+            selection_history: HashMap::new(),
         })
     }
 
@@ -10,6 +10,10 @@ pub struct FileTreeView {
     tree: FileTree,
     /// Currently selected node
     selected_node: Option<NodeId>,
+    /// Nodes of a multi-selection (empty when only `selected_node` is selected)
+    marked: Vec<NodeId>,
+    /// Node a range selection extends from
+    selection_anchor: Option<NodeId>,
     /// Scroll offset (index into visible nodes)
     scroll_offset: usize,
     /// Sort mode for entries
@@ -38,6 +42,8 @@ impl FileTreeView {
         Self {
             tree,
             selected_node: Some(root_id),
+            marked: Vec::new(),
+            selection_anchor: None,
             scroll_offset: 0,
             sort_mode: SortMode::Type,
             ignore_patterns: IgnorePatterns::new(),
@@ -86,6 +92,7 @@ impl FileTreeView {
 
     /// Select the next visible node
     pub fn select_next(&mut self) {
+        self.clear_marks();
         let visible = self.tree.get_visible_nodes();
         if visible.is_empty() {
             return;
@@ -104,6 +111,7 @@ impl FileTreeView {
 
     /// Select the previous visible node
     pub fn select_prev(&mut self) {
+        self.clear_marks();
         let visible = self.tree.get_visible_nodes();
         if visible.is_empty() {
             return;
@@ -122,6 +130,7 @@ impl FileTreeView {
 
     /// Move selection up by a page (viewport height)
     pub fn select_page_up(&mut self) {
+        self.clear_marks();
         if self.viewport_height == 0 {
             return;
         }
@@ -143,6 +152,7 @@ impl FileTreeView {
 
     /// Move selection down by a page (viewport height)
     pub fn select_page_down(&mut self) {
+        self.clear_marks();
         if self.viewport_height == 0 {
             return;
         }
@@ -162,6 +172,88 @@ impl FileTreeView {
         }
     }
 
+    /// Extend the selection to the next visible node (a range from the anchor)
+    pub fn extend_selection_next(&mut self) {
+        let anchor = self.selection_anchor.or(self.selected_node);
+        self.select_next();
+        self.mark_range_from(anchor);
+    }
+
+    /// Extend the selection to the previous visible node (a range from the anchor)
+    pub fn extend_selection_prev(&mut self) {
+        let anchor = self.selection_anchor.or(self.selected_node);
+        self.select_prev();
+        self.mark_range_from(anchor);
+    }
+
+    /// Select the visible nodes from the anchor (or the selected node) to `node_id`
+    pub fn select_range_to(&mut self, node_id: NodeId) {
+        let anchor = self.selection_anchor.or(self.selected_node);
+        self.selected_node = Some(node_id);
+        self.mark_range_from(anchor);
+    }
+
+    /// Add `node_id` to the selection, or take it out if it is in it
+    pub fn toggle_marked(&mut self, node_id: NodeId) {
+        if self.marked.is_empty() {
+            // The single selection becomes part of the multi-selection
+            self.marked
+                .extend(self.selected_node.filter(|&id| id != node_id));
+        }
+        if let Some(pos) = self.marked.iter().position(|&id| id == node_id) {
+            self.marked.remove(pos);
+        } else {
+            self.marked.push(node_id);
+        }
+        self.selected_node = Some(node_id);
+        self.selection_anchor = Some(node_id);
+    }
+
+    fn mark_range_from(&mut self, anchor: Option<NodeId>) {
+        let (Some(anchor), Some(selected)) = (anchor, self.selected_node) else {
+            return;
+        };
+        let visible = self.tree.get_visible_nodes();
+        let (Some(start), Some(end)) = (
+            visible.iter().position(|&id| id == anchor),
+            visible.iter().position(|&id| id == selected),
+        ) else {
+            return;
+        };
+        self.marked = visible[start.min(end)..=start.max(end)].to_vec();
+        self.selection_anchor = Some(anchor);
+    }
+
+    /// Reduce the selection to the selected node
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+        self.selection_anchor = None;
+    }
+
+    /// Whether a node is part of a multi-selection
+    pub fn is_marked(&self, node_id: NodeId) -> bool {
+        self.marked.contains(&node_id)
+    }
+
+    /// The selected nodes in display order: the multi-selection if there is
+    /// one, otherwise the selected node
+    pub fn selected_nodes(&self) -> Vec<NodeId> {
+        let marked: Vec<NodeId> = self
+            .tree
+            .get_visible_nodes()
+            .into_iter()
+            .filter(|id| self.marked.contains(id))
+            .collect();
+        if marked.is_empty() {
+            self.selected_node
+                .filter(|&id| self.tree.get_node(id).is_some())
+                .into_iter()
+                .collect()
+        } else {
+            marked
+        }
+    }
+
     /// Update scroll offset to ensure symmetric scrolling behavior
     ///
     /// This should be called after navigation to implement symmetric scrolling:
@@ -195,6 +287,7 @@ impl FileTreeView {
 
     /// Select the first visible node
     pub fn select_first(&mut self) {
+        self.clear_marks();
         let visible = self.tree.get_visible_nodes();
         if !visible.is_empty() {
             self.selected_node = Some(visible[0]);
@@ -203,6 +296,7 @@ impl FileTreeView {
 
     /// Select the last visible node
     pub fn select_last(&mut self) {
+        self.clear_marks();
         let visible = self.tree.get_visible_nodes();
         if !visible.is_empty() {
             self.selected_node = Some(*visible.last().unwrap());
@@ -211,6 +305,7 @@ impl FileTreeView {
 
     /// Select the parent of the currently selected node
     pub fn select_parent(&mut self) {
+        self.clear_marks();
         if let Some(current) = self.selected_node {
             if let Some(node) = self.tree.get_node(current) {
                 if let Some(parent_id) = node.parent {
@@ -568,4 +663,38 @@ mod tests {
         view.set_sort_mode(SortMode::Modified);
         assert_eq!(view.get_sort_mode(), SortMode::Modified);
     }
+
+    #[tokio::test]
+    async fn test_multi_selection() {
+        let (_temp_dir, mut view) = create_test_view().await;
+        let root_id = view.tree().root_id();
+        view.tree_mut().expand_node(root_id).await.unwrap();
+        let visible = view.tree().get_visible_nodes();
+
+        // Without a multi-selection, the selected node is the selection
+        view.select_next();
+        assert_eq!(view.selected_nodes(), vec![visible[1]]);
+
+        // Shift ranges extend from the anchor
+        view.extend_selection_next();
+        view.extend_selection_next();
+        assert_eq!(view.selected_nodes(), visible[1..=3].to_vec());
+        view.extend_selection_prev();
+        assert_eq!(view.selected_nodes(), visible[1..=2].to_vec());
+
+        // Ctrl toggles single nodes
+        view.toggle_marked(visible[1]);
+        assert_eq!(view.selected_nodes(), vec![visible[2]]);
+        view.toggle_marked(visible[3]);
+        assert_eq!(view.selected_nodes(), vec![visible[2], visible[3]]);
+        assert!(view.is_marked(visible[3]));
+
+        // Plain navigation drops the multi-selection
+        view.select_prev();
+        assert_eq!(view.selected_nodes(), vec![visible[2]]);
+        assert!(!view.is_marked(visible[3]));
+
+        view.select_range_to(visible[0]);
+        assert_eq!(view.selected_nodes(), visible[0..=2].to_vec());
+    }
 }
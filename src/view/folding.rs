@@ -0,0 +1,171 @@
+//! Collapsed (folded) regions of a buffer shown in a split
+//!
+//! Folds are view state: each split keeps its own set per buffer, so the same
+//! buffer can be folded differently in two splits. A fold hides whole lines,
+//! stored as a byte range that is kept up to date as the buffer is edited.
+
+use std::ops::Range;
+
+/// Margin indicator namespace for the fold markers on header lines
+pub const FOLD_NAMESPACE: &str = "folding";
+
+/// A collapsed region
+///
+/// `start` is the first byte of the line after the fold's header line and
+/// `end` is the first byte after the last hidden line (or the buffer length).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fold {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Fold {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Whether `position` is hidden by this fold
+    pub fn hides(&self, position: usize) -> bool {
+        position >= self.start && position < self.end
+    }
+
+    /// Byte offset of the newline ending the header line
+    pub fn header_end(&self) -> usize {
+        self.start.saturating_sub(1)
+    }
+}
+
+/// The folds of one buffer in one split
+#[derive(Debug, Clone, Default)]
+pub struct FoldState {
+    /// Folds sorted by start; a fold may be nested inside another
+    folds: Vec<Fold>,
+}
+
+impl FoldState {
+    pub fn is_empty(&self) -> bool {
+        self.folds.is_empty()
+    }
+
+    pub fn folds(&self) -> &[Fold] {
+        &self.folds
+    }
+
+    /// Collapse a region (ignored if empty or already folded)
+    pub fn add(&mut self, fold: Fold) {
+        if fold.start >= fold.end || self.folds.contains(&fold) {
+            return;
+        }
+        let index = self.folds.partition_point(|f| {
+            (f.start, std::cmp::Reverse(f.end)) < (fold.start, std::cmp::Reverse(fold.end))
+        });
+        self.folds.insert(index, fold);
+    }
+
+    /// Expand the folds matching `predicate`, returning how many were removed
+    pub fn remove_where(&mut self, mut predicate: impl FnMut(&Fold) -> bool) -> usize {
+        let before = self.folds.len();
+        self.folds.retain(|fold| !predicate(fold));
+        before - self.folds.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.folds.clear();
+    }
+
+    /// The outermost fold hiding `position`, if any
+    pub fn hiding(&self, position: usize) -> Option<Fold> {
+        self.hidden_ranges()
+            .into_iter()
+            .find(|range| range.contains(&position))
+            .map(|range| Fold::new(range.start, range.end))
+    }
+
+    /// The byte ranges hidden by folds, sorted and merged
+    pub fn hidden_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::with_capacity(self.folds.len());
+        for fold in &self.folds {
+            match ranges.last_mut() {
+                Some(last) if fold.start <= last.end => last.end = last.end.max(fold.end),
+                _ => ranges.push(fold.start..fold.end),
+            }
+        }
+        ranges
+    }
+
+    /// Shift folds after an edit of `old_len` bytes at `position` replaced by
+    /// `new_len` bytes. Folds whose hidden text (or header line break) is
+    /// edited are expanded.
+    pub fn adjust_for_edit(&mut self, position: usize, old_len: usize, new_len: usize) {
+        let edit_end = position + old_len;
+        self.folds.retain_mut(|fold| {
+            let touches_hidden_text = if old_len > 0 {
+                position < fold.end && edit_end > fold.header_end()
+            } else {
+                position > fold.header_end() && position < fold.end
+            };
+            if touches_hidden_text {
+                return false;
+            }
+            if fold.start > position {
+                fold.start = fold.start - old_len.min(fold.start - position) + new_len;
+            }
+            if fold.end > position {
+                fold.end = fold.end - old_len.min(fold.end - position) + new_len;
+            }
+            fold.start < fold.end
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hidden_ranges_merge_nested_folds() {
+        let mut state = FoldState::default();
+        state.add(Fold::new(20, 30));
+        state.add(Fold::new(10, 40));
+        state.add(Fold::new(50, 60));
+        state.add(Fold::new(50, 60));
+
+        assert_eq!(state.folds().len(), 3);
+        assert_eq!(state.hidden_ranges(), vec![10..40, 50..60]);
+        assert_eq!(state.hiding(25), Some(Fold::new(10, 40)));
+        assert_eq!(state.hiding(45), None);
+    }
+
+    #[test]
+    fn test_adjust_for_edit_shifts_folds() {
+        let mut state = FoldState::default();
+        state.add(Fold::new(10, 20));
+
+        // Typing on the header line
+        state.adjust_for_edit(5, 0, 3);
+        assert_eq!(state.folds(), &[Fold::new(13, 23)]);
+
+        // Inserting right after the fold leaves it alone
+        state.adjust_for_edit(23, 0, 2);
+        assert_eq!(state.folds(), &[Fold::new(13, 23)]);
+
+        // Deleting before the fold
+        state.adjust_for_edit(0, 4, 0);
+        assert_eq!(state.folds(), &[Fold::new(9, 19)]);
+    }
+
+    #[test]
+    fn test_adjust_for_edit_expands_edited_folds() {
+        let mut state = FoldState::default();
+        state.add(Fold::new(10, 20));
+        state.add(Fold::new(30, 40));
+
+        // Deleting the first fold's header newline
+        state.adjust_for_edit(9, 1, 0);
+        assert_eq!(state.folds(), &[Fold::new(29, 39)]);
+
+        // Inserting into hidden text
+        state.adjust_for_edit(35, 0, 1);
+        assert!(state.is_empty());
+    }
+}
@@ -428,6 +428,24 @@ impl MarginManager {
         }
     }
 
+    /// Get the markers and current byte positions of a namespace's indicators,
+    /// ordered by position
+    pub fn line_indicator_positions(&self, namespace: &str) -> Vec<(MarkerId, usize)> {
+        let mut positions: Vec<(MarkerId, usize)> = self
+            .line_indicators
+            .iter()
+            .filter(|(_, indicators)| indicators.contains_key(namespace))
+            .filter_map(|(&marker_id, _)| {
+                let marker_id = MarkerId(marker_id);
+                self.indicator_markers
+                    .get_position(marker_id)
+                    .map(|position| (marker_id, position))
+            })
+            .collect();
+        positions.sort_by_key(|&(_, position)| position);
+        positions
+    }
+
     /// Get the line indicator for a specific line number
     ///
     /// This looks up all indicators whose markers resolve to the given line.
@@ -1003,4 +1021,32 @@ mod tests {
         assert!(manager.get_line_indicator(7, byte_to_line).is_some());
         assert!(manager.get_line_indicator(9, byte_to_line).is_some());
     }
+
+    #[test]
+    fn test_line_indicator_positions_for_namespace() {
+        let mut manager = MarginManager::new();
+
+        let later = manager.set_line_indicator(
+            line_to_byte(7),
+            "breakpoints".to_string(),
+            LineIndicator::new("●", Color::Red, 20),
+        );
+        manager.set_line_indicator(
+            line_to_byte(4),
+            "git-gutter".to_string(),
+            LineIndicator::new("│", Color::Green, 10),
+        );
+        let earlier = manager.set_line_indicator(
+            line_to_byte(2),
+            "breakpoints".to_string(),
+            LineIndicator::new("●", Color::Red, 20),
+        );
+
+        manager.adjust_for_insert(0, 10);
+
+        assert_eq!(
+            manager.line_indicator_positions("breakpoints"),
+            vec![(earlier, line_to_byte(3)), (later, line_to_byte(8))]
+        );
+    }
 }
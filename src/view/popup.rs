@@ -401,6 +401,14 @@ impl Popup {
         }
     }
 
+    /// Index of the selected item (if this is a list popup)
+    pub fn selected_index(&self) -> Option<usize> {
+        match &self.content {
+            PopupContent::List { selected, .. } => Some(*selected),
+            _ => None,
+        }
+    }
+
     /// Move selection down (for list popups)
     pub fn select_next(&mut self) {
         if let PopupContent::List { items, selected } = &mut self.content {
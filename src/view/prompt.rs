@@ -12,6 +12,26 @@ pub enum PromptType {
     OpenFile,
     /// Switch to a different project folder (change working directory)
     SwitchProject,
+    /// Save the session under a name
+    SaveSessionAs,
+    /// Switch to another session (select from list)
+    SwitchSession,
+    /// Rename the active named session
+    RenameSession,
+    /// Delete a named session (select from list)
+    DeleteSession,
+    /// Install a plugin package from a git URL or directory
+    InstallPluginPackage,
+    /// Uninstall a plugin package (select from list)
+    RemovePluginPackage,
+    /// Enable a disabled plugin package (select from list)
+    EnablePluginPackage,
+    /// Disable a plugin package (select from list)
+    DisablePluginPackage,
+    /// Pin a git plugin package at its current commit (select from list)
+    PinPluginPackage,
+    /// Unpin a pinned plugin package (select from list)
+    UnpinPluginPackage,
     /// Save current buffer to a new file
     SaveFileAs,
     /// Search for text in buffer
@@ -26,6 +46,16 @@ pub enum PromptType {
     QueryReplace { search: String },
     /// Query replace confirmation prompt (y/n/!/q for each match)
     QueryReplaceConfirm,
+    /// Search for text in all files of the project
+    ProjectSearch,
+    /// Search for text in the project (will prompt for replacement after)
+    ProjectReplaceSearch,
+    /// Replacement text for a project-wide replace
+    ProjectReplace { search: String },
+    /// Globs of files to include in project search
+    ProjectSearchInclude,
+    /// Globs of files to exclude from project search
+    ProjectSearchExclude,
     /// Execute a command by name (M-x)
     Command,
     /// Go to a specific line number
@@ -34,6 +64,8 @@ pub enum PromptType {
     SetBackgroundFile,
     /// Set background blend ratio (0-1)
     SetBackgroundBlend,
+    /// Allow or deny a sandboxed plugin's permission request
+    PluginPermission,
     /// Plugin-controlled prompt with custom type identifier
     /// The string identifier is used to filter hooks in plugin code
     Plugin { custom_type: String },
@@ -47,8 +79,10 @@ pub enum PromptType {
     },
     /// Record a macro - prompts for register (0-9)
     RecordMacro,
-    /// Play a macro - prompts for register (0-9)
+    /// Play a macro - prompts for register (0-9) or name
     PlayMacro,
+    /// Show a macro in an editable buffer - prompts for register or name
+    ShowMacro,
     /// Set a bookmark - prompts for register (0-9)
     SetBookmark,
     /// Jump to a bookmark - prompts for register (0-9)
@@ -59,8 +93,14 @@ pub enum PromptType {
     StopLspServer,
     /// Select a theme (select from list)
     SelectTheme,
+    /// Insert a user snippet (select from list)
+    InsertSnippet,
     /// Select a keybinding map (select from list)
     SelectKeybindingMap,
+    /// Reload the file with a different encoding (select from list)
+    ReopenWithEncoding,
+    /// Save the file with a different encoding (select from list)
+    SaveWithEncoding,
     /// Confirm reverting a modified file
     ConfirmRevert,
     /// Confirm saving over a file that changed on disk
@@ -72,14 +112,25 @@ pub enum PromptType {
     },
     /// Confirm quitting with modified buffers
     ConfirmQuitWithModified,
+    /// Resolve a buffer stashed by hot exit whose file changed on disk
+    /// (keep stashed changes/reload from disk/merge)
+    ConfirmHotExitConflict {
+        buffer_id: crate::model::event::BufferId,
+    },
     /// File Explorer rename operation
     /// Stores the original path and name for the file/directory being renamed
     FileExplorerRename {
         original_path: std::path::PathBuf,
         original_name: String,
     },
+    /// Confirm deleting files and directories from the File Explorer
+    ConfirmFileExplorerDelete { paths: Vec<std::path::PathBuf> },
+    /// Resolve a name conflict while pasting in the File Explorer
+    FileExplorerPasteConflict,
     /// Switch to a tab by name (from the current split's open buffers)
     SwitchToTab,
+    /// Nesting level to unfold to (deeper regions are folded)
+    FoldLevel,
 }
 
 /// Prompt state for the minibuffer
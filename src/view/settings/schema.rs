@@ -255,6 +255,32 @@ pub fn parse_schema(schema_json: &str) -> Result<Vec<SettingCategory>, serde_jso
     Ok(categories)
 }
 
+/// Build the category of the settings a plugin declares in its manifest (a
+/// schema with `properties`), stored in the config under
+/// `/plugin_settings/<plugin>`
+pub fn parse_plugin_schema(
+    plugin: &str,
+    schema: &serde_json::Value,
+) -> Result<SettingCategory, serde_json::Error> {
+    let raw: RawSchema = serde_json::from_value(schema.clone())?;
+
+    let defs = raw.defs.unwrap_or_default();
+    let properties = raw.properties.unwrap_or_default();
+    let enum_values_map = build_enum_values_map(&raw.extensible_enum_values);
+
+    let path = format!("/plugin_settings/{}", plugin);
+    let settings = parse_properties(&properties, &path, &defs, &enum_values_map);
+    Ok(SettingCategory {
+        name: format!("Plugin: {}", plugin),
+        path,
+        description: raw
+            .description
+            .or_else(|| Some(format!("Settings of the {} plugin", plugin))),
+        settings,
+        subcategories: Vec::new(),
+    })
+}
+
 /// Build a map from $ref paths to their enum options
 fn build_enum_values_map(entries: &[EnumValueEntry]) -> EnumValuesMap {
     let mut map: EnumValuesMap = HashMap::new();
@@ -549,6 +575,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_plugin_schema() {
+        let schema = serde_json::json!({
+            "properties": {
+                "max_results": { "type": "integer", "minimum": 1, "default": 50 },
+                "mode": { "type": "string", "enum": ["fast", "thorough"] }
+            }
+        });
+        let category = parse_plugin_schema("live_grep", &schema).unwrap();
+
+        assert_eq!(category.name, "Plugin: live_grep");
+        assert_eq!(category.path, "/plugin_settings/live_grep");
+        assert_eq!(category.settings.len(), 2);
+        assert_eq!(
+            category.settings[0].path,
+            "/plugin_settings/live_grep/max_results"
+        );
+        assert!(matches!(
+            category.settings[1].setting_type,
+            SettingType::Enum { .. }
+        ));
+    }
+
     #[test]
     fn test_humanize_name() {
         assert_eq!(humanize_name("tab_size"), "Tab Size");
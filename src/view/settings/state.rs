@@ -5,7 +5,7 @@
 
 use super::items::{control_to_value, SettingControl, SettingItem, SettingsPage};
 use super::layout::SettingsHit;
-use super::schema::{parse_schema, SettingCategory};
+use super::schema::{parse_plugin_schema, parse_schema, SettingCategory};
 use super::search::{search_settings, SearchResult};
 use crate::config::Config;
 use crate::view::controls::FocusState;
@@ -103,6 +103,21 @@ impl SettingsState {
         })
     }
 
+    /// Add a page for the settings a plugin declares in its manifest
+    pub fn add_plugin_settings(
+        &mut self,
+        plugin: &str,
+        schema: &serde_json::Value,
+    ) -> Result<(), serde_json::Error> {
+        let category = parse_plugin_schema(plugin, schema)?;
+        self.pages.extend(super::items::build_pages(
+            std::slice::from_ref(&category),
+            &self.original_config,
+        ));
+        self.categories.push(category);
+        Ok(())
+    }
+
     /// Show the settings panel
     pub fn show(&mut self) {
         self.visible = true;
@@ -256,6 +271,9 @@ impl SettingsState {
         for (path, value) in &self.pending_changes {
             if let Some(target) = config_value.pointer_mut(path) {
                 *target = value.clone();
+            } else {
+                // Plugin settings the user hasn't set before
+                insert_at_pointer(&mut config_value, path, value.clone());
             }
         }
 
@@ -890,6 +908,19 @@ fn update_control_from_value(control: &mut SettingControl, value: &serde_json::V
     }
 }
 
+/// Set the value at a JSON pointer, creating the missing objects on the way
+fn insert_at_pointer(root: &mut serde_json::Value, path: &str, value: serde_json::Value) {
+    let mut target = root;
+    for token in path.split('/').skip(1) {
+        let key = token.replace("~1", "/").replace("~0", "~");
+        let Some(object) = target.as_object_mut() else {
+            return;
+        };
+        target = object.entry(key).or_insert_with(|| serde_json::json!({}));
+    }
+    *target = value;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -959,6 +990,27 @@ mod tests {
         assert!(!state.has_changes());
     }
 
+    #[test]
+    fn test_plugin_settings() {
+        let config = test_config();
+        let mut state = SettingsState::new(TEST_SCHEMA, &config).unwrap();
+        let schema = serde_json::json!({
+            "properties": { "max_results": { "type": "integer", "default": 50 } }
+        });
+        state.add_plugin_settings("live_grep", &schema).unwrap();
+
+        let page = state.pages.last().unwrap();
+        assert_eq!(page.name, "Plugin: live_grep");
+        assert_eq!(page.items[0].path, "/plugin_settings/live_grep/max_results");
+
+        state.set_pending_change("/plugin_settings/live_grep/max_results", 100.into());
+        let config = state.apply_changes(&config).unwrap();
+        assert_eq!(
+            config.plugin_settings["live_grep"],
+            serde_json::json!({ "max_results": 100 })
+        );
+    }
+
     #[test]
     fn test_show_hide() {
         let config = test_config();
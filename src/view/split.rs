@@ -25,11 +25,13 @@
 /// ```
 use crate::model::cursor::Cursors;
 use crate::model::event::{BufferId, SplitDirection, SplitId};
+use crate::view::folding::FoldState;
 use crate::view::ui::view_pipeline::Layout;
 use crate::view::viewport::Viewport;
 use crate::{services::plugins::api::ViewTransformPayload, state::ViewMode};
 use ratatui::layout::Rect;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// A node in the split tree
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -105,6 +107,9 @@ pub struct SplitViewState {
 
     /// Previously active buffer in this split (for "Switch to Previous Tab" command)
     pub previous_buffer: Option<BufferId>,
+
+    /// Collapsed regions per buffer shown in this split
+    pub folds: HashMap<BufferId, FoldState>,
 }
 
 impl SplitViewState {
@@ -123,6 +128,7 @@ impl SplitViewState {
             layout: None,
             layout_dirty: true, // Start dirty so first operation builds layout
             previous_buffer: None,
+            folds: HashMap::new(),
         }
     }
 
@@ -141,6 +147,7 @@ impl SplitViewState {
             layout: None,
             layout_dirty: true, // Start dirty so first operation builds layout
             previous_buffer: None,
+            folds: HashMap::new(),
         }
     }
 
@@ -192,6 +199,20 @@ impl SplitViewState {
     pub fn has_buffer(&self, buffer_id: BufferId) -> bool {
         self.open_buffers.contains(&buffer_id)
     }
+
+    /// Byte ranges of `buffer_id` hidden by folds in this split
+    pub fn hidden_ranges(&self, buffer_id: BufferId) -> Vec<std::ops::Range<usize>> {
+        self.folds
+            .get(&buffer_id)
+            .map(|folds| folds.hidden_ranges())
+            .unwrap_or_default()
+    }
+
+    /// Update the viewport's hidden ranges from the folds of `buffer_id`
+    pub fn sync_hidden_ranges(&mut self, buffer_id: BufferId) {
+        let ranges = self.hidden_ranges(buffer_id);
+        self.viewport.set_hidden_ranges(ranges);
+    }
 }
 
 impl SplitNode {
@@ -271,7 +271,13 @@ impl FileExplorerRenderer {
             ));
         }
 
-        ListItem::new(Line::from(spans)).style(Style::default().bg(theme.editor_bg))
+        // Rows of a multi-selection are highlighted like the selected row
+        let row_bg = if view.is_marked(node_id) {
+            theme.selection_bg
+        } else {
+            theme.editor_bg
+        };
+        ListItem::new(Line::from(spans)).style(Style::default().bg(row_bg))
     }
 
     /// Format file size for display
@@ -12,6 +12,7 @@ use crate::primitives::ansi_background::AnsiBackground;
 use crate::primitives::display_width::char_width;
 use crate::services::plugins::api::ViewTransformPayload;
 use crate::state::{EditorState, ViewMode};
+use crate::view::folding::FOLD_NAMESPACE;
 use crate::view::split::SplitManager;
 use crate::view::ui::tabs::TabsRenderer;
 use crate::view::ui::view_pipeline::{
@@ -80,6 +81,8 @@ struct DecorationContext {
     diagnostic_lines: HashSet<usize>,
     /// Line indicators indexed by line number (highest priority indicator per line)
     line_indicators: BTreeMap<usize, crate::view::margin::LineIndicator>,
+    /// Number of lines hidden by a fold, indexed by the byte where the fold ends
+    folded_line_counts: HashMap<usize, usize>,
 }
 
 struct LineRenderOutput {
@@ -559,6 +562,11 @@ impl SplitRenderer {
                             )
                         });
                 let mut viewport = viewport_clone;
+                viewport.set_hidden_ranges(
+                    view_state_opt
+                        .map(|vs| vs.hidden_ranges(buffer_id))
+                        .unwrap_or_default(),
+                );
 
                 let saved_cursors = Self::temporary_split_state(
                     state,
@@ -978,6 +986,7 @@ impl SplitRenderer {
             estimated_line_length,
             visible_count,
             is_binary,
+            viewport.hidden_ranges(),
         );
 
         // Use plugin transform if available, otherwise use base tokens
@@ -1118,6 +1127,7 @@ impl SplitRenderer {
         estimated_line_length: usize,
         visible_count: usize,
         is_binary: bool,
+        hidden_ranges: &[Range<usize>],
     ) -> Vec<crate::services::plugins::api::ViewTokenWire> {
         use crate::services::plugins::api::{ViewTokenWire, ViewTokenWireKind};
 
@@ -1199,6 +1209,12 @@ impl SplitRenderer {
                     byte_offset += ch_len;
                 }
                 lines_seen += 1;
+
+                // Skip the lines hidden by a fold whose header this line is
+                let next_line_start = line_start + line_content.len();
+                if let Some(hidden) = hidden_ranges.iter().find(|r| r.start == next_line_start) {
+                    iter = buffer.line_iterator(hidden.end, estimated_line_length);
+                }
             } else {
                 break;
             }
@@ -1386,6 +1402,7 @@ impl SplitRenderer {
         estimated_line_length: usize,
         visible_count: usize,
         is_binary: bool,
+        hidden_ranges: &[Range<usize>],
     ) -> Vec<crate::services::plugins::api::ViewTokenWire> {
         Self::build_base_tokens(
             buffer,
@@ -1393,6 +1410,7 @@ impl SplitRenderer {
             estimated_line_length,
             visible_count,
             is_binary,
+            hidden_ranges,
         )
     }
 
@@ -1713,6 +1731,7 @@ impl SplitRenderer {
         primary_cursor_position: usize,
         theme: &crate::view::theme::Theme,
         highlight_context_bytes: usize,
+        hidden_ranges: &[Range<usize>],
     ) -> DecorationContext {
         // Extend highlighting range by ~1 viewport size before/after for better context.
         // This helps tree-sitter parse multi-line constructs that span viewport boundaries.
@@ -1775,6 +1794,16 @@ impl SplitRenderer {
             |byte_offset| state.buffer.get_line_number(byte_offset),
         );
 
+        let folded_line_counts = hidden_ranges
+            .iter()
+            .filter(|range| range.end > viewport_start && range.start <= viewport_end)
+            .map(|range| {
+                let hidden_lines = state.buffer.get_line_number(range.end)
+                    - state.buffer.get_line_number(range.start);
+                (range.end, hidden_lines)
+            })
+            .collect();
+
         DecorationContext {
             highlight_spans,
             semantic_spans,
@@ -1782,6 +1811,7 @@ impl SplitRenderer {
             virtual_text_lookup,
             diagnostic_lines,
             line_indicators,
+            folded_line_counts,
         }
     }
 
@@ -1790,6 +1820,7 @@ impl SplitRenderer {
         viewport_start: usize,
         estimated_line_length: usize,
         visible_count: usize,
+        hidden_ranges: &[Range<usize>],
     ) -> usize {
         let mut iter_temp = state
             .buffer
@@ -1798,6 +1829,12 @@ impl SplitRenderer {
         for _ in 0..visible_count {
             if let Some((line_start, line_content)) = iter_temp.next() {
                 viewport_end = line_start + line_content.len();
+                if let Some(hidden) = hidden_ranges.iter().find(|r| r.start == viewport_end) {
+                    viewport_end = hidden.end;
+                    iter_temp = state
+                        .buffer
+                        .line_iterator(hidden.end, estimated_line_length);
+                }
             } else {
                 break;
             }
@@ -1805,6 +1842,38 @@ impl SplitRenderer {
         viewport_end
     }
 
+    /// Show a fold marker in the margin on the header line of each folded region
+    fn sync_fold_indicators(
+        state: &mut EditorState,
+        hidden_ranges: &[Range<usize>],
+        theme: &crate::view::theme::Theme,
+    ) {
+        let headers: Vec<usize> = hidden_ranges
+            .iter()
+            .map(|range| range.start.saturating_sub(1))
+            .collect();
+        let current: Vec<usize> = state
+            .margins
+            .line_indicator_positions(FOLD_NAMESPACE)
+            .into_iter()
+            .map(|(_, position)| position)
+            .collect();
+        if current == headers {
+            return;
+        }
+
+        state
+            .margins
+            .clear_line_indicators_for_namespace(FOLD_NAMESPACE);
+        for header in headers {
+            state.margins.set_line_indicator(
+                header,
+                FOLD_NAMESPACE.to_string(),
+                crate::view::margin::LineIndicator::new("▸", theme.line_number_fg, 15),
+            );
+        }
+    }
+
     fn render_view_lines(input: LineRenderInput<'_>) -> LineRenderOutput {
         let LineRenderInput {
             state,
@@ -1908,6 +1977,15 @@ impl SplitRenderer {
             // This ensures virtual/injected lines don't cause line numbers to skip
             if show_line_number && prev_was_source_line {
                 current_source_line_num += 1;
+                // Skip the line numbers hidden by a fold ending here
+                if let Some(hidden_lines) = current_view_line
+                    .char_source_bytes
+                    .iter()
+                    .find_map(|b| *b)
+                    .and_then(|first_byte| decorations.folded_line_counts.get(&first_byte))
+                {
+                    current_source_line_num += hidden_lines;
+                }
             }
             // Only update the flag when we see a source line - virtual lines
             // between source lines shouldn't reset the tracking
@@ -2564,8 +2642,10 @@ impl SplitRenderer {
             viewport_start,
             estimated_line_length,
             visible_count,
+            viewport.hidden_ranges(),
         );
 
+        Self::sync_fold_indicators(state, viewport.hidden_ranges(), theme);
         let decorations = Self::decoration_context(
             state,
             viewport_start,
@@ -2573,6 +2653,7 @@ impl SplitRenderer {
             selection.primary_cursor_position,
             theme,
             highlight_context_bytes,
+            viewport.hidden_ranges(),
         );
 
         // Apply top_view_line_offset to skip virtual lines when scrolling through them
@@ -2878,6 +2959,7 @@ mod tests {
             viewport_start,
             content.len().max(1),
             visible_count,
+            &[],
         );
         let theme = Theme::default();
         let decorations = SplitRenderer::decoration_context(
@@ -2887,6 +2969,7 @@ mod tests {
             selection.primary_cursor_position,
             &theme,
             100_000, // default highlight context bytes
+            &[],
         );
 
         let output = SplitRenderer::render_view_lines(LineRenderInput {
@@ -440,6 +440,14 @@ impl StatusBarRenderer {
             String::new()
         };
 
+        // Show the file encoding, unless it is plain UTF-8
+        let encoding = state.buffer.encoding();
+        let encoding_indicator = if encoding.is_plain_utf8() {
+            String::new()
+        } else {
+            format!(" | {}", encoding)
+        };
+
         // Build the status string with optional LSP status and status message
         let lsp_indicator = if !lsp_status.is_empty() {
             format!(" | {}", lsp_status)
@@ -466,7 +474,7 @@ impl StatusBarRenderer {
         };
 
         let base_status = format!(
-            "{filename}{modified} | Ln {line}, Col {col}{diagnostics_summary}{cursor_count_indicator}{lsp_indicator}"
+            "{filename}{modified} | Ln {line}, Col {col}{encoding_indicator}{diagnostics_summary}{cursor_count_indicator}{lsp_indicator}"
         );
         let left_status = format!("{base_status}{chord_display}{message_suffix}");
 
@@ -3,6 +3,7 @@ use crate::model::cursor::Cursor;
 use crate::primitives::display_width::{char_width, str_width};
 use crate::primitives::line_wrapping::{char_position_to_segment, wrap_line, WrapConfig};
 use crate::view::ui::view_pipeline::ViewLine;
+use std::ops::Range;
 /// The viewport - what portion of the buffer is visible
 #[derive(Debug, Clone)]
 pub struct Viewport {
@@ -49,6 +50,10 @@ pub struct Viewport {
     /// This is set after scroll actions (Ctrl+Up/Down) to prevent the scroll
     /// from being immediately undone by ensure_visible
     skip_ensure_visible: bool,
+
+    /// Byte ranges hidden by folded regions (sorted, non-overlapping)
+    /// Lines starting inside these ranges take no rows when scrolling
+    hidden_ranges: Vec<Range<usize>>,
 }
 
 impl Viewport {
@@ -66,6 +71,7 @@ impl Viewport {
             needs_sync: false,
             skip_resize_sync: false,
             skip_ensure_visible: false,
+            hidden_ranges: Vec::new(),
         }
     }
 
@@ -101,6 +107,92 @@ impl Viewport {
         self.skip_ensure_visible = false;
     }
 
+    /// Set the byte ranges hidden by folded regions
+    pub fn set_hidden_ranges(&mut self, ranges: Vec<Range<usize>>) {
+        self.hidden_ranges = ranges;
+    }
+
+    /// Byte ranges hidden by folded regions
+    pub fn hidden_ranges(&self) -> &[Range<usize>] {
+        &self.hidden_ranges
+    }
+
+    /// The hidden range containing `position`, if any
+    fn hidden_range_at(&self, position: usize) -> Option<Range<usize>> {
+        self.hidden_ranges
+            .iter()
+            .find(|range| range.contains(&position))
+            .cloned()
+    }
+
+    /// Start of the first visible line after the line starting at `line_start`
+    fn next_visible_line(&self, buffer: &mut Buffer, line_start: usize) -> Option<usize> {
+        let mut iter = buffer.line_iterator(line_start, 80);
+        iter.next()?;
+        let next = iter.current_position();
+        Some(self.hidden_range_at(next).map_or(next, |range| range.end))
+    }
+
+    /// Start of the last visible line before the line starting at `line_start`
+    fn prev_visible_line(&self, buffer: &mut Buffer, line_start: usize) -> Option<usize> {
+        let mut iter = buffer.line_iterator(line_start, 80);
+        iter.prev()?;
+        let prev = iter.current_position();
+        Some(self.visible_line_start(buffer, prev))
+    }
+
+    /// Start of the visible line at or before `position` (a fold's header line
+    /// if `position` is hidden)
+    fn visible_line_start(&self, buffer: &mut Buffer, position: usize) -> usize {
+        let position = self
+            .hidden_range_at(position)
+            .map_or(position, |range| range.start.saturating_sub(1));
+        buffer.line_iterator(position, 80).current_position()
+    }
+
+    /// Number of visible lines from `from` up to the line starting at `to`, at most `limit`
+    fn visible_lines_between(
+        &self,
+        buffer: &mut Buffer,
+        from: usize,
+        to: usize,
+        limit: usize,
+    ) -> usize {
+        let mut position = from;
+        let mut count = 0;
+        while position < to && count < limit {
+            match self.next_visible_line(buffer, position) {
+                Some(next) => position = next,
+                None => break,
+            }
+            count += 1;
+        }
+        count
+    }
+
+    /// Move `lines` visible lines forward or backward from the line at `line_start`
+    fn step_visible_lines(
+        &self,
+        buffer: &mut Buffer,
+        line_start: usize,
+        lines: usize,
+        forward: bool,
+    ) -> usize {
+        let mut position = line_start;
+        for _ in 0..lines {
+            let step = if forward {
+                self.next_visible_line(buffer, position)
+            } else {
+                self.prev_visible_line(buffer, position)
+            };
+            match step {
+                Some(next) => position = next,
+                None => break,
+            }
+        }
+        position
+    }
+
     /// Set the scroll offset
     pub fn set_scroll_offset(&mut self, offset: usize) {
         self.scroll_offset = offset;
@@ -139,6 +231,12 @@ impl Viewport {
     /// Scroll up by N lines (byte-based)
     /// LineCache automatically tracks line numbers
     pub fn scroll_up(&mut self, buffer: &mut Buffer, lines: usize) {
+        if !self.hidden_ranges.is_empty() {
+            let top = self.visible_line_start(buffer, self.top_byte);
+            let new_position = self.step_visible_lines(buffer, top, lines, false);
+            self.set_top_byte_with_limit(buffer, new_position);
+            return;
+        }
         let mut iter = buffer.line_iterator(self.top_byte, 80);
         for _ in 0..lines {
             if iter.prev().is_none() {
@@ -153,6 +251,12 @@ impl Viewport {
     /// Scroll down by N lines (byte-based)
     /// LineCache automatically tracks line numbers
     pub fn scroll_down(&mut self, buffer: &mut Buffer, lines: usize) {
+        if !self.hidden_ranges.is_empty() {
+            let top = self.visible_line_start(buffer, self.top_byte);
+            let new_position = self.step_visible_lines(buffer, top, lines, true);
+            self.set_top_byte_with_limit(buffer, new_position);
+            return;
+        }
         let mut iter = buffer.line_iterator(self.top_byte, 80);
         for _ in 0..lines {
             if iter.next().is_none() {
@@ -463,6 +567,11 @@ impl Viewport {
             return;
         }
 
+        if !self.hidden_ranges.is_empty() {
+            self.set_top_byte_with_folds(buffer, proposed_top_byte);
+            return;
+        }
+
         // Try to iterate viewport_height lines from proposed_top_byte
         // If we can't reach viewport_height lines before hitting EOF,
         // then we need to adjust backward
@@ -554,6 +663,33 @@ impl Viewport {
         self.top_byte = final_top_byte;
     }
 
+    /// Fold-aware variant of `set_top_byte_with_limit`: hidden lines don't
+    /// count towards filling the viewport
+    fn set_top_byte_with_folds(&mut self, buffer: &mut Buffer, proposed_top_byte: usize) {
+        let viewport_height = self.visible_line_count();
+        let proposed = self.visible_line_start(buffer, proposed_top_byte);
+
+        let mut lines_visible = self.visible_lines_between(
+            buffer,
+            proposed,
+            buffer.len().saturating_add(1),
+            viewport_height,
+        );
+        // Account for the phantom line if buffer ends with newline
+        let buffer_len = buffer.len();
+        if lines_visible < viewport_height
+            && buffer.slice_bytes(buffer_len - 1..buffer_len) == b"\n"
+        {
+            lines_visible += 1;
+        }
+
+        self.top_byte = if lines_visible >= viewport_height {
+            proposed
+        } else {
+            self.step_visible_lines(buffer, proposed, viewport_height - lines_visible, false)
+        };
+    }
+
     /// Scroll to a specific line (byte-based)
     /// This seeks from the beginning to find the byte position of the line
     pub fn scroll_to(&mut self, buffer: &mut Buffer, line: usize) {
@@ -663,6 +799,17 @@ impl Viewport {
         let cursor_is_visible = if cursor_line_start < self.top_byte {
             // Cursor is above viewport
             false
+        } else if !self.hidden_ranges.is_empty() {
+            // With folds: count visible lines only. Wrapped rows are checked
+            // afterwards by ensure_visible_in_layout on the rendered view lines.
+            let lines_from_top = self.visible_lines_between(
+                buffer,
+                self.top_byte,
+                cursor_line_start,
+                viewport_lines,
+            );
+            lines_from_top > effective_offset
+                && lines_from_top < viewport_lines.saturating_sub(effective_offset)
         } else {
             if self.line_wrap_enabled {
                 // With line wrapping: count VISUAL ROWS (wrapped segments), not logical lines
@@ -765,7 +912,11 @@ impl Viewport {
             // Position cursor at center of viewport when jumping
             let target_rows_from_top = viewport_lines / 2;
 
-            if self.line_wrap_enabled {
+            if !self.hidden_ranges.is_empty() {
+                let new_top_byte =
+                    self.step_visible_lines(buffer, cursor_line_start, target_rows_from_top, false);
+                self.set_top_byte_with_limit(buffer, new_top_byte);
+            } else if self.line_wrap_enabled {
                 // When wrapping is enabled, count visual rows (wrapped segments) not logical lines
                 let gutter_width = self.gutter_width(buffer);
                 let wrap_config = WrapConfig::new(self.width as usize, gutter_width, true);
@@ -1126,6 +1277,28 @@ mod tests {
         assert_eq!(vp.top_byte, 0); // Can't scroll past 0
     }
 
+    #[test]
+    fn test_scroll_skips_hidden_lines() {
+        // 50 lines of "lineNN\n" (7 bytes each); hide lines 2..=41
+        let content: String = (10..60).map(|i| format!("line{}\n", i)).collect();
+        let mut buffer = Buffer::from_str_test(&content);
+        let mut vp = Viewport::new(80, 5);
+        vp.set_hidden_ranges(std::iter::once(14..287).collect());
+
+        vp.scroll_down(&mut buffer, 2);
+        assert_eq!(
+            vp.top_byte, 287,
+            "Scrolling past the header lands after the fold"
+        );
+
+        vp.scroll_up(&mut buffer, 1);
+        assert_eq!(vp.top_byte, 7, "Scrolling up lands on the fold header");
+
+        // The bottom clamps to a full viewport (last 4 lines + the empty last line)
+        vp.scroll_down(&mut buffer, 100);
+        assert_eq!(vp.top_byte, 322);
+    }
+
     #[test]
     fn test_ensure_line_visible() {
         let mut buffer = Buffer::from_str_test("line1\nline2\nline3\nline4\nline5\nline6\nline7\nline8\nline9\nline10\nline11\nline12\nline13\nline14\nline15\nline16\nline17\nline18\nline19\nline20\nline21\nline22\nline23\nline24\nline25\nline26\nline27\nline28\nline29\nline30\nline31\nline32\nline33\nline34\nline35\nline36\nline37\nline38\nline39\nline40\nline41\nline42\nline43\nline44\nline45\nline46\nline47\nline48\nline49\nline50\nline51");
@@ -0,0 +1,141 @@
+#![cfg(test)]
+//! Fake debug adapter for E2E testing
+//!
+//! A Bash script speaking just enough of the Debug Adapter Protocol to run a
+//! session: it stops at the first breakpoint it is given, steps one line at a
+//! time, reports a `counter` variable equal to the current line, prints one
+//! line of output and exits with code 0 when continued.
+
+use std::path::{Path, PathBuf};
+
+const SCRIPT: &str = r#"#!/bin/bash
+export LC_ALL=C
+
+out_seq=0
+program=""
+bp_line=1
+current=1
+
+read_message() {
+    local content_length=0
+    while IFS=: read -r key value; do
+        key=$(echo "$key" | tr -d '\r\n')
+        value=$(echo "$value" | tr -d '\r\n ')
+        if [ "$key" = "Content-Length" ]; then
+            content_length=$value
+        fi
+        if [ -z "$key" ]; then
+            break
+        fi
+    done
+    if [ $content_length -gt 0 ]; then
+        dd bs=1 count=$content_length 2>/dev/null
+    fi
+}
+
+send_message() {
+    local message="$1"
+    printf 'Content-Length: %d\r\n\r\n%s' "${#message}" "$message"
+}
+
+respond() {
+    out_seq=$((out_seq + 1))
+    send_message '{"seq":'$out_seq',"type":"response","request_seq":'$seq',"success":true,"command":"'$command'","body":'"$1"'}'
+}
+
+event() {
+    out_seq=$((out_seq + 1))
+    send_message '{"seq":'$out_seq',"type":"event","event":"'$1'","body":'"$2"'}'
+}
+
+while true; do
+    msg=$(read_message)
+    if [ -z "$msg" ]; then
+        break
+    fi
+
+    seq=$(echo "$msg" | grep -o '"seq":[0-9]*' | head -1 | cut -d':' -f2)
+    command=$(echo "$msg" | grep -o '"command":"[^"]*"' | head -1 | cut -d'"' -f4)
+
+    case "$command" in
+        "initialize")
+            respond '{"supportsConfigurationDoneRequest":true}'
+            event "initialized" '{}'
+            ;;
+        "launch")
+            program=$(echo "$msg" | grep -o '"program":"[^"]*"' | head -1 | cut -d'"' -f4)
+            respond '{}'
+            ;;
+        "setBreakpoints")
+            line=$(echo "$msg" | grep -o '"line":[0-9]*' | head -1 | cut -d':' -f2)
+            if [ -n "$line" ]; then
+                bp_line=$line
+                respond '{"breakpoints":[{"verified":true,"line":'$line'}]}'
+            else
+                respond '{"breakpoints":[]}'
+            fi
+            ;;
+        "configurationDone")
+            respond '{}'
+            event "output" '{"category":"stdout","output":"hello from debuggee\n"}'
+            current=$bp_line
+            event "stopped" '{"reason":"breakpoint","threadId":1}'
+            ;;
+        "threads")
+            respond '{"threads":[{"id":1,"name":"main"}]}'
+            ;;
+        "stackTrace")
+            respond '{"stackFrames":[{"id":1,"name":"main","source":{"name":"main.rs","path":"'$program'"},"line":'$current',"column":1},{"id":2,"name":"start","source":{"name":"main.rs","path":"'$program'"},"line":1,"column":1}],"totalFrames":2}'
+            ;;
+        "scopes")
+            frame=$(echo "$msg" | grep -o '"frameId":[0-9]*' | head -1 | cut -d':' -f2)
+            respond '{"scopes":[{"name":"Locals","variablesReference":'$frame'0,"expensive":false}]}'
+            ;;
+        "variables")
+            reference=$(echo "$msg" | grep -o '"variablesReference":[0-9]*' | head -1 | cut -d':' -f2)
+            if [ "$reference" = "10" ]; then
+                respond '{"variables":[{"name":"counter","value":"'$current'","type":"int","variablesReference":0}]}'
+            else
+                respond '{"variables":[{"name":"argc","value":"1","type":"int","variablesReference":0}]}'
+            fi
+            ;;
+        "next")
+            respond '{}'
+            current=$((current + 1))
+            event "stopped" '{"reason":"step","threadId":1}'
+            ;;
+        "continue")
+            respond '{"allThreadsContinued":true}'
+            event "exited" '{"exitCode":0}'
+            event "terminated" '{}'
+            ;;
+        "pause")
+            respond '{}'
+            event "stopped" '{"reason":"pause","threadId":1}'
+            ;;
+        "disconnect")
+            respond '{}'
+            break
+            ;;
+        *)
+            respond '{}'
+            ;;
+    esac
+done
+"#;
+
+/// Write the fake adapter script into `dir`, returning its path
+pub fn write_fake_dap_adapter(dir: &Path) -> std::io::Result<PathBuf> {
+    let script_path = dir.join("fake_dap_adapter.sh");
+    std::fs::write(&script_path, SCRIPT)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = std::fs::metadata(&script_path)?.permissions();
+        perms.set_mode(0o755);
+        std::fs::set_permissions(&script_path, perms)?;
+    }
+
+    Ok(script_path)
+}
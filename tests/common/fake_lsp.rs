@@ -73,11 +73,16 @@ while true; do
 case "$method" in
     "initialize")
         # Send initialize response
-        send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"capabilities":{"completionProvider":{"triggerCharacters":[".",":",":"]},"definitionProvider":true,"textDocumentSync":1}}}'
+        send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"capabilities":{"completionProvider":{"triggerCharacters":[".",":",":"]},"definitionProvider":true,"selectionRangeProvider":true,"textDocumentSync":1}}}'
         ;;
     "textDocument/completion")
         # Send completion response with sample items
-        send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"items":[{"label":"test_function","kind":3,"detail":"fn test_function()","insertText":"test_function"},{"label":"test_variable","kind":6,"detail":"let test_variable","insertText":"test_variable"},{"label":"test_struct","kind":22,"detail":"struct TestStruct","insertText":"test_struct"}]}}'
+        send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"isIncomplete":false,"items":[{"label":"test_function","kind":3,"detail":"fn test_function()","insertText":"test_function"},{"label":"test_variable","kind":6,"detail":"let test_variable","insertText":"test_variable"},{"label":"test_struct","kind":22,"detail":"struct TestStruct","insertText":"test_struct"},{"label":"test_snippet","kind":15,"detail":"snippet","insertText":"test_snippet(${1:arg}, ${2:other})","insertTextFormat":2}]}}'
+        ;;
+    "textDocument/selectionRange")
+        # Send the same chain for every request: characters 3-7 of the first
+        # line inside characters 0-9 of it
+        send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":[{"range":{"start":{"line":0,"character":3},"end":{"line":0,"character":7}},"parent":{"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":9}}}}]}'
         ;;
     "textDocument/definition")
         # Send definition response (points to line 0, col 0)
@@ -102,6 +107,10 @@ case "$method" in
         uri=$(echo "$msg" | grep -o '"uri":"[^"]*"' | head -1 | cut -d'"' -f4)
         send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":[]}'
         ;;
+    "textDocument/formatting")
+        # Replace the whole second line with an indented copy of it
+        send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":[{"range":{"start":{"line":1,"character":0},"end":{"line":1,"character":10}},"newText":"    let x = 1;"}]}'
+        ;;
     "textDocument/switchSourceHeader")
         uri=$(echo "$msg" | grep -o '"uri":"[^"]*"' | head -1 | cut -d'"' -f4)
         header="${uri%.*}.h"
@@ -820,6 +829,113 @@ done
         std::env::temp_dir().join("fake_lsp_server_inlay_hints.sh")
     }
 
+    /// Spawn a fake LSP server that registers for file renames of Rust files
+    ///
+    /// The script takes two arguments: the URI of a file to edit, and a file to
+    /// log the methods it receives to. It answers `workspace/willRenameFiles`
+    /// with an edit renaming `old` on the first line of the file to edit (as in
+    /// `mod old;`) to `new`.
+    pub fn spawn_with_file_renames() -> std::io::Result<Self> {
+        let (stop_tx, stop_rx) = mpsc::channel();
+
+        // Create a Bash script that supports file operations
+        let script = r#"#!/bin/bash
+
+edited_uri="$1"
+log_file="$2"
+
+# Function to read a message
+read_message() {
+    # Read headers
+    local content_length=0
+    while IFS=: read -r key value; do
+        key=$(echo "$key" | tr -d '\r\n')
+        value=$(echo "$value" | tr -d '\r\n ')
+        if [ "$key" = "Content-Length" ]; then
+            content_length=$value
+        fi
+        # Empty line marks end of headers
+        if [ -z "$key" ]; then
+            break
+        fi
+    done
+
+    # Read content
+    if [ $content_length -gt 0 ]; then
+        dd bs=1 count=$content_length 2>/dev/null
+    fi
+}
+
+# Function to send a message
+send_message() {
+    local message="$1"
+    local length=${#message}
+    echo -en "Content-Length: $length\r\n\r\n$message"
+}
+
+# Main loop
+while true; do
+    # Read incoming message
+    msg=$(read_message)
+
+    if [ -z "$msg" ]; then
+        break
+    fi
+
+    # Extract method from JSON
+    method=$(echo "$msg" | grep -o '"method":"[^"]*"' | cut -d'"' -f4)
+    msg_id=$(echo "$msg" | grep -o '"id":[0-9]*' | cut -d':' -f2)
+    echo "$method" >> "$log_file"
+
+    case "$method" in
+        "initialize")
+            # Send initialize response with file operations capabilities
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"capabilities":{"textDocumentSync":1,"workspace":{"fileOperations":{"willRename":{"filters":[{"pattern":{"glob":"**/*.rs"}}]},"didRename":{"filters":[{"pattern":{"glob":"**/*.rs"}}]}}}}}}'
+            ;;
+        "workspace/willRenameFiles")
+            # Rename the module declared on the first line of the edited file
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"changes":{"'$edited_uri'":[{"range":{"start":{"line":0,"character":4},"end":{"line":0,"character":7}},"newText":"new"}]}}}'
+            ;;
+        "shutdown")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            break
+            ;;
+        *)
+            # Answer any other request with no result
+            if [ -n "$msg_id" ]; then
+                send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            fi
+            ;;
+    esac
+done
+"#;
+
+        // Write script to a temporary file
+        let script_path = std::env::temp_dir().join("fake_lsp_server_file_renames.sh");
+        std::fs::write(&script_path, script)?;
+
+        // Make it executable
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&script_path)?.permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&script_path, perms)?;
+        }
+
+        let handle = Some(thread::spawn(move || {
+            // Wait for stop signal
+            let _ = stop_rx.recv();
+        }));
+
+        Ok(Self { handle, stop_tx })
+    }
+
+    /// Get the path to the file renames fake LSP server script
+    pub fn file_renames_script_path() -> std::path::PathBuf {
+        std::env::temp_dir().join("fake_lsp_server_file_renames.sh")
+    }
+
     /// Stop the server
     pub fn stop(&mut self) {
         let _ = self.stop_tx.send(());
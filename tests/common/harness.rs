@@ -65,6 +65,7 @@ pub mod layout {
 use fresh::config::DirectoryContext;
 use fresh::primitives::highlight_engine::HighlightEngine;
 use fresh::services::fs::{BackendMetrics, FsBackend, LocalFsBackend, SlowFsBackend, SlowFsConfig};
+use fresh::services::remote::RemoteConnection;
 use fresh::{app::Editor, config::Config};
 use ratatui::{backend::TestBackend, Terminal};
 use std::io::{self, Write};
@@ -380,6 +381,50 @@ impl EditorTestHarness {
         })
     }
 
+    /// Create a test harness editing remotely over `remote`
+    ///
+    /// The project directory ("project_root" under the temp dir) is opened as
+    /// the remote working directory, so the connection's agent must run on
+    /// this machine.
+    pub fn with_remote(width: u16, height: u16, remote: Arc<RemoteConnection>) -> io::Result<Self> {
+        let temp_dir = TempDir::new()?;
+        let dir_context = DirectoryContext::for_testing(temp_dir.path());
+        let project_root = temp_dir.path().join("project_root");
+        std::fs::create_dir(&project_root)?;
+
+        let backend = TestBackend::new(width, height);
+        let terminal = Terminal::new(backend)?;
+        // Disable update checking in tests to avoid flaky status bar changes
+        let config = Config {
+            check_for_updates: false,
+            ..Default::default()
+        };
+
+        let editor = Editor::with_remote(
+            config,
+            width,
+            height,
+            project_root,
+            remote,
+            dir_context,
+            true,
+        )?;
+
+        Ok(EditorTestHarness {
+            editor,
+            terminal,
+            _temp_dir: Some(temp_dir),
+            fs_metrics: None,
+            _tokio_runtime: None,
+            shadow_string: String::new(),
+            shadow_cursor: 0,
+            enable_shadow_validation: false,
+            vt100_parser: vt100::Parser::new(height, width, 0),
+            term_width: width,
+            term_height: height,
+        })
+    }
+
     /// Get filesystem metrics (if using slow filesystem backend)
     pub fn fs_metrics(&self) -> Option<Arc<tokio::sync::Mutex<BackendMetrics>>> {
         self.fs_metrics.clone()
@@ -1,5 +1,8 @@
 // Common test utilities
 
+#[cfg(test)]
+#[allow(dead_code)]
+pub mod fake_dap;
 #[cfg(test)]
 #[allow(dead_code)]
 pub mod fake_lsp;
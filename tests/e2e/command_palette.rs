@@ -503,7 +503,7 @@ fn test_command_palette_from_file_explorer() {
     harness.assert_screen_contains("Command:");
 
     // Should show commands
-    harness.assert_screen_contains("Open File");
+    harness.assert_screen_contains("File Explorer: New File");
 
     // Should be able to execute a command
     harness.type_text("toggle hidden").unwrap();
@@ -0,0 +1,123 @@
+//! E2E tests for debugging through the Debug Adapter Protocol
+
+use crate::common::fake_dap::write_fake_dap_adapter;
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use fresh::config::{Config, DebugAdapterConfig, DebugRequest};
+use tempfile::TempDir;
+
+const SOURCE: &str = "fn main() {\n    let a = 1;\n    let b = 2;\n    let c = 3;\n}\n";
+
+fn wait_for_screen(harness: &mut EditorTestHarness, text: &str) {
+    harness
+        .wait_until(|h| h.screen_to_string().contains(text))
+        .unwrap();
+}
+
+/// Byte range of the highlighted current line in the active buffer
+fn current_line_highlight(harness: &EditorTestHarness) -> Option<std::ops::Range<usize>> {
+    let state = harness.editor().active_state();
+    state
+        .overlays
+        .all()
+        .iter()
+        .find(|overlay| {
+            overlay
+                .namespace
+                .as_ref()
+                .is_some_and(|ns| ns.0 == "debug-current-line")
+        })
+        .map(|overlay| overlay.range(&state.marker_list))
+}
+
+/// A session stops at a breakpoint set in the gutter, shows the call stack,
+/// variables and output in the panel, steps, and ends when continued
+#[test]
+#[cfg_attr(windows, ignore)] // The fake adapter is a Bash script
+fn test_debug_session_with_breakpoint() {
+    let temp_dir = TempDir::new().unwrap();
+    let adapter = write_fake_dap_adapter(temp_dir.path()).unwrap();
+    let file_path = temp_dir.path().join("main.rs");
+    std::fs::write(&file_path, SOURCE).unwrap();
+
+    let mut config = Config::default();
+    config.debug.insert(
+        "rust".to_string(),
+        DebugAdapterConfig {
+            command: adapter.to_string_lossy().to_string(),
+            args: vec![],
+            request: DebugRequest::Launch,
+            configuration: serde_json::json!({"program": "$FILE"}),
+        },
+    );
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        100,
+        40,
+        config,
+        temp_dir.path().to_path_buf(),
+    )
+    .unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    // Breakpoint on line 2
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    harness.send_key(KeyCode::F(9), KeyModifiers::NONE).unwrap();
+    harness.assert_screen_contains("Breakpoint set on line 2");
+    harness.assert_screen_contains("●");
+
+    harness.send_key(KeyCode::Up, KeyModifiers::NONE).unwrap();
+    harness
+        .send_key(KeyCode::F(5), KeyModifiers::CONTROL)
+        .unwrap();
+    wait_for_screen(&mut harness, "stopped: breakpoint");
+    wait_for_screen(&mut harness, "counter: int = 2");
+    harness.assert_screen_contains("*Debug*");
+    harness.assert_screen_contains("> main  main.rs:2");
+    harness.assert_screen_contains("  start  main.rs:1");
+    harness.assert_screen_contains("hello from debuggee");
+
+    // The stopped line is shown and highlighted in the source split
+    harness.assert_buffer_content(SOURCE);
+    assert_eq!(harness.cursor_position(), 12);
+    assert_eq!(current_line_highlight(&harness), Some(12..27));
+
+    harness.send_key(KeyCode::F(7), KeyModifiers::NONE).unwrap();
+    wait_for_screen(&mut harness, "stopped: step");
+    wait_for_screen(&mut harness, "counter: int = 3");
+    assert_eq!(harness.cursor_position(), 27);
+    assert_eq!(current_line_highlight(&harness), Some(27..42));
+
+    harness.send_key(KeyCode::F(6), KeyModifiers::NONE).unwrap();
+    wait_for_screen(&mut harness, "exited with code 0");
+    harness.assert_screen_not_contains("> main");
+    assert_eq!(current_line_highlight(&harness), None);
+}
+
+/// Breakpoints toggle off, and debugging needs an adapter for the language
+#[test]
+fn test_toggle_breakpoint_and_missing_adapter() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("main.rs");
+    std::fs::write(&file_path, SOURCE).unwrap();
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        100,
+        30,
+        Config::default(),
+        temp_dir.path().to_path_buf(),
+    )
+    .unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    harness.send_key(KeyCode::F(9), KeyModifiers::NONE).unwrap();
+    harness.assert_screen_contains("●");
+    harness.send_key(KeyCode::F(9), KeyModifiers::NONE).unwrap();
+    harness.assert_screen_contains("Breakpoint removed from line 1");
+    harness.assert_screen_not_contains("●");
+
+    harness
+        .send_key(KeyCode::F(5), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.assert_screen_contains("No debug adapter configured for rust");
+    harness.assert_screen_not_contains("*Debug*");
+}
@@ -0,0 +1,163 @@
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use tempfile::TempDir;
+
+fn utf16le_with_bom(text: &str) -> Vec<u8> {
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+    bytes
+}
+
+/// Run a command from the command palette
+fn run_command(harness: &mut EditorTestHarness, name: &str) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text(name).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+}
+
+/// Replace the text of the open prompt and confirm it
+fn choose_in_prompt(harness: &mut EditorTestHarness, choice: &str) {
+    harness
+        .send_key_repeat(KeyCode::Backspace, KeyModifiers::NONE, 32)
+        .unwrap();
+    harness.type_text(choice).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+}
+
+fn status(harness: &EditorTestHarness) -> String {
+    harness
+        .editor()
+        .get_status_message()
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// A UTF-16 file is decoded for editing, its encoding is shown in the status
+/// bar, and it is written back as UTF-16 with its BOM and line endings
+#[test]
+fn test_utf16_file_round_trip() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("app.rc");
+    std::fs::write(&file_path, utf16le_with_bom("BEGIN\r\nEND\r\n")).unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness.render().unwrap();
+
+    harness.assert_buffer_content("BEGIN\nEND\n");
+    harness.assert_screen_contains("UTF-16 LE");
+
+    harness.type_text("// ").unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    assert_eq!(
+        std::fs::read(&file_path).unwrap(),
+        utf16le_with_bom("// BEGIN\r\nEND\r\n")
+    );
+}
+
+/// Plain UTF-8 files don't show an encoding in the status bar
+#[test]
+fn test_utf8_encoding_not_shown() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("notes.txt");
+    std::fs::write(&file_path, "h\u{e9}llo\n").unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness.render().unwrap();
+
+    harness.assert_buffer_content("h\u{e9}llo\n");
+    harness.assert_screen_not_contains("UTF-8");
+}
+
+/// Reopen with Encoding re-decodes the file with the chosen encoding
+#[test]
+fn test_reopen_with_encoding() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("quotes.txt");
+    std::fs::write(&file_path, b"\x93hi\x94\n").unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness.assert_buffer_content("\u{201c}hi\u{201d}\n");
+
+    run_command(&mut harness, "Reopen with Encoding");
+    choose_in_prompt(&mut harness, "Latin-1");
+    harness.render().unwrap();
+
+    assert_eq!(status(&harness), "Reopened with Latin-1");
+    harness.assert_buffer_content("\u{93}hi\u{94}\n");
+    harness.assert_screen_contains("Latin-1");
+}
+
+/// Reopening is refused while the buffer has unsaved changes
+#[test]
+fn test_reopen_with_encoding_refuses_modified_buffer() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("notes.txt");
+    std::fs::write(&file_path, "abc\n").unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness.type_text("x").unwrap();
+
+    run_command(&mut harness, "Reopen with Encoding");
+    choose_in_prompt(&mut harness, "Latin-1");
+
+    assert!(status(&harness).contains("unsaved changes"));
+    harness.assert_buffer_content("xabc\n");
+}
+
+/// Save with Encoding converts the file on disk
+#[test]
+fn test_save_with_encoding() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("notes.txt");
+    std::fs::write(&file_path, "h\u{e9}llo\n").unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    run_command(&mut harness, "Save with Encoding");
+    choose_in_prompt(&mut harness, "UTF-16 LE");
+
+    assert_eq!(status(&harness), "Saved as UTF-16 LE");
+    assert_eq!(
+        std::fs::read(&file_path).unwrap(),
+        utf16le_with_bom("h\u{e9}llo\n")
+    );
+    assert!(!harness.editor().active_state().buffer.is_modified());
+}
+
+/// Saving text the encoding cannot represent fails without touching the
+/// file, and the buffer keeps its encoding
+#[test]
+fn test_save_with_encoding_unmappable_character() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("prices.txt");
+    std::fs::write(&file_path, "5 \u{20ac}\n").unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    run_command(&mut harness, "Save with Encoding");
+    choose_in_prompt(&mut harness, "Latin-1");
+
+    assert!(
+        status(&harness).starts_with("Failed to save as Latin-1"),
+        "unexpected status: {}",
+        status(&harness)
+    );
+    assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "5 \u{20ac}\n");
+    let buffer = &harness.editor().active_state().buffer;
+    assert!(buffer.encoding().is_plain_utf8());
+    assert!(!buffer.is_modified());
+}
@@ -514,9 +514,10 @@ fn test_explorer_menu_toggle_hidden_via_menu() {
     harness.render().unwrap();
 
     // Navigate to "Show Hidden Files"
-    // Menu items (separators are auto-skipped): New File -> New Folder -> Open -> Rename -> Delete -> Refresh -> Show Hidden Files
-    // That's 6 Down presses from New File to Show Hidden Files
-    for _ in 0..6 {
+    // Menu items (separators are auto-skipped): New File -> New Folder -> Open -> Rename -> Duplicate -> Delete
+    // -> Copy -> Cut -> Paste -> Undo File Operation -> Refresh -> Show Hidden Files
+    // That's 11 Down presses from New File to Show Hidden Files
+    for _ in 0..11 {
         harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
     }
     harness.render().unwrap();
@@ -570,39 +570,320 @@ fn test_file_explorer_new_directory_smoke() {
     // Test passes if no panic occurs
 }
 
-/// Test that file_explorer_delete can be called (smoke test)
+/// Open the file explorer and wait until `name` is listed under the root,
+/// which is expanded when the explorer opens
+fn show_in_explorer(harness: &mut EditorTestHarness, name: &str) {
+    harness.editor_mut().focus_file_explorer();
+    harness
+        .wait_until(|h| h.screen_to_string().contains(name))
+        .unwrap();
+}
+
+/// Test that deleting asks for confirmation, moves the file to the trash,
+/// and can be undone
 #[test]
-fn test_file_explorer_delete_smoke() {
+fn test_file_explorer_delete_to_trash_and_undo() {
     // Create harness with isolated temp project
     let mut harness = EditorTestHarness::with_temp_project(120, 40).unwrap();
     let project_root = harness.project_dir().unwrap();
+    let trash_dir = project_root.parent().unwrap().join("Trash");
 
     // Create a test file
     fs::write(project_root.join("test.txt"), "test").unwrap();
 
-    // Toggle file explorer on
-    harness.editor_mut().toggle_file_explorer();
-    std::thread::sleep(std::time::Duration::from_millis(100));
-    let _ = harness.editor_mut().process_async_messages();
-    std::thread::sleep(std::time::Duration::from_millis(100));
-    let _ = harness.editor_mut().process_async_messages();
+    show_in_explorer(&mut harness, "test.txt");
+    harness.editor_mut().file_explorer_navigate_down();
+
+    // Deleting asks first, telling what will be removed
+    harness.editor_mut().file_explorer_delete();
     harness.render().unwrap();
+    harness.assert_screen_contains("Move 'test.txt' (4 B) to trash?");
+    assert!(project_root.join("test.txt").exists());
 
-    // Expand root and navigate
-    harness.editor_mut().file_explorer_toggle_expand();
-    std::thread::sleep(std::time::Duration::from_millis(50));
-    let _ = harness.editor_mut().process_async_messages();
+    harness.type_text("y").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+    assert!(!project_root.join("test.txt").exists());
+    assert!(trash_dir.join("files/test.txt").exists());
+    assert!(trash_dir.join("info/test.txt.trashinfo").exists());
+
+    // Undo restores the file
+    harness.editor_mut().file_explorer_undo();
     harness.render().unwrap();
+    assert_eq!(
+        fs::read_to_string(project_root.join("test.txt")).unwrap(),
+        "test"
+    );
+    assert!(!trash_dir.join("files/test.txt").exists());
+    harness.assert_screen_contains("Restored test.txt");
+}
+
+/// Test that declining the confirmation keeps the file
+#[test]
+fn test_file_explorer_delete_cancelled() {
+    let mut harness = EditorTestHarness::with_temp_project(120, 40).unwrap();
+    let project_root = harness.project_dir().unwrap();
+    fs::write(project_root.join("keep.txt"), "keep").unwrap();
 
+    show_in_explorer(&mut harness, "keep.txt");
     harness.editor_mut().file_explorer_navigate_down();
+
+    harness.editor_mut().file_explorer_delete();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
     harness.render().unwrap();
 
-    // Call delete - should not panic (actual deletion depends on runtime and safety checks)
+    assert!(project_root.join("keep.txt").exists());
+    harness.assert_screen_contains("Delete cancelled");
+}
+
+/// Test that a buffer follows its file when it is renamed, and back on undo
+#[test]
+fn test_file_explorer_rename_retargets_open_buffer() {
+    let mut harness = EditorTestHarness::with_temp_project(120, 40).unwrap();
+    let project_root = harness.project_dir().unwrap();
+    let original = project_root.join("before.txt");
+    fs::write(&original, "content").unwrap();
+    harness.open_file(&original).unwrap();
+
+    harness.editor_mut().perform_file_explorer_rename(
+        original.clone(),
+        "before.txt".to_string(),
+        "after.txt".to_string(),
+    );
+    let renamed = project_root.join("after.txt");
+    assert!(renamed.exists());
+    assert_eq!(
+        harness.editor().active_state().buffer.file_path(),
+        Some(renamed.as_path())
+    );
+    harness.render().unwrap();
+    harness.assert_screen_contains("after.txt");
+
+    harness.editor_mut().file_explorer_undo();
+    assert!(original.exists());
+    assert_eq!(
+        harness.editor().active_state().buffer.file_path(),
+        Some(original.as_path())
+    );
+}
+
+/// Test that deleting a file open with unsaved changes keeps the buffer,
+/// marked as deleted, until the file is restored
+#[test]
+fn test_file_explorer_delete_orphans_modified_buffer() {
+    let mut harness = EditorTestHarness::with_temp_project(120, 40).unwrap();
+    let project_root = harness.project_dir().unwrap();
+    let path = project_root.join("edited.txt");
+    fs::write(&path, "original").unwrap();
+    harness.open_file(&path).unwrap();
+    harness.type_text("unsaved ").unwrap();
+
+    harness
+        .editor_mut()
+        .perform_file_explorer_delete(vec![path.clone()]);
+    harness.render().unwrap();
+    assert!(!path.exists());
+    harness.assert_screen_contains("edited.txt [deleted]");
+    harness.assert_buffer_content("unsaved original");
+
+    harness.editor_mut().file_explorer_undo();
+    harness.render().unwrap();
+    assert!(path.exists());
+    harness.assert_screen_not_contains("[deleted]");
+}
+
+/// Test that several selected files are deleted together and restored by
+/// a single undo
+#[test]
+fn test_file_explorer_multi_select_delete_and_undo() {
+    let mut harness = EditorTestHarness::with_temp_project(120, 40).unwrap();
+    let project_root = harness.project_dir().unwrap();
+    fs::write(project_root.join("a.txt"), "a").unwrap();
+    fs::write(project_root.join("b.txt"), "b").unwrap();
+    fs::write(project_root.join("c.txt"), "c").unwrap();
+
+    show_in_explorer(&mut harness, "c.txt");
+
+    // Select a.txt and b.txt
+    harness.editor_mut().file_explorer_navigate_down();
+    harness.editor_mut().file_explorer_extend_selection_down();
+
     harness.editor_mut().file_explorer_delete();
-    std::thread::sleep(std::time::Duration::from_millis(100));
     harness.render().unwrap();
+    harness.assert_screen_contains("Move 2 items (2 files, 2 B) to trash?");
+    harness.type_text("y").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    assert!(!project_root.join("a.txt").exists());
+    assert!(!project_root.join("b.txt").exists());
+    assert!(project_root.join("c.txt").exists());
 
-    // Test passes if no panic occurs
+    harness.editor_mut().file_explorer_undo();
+    harness.render().unwrap();
+    assert!(project_root.join("a.txt").exists());
+    assert!(project_root.join("b.txt").exists());
+    harness.assert_screen_contains("Undid 2 file operations");
+}
+
+/// Test copying a file into a directory holding one of the same name,
+/// keeping both by renaming the copy
+#[test]
+fn test_file_explorer_copy_paste_name_conflict() {
+    let mut harness = EditorTestHarness::with_temp_project(120, 40).unwrap();
+    let project_root = harness.project_dir().unwrap();
+    fs::create_dir(project_root.join("dest")).unwrap();
+    fs::write(project_root.join("dest/a.txt"), "old").unwrap();
+    fs::write(project_root.join("a.txt"), "new").unwrap();
+
+    show_in_explorer(&mut harness, "a.txt");
+
+    // Directories come first: root, dest/, a.txt
+    harness.editor_mut().file_explorer_navigate_down();
+    harness.editor_mut().file_explorer_navigate_down();
+    harness.editor_mut().file_explorer_copy();
+    harness.editor_mut().file_explorer_navigate_up();
+    harness.editor_mut().file_explorer_paste();
+    harness.render().unwrap();
+    harness.assert_screen_contains("'a.txt' already exists");
+
+    harness.type_text("r").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_screen_contains("Pasted 1 item");
+    assert_eq!(
+        fs::read_to_string(project_root.join("dest/a.txt")).unwrap(),
+        "old"
+    );
+    assert_eq!(
+        fs::read_to_string(project_root.join("dest/a copy.txt")).unwrap(),
+        "new"
+    );
+    assert!(project_root.join("a.txt").exists());
+
+    // Undo takes the copy away
+    harness.editor_mut().file_explorer_undo();
+    assert!(!project_root.join("dest/a copy.txt").exists());
+    assert!(project_root.join("dest/a.txt").exists());
+}
+
+/// Test that cut and paste moves a file, and undo moves it back
+#[test]
+fn test_file_explorer_cut_paste_moves_file() {
+    let mut harness = EditorTestHarness::with_temp_project(120, 40).unwrap();
+    let project_root = harness.project_dir().unwrap();
+    fs::create_dir(project_root.join("sub")).unwrap();
+    fs::write(project_root.join("moved.txt"), "content").unwrap();
+
+    show_in_explorer(&mut harness, "moved.txt");
+
+    // Directories come first: root, sub/, moved.txt
+    harness.editor_mut().file_explorer_navigate_down();
+    harness.editor_mut().file_explorer_navigate_down();
+    harness.editor_mut().file_explorer_cut();
+    harness.editor_mut().file_explorer_navigate_up();
+    harness.editor_mut().file_explorer_paste();
+    harness.render().unwrap();
+
+    assert!(!project_root.join("moved.txt").exists());
+    assert!(project_root.join("sub/moved.txt").exists());
+    harness.assert_screen_contains("Moved 1 item");
+
+    // The cut files are pasted once only
+    harness.editor_mut().file_explorer_paste();
+    harness.render().unwrap();
+    harness.assert_screen_contains("Nothing to paste");
+
+    harness.editor_mut().file_explorer_undo();
+    assert!(project_root.join("moved.txt").exists());
+    assert!(!project_root.join("sub/moved.txt").exists());
+}
+
+/// Test that a buffer follows its file when the file is dropped into
+/// another directory
+#[test]
+fn test_file_explorer_drop_retargets_open_buffer() {
+    let mut harness = EditorTestHarness::with_temp_project(120, 40).unwrap();
+    let project_root = harness.project_dir().unwrap();
+    let sub = project_root.join("sub");
+    fs::create_dir(&sub).unwrap();
+    let original = project_root.join("dragged.txt");
+    fs::write(&original, "content").unwrap();
+    harness.open_file(&original).unwrap();
+
+    harness
+        .editor_mut()
+        .file_explorer_move_to(vec![original.clone()], sub.clone());
+    let moved = sub.join("dragged.txt");
+    assert!(!original.exists());
+    assert!(moved.exists());
+    assert_eq!(
+        harness.editor().active_state().buffer.file_path(),
+        Some(moved.as_path())
+    );
+}
+
+/// Test that duplicating a file puts a copy next to it
+#[test]
+fn test_file_explorer_duplicate() {
+    let mut harness = EditorTestHarness::with_temp_project(120, 40).unwrap();
+    let project_root = harness.project_dir().unwrap();
+    fs::write(project_root.join("notes.md"), "notes").unwrap();
+
+    show_in_explorer(&mut harness, "notes.md");
+
+    harness.editor_mut().file_explorer_navigate_down();
+    harness.editor_mut().file_explorer_duplicate();
+    harness.render().unwrap();
+
+    assert_eq!(
+        fs::read_to_string(project_root.join("notes copy.md")).unwrap(),
+        "notes"
+    );
+    harness.assert_screen_contains("Duplicated 'notes.md'");
+    harness.assert_screen_contains("notes copy.md");
+}
+
+/// Test that duplicating a directory copies the symlinks in it as links
+#[cfg(unix)]
+#[test]
+fn test_file_explorer_duplicate_keeps_symlinked_directory() {
+    let mut harness = EditorTestHarness::with_temp_project(120, 40).unwrap();
+    let project_root = harness.project_dir().unwrap();
+    fs::create_dir_all(project_root.join("real")).unwrap();
+    fs::write(project_root.join("real/lib.rs"), "lib").unwrap();
+    fs::create_dir(project_root.join("pkg")).unwrap();
+    fs::write(project_root.join("pkg/main.rs"), "main").unwrap();
+    std::os::unix::fs::symlink("../real", project_root.join("pkg/lib")).unwrap();
+
+    show_in_explorer(&mut harness, "pkg");
+
+    // Directories come first: root, pkg/, real/
+    harness.editor_mut().file_explorer_navigate_down();
+    harness.editor_mut().file_explorer_duplicate();
+    harness.render().unwrap();
+
+    harness.assert_screen_contains("Duplicated 'pkg'");
+    let copy = project_root.join("pkg copy");
+    assert_eq!(fs::read_to_string(copy.join("main.rs")).unwrap(), "main");
+    assert_eq!(
+        fs::read_link(copy.join("lib")).unwrap(),
+        std::path::Path::new("../real")
+    );
+
+    // Undo removes the copy, link included, and leaves the linked directory
+    harness.editor_mut().file_explorer_undo();
+    assert!(!copy.exists());
+    assert_eq!(
+        fs::read_to_string(project_root.join("real/lib.rs")).unwrap(),
+        "lib"
+    );
 }
 
 /// Test Feature 1: Enter key on directory toggles expand/collapse
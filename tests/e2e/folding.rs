@@ -0,0 +1,189 @@
+//! E2E tests for code folding
+
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use fresh::config::Config;
+use tempfile::TempDir;
+
+const SOURCE: &str = "fn main() {
+    let a = 1;
+    let b = 2;
+    let c = 3;
+}
+
+fn other() {
+    let d = 4;
+}
+";
+
+fn open_source(temp_dir: &TempDir) -> EditorTestHarness {
+    let file = temp_dir.path().join("main.rs");
+    std::fs::write(&file, SOURCE).unwrap();
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&file).unwrap();
+    harness.render().unwrap();
+    harness
+}
+
+fn fold(harness: &mut EditorTestHarness) {
+    harness
+        .send_key(
+            KeyCode::Char('['),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        )
+        .unwrap();
+    harness.render().unwrap();
+}
+
+fn run_command(harness: &mut EditorTestHarness, command: &str) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text(command).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+}
+
+/// Folding a function hides its body but keeps the closing brace visible
+#[test]
+fn test_fold_hides_function_body() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = open_source(&temp_dir);
+
+    fold(&mut harness);
+
+    harness.assert_screen_not_contains("let a = 1;");
+    harness.assert_screen_not_contains("let c = 3;");
+    harness.assert_screen_contains("fn main() {");
+    harness.assert_screen_contains("let d = 4;");
+    harness.assert_screen_contains("▸");
+    // Line numbers after the fold skip the hidden lines
+    let header_row = harness.get_screen_row(2);
+    assert!(
+        header_row.contains("1 │ fn main() {"),
+        "row was {header_row:?}"
+    );
+    let next_row = harness.get_screen_row(3);
+    assert!(next_row.contains("5 │ }"), "row was {next_row:?}");
+
+    // The buffer itself is unchanged
+    harness.assert_buffer_content(SOURCE);
+}
+
+/// Moving down from a fold header skips the hidden lines
+#[test]
+fn test_cursor_down_skips_fold() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = open_source(&temp_dir);
+
+    fold(&mut harness);
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+
+    let closing_brace = SOURCE.find("}\n").unwrap();
+    assert_eq!(harness.cursor_position(), closing_brace);
+
+    harness.send_key(KeyCode::Up, KeyModifiers::NONE).unwrap();
+    assert_eq!(harness.cursor_position(), 0);
+}
+
+/// Unfolding at the header shows the hidden lines again
+#[test]
+fn test_unfold_restores_lines() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = open_source(&temp_dir);
+
+    fold(&mut harness);
+    harness.assert_screen_not_contains("let b = 2;");
+
+    harness
+        .send_key(
+            KeyCode::Char(']'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        )
+        .unwrap();
+    harness.render().unwrap();
+
+    harness.assert_screen_contains("let b = 2;");
+    harness.assert_screen_not_contains("▸");
+}
+
+/// Searching for text inside a fold expands it
+#[test]
+fn test_search_reveals_folded_text() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = open_source(&temp_dir);
+
+    fold(&mut harness);
+    harness.assert_screen_not_contains("let b = 2;");
+
+    harness
+        .send_key(KeyCode::Char('f'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("let b").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+
+    harness.assert_screen_contains("let b = 2;");
+    assert_eq!(harness.cursor_position(), SOURCE.find("let b").unwrap());
+}
+
+/// Fold All collapses every region and Unfold All expands them again
+#[test]
+fn test_fold_all_and_unfold_all() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = open_source(&temp_dir);
+
+    run_command(&mut harness, "Fold All");
+    harness.assert_screen_not_contains("let a = 1;");
+    harness.assert_screen_not_contains("let d = 4;");
+    harness.assert_screen_contains("fn other() {");
+
+    run_command(&mut harness, "Unfold All");
+    harness.assert_screen_contains("let a = 1;");
+    harness.assert_screen_contains("let d = 4;");
+}
+
+/// Folds are saved with the session and restored
+#[test]
+fn test_session_restores_folds() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path().join("project");
+    std::fs::create_dir(&project_dir).unwrap();
+    let file = project_dir.join("main.rs");
+    std::fs::write(&file, SOURCE).unwrap();
+
+    {
+        let mut harness = EditorTestHarness::with_config_and_working_dir(
+            80,
+            24,
+            Config::default(),
+            project_dir.clone(),
+        )
+        .unwrap();
+        harness.open_file(&file).unwrap();
+        harness.render().unwrap();
+        fold(&mut harness);
+        harness.assert_screen_not_contains("let a = 1;");
+        harness.editor_mut().save_session().unwrap();
+    }
+
+    {
+        let mut harness = EditorTestHarness::with_config_and_working_dir(
+            80,
+            24,
+            Config::default(),
+            project_dir.clone(),
+        )
+        .unwrap();
+        harness.editor_mut().try_restore_session().unwrap();
+        harness.render().unwrap();
+
+        harness.assert_screen_contains("fn main() {");
+        harness.assert_screen_not_contains("let a = 1;");
+        harness.assert_screen_contains("let d = 4;");
+    }
+}
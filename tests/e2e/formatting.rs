@@ -0,0 +1,265 @@
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use fresh::config::{Config, FormatterConfig};
+use tempfile::TempDir;
+
+/// Config whose "rust" language is formatted by a sed script that adds
+/// spaces around `=` (a stand-in for a real formatter)
+fn config_with_formatter(format_on_save: bool) -> Config {
+    let mut config = Config::default();
+    let rust = config.languages.get_mut("rust").unwrap();
+    rust.formatter = Some(FormatterConfig {
+        command: "sed".to_string(),
+        args: vec!["s/\\([a-z]\\)=\\([0-9]\\)/\\1 = \\2/g".to_string()],
+        stdin: true,
+    });
+    rust.format_on_save = format_on_save;
+    config
+}
+
+/// Format Buffer applies only the changed region, keeps the cursor on its
+/// text, and is undone in a single step
+#[test]
+#[cfg_attr(windows, ignore)]
+fn test_format_buffer_with_external_formatter() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("main.rs");
+    let original = "let x=1;\nlet y = 2;\n";
+    std::fs::write(&file_path, original).unwrap();
+
+    let mut harness = EditorTestHarness::with_config(80, 24, config_with_formatter(false)).unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    // Put the cursor on the 'y' of the second line
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    harness
+        .send_key_repeat(KeyCode::Right, KeyModifiers::NONE, 4)
+        .unwrap();
+    assert_eq!(harness.cursor_position(), 13);
+
+    harness
+        .send_key(KeyCode::Char('f'), KeyModifiers::ALT | KeyModifiers::SHIFT)
+        .unwrap();
+    wait_for_status(&mut harness, "Formatted");
+
+    harness.assert_buffer_content("let x = 1;\nlet y = 2;\n");
+    // Two bytes were inserted before the cursor, which stays on the 'y'
+    assert_eq!(harness.cursor_position(), 15);
+
+    // Formatting again is a no-op
+    harness
+        .send_key(KeyCode::Char('f'), KeyModifiers::ALT | KeyModifiers::SHIFT)
+        .unwrap();
+    wait_for_status(&mut harness, "Already formatted");
+
+    // A single undo restores the original text
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.assert_buffer_content(original);
+}
+
+/// With format_on_save, saving writes the formatted content to disk
+#[test]
+#[cfg_attr(windows, ignore)]
+fn test_format_on_save() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("main.rs");
+    std::fs::write(&file_path, "let x=1;\n").unwrap();
+
+    let mut harness = EditorTestHarness::with_config(80, 24, config_with_formatter(true)).unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    harness.type_text("let z=3;").unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    wait_for_status(&mut harness, "Saved");
+
+    let expected = "let z = 3;let x = 1;\n";
+    harness.assert_buffer_content(expected);
+    assert_eq!(std::fs::read_to_string(&file_path).unwrap(), expected);
+    // The cursor stays after the typed text, shifted by the inserted spaces
+    assert_eq!(harness.cursor_position(), 10);
+}
+
+/// A failing formatter does not prevent saving
+#[test]
+#[cfg_attr(windows, ignore)]
+fn test_format_on_save_failure_still_saves() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("main.rs");
+    std::fs::write(&file_path, "a\n").unwrap();
+
+    let mut config = Config::default();
+    let rust = config.languages.get_mut("rust").unwrap();
+    rust.formatter = Some(FormatterConfig {
+        command: "sh".to_string(),
+        args: vec![
+            "-c".to_string(),
+            "echo 'parse error' >&2; exit 1".to_string(),
+        ],
+        stdin: true,
+    });
+    rust.format_on_save = true;
+
+    let mut harness = EditorTestHarness::with_config(80, 24, config).unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    harness.type_text("b").unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness
+        .wait_until(|h| !h.editor().active_state().buffer.is_modified())
+        .unwrap();
+
+    assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "ba\n");
+    let status = harness.editor().get_status_message().cloned().unwrap();
+    assert!(
+        status.starts_with("Saved (formatting failed:") && status.contains("parse error"),
+        "unexpected status: {}",
+        status
+    );
+}
+
+/// A formatter that takes too long does not hold up saving: the buffer is
+/// saved unformatted, and the late result is dropped
+#[test]
+#[cfg_attr(windows, ignore)]
+fn test_format_on_save_timeout_saves_unformatted() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("main.rs");
+    std::fs::write(&file_path, "a\n").unwrap();
+
+    let mut config = Config::default();
+    let rust = config.languages.get_mut("rust").unwrap();
+    rust.formatter = Some(FormatterConfig {
+        command: "sh".to_string(),
+        args: vec!["-c".to_string(), "sleep 4; echo formatted".to_string()],
+        stdin: true,
+    });
+    rust.format_on_save = true;
+
+    let mut harness = EditorTestHarness::with_config(80, 24, config).unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    harness.type_text("b").unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    // The editor keeps running while the formatter does
+    assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "a\n");
+
+    wait_for_status(
+        &mut harness,
+        "Saved (formatting failed: timed out after 3s)",
+    );
+    assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "ba\n");
+
+    // The formatter's late output is ignored
+    std::thread::sleep(std::time::Duration::from_secs(2));
+    harness.process_async_and_render().unwrap();
+    harness.assert_buffer_content("ba\n");
+}
+
+/// Saving while Format Buffer is running saves once the formatted text is in
+#[test]
+#[cfg_attr(windows, ignore)]
+fn test_save_while_formatting_waits_for_formatter() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("main.rs");
+    std::fs::write(&file_path, "let x=1;\n").unwrap();
+
+    let mut config = Config::default();
+    let rust = config.languages.get_mut("rust").unwrap();
+    rust.formatter = Some(FormatterConfig {
+        command: "sh".to_string(),
+        args: vec!["-c".to_string(), "sleep 1; sed 's/x=1/x = 1/'".to_string()],
+        stdin: true,
+    });
+    rust.format_on_save = true;
+
+    let mut harness = EditorTestHarness::with_config(80, 24, config).unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness.type_text("\n").unwrap();
+
+    harness
+        .send_key(KeyCode::Char('f'), KeyModifiers::ALT | KeyModifiers::SHIFT)
+        .unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    wait_for_status(&mut harness, "Saved");
+
+    let expected = "\nlet x = 1;\n";
+    harness.assert_buffer_content(expected);
+    assert_eq!(std::fs::read_to_string(&file_path).unwrap(), expected);
+}
+
+/// Wait until the status bar message is `status`
+fn wait_for_status(harness: &mut EditorTestHarness, status: &str) {
+    harness
+        .wait_until(|h| h.editor().get_status_message().is_some_and(|s| s == status))
+        .unwrap();
+}
+
+/// Without an external formatter, Format Buffer asks the language server and
+/// applies its whole-line edit as a minimal change
+#[test]
+#[cfg_attr(windows, ignore)]
+fn test_format_buffer_falls_back_to_lsp() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let _fake_server = FakeLspServer::spawn()?;
+
+    let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("main.rs");
+    std::fs::write(&file_path, "fn main() {\nlet x = 1;\n}\n")?;
+
+    let mut config = Config::default();
+    config.lsp.insert(
+        "rust".to_string(),
+        fresh::services::lsp::client::LspServerConfig {
+            command: FakeLspServer::script_path().to_string_lossy().to_string(),
+            args: vec![],
+            enabled: true,
+            auto_start: true,
+            process_limits: fresh::services::process_limits::ProcessLimits::default(),
+            initialization_options: None,
+            name: None,
+            priority: 0,
+        }
+        .into(),
+    );
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        config,
+        temp_dir.path().to_path_buf(),
+    )?;
+    harness.open_file(&file_path)?;
+    harness.wait_until(|h| {
+        h.editor()
+            .get_status_message()
+            .is_some_and(|s| s == "LSP (rust) ready")
+    })?;
+
+    // Cursor at the end of the buffer, past the edited line
+    harness.send_key(KeyCode::End, KeyModifiers::CONTROL)?;
+    assert_eq!(harness.cursor_position(), 25);
+
+    harness.send_key(KeyCode::Char('f'), KeyModifiers::ALT | KeyModifiers::SHIFT)?;
+    harness.wait_until(|h| {
+        h.get_buffer_content().as_deref() == Some("fn main() {\n    let x = 1;\n}\n")
+    })?;
+
+    // Only the indentation was inserted, so the cursor just shifts by four
+    assert_eq!(harness.cursor_position(), 29);
+    assert_eq!(
+        harness.editor().get_status_message().map(|s| s.as_str()),
+        Some("Formatted")
+    );
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use crate::common::harness::EditorTestHarness;
+use fresh::config::{Config, DirectoryContext};
+use tempfile::TempDir;
+
+/// A TextMate grammar for `.gzm` files, in the property list format
+const GIZMO_GRAMMAR: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>name</key>
+    <string>Gizmo</string>
+    <key>scopeName</key>
+    <string>source.gizmo</string>
+    <key>fileTypes</key>
+    <array>
+        <string>gzm</string>
+    </array>
+    <key>patterns</key>
+    <array>
+        <dict>
+            <key>match</key>
+            <string>\bgizmo\b</string>
+            <key>name</key>
+            <string>keyword.other.gizmo</string>
+        </dict>
+    </array>
+</dict>
+</plist>
+"#;
+
+/// Test that a grammar dropped into the grammars directory while the editor
+/// runs is loaded, and used for the open buffers it applies to
+#[test]
+fn test_grammar_added_while_running_is_used() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir_context = DirectoryContext::for_testing(temp_dir.path());
+    let project_dir = temp_dir.path().join("project");
+    std::fs::create_dir(&project_dir).unwrap();
+    let file_path = project_dir.join("test.gzm");
+    std::fs::write(&file_path, "gizmo\n").unwrap();
+
+    let mut harness = EditorTestHarness::with_shared_dir_context(
+        80,
+        24,
+        Config::default(),
+        project_dir,
+        dir_context.clone(),
+    )
+    .unwrap();
+    harness.open_file(&file_path).unwrap();
+    assert_ne!(
+        harness.editor().active_state().highlighter.syntax_name(),
+        Some("Gizmo")
+    );
+
+    // The grammars directory is created at startup, so it is already watched
+    std::fs::write(
+        dir_context.grammars_dir().join("gizmo.tmLanguage"),
+        GIZMO_GRAMMAR,
+    )
+    .unwrap();
+
+    harness
+        .wait_until(|h| h.editor().active_state().highlighter.syntax_name() == Some("Gizmo"))
+        .unwrap();
+    harness.assert_screen_contains("Reloaded grammars (1 user grammars)");
+}
@@ -560,3 +560,73 @@ fn test_large_file_save_preserves_unloaded_regions() {
         "Should preserve content from middle of file (Line 0500)"
     );
 }
+
+/// Test that search works in large file mode, where the buffer is only
+/// partially loaded, including a match that straddles a load-chunk boundary
+#[test]
+fn test_large_file_streaming_search() {
+    use std::fs;
+    use tempfile::TempDir;
+
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("large_search.txt");
+
+    // ~3MB of filler with needles at known offsets; the second one straddles
+    // the 1MB chunk boundary used for lazy loading
+    let chunk = fresh::model::buffer::LOAD_CHUNK_SIZE;
+    let mut content = vec![b'.'; 3 * chunk];
+    for i in (79..content.len()).step_by(80) {
+        content[i] = b'\n';
+    }
+    let needles = [10, chunk - 3, 2 * chunk + 500];
+    for &pos in &needles {
+        content[pos..pos + 6].copy_from_slice(b"NEEDLE");
+    }
+    fs::write(&file_path, &content).unwrap();
+
+    let mut harness = EditorTestHarness::with_config(
+        80,
+        24,
+        fresh::config::Config {
+            editor: fresh::config::EditorConfig {
+                large_file_threshold_bytes: 1024,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness.render().unwrap();
+
+    // Start search from past the first needle so the jump target is the second
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+
+    harness
+        .send_key(KeyCode::Char('f'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("NEEDLE").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+
+    harness
+        .wait_until(|h| {
+            h.editor()
+                .get_status_message()
+                .is_some_and(|m| m.starts_with("Found"))
+        })
+        .unwrap();
+
+    assert_eq!(
+        harness.editor().get_status_message().unwrap(),
+        "Found 3 matches for 'NEEDLE'"
+    );
+    assert_eq!(harness.cursor_position(), needles[1]);
+
+    // F3 continues through the streamed matches and wraps around
+    harness.send_key(KeyCode::F(3), KeyModifiers::NONE).unwrap();
+    assert_eq!(harness.cursor_position(), needles[2]);
+    harness.send_key(KeyCode::F(3), KeyModifiers::NONE).unwrap();
+    assert_eq!(harness.cursor_position(), needles[0]);
+}
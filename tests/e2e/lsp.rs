@@ -776,7 +776,10 @@ fn test_lsp_waiting_indicator() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            priority: 0,
+        }
+        .into(),
     );
 
     // Create harness with config
@@ -949,7 +952,10 @@ fn test_lsp_completion_canceled_on_cursor_move() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            priority: 0,
+        }
+        .into(),
     );
 
     // Create harness with config
@@ -1014,7 +1020,10 @@ fn test_lsp_cursor_animation() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            priority: 0,
+        }
+        .into(),
     );
 
     // Create harness with config
@@ -1080,7 +1089,10 @@ fn test_lsp_completion_canceled_on_text_edit() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            priority: 0,
+        }
+        .into(),
     );
 
     // Create harness with config
@@ -1664,7 +1676,10 @@ fn test_lsp_diagnostics_non_blocking() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            priority: 0,
+        }
+        .into(),
     );
 
     // Create harness with config and working directory
@@ -1821,7 +1836,10 @@ fn test_rust_analyzer_rename_real_scenario() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            priority: 0,
+        }
+        .into(),
     );
 
     // CRITICAL: Set working directory to the temp project so rust-analyzer
@@ -2429,7 +2447,10 @@ fn test_lsp_progress_status_display() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            priority: 0,
+        }
+        .into(),
     );
 
     // Create harness with config and working directory
@@ -2589,7 +2610,10 @@ fn test_lsp_crash_detection_and_restart() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            priority: 0,
+        }
+        .into(),
     );
 
     // Create harness with config and working directory
@@ -2801,6 +2825,7 @@ fn test_pull_diagnostics_message_handling() -> std::io::Result<()> {
         let _ = bridge.sender().send(AsyncMessage::LspPulledDiagnostics {
             request_id: 1,
             uri: uri.as_str().to_string(),
+            server: "test".to_string(),
             result_id: Some("test-result-id-123".to_string()),
             diagnostics: vec![diagnostic],
             unchanged: false,
@@ -2847,6 +2872,7 @@ fn test_pull_diagnostics_unchanged_response() -> std::io::Result<()> {
         let _ = bridge.sender().send(AsyncMessage::LspPulledDiagnostics {
             request_id: 2,
             uri: uri.as_str().to_string(),
+            server: "test".to_string(),
             result_id: Some("test-result-id-456".to_string()),
             diagnostics: Vec::new(), // Empty when unchanged
             unchanged: true,
@@ -2885,7 +2911,10 @@ fn test_pull_diagnostics_auto_trigger_after_open() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            priority: 0,
+        }
+        .into(),
     );
 
     // Create a temp directory and test file
@@ -2963,7 +2992,10 @@ fn test_pull_diagnostics_result_id_tracking() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            priority: 0,
+        }
+        .into(),
     );
 
     // Create a temp directory and test file
@@ -3220,7 +3252,10 @@ fn test_stopped_lsp_does_not_auto_restart_on_edit() -> std::io::Result<()> {
             auto_start: true, // Auto-start so it starts when we open the file
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            priority: 0,
+        }
+        .into(),
     );
 
     // Create harness with config and working directory
@@ -3368,3 +3403,91 @@ fn test_hover_popup_at_right_edge_does_not_panic() -> std::io::Result<()> {
 
     Ok(())
 }
+
+/// Test that renaming a file sends willRenameFiles, applies the returned edit,
+/// and re-points the renamed buffer before sending didRenameFiles
+#[test]
+fn test_lsp_file_rename_applies_will_rename_edit() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let _server = FakeLspServer::spawn_with_file_renames()?;
+
+    // Create a crate whose lib.rs declares the module about to be renamed
+    let temp_dir = tempfile::TempDir::new()?;
+    let src_dir = temp_dir.path().join("src");
+    std::fs::create_dir_all(&src_dir)?;
+    let lib_file = src_dir.join("lib.rs");
+    let old_file = src_dir.join("old.rs");
+    let new_file = src_dir.join("new.rs");
+    std::fs::write(&lib_file, "mod old;\n")?;
+    std::fs::write(&old_file, "pub fn f() {}\n")?;
+    let log_file = temp_dir.path().join("lsp.log");
+    let lib_uri = url::Url::from_file_path(&lib_file).unwrap().to_string();
+
+    let mut config = fresh::config::Config::default();
+    config.lsp.insert(
+        "rust".to_string(),
+        fresh::services::lsp::client::LspServerConfig {
+            command: FakeLspServer::file_renames_script_path()
+                .to_string_lossy()
+                .to_string(),
+            args: vec![lib_uri, log_file.to_string_lossy().to_string()],
+            enabled: true,
+            auto_start: true,
+            process_limits: fresh::services::process_limits::ProcessLimits::default(),
+            initialization_options: None,
+            name: None,
+            priority: 0,
+        }
+        .into(),
+    );
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        config,
+        temp_dir.path().to_path_buf(),
+    )?;
+    harness.open_file(&old_file)?;
+
+    // Wait for the server to be initialized and to have the file opened
+    let log = |log_file: &std::path::Path| std::fs::read_to_string(log_file).unwrap_or_default();
+    for _ in 0..50 {
+        harness.send_key(KeyCode::Null, KeyModifiers::NONE)?;
+        if log(&log_file).contains("textDocument/didOpen") {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert!(log(&log_file).contains("textDocument/didOpen"));
+
+    harness.editor_mut().perform_file_explorer_rename(
+        old_file.clone(),
+        "old.rs".to_string(),
+        "new.rs".to_string(),
+    );
+    assert!(new_file.exists());
+
+    // The edit from willRenameFiles updates the module declaration
+    for _ in 0..50 {
+        harness.send_key(KeyCode::Null, KeyModifiers::NONE)?;
+        if harness.get_buffer_content().as_deref() == Some("mod new;\n") {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert_eq!(harness.get_buffer_content().as_deref(), Some("mod new;\n"));
+
+    let methods: Vec<String> = log(&log_file).lines().map(String::from).collect();
+    let position = |method: &str| methods.iter().position(|m| m == method);
+    let will_rename = position("workspace/willRenameFiles").expect("willRenameFiles sent");
+    let did_close = position("textDocument/didClose").expect("didClose sent");
+    let did_rename = position("workspace/didRenameFiles").expect("didRenameFiles sent");
+    assert!(will_rename < did_close && did_close < did_rename);
+    assert!(
+        methods[did_close..did_rename].contains(&"textDocument/didOpen".to_string()),
+        "The renamed buffer should be opened again under its new URI"
+    );
+
+    Ok(())
+}
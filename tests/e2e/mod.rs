@@ -6,12 +6,17 @@ pub mod binary_file;
 pub mod buffer_lifecycle;
 pub mod command_palette;
 pub mod crlf_rendering;
+pub mod debugger;
 pub mod document_model;
 pub mod emacs_actions;
+pub mod encoding;
 pub mod explorer_menu;
 pub mod file_browser;
 pub mod file_explorer;
 pub mod file_permissions;
+pub mod folding;
+pub mod formatting;
+pub mod grammars;
 pub mod large_file_mode;
 pub mod lifecycle;
 pub mod line_wrapping;
@@ -32,9 +37,11 @@ pub mod position_history;
 pub mod position_history_bugs;
 pub mod position_history_debug;
 pub mod position_history_truncate_debug;
+pub mod project_search;
 pub mod prompt;
 pub mod prompt_editing;
 pub mod recovery;
+pub mod remote_editing;
 pub mod rendering;
 pub mod scroll_clearing;
 pub mod scrolling;
@@ -44,6 +51,7 @@ pub mod session;
 pub mod settings;
 pub mod slow_filesystem;
 pub mod smart_editing;
+pub mod snippets;
 pub mod split_tabs;
 pub mod split_view;
 pub mod split_view_expectations;
@@ -135,7 +135,10 @@ done
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            priority: 0,
+        }
+        .into(),
     );
 
     // Create harness with config and working directory
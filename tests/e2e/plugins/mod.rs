@@ -6,4 +6,5 @@ pub mod git;
 pub mod gutter;
 pub mod lsp_find_references;
 pub mod plugin;
+pub mod plugin_packages;
 pub mod theme_editor;
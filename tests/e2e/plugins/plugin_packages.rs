@@ -0,0 +1,162 @@
+//! E2E tests for plugin packages installed from git repositories
+
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use fresh::config::{Config, DirectoryContext};
+use fresh::services::plugins::packages::PluginPackages;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn git(repo: &Path, args: &[&str]) {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args([
+            "-c",
+            "user.name=Test User",
+            "-c",
+            "user.email=test@example.com",
+            "-c",
+            "commit.gpgsign=false",
+        ])
+        .args(args)
+        .output()
+        .expect("Failed to run git");
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Commit a plugin greeting with `version`, and declaring a setting
+fn commit_plugin(repo: &Path, version: &str) {
+    let plugin = format!(
+        r#"
+globalThis.hello_package_greet = function(): void {{
+    const settings = editor.getConfig().plugin_settings?.hello ?? {{}};
+    editor.setStatus(`${{settings.greeting ?? "Hello"}} from package {}`);
+}};
+
+editor.registerCommand("Hello Package", "Greet from a package", "hello_package_greet", "normal");
+"#,
+        version
+    );
+    std::fs::write(repo.join("hello.ts"), plugin).unwrap();
+    std::fs::write(
+        repo.join("hello.manifest.json"),
+        r#"{ "settings": { "properties": { "greeting": { "type": "string", "default": "Hello" } } } }"#,
+    )
+    .unwrap();
+    git(repo, &["add", "."]);
+    git(repo, &["commit", "--quiet", "-m", version]);
+}
+
+fn run_command(harness: &mut EditorTestHarness, command: &str) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text(command).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+}
+
+/// Test installing, updating, pinning and disabling a package from a local
+/// git repository
+#[test]
+fn test_install_update_and_disable_plugin_package() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path().join("project");
+    std::fs::create_dir(&project_dir).unwrap();
+    let repo = temp_dir.path().join("hello-plugin");
+    std::fs::create_dir(&repo).unwrap();
+    git(&repo, &["init", "--quiet"]);
+    commit_plugin(&repo, "v1");
+
+    let dir_context = DirectoryContext::for_testing(&temp_dir.path().join("state"));
+    let packages = PluginPackages::new(
+        dir_context.plugin_registry_path(),
+        dir_context.plugin_packages_dir(),
+    );
+    let mut harness = EditorTestHarness::with_shared_dir_context(
+        100,
+        30,
+        Config::default(),
+        project_dir,
+        dir_context,
+    )
+    .unwrap();
+
+    run_command(&mut harness, "Install Plugin");
+    harness
+        .type_text(&format!("file://{}", repo.display()))
+        .unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness
+        .wait_until(|h| {
+            h.screen_to_string()
+                .contains("Installed plugin package 'hello-plugin'")
+        })
+        .unwrap();
+
+    run_command(&mut harness, "Hello Package");
+    harness
+        .wait_until(|h| h.screen_to_string().contains("Hello from package v1"))
+        .unwrap();
+
+    // The plugin's settings get a page of their own
+    harness
+        .send_key(KeyCode::Char(','), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_screen_contains("Plugin: hello");
+    harness.send_key(KeyCode::Esc, KeyModifiers::NONE).unwrap();
+    harness.render().unwrap();
+
+    // Updating reloads the plugin at the new commit
+    commit_plugin(&repo, "v2");
+    run_command(&mut harness, "Update Plugins");
+    harness
+        .wait_until(|h| {
+            h.screen_to_string()
+                .contains("Updated plugin package 'hello-plugin'")
+        })
+        .unwrap();
+    run_command(&mut harness, "Hello Package");
+    harness
+        .wait_until(|h| h.screen_to_string().contains("Hello from package v2"))
+        .unwrap();
+
+    // A pinned package stays at its commit
+    run_command(&mut harness, "Pin Plugin");
+    harness.assert_screen_contains("hello-plugin");
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_screen_contains("Pinned plugin package 'hello-plugin'");
+    commit_plugin(&repo, "v3");
+    run_command(&mut harness, "Update Plugins");
+    harness
+        .wait_until(|h| {
+            h.screen_to_string()
+                .contains("Plugin package 'hello-plugin' is pinned")
+        })
+        .unwrap();
+
+    run_command(&mut harness, "Disable Plugin");
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_screen_contains("Disabled plugin package 'hello-plugin'");
+    let registry = packages.registry().unwrap();
+    assert!(!registry.packages["hello-plugin"].enabled);
+    assert!(registry.packages["hello-plugin"].pinned);
+}
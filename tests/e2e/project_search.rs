@@ -0,0 +1,195 @@
+//! E2E tests for project-wide search and replace
+
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::fs;
+
+/// Run a command from the command palette
+fn run_command(harness: &mut EditorTestHarness, name: &str) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text(name).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+}
+
+fn wait_for_screen(harness: &mut EditorTestHarness, text: &str) {
+    harness
+        .wait_until(|h| h.screen_to_string().contains(text))
+        .unwrap();
+}
+
+/// Results are grouped by file, skip gitignored files, and come from the
+/// unsaved content of open buffers rather than the files on disk
+#[test]
+fn test_project_search_lists_matches_by_file() {
+    let mut harness = EditorTestHarness::with_temp_project(100, 30).unwrap();
+    let project = harness.project_dir().unwrap();
+    fs::create_dir_all(project.join("src")).unwrap();
+    fs::create_dir_all(project.join(".git")).unwrap();
+    fs::write(project.join(".gitignore"), "build/\n").unwrap();
+    fs::create_dir_all(project.join("build")).unwrap();
+    fs::write(project.join("build/out.txt"), "needle in build\n").unwrap();
+    fs::write(project.join("src/lib.rs"), "fn needle() {}\n").unwrap();
+    fs::write(project.join("notes.txt"), "nothing here\n").unwrap();
+
+    // Unsaved edit in an open buffer
+    harness.open_file(&project.join("notes.txt")).unwrap();
+    harness
+        .send_key(KeyCode::End, KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("unsaved needle\n").unwrap();
+
+    harness
+        .send_key(
+            KeyCode::Char('f'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        )
+        .unwrap();
+    harness.assert_screen_contains("Search in project:");
+    harness.type_text("needle").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+
+    wait_for_screen(&mut harness, "2 matches in 2 files");
+    harness.assert_screen_contains("*Project Search*");
+    harness.assert_screen_contains("notes.txt");
+    harness.assert_screen_contains("2:9  unsaved needle");
+    harness.assert_screen_contains("src/lib.rs");
+    harness.assert_screen_contains("1:4  fn needle() {}");
+    harness.assert_screen_not_contains("needle in build");
+
+    // Enter on a match opens it with the cursor on the match
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.assert_buffer_content("fn needle() {}\n");
+    assert_eq!(harness.cursor_position(), 3);
+}
+
+/// Include and exclude globs limit which files are searched
+#[test]
+fn test_project_search_globs() {
+    let mut harness = EditorTestHarness::with_temp_project(100, 30).unwrap();
+    let project = harness.project_dir().unwrap();
+    fs::write(project.join("a.rs"), "needle\n").unwrap();
+    fs::write(project.join("b.md"), "needle\n").unwrap();
+
+    run_command(&mut harness, "Search in Project");
+    harness.type_text("needle").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    wait_for_screen(&mut harness, "2 matches in 2 files");
+
+    harness
+        .send_key(KeyCode::Char('x'), KeyModifiers::NONE)
+        .unwrap();
+    harness.type_text("*.md").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    wait_for_screen(&mut harness, "1 match in 1 file");
+    harness.assert_screen_contains("a.rs");
+    harness.assert_screen_not_contains("b.md");
+}
+
+/// Replacements are previewed, apply per match or for everything, and can be
+/// undone; files are left unsaved
+#[test]
+fn test_project_replace_preview_and_undo() {
+    let mut harness = EditorTestHarness::with_temp_project(100, 30).unwrap();
+    let project = harness.project_dir().unwrap();
+    fs::write(project.join("a.txt"), "alpha beta alpha\n").unwrap();
+    fs::write(project.join("b.txt"), "alpha\n").unwrap();
+
+    run_command(&mut harness, "Replace in Project");
+    harness.type_text("alpha").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.assert_screen_contains("Replace 'alpha' in project with:");
+    harness.type_text("omega").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+
+    wait_for_screen(&mut harness, "3 matches in 2 files");
+    harness.assert_screen_contains("->  omega beta alpha");
+    harness.assert_screen_contains("->  alpha beta omega");
+
+    // Replace the first match only, then everything that's left
+    harness
+        .send_key(KeyCode::Char('r'), KeyModifiers::NONE)
+        .unwrap();
+    wait_for_screen(&mut harness, "2 matches in 2 files");
+    harness
+        .send_key(KeyCode::Char('R'), KeyModifiers::SHIFT)
+        .unwrap();
+    wait_for_screen(&mut harness, "0 matches in 0 files");
+    assert_eq!(
+        fs::read_to_string(project.join("a.txt")).unwrap(),
+        "alpha beta alpha\n"
+    );
+
+    harness
+        .send_key(KeyCode::Char('q'), KeyModifiers::NONE)
+        .unwrap();
+    harness.open_file(&project.join("b.txt")).unwrap();
+    harness.assert_buffer_content("omega\n");
+    harness.open_file(&project.join("a.txt")).unwrap();
+    harness.assert_buffer_content("omega beta omega\n");
+
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.assert_buffer_content("omega beta alpha\n");
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.assert_buffer_content("alpha beta alpha\n");
+}
+
+/// Skipping a match that changed since the search leaves the offsets of the
+/// other matches in that file alone
+#[test]
+fn test_project_replace_skips_stale_match() {
+    let mut harness = EditorTestHarness::with_temp_project(140, 30).unwrap();
+    let project = harness.project_dir().unwrap();
+    fs::write(project.join("a.txt"), "alpha beta alpha\n").unwrap();
+
+    run_command(&mut harness, "Replace in Project");
+    harness.type_text("alpha").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.type_text("x").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    wait_for_screen(&mut harness, "2 matches in 1 file");
+
+    // The first match changes on disk before the file is opened
+    fs::write(project.join("a.txt"), "alphx beta alpha\n").unwrap();
+    harness
+        .send_key(KeyCode::Char('r'), KeyModifiers::NONE)
+        .unwrap();
+    wait_for_screen(&mut harness, "skipped 1 changed since the search");
+    wait_for_screen(&mut harness, "1 match in 1 file");
+
+    harness
+        .send_key(KeyCode::Char('R'), KeyModifiers::SHIFT)
+        .unwrap();
+    wait_for_screen(&mut harness, "Replaced 1 match in 1 file");
+
+    harness
+        .send_key(KeyCode::Char('q'), KeyModifiers::NONE)
+        .unwrap();
+    harness.open_file(&project.join("a.txt")).unwrap();
+    harness.assert_buffer_content("alphx beta x\n");
+}
@@ -0,0 +1,157 @@
+//! E2E tests for remote editing
+//!
+//! These run `fresh --remote-agent` as a local child process, which is what
+//! ssh starts on the remote host. The agent is given an environment variable
+//! the test process doesn't have, so processes can show they ran on the
+//! agent's side.
+
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use fresh::services::fs::FsManager;
+use fresh::services::remote::{RemoteConnection, RemoteFsBackend};
+use std::io;
+use std::process::Command;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Set only in the agent's environment
+const AGENT_MARKER_VAR: &str = "FRESH_REMOTE_TEST_SIDE";
+
+fn connect_to_local_agent() -> Arc<RemoteConnection> {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_fresh"));
+    command
+        .arg("--remote-agent")
+        .env(AGENT_MARKER_VAR, "agent")
+        // Keep the remote terminal's shell free of user startup files
+        .env("SHELL", "/bin/sh");
+    Arc::new(RemoteConnection::spawn(command, "local-agent".to_string()).unwrap())
+}
+
+#[test]
+fn test_remote_file_operations() {
+    let remote = connect_to_local_agent();
+    let fs = FsManager::new(Arc::new(RemoteFsBackend::new(remote)));
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let dir = temp_dir.path().join("project/src");
+
+    fs.block_on(fs.create_dir_all(&dir)).unwrap();
+    let file = dir.join("main.rs");
+    fs.block_on(fs.write_file(&file, b"fn main() {\n")).unwrap();
+    fs.block_on(fs.append_file(&file, b"}\n")).unwrap();
+    assert_eq!(std::fs::read(&file).unwrap(), b"fn main() {\n}\n");
+
+    assert_eq!(
+        fs.block_on(fs.read_file(&file)).unwrap(),
+        b"fn main() {\n}\n"
+    );
+    assert_eq!(fs.block_on(fs.read_range(&file, 3, 4)).unwrap(), b"main");
+    assert!(fs.block_on(fs.exists(&file)));
+    assert!(fs.block_on(fs.is_dir(&dir)).unwrap());
+
+    let entries = fs.block_on(fs.list_dir_with_metadata(dir.clone())).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].name, "main.rs");
+    assert_eq!(entries[0].metadata.as_ref().unwrap().size, Some(14));
+
+    let renamed = dir.join("lib.rs");
+    fs.block_on(fs.rename(&file, &renamed)).unwrap();
+    assert!(!file.exists());
+    assert!(renamed.exists());
+
+    // Remote errors keep their kind
+    let error = fs.block_on(fs.read_file(&file)).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::NotFound);
+
+    fs.block_on(fs.remove_file(&renamed)).unwrap();
+    fs.block_on(fs.remove_dir_all(&temp_dir.path().join("project")))
+        .unwrap();
+    assert!(!temp_dir.path().join("project").exists());
+}
+
+#[test]
+#[cfg_attr(target_os = "windows", ignore)] // Uses Unix shell commands
+fn test_remote_process_stdio_is_tunneled() {
+    let remote = connect_to_local_agent();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+
+    runtime.block_on(async {
+        // stdin is relayed to the process and stdout back, until stdin closes
+        let mut cat = remote
+            .spawn_process(Some("cat"), &[], None, None)
+            .await
+            .unwrap();
+        let mut stdin = cat.take_stdin().unwrap();
+        let mut stdout = cat.take_stdout().unwrap();
+        stdin.write_all(b"hello over the wire\n").await.unwrap();
+        drop(stdin);
+        let mut output = Vec::new();
+        stdout.read_to_end(&mut output).await.unwrap();
+        assert_eq!(output, b"hello over the wire\n");
+
+        // The process runs in the agent's environment, with stderr kept apart
+        let script = format!("echo \"${}\"; echo oops >&2", AGENT_MARKER_VAR);
+        let mut shell = remote
+            .spawn_process(Some("sh"), &["-c".to_string(), script], None, None)
+            .await
+            .unwrap();
+        let mut stdout = shell.take_stdout().unwrap();
+        let mut stderr = shell.take_stderr().unwrap();
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).await.unwrap();
+        let mut errors = String::new();
+        stderr.read_to_string(&mut errors).await.unwrap();
+        assert_eq!(output, "agent\n");
+        assert_eq!(errors, "oops\n");
+    });
+}
+
+#[test]
+fn test_edit_and_save_remote_file() {
+    let remote = connect_to_local_agent();
+    let mut harness = EditorTestHarness::with_remote(80, 24, remote).unwrap();
+    let file = harness.project_dir().unwrap().join("notes.txt");
+    std::fs::write(&file, "remote line\n").unwrap();
+
+    harness.open_file(&file).unwrap();
+    harness.assert_screen_contains("remote line");
+
+    harness
+        .send_key(KeyCode::End, KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("edited locally\n").unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "remote line\nedited locally\n"
+    );
+}
+
+#[test]
+#[cfg_attr(target_os = "windows", ignore)] // Uses Unix shell commands
+fn test_terminal_runs_on_remote_host() {
+    use portable_pty::{native_pty_system, PtySize};
+    let pty_size = PtySize {
+        rows: 1,
+        cols: 1,
+        pixel_width: 0,
+        pixel_height: 0,
+    };
+    if native_pty_system().openpty(pty_size).is_err() {
+        eprintln!("Skipping terminal test: PTY not available in this environment");
+        return;
+    }
+
+    let remote = connect_to_local_agent();
+    let mut harness = EditorTestHarness::with_remote(80, 24, remote).unwrap();
+    harness.editor_mut().open_terminal();
+    harness
+        .editor_mut()
+        .send_terminal_input(format!("echo \"side=${}\"\n", AGENT_MARKER_VAR).as_bytes());
+
+    harness
+        .wait_until(|h| h.screen_to_string().contains("side=agent"))
+        .unwrap();
+}
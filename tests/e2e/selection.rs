@@ -1579,3 +1579,178 @@ fn test_select_at_file_boundaries() {
     // Just verify we can get selected text without panicking
     // The test validates that boundary operations don't crash
 }
+
+const RUST_SOURCE: &str =
+    "fn main() {\n    let total = add(1, 2);\n    let count = add(3, 4);\n}\n";
+
+/// Open a Rust file (so tree-sitter is available) with the cursor at `offset`
+fn open_rust_source(temp_dir: &TempDir, offset: usize) -> EditorTestHarness {
+    let file_path = temp_dir.path().join("main.rs");
+    std::fs::write(&file_path, RUST_SOURCE).unwrap();
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness
+        .editor_mut()
+        .active_state_mut()
+        .cursors
+        .primary_mut()
+        .position = offset;
+    harness
+}
+
+fn selected_texts(harness: &mut EditorTestHarness) -> Vec<String> {
+    let state = harness.editor_mut().active_state_mut();
+    let mut ranges: Vec<_> = state
+        .cursors
+        .iter()
+        .filter_map(|(_, cursor)| cursor.selection_range())
+        .collect();
+    ranges.sort_by_key(|range| range.start);
+    ranges
+        .into_iter()
+        .map(|range| state.get_text_range(range.start, range.end))
+        .collect()
+}
+
+fn expand(harness: &mut EditorTestHarness) {
+    harness
+        .send_key(KeyCode::Up, KeyModifiers::CONTROL | KeyModifiers::SHIFT)
+        .unwrap();
+}
+
+fn shrink(harness: &mut EditorTestHarness) {
+    harness
+        .send_key(KeyCode::Down, KeyModifiers::CONTROL | KeyModifiers::SHIFT)
+        .unwrap();
+}
+
+/// In a file with a syntax tree, expand selection grows to enclosing nodes
+/// and shrink selection walks back down
+#[test]
+fn test_expand_selection_follows_syntax_tree() {
+    let temp_dir = TempDir::new().unwrap();
+    let offset = RUST_SOURCE.find("add").unwrap() + 1;
+    let mut harness = open_rust_source(&temp_dir, offset);
+
+    expand(&mut harness);
+    assert_eq!(selected_texts(&mut harness), vec!["add"]);
+    expand(&mut harness);
+    assert_eq!(selected_texts(&mut harness), vec!["add(1, 2)"]);
+    expand(&mut harness);
+    assert_eq!(selected_texts(&mut harness), vec!["let total = add(1, 2);"]);
+    expand(&mut harness);
+    assert!(selected_texts(&mut harness)[0].starts_with("{\n    let total"));
+
+    shrink(&mut harness);
+    assert_eq!(selected_texts(&mut harness), vec!["let total = add(1, 2);"]);
+    shrink(&mut harness);
+    shrink(&mut harness);
+    assert_eq!(selected_texts(&mut harness), vec!["add"]);
+
+    // Shrinking past the first expansion restores the original cursor
+    shrink(&mut harness);
+    assert!(selected_texts(&mut harness).is_empty());
+    assert_eq!(harness.cursor_position(), offset);
+}
+
+/// Every cursor expands and shrinks on its own
+#[test]
+fn test_expand_selection_with_multiple_cursors() {
+    let temp_dir = TempDir::new().unwrap();
+    let offset = RUST_SOURCE.find("add").unwrap();
+    let mut harness = open_rust_source(&temp_dir, offset + "add".len());
+
+    // Select "add", then add a cursor at its next occurrence
+    harness
+        .editor_mut()
+        .active_state_mut()
+        .cursors
+        .primary_mut()
+        .set_anchor(offset);
+    harness
+        .send_key(KeyCode::Char('d'), KeyModifiers::CONTROL)
+        .unwrap();
+    assert_eq!(selected_texts(&mut harness), vec!["add", "add"]);
+
+    expand(&mut harness);
+    assert_eq!(selected_texts(&mut harness), vec!["add(1, 2)", "add(3, 4)"]);
+    expand(&mut harness);
+    assert_eq!(
+        selected_texts(&mut harness),
+        vec!["let total = add(1, 2);", "let count = add(3, 4);"]
+    );
+
+    shrink(&mut harness);
+    assert_eq!(selected_texts(&mut harness), vec!["add(1, 2)", "add(3, 4)"]);
+}
+
+/// Selection ranges from the language server take precedence
+#[test]
+fn test_expand_selection_uses_lsp_selection_ranges() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let _fake_server = FakeLspServer::spawn()?;
+    let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("main.rs");
+    std::fs::write(&file_path, RUST_SOURCE)?;
+
+    let mut config = fresh::config::Config::default();
+    config.lsp.insert(
+        "rust".to_string(),
+        fresh::services::lsp::client::LspServerConfig {
+            command: FakeLspServer::script_path().to_string_lossy().to_string(),
+            args: vec![],
+            enabled: true,
+            auto_start: true,
+            process_limits: fresh::services::process_limits::ProcessLimits::default(),
+            initialization_options: None,
+            name: None,
+            priority: 0,
+        }
+        .into(),
+    );
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        config,
+        temp_dir.path().to_path_buf(),
+    )?;
+    harness.open_file(&file_path)?;
+    // Expansions sent before the server is ready fall back to tree-sitter, so
+    // retry from the cursor until the server answers
+    let mut selected = false;
+    for _ in 0..10 {
+        let cursor = harness
+            .editor_mut()
+            .active_state_mut()
+            .cursors
+            .primary_mut();
+        cursor.position = 4;
+        cursor.anchor = None;
+
+        expand(&mut harness);
+        harness.wait_for_async(
+            |h| h.editor().active_state().cursors.primary().anchor.is_some(),
+            1000,
+        )?;
+        // "fn main()" is not a syntax node, so this range can only come from LSP
+        expand(&mut harness);
+        selected = harness.wait_for_async(
+            |h| {
+                h.editor()
+                    .active_state()
+                    .cursors
+                    .primary()
+                    .selection_range()
+                    == Some(0..9)
+            },
+            1000,
+        )?;
+        if selected {
+            break;
+        }
+    }
+    assert!(selected, "selection range response was not applied");
+    assert_eq!(selected_texts(&mut harness), vec!["fn main()"]);
+    Ok(())
+}
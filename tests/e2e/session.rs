@@ -1103,3 +1103,275 @@ fn test_session_restores_splits() {
         harness.assert_buffer_content("Left split content");
     }
 }
+
+fn hot_exit_config() -> Config {
+    let mut config = Config::default();
+    config.editor.hot_exit = true;
+    config
+}
+
+/// Test that hot exit quits without prompting and restores unsaved edits
+/// and unnamed buffers, including their undo history
+#[test]
+fn test_hot_exit_restores_unsaved_buffers() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path().join("project");
+    std::fs::create_dir(&project_dir).unwrap();
+    let file = project_dir.join("a.txt");
+    std::fs::write(&file, "original").unwrap();
+
+    let context_temp = TempDir::new().unwrap();
+    let dir_context = fresh::config::DirectoryContext::for_testing(context_temp.path());
+
+    // First session: modify a file and create an unnamed buffer, then quit
+    {
+        let mut harness = EditorTestHarness::with_shared_dir_context(
+            80,
+            24,
+            hot_exit_config(),
+            project_dir.clone(),
+            dir_context.clone(),
+        )
+        .unwrap();
+        harness.editor_mut().set_session_enabled(true);
+
+        harness.open_file(&file).unwrap();
+        harness.type_text("X").unwrap();
+        harness.new_buffer().unwrap();
+        harness.type_text("scratch").unwrap();
+
+        harness.editor_mut().quit();
+        assert!(
+            harness.should_quit(),
+            "Hot exit should quit without asking about unsaved changes"
+        );
+        harness.editor_mut().save_session().unwrap();
+    }
+
+    // The file on disk is untouched
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "original");
+
+    // Second session: unsaved content comes back
+    {
+        let mut harness = EditorTestHarness::with_shared_dir_context(
+            80,
+            24,
+            hot_exit_config(),
+            project_dir.clone(),
+            dir_context.clone(),
+        )
+        .unwrap();
+
+        let restored = harness.editor_mut().try_restore_session().unwrap();
+        assert!(restored);
+
+        // The unnamed buffer was active when we quit
+        harness.assert_buffer_content("scratch");
+
+        harness.open_file(&file).unwrap();
+        harness.assert_buffer_content("Xoriginal");
+        assert!(harness.editor().active_state().buffer.is_modified());
+
+        // Undo history survives, and undoing back to the file state clears
+        // the modified flag
+        harness
+            .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+            .unwrap();
+        harness.assert_buffer_content("original");
+        assert!(!harness.editor().active_state().buffer.is_modified());
+    }
+}
+
+/// Stash `typed` typed at the start of a file containing `original`, change
+/// the file on disk to `on_disk`, then restore and merge, returning the
+/// merged buffer content
+fn hot_exit_merge(original: &str, typed: &str, on_disk: &str) -> String {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path().join("project");
+    std::fs::create_dir(&project_dir).unwrap();
+    let file = project_dir.join("a.txt");
+    std::fs::write(&file, original).unwrap();
+
+    let context_temp = TempDir::new().unwrap();
+    let dir_context = fresh::config::DirectoryContext::for_testing(context_temp.path());
+
+    {
+        let mut harness = EditorTestHarness::with_shared_dir_context(
+            80,
+            24,
+            hot_exit_config(),
+            project_dir.clone(),
+            dir_context.clone(),
+        )
+        .unwrap();
+        harness.editor_mut().set_session_enabled(true);
+
+        harness.open_file(&file).unwrap();
+        harness.type_text(typed).unwrap();
+        harness.editor_mut().quit();
+        assert!(harness.should_quit());
+        harness.editor_mut().save_session().unwrap();
+    }
+
+    // Someone else edits the file; bump the mtime past the stash's resolution
+    std::fs::write(&file, on_disk).unwrap();
+    std::fs::File::options()
+        .write(true)
+        .open(&file)
+        .unwrap()
+        .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(3600))
+        .unwrap();
+
+    let mut harness = EditorTestHarness::with_shared_dir_context(
+        80,
+        24,
+        hot_exit_config(),
+        project_dir.clone(),
+        dir_context.clone(),
+    )
+    .unwrap();
+
+    harness.editor_mut().try_restore_session().unwrap();
+    harness.render().unwrap();
+    harness.assert_screen_contains("changed on disk");
+
+    harness
+        .send_key(KeyCode::Char('m'), KeyModifiers::NONE)
+        .unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+
+    harness.get_buffer_content().unwrap()
+}
+
+/// Test that a file changed on disk after hot exit can be merged with the
+/// stashed changes
+#[test]
+fn test_hot_exit_conflict_merge() {
+    assert_eq!(
+        hot_exit_merge("line\n", "ours ", "theirs line\n"),
+        "<<<<<<< stashed\nours line\n=======\ntheirs line\n>>>>>>> on disk\n"
+    );
+}
+
+/// Test that merging a CRLF file only marks the lines that really differ,
+/// not every line for its line ending
+#[test]
+fn test_hot_exit_conflict_merge_crlf() {
+    assert_eq!(
+        hot_exit_merge("one\r\ntwo\r\n", "ours ", "theirs one\r\ntwo\r\n"),
+        "<<<<<<< stashed\nours one\n=======\ntheirs one\n>>>>>>> on disk\ntwo\n"
+    );
+}
+
+fn run_command(harness: &mut EditorTestHarness, command: &str) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text(command).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+}
+
+/// Test saving, switching between and deleting named sessions of a project
+#[test]
+fn test_named_sessions_save_switch_and_delete() {
+    use fresh::session::Session;
+
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path().join("project");
+    std::fs::create_dir(&project_dir).unwrap();
+    let file_a = project_dir.join("alpha.txt");
+    let file_b = project_dir.join("beta.txt");
+    std::fs::write(&file_a, "Alpha content").unwrap();
+    std::fs::write(&file_b, "Beta content").unwrap();
+
+    let context_temp = TempDir::new().unwrap();
+    let dir_context = fresh::config::DirectoryContext::for_testing(context_temp.path());
+    let new_harness = || {
+        EditorTestHarness::with_shared_dir_context(
+            100,
+            24,
+            Config::default(),
+            project_dir.clone(),
+            dir_context.clone(),
+        )
+        .unwrap()
+    };
+
+    // Save the default session with alpha.txt, then a named one that also
+    // has beta.txt, and switch back to the default session
+    {
+        let mut harness = new_harness();
+        harness.editor_mut().set_session_enabled(true);
+
+        harness.open_file(&file_a).unwrap();
+        harness.editor_mut().save_session().unwrap();
+
+        run_command(&mut harness, "Save Session As");
+        harness.type_text("feature-x").unwrap();
+        harness
+            .send_key(KeyCode::Enter, KeyModifiers::NONE)
+            .unwrap();
+        assert_eq!(harness.editor().session_name(), Some("feature-x"));
+        assert_eq!(
+            Session::list_named(&project_dir).unwrap(),
+            vec!["feature-x".to_string()]
+        );
+
+        harness.open_file(&file_b).unwrap();
+
+        // The default session is listed first
+        run_command(&mut harness, "Switch Session");
+        harness.assert_screen_contains("(default)");
+        harness
+            .send_key(KeyCode::Enter, KeyModifiers::NONE)
+            .unwrap();
+
+        // Switching restarts the editor like Switch Project does
+        assert!(harness.should_quit());
+        assert_eq!(harness.editor_mut().take_restart_session(), Some(None));
+
+        // The main loop saves the session being left
+        harness.editor_mut().save_session().unwrap();
+    }
+
+    // The default session only has alpha.txt
+    {
+        let mut harness = new_harness();
+        harness.editor_mut().set_session_enabled(true);
+        assert!(harness.editor_mut().try_restore_session().unwrap());
+        harness.render().unwrap();
+        harness.assert_screen_contains("alpha.txt");
+        harness.assert_screen_not_contains("beta.txt");
+    }
+
+    // The named session has both files, with beta.txt active
+    {
+        let mut harness = new_harness();
+        harness.editor_mut().set_session_enabled(true);
+        harness
+            .editor_mut()
+            .set_session_name(Some("feature-x".to_string()));
+        assert!(harness.editor_mut().try_restore_session().unwrap());
+        harness.render().unwrap();
+        harness.assert_screen_contains("alpha.txt");
+        harness.assert_buffer_content("Beta content");
+    }
+
+    // Delete the named session from the default one
+    {
+        let mut harness = new_harness();
+        harness.editor_mut().set_session_enabled(true);
+
+        run_command(&mut harness, "Delete Session");
+        harness.assert_screen_contains("feature-x");
+        harness
+            .send_key(KeyCode::Enter, KeyModifiers::NONE)
+            .unwrap();
+        assert!(Session::list_named(&project_dir).unwrap().is_empty());
+    }
+}
@@ -393,3 +393,46 @@ fn test_large_file_editing_with_slow_fs() {
         "Last line should be present"
     );
 }
+
+#[test]
+fn test_open_and_save_go_through_fs_backend() {
+    // Loading and saving a file should be served by the editor's filesystem
+    // backend, so a slow (or remote) backend sees every read and write
+    let slow_config = SlowFsConfig::uniform(Duration::from_millis(5));
+    let mut harness = EditorTestHarness::with_slow_fs(80, 24, slow_config).unwrap();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("notes.txt");
+    std::fs::write(&file_path, "hello\n").unwrap();
+
+    harness.open_file(&file_path).unwrap();
+    let metrics = runtime.block_on(harness.get_fs_metrics_snapshot()).unwrap();
+    assert!(
+        metrics.read_file_calls > 0,
+        "Opening a file should read it through the backend"
+    );
+
+    harness
+        .send_key(KeyCode::End, KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("world\n").unwrap();
+    let writes_before = metrics.write_file_calls;
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+
+    let metrics = runtime.block_on(harness.get_fs_metrics_snapshot()).unwrap();
+    assert!(
+        metrics.write_file_calls > writes_before,
+        "Saving should write through the backend"
+    );
+    assert_eq!(
+        metrics.rename_calls, 1,
+        "Saving should atomically rename the temp file over the original"
+    );
+    assert_eq!(
+        std::fs::read_to_string(&file_path).unwrap(),
+        "hello\nworld\n"
+    );
+}
@@ -1240,3 +1240,209 @@ fn test_block_selection_up() {
         .get_line_number(cursor.position);
     assert_eq!(cur_line, 1, "Cursor should be on line 1 after moving up");
 }
+
+/// Test that a macro shown in a buffer can be renamed by editing its JSON
+/// definition, played by name, and is persisted for the next session
+#[test]
+fn test_edit_named_macro_and_persist() {
+    use fresh::config::DirectoryContext;
+
+    let temp_dir = TempDir::new().unwrap();
+    let dir_context = DirectoryContext::for_testing(&temp_dir.path().join("state"));
+    let file_path = temp_dir.path().join("test.txt");
+    std::fs::write(&file_path, "a\nb\nc\n").unwrap();
+
+    let mut harness = EditorTestHarness::with_shared_dir_context(
+        80,
+        24,
+        Config::default(),
+        temp_dir.path().to_path_buf(),
+        dir_context.clone(),
+    )
+    .unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    // Record macro 3: append "!" and move to the next line
+    harness
+        .send_key(KeyCode::Char('3'), KeyModifiers::ALT | KeyModifiers::SHIFT)
+        .unwrap();
+    harness.send_key(KeyCode::End, KeyModifiers::NONE).unwrap();
+    harness.type_text("!").unwrap();
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    harness
+        .send_key(KeyCode::Char('3'), KeyModifiers::ALT | KeyModifiers::SHIFT)
+        .unwrap();
+    assert!(dir_context.macros_path().exists());
+
+    // Show it, replace `"name": null` with a name and save the buffer
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("Show Macro").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.type_text("3").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_screen_contains("\"name\": null");
+
+    harness
+        .send_key(KeyCode::End, KeyModifiers::CONTROL)
+        .unwrap();
+    harness
+        .send_key_repeat(KeyCode::Up, KeyModifiers::NONE, 2)
+        .unwrap();
+    harness.send_key(KeyCode::End, KeyModifiers::NONE).unwrap();
+    harness
+        .send_key_repeat(KeyCode::Backspace, KeyModifiers::NONE, 4)
+        .unwrap();
+    harness.type_text("\"bang\"").unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    let status = harness
+        .editor()
+        .get_status_message()
+        .cloned()
+        .unwrap_or_default();
+    assert!(
+        status.starts_with("Macro '3' updated"),
+        "Saving the macro buffer should update the macro, got: {}",
+        status
+    );
+    drop(harness);
+
+    // A new editor loads the macro and can play it by name
+    let mut harness = EditorTestHarness::with_shared_dir_context(
+        80,
+        24,
+        Config::default(),
+        temp_dir.path().to_path_buf(),
+        dir_context,
+    )
+    .unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("Play Macro").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.type_text("bang").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.assert_buffer_content("a!\nb\nc\n");
+}
+
+// =============================================================================
+// Comment Toggling Tests
+// =============================================================================
+
+fn toggle_comment(harness: &mut EditorTestHarness) {
+    harness
+        .send_key(KeyCode::Char('/'), KeyModifiers::CONTROL)
+        .unwrap();
+}
+
+fn toggle_block_comment(harness: &mut EditorTestHarness) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("Toggle Block Comment").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+}
+
+/// The line comment prefix comes from the language config
+#[test]
+fn test_toggle_comment_uses_language_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("test.rs");
+    std::fs::write(&file_path, "fn main() {\n    call();\n}\n").unwrap();
+
+    let mut config = Config::default();
+    config.languages.get_mut("rust").unwrap().comment_prefix = Some("///".to_string());
+    let mut harness = EditorTestHarness::with_config(80, 24, config).unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    toggle_comment(&mut harness);
+    harness.assert_buffer_content("fn main() {\n    /// call();\n}\n");
+
+    toggle_comment(&mut harness);
+    harness.assert_buffer_content("fn main() {\n    call();\n}\n");
+}
+
+/// Languages without line comments wrap each line in a block comment
+#[test]
+fn test_toggle_comment_in_css_uses_block_comments() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("style.css");
+    std::fs::write(&file_path, "p { color: red; }\n").unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    toggle_comment(&mut harness);
+    harness.assert_buffer_content("/* p { color: red; } */\n");
+
+    toggle_comment(&mut harness);
+    harness.assert_buffer_content("p { color: red; }\n");
+}
+
+/// Script embedded in HTML is commented as JavaScript
+#[test]
+fn test_toggle_comment_in_html_script() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("index.html");
+    std::fs::write(&file_path, "<p>hi</p>\n<script>\nlet x = 1;\n</script>\n").unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    toggle_comment(&mut harness);
+    harness.assert_buffer_content("<!-- <p>hi</p> -->\n<script>\nlet x = 1;\n</script>\n");
+
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    toggle_comment(&mut harness);
+    harness.assert_buffer_content("<!-- <p>hi</p> -->\n<script>\n// let x = 1;\n</script>\n");
+}
+
+/// Toggle Block Comment wraps the selection and unwraps it again
+#[test]
+fn test_toggle_block_comment_on_selection() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("test.rs");
+    std::fs::write(&file_path, "let x = 1 + 2;\n").unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    // Select "1 + 2"
+    for _ in 0..8 {
+        harness
+            .send_key(KeyCode::Right, KeyModifiers::NONE)
+            .unwrap();
+    }
+    for _ in 0..5 {
+        harness
+            .send_key(KeyCode::Right, KeyModifiers::SHIFT)
+            .unwrap();
+    }
+    toggle_block_comment(&mut harness);
+    harness.assert_buffer_content("let x = /* 1 + 2 */;\n");
+
+    // Without a selection the whole line is wrapped
+    harness.send_key(KeyCode::Home, KeyModifiers::NONE).unwrap();
+    toggle_block_comment(&mut harness);
+    harness.assert_buffer_content("/* let x = /* 1 + 2 */; */\n");
+    toggle_block_comment(&mut harness);
+    harness.assert_buffer_content("let x = /* 1 + 2 */;\n");
+}
@@ -0,0 +1,189 @@
+//! E2E tests for snippets and tabstop navigation
+
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use fresh::config::{Config, DirectoryContext};
+use tempfile::TempDir;
+
+const RUST_SNIPPETS: &str = r#"{
+    "Function": {
+        "prefix": "fnx",
+        "body": ["fn ${1:name}(${2}) {", "\t$0", "}"],
+        "description": "Function definition"
+    },
+    "Pair": {
+        "prefix": "pair",
+        "body": "let ${1:x} = ${1:x};"
+    }
+}"#;
+
+/// Open `main.rs` in an editor whose config directory has Rust snippets
+fn open_with_snippets(temp_dir: &TempDir, content: &str) -> EditorTestHarness {
+    // No language server, so completion offers user snippets only
+    let mut config = Config::default();
+    config.lsp.clear();
+
+    // No language server, so completion offers user snippets only
+    let mut config = Config::default();
+    config.lsp.clear();
+
+    let dir_context = DirectoryContext::for_testing(temp_dir.path());
+    let snippets_dir = dir_context.snippets_dir();
+    std::fs::create_dir_all(&snippets_dir).unwrap();
+    std::fs::write(snippets_dir.join("rust.json"), RUST_SNIPPETS).unwrap();
+
+    let project_dir = temp_dir.path().join("project");
+    std::fs::create_dir_all(&project_dir).unwrap();
+    let file = project_dir.join("main.rs");
+    std::fs::write(&file, content).unwrap();
+
+    let mut harness =
+        EditorTestHarness::with_shared_dir_context(80, 24, config, project_dir, dir_context)
+            .unwrap();
+    harness.open_file(&file).unwrap();
+    harness.render().unwrap();
+    harness
+}
+
+fn insert_snippet(harness: &mut EditorTestHarness, name: &str) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("Insert Snippet").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.type_text(name).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+}
+
+/// A snippet inserted from the palette selects its first placeholder, and
+/// Tab / Shift-Tab move between tabstops
+#[test]
+fn test_insert_snippet_and_navigate_tabstops() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = open_with_snippets(&temp_dir, "");
+
+    insert_snippet(&mut harness, "Function");
+    harness.assert_buffer_content("fn name() {\n    \n}");
+    let primary = *harness.editor().active_state().cursors.primary();
+    assert_eq!(primary.selection_range(), Some(3..7));
+
+    // Typing replaces the placeholder
+    harness.type_text("run").unwrap();
+    harness.send_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
+    assert_eq!(harness.cursor_position(), "fn run(".len());
+
+    harness.type_text("n: u32").unwrap();
+    harness.send_key(KeyCode::Tab, KeyModifiers::SHIFT).unwrap();
+    let primary = *harness.editor().active_state().cursors.primary();
+    assert_eq!(primary.selection_range(), Some(3..6));
+
+    // Tab twice reaches the final tabstop and ends the session
+    harness.send_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
+    harness.send_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
+    harness.assert_buffer_content("fn run(n: u32) {\n    \n}");
+    assert_eq!(harness.cursor_position(), "fn run(n: u32) {\n    ".len());
+
+    // With the session over, Tab indents again
+    harness.send_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
+    harness.assert_buffer_content("fn run(n: u32) {\n        \n}");
+}
+
+/// Mirrored placeholders are edited together with multiple cursors
+#[test]
+fn test_mirrored_placeholders_use_multiple_cursors() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = open_with_snippets(&temp_dir, "");
+
+    insert_snippet(&mut harness, "Pair");
+    harness.assert_buffer_content("let x = x;");
+    assert_eq!(harness.editor().active_state().cursors.count(), 2);
+
+    harness.type_text("value").unwrap();
+    harness.assert_buffer_content("let value = value;");
+
+    harness.send_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
+    assert_eq!(harness.editor().active_state().cursors.count(), 1);
+    assert_eq!(harness.cursor_position(), "let value = value;".len());
+}
+
+/// User snippets show up in completion and replace the typed prefix
+#[test]
+fn test_snippet_from_completion() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = open_with_snippets(&temp_dir, "");
+
+    harness.type_text("fn").unwrap();
+    harness
+        .send_key(KeyCode::Char(' '), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_screen_contains("fnx");
+
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.assert_buffer_content("fn name() {\n    \n}");
+    let primary = *harness.editor().active_state().cursors.primary();
+    assert_eq!(primary.selection_range(), Some(3..7));
+}
+
+/// Snippet completions from a language server expand their placeholders
+#[test]
+fn test_lsp_snippet_completion() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let _fake_server = FakeLspServer::spawn()?;
+    let temp_dir = TempDir::new()?;
+    let file = temp_dir.path().join("main.rs");
+    std::fs::write(&file, "")?;
+
+    let mut config = Config::default();
+    config.lsp.insert(
+        "rust".to_string(),
+        fresh::services::lsp::client::LspServerConfig {
+            command: FakeLspServer::script_path().to_string_lossy().to_string(),
+            args: vec![],
+            enabled: true,
+            auto_start: false,
+            process_limits: fresh::services::process_limits::ProcessLimits::default(),
+            initialization_options: None,
+            name: None,
+            priority: 0,
+        }
+        .into(),
+    );
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        config,
+        temp_dir.path().to_path_buf(),
+    )?;
+    harness.open_file(&file)?;
+
+    // The first request starts the server and may be answered before it is ready
+    harness.type_text("test_sn")?;
+    let mut shown = false;
+    for _ in 0..10 {
+        harness.send_key(KeyCode::Char(' '), KeyModifiers::CONTROL)?;
+        shown = harness.wait_for_async(|h| h.screen_to_string().contains("test_snippet"), 1000)?;
+        if shown {
+            break;
+        }
+    }
+    assert!(shown, "completion popup did not appear");
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)?;
+
+    harness.assert_buffer_content("test_snippet(arg, other)");
+    let primary = *harness.editor().active_state().cursors.primary();
+    assert_eq!(primary.selection_range(), Some(13..16));
+
+    harness.send_key(KeyCode::Tab, KeyModifiers::NONE)?;
+    let primary = *harness.editor().active_state().cursors.primary();
+    assert_eq!(primary.selection_range(), Some(18..23));
+    Ok(())
+}
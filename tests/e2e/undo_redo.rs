@@ -503,3 +503,149 @@ fn test_undo_to_empty_after_save_as() {
         final_content
     );
 }
+
+/// Run a command from the command palette
+fn run_command(harness: &mut EditorTestHarness, name: &str) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text(name).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+}
+
+fn undo(harness: &mut EditorTestHarness) {
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+}
+
+/// Editing after an undo keeps the undone edit on its own branch, which
+/// redo can be switched to
+#[test]
+fn test_switch_undo_branch() {
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+
+    harness.type_text("x").unwrap();
+    undo(&mut harness);
+    harness.type_text("y").unwrap();
+    undo(&mut harness);
+    harness.assert_buffer_content("");
+
+    // Redo follows the newest branch by default
+    harness
+        .send_key(KeyCode::Char('y'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.assert_buffer_content("y");
+    undo(&mut harness);
+
+    run_command(&mut harness, "Switch Undo Branch");
+    assert_eq!(
+        harness.editor().get_status_message().cloned(),
+        Some("Redo follows branch 1 of 2".to_string())
+    );
+    harness
+        .send_key(KeyCode::Char('y'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.assert_buffer_content("x");
+}
+
+/// The undo history panel lists every branch; Enter jumps to the selected
+/// state, while closing after a preview goes back to where we started
+#[test]
+fn test_undo_history_panel() {
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+
+    harness.type_text("x").unwrap();
+    undo(&mut harness);
+    harness.type_text("y").unwrap();
+
+    run_command(&mut harness, "Undo History");
+    harness.render().unwrap();
+    harness.assert_screen_contains("*Undo History*");
+    harness.assert_screen_contains("original");
+    harness.assert_screen_contains("insert \"x\"");
+    harness.assert_screen_contains("insert \"y\"");
+
+    // Preview the initial state, then close to go back to "y"
+    harness
+        .send_key(KeyCode::Home, KeyModifiers::CONTROL)
+        .unwrap();
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+    assert!(!harness.get_row_text(2).contains('y'));
+    harness
+        .send_key(KeyCode::Char('q'), KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_screen_not_contains("*Undo History*");
+    harness.assert_buffer_content("y");
+
+    // Jump to the "x" branch
+    run_command(&mut harness, "Undo History");
+    harness
+        .send_key(KeyCode::Home, KeyModifiers::CONTROL)
+        .unwrap();
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_screen_not_contains("*Undo History*");
+    harness.assert_buffer_content("x");
+    assert!(harness.editor().active_state().buffer.is_modified());
+}
+
+/// Undo history is saved with the file and restored when the unchanged file
+/// is opened by a new editor, but not once the file changed on disk
+#[test]
+fn test_undo_history_persists_across_restarts() {
+    use fresh::config::{Config, DirectoryContext};
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let dir_context = DirectoryContext::for_testing(&temp_dir.path().join("state"));
+    let file_path = temp_dir.path().join("notes.txt");
+    std::fs::write(&file_path, "one\n").unwrap();
+
+    let new_editor = || {
+        EditorTestHarness::with_shared_dir_context(
+            80,
+            24,
+            Config::default(),
+            temp_dir.path().to_path_buf(),
+            dir_context.clone(),
+        )
+        .unwrap()
+    };
+
+    let mut harness = new_editor();
+    harness.open_file(&file_path).unwrap();
+    harness.send_key(KeyCode::End, KeyModifiers::NONE).unwrap();
+    harness.type_text(" two").unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    drop(harness);
+
+    // Reopening the unchanged file brings back the edits
+    let mut harness = new_editor();
+    harness.open_file(&file_path).unwrap();
+    harness.assert_buffer_content("one two\n");
+    assert!(!harness.editor().active_state().buffer.is_modified());
+    for _ in 0..4 {
+        undo(&mut harness);
+    }
+    harness.assert_buffer_content("one\n");
+    assert!(harness.editor().active_state().buffer.is_modified());
+    drop(harness);
+
+    // Once the file changes outside the editor the history no longer applies
+    std::fs::write(&file_path, "one two three\n").unwrap();
+    let mut harness = new_editor();
+    harness.open_file(&file_path).unwrap();
+    undo(&mut harness);
+    harness.assert_buffer_content("one two three\n");
+}